@@ -787,7 +787,42 @@ IF NOT EXISTS (SELECT * FROM sys.tables WHERE name = 'TRENDLOG_DATA_DETAIL')
 CREATE TABLE TRENDLOG_DATA_DETAIL (
     ParentId INT NOT NULL,
     Value NVARCHAR(255) NOT NULL,
-    LoggingTime_Fmt NVARCHAR(64) NOT NULL
+    LoggingTime_Fmt NVARCHAR(64) NOT NULL,
+    NumericValue FLOAT NULL,
+    LoggingTime BIGINT NULL
+);
+
+-- Numeric value / Unix-seconds time for tables created before they existed
+IF COL_LENGTH('TRENDLOG_DATA_DETAIL', 'NumericValue') IS NULL
+    ALTER TABLE TRENDLOG_DATA_DETAIL ADD NumericValue FLOAT NULL;
+IF COL_LENGTH('TRENDLOG_DATA_DETAIL', 'LoggingTime') IS NULL
+    ALTER TABLE TRENDLOG_DATA_DETAIL ADD LoggingTime BIGINT NULL;
+
+-- Hourly/daily summaries of TRENDLOG_DATA_DETAIL (avg = SumValue / SampleCount)
+IF NOT EXISTS (SELECT * FROM sys.tables WHERE name = 'TRENDLOG_ROLLUP_HOURLY')
+CREATE TABLE TRENDLOG_ROLLUP_HOURLY (
+    ParentId INT NOT NULL,
+    BucketStart NVARCHAR(32) NOT NULL,
+    MinValue FLOAT NOT NULL,
+    MaxValue FLOAT NOT NULL,
+    SumValue FLOAT NOT NULL,
+    SampleCount INT NOT NULL,
+    LastValue FLOAT NOT NULL,
+    LastTime NVARCHAR(64) NOT NULL,
+    CONSTRAINT PK_TRENDLOG_ROLLUP_HOURLY PRIMARY KEY (ParentId, BucketStart)
+);
+
+IF NOT EXISTS (SELECT * FROM sys.tables WHERE name = 'TRENDLOG_ROLLUP_DAILY')
+CREATE TABLE TRENDLOG_ROLLUP_DAILY (
+    ParentId INT NOT NULL,
+    BucketStart NVARCHAR(32) NOT NULL,
+    MinValue FLOAT NOT NULL,
+    MaxValue FLOAT NOT NULL,
+    SumValue FLOAT NOT NULL,
+    SampleCount INT NOT NULL,
+    LastValue FLOAT NOT NULL,
+    LastTime NVARCHAR(64) NOT NULL,
+    CONSTRAINT PK_TRENDLOG_ROLLUP_DAILY PRIMARY KEY (ParentId, BucketStart)
 );
 
 -- =================================================================
@@ -971,6 +1006,14 @@ IF NOT EXISTS (SELECT * FROM sys.indexes WHERE name = 'IDX_TRENDLOG_DETAIL_PAREN
     CREATE INDEX IDX_TRENDLOG_DETAIL_PARENT_TIME ON TRENDLOG_DATA_DETAIL(ParentId, LoggingTime_Fmt DESC);
 IF NOT EXISTS (SELECT * FROM sys.indexes WHERE name = 'IDX_TRENDLOG_DETAIL_TIME_RANGE')
     CREATE INDEX IDX_TRENDLOG_DETAIL_TIME_RANGE ON TRENDLOG_DATA_DETAIL(LoggingTime_Fmt, ParentId);
+IF NOT EXISTS (SELECT * FROM sys.indexes WHERE name = 'IDX_TRENDLOG_DETAIL_PARENT_EPOCH')
+    CREATE INDEX IDX_TRENDLOG_DETAIL_PARENT_EPOCH ON TRENDLOG_DATA_DETAIL(ParentId, LoggingTime) INCLUDE (NumericValue);
+
+-- Rollup indexes
+IF NOT EXISTS (SELECT * FROM sys.indexes WHERE name = 'IDX_TRENDLOG_ROLLUP_HOURLY_BUCKET')
+    CREATE INDEX IDX_TRENDLOG_ROLLUP_HOURLY_BUCKET ON TRENDLOG_ROLLUP_HOURLY(BucketStart);
+IF NOT EXISTS (SELECT * FROM sys.indexes WHERE name = 'IDX_TRENDLOG_ROLLUP_DAILY_BUCKET')
+    CREATE INDEX IDX_TRENDLOG_ROLLUP_DAILY_BUCKET ON TRENDLOG_ROLLUP_DAILY(BucketStart);
 
 -- TRENDLOG_DATA_SYNC_METADATA indexes
 IF NOT EXISTS (SELECT * FROM sys.indexes WHERE name = 'IDX_SYNC_META_TIME')
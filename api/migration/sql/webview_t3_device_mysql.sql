@@ -767,7 +767,39 @@ CREATE TABLE IF NOT EXISTS TRENDLOG_DATA_SYNC_METADATA (
 CREATE TABLE IF NOT EXISTS TRENDLOG_DATA_DETAIL (
     ParentId INT NOT NULL,
     Value TEXT NOT NULL,
-    LoggingTime_Fmt TEXT NOT NULL
+    LoggingTime_Fmt TEXT NOT NULL,
+    NumericValue DOUBLE,
+    LoggingTime BIGINT
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+-- Numeric value / Unix-seconds time for tables created before they existed
+-- (fails harmlessly once the columns exist)
+ALTER TABLE TRENDLOG_DATA_DETAIL ADD COLUMN NumericValue DOUBLE;
+ALTER TABLE TRENDLOG_DATA_DETAIL ADD COLUMN LoggingTime BIGINT;
+
+-- Hourly/daily summaries of TRENDLOG_DATA_DETAIL (avg = SumValue / SampleCount)
+CREATE TABLE IF NOT EXISTS TRENDLOG_ROLLUP_HOURLY (
+    ParentId INT NOT NULL,
+    BucketStart VARCHAR(32) NOT NULL,
+    MinValue DOUBLE NOT NULL,
+    MaxValue DOUBLE NOT NULL,
+    SumValue DOUBLE NOT NULL,
+    SampleCount INT NOT NULL,
+    LastValue DOUBLE NOT NULL,
+    LastTime VARCHAR(32) NOT NULL,
+    PRIMARY KEY (ParentId, BucketStart)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+CREATE TABLE IF NOT EXISTS TRENDLOG_ROLLUP_DAILY (
+    ParentId INT NOT NULL,
+    BucketStart VARCHAR(32) NOT NULL,
+    MinValue DOUBLE NOT NULL,
+    MaxValue DOUBLE NOT NULL,
+    SumValue DOUBLE NOT NULL,
+    SampleCount INT NOT NULL,
+    LastValue DOUBLE NOT NULL,
+    LastTime VARCHAR(32) NOT NULL,
+    PRIMARY KEY (ParentId, BucketStart)
 ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
 
 -- =================================================================
@@ -905,6 +937,11 @@ CREATE INDEX IDX_TRENDLOG_DETAIL_PARENT ON TRENDLOG_DATA_DETAIL(ParentId);
 CREATE INDEX IDX_TRENDLOG_DETAIL_TIME_FMT ON TRENDLOG_DATA_DETAIL(LoggingTime_Fmt(32));
 CREATE INDEX IDX_TRENDLOG_DETAIL_PARENT_TIME ON TRENDLOG_DATA_DETAIL(ParentId, LoggingTime_Fmt(32));
 CREATE INDEX IDX_TRENDLOG_DETAIL_TIME_RANGE ON TRENDLOG_DATA_DETAIL(LoggingTime_Fmt(32), ParentId);
+CREATE INDEX IDX_TRENDLOG_DETAIL_PARENT_EPOCH ON TRENDLOG_DATA_DETAIL(ParentId, LoggingTime, NumericValue);
+
+-- Rollup indexes
+CREATE INDEX IDX_TRENDLOG_ROLLUP_HOURLY_BUCKET ON TRENDLOG_ROLLUP_HOURLY(BucketStart);
+CREATE INDEX IDX_TRENDLOG_ROLLUP_DAILY_BUCKET ON TRENDLOG_ROLLUP_DAILY(BucketStart);
 
 -- TRENDLOG_DATA_SYNC_METADATA indexes
 CREATE INDEX IDX_SYNC_META_TIME ON TRENDLOG_DATA_SYNC_METADATA(SyncTime_Fmt(32));
@@ -767,7 +767,38 @@ CREATE TABLE IF NOT EXISTS TRENDLOG_DATA_SYNC_METADATA (
 CREATE TABLE IF NOT EXISTS TRENDLOG_DATA_DETAIL (
     ParentId INTEGER NOT NULL,
     Value TEXT NOT NULL,
-    LoggingTime_Fmt TEXT NOT NULL
+    LoggingTime_Fmt TEXT NOT NULL,
+    NumericValue DOUBLE PRECISION,
+    LoggingTime BIGINT
+);
+
+-- Numeric value / Unix-seconds time for tables created before they existed
+ALTER TABLE TRENDLOG_DATA_DETAIL ADD COLUMN IF NOT EXISTS NumericValue DOUBLE PRECISION;
+ALTER TABLE TRENDLOG_DATA_DETAIL ADD COLUMN IF NOT EXISTS LoggingTime BIGINT;
+
+-- Hourly/daily summaries of TRENDLOG_DATA_DETAIL (avg = SumValue / SampleCount)
+CREATE TABLE IF NOT EXISTS TRENDLOG_ROLLUP_HOURLY (
+    ParentId INTEGER NOT NULL,
+    BucketStart TEXT NOT NULL,
+    MinValue DOUBLE PRECISION NOT NULL,
+    MaxValue DOUBLE PRECISION NOT NULL,
+    SumValue DOUBLE PRECISION NOT NULL,
+    SampleCount INTEGER NOT NULL,
+    LastValue DOUBLE PRECISION NOT NULL,
+    LastTime TEXT NOT NULL,
+    PRIMARY KEY (ParentId, BucketStart)
+);
+
+CREATE TABLE IF NOT EXISTS TRENDLOG_ROLLUP_DAILY (
+    ParentId INTEGER NOT NULL,
+    BucketStart TEXT NOT NULL,
+    MinValue DOUBLE PRECISION NOT NULL,
+    MaxValue DOUBLE PRECISION NOT NULL,
+    SumValue DOUBLE PRECISION NOT NULL,
+    SampleCount INTEGER NOT NULL,
+    LastValue DOUBLE PRECISION NOT NULL,
+    LastTime TEXT NOT NULL,
+    PRIMARY KEY (ParentId, BucketStart)
 );
 
 -- =================================================================
@@ -905,6 +936,11 @@ CREATE INDEX IF NOT EXISTS IDX_TRENDLOG_DETAIL_PARENT ON TRENDLOG_DATA_DETAIL(Pa
 CREATE INDEX IF NOT EXISTS IDX_TRENDLOG_DETAIL_TIME_FMT ON TRENDLOG_DATA_DETAIL(LoggingTime_Fmt DESC);
 CREATE INDEX IF NOT EXISTS IDX_TRENDLOG_DETAIL_PARENT_TIME ON TRENDLOG_DATA_DETAIL(ParentId, LoggingTime_Fmt DESC);
 CREATE INDEX IF NOT EXISTS IDX_TRENDLOG_DETAIL_TIME_RANGE ON TRENDLOG_DATA_DETAIL(LoggingTime_Fmt, ParentId);
+CREATE INDEX IF NOT EXISTS IDX_TRENDLOG_DETAIL_PARENT_EPOCH ON TRENDLOG_DATA_DETAIL(ParentId, LoggingTime, NumericValue);
+
+-- Rollup indexes
+CREATE INDEX IF NOT EXISTS IDX_TRENDLOG_ROLLUP_HOURLY_BUCKET ON TRENDLOG_ROLLUP_HOURLY(BucketStart);
+CREATE INDEX IF NOT EXISTS IDX_TRENDLOG_ROLLUP_DAILY_BUCKET ON TRENDLOG_ROLLUP_DAILY(BucketStart);
 
 -- TRENDLOG_DATA_SYNC_METADATA indexes
 CREATE INDEX IF NOT EXISTS IDX_SYNC_META_TIME ON TRENDLOG_DATA_SYNC_METADATA(SyncTime_Fmt DESC);
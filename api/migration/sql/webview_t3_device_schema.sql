@@ -180,11 +180,33 @@ CREATE TABLE IF NOT EXISTS FDD_FINDINGS (
     severity      TEXT,
     fault_hours   REAL,
     evidence      TEXT,
-    created_at    TEXT DEFAULT (datetime('now'))
+    created_at    TEXT DEFAULT (datetime('now')),
+    status        TEXT DEFAULT 'open',
+    first_seen    TEXT,
+    last_seen     TEXT,
+    cleared_at    TEXT,
+    occurrences   INTEGER DEFAULT 1,
+    est_kwh       REAL,
+    est_cost      REAL
 );
 CREATE INDEX IF NOT EXISTS idx_fdd_findings_device ON FDD_FINDINGS (device_serial);
 CREATE INDEX IF NOT EXISTS idx_fdd_findings_rule ON FDD_FINDINGS (rule_id);
 CREATE INDEX IF NOT EXISTS idx_fdd_findings_created ON FDD_FINDINGS (created_at);
+CREATE INDEX IF NOT EXISTS idx_fdd_findings_active ON FDD_FINDINGS (device_serial, equipment, rule_id, cleared_at);
+
+-- FDD role overrides — manual role → point assignments that win over tag inference
+CREATE TABLE IF NOT EXISTS FDD_ROLE_OVERRIDES (
+    id            INTEGER PRIMARY KEY AUTOINCREMENT,
+    serial_number INTEGER NOT NULL,
+    equipment     TEXT NOT NULL DEFAULT '',
+    role          TEXT NOT NULL,
+    point_type    TEXT NOT NULL,
+    point_index   INTEGER NOT NULL,
+    point_id      TEXT,
+    note          TEXT,
+    updated_at    TEXT DEFAULT (datetime('now')),
+    UNIQUE (serial_number, equipment, role)
+);
 -- Optimized schema - removed unused BinaryArray field
 CREATE TABLE IF NOT EXISTS PROGRAMS (
     SerialNumber INTEGER NOT NULL,             -- C++ SerialNumber (references DEVICES.SerialNumber)
@@ -929,7 +951,39 @@ CREATE TABLE IF NOT EXISTS TRENDLOG_DATA_DETAIL (
     -- Core fields only (NO id field - use built-in rowid)
     ParentId INTEGER NOT NULL,                         -- References TRENDLOG_DATA(id)
     Value TEXT NOT NULL,                               -- C++ Point Value (actual sensor/point value)
-    LoggingTime_Fmt TEXT NOT NULL                      -- C++ Formatted Time (e.g., "2025-10-28 13:35:49")
+    LoggingTime_Fmt TEXT NOT NULL,                     -- C++ Formatted Time (e.g., "2025-10-28 13:35:49")
+    NumericValue REAL,                                 -- Value as a number (NULL until backfilled / not numeric)
+    LoggingTime INTEGER                                -- LoggingTime_Fmt as Unix seconds (NULL until backfilled)
+);
+
+-- TRENDLOG_ROLLUP_HOURLY / TRENDLOG_ROLLUP_DAILY (Summaries of TRENDLOG_DATA_DETAIL)
+-- One row per ParentId and local-clock hour/day, updated by the FFI sync as
+-- detail rows are written and rebuildable from raw data. Values keep the raw
+-- stored scale; avg = SumValue / SampleCount.
+-- Retention is separate from partition retention (APPLICATION_CONFIG key
+-- 'trendlog.rollup_retention'), so long-term history outlives purged partitions.
+CREATE TABLE IF NOT EXISTS TRENDLOG_ROLLUP_HOURLY (
+    ParentId INTEGER NOT NULL,                         -- References TRENDLOG_DATA(id)
+    BucketStart TEXT NOT NULL,                         -- "YYYY-MM-DD HH:00:00"
+    MinValue REAL NOT NULL,
+    MaxValue REAL NOT NULL,
+    SumValue REAL NOT NULL,
+    SampleCount INTEGER NOT NULL,
+    LastValue REAL NOT NULL,
+    LastTime TEXT NOT NULL,                            -- LoggingTime_Fmt of LastValue
+    PRIMARY KEY (ParentId, BucketStart)
+);
+
+CREATE TABLE IF NOT EXISTS TRENDLOG_ROLLUP_DAILY (
+    ParentId INTEGER NOT NULL,                         -- References TRENDLOG_DATA(id)
+    BucketStart TEXT NOT NULL,                         -- "YYYY-MM-DD 00:00:00"
+    MinValue REAL NOT NULL,
+    MaxValue REAL NOT NULL,
+    SumValue REAL NOT NULL,
+    SampleCount INTEGER NOT NULL,
+    LastValue REAL NOT NULL,
+    LastTime TEXT NOT NULL,                            -- LoggingTime_Fmt of LastValue
+    PRIMARY KEY (ParentId, BucketStart)
 );
 
 -- =================================================================
@@ -974,6 +1028,12 @@ CREATE INDEX IF NOT EXISTS IDX_TRENDLOG_DETAIL_TIME_FMT ON TRENDLOG_DATA_DETAIL(
 CREATE INDEX IF NOT EXISTS IDX_TRENDLOG_DETAIL_PARENT_TIME ON TRENDLOG_DATA_DETAIL(ParentId, LoggingTime_Fmt DESC);
 -- Composite index for history query time range filtering
 CREATE INDEX IF NOT EXISTS IDX_TRENDLOG_DETAIL_TIME_RANGE ON TRENDLOG_DATA_DETAIL(LoggingTime_Fmt, ParentId);
+-- Covering index for per-point range reads on the numeric columns
+CREATE INDEX IF NOT EXISTS IDX_TRENDLOG_DETAIL_PARENT_EPOCH ON TRENDLOG_DATA_DETAIL(ParentId, LoggingTime, NumericValue);
+
+-- Rollup indexes - retention pruning and cross-point range scans
+CREATE INDEX IF NOT EXISTS IDX_TRENDLOG_ROLLUP_HOURLY_BUCKET ON TRENDLOG_ROLLUP_HOURLY(BucketStart);
+CREATE INDEX IF NOT EXISTS IDX_TRENDLOG_ROLLUP_DAILY_BUCKET ON TRENDLOG_ROLLUP_DAILY(BucketStart);
 
 -- New TRENDLOG_DATA_SYNC_METADATA indexes - for tracking sync operations
 CREATE INDEX IF NOT EXISTS IDX_SYNC_META_TIME ON TRENDLOG_DATA_SYNC_METADATA(SyncTime_Fmt DESC);
@@ -1541,3 +1601,125 @@ CREATE TABLE IF NOT EXISTS TRENDLOG_POINT_SETS (
 CREATE UNIQUE INDEX IF NOT EXISTS uq_trendpointsets_serial_name ON TRENDLOG_POINT_SETS (serial_number, set_name);
 CREATE INDEX IF NOT EXISTS idx_trendpointsets_serial     ON TRENDLOG_POINT_SETS (serial_number);
 CREATE INDEX IF NOT EXISTS idx_trendpointsets_updated_at ON TRENDLOG_POINT_SETS (updated_at);
+
+-- ============================================================================
+-- APP_USERS / APP_SESSIONS - Web UI accounts and login sessions
+-- Roles: viewer, operator, engineer, admin. Only a SHA-256 of each session
+-- token is stored. The API stays open until the first account exists.
+-- ============================================================================
+CREATE TABLE IF NOT EXISTS APP_USERS (
+    id            INTEGER PRIMARY KEY AUTOINCREMENT,
+    username      TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    password_hash TEXT NOT NULL,                 -- argon2 PHC string
+    role          TEXT NOT NULL,
+    enabled       INTEGER NOT NULL DEFAULT 1,
+    created_at    TEXT DEFAULT (datetime('now')),
+    last_login_at TEXT
+);
+CREATE TABLE IF NOT EXISTS APP_SESSIONS (
+    token_hash TEXT PRIMARY KEY,
+    user_id    INTEGER NOT NULL,
+    created_at INTEGER NOT NULL,                 -- unix epoch seconds
+    expires_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_app_sessions_user ON APP_SESSIONS (user_id);
+
+-- ============================================================================
+-- AUDIT_LOG - Who changed what: point writes, settings and configuration
+-- changes from the UI (REST), MCP clients and AI chat tool calls.
+-- ============================================================================
+CREATE TABLE IF NOT EXISTS AUDIT_LOG (
+    id            INTEGER PRIMARY KEY AUTOINCREMENT,
+    ts            INTEGER NOT NULL,              -- unix epoch ms
+    actor         TEXT,                          -- APP_USERS.username, NULL before accounts exist
+    origin        TEXT NOT NULL,                 -- ui | mcp | ai | system
+    session_id    TEXT,                          -- MCP or AI chat session id
+    action        TEXT NOT NULL,                 -- "PUT /api/..." or MCP tool name
+    serial_number INTEGER,
+    point         TEXT,                          -- e.g. "INPUT 3", "settings:network"
+    old_value     TEXT,                          -- JSON
+    new_value     TEXT,                          -- JSON, credentials redacted
+    result        TEXT NOT NULL,                 -- ok | error
+    detail        TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_audit_log_ts     ON AUDIT_LOG (ts);
+CREATE INDEX IF NOT EXISTS idx_audit_log_serial ON AUDIT_LOG (serial_number, ts);
+
+-- ============================================================================
+-- AI_PENDING_ACTIONS - AI chat write tool calls awaiting human approval,
+-- and the decision taken on each (approved / rejected / expired).
+-- ============================================================================
+CREATE TABLE IF NOT EXISTS AI_PENDING_ACTIONS (
+    id           TEXT PRIMARY KEY,               -- uuid
+    session_id   TEXT NOT NULL,                  -- AI chat session
+    tool_call_id TEXT,
+    tool_name    TEXT NOT NULL,
+    arguments    TEXT NOT NULL,                  -- JSON, without the model's "confirm"
+    requested_by TEXT,                           -- chat user
+    status       TEXT NOT NULL DEFAULT 'pending',-- pending | approved | rejected | expired
+    created_at   INTEGER NOT NULL,               -- unix epoch ms
+    expires_at   INTEGER NOT NULL,
+    decided_by   TEXT,
+    decided_at   INTEGER,
+    reason       TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_ai_pending_actions_status ON AI_PENDING_ACTIONS (status, created_at);
+
+-- ============================================================================
+-- MCP_API_TOKENS - Scoped bearer tokens ("t3mcp_...") for external MCP
+-- clients: tool and device allow-lists, read-only mode, rate limit.
+-- Only the SHA-256 of the secret is stored.
+-- ============================================================================
+CREATE TABLE IF NOT EXISTS MCP_API_TOKENS (
+    id                    INTEGER PRIMARY KEY AUTOINCREMENT,
+    name                  TEXT NOT NULL,
+    token_hash            TEXT NOT NULL UNIQUE,
+    token_hint            TEXT NOT NULL,             -- first characters, for display
+    allowed_tools         TEXT,                      -- JSON array, NULL = all tools
+    allowed_serials       TEXT,                      -- JSON array, NULL = all devices
+    read_only             INTEGER NOT NULL DEFAULT 1,
+    rate_limit_per_minute INTEGER,                   -- NULL = unlimited
+    enabled               INTEGER NOT NULL DEFAULT 1,
+    created_by            TEXT,
+    created_at            TEXT DEFAULT (datetime('now')),
+    last_used_at          TEXT
+);
+
+-- ============================================================================
+-- AI_USAGE - One row per LLM call in the AI chat loop: tokens, tool calls,
+-- latency and cost. AI_MODEL_PRICES prices models (USD per million tokens);
+-- AI_USAGE_BUDGETS are monthly limits checked before a chat starts.
+-- ============================================================================
+CREATE TABLE IF NOT EXISTS AI_USAGE (
+    id            INTEGER PRIMARY KEY AUTOINCREMENT,
+    ts            INTEGER NOT NULL,              -- unix epoch ms
+    session_id    TEXT NOT NULL,                 -- AI chat session
+    user_name     TEXT,                          -- chat user
+    provider      TEXT NOT NULL,                 -- local | anthropic | gemini
+    model         TEXT NOT NULL,
+    input_tokens  INTEGER NOT NULL DEFAULT 0,
+    output_tokens INTEGER NOT NULL DEFAULT 0,
+    tool_calls    INTEGER NOT NULL DEFAULT 0,
+    latency_ms    INTEGER NOT NULL DEFAULT 0,    -- LLM request to end of stream
+    tool_ms       INTEGER NOT NULL DEFAULT 0,    -- running the requested tools
+    cost_usd      REAL                           -- NULL = model has no price
+);
+CREATE INDEX IF NOT EXISTS idx_ai_usage_ts      ON AI_USAGE (ts);
+CREATE INDEX IF NOT EXISTS idx_ai_usage_session ON AI_USAGE (session_id);
+
+CREATE TABLE IF NOT EXISTS AI_MODEL_PRICES (
+    model           TEXT PRIMARY KEY,
+    input_per_mtok  REAL NOT NULL DEFAULT 0,
+    output_per_mtok REAL NOT NULL DEFAULT 0
+);
+
+CREATE TABLE IF NOT EXISTS AI_USAGE_BUDGETS (
+    id               INTEGER PRIMARY KEY AUTOINCREMENT,
+    name             TEXT NOT NULL,
+    provider         TEXT,                       -- NULL = any
+    model            TEXT,                       -- NULL = any
+    user_name        TEXT,                       -- NULL = any
+    monthly_tokens   INTEGER,                    -- prompt + completion
+    monthly_cost_usd REAL,
+    enabled          INTEGER NOT NULL DEFAULT 1
+);
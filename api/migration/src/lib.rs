@@ -16,6 +16,8 @@ mod m20260611_add_device_online_status;
 mod m20260715_add_auto_tagging_rules;
 mod m20260812_add_lan_scan_fields;
 mod m20260819_add_fdd_tables;
+mod m20261018_add_trendlog_rollup_tables;
+mod m20261019_add_trendlog_detail_numeric_columns;
 
 /// Migrator for webview_database.db (users, files, app config, LCD options)
 pub struct Migrator;
@@ -51,6 +53,8 @@ impl MigratorTrait for T3DeviceMigrator {
             Box::new(m20260715_add_auto_tagging_rules::Migration),
             Box::new(m20260812_add_lan_scan_fields::Migration),
             Box::new(m20260819_add_fdd_tables::Migration),
+            Box::new(m20261018_add_trendlog_rollup_tables::Migration),
+            Box::new(m20261019_add_trendlog_detail_numeric_columns::Migration),
         ]
     }
 }
@@ -0,0 +1,44 @@
+use sea_orm_migration::{async_trait::async_trait, prelude::*};
+
+/// Migration: add TRENDLOG_ROLLUP_HOURLY / TRENDLOG_ROLLUP_DAILY.
+///
+/// Per-ParentId min/max/sum/count/last summaries of TRENDLOG_DATA_DETAIL,
+/// maintained by the FFI sync and rebuildable from raw data. They stay in
+/// the main database when raw data moves to partitions.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        for table in ["TRENDLOG_ROLLUP_HOURLY", "TRENDLOG_ROLLUP_DAILY"] {
+            db.execute_unprepared(&format!(
+                "CREATE TABLE IF NOT EXISTS {table} (
+                    ParentId    INTEGER NOT NULL,
+                    BucketStart TEXT    NOT NULL,
+                    MinValue    REAL    NOT NULL,
+                    MaxValue    REAL    NOT NULL,
+                    SumValue    REAL    NOT NULL,
+                    SampleCount INTEGER NOT NULL,
+                    LastValue   REAL    NOT NULL,
+                    LastTime    TEXT    NOT NULL,
+                    PRIMARY KEY (ParentId, BucketStart)
+                )"
+            ))
+            .await?;
+            db.execute_unprepared(&format!(
+                "CREATE INDEX IF NOT EXISTS IDX_{table}_BUCKET ON {table} (BucketStart)"
+            ))
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        // Keep rollups — they may hold the only copy of purged history.
+        Ok(())
+    }
+}
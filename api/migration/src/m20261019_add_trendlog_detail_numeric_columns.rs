@@ -0,0 +1,34 @@
+use sea_orm_migration::{async_trait::async_trait, prelude::*};
+
+/// Migration: add NumericValue / LoggingTime to TRENDLOG_DATA_DETAIL.
+///
+/// Nullable, so the ALTER is instant on large tables. New rows get both
+/// columns from the writers; existing rows are backfilled, and the covering
+/// index built, in the background by `migrate_trendlog_numeric`.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        let columns: Vec<(&str, &str)> = vec![
+            ("NumericValue", "REAL DEFAULT NULL"),
+            ("LoggingTime", "INTEGER DEFAULT NULL"),
+        ];
+
+        for (col, col_def) in &columns {
+            let sql = format!("ALTER TABLE TRENDLOG_DATA_DETAIL ADD COLUMN {} {}", col, col_def);
+            // Ignore error if column already exists (SQLite: duplicate column name)
+            let _ = db.execute_unprepared(&sql).await;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        // Columns stay — readers fall back to the text columns when they're NULL.
+        Ok(())
+    }
+}
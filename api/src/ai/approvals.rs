@@ -0,0 +1,333 @@
+// AI Chat — Write approval queue.
+//
+// Write tool calls from the AI loop don't run on the model's say-so. Each
+// one becomes a pending action (AI_PENDING_ACTIONS), is streamed to the UI
+// as an `approval_required` event, and the loop waits until a user approves
+// or rejects it via POST /api/ai/approvals/:id/{approve,reject} — or until
+// it times out. Every decision stays in the table.
+//
+// GET  /api/ai/approvals               — list (?status=pending&sessionId=...)
+// POST /api/ai/approvals/:id/approve   — run the tool
+// POST /api/ai/approvals/:id/reject    — body { "reason": "..." }
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+    routing::{get, post},
+    Extension, Router,
+};
+use once_cell::sync::Lazy;
+use sea_orm::{ConnectionTrait, DatabaseBackend, DatabaseConnection, QueryResult, Statement, Value as DbValue};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::sync::oneshot;
+use tracing::{info, warn};
+
+use crate::app_state::T3AppState;
+use crate::auth::CurrentUser;
+use crate::error::{Error, Result};
+
+/// How long a write waits for a decision before it's dropped.
+pub const APPROVAL_TIMEOUT_SECS: u64 = 300;
+
+/// Whether an AI tool call must be approved before it runs: every tool that
+/// writes to devices or site configuration, the same set the audit trail
+/// records and read-only MCP tokens are refused.
+pub fn requires_approval(name: &str) -> bool {
+    crate::audit::tools::is_audited(name)
+}
+
+const DDL: &str = "
+CREATE TABLE IF NOT EXISTS AI_PENDING_ACTIONS (
+    id           TEXT PRIMARY KEY,
+    session_id   TEXT NOT NULL,
+    tool_call_id TEXT,
+    tool_name    TEXT NOT NULL,
+    arguments    TEXT NOT NULL,
+    requested_by TEXT,
+    status       TEXT NOT NULL DEFAULT 'pending',
+    created_at   INTEGER NOT NULL,
+    expires_at   INTEGER NOT NULL,
+    decided_by   TEXT,
+    decided_at   INTEGER,
+    reason       TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_ai_pending_actions_status ON AI_PENDING_ACTIONS (status, created_at);
+";
+
+/// A write tool call waiting for (or past) a human decision.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingAction {
+    pub id: String,
+    pub session_id: String,
+    pub tool_call_id: Option<String>,
+    pub tool_name: String,
+    pub arguments: Value,
+    /// The chat user the model was acting for.
+    pub requested_by: Option<String>,
+    /// pending | approved | rejected | expired
+    pub status: String,
+    /// Unix epoch ms.
+    pub created_at: i64,
+    pub expires_at: i64,
+    pub decided_by: Option<String>,
+    pub decided_at: Option<i64>,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    Approved { by: Option<String> },
+    Rejected { by: Option<String>, reason: Option<String> },
+    Expired,
+}
+
+/// Chat loops blocked on a decision, by action id. Whoever removes the
+/// entry (a decision or the timeout) settles the action.
+static WAITERS: Lazy<Mutex<HashMap<String, oneshot::Sender<Decision>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn stmt(sql: &str, values: Vec<DbValue>) -> Statement {
+    Statement::from_sql_and_values(DatabaseBackend::Sqlite, sql, values)
+}
+
+fn db_err(e: sea_orm::DbErr) -> Error {
+    Error::DbError(e.to_string())
+}
+
+pub(crate) async fn approvals_db(state: &T3AppState) -> DatabaseConnection {
+    state.local_config_conn.as_ref().unwrap_or(&state.conn).lock().await.clone()
+}
+
+pub async fn ensure_schema(db: &DatabaseConnection) -> Result<()> {
+    db.execute_unprepared(DDL).await.map_err(db_err)?;
+    Ok(())
+}
+
+fn action_from_row(row: &QueryResult) -> PendingAction {
+    let arguments: String = row.try_get("", "arguments").unwrap_or_default();
+    PendingAction {
+        id: row.try_get("", "id").unwrap_or_default(),
+        session_id: row.try_get("", "session_id").unwrap_or_default(),
+        tool_call_id: row.try_get("", "tool_call_id").ok().flatten(),
+        tool_name: row.try_get("", "tool_name").unwrap_or_default(),
+        arguments: serde_json::from_str(&arguments).unwrap_or(Value::String(arguments)),
+        requested_by: row.try_get("", "requested_by").ok().flatten(),
+        status: row.try_get("", "status").unwrap_or_default(),
+        created_at: row.try_get("", "created_at").unwrap_or(0),
+        expires_at: row.try_get("", "expires_at").unwrap_or(0),
+        decided_by: row.try_get("", "decided_by").ok().flatten(),
+        decided_at: row.try_get("", "decided_at").ok().flatten(),
+        reason: row.try_get("", "reason").ok().flatten(),
+    }
+}
+
+pub async fn get_action(db: &DatabaseConnection, id: &str) -> Result<Option<PendingAction>> {
+    let row = db
+        .query_one(stmt("SELECT * FROM AI_PENDING_ACTIONS WHERE id = ?", vec![id.into()]))
+        .await
+        .map_err(db_err)?;
+    Ok(row.as_ref().map(action_from_row))
+}
+
+/// Record a pending action and register a waiter for its decision.
+pub async fn create(
+    db: &DatabaseConnection,
+    session_id: &str,
+    tool_call_id: Option<&str>,
+    tool_name: &str,
+    arguments: &Value,
+    requested_by: Option<&str>,
+    timeout: Duration,
+) -> Result<(PendingAction, oneshot::Receiver<Decision>)> {
+    ensure_schema(db).await?;
+    let id = uuid::Uuid::new_v4().to_string();
+    let now = chrono::Utc::now().timestamp_millis();
+    // Register the waiter before the row exists, so `list` never sees the
+    // row as an orphan and expires it under a waiting chat loop.
+    let (tx, rx) = oneshot::channel();
+    WAITERS.lock().unwrap().insert(id.clone(), tx);
+    let inserted = db.execute(stmt(
+        "INSERT INTO AI_PENDING_ACTIONS \
+         (id, session_id, tool_call_id, tool_name, arguments, requested_by, created_at, expires_at) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        vec![
+            id.clone().into(),
+            session_id.into(),
+            tool_call_id.map(str::to_owned).into(),
+            tool_name.into(),
+            arguments.to_string().into(),
+            requested_by.map(str::to_owned).into(),
+            now.into(),
+            (now + timeout.as_millis() as i64).into(),
+        ],
+    ))
+    .await;
+    if let Err(e) = inserted {
+        WAITERS.lock().unwrap().remove(&id);
+        return Err(db_err(e));
+    }
+
+    let action = get_action(db, &id).await?.ok_or_else(|| Error::ServerError("Pending action not saved".into()))?;
+    Ok((action, rx))
+}
+
+/// Record `decision` on a still-pending action; false if it was already settled.
+async fn settle(db: &DatabaseConnection, id: &str, decision: &Decision) -> Result<bool> {
+    let (status, by, reason) = match decision {
+        Decision::Approved { by } => ("approved", by.clone(), None),
+        Decision::Rejected { by, reason } => ("rejected", by.clone(), reason.clone()),
+        Decision::Expired => ("expired", None, None),
+    };
+    let result = db.execute(stmt(
+        "UPDATE AI_PENDING_ACTIONS SET status = ?, decided_by = ?, decided_at = ?, reason = ? \
+         WHERE id = ? AND status = 'pending'",
+        vec![
+            status.into(),
+            by.into(),
+            chrono::Utc::now().timestamp_millis().into(),
+            reason.into(),
+            id.into(),
+        ],
+    ))
+    .await
+    .map_err(db_err)?;
+    Ok(result.rows_affected() == 1)
+}
+
+/// Wait for a decision on `action`; after `timeout` it expires.
+pub async fn wait(db: &DatabaseConnection, action: &PendingAction, rx: oneshot::Receiver<Decision>, timeout: Duration) -> Decision {
+    match tokio::time::timeout(timeout, rx).await {
+        Ok(Ok(decision)) => decision,
+        // Timed out (or the sender vanished) — unless a decision raced in
+        // first, the action expires.
+        _ => {
+            if WAITERS.lock().unwrap().remove(&action.id).is_none() {
+                if let Ok(Some(settled)) = get_action(db, &action.id).await {
+                    match settled.status.as_str() {
+                        "approved" => return Decision::Approved { by: settled.decided_by },
+                        "rejected" => return Decision::Rejected { by: settled.decided_by, reason: settled.reason },
+                        _ => {}
+                    }
+                }
+            }
+            if let Err(e) = settle(db, &action.id, &Decision::Expired).await {
+                warn!("[AI] Failed to expire pending action {}: {}", action.id, e);
+            }
+            Decision::Expired
+        }
+    }
+}
+
+/// Approve or reject a pending action and wake its chat loop.
+pub async fn decide(db: &DatabaseConnection, id: &str, decision: Decision) -> Result<PendingAction> {
+    ensure_schema(db).await?;
+    let waiter = WAITERS.lock().unwrap().remove(id);
+    let Some(waiter) = waiter else {
+        return match get_action(db, id).await? {
+            None => Err(Error::NotFound),
+            Some(action) if action.status == "pending" => {
+                // Left over from before a restart; nothing is waiting for it.
+                settle(db, id, &Decision::Expired).await?;
+                Err(Error::BadRequest(format!("Action {} expired", id)))
+            }
+            Some(action) => Err(Error::BadRequest(format!("Action {} is already {}", id, action.status))),
+        };
+    };
+    // Dropping the waiter unsent lets the chat loop read the settled status
+    if !settle(db, id, &decision).await? {
+        return Err(Error::BadRequest(format!("Action {} is no longer pending", id)));
+    }
+    let _ = waiter.send(decision);
+    get_action(db, id).await?.ok_or(Error::NotFound)
+}
+
+/// Actions, newest first. Pending rows nothing is waiting for (left over
+/// from a restart) are expired first.
+pub async fn list(db: &DatabaseConnection, status: Option<&str>, session_id: Option<&str>) -> Result<Vec<PendingAction>> {
+    ensure_schema(db).await?;
+    let waiting: HashSet<String> = WAITERS.lock().unwrap().keys().cloned().collect();
+    let orphans = db
+        .query_all(stmt("SELECT id FROM AI_PENDING_ACTIONS WHERE status = 'pending'", vec![]))
+        .await
+        .map_err(db_err)?;
+    for row in orphans {
+        let id: String = row.try_get("", "id").unwrap_or_default();
+        if !waiting.contains(&id) {
+            settle(db, &id, &Decision::Expired).await?;
+        }
+    }
+
+    let mut sql = String::from("SELECT * FROM AI_PENDING_ACTIONS WHERE 1 = 1");
+    let mut values: Vec<DbValue> = Vec::new();
+    if let Some(status) = status {
+        sql.push_str(" AND status = ?");
+        values.push(status.into());
+    }
+    if let Some(session_id) = session_id {
+        sql.push_str(" AND session_id = ?");
+        values.push(session_id.into());
+    }
+    sql.push_str(" ORDER BY created_at DESC LIMIT 500");
+    let rows = db.query_all(stmt(&sql, values)).await.map_err(db_err)?;
+    Ok(rows.iter().map(action_from_row).collect())
+}
+
+// ═══ Routes ═══
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListQuery {
+    pub status: Option<String>,
+    pub session_id: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RejectRequest {
+    pub reason: Option<String>,
+}
+
+fn username(user: Option<Extension<CurrentUser>>) -> Option<String> {
+    user.map(|Extension(CurrentUser(u))| u.username)
+}
+
+async fn handle_list(State(state): State<T3AppState>, Query(q): Query<ListQuery>) -> Result<Json<Value>> {
+    let actions = list(&approvals_db(&state).await, q.status.as_deref(), q.session_id.as_deref()).await?;
+    Ok(Json(json!({ "actions": actions })))
+}
+
+async fn handle_approve(
+    State(state): State<T3AppState>,
+    Path(id): Path<String>,
+    user: Option<Extension<CurrentUser>>,
+) -> Result<Json<Value>> {
+    let by = username(user);
+    info!("[AI] Pending action {} approved by {:?}", id, by);
+    let action = decide(&approvals_db(&state).await, &id, Decision::Approved { by }).await?;
+    Ok(Json(json!(action)))
+}
+
+async fn handle_reject(
+    State(state): State<T3AppState>,
+    Path(id): Path<String>,
+    user: Option<Extension<CurrentUser>>,
+    body: Option<Json<RejectRequest>>,
+) -> Result<Json<Value>> {
+    let by = username(user);
+    let reason = body.and_then(|Json(b)| b.reason).filter(|r| !r.trim().is_empty());
+    info!("[AI] Pending action {} rejected by {:?}", id, by);
+    let action = decide(&approvals_db(&state).await, &id, Decision::Rejected { by, reason }).await?;
+    Ok(Json(json!(action)))
+}
+
+pub fn approval_routes() -> Router<T3AppState> {
+    Router::new()
+        .route("/api/ai/approvals", get(handle_list))
+        .route("/api/ai/approvals/:id/approve", post(handle_approve))
+        .route("/api/ai/approvals/:id/reject", post(handle_reject))
+}
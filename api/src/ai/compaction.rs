@@ -0,0 +1,322 @@
+// AI Chat — Conversation compaction for long sessions.
+//
+// The chat loop resends the whole conversation on every LLM call. Once the
+// estimated prompt exceeds the model's budget, the oldest user turns (their
+// questions, the tools they ran and the answers) are folded into a short
+// summary that rides on the system prompt, and only the most recent turns
+// are sent verbatim. If that still doesn't fit, older tool results of the
+// current turn are cut down to a digest.
+//
+// The full history is left alone — it's what the UI shows and what the
+// session file keeps. The summary and the number of turns it covers are
+// saved with the session, so a resumed session picks up from the summary
+// instead of starting over. Devices and changes mentioned in summarized
+// turns are also saved to site memory (`mcp_memory.json`), which every new
+// system prompt includes.
+
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::types::Message;
+
+/// User turns always sent verbatim.
+const KEEP_TURNS: usize = 2;
+/// Tool results of the current turn kept whole when digesting the rest.
+const KEEP_TOOL_RESULTS: usize = 4;
+/// Characters kept from a digested tool result.
+const TOOL_DIGEST_CHARS: usize = 300;
+/// Share of the context window the prompt may use; the rest is for the reply.
+const PROMPT_SHARE: f64 = 0.75;
+/// Summaries beyond this are cut from the front (oldest turns go first).
+const MAX_SUMMARY_CHARS: usize = 12_000;
+/// Heading of the summary inside the system prompt.
+pub const SUMMARY_HEADING: &str = "## Earlier in this conversation (compacted)";
+
+/// Summary of the oldest `turns` user turns of a session.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Compaction {
+    pub summary: String,
+    pub turns: usize,
+}
+
+/// What one compaction pass did.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Outcome {
+    pub tokens_before: usize,
+    pub tokens_after: usize,
+    /// Turns newly folded into the summary.
+    pub turns_compacted: usize,
+    pub tool_results_digested: usize,
+    /// Devices and changes from the newly summarized turns, for site memory.
+    pub facts: Vec<String>,
+}
+
+/// Context window in tokens when the request doesn't give one.
+pub fn default_context_tokens(provider: &str, model: &str) -> usize {
+    let model = model.to_ascii_lowercase();
+    match provider {
+        "anthropic" => 200_000,
+        "gemini" if model.contains("1.0") => 32_768,
+        "gemini" => 1_048_576,
+        // Local servers default to small windows unless configured otherwise
+        _ => 32_768,
+    }
+}
+
+/// Prompt tokens available for messages in a `context_tokens` window after
+/// `reserved` tokens (tool definitions).
+pub fn prompt_budget(context_tokens: usize, reserved: usize) -> usize {
+    ((context_tokens as f64 * PROMPT_SHARE) as usize).saturating_sub(reserved)
+}
+
+/// Rough token estimate (~4 characters per token), tool calls included.
+pub fn estimate_tokens(messages: &[Message]) -> usize {
+    let chars: usize = messages
+        .iter()
+        .map(|m| {
+            m.content.len()
+                + m.tool_calls
+                    .as_ref()
+                    .map(|calls| calls.iter().map(|c| c.function.name.len() + c.function.arguments.len()).sum())
+                    .unwrap_or(0)
+        })
+        .sum();
+    chars / 4
+}
+
+fn clip(text: &str, max: usize) -> String {
+    let text = text.trim();
+    match text.char_indices().nth(max) {
+        Some((cut, _)) => format!("{}…", &text[..cut]),
+        None => text.to_string(),
+    }
+}
+
+/// Index of each user message.
+fn user_turns(messages: &[Message]) -> Vec<usize> {
+    messages.iter().enumerate().filter(|(_, m)| m.role == "user").map(|(i, _)| i).collect()
+}
+
+/// The messages sent to the model: the system prompt (with the summary
+/// appended) and everything from the first turn the summary doesn't cover.
+pub fn view(messages: &[Message], compaction: Option<&Compaction>) -> Vec<Message> {
+    let Some(c) = compaction.filter(|c| c.turns > 0) else {
+        return messages.to_vec();
+    };
+    let turns = user_turns(messages);
+    // Nothing to skip (or the history changed under us): send it all
+    let Some(&from) = turns.get(c.turns) else {
+        return messages.to_vec();
+    };
+    let mut out: Vec<Message> = messages.iter().take_while(|m| m.role == "system").cloned().collect();
+    match out.first_mut() {
+        Some(system) => system.content = format!("{}\n\n{}\n{}", system.content, SUMMARY_HEADING, c.summary),
+        None => out.push(Message {
+            role: "system".to_string(),
+            content: format!("{}\n{}", SUMMARY_HEADING, c.summary),
+            name: None,
+            tool_calls: None,
+            tool_call_id: None,
+            ui: None,
+            usage: None,
+        }),
+    }
+    out.extend(messages[from..].iter().cloned());
+    out
+}
+
+/// Argument values worth keeping in a summary line. Credentials are masked
+/// the way the audit trail masks them, since summaries and facts end up in
+/// site memory and later system prompts.
+fn brief_args(arguments: &str) -> String {
+    let Ok(args) = serde_json::from_str::<Value>(arguments) else {
+        return String::new();
+    };
+    let Value::Object(args) = crate::audit::redact(&args) else {
+        return String::new();
+    };
+    args.iter()
+        .filter(|(k, _)| !matches!(k.as_str(), "confirm" | "reason"))
+        .take(4)
+        .map(|(k, v)| format!("{}={}", k, clip(&v.to_string(), 40)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Serial numbers named in tool arguments.
+fn serials(arguments: &str, into: &mut BTreeSet<i64>) {
+    let Ok(args) = serde_json::from_str::<Value>(arguments) else { return };
+    into.extend(args.get("serial_number").and_then(|v| v.as_i64()));
+    for key in ["serial_numbers", "points"] {
+        if let Some(items) = args.get(key).and_then(|v| v.as_array()) {
+            into.extend(items.iter().filter_map(|v| v.as_i64().or_else(|| v.get("serial_number")?.as_i64())));
+        }
+    }
+}
+
+/// Whether a wrapped tool result (`{"tool":..,"ok":..}`) reports success.
+fn tool_ok(content: &str) -> bool {
+    serde_json::from_str::<Value>(content)
+        .ok()
+        .and_then(|v| v.get("ok").and_then(|ok| ok.as_bool()))
+        .unwrap_or(true)
+}
+
+/// Summary lines for one user turn (the user message up to the next one),
+/// plus the facts worth remembering.
+fn summarize_turn(turn: &[Message], is_write_tool: fn(&str) -> bool) -> (String, Vec<String>) {
+    let mut lines = vec![format!("- User: {}", clip(&turn[0].content, 200))];
+    let mut devices = BTreeSet::new();
+    let mut facts = Vec::new();
+    let mut tools = Vec::new();
+    for (i, msg) in turn.iter().enumerate() {
+        let Some(calls) = msg.tool_calls.as_ref() else { continue };
+        for call in calls {
+            serials(&call.function.arguments, &mut devices);
+            let ok = turn[i..]
+                .iter()
+                .find(|m| m.role == "tool" && m.tool_call_id.as_deref() == Some(call.id.as_str()))
+                .is_none_or(|m| tool_ok(&m.content));
+            let args = brief_args(&call.function.arguments);
+            tools.push(format!("{}({}){}", call.function.name, args, if ok { "" } else { " failed" }));
+            if ok && is_write_tool(&call.function.name) {
+                facts.push(format!("{}({})", call.function.name, args));
+            }
+        }
+    }
+    if !tools.is_empty() {
+        let more = tools.len().saturating_sub(8);
+        let mut shown = tools.into_iter().take(8).collect::<Vec<_>>().join("; ");
+        if more > 0 {
+            shown.push_str(&format!("; +{} more", more));
+        }
+        lines.push(format!("  Tools: {}", shown));
+    }
+    if let Some(answer) = turn.iter().rev().find(|m| m.role == "assistant" && !m.content.trim().is_empty()) {
+        lines.push(format!("  Answer: {}", clip(&answer.content, 300)));
+    }
+    if !devices.is_empty() {
+        let list = devices.iter().map(|d| d.to_string()).collect::<Vec<_>>().join(", ");
+        facts.insert(0, format!("devices {}", list));
+    }
+    (lines.join("\n"), facts)
+}
+
+/// Keep the newest part of an over-long summary.
+fn cap_summary(summary: String) -> String {
+    if summary.len() <= MAX_SUMMARY_CHARS {
+        return summary;
+    }
+    let mut cut = summary.len() - MAX_SUMMARY_CHARS;
+    while !summary.is_char_boundary(cut) {
+        cut += 1;
+    }
+    let rest = &summary[cut..];
+    let rest = rest.find("\n- ").map(|i| &rest[i + 1..]).unwrap_or(rest);
+    format!("- (older turns omitted)\n{}", rest)
+}
+
+/// Make `messages` fit `budget` prompt tokens: summarize old turns into
+/// `compaction`, then digest old tool results. Returns the messages to send.
+pub fn fit(
+    messages: &[Message],
+    compaction: &mut Option<Compaction>,
+    budget: usize,
+    is_write_tool: fn(&str) -> bool,
+) -> (Vec<Message>, Outcome) {
+    let mut out = view(messages, compaction.as_ref());
+    let mut outcome = Outcome { tokens_before: estimate_tokens(&out), ..Default::default() };
+    outcome.tokens_after = outcome.tokens_before;
+    if outcome.tokens_before <= budget {
+        return (out, outcome);
+    }
+
+    // 1. Fold every turn but the last KEEP_TURNS into the summary
+    let turns = user_turns(messages);
+    let done = compaction.as_ref().map(|c| c.turns).unwrap_or(0).min(turns.len());
+    let target = turns.len().saturating_sub(KEEP_TURNS);
+    if target > done {
+        let mut summary = compaction.take().map(|c| c.summary).unwrap_or_default();
+        for t in done..target {
+            let (lines, facts) = summarize_turn(&messages[turns[t]..turns[t + 1]], is_write_tool);
+            if !summary.is_empty() {
+                summary.push('\n');
+            }
+            summary.push_str(&lines);
+            outcome.facts.extend(facts);
+        }
+        *compaction = Some(Compaction { summary: cap_summary(summary), turns: target });
+        outcome.turns_compacted = target - done;
+        out = view(messages, compaction.as_ref());
+    }
+
+    // 2. Still too big (one long turn): digest all but the newest tool results
+    if estimate_tokens(&out) > budget {
+        let tool_indices: Vec<usize> = out.iter().enumerate().filter(|(_, m)| m.role == "tool").map(|(i, _)| i).collect();
+        let older = tool_indices.len().saturating_sub(KEEP_TOOL_RESULTS);
+        for &i in &tool_indices[..older] {
+            if out[i].content.len() > TOOL_DIGEST_CHARS {
+                let total = out[i].content.len();
+                out[i].content = format!("{} [digested: {} chars, ask again for details]", clip(&out[i].content, TOOL_DIGEST_CHARS), total);
+                outcome.tool_results_digested += 1;
+            }
+        }
+    }
+
+    outcome.tokens_after = estimate_tokens(&out);
+    (out, outcome)
+}
+
+/// Facts kept per chat memory entry; the oldest go first.
+const MAX_MEMORY_FACTS: usize = 40;
+
+/// A chat session's site-memory entry with `facts` merged into the facts of
+/// its `previous` entry. The title and facts are kept as fields; `content` is
+/// only rendered from them for the memory readers.
+pub fn memory_entry(previous: Option<&Value>, key: &str, title: &str, facts: &[String], now: &str) -> Value {
+    let mut known: Vec<String> = previous
+        .and_then(|p| p.get("facts"))
+        .and_then(|f| serde_json::from_value(f.clone()).ok())
+        .unwrap_or_default();
+    for fact in facts {
+        if !known.contains(fact) {
+            known.push(fact.clone());
+        }
+    }
+    if known.len() > MAX_MEMORY_FACTS {
+        known = known.split_off(known.len() - MAX_MEMORY_FACTS);
+    }
+    let title = clip(title, 60);
+    let content = clip(&format!("Chat \"{}\": {}", title, known.join("; ")), 800);
+    let created_at = previous
+        .and_then(|p| p.get("created_at"))
+        .and_then(|v| v.as_str())
+        .unwrap_or(now);
+    serde_json::json!({
+        "key": key,
+        "title": title,
+        "facts": known,
+        "content": content,
+        "category": "conversation",
+        "created_at": created_at,
+        "updated_at": now,
+    })
+}
+
+/// Save what the summarized turns touched to site memory, one entry per
+/// chat session, so later conversations know about it.
+pub async fn remember(session_id: &str, title: &str, facts: &[String]) -> Result<(), String> {
+    if facts.is_empty() {
+        return Ok(());
+    }
+    let key = format!("chat-{}", session_id.chars().take(8).collect::<String>());
+    let now = chrono::Utc::now().to_rfc3339();
+    let mut memories = crate::mcp::storage::load_memories().await?;
+    let is_key = |m: &Value| m.get("key").and_then(|v| v.as_str()) == Some(key.as_str());
+    let entry = memory_entry(memories.iter().find(|m| is_key(m)), &key, title, facts, &now);
+    memories.retain(|m| !is_key(m));
+    memories.push(entry);
+    crate::mcp::storage::save_memories(&memories).await
+}
@@ -3,6 +3,8 @@
 // Mount all AI routes under /api/ai/* via create_ai_routes().
 // Called from server.rs during app construction.
 
+pub mod approvals;
+pub mod compaction;
 pub mod mcp_client;
 pub mod prompt_builder;
 pub mod providers;
@@ -11,6 +13,7 @@ pub mod session;
 pub mod session_store;
 pub mod tool_executor;
 pub mod types;
+pub mod usage;
 
 use axum::Router;
 use crate::app_state::T3AppState;
@@ -158,7 +158,7 @@ impl LlmProvider for AnthropicProvider {
                         url,
                         e
                     );
-                    last_err = Some(AiError::Provider(format!(
+                    last_err = Some(AiError::Unavailable(format!(
                         "Failed to connect to Anthropic: {}",
                         e
                     )));
@@ -172,17 +172,17 @@ impl LlmProvider for AnthropicProvider {
 
         let response = response.ok_or_else(|| {
             last_err.unwrap_or_else(|| {
-                AiError::Provider("Failed to connect to Anthropic".to_string())
+                AiError::Unavailable("Failed to connect to Anthropic".to_string())
             })
         })?;
 
         let status = response.status();
         if !status.is_success() {
             let body_text = response.text().await.unwrap_or_default();
-            return Err(AiError::Provider(format!(
-                "Anthropic returned {}: {}",
-                status, body_text
-            )));
+            return Err(AiError::from_status(
+                status,
+                format!("Anthropic returned {}: {}", status, body_text),
+            ));
         }
 
         // Parse SSE stream
@@ -192,6 +192,9 @@ impl LlmProvider for AnthropicProvider {
         let mut tool_name_buf: Option<String> = None;
         let mut tool_args_buf: String = String::new();
         let mut in_tool_use = false;
+        // message_start reports the prompt tokens, message_delta the running output count
+        let mut input_tokens = 0u64;
+        let mut output_tokens = 0u64;
 
         while let Some(chunk_result) = stream.next().await {
             let chunk = chunk_result
@@ -223,6 +226,19 @@ impl LlmProvider for AnthropicProvider {
                     let event_type = parsed["type"].as_str().unwrap_or("");
 
                     match event_type {
+                        "message_start" => {
+                            let u = &parsed["message"]["usage"];
+                            input_tokens = ["input_tokens", "cache_creation_input_tokens", "cache_read_input_tokens"]
+                                .iter()
+                                .filter_map(|k| u[*k].as_u64())
+                                .sum();
+                            output_tokens = u["output_tokens"].as_u64().unwrap_or(0);
+                        }
+                        "message_delta" => {
+                            if let Some(n) = parsed["usage"]["output_tokens"].as_u64() {
+                                output_tokens = n;
+                            }
+                        }
                         "content_block_start" => {
                             if let Some(cb) = parsed.get("content_block") {
                                 if cb["type"].as_str() == Some("tool_use") {
@@ -286,6 +302,10 @@ impl LlmProvider for AnthropicProvider {
             }
         }
 
+        if input_tokens > 0 || output_tokens > 0 {
+            let _ = tx.send(StreamEvent::Usage { input_tokens, output_tokens });
+        }
+
         Ok(None)
     }
 }
@@ -0,0 +1,151 @@
+// AI Chat — Provider fallback chains.
+//
+// A chat is configured with one provider plus an optional list of fallbacks
+// (e.g. local Ollama → Anthropic). Each call tries the providers in order:
+// transient failures (connection errors, timeouts, 429, 5xx) are retried
+// with exponential backoff, then the next provider takes over. Anything
+// else — a bad API key, an unknown model — fails over straight away.
+//
+// A provider that has already streamed output is never retried: the
+// frontend has shown that text, and replaying it would duplicate it.
+
+use std::time::Duration;
+
+use tokio::sync::mpsc::{self, UnboundedSender};
+
+use super::super::types::{AiError, Message, ProviderTarget, StreamEvent};
+use super::{get_provider, LlmProvider, ToolDef};
+
+/// Retries per provider when the settings don't say.
+pub const DEFAULT_MAX_RETRIES: u32 = 2;
+/// Most retries per provider a request may ask for.
+pub const MAX_RETRIES: u32 = 5;
+const BASE_DELAY: Duration = Duration::from_millis(500);
+const MAX_DELAY: Duration = Duration::from_secs(8);
+
+/// Providers tried in order for every LLM call.
+pub struct FallbackChain {
+    targets: Vec<(ProviderTarget, Box<dyn LlmProvider>)>,
+    max_retries: u32,
+    base_delay: Duration,
+}
+
+/// The provider that answered a call.
+#[derive(Debug, Clone)]
+pub struct ChainReply {
+    /// Position in the chain — 0 is the primary provider.
+    pub index: usize,
+    pub target: ProviderTarget,
+    pub finish_reason: Option<String>,
+}
+
+impl FallbackChain {
+    /// Build a chain; fails if any provider key is unknown.
+    pub fn new(targets: Vec<ProviderTarget>, max_retries: u32) -> Result<Self, AiError> {
+        if targets.is_empty() {
+            return Err(AiError::NoProvider);
+        }
+        let targets = targets
+            .into_iter()
+            .map(|t| get_provider(&t.provider).map(|p| (t, p)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { targets, max_retries, base_delay: BASE_DELAY })
+    }
+
+    /// Override the first backoff delay (doubles on each retry).
+    pub fn with_base_delay(mut self, delay: Duration) -> Self {
+        self.base_delay = delay;
+        self
+    }
+
+    /// Stream one chat completion from the first provider that succeeds.
+    /// Emits `ProviderFallback` on `tx` whenever the next provider takes over.
+    pub async fn stream_chat(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDef],
+        tx: &UnboundedSender<StreamEvent>,
+    ) -> Result<ChainReply, AiError> {
+        let mut last_err = AiError::NoProvider;
+        for (index, (target, provider)) in self.targets.iter().enumerate() {
+            if index > 0 {
+                let from = &self.targets[index - 1].0;
+                tracing::warn!("[AI] Falling back from {} to {}: {}", label(from), label(target), last_err);
+                let _ = tx.send(StreamEvent::ProviderFallback {
+                    from: label(from),
+                    to: label(target),
+                    reason: last_err.to_string(),
+                });
+            }
+            let mut attempt = 0;
+            loop {
+                let (result, streamed) = attempt_once(provider.as_ref(), target, messages, tools, tx).await;
+                match result {
+                    Ok(finish_reason) => {
+                        return Ok(ChainReply { index, target: target.clone(), finish_reason });
+                    }
+                    // Output already reached the user — neither a retry nor a fallback can take it back
+                    Err(e) if streamed => return Err(e),
+                    Err(e) if e.is_transient() && attempt < self.max_retries => {
+                        let delay = self.backoff(attempt);
+                        attempt += 1;
+                        tracing::warn!("[AI] {} failed ({}), retry {}/{} in {:?}",
+                            label(target), e, attempt, self.max_retries, delay);
+                        tokio::time::sleep(delay).await;
+                    }
+                    Err(e) => {
+                        last_err = e;
+                        break;
+                    }
+                }
+            }
+        }
+        Err(last_err)
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.base_delay.saturating_mul(1 << attempt.min(16)).min(MAX_DELAY)
+    }
+}
+
+/// Run one provider call, forwarding its events to `tx`. Also reports
+/// whether any output (text, thinking or tool calls) was forwarded.
+async fn attempt_once(
+    provider: &dyn LlmProvider,
+    target: &ProviderTarget,
+    messages: &[Message],
+    tools: &[ToolDef],
+    tx: &UnboundedSender<StreamEvent>,
+) -> (Result<Option<String>, AiError>, bool) {
+    let (attempt_tx, mut attempt_rx) = mpsc::unbounded_channel();
+    let call = provider.stream_chat(
+        &target.endpoint,
+        target.api_key.as_deref(),
+        &target.model,
+        messages,
+        tools,
+        &attempt_tx,
+    );
+    tokio::pin!(call);
+
+    let mut streamed = false;
+    let mut forward = |event: StreamEvent| {
+        streamed |= !matches!(event, StreamEvent::Usage { .. });
+        let _ = tx.send(event);
+    };
+    let result = loop {
+        tokio::select! {
+            biased;
+            Some(event) = attempt_rx.recv() => forward(event),
+            result = &mut call => break result,
+        }
+    };
+    while let Ok(event) = attempt_rx.try_recv() {
+        forward(event);
+    }
+    (result, streamed)
+}
+
+fn label(target: &ProviderTarget) -> String {
+    format!("{}/{}", if target.provider.is_empty() { "local" } else { &target.provider }, target.model)
+}
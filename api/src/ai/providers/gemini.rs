@@ -242,7 +242,7 @@ impl GeminiProvider {
                         url,
                         e
                     );
-                    last_err = Some(AiError::Provider(format!(
+                    last_err = Some(AiError::Unavailable(format!(
                         "Failed to connect to Gemini: {}",
                         e
                     )));
@@ -258,16 +258,16 @@ impl GeminiProvider {
             tracing::info!("[Gemini HTTP] url={} status={} headers={:?}", url, status, response.headers());
             if !status.is_success() {
                 let body_text = response.text().await.unwrap_or_default();
-                return Err(AiError::Provider(format!(
-                    "Gemini returned {}: {}",
-                    status, body_text
-                )));
+                return Err(AiError::from_status(
+                    status,
+                    format!("Gemini returned {}: {}", status, body_text),
+                ));
             }
             return Ok(response);
         }
 
         Err(last_err.unwrap_or_else(|| {
-            AiError::Provider("Failed to connect to Gemini".to_string())
+            AiError::Unavailable("Failed to connect to Gemini".to_string())
         }))
     }
 
@@ -334,6 +334,22 @@ impl GeminiProvider {
         }
     }
 
+    /// Prompt and output tokens from a response's `usageMetadata`. Streamed
+    /// chunks repeat the running totals, so only the last one counts.
+    /// Thinking tokens are billed as output.
+    pub fn usage(parsed: &Value) -> Option<(u64, u64)> {
+        let meta = parsed.get("usageMetadata")?;
+        let prompt = meta["promptTokenCount"].as_u64().unwrap_or(0);
+        let output = meta["candidatesTokenCount"].as_u64().unwrap_or(0) + meta["thoughtsTokenCount"].as_u64().unwrap_or(0);
+        Some((prompt, output))
+    }
+
+    fn send_usage(usage: Option<(u64, u64)>, tx: &UnboundedSender<StreamEvent>) {
+        if let Some((input_tokens, output_tokens)) = usage {
+            let _ = tx.send(StreamEvent::Usage { input_tokens, output_tokens });
+        }
+    }
+
     /// POST to `:streamGenerateContent?alt=sse` and parse the SSE stream.
     async fn stream_generate(
         client: &Client,
@@ -348,6 +364,7 @@ impl GeminiProvider {
 
         let mut stream = response.bytes_stream();
         let mut buffer = String::new();
+        let mut usage = None;
 
         while let Some(chunk_result) = stream.next().await {
             let chunk = chunk_result
@@ -380,10 +397,12 @@ impl GeminiProvider {
                     };
 
                     Self::handle_candidate(&parsed, final_text, had_tool_calls, tx);
+                    usage = Self::usage(&parsed).or(usage);
                 }
             }
         }
 
+        Self::send_usage(usage, tx);
         Ok(())
     }
 
@@ -408,6 +427,7 @@ impl GeminiProvider {
             .map_err(|e| AiError::Stream(format!("Invalid JSON from generateContent: {}", e)))?;
 
         Self::handle_candidate(&parsed, final_text, had_tool_calls, tx);
+        Self::send_usage(Self::usage(&parsed), tx);
 
         Ok(())
     }
@@ -1,7 +1,9 @@
 // AI Chat — Local provider (OpenAI-compatible API).
 //
 // Handles Ollama, vLLM, LM Studio, llama.cpp — any server that exposes
-// the OpenAI `/v1/chat/completions` endpoint with SSE streaming.
+// the OpenAI `/v1/chat/completions` endpoint with SSE streaming. These
+// servers disagree on how tool calls are streamed (parallel calls, missing
+// ids, calls written into the message text), so parsing accepts all of them.
 
 use async_trait::async_trait;
 use futures_util::StreamExt;
@@ -9,6 +11,7 @@ use reqwest::Client;
 use serde_json::{json, Value};
 use std::time::Instant;
 use tokio::sync::mpsc::UnboundedSender;
+use uuid::Uuid;
 
 use super::super::types::{AiError, Message, StreamEvent};
 use super::{LlmProvider, ToolDef};
@@ -91,7 +94,7 @@ impl LlmProvider for LocalProvider {
         let response = req_builder
             .send()
             .await
-            .map_err(|e| AiError::Provider(format!("Failed to connect: {}", e)))?;
+            .map_err(|e| AiError::Unavailable(format!("Failed to connect: {}", e)))?;
 
         let status = response.status();
         tracing::info!("[Local] Response: {}", status);
@@ -99,11 +102,11 @@ impl LlmProvider for LocalProvider {
             let text = response.text().await.unwrap_or_default();
             let truncated = if text.len() > 500 { format!("{}... ({} chars)", &text[..500], text.len()) } else { text.clone() };
             tracing::error!("[Local] LLM error response ({}): {}", status, truncated);
-            return Err(AiError::Provider(format!("LLM {}: {}", status, truncated)));
+            return Err(AiError::from_status(status, format!("LLM {}: {}", status, truncated)));
         }
 
         tracing::info!("[Local] Starting SSE stream parse");
-        let result = Self::parse_and_collect(response, tx).await?;
+        let result = Self::parse_and_collect(response, tools, tx).await?;
         Ok(Some(result.2))
     }
 }
@@ -111,11 +114,13 @@ impl LlmProvider for LocalProvider {
 impl LocalProvider {
     async fn parse_and_collect(
         resp: reqwest::Response,
+        tools: &[ToolDef],
         tx: &UnboundedSender<StreamEvent>,
     ) -> Result<(String, String, String), AiError> {
         let mut stream = resp.bytes_stream();
         let mut buffer = String::new();
-        let mut tool_call_buf: Option<(String, String, String)> = None;
+        // Tool calls being assembled from deltas — several when the server streams parallel calls
+        let mut pending_calls: Vec<PendingCall> = Vec::new();
         let mut full_text = String::new();
         let mut full_reasoning = String::new();
         let mut content_count = 0u64;
@@ -128,8 +133,13 @@ impl LocalProvider {
         // Buffer for content that arrives before tool calls (models without native reasoning)
         let mut pre_tool_content: Vec<String> = Vec::new();
         let mut has_tool_calls = false;
+        // Where a `<tool_call>` block starts in the answer of a native reasoning
+        // model — held back from the stream so it can be recovered below
+        let mut held_from: Option<usize> = None;
+        // Final chunk carries `usage` (requested via stream_options.include_usage)
+        let mut usage: Option<(u64, u64)> = None;
 
-        while let Some(chunk_result) = stream.next().await {
+        'stream: while let Some(chunk_result) = stream.next().await {
             let chunk = chunk_result.map_err(|e| {
                 AiError::Stream(format!("Stream read error: {}", e))
             })?;
@@ -150,16 +160,7 @@ impl LocalProvider {
 
                     let data = &line[6..];
                     if data == "[DONE]" {
-                        if let Some((id, name, args)) = tool_call_buf.take() {
-                            if is_valid_tool_args(&args) {
-                                let _ = tx.send(StreamEvent::ToolCall { id, name, arguments: args, thought_signature: None });
-                                tool_call_count += 1;
-                                has_tool_calls = true;
-                            }
-                        }
-                        // Flush buffered pre-tool content
-                        flush_pre_tool_content(tx, &mut pre_tool_content, &mut full_reasoning, &mut reasoning_count, &mut thinking_ended, has_tool_calls, thinking_start);
-                        continue;
+                        break 'stream;
                     }
 
                     let parsed: Value = match serde_json::from_str(data) {
@@ -167,34 +168,28 @@ impl LocalProvider {
                         Err(_) => continue,
                     };
 
-                    let delta = match parsed.get("choices").and_then(|c| c.get(0)).and_then(|c| c.get("delta")) {
+                    if let Some(u) = parsed.get("usage").filter(|u| u.is_object()) {
+                        usage = Some((
+                            u["prompt_tokens"].as_u64().unwrap_or(0),
+                            u["completion_tokens"].as_u64().unwrap_or(0),
+                        ));
+                    }
+
+                    let choice = parsed.get("choices").and_then(|c| c.get(0));
+                    if let Some(reason) = choice.and_then(|c| c.get("finish_reason")).and_then(|r| r.as_str()) {
+                        finish_reason = reason.to_string();
+                    }
+                    let delta = match choice.and_then(|c| c.get("delta")) {
                         Some(d) => d,
-                        None => {
-                            if let Some(reason) = parsed.get("choices").and_then(|c| c.get(0)).and_then(|c| c.get("finish_reason")).and_then(|r| r.as_str()) {
-                                finish_reason = reason.to_string();
-                            }
-                            continue;
-                        }
+                        None => continue,
                     };
 
                     // Check for tool_calls BEFORE content, so we know if content is pre-tool
                     if let Some(tool_calls) = delta.get("tool_calls").and_then(|tc| tc.as_array()) {
                         for tc in tool_calls {
-                            let tc_id = tc.get("id").and_then(|i| i.as_str()).unwrap_or("").to_string();
-                            if let Some(func) = tc.get("function") {
-                                let func_name = func.get("name").and_then(|n| n.as_str()).unwrap_or("").to_string();
-                                let func_args = func.get("arguments").and_then(|a| a.as_str()).unwrap_or("").to_string();
-                                if !tc_id.is_empty() && !func_name.is_empty() {
-                                    // Flush buffered pre-tool content as thinking before sending tool call
-                                    flush_pre_tool_content(tx, &mut pre_tool_content, &mut full_reasoning, &mut reasoning_count, &mut thinking_ended, true, thinking_start);
-                                    // Start new tool call
-                                    tool_call_buf = Some((tc_id, func_name, func_args));
-                                } else if !func_args.is_empty() {
-                                    // Arguments-only delta (may have no id/name) — append to existing
-                                    if let Some((_, _, ref mut existing_args)) = tool_call_buf {
-                                        existing_args.push_str(&func_args);
-                                    }
-                                }
+                            if merge_tool_call_delta(&mut pending_calls, tc) {
+                                // Flush buffered pre-tool content as thinking before the tool call
+                                flush_pre_tool_content(tx, &mut pre_tool_content, &mut full_reasoning, &mut reasoning_count, &mut thinking_ended, true, thinking_start);
                             }
                         }
                     }
@@ -221,10 +216,15 @@ impl LocalProvider {
                                     });
                                     thinking_ended = true;
                                 }
-                                let _ = tx.send(StreamEvent::TextDelta { content: t.to_string() });
+                                if held_from.is_none() && t.contains("<tool_call>") {
+                                    held_from = Some(full_text.len());
+                                }
+                                if held_from.is_none() {
+                                    let _ = tx.send(StreamEvent::TextDelta { content: t.to_string() });
+                                }
                                 full_text.push_str(t);
                                 content_count += 1;
-                            } else if has_tool_calls || !tool_call_buf.is_none() {
+                            } else if has_tool_calls || !pending_calls.is_empty() {
                                 // Content after tool calls in same stream — send as TextDelta
                                 if !thinking_ended {
                                     thinking_ended = true;
@@ -244,17 +244,60 @@ impl LocalProvider {
             }
         }
 
-        if let Some((id, name, args)) = tool_call_buf.take() {
-            if is_valid_tool_args(&args) {
-                let _ = tx.send(StreamEvent::ToolCall { id, name, arguments: args, thought_signature: None });
+        for call in pending_calls.drain(..) {
+            if is_valid_tool_args(&call.args) {
+                let id = if call.id.is_empty() { generated_call_id() } else { call.id };
+                let _ = tx.send(StreamEvent::ToolCall { id, name: call.name, arguments: call.args, thought_signature: None });
                 tool_call_count += 1;
                 has_tool_calls = true;
             }
         }
 
+        // Servers without native tool-call support leave the call as JSON in the text
+        let names: Vec<&str> = tools.iter().map(|t| t.name.as_str()).collect();
+        if let Some(from) = held_from {
+            let held = full_text.split_off(from);
+            match content_tool_calls(&held, &names).filter(|_| !has_tool_calls) {
+                Some((calls, rest)) => {
+                    tracing::info!("[Local] Recovered {} tool call(s) from reasoning model content", calls.len());
+                    for (name, arguments) in calls {
+                        let _ = tx.send(StreamEvent::ToolCall { id: generated_call_id(), name, arguments, thought_signature: None });
+                        tool_call_count += 1;
+                    }
+                    has_tool_calls = true;
+                    finish_reason = "tool_calls".to_string();
+                    if !rest.is_empty() {
+                        let _ = tx.send(StreamEvent::TextDelta { content: rest.clone() });
+                    }
+                    full_text.push_str(&rest);
+                }
+                None => {
+                    let _ = tx.send(StreamEvent::TextDelta { content: held.clone() });
+                    full_text.push_str(&held);
+                }
+            }
+        }
+        if !has_tool_calls && !pre_tool_content.is_empty() {
+            if let Some((calls, rest)) = content_tool_calls(&full_text, &names) {
+                tracing::info!("[Local] Recovered {} tool call(s) from message content", calls.len());
+                pre_tool_content = if rest.is_empty() { Vec::new() } else { vec![rest.clone()] };
+                full_text = rest;
+                for (name, arguments) in calls {
+                    let _ = tx.send(StreamEvent::ToolCall { id: generated_call_id(), name, arguments, thought_signature: None });
+                    tool_call_count += 1;
+                }
+                has_tool_calls = true;
+                finish_reason = "tool_calls".to_string();
+            }
+        }
+
         // Flush any remaining buffered pre-tool content at end of stream
         flush_pre_tool_content(tx, &mut pre_tool_content, &mut full_reasoning, &mut reasoning_count, &mut thinking_ended, has_tool_calls, thinking_start);
 
+        if let Some((input_tokens, output_tokens)) = usage {
+            let _ = tx.send(StreamEvent::Usage { input_tokens, output_tokens });
+        }
+
         tracing::info!("[Local] SSE done frames={} content={} reasoning={} tool_calls={} finish={}", frame_count, content_count, reasoning_count, tool_call_count, finish_reason);
 
         // Detect truncation: only when no real text was produced (model stalled in thinking)
@@ -267,6 +310,132 @@ impl LocalProvider {
     }
 }
 
+/// A tool call being assembled from streamed deltas.
+struct PendingCall {
+    /// `index` from the delta; servers that stream one call at a time omit it.
+    index: Option<u64>,
+    id: String,
+    name: String,
+    args: String,
+}
+
+/// Fold one `tool_calls[]` delta into the pending calls. Returns true when
+/// it starts a new call.
+///
+/// Servers differ: parallel calls are told apart by `index` (some send
+/// index 0 for every call and rely on distinct ids), ids may be missing
+/// altogether, and `arguments` may arrive as an object instead of a string.
+fn merge_tool_call_delta(pending: &mut Vec<PendingCall>, tc: &Value) -> bool {
+    let index = tc.get("index").and_then(|i| i.as_u64());
+    let id = tc.get("id").and_then(|i| i.as_str()).unwrap_or("");
+    let func = tc.get("function");
+    let name = func.and_then(|f| f.get("name")).and_then(|n| n.as_str()).unwrap_or("");
+    let args = match func.and_then(|f| f.get("arguments")) {
+        Some(Value::String(a)) => a.clone(),
+        Some(v @ Value::Object(_)) => v.to_string(),
+        _ => String::new(),
+    };
+
+    let existing = match index {
+        // Same index continues a call, unless the delta names a call of its own
+        Some(i) => pending.iter().rposition(|p| {
+            p.index == Some(i) && if id.is_empty() { name.is_empty() } else { p.id == id || p.id.is_empty() }
+        }),
+        // Without an index, a named delta starts a new call and the rest continue the last one
+        None if name.is_empty() => pending.len().checked_sub(1),
+        None => None,
+    };
+    match existing {
+        Some(pos) => {
+            let call = &mut pending[pos];
+            if call.id.is_empty() {
+                call.id = id.to_string();
+            }
+            if call.name.is_empty() {
+                call.name = name.to_string();
+            }
+            call.args.push_str(&args);
+            false
+        }
+        None if !name.is_empty() || !id.is_empty() => {
+            pending.push(PendingCall { index, id: id.to_string(), name: name.to_string(), args });
+            true
+        }
+        None => false,
+    }
+}
+
+/// Id for a tool call the server sent without one.
+fn generated_call_id() -> String {
+    format!("call_{}", Uuid::new_v4().simple())
+}
+
+/// Tool calls written into the message text instead of `tool_calls`:
+/// `<tool_call>{..}</tool_call>` blocks (Hermes/Qwen templates), fenced JSON,
+/// or a bare JSON object or array (Llama templates). Each call is
+/// `{"name", "arguments"|"parameters"}` or `{"function": {"name", "arguments"}}`.
+///
+/// Only counts when every call names one of `tool_names`, so a JSON answer
+/// isn't mistaken for a call. Returns `(name, arguments)` pairs and the text
+/// around them.
+pub fn content_tool_calls(text: &str, tool_names: &[&str]) -> Option<(Vec<(String, String)>, String)> {
+    let mut blocks: Vec<&str> = Vec::new();
+    let mut rest = String::new();
+    let mut remaining = text;
+    let (open, close) = if text.contains("<tool_call>") {
+        ("<tool_call>", "</tool_call>")
+    } else if text.contains("```") {
+        ("```", "```")
+    } else {
+        let trimmed = text.trim();
+        if !(trimmed.starts_with('{') || trimmed.starts_with('[')) {
+            return None;
+        }
+        blocks.push(trimmed);
+        ("", "")
+    };
+    if !open.is_empty() {
+        while let Some(start) = remaining.find(open) {
+            rest.push_str(&remaining[..start]);
+            let after = &remaining[start + open.len()..];
+            let end = after.find(close).unwrap_or(after.len());
+            let block = after[..end].trim();
+            // Fences may carry a language tag
+            blocks.push(block.strip_prefix("json").unwrap_or(block));
+            remaining = after.get(end + close.len()..).unwrap_or("");
+        }
+        rest.push_str(remaining);
+    }
+
+    let mut calls = Vec::new();
+    for block in blocks {
+        match serde_json::from_str::<Value>(block).ok()? {
+            Value::Array(items) => {
+                for item in &items {
+                    calls.push(content_call(item)?);
+                }
+            }
+            item => calls.push(content_call(&item)?),
+        }
+    }
+    if calls.is_empty() || !calls.iter().all(|(name, _)| tool_names.contains(&name.as_str())) {
+        return None;
+    }
+    Some((calls, rest.trim().to_string()))
+}
+
+/// `(name, arguments)` of one JSON tool call found in message content.
+fn content_call(v: &Value) -> Option<(String, String)> {
+    let call = v.get("function").filter(|f| f.is_object()).unwrap_or(v);
+    let name = call.get("name")?.as_str()?.to_string();
+    let arguments = match call.get("arguments").or_else(|| call.get("parameters")) {
+        Some(Value::String(a)) => a.clone(),
+        Some(v) => v.to_string(),
+        None => "{}".to_string(),
+    };
+    Some((name, arguments))
+}
+
 /// Flush buffered pre-tool content: emit as ThinkingDelta if tool calls followed, otherwise as TextDelta.
 fn flush_pre_tool_content(
     tx: &UnboundedSender<StreamEvent>,
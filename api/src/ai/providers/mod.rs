@@ -5,6 +5,7 @@
 // response, and emits parsed StreamEvents via a channel sender.
 //
 // The route handler owns the tool-call loop — providers are stateless.
+// `fallback::FallbackChain` wraps a list of them with retries.
 
 use async_trait::async_trait;
 use serde_json::Value;
@@ -14,6 +15,7 @@ use super::types::{AiError, Message, StreamEvent};
 pub mod local;
 pub mod anthropic;
 pub mod gemini;
+pub mod fallback;
 
 /// Canonical tool definition (matches MCP ToolDef schema).
 #[derive(Debug, Clone, serde::Serialize)]
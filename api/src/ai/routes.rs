@@ -5,6 +5,8 @@
 // GET    /api/ai/settings    — Get stored AI settings (Phase 3)
 // PUT    /api/ai/settings    — Save AI settings (Phase 3)
 // GET    /api/ai/tools       — List available MCP tools for transparency
+// *      /api/ai/approvals   — Write approval queue (see approvals.rs)
+// *      /api/ai/usage       — Token/cost accounting and budgets (see usage.rs)
 
 use axum::{
     extract::{Path, State},
@@ -27,7 +29,12 @@ use crate::app_state::T3AppState;
 use crate::mcp::tools::TOOLS;
 use crate::ai::prompt_builder;
 
-use super::providers::{get_provider, ToolDef};
+use super::approvals;
+use super::compaction;
+use super::usage::{self, MessageUsage, SessionUsage, TokenCounts};
+use super::providers::fallback::FallbackChain;
+use super::providers::ToolDef;
+use super::types::ProviderTarget;
 use super::session::SessionManager;
 use super::types::{AiError, ChatRequest, Message, StreamEvent};
 use super::mcp_client::{McpClient, McpClientManager, McpServerConfig};
@@ -79,15 +86,24 @@ fn get_all_tool_defs() -> Vec<ToolDef> {
 
 // ═══ POST /api/ai/chat ═══
 
+type ChatStream = UnboundedReceiverStream<Result<Event, Infallible>>;
+
+/// Refuse a chat before it starts: one SSE error event, then the stream closes.
+fn refuse_chat(message: &str) -> Sse<ChatStream> {
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel::<Result<Event, Infallible>>();
+    let _ = tx.send(Ok(Event::default().data(
+        serde_json::to_string(&StreamEvent::Error { message: message.to_string() })
+            .unwrap_or_else(|_| r#"{"event":"error","data":{"message":"Chat refused."}}"#.to_string()),
+    )));
+    Sse::new(UnboundedReceiverStream::new(rx))
+}
+
 pub async fn handle_ai_chat(
     State(state): State<T3AppState>,
     Json(req): Json<ChatRequest>,
 ) -> impl IntoResponse {
     let session_manager = SESSION_MANAGER.clone();
 
-    // Create a channel for streaming events
-    let (tx, rx) = tokio::sync::mpsc::unbounded_channel::<Result<Event, std::convert::Infallible>>();
-
     // Reject unconfigured requests before starting a chat — emit one SSE error
     // event and close the stream.
     let has_endpoint = req
@@ -98,22 +114,37 @@ pub async fn handle_ai_chat(
         .filter(|e| !e.is_empty())
         .is_some();
     if req.model.trim().is_empty() || !has_endpoint {
-        let _ = tx.send(Ok(Event::default().data(
-            serde_json::to_string(&StreamEvent::Error {
-                message: "AI assistant is not configured. Set your endpoint URL and model name in Settings.".to_string(),
-            })
-            .unwrap_or_else(|_| r#"{"event":"error","data":{"message":"AI assistant is not configured."}}"#.to_string()),
-        )));
-        drop(tx);
-        return Sse::new(UnboundedReceiverStream::new(rx));
+        return refuse_chat("AI assistant is not configured. Set your endpoint URL and model name in Settings.");
     }
 
     let session = session_manager.create_or_resume(&req).await;
 
-    // Spawn the chat processing task
+    // Monthly budgets: drop every provider in the chain whose budget is used
+    // up, and refuse once none is left
+    let user = crate::audit::current().actor;
+    let (targets, exhausted) =
+        usage::within_budget(&usage::usage_db(&state).await, session.targets(), user.as_deref()).await;
+    for (target, budget) in &exhausted {
+        info!("[AI] Skipping {}/{}: budget '{}' exhausted", target.provider, target.model, budget.budget.name);
+    }
+    if targets.is_empty() {
+        let refusal = exhausted.first().map(|(_, budget)| budget.refusal()).unwrap_or_default();
+        return refuse_chat(&if exhausted.len() > 1 {
+            format!("{} The budgets of every fallback provider are exhausted too.", refusal)
+        } else {
+            refusal
+        });
+    }
+
+    // Create a channel for streaming events
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel::<Result<Event, std::convert::Infallible>>();
+
+    // Spawn the chat processing task; its tool calls are audited as this chat session
     let state_clone = state.clone();
+    let audit_ctx = crate::audit::current().via(crate::audit::Origin::Ai, session.id.clone());
     tokio::spawn(async move {
-        if let Err(e) = process_chat(session_manager, session, &state_clone, &tx).await {
+        let chat = process_chat(session_manager, session, targets, &state_clone, &tx);
+        if let Err(e) = crate::audit::scope(audit_ctx, chat).await {
             let _ = tx.send(Ok(Event::default().data(
                 serde_json::to_string(&StreamEvent::Error {
                     message: e.to_string(),
@@ -139,10 +170,12 @@ pub async fn handle_ai_chat(
 async fn process_chat(
     session_manager: Arc<SessionManager>,
     mut session: super::session::Session,
+    targets: Vec<ProviderTarget>,
     state: &T3AppState,
     tx: &tokio::sync::mpsc::UnboundedSender<Result<Event, Infallible>>,
 ) -> Result<(), AiError> {
-    let provider = Arc::new(get_provider(&session.provider)?);
+    // The session's chain, less the providers over budget
+    let chain = Arc::new(FallbackChain::new(targets, session.max_retries)?);
     let tools = get_all_tool_defs();
     info!("[AI] process_chat START: provider={} model={} endpoint={} tools={} session_msgs={}",
         session.provider, session.model, session.endpoint, tools.len(), session.messages.len());
@@ -200,6 +233,7 @@ async fn process_chat(
             tool_calls: None,
             tool_call_id: None,
             ui: None,
+            usage: None,
         }];
         msgs.extend(session.messages.clone());
         msgs
@@ -208,13 +242,33 @@ async fn process_chat(
     // Outer loop: keep calling the LLM until it produces a final response
     let mut current_messages = messages;
     let max_iterations = 100;
+    let usage_db = usage::usage_db(state).await;
+    let user = crate::audit::current().actor;
+    // Tool definitions go out with every call and count against the window
+    let tool_tokens = serde_json::to_string(&tools).map(|t| t.len() / 4).unwrap_or(0);
+    let budget = compaction::prompt_budget(session.context_tokens, tool_tokens);
 
     for iteration in 0..max_iterations {
         info!("[AI] === Iteration {}/{} === messages={}", iteration + 1, max_iterations, current_messages.len());
 
-        // Trim old tool results to prevent context overflow on local models
-        if session.provider == "local" {
-            trim_old_tool_results(&mut current_messages);
+        // Fold older turns into the summary note once the model's context is exceeded
+        let (llm_messages, compacted) = compaction::fit(&current_messages, &mut session.compaction, budget, is_write_tool);
+        if compacted.turns_compacted > 0 || compacted.tool_results_digested > 0 {
+            info!("[AI] Context compacted: ~{}→{} tokens (turns={}, tool results digested={})",
+                compacted.tokens_before, compacted.tokens_after, compacted.turns_compacted, compacted.tool_results_digested);
+            let event = StreamEvent::ContextCompacted {
+                turns: compacted.turns_compacted,
+                tokens_before: compacted.tokens_before,
+                tokens_after: compacted.tokens_after,
+            };
+            let _ = tx.send(Ok(Event::default().data(serde_json::to_string(&event).unwrap())));
+        }
+        if compacted.turns_compacted > 0 {
+            session_manager.update_compaction(&session.id, session.compaction.clone()).await;
+            let title = session.messages.iter().find(|m| m.role == "user").map(|m| m.content.as_str()).unwrap_or("");
+            if let Err(e) = compaction::remember(&session.id, title, &compacted.facts).await {
+                info!("[AI] Failed to save compacted facts to memory: {}", e);
+            }
         }
 
         let (inner_tx, mut inner_rx) = tokio::sync::mpsc::unbounded_channel::<StreamEvent>();
@@ -224,17 +278,10 @@ async fn process_chat(
         let llm_start = std::time::Instant::now();
         let provider_tx = inner_tx.clone();
         let provider_handle = {
-            let provider = Arc::clone(&provider);
-            let endpoint = session.endpoint.clone();
-            let api_key = session.api_key.clone();
-            let model = session.model.clone();
-            let messages = current_messages.clone();
+            let chain = Arc::clone(&chain);
+            let messages = llm_messages;
             let tools = tools.clone();
-            tokio::spawn(async move {
-                provider
-                    .stream_chat(&endpoint, api_key.as_deref(), &model, &messages, &tools, &provider_tx)
-                    .await
-            })
+            tokio::spawn(async move { chain.stream_chat(&messages, &tools, &provider_tx).await })
         };
 
         // Drop our handle to inner_tx so the receiver closes when provider finishes
@@ -244,6 +291,7 @@ async fn process_chat(
         // (id, name, args, gemini thought_signature)
         let mut tool_call_records: Vec<(String, String, String, Option<String>)> = vec![];
         let mut assistant_text = String::new();
+        let mut tokens = TokenCounts::default();
 
         while let Some(event) = inner_rx.recv().await {
             // Track tool calls for the loop decision
@@ -254,6 +302,9 @@ async fn process_chat(
                 StreamEvent::TextDelta { content } => {
                     assistant_text.push_str(content);
                 }
+                StreamEvent::Usage { input_tokens, output_tokens } => {
+                    tokens += TokenCounts { input_tokens: *input_tokens, output_tokens: *output_tokens };
+                }
                 _ => {}
             }
 
@@ -263,12 +314,13 @@ async fn process_chat(
         }
 
         // Wait for provider to complete and check for errors
-        let finish_reason = match provider_handle.await {
-            Ok(Ok(reason)) => {
+        let (finish_reason, answered_by) = match provider_handle.await {
+            Ok(Ok(reply)) => {
                 let elapsed = llm_start.elapsed();
-                info!("[AI] Iteration {}: LLM done in {:?}, finish_reason={:?}, text_len={}, tool_calls={}",
-                    iteration + 1, elapsed, reason, assistant_text.len(), tool_call_records.len());
-                reason.unwrap_or_else(|| "stop".into())
+                info!("[AI] Iteration {}: LLM done in {:?} by {}/{}, finish_reason={:?}, text_len={}, tool_calls={}",
+                    iteration + 1, elapsed, reply.target.provider, reply.target.model, reply.finish_reason,
+                    assistant_text.len(), tool_call_records.len());
+                (reply.finish_reason.unwrap_or_else(|| "stop".into()), reply.target)
             }
             Ok(Err(e)) => {
                 info!("[AI] Iteration {}: LLM error: {}", iteration + 1, e);
@@ -280,9 +332,21 @@ async fn process_chat(
             }
         };
 
+        // Charged to whichever provider in the chain answered
+        let llm_usage = MessageUsage {
+            provider: answered_by.provider,
+            model: answered_by.model,
+            input_tokens: tokens.input_tokens,
+            output_tokens: tokens.output_tokens,
+            tool_calls: tool_call_records.len() as u64,
+            latency_ms: llm_start.elapsed().as_millis() as u64,
+            ..Default::default()
+        };
+
         // ── No tools called — this is the final turn ──
         if tool_call_records.is_empty() {
             info!("[AI] No tools called — final turn complete. text_len={} finish={}", assistant_text.len(), finish_reason);
+            let llm_usage = usage::record_call(&usage_db, &session.id, user.as_deref(), llm_usage).await;
 
             // Append assistant message to conversation
             if !assistant_text.is_empty() {
@@ -293,6 +357,7 @@ async fn process_chat(
                     tool_calls: None,
                     tool_call_id: None,
                     ui: None,
+                    usage: Some(llm_usage),
                 });
             }
 
@@ -320,6 +385,11 @@ async fn process_chat(
                 provider: session.provider.clone(),
                 model: session.model.clone(),
                 messages: current_messages.clone(),
+                usage: match usage::session_totals(&usage_db, &session.id).await {
+                    Ok(totals) => totals,
+                    Err(_) => SessionUsage::from_messages(&current_messages),
+                },
+                compaction: session.compaction.clone(),
             }) {
                 Ok(()) => info!("[AI] Session saved: {} ({})", session.id, title),
                 Err(e) => info!("[AI] Failed to save session {}: {}", session.id, e),
@@ -342,21 +412,27 @@ async fn process_chat(
             let tc_start = std::time::Instant::now();
             info!("[AI] Tool {}/{}: {} args={}", tool_results.len() + 1, tool_call_records.len(), tc_name, tc_args);
 
-            // Safety guard: block write tools without confirm
-            if is_write_tool(tc_name) && !has_confirm(tc_args) {
-                info!("[AI] BLOCKED write tool {}: missing confirm:true in args", tc_name);
-                let blocked = wrap_error(tc_name, "This write operation requires user confirmation. Please ask the user before proceeding.");
-                let event = StreamEvent::ToolResult {
-                    id: tc_id.clone(),
-                    result: blocked.clone(),
-                };
-                let json = serde_json::to_string(&event).unwrap();
-                let _ = tx.send(Ok(Event::default().data(json)));
-                tool_results.push((tc_id.clone(), blocked));
-                continue;
+            // Safety guard: write tools wait for a user to approve them
+            let mut exec_args = tc_args.clone();
+            if is_write_tool(tc_name) {
+                match await_approval(state, &session.id, tc_id, tc_name, tc_args, tx).await {
+                    Ok(approved_args) => exec_args = approved_args,
+                    Err(message) => {
+                        info!("[AI] BLOCKED write tool {}: {}", tc_name, message);
+                        let blocked = wrap_error(tc_name, &message);
+                        let event = StreamEvent::ToolResult {
+                            id: tc_id.clone(),
+                            result: blocked.clone(),
+                        };
+                        let json = serde_json::to_string(&event).unwrap();
+                        let _ = tx.send(Ok(Event::default().data(json)));
+                        tool_results.push((tc_id.clone(), blocked));
+                        continue;
+                    }
+                }
             }
 
-            match execute_mcp_tool(tc_name, tc_args, state).await {
+            match execute_mcp_tool(tc_name, &exec_args, state).await {
                 Ok(result) => {
                     let elapsed = tc_start.elapsed();
                     let result_json = serde_json::to_string(&result).unwrap_or_else(|_| "null".to_string());
@@ -387,6 +463,8 @@ async fn process_chat(
         }
 
         info!("[AI] All {} tools executed in {:?}", tool_results.len(), tool_start.elapsed());
+        let llm_usage = MessageUsage { tool_ms: tool_start.elapsed().as_millis() as u64, ..llm_usage };
+        let llm_usage = usage::record_call(&usage_db, &session.id, user.as_deref(), llm_usage).await;
 
         // Build assistant message with tool calls for the next iteration
         let openai_tool_calls: Vec<super::types::ToolCall> = tool_call_records
@@ -408,6 +486,7 @@ async fn process_chat(
             name: None,
             tool_calls: Some(openai_tool_calls),
             ui: None,
+            usage: Some(llm_usage),
             tool_call_id: None,
         });
 
@@ -425,6 +504,7 @@ async fn process_chat(
                 name: tool_name,
                 tool_calls: None,
                 ui: None,
+                usage: None,
                 tool_call_id: Some(tc_id.clone()),
             });
         }
@@ -449,25 +529,73 @@ async fn execute_mcp_tool(
 }
 
 /// Check if a tool is a write/dangerous tool that requires confirmation.
-const WRITE_TOOLS: &[&str] = &[
-    "t3000_point_write",
-    "t3000_point_write_batch",
-    "t3000_settings_write",
-    "t3000_device_control",
-    "t3000_device_delete",
-    "t3000_fdd_rule_create",
-    "t3000_fdd_rule_update",
-    "t3000_fdd_rule_toggle",
-    "t3000_fdd_rule_import",
-];
-
 fn is_write_tool(name: &str) -> bool {
-    WRITE_TOOLS.contains(&name)
+    approvals::requires_approval(name)
 }
 
-/// Verify that a write tool has confirm:true in its arguments.
-fn has_confirm(arguments: &str) -> bool {
-    arguments.contains("\"confirm\": true") || arguments.contains("\"confirm\":true")
+/// Queue a write tool call for approval and wait for the decision.
+/// Returns the arguments to run it with (`confirm: true` added, since a
+/// person has now confirmed it), or the message to give the model instead.
+async fn await_approval(
+    state: &T3AppState,
+    session_id: &str,
+    tool_call_id: &str,
+    name: &str,
+    arguments: &str,
+    tx: &tokio::sync::mpsc::UnboundedSender<Result<Event, Infallible>>,
+) -> Result<String, String> {
+    let mut args: Value = serde_json::from_str(arguments)
+        .map_err(|e| format!("Failed to parse tool arguments: {}", e))?;
+    // The model can't approve its own writes.
+    if let Some(obj) = args.as_object_mut() {
+        obj.remove("confirm");
+    }
+
+    let db = approvals::approvals_db(state).await;
+    let timeout = std::time::Duration::from_secs(approvals::APPROVAL_TIMEOUT_SECS);
+    let requested_by = crate::audit::current().actor;
+    let (action, rx) = approvals::create(&db, session_id, Some(tool_call_id), name, &args, requested_by.as_deref(), timeout)
+        .await
+        .map_err(|e| format!("Could not queue the write for approval: {}", e))?;
+    info!("[AI] Write tool {} waiting for approval (action {})", name, action.id);
+    let event = StreamEvent::ApprovalRequired {
+        action_id: action.id.clone(),
+        id: tool_call_id.to_string(),
+        name: name.to_string(),
+        arguments: args.clone(),
+        expires_at: action.expires_at,
+    };
+    let _ = tx.send(Ok(Event::default().data(serde_json::to_string(&event).unwrap())));
+
+    let decision = approvals::wait(&db, &action, rx, timeout).await;
+    let (status, decided_by) = match &decision {
+        approvals::Decision::Approved { by } => ("approved", by.clone()),
+        approvals::Decision::Rejected { by, .. } => ("rejected", by.clone()),
+        approvals::Decision::Expired => ("expired", None),
+    };
+    let event = StreamEvent::ApprovalResolved {
+        action_id: action.id.clone(),
+        id: tool_call_id.to_string(),
+        status: status.to_string(),
+        decided_by,
+    };
+    let _ = tx.send(Ok(Event::default().data(serde_json::to_string(&event).unwrap())));
+
+    match decision {
+        approvals::Decision::Approved { .. } => {
+            if let Some(obj) = args.as_object_mut() {
+                obj.insert("confirm".into(), Value::Bool(true));
+            }
+            Ok(args.to_string())
+        }
+        approvals::Decision::Rejected { reason, .. } => Err(match reason {
+            Some(reason) => format!("The user rejected this write operation: {}. Do not retry it unless asked.", reason),
+            None => "The user rejected this write operation. Do not retry it unless asked.".to_string(),
+        }),
+        approvals::Decision::Expired => Err(
+            "Nobody approved this write operation in time, so it was not run. Ask the user whether to try again.".to_string(),
+        ),
+    }
 }
 
 /// Wrap a successful tool result in a consistent schema.
@@ -517,47 +645,6 @@ fn truncate_tool_result(json: &str) -> String {
     )
 }
 
-/// Trim old tool results from the conversation to prevent context overflow.
-/// Local models typically have 8K-32K context. Tool results accumulate quickly.
-/// We keep the system prompt + last 2 user-assistant exchanges, trimming older tool results.
-fn trim_old_tool_results(messages: &mut Vec<Message>) {
-    // Rough token estimator: ~4 chars per token
-    let total_chars: usize = messages.iter().map(|m| m.content.len()).sum();
-    let estimated_tokens = total_chars / 4;
-
-    // Only trim if approaching 30K tokens (effectively disabled for 32K+ models)
-    if estimated_tokens < 30000 {
-        return;
-    }
-
-    // Find the last 2 user messages and keep everything from the second-to-last onward
-    let user_indices: Vec<usize> = messages.iter().enumerate()
-        .filter(|(_, m)| m.role == "user")
-        .map(|(i, _)| i)
-        .collect();
-
-    if user_indices.len() > 2 {
-        let keep_from = user_indices[user_indices.len() - 2];
-        // Keep system prompt (index 0) + messages from keep_from onward
-        let system_msg = messages[0].clone();
-        let kept: Vec<Message> = messages.drain(keep_from..).collect();
-        messages.clear();
-        messages.push(system_msg);
-        messages.extend(kept);
-    }
-
-    // Also truncate remaining tool result messages that are still too long
-    for msg in messages.iter_mut() {
-        if msg.role == "tool" && msg.content.len() > MAX_TOOL_RESULT_CHARS {
-            msg.content = truncate_tool_result(&msg.content);
-        }
-    }
-
-    let new_chars: usize = messages.iter().map(|m| m.content.len()).sum();
-    tracing::info!("[AI] Context trimmed: {}→{} chars (~{}→{} tokens)",
-        total_chars, new_chars, total_chars/4, new_chars/4);
-}
-
 // ═══ DELETE /api/ai/sessions/:id ═══
 
 pub async fn handle_delete_session(
@@ -927,6 +1014,10 @@ pub async fn handle_save_session(
         provider,
         model,
         messages: body.messages,
+        // Totals come from the chat loop; the UI's copy of the messages doesn't carry them
+        usage: existing.as_ref().map(|s| s.usage.clone()).unwrap_or_default(),
+        // Likewise the compaction summary, which is counted in user turns
+        compaction: existing.and_then(|s| s.compaction),
     };
     match super::session_store::save_session(&session) {
         Ok(()) => {
@@ -964,6 +1055,8 @@ pub async fn handle_list_tools() -> impl IntoResponse {
 
 pub fn ai_routes() -> Router<T3AppState> {
     Router::new()
+        .merge(approvals::approval_routes())
+        .merge(usage::usage_routes())
         .route("/api/ai/chat", post(handle_ai_chat))
         .route("/api/ai/sessions", get(handle_list_sessions))
         .route("/api/ai/sessions/{id}", get(handle_get_session).delete(handle_delete_session).put(handle_rename_session))
@@ -12,7 +12,9 @@ use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
 use uuid::Uuid;
 
-use super::types::{ChatRequest, Message};
+use super::compaction::{self, Compaction};
+use super::providers::fallback::{DEFAULT_MAX_RETRIES, MAX_RETRIES};
+use super::types::{ChatRequest, Message, ProviderTarget};
 
 const MAX_SESSIONS: usize = 100;
 const SESSION_IDLE_TIMEOUT: Duration = Duration::from_secs(3600); // 1 hour
@@ -27,10 +29,31 @@ pub struct Session {
     pub endpoint: String,
     pub api_key: Option<String>,
     pub messages: Vec<Message>,
+    /// Model context window in tokens.
+    pub context_tokens: usize,
+    /// Summary of the oldest turns, once the conversation outgrew the context.
+    pub compaction: Option<Compaction>,
+    /// Providers tried after this one fails, in order.
+    pub fallbacks: Vec<ProviderTarget>,
+    /// Retries per provider on transient errors.
+    pub max_retries: u32,
     pub created_at: Instant,
     pub last_active: Instant,
 }
 
+impl Session {
+    /// The session's provider first, then its fallbacks.
+    pub fn targets(&self) -> Vec<ProviderTarget> {
+        let primary = ProviderTarget {
+            provider: self.provider.clone(),
+            model: self.model.clone(),
+            endpoint: self.endpoint.clone(),
+            api_key: self.api_key.clone(),
+        };
+        std::iter::once(primary).chain(self.fallbacks.iter().cloned()).collect()
+    }
+}
+
 /// Thread-safe session store.
 #[derive(Clone)]
 pub struct SessionManager {
@@ -78,12 +101,19 @@ impl SessionManager {
                             .and_then(|s| s.api_key.clone())
                             .filter(|k| !k.is_empty()),
                         messages: f.messages,
+                        context_tokens: context_tokens(req),
+                        compaction: f.compaction,
+                        fallbacks: fallbacks(req),
+                        max_retries: max_retries(req),
                         created_at: Instant::now(),
                         last_active: Instant::now(),
                     })
             };
 
             if let Some(mut session) = existing {
+                session.context_tokens = context_tokens(req);
+                session.fallbacks = fallbacks(req);
+                session.max_retries = max_retries(req);
                 // The frontend sends the FULL message history, so append only
                 // the user turns we don't already have — this keeps the new
                 // user message while avoiding duplicating earlier turns.
@@ -136,6 +166,10 @@ impl SessionManager {
             endpoint,
             api_key,
             messages: req.messages.clone(),
+            context_tokens: context_tokens(req),
+            compaction: None,
+            fallbacks: fallbacks(req),
+            max_retries: max_retries(req),
             created_at: Instant::now(),
             last_active: Instant::now(),
         };
@@ -159,6 +193,14 @@ impl SessionManager {
         }
     }
 
+    /// Replace the compaction summary for a session.
+    pub async fn update_compaction(&self, id: &str, compaction: Option<Compaction>) {
+        let mut sessions = self.sessions.lock().await;
+        if let Some(session) = sessions.get_mut(id) {
+            session.compaction = compaction;
+        }
+    }
+
     /// Delete a session.
     pub async fn delete(&self, id: &str) {
         let mut sessions = self.sessions.lock().await;
@@ -183,3 +225,33 @@ impl SessionManager {
         });
     }
 }
+
+/// Context window from the request settings, or the provider default.
+fn context_tokens(req: &ChatRequest) -> usize {
+    req.settings
+        .as_ref()
+        .and_then(|s| s.context_tokens)
+        .filter(|&n| n > 0)
+        .unwrap_or_else(|| compaction::default_context_tokens(&req.provider, &req.model))
+}
+
+/// Fallback chain from the request settings, or the one saved in AI settings.
+fn fallbacks(req: &ChatRequest) -> Vec<ProviderTarget> {
+    if let Some(chain) = req.settings.as_ref().and_then(|s| s.fallbacks.clone()) {
+        return chain;
+    }
+    super::session_store::load_ai_settings()
+        .ok()
+        .and_then(|s| s.get("fallbacks").cloned())
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// Retries per provider from the request settings, capped at `MAX_RETRIES`.
+fn max_retries(req: &ChatRequest) -> u32 {
+    req.settings
+        .as_ref()
+        .and_then(|s| s.max_retries)
+        .unwrap_or(DEFAULT_MAX_RETRIES)
+        .min(MAX_RETRIES)
+}
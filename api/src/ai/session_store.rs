@@ -15,8 +15,10 @@ use std::fs;
 use std::io;
 use std::path::PathBuf;
 
+use super::compaction::Compaction;
 use super::mcp_client::McpServerConfig;
 use super::types::Message;
+use super::usage::SessionUsage;
 
 // ── Persisted types ──
 
@@ -28,6 +30,12 @@ pub struct SessionFile {
     pub provider: String,
     pub model: String,
     pub messages: Vec<Message>,
+    /// Token, tool-call, latency and cost totals for every turn so far.
+    #[serde(default)]
+    pub usage: SessionUsage,
+    /// Summary of the oldest turns once the conversation outgrew the model's context.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compaction: Option<Compaction>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
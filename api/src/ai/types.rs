@@ -39,6 +39,10 @@ pub struct Message {
     /// to the LLM (providers build their request bodies from explicit fields).
     #[serde(skip_serializing_if = "Option::is_none", default)]
     pub ui: Option<serde_json::Value>,
+    /// Tokens, tool calls, latency and cost of the LLM call that produced
+    /// this assistant message. Never sent to the LLM.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub usage: Option<super::usage::MessageUsage>,
 }
 
 /// A tool call requested by the LLM.
@@ -71,6 +75,30 @@ pub struct FunctionCall {
 pub struct AiSettings {
     pub endpoint: Option<String>,
     pub api_key: Option<String>,
+    /// Model context window in tokens; defaults per provider when absent.
+    /// Conversations are compacted to fit within it.
+    #[serde(default)]
+    pub context_tokens: Option<usize>,
+    /// Providers to try, in order, when this one fails (e.g. local Ollama →
+    /// Anthropic). Falls back to the `fallbacks` saved in AI settings.
+    #[serde(default)]
+    pub fallbacks: Option<Vec<ProviderTarget>>,
+    /// Retries per provider on transient errors before moving down the chain
+    /// (at most `fallback::MAX_RETRIES`).
+    #[serde(default)]
+    pub max_retries: Option<u32>,
+}
+
+/// One provider in a fallback chain.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProviderTarget {
+    /// Provider key: "local", "anthropic", or "gemini".
+    pub provider: String,
+    pub model: String,
+    #[serde(default)]
+    pub endpoint: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
 }
 
 // ═══ SSE Stream Events ═══
@@ -103,6 +131,38 @@ pub enum StreamEvent {
     /// The result of a locally-executed tool.
     #[serde(rename = "tool_result")]
     ToolResult { id: String, result: String },
+    /// A write tool call is waiting for a user to approve or reject it.
+    #[serde(rename = "approval_required")]
+    ApprovalRequired {
+        /// Pending action id for /api/ai/approvals/:id/{approve,reject}.
+        action_id: String,
+        /// The tool call it belongs to.
+        id: String,
+        name: String,
+        #[serde(rename = "args")]
+        arguments: serde_json::Value,
+        /// Unix epoch ms after which the call is dropped.
+        expires_at: i64,
+    },
+    /// A pending write was approved, rejected or timed out.
+    #[serde(rename = "approval_resolved")]
+    ApprovalResolved {
+        action_id: String,
+        id: String,
+        /// approved | rejected | expired
+        status: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        decided_by: Option<String>,
+    },
+    /// Prompt/completion tokens the provider reported for one LLM call.
+    #[serde(rename = "usage")]
+    Usage { input_tokens: u64, output_tokens: u64 },
+    /// Older turns were folded into the summary note to fit the model's context.
+    #[serde(rename = "context_compacted")]
+    ContextCompacted { turns: usize, tokens_before: usize, tokens_after: usize },
+    /// A provider failed and the next one in the fallback chain takes over.
+    #[serde(rename = "provider_fallback")]
+    ProviderFallback { from: String, to: String, reason: String },
     /// The turn is complete.
     #[serde(rename = "done")]
     Done {
@@ -138,6 +198,10 @@ pub enum AiError {
     #[error("Provider error: {0}")]
     Provider(String),
 
+    /// Connection failures, timeouts, 429 and 5xx responses — worth retrying.
+    #[error("Provider unavailable: {0}")]
+    Unavailable(String),
+
     #[error("Session not found: {0}")]
     SessionNotFound(String),
 
@@ -156,3 +220,26 @@ pub enum AiError {
     #[error("No provider configured")]
     NoProvider,
 }
+
+impl AiError {
+    /// Whether a retry (or the next provider) might succeed.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            AiError::Unavailable(_) | AiError::Stream(_) => true,
+            AiError::Http(e) => e.is_connect() || e.is_timeout(),
+            _ => false,
+        }
+    }
+
+    /// Classify a non-success HTTP status from a provider.
+    pub fn from_status(status: reqwest::StatusCode, message: String) -> Self {
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS
+            || status == reqwest::StatusCode::REQUEST_TIMEOUT
+            || status.is_server_error()
+        {
+            AiError::Unavailable(message)
+        } else {
+            AiError::Provider(message)
+        }
+    }
+}
@@ -0,0 +1,670 @@
+// AI Chat — Token usage, cost and latency accounting.
+//
+// Every LLM call in the chat loop reports the prompt/completion tokens the
+// provider streamed back (`StreamEvent::Usage`), how many tools it asked for
+// and how long it and those tools took. The numbers are kept on the assistant
+// message in the session file and appended to AI_USAGE, which the aggregate
+// endpoints and the monthly budgets read. Costs come from AI_MODEL_PRICES
+// (USD per million tokens); calls to unpriced models count tokens only.
+//
+// GET    /api/ai/usage?groupBy=day|model|user&from=&to=  — aggregates (dates inclusive, UTC)
+// GET    /api/ai/usage/sessions/:id                      — one chat session
+// GET    /api/ai/usage/prices     PUT (replaces the list)
+// GET    /api/ai/usage/budgets    POST, PUT/DELETE /api/ai/usage/budgets/:id
+//
+// A budget exhausted for the current calendar month (UTC) takes its
+// providers out of the chat's fallback chain; POST /api/ai/chat refuses to
+// start once no provider in the chain is left.
+
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+    routing::{get, put},
+    Router,
+};
+use chrono::{Datelike, TimeZone, Utc};
+use sea_orm::{ConnectionTrait, DatabaseBackend, DatabaseConnection, QueryResult, Statement, TransactionTrait, Value as DbValue};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::warn;
+
+use crate::app_state::T3AppState;
+use super::types::ProviderTarget;
+use crate::error::{Error, Result};
+
+use super::types::Message;
+
+const DDL: &str = "
+CREATE TABLE IF NOT EXISTS AI_USAGE (
+    id            INTEGER PRIMARY KEY AUTOINCREMENT,
+    ts            INTEGER NOT NULL,
+    session_id    TEXT NOT NULL,
+    user_name     TEXT,
+    provider      TEXT NOT NULL,
+    model         TEXT NOT NULL,
+    input_tokens  INTEGER NOT NULL DEFAULT 0,
+    output_tokens INTEGER NOT NULL DEFAULT 0,
+    tool_calls    INTEGER NOT NULL DEFAULT 0,
+    latency_ms    INTEGER NOT NULL DEFAULT 0,
+    tool_ms       INTEGER NOT NULL DEFAULT 0,
+    cost_usd      REAL
+);
+CREATE INDEX IF NOT EXISTS idx_ai_usage_ts      ON AI_USAGE (ts);
+CREATE INDEX IF NOT EXISTS idx_ai_usage_session ON AI_USAGE (session_id);
+CREATE TABLE IF NOT EXISTS AI_MODEL_PRICES (
+    model           TEXT PRIMARY KEY,
+    input_per_mtok  REAL NOT NULL DEFAULT 0,
+    output_per_mtok REAL NOT NULL DEFAULT 0
+);
+CREATE TABLE IF NOT EXISTS AI_USAGE_BUDGETS (
+    id               INTEGER PRIMARY KEY AUTOINCREMENT,
+    name             TEXT NOT NULL,
+    provider         TEXT,
+    model            TEXT,
+    user_name        TEXT,
+    monthly_tokens   INTEGER,
+    monthly_cost_usd REAL,
+    enabled          INTEGER NOT NULL DEFAULT 1
+);
+";
+
+/// Day of a usage row (UTC), as YYYY-MM-DD.
+const DAY: &str = "strftime('%Y-%m-%d', ts / 1000, 'unixepoch')";
+
+/// Prompt and completion tokens reported by a provider for one call.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenCounts {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+impl std::ops::AddAssign for TokenCounts {
+    fn add_assign(&mut self, other: Self) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+    }
+}
+
+/// One LLM call, as kept on the assistant message it produced.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MessageUsage {
+    pub provider: String,
+    pub model: String,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// Tool calls the model asked for in this response.
+    pub tool_calls: u64,
+    /// Request sent to end of stream.
+    pub latency_ms: u64,
+    /// Running (and waiting on approval for) the tool calls.
+    pub tool_ms: u64,
+    /// `None` when the model has no price.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub cost_usd: Option<f64>,
+}
+
+/// Totals for a chat session.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionUsage {
+    pub llm_calls: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub tool_calls: u64,
+    pub latency_ms: u64,
+    pub tool_ms: u64,
+    pub cost_usd: f64,
+}
+
+impl SessionUsage {
+    /// Sum of the usage carried by `messages`.
+    pub fn from_messages(messages: &[Message]) -> Self {
+        let mut total = Self::default();
+        for usage in messages.iter().filter_map(|m| m.usage.as_ref()) {
+            total.llm_calls += 1;
+            total.input_tokens += usage.input_tokens;
+            total.output_tokens += usage.output_tokens;
+            total.tool_calls += usage.tool_calls;
+            total.latency_ms += usage.latency_ms;
+            total.tool_ms += usage.tool_ms;
+            total.cost_usd += usage.cost_usd.unwrap_or(0.0);
+        }
+        total
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelPrice {
+    pub model: String,
+    /// USD per million prompt tokens.
+    pub input_per_mtok: f64,
+    /// USD per million completion tokens.
+    pub output_per_mtok: f64,
+}
+
+/// A monthly limit on the calls matching its filters (`None` = any).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Budget {
+    pub id: i64,
+    pub name: String,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub user: Option<String>,
+    /// Prompt + completion tokens per month.
+    pub monthly_tokens: Option<i64>,
+    pub monthly_cost_usd: Option<f64>,
+    pub enabled: bool,
+}
+
+/// New budget, or the full replacement on update.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetSpec {
+    pub name: String,
+    #[serde(default)]
+    pub provider: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub user: Option<String>,
+    #[serde(default)]
+    pub monthly_tokens: Option<i64>,
+    #[serde(default)]
+    pub monthly_cost_usd: Option<f64>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// A budget with what the current month has used of it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetStatus {
+    #[serde(flatten)]
+    pub budget: Budget,
+    pub used_tokens: i64,
+    pub used_cost_usd: f64,
+    pub exhausted: bool,
+}
+
+/// One row of an aggregate; only the grouping's key fields are set.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageRow {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub day: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    pub llm_calls: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub tool_calls: i64,
+    pub avg_latency_ms: f64,
+    pub cost_usd: f64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupBy {
+    #[default]
+    Day,
+    /// Provider and model.
+    Model,
+    User,
+}
+
+fn stmt(sql: &str, values: Vec<DbValue>) -> Statement {
+    Statement::from_sql_and_values(DatabaseBackend::Sqlite, sql, values)
+}
+
+fn db_err(e: sea_orm::DbErr) -> Error {
+    Error::DbError(e.to_string())
+}
+
+pub(crate) async fn usage_db(state: &T3AppState) -> DatabaseConnection {
+    state.local_config_conn.as_ref().unwrap_or(&state.conn).lock().await.clone()
+}
+
+pub async fn ensure_schema(db: &DatabaseConnection) -> Result<()> {
+    db.execute_unprepared(DDL).await.map_err(db_err)?;
+    Ok(())
+}
+
+/// Unix epoch ms at the start of the current month (UTC).
+fn month_start_ms() -> i64 {
+    let now = Utc::now();
+    Utc.with_ymd_and_hms(now.year(), now.month(), 1, 0, 0, 0)
+        .single()
+        .map(|t| t.timestamp_millis())
+        .unwrap_or(0)
+}
+
+// ═══ Prices ═══
+
+pub async fn list_prices(db: &DatabaseConnection) -> Result<Vec<ModelPrice>> {
+    ensure_schema(db).await?;
+    let rows = db
+        .query_all(stmt("SELECT * FROM AI_MODEL_PRICES ORDER BY model", vec![]))
+        .await
+        .map_err(db_err)?;
+    Ok(rows
+        .iter()
+        .map(|row| ModelPrice {
+            model: row.try_get("", "model").unwrap_or_default(),
+            input_per_mtok: row.try_get("", "input_per_mtok").unwrap_or(0.0),
+            output_per_mtok: row.try_get("", "output_per_mtok").unwrap_or(0.0),
+        })
+        .collect())
+}
+
+/// Replace the price list.
+pub async fn set_prices(db: &DatabaseConnection, prices: &[ModelPrice]) -> Result<()> {
+    ensure_schema(db).await?;
+    if let Some(bad) = prices.iter().find(|p| p.model.trim().is_empty() || p.input_per_mtok < 0.0 || p.output_per_mtok < 0.0) {
+        return Err(Error::ValidationError(format!("Invalid price for model '{}'", bad.model)));
+    }
+    let txn = db.begin().await.map_err(db_err)?;
+    txn.execute_unprepared("DELETE FROM AI_MODEL_PRICES").await.map_err(db_err)?;
+    for price in prices {
+        txn.execute(stmt(
+            "INSERT OR REPLACE INTO AI_MODEL_PRICES (model, input_per_mtok, output_per_mtok) VALUES (?, ?, ?)",
+            vec![price.model.trim().into(), price.input_per_mtok.into(), price.output_per_mtok.into()],
+        ))
+        .await
+        .map_err(db_err)?;
+    }
+    txn.commit().await.map_err(db_err)
+}
+
+/// Cost of `counts` on `model`, if the model has a price.
+pub async fn cost(db: &DatabaseConnection, model: &str, counts: TokenCounts) -> Result<Option<f64>> {
+    ensure_schema(db).await?;
+    let row = db
+        .query_one(stmt("SELECT * FROM AI_MODEL_PRICES WHERE model = ?", vec![model.into()]))
+        .await
+        .map_err(db_err)?;
+    Ok(row.map(|row| {
+        let input: f64 = row.try_get("", "input_per_mtok").unwrap_or(0.0);
+        let output: f64 = row.try_get("", "output_per_mtok").unwrap_or(0.0);
+        (counts.input_tokens as f64 * input + counts.output_tokens as f64 * output) / 1_000_000.0
+    }))
+}
+
+// ═══ Ledger ═══
+
+/// Price one LLM call and append it to the ledger. Accounting never fails
+/// the chat: errors are logged and the usage is returned unpriced.
+pub async fn record_call(db: &DatabaseConnection, session_id: &str, user: Option<&str>, mut usage: MessageUsage) -> MessageUsage {
+    let counts = TokenCounts { input_tokens: usage.input_tokens, output_tokens: usage.output_tokens };
+    let result = async {
+        usage.cost_usd = cost(db, &usage.model, counts).await?;
+        db.execute(stmt(
+            "INSERT INTO AI_USAGE (ts, session_id, user_name, provider, model, input_tokens, output_tokens, \
+             tool_calls, latency_ms, tool_ms, cost_usd) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            vec![
+                Utc::now().timestamp_millis().into(),
+                session_id.into(),
+                user.map(str::to_owned).into(),
+                usage.provider.clone().into(),
+                usage.model.clone().into(),
+                (usage.input_tokens as i64).into(),
+                (usage.output_tokens as i64).into(),
+                (usage.tool_calls as i64).into(),
+                (usage.latency_ms as i64).into(),
+                (usage.tool_ms as i64).into(),
+                usage.cost_usd.into(),
+            ],
+        ))
+        .await
+        .map_err(db_err)?;
+        Ok::<_, Error>(())
+    }
+    .await;
+    if let Err(e) = result {
+        warn!("[AI] Failed to record usage for session {}: {}", session_id, e);
+    }
+    usage
+}
+
+/// Totals for one chat session from the ledger (all turns, all saves).
+pub async fn session_totals(db: &DatabaseConnection, session_id: &str) -> Result<SessionUsage> {
+    ensure_schema(db).await?;
+    let row = db
+        .query_one(stmt(
+            "SELECT COUNT(*) AS llm_calls, COALESCE(SUM(input_tokens), 0) AS input_tokens, \
+             COALESCE(SUM(output_tokens), 0) AS output_tokens, COALESCE(SUM(tool_calls), 0) AS tool_calls, \
+             COALESCE(SUM(latency_ms), 0) AS latency_ms, COALESCE(SUM(tool_ms), 0) AS tool_ms, \
+             COALESCE(SUM(cost_usd), 0.0) AS cost_usd FROM AI_USAGE WHERE session_id = ?",
+            vec![session_id.into()],
+        ))
+        .await
+        .map_err(db_err)?;
+    let Some(row) = row else {
+        return Ok(SessionUsage::default());
+    };
+    let count = |col: &str| row.try_get::<i64>("", col).unwrap_or(0).max(0) as u64;
+    Ok(SessionUsage {
+        llm_calls: count("llm_calls"),
+        input_tokens: count("input_tokens"),
+        output_tokens: count("output_tokens"),
+        tool_calls: count("tool_calls"),
+        latency_ms: count("latency_ms"),
+        tool_ms: count("tool_ms"),
+        cost_usd: row.try_get("", "cost_usd").unwrap_or(0.0),
+    })
+}
+
+/// Usage grouped by day, provider/model or user, optionally between two
+/// YYYY-MM-DD dates (inclusive, UTC).
+pub async fn summarize(db: &DatabaseConnection, group_by: GroupBy, from: Option<&str>, to: Option<&str>) -> Result<Vec<UsageRow>> {
+    ensure_schema(db).await?;
+    for date in [from, to].into_iter().flatten() {
+        if chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").is_err() {
+            return Err(Error::BadRequest(format!("Invalid date '{}', expected YYYY-MM-DD", date)));
+        }
+    }
+    let keys = match group_by {
+        GroupBy::Day => format!("{} AS day", DAY),
+        GroupBy::Model => "provider, model".to_string(),
+        GroupBy::User => "user_name".to_string(),
+    };
+    let group = match group_by {
+        GroupBy::Day => "day",
+        GroupBy::Model => "provider, model",
+        GroupBy::User => "user_name",
+    };
+    let mut sql = format!(
+        "SELECT {}, COUNT(*) AS llm_calls, SUM(input_tokens) AS input_tokens, SUM(output_tokens) AS output_tokens, \
+         SUM(tool_calls) AS tool_calls, AVG(latency_ms) AS avg_latency_ms, COALESCE(SUM(cost_usd), 0.0) AS cost_usd \
+         FROM AI_USAGE WHERE 1 = 1",
+        keys
+    );
+    let mut values: Vec<DbValue> = Vec::new();
+    if let Some(from) = from {
+        sql.push_str(&format!(" AND {} >= ?", DAY));
+        values.push(from.into());
+    }
+    if let Some(to) = to {
+        sql.push_str(&format!(" AND {} <= ?", DAY));
+        values.push(to.into());
+    }
+    sql.push_str(&format!(" GROUP BY {} ORDER BY {}", group, group));
+    let rows = db.query_all(stmt(&sql, values)).await.map_err(db_err)?;
+    Ok(rows.iter().map(|row| usage_row(row, group_by)).collect())
+}
+
+fn usage_row(row: &QueryResult, group_by: GroupBy) -> UsageRow {
+    let key = |col: &str| row.try_get::<Option<String>>("", col).ok().flatten();
+    let count = |col: &str| row.try_get::<i64>("", col).unwrap_or(0);
+    UsageRow {
+        day: (group_by == GroupBy::Day).then(|| key("day")).flatten(),
+        provider: (group_by == GroupBy::Model).then(|| key("provider")).flatten(),
+        model: (group_by == GroupBy::Model).then(|| key("model")).flatten(),
+        user: (group_by == GroupBy::User).then(|| key("user_name")).flatten(),
+        llm_calls: count("llm_calls"),
+        input_tokens: count("input_tokens"),
+        output_tokens: count("output_tokens"),
+        tool_calls: count("tool_calls"),
+        avg_latency_ms: row.try_get("", "avg_latency_ms").unwrap_or(0.0),
+        cost_usd: row.try_get("", "cost_usd").unwrap_or(0.0),
+    }
+}
+
+// ═══ Budgets ═══
+
+fn budget_from_row(row: &QueryResult) -> Budget {
+    Budget {
+        id: row.try_get("", "id").unwrap_or(0),
+        name: row.try_get("", "name").unwrap_or_default(),
+        provider: row.try_get("", "provider").ok().flatten(),
+        model: row.try_get("", "model").ok().flatten(),
+        user: row.try_get("", "user_name").ok().flatten(),
+        monthly_tokens: row.try_get("", "monthly_tokens").ok().flatten(),
+        monthly_cost_usd: row.try_get("", "monthly_cost_usd").ok().flatten(),
+        enabled: row.try_get::<i32>("", "enabled").map(|v| v != 0).unwrap_or(true),
+    }
+}
+
+fn validate(spec: &BudgetSpec) -> Result<()> {
+    if spec.name.trim().is_empty() {
+        return Err(Error::ValidationError("Budget name is required".into()));
+    }
+    if spec.monthly_tokens.is_none() && spec.monthly_cost_usd.is_none() {
+        return Err(Error::ValidationError("Set monthlyTokens and/or monthlyCostUsd".into()));
+    }
+    if spec.monthly_tokens.is_some_and(|t| t < 0) || spec.monthly_cost_usd.is_some_and(|c| c < 0.0) {
+        return Err(Error::ValidationError("Budget limits can't be negative".into()));
+    }
+    Ok(())
+}
+
+/// Blank filters mean "any".
+fn filter(value: &Option<String>) -> Option<String> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty()).map(str::to_owned)
+}
+
+fn spec_values(spec: &BudgetSpec) -> Vec<DbValue> {
+    vec![
+        spec.name.trim().into(),
+        filter(&spec.provider).into(),
+        filter(&spec.model).into(),
+        filter(&spec.user).into(),
+        spec.monthly_tokens.into(),
+        spec.monthly_cost_usd.into(),
+        (spec.enabled as i32).into(),
+    ]
+}
+
+pub async fn get_budget(db: &DatabaseConnection, id: i64) -> Result<Option<Budget>> {
+    ensure_schema(db).await?;
+    let row = db
+        .query_one(stmt("SELECT * FROM AI_USAGE_BUDGETS WHERE id = ?", vec![id.into()]))
+        .await
+        .map_err(db_err)?;
+    Ok(row.as_ref().map(budget_from_row))
+}
+
+pub async fn create_budget(db: &DatabaseConnection, spec: &BudgetSpec) -> Result<Budget> {
+    validate(spec)?;
+    ensure_schema(db).await?;
+    let result = db
+        .execute(stmt(
+            "INSERT INTO AI_USAGE_BUDGETS (name, provider, model, user_name, monthly_tokens, monthly_cost_usd, enabled) \
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+            spec_values(spec),
+        ))
+        .await
+        .map_err(db_err)?;
+    get_budget(db, result.last_insert_id() as i64)
+        .await?
+        .ok_or_else(|| Error::ServerError("Created budget not found".into()))
+}
+
+pub async fn update_budget(db: &DatabaseConnection, id: i64, spec: &BudgetSpec) -> Result<Option<Budget>> {
+    validate(spec)?;
+    ensure_schema(db).await?;
+    let mut values = spec_values(spec);
+    values.push(id.into());
+    db.execute(stmt(
+        "UPDATE AI_USAGE_BUDGETS SET name = ?, provider = ?, model = ?, user_name = ?, monthly_tokens = ?, \
+         monthly_cost_usd = ?, enabled = ? WHERE id = ?",
+        values,
+    ))
+    .await
+    .map_err(db_err)?;
+    get_budget(db, id).await
+}
+
+pub async fn delete_budget(db: &DatabaseConnection, id: i64) -> Result<Option<Budget>> {
+    let Some(budget) = get_budget(db, id).await? else {
+        return Ok(None);
+    };
+    db.execute(stmt("DELETE FROM AI_USAGE_BUDGETS WHERE id = ?", vec![id.into()]))
+        .await
+        .map_err(db_err)?;
+    Ok(Some(budget))
+}
+
+/// What the current month has used of `budget`.
+pub async fn budget_status(db: &DatabaseConnection, budget: Budget) -> Result<BudgetStatus> {
+    let mut sql = String::from(
+        "SELECT COALESCE(SUM(input_tokens + output_tokens), 0) AS tokens, COALESCE(SUM(cost_usd), 0.0) AS cost \
+         FROM AI_USAGE WHERE ts >= ?",
+    );
+    let mut values: Vec<DbValue> = vec![month_start_ms().into()];
+    for (col, value) in [("provider", &budget.provider), ("model", &budget.model), ("user_name", &budget.user)] {
+        if let Some(value) = value {
+            sql.push_str(&format!(" AND {} = ?", col));
+            values.push(value.clone().into());
+        }
+    }
+    let row = db.query_one(stmt(&sql, values)).await.map_err(db_err)?;
+    let used_tokens: i64 = row.as_ref().and_then(|r| r.try_get("", "tokens").ok()).unwrap_or(0);
+    let used_cost_usd: f64 = row.as_ref().and_then(|r| r.try_get("", "cost").ok()).unwrap_or(0.0);
+    let exhausted = budget.enabled
+        && (budget.monthly_tokens.is_some_and(|limit| used_tokens >= limit)
+            || budget.monthly_cost_usd.is_some_and(|limit| used_cost_usd >= limit));
+    Ok(BudgetStatus { budget, used_tokens, used_cost_usd, exhausted })
+}
+
+pub async fn list_budgets(db: &DatabaseConnection) -> Result<Vec<BudgetStatus>> {
+    ensure_schema(db).await?;
+    let rows = db
+        .query_all(stmt("SELECT * FROM AI_USAGE_BUDGETS ORDER BY id", vec![]))
+        .await
+        .map_err(db_err)?;
+    let mut statuses = Vec::with_capacity(rows.len());
+    for row in &rows {
+        statuses.push(budget_status(db, budget_from_row(row)).await?);
+    }
+    Ok(statuses)
+}
+
+/// The first enabled budget covering a chat with `provider` / `model` for
+/// `user` that this month has used up.
+pub async fn exhausted_budget(
+    db: &DatabaseConnection,
+    provider: &str,
+    model: &str,
+    user: Option<&str>,
+) -> Result<Option<BudgetStatus>> {
+    let applies = |budget: &Budget| {
+        budget.enabled
+            && budget.provider.as_deref().is_none_or(|p| p == provider)
+            && budget.model.as_deref().is_none_or(|m| m == model)
+            && budget.user.as_deref().is_none_or(|u| Some(u) == user)
+    };
+    Ok(list_budgets(db).await?.into_iter().find(|s| s.exhausted && applies(&s.budget)))
+}
+
+/// Split a fallback chain into the targets `user` may still call this month
+/// and the exhausted budget that rules out each of the others. A target
+/// whose budget check fails is kept — budgets never block chat on a DB error.
+pub async fn within_budget(
+    db: &DatabaseConnection,
+    targets: Vec<ProviderTarget>,
+    user: Option<&str>,
+) -> (Vec<ProviderTarget>, Vec<(ProviderTarget, BudgetStatus)>) {
+    let mut usable = Vec::with_capacity(targets.len());
+    let mut exhausted = Vec::new();
+    for target in targets {
+        match exhausted_budget(db, &target.provider, &target.model, user).await {
+            Ok(Some(budget)) => exhausted.push((target, budget)),
+            Ok(None) => usable.push(target),
+            Err(e) => {
+                warn!("[AI] Budget check failed for {}/{}, allowing it: {}", target.provider, target.model, e);
+                usable.push(target);
+            }
+        }
+    }
+    (usable, exhausted)
+}
+
+impl BudgetStatus {
+    /// Shown to the chat user when the budget stops a request.
+    pub fn refusal(&self) -> String {
+        let b = &self.budget;
+        let limit = match (b.monthly_tokens, b.monthly_cost_usd) {
+            (Some(tokens), _) if self.used_tokens >= tokens => format!("{} of {} tokens", self.used_tokens, tokens),
+            (_, Some(cost)) => format!("${:.2} of ${:.2}", self.used_cost_usd, cost),
+            _ => format!("{} tokens", self.used_tokens),
+        };
+        format!(
+            "The monthly AI budget \"{}\" is exhausted ({} used this month). Ask an administrator to raise it, or try again next month.",
+            b.name, limit
+        )
+    }
+}
+
+// ═══ Routes ═══
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SummaryQuery {
+    #[serde(default)]
+    pub group_by: GroupBy,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+async fn handle_summary(State(state): State<T3AppState>, Query(q): Query<SummaryQuery>) -> Result<Json<Value>> {
+    let rows = summarize(&usage_db(&state).await, q.group_by, q.from.as_deref(), q.to.as_deref()).await?;
+    Ok(Json(json!({ "rows": rows })))
+}
+
+async fn handle_session(State(state): State<T3AppState>, Path(id): Path<String>) -> Result<Json<Value>> {
+    let usage = session_totals(&usage_db(&state).await, &id).await?;
+    Ok(Json(json!({ "sessionId": id, "usage": usage })))
+}
+
+async fn handle_list_prices(State(state): State<T3AppState>) -> Result<Json<Value>> {
+    Ok(Json(json!({ "prices": list_prices(&usage_db(&state).await).await? })))
+}
+
+async fn handle_set_prices(State(state): State<T3AppState>, Json(prices): Json<Vec<ModelPrice>>) -> Result<Json<Value>> {
+    let db = usage_db(&state).await;
+    set_prices(&db, &prices).await?;
+    Ok(Json(json!({ "prices": list_prices(&db).await? })))
+}
+
+async fn handle_list_budgets(State(state): State<T3AppState>) -> Result<Json<Value>> {
+    Ok(Json(json!({ "budgets": list_budgets(&usage_db(&state).await).await? })))
+}
+
+async fn handle_create_budget(State(state): State<T3AppState>, Json(spec): Json<BudgetSpec>) -> Result<Json<Value>> {
+    let db = usage_db(&state).await;
+    let budget = create_budget(&db, &spec).await?;
+    Ok(Json(json!(budget_status(&db, budget).await?)))
+}
+
+async fn handle_update_budget(
+    State(state): State<T3AppState>,
+    Path(id): Path<i64>,
+    Json(spec): Json<BudgetSpec>,
+) -> Result<Json<Value>> {
+    let db = usage_db(&state).await;
+    let budget = update_budget(&db, id, &spec).await?.ok_or(Error::NotFound)?;
+    Ok(Json(json!(budget_status(&db, budget).await?)))
+}
+
+async fn handle_delete_budget(State(state): State<T3AppState>, Path(id): Path<i64>) -> Result<Json<Value>> {
+    let budget = delete_budget(&usage_db(&state).await, id).await?.ok_or(Error::NotFound)?;
+    Ok(Json(json!({ "deleted": budget })))
+}
+
+pub fn usage_routes() -> Router<T3AppState> {
+    Router::new()
+        .route("/api/ai/usage", get(handle_summary))
+        .route("/api/ai/usage/sessions/:id", get(handle_session))
+        .route("/api/ai/usage/prices", get(handle_list_prices).put(handle_set_prices))
+        .route("/api/ai/usage/budgets", get(handle_list_budgets).post(handle_create_budget))
+        .route("/api/ai/usage/budgets/:id", put(handle_update_budget).delete(handle_delete_budget))
+}
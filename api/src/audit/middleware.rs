@@ -0,0 +1,241 @@
+//! HTTP side of the audit trail.
+//!
+//! Sets the [`AuditContext`] for every request and records writes that need
+//! at least the engineer role (see [`crate::auth::policy`]) — point writes,
+//! device settings and programming, configuration, accounts and the database
+//! backend. Operator-level writes (refresh/sync from devices) only update the
+//! local copy and aren't recorded. MCP and AI chat requests are recorded per
+//! tool call instead, see [`super::tools`].
+
+use axum::{
+    body::{to_bytes, Body, Bytes, HttpBody},
+    extract::State,
+    http::{header, Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde_json::{json, Map, Value};
+
+use super::{point_name, point_snapshot, AuditContext, NewEntry, Origin};
+use crate::app_state::T3AppState;
+use crate::auth::{required_access, Access, CurrentUser, Role};
+
+/// Error responses are kept in `detail` up to this many characters.
+const MAX_DETAIL_CHARS: usize = 500;
+
+/// Largest body buffered for the record — axum's default `Json` limit, so
+/// no JSON handler behind this layer accepts more anyway.
+const MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+fn is_audited(method: &Method, path: &str) -> bool {
+    if matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) {
+        return false;
+    }
+    if path.starts_with("/api/mcp") || path.starts_with("/api/ai/chat") {
+        return false;
+    }
+    matches!(required_access(method, path), Access::Role(role) if role >= Role::Engineer)
+}
+
+/// A point route: `/api/t3_device/{inputs|outputs|variables}/:serial/:index[/...]`
+/// or `.../:serial/batch_save`.
+struct PointTarget {
+    point_type: &'static str,
+    serial: i32,
+    /// `None` for batch saves.
+    index: Option<i32>,
+}
+
+fn point_target(path: &str) -> Option<PointTarget> {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let [_, "t3_device", kind, serial, rest, ..] = segments.as_slice() else {
+        return None;
+    };
+    let point_type = match *kind {
+        "inputs" => "INPUT",
+        "outputs" => "OUTPUT",
+        "variables" => "VARIABLE",
+        _ => return None,
+    };
+    let serial = serial.parse().ok()?;
+    let index = match *rest {
+        "batch_save" => None,
+        index => Some(index.parse().ok()?),
+    };
+    Some(PointTarget { point_type, serial, index })
+}
+
+/// Serial number of a `/api/t3_device/...` route — its first numeric segment.
+fn device_serial(path: &str) -> Option<i64> {
+    path.strip_prefix("/api/t3_device/")?
+        .split('/')
+        .find(|s| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()))?
+        .parse()
+        .ok()
+}
+
+/// Indexes listed in a batch_save body, e.g. `{"inputs": [{"inputIndex": "3"}, ...]}`,
+/// and how many items had no usable index.
+fn batch_indexes(point_type: &str, body: &Value) -> (Vec<i32>, usize) {
+    let (list, key) = match point_type {
+        "INPUT" => ("inputs", "inputIndex"),
+        "OUTPUT" => ("outputs", "outputIndex"),
+        _ => ("variables", "variableIndex"),
+    };
+    let items = body.get(list).and_then(|v| v.as_array()).map(Vec::as_slice).unwrap_or_default();
+    let indexes: Vec<i32> = items
+        .iter()
+        .filter_map(|item| match item.get(key)? {
+            Value::String(s) => s.parse().ok(),
+            v => v.as_i64().map(|n| n as i32),
+        })
+        .collect();
+    let skipped = items.len() - indexes.len();
+    (indexes, skipped)
+}
+
+/// Points a batch save reported as failed, from its
+/// `{"updatedCount", "failedCount", "errors"}` response.
+fn batch_failures(response: &Value) -> Option<(i64, i64, Vec<String>)> {
+    let failed = response.get("failedCount")?.as_i64()?;
+    let updated = response.get("updatedCount").and_then(|v| v.as_i64()).unwrap_or(0);
+    let errors = response
+        .get("errors")
+        .and_then(|v| v.as_array())
+        .map(|errors| errors.iter().filter_map(|e| e.as_str().map(str::to_string)).collect())
+        .unwrap_or_default();
+    Some((updated, failed, errors))
+}
+
+/// Buffer a response body of known, bounded size; larger or streamed
+/// bodies are passed through unread.
+async fn buffer_response(response: Response) -> (Response, Option<Bytes>) {
+    let fits = HttpBody::size_hint(response.body()).upper().is_some_and(|n| n <= MAX_BODY_BYTES as u64);
+    if !fits {
+        return (response, None);
+    }
+    let (parts, body) = response.into_parts();
+    let bytes = to_bytes(body, MAX_BODY_BYTES).await.unwrap_or_default();
+    (Response::from_parts(parts, Body::from(bytes.clone())), Some(bytes))
+}
+
+pub async fn audit_changes(State(state): State<T3AppState>, req: Request<Body>, next: Next) -> Response {
+    let ctx = AuditContext {
+        actor: req.extensions().get::<CurrentUser>().map(|u| u.0.username.clone()),
+        origin: Origin::Ui,
+        session_id: None,
+    };
+    let method = req.method().clone();
+    let path = req.uri().path().to_string();
+    if !is_audited(&method, &path) {
+        return super::scope(ctx, next.run(req)).await;
+    }
+
+    // JSON bodies are buffered so they can be recorded; anything else
+    // (uploads) is passed through untouched.
+    let is_json = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_none_or(|ct| ct.starts_with("application/json"));
+    let (req, body) = if is_json {
+        let (parts, body) = req.into_parts();
+        let bytes = match to_bytes(body, MAX_BODY_BYTES).await {
+            Ok(bytes) => bytes,
+            Err(e) => return (StatusCode::PAYLOAD_TOO_LARGE, e.to_string()).into_response(),
+        };
+        let body = serde_json::from_slice::<Value>(&bytes).ok();
+        (Request::from_parts(parts, Body::from(bytes)), body)
+    } else {
+        (req, None)
+    };
+
+    let db = state.t3_device_conn.as_ref().unwrap_or(&state.conn).lock().await.clone();
+    let target = point_target(&path);
+    // Batch items without a usable index can't be snapshotted
+    let mut skipped = 0;
+    let (point, old_value) = match &target {
+        Some(PointTarget { point_type, serial, index: Some(index) }) => {
+            (Some(point_name(point_type, *index)), point_snapshot(&db, point_type, *serial, *index).await)
+        }
+        Some(PointTarget { point_type, serial, index: None }) => {
+            let mut old = Map::new();
+            let (indexes, unindexed) = body.as_ref().map(|b| batch_indexes(point_type, b)).unwrap_or_default();
+            skipped = unindexed;
+            for index in indexes {
+                let snapshot = point_snapshot(&db, point_type, *serial, index).await;
+                old.insert(point_name(point_type, index), json!(snapshot));
+            }
+            (Some(format!("{} batch", point_type)), Some(Value::Object(old)))
+        }
+        None => (None, None),
+    };
+    let is_batch = matches!(target, Some(PointTarget { index: None, .. }));
+
+    let response = super::scope(ctx.clone(), next.run(req)).await;
+    let status = response.status();
+    let mut ok = status.is_success();
+    let (response, mut detail) = if status.is_success() && !is_batch {
+        (response, status.to_string())
+    } else {
+        let (response, bytes) = buffer_response(response).await;
+        let bytes = bytes.unwrap_or_default();
+        let detail = if !status.is_success() {
+            let text: String = String::from_utf8_lossy(&bytes).chars().take(MAX_DETAIL_CHARS).collect();
+            format!("{}: {}", status, text)
+        } else {
+            // A batch save answers 200 even when some of its points failed
+            match serde_json::from_slice::<Value>(&bytes).ok().as_ref().and_then(batch_failures) {
+                Some((updated, failed, errors)) if failed > 0 => {
+                    ok = false;
+                    let text: String = errors.join("; ").chars().take(MAX_DETAIL_CHARS).collect();
+                    format!("{}: {} updated, {} failed: {}", status, updated, failed, text)
+                }
+                Some((updated, _, _)) => format!("{}: {} updated", status, updated),
+                None => status.to_string(),
+            }
+        };
+        (response, detail)
+    };
+    if skipped > 0 {
+        ok = false;
+        detail.push_str(&format!("; {} point(s) skipped without a valid index", skipped));
+    }
+
+    let entry = NewEntry {
+        action: format!("{} {}", method, path),
+        serial_number: target.as_ref().map(|t| t.serial as i64).or_else(|| device_serial(&path)),
+        point,
+        old_value,
+        new_value: body.as_ref().map(super::redact),
+        ok,
+        detail: Some(detail),
+    };
+    let audit_db = super::audit_db(&state).await;
+    super::scope(ctx, super::record(&audit_db, entry)).await;
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_point_targets() {
+        let t = point_target("/api/t3_device/inputs/4001/3").unwrap();
+        assert_eq!((t.point_type, t.serial, t.index), ("INPUT", 4001, Some(3)));
+        let t = point_target("/api/t3_device/outputs/4001/2/db").unwrap();
+        assert_eq!((t.point_type, t.index), ("OUTPUT", Some(2)));
+        assert_eq!(point_target("/api/t3_device/variables/4001/batch_save").unwrap().index, None);
+        assert!(point_target("/api/t3_device/inputs/4001/refresh").is_none());
+        assert!(point_target("/api/t3_device/devices/4001").is_none());
+        assert_eq!(device_serial("/api/t3_device/devices/4001/programs/2"), Some(4001));
+    }
+
+    #[test]
+    fn test_batch_indexes_count_unusable_items() {
+        let body = json!({ "inputs": [{ "inputIndex": "3" }, { "inputIndex": 4 }, { "inputIndex": "x" }, {}] });
+        assert_eq!(batch_indexes("INPUT", &body), (vec![3, 4], 2));
+        assert_eq!(batch_indexes("OUTPUT", &body), (vec![], 0));
+    }
+}
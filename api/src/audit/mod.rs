@@ -0,0 +1,170 @@
+//! Audit trail — who changed what, from where, and whether it worked.
+//!
+//! Changes reach devices through several paths: the REST update/batch routes,
+//! MCP tools, and AI chat tool calls (which run MCP tools). Each path records
+//! into one AUDIT_LOG table:
+//!
+//! - [`middleware::audit_changes`] records every engineer/admin-level HTTP
+//!   write, with a before-snapshot for point routes.
+//! - [`tools`] records write tools at the MCP `execute_tool` choke point,
+//!   which covers both MCP clients and the AI loop.
+//!
+//! Who and where come from an [`AuditContext`] carried in a task-local:
+//! the middleware sets the signed-in user with origin `ui`, the MCP handler
+//! and AI chat loop narrow it to `mcp` / `ai` with their session id.
+//! Recording never fails the change itself; errors are only logged.
+
+pub mod middleware;
+pub mod routes;
+pub mod store;
+pub mod tools;
+
+use std::future::Future;
+
+use sea_orm::{ConnectionTrait, DatabaseBackend, DatabaseConnection, Statement};
+use serde_json::{json, Map, Value};
+use tracing::warn;
+
+use crate::app_state::T3AppState;
+
+pub use store::{AuditEntry, AuditFilter, NewEntry};
+
+/// Where a change came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Origin {
+    /// REST API, i.e. the web UI or a script.
+    Ui,
+    Mcp,
+    Ai,
+    /// Background work with no request behind it.
+    #[default]
+    System,
+}
+
+impl Origin {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Origin::Ui => "ui",
+            Origin::Mcp => "mcp",
+            Origin::Ai => "ai",
+            Origin::System => "system",
+        }
+    }
+}
+
+/// Who is acting, for everything recorded inside [`scope`].
+#[derive(Debug, Clone, Default)]
+pub struct AuditContext {
+    /// Username; `None` before accounts are set up.
+    pub actor: Option<String>,
+    pub origin: Origin,
+    /// MCP or AI chat session id.
+    pub session_id: Option<String>,
+}
+
+impl AuditContext {
+    /// Same actor, acting through `origin` in `session_id`.
+    pub fn via(&self, origin: Origin, session_id: impl Into<String>) -> Self {
+        Self { actor: self.actor.clone(), origin, session_id: Some(session_id.into()) }
+    }
+}
+
+tokio::task_local! {
+    static CONTEXT: AuditContext;
+}
+
+/// Run `fut` with `ctx` as the audit context.
+pub async fn scope<F: Future>(ctx: AuditContext, fut: F) -> F::Output {
+    CONTEXT.scope(ctx, fut).await
+}
+
+/// The audit context of the current task (origin `system` outside any scope).
+pub fn current() -> AuditContext {
+    CONTEXT.try_with(|c| c.clone()).unwrap_or_default()
+}
+
+/// AUDIT_LOG lives in the local webview database alongside FDD/MCP data.
+pub(crate) async fn audit_db(state: &T3AppState) -> DatabaseConnection {
+    state.local_config_conn.as_ref().unwrap_or(&state.conn).lock().await.clone()
+}
+
+/// Record `entry` under the current context. Failures are logged, not returned.
+pub async fn record(db: &DatabaseConnection, entry: NewEntry) {
+    let ctx = current();
+    let result = async {
+        store::ensure_schema(db).await?;
+        store::insert(db, ctx.actor.as_deref(), ctx.origin, ctx.session_id.as_deref(), &entry).await
+    }
+    .await;
+    if let Err(e) = result {
+        warn!("[audit] failed to record {}: {}", entry.action, e);
+    }
+}
+
+const SECRET_KEYS: &[&str] = &["password", "secret", "token", "apikey", "api_key"];
+
+/// `value` with anything that looks like a credential replaced by `"***"`.
+pub fn redact(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| {
+                    let key = k.to_ascii_lowercase();
+                    if SECRET_KEYS.iter().any(|s| key.contains(s)) {
+                        (k.clone(), json!("***"))
+                    } else {
+                        (k.clone(), redact(v))
+                    }
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(redact).collect()),
+        other => other.clone(),
+    }
+}
+
+/// INPUTS/OUTPUTS/VARIABLES table and index column for a point type.
+pub(crate) fn point_table(point_type: &str) -> Option<(&'static str, &'static str)> {
+    match point_type {
+        "INPUT" => Some(("INPUTS", "Input_Index")),
+        "OUTPUT" => Some(("OUTPUTS", "Output_Index")),
+        "VARIABLE" => Some(("VARIABLES", "Variable_Index")),
+        _ => None,
+    }
+}
+
+/// "INPUT 3" — 1-based like the T3000 point labels (IN3).
+pub(crate) fn point_name(point_type: &str, index: i32) -> String {
+    format!("{} {}", point_type, index + 1)
+}
+
+/// Current stored state of one point, for the `old_value` side of an entry.
+/// Keys match the MCP `t3000_point_write` field names; input values are
+/// scaled to display units the same way the tools report them.
+pub async fn point_snapshot(db: &DatabaseConnection, point_type: &str, serial: i32, index: i32) -> Option<Value> {
+    let (table, idx_col) = point_table(point_type)?;
+    let row = db
+        .query_one(Statement::from_sql_and_values(
+            DatabaseBackend::Sqlite,
+            format!(
+                "SELECT Label, Full_Label, fValue, Range_Field, Auto_Manual, Digital_Analog \
+                 FROM {} WHERE SerialNumber = ? AND {} = ?",
+                table, idx_col
+            ),
+            vec![serial.into(), index.to_string().into()],
+        ))
+        .await
+        .ok()??;
+    let text = |col: &str| row.try_get::<Option<String>>("", col).ok().flatten();
+    let value = text("fValue")
+        .and_then(|v| v.parse::<f64>().ok())
+        .map(|v| if point_type == "INPUT" { v / 1000.0 } else { v });
+    let mut snapshot = Map::new();
+    snapshot.insert("label".into(), json!(text("Label")));
+    snapshot.insert("description".into(), json!(text("Full_Label")));
+    snapshot.insert("value".into(), json!(value));
+    snapshot.insert("range".into(), json!(text("Range_Field")));
+    snapshot.insert("auto_manual".into(), json!(text("Auto_Manual")));
+    snapshot.insert("digital_analog".into(), json!(text("Digital_Analog")));
+    Some(Value::Object(snapshot))
+}
@@ -0,0 +1,76 @@
+//! `/api/audit` — query and export the audit trail.
+
+use axum::{
+    extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Json, Response},
+    routing::get,
+    Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+
+use super::store::{self, AuditFilter};
+use crate::app_state::T3AppState;
+use crate::error::{Error, Result};
+
+const DEFAULT_PAGE_SIZE: u64 = 100;
+const MAX_PAGE_SIZE: u64 = 1000;
+
+async fn db(state: &T3AppState) -> Result<sea_orm::DatabaseConnection> {
+    let db = super::audit_db(state).await;
+    store::ensure_schema(&db).await.map_err(Error::DbError)?;
+    Ok(db)
+}
+
+/// GET /api/audit?actor=&origin=&sessionId=&serialNumber=&point=&action=&result=&from=&to=&limit=&offset=
+async fn list_entries(State(state): State<T3AppState>, Query(mut filter): Query<AuditFilter>) -> Result<Json<serde_json::Value>> {
+    filter.limit = Some(filter.limit.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE));
+    let (entries, total) = store::query(&db(&state).await?, &filter).await.map_err(Error::DbError)?;
+    Ok(Json(json!({
+        "entries": entries,
+        "total": total,
+        "limit": filter.limit,
+        "offset": filter.offset.unwrap_or(0),
+    })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportFormat {
+    /// "csv" (default) or "json"
+    pub format: Option<String>,
+}
+
+/// GET /api/audit/export?format=csv|json plus the list filters; all matches, no paging.
+async fn export_entries(
+    State(state): State<T3AppState>,
+    Query(format): Query<ExportFormat>,
+    Query(filter): Query<AuditFilter>,
+) -> Result<Response> {
+    let filter = AuditFilter { limit: None, offset: None, ..filter };
+    let (entries, _) = store::query(&db(&state).await?, &filter).await.map_err(Error::DbError)?;
+    let stamp = chrono::Utc::now().format("%Y%m%d-%H%M%S");
+    let (content_type, ext, body) = match format.format.as_deref().unwrap_or("csv") {
+        "csv" => ("text/csv; charset=utf-8", "csv", store::to_csv(&entries)),
+        "json" => (
+            "application/json",
+            "json",
+            serde_json::to_string_pretty(&entries).map_err(|e| Error::ServerError(e.to_string()))?,
+        ),
+        other => return Err(Error::BadRequest(format!("Unknown export format '{}': use csv or json", other))),
+    };
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"audit-{}.{}\"", stamp, ext)),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+pub fn create_audit_routes() -> Router<T3AppState> {
+    Router::new()
+        .route("/api/audit", get(list_entries))
+        .route("/api/audit/export", get(export_entries))
+}
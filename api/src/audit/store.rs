@@ -0,0 +1,253 @@
+//! AUDIT_LOG storage — one row per change, queried by the audit routes.
+
+use sea_orm::{ConnectionTrait, DatabaseBackend, DatabaseConnection, QueryResult, Statement, Value as DbValue};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::Origin;
+
+const DDL: &str = "
+CREATE TABLE IF NOT EXISTS AUDIT_LOG (
+    id            INTEGER PRIMARY KEY AUTOINCREMENT,
+    ts            INTEGER NOT NULL,
+    actor         TEXT,
+    origin        TEXT NOT NULL,
+    session_id    TEXT,
+    action        TEXT NOT NULL,
+    serial_number INTEGER,
+    point         TEXT,
+    old_value     TEXT,
+    new_value     TEXT,
+    result        TEXT NOT NULL,
+    detail        TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_audit_log_ts     ON AUDIT_LOG (ts);
+CREATE INDEX IF NOT EXISTS idx_audit_log_serial ON AUDIT_LOG (serial_number, ts);
+";
+
+/// A recorded change.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub id: i64,
+    /// Unix epoch milliseconds.
+    pub ts: i64,
+    pub actor: Option<String>,
+    pub origin: String,
+    /// MCP session id or AI chat session id.
+    pub session_id: Option<String>,
+    /// HTTP method + path, or the MCP tool name.
+    pub action: String,
+    pub serial_number: Option<i64>,
+    /// e.g. "INPUT 3", "OUTPUT batch", "settings:network".
+    pub point: Option<String>,
+    pub old_value: Option<Value>,
+    pub new_value: Option<Value>,
+    /// "ok" or "error".
+    pub result: String,
+    pub detail: Option<String>,
+}
+
+/// A change about to be recorded.
+#[derive(Debug, Clone, Default)]
+pub struct NewEntry {
+    pub action: String,
+    pub serial_number: Option<i64>,
+    pub point: Option<String>,
+    pub old_value: Option<Value>,
+    pub new_value: Option<Value>,
+    pub ok: bool,
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditFilter {
+    pub actor: Option<String>,
+    pub origin: Option<String>,
+    pub session_id: Option<String>,
+    pub serial_number: Option<i64>,
+    /// Substring match on `point`.
+    pub point: Option<String>,
+    /// Substring match on `action`.
+    pub action: Option<String>,
+    pub result: Option<String>,
+    /// Inclusive lower bound, epoch ms.
+    pub from: Option<i64>,
+    /// Exclusive upper bound, epoch ms.
+    pub to: Option<i64>,
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+}
+
+fn stmt(sql: &str, values: Vec<DbValue>) -> Statement {
+    Statement::from_sql_and_values(DatabaseBackend::Sqlite, sql, values)
+}
+
+/// Create AUDIT_LOG if missing.
+pub async fn ensure_schema(db: &DatabaseConnection) -> Result<(), String> {
+    db.execute_unprepared(DDL)
+        .await
+        .map_err(|e| format!("Audit schema error: {}", e))?;
+    Ok(())
+}
+
+pub async fn insert(
+    db: &DatabaseConnection,
+    actor: Option<&str>,
+    origin: Origin,
+    session_id: Option<&str>,
+    entry: &NewEntry,
+) -> Result<(), String> {
+    let json = |v: &Option<Value>| v.as_ref().map(|v| v.to_string());
+    db.execute(stmt(
+        "INSERT INTO AUDIT_LOG (ts, actor, origin, session_id, action, serial_number, point, \
+                                old_value, new_value, result, detail) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        vec![
+            chrono::Utc::now().timestamp_millis().into(),
+            actor.map(str::to_owned).into(),
+            origin.as_str().into(),
+            session_id.map(str::to_owned).into(),
+            entry.action.clone().into(),
+            entry.serial_number.into(),
+            entry.point.clone().into(),
+            json(&entry.old_value).into(),
+            json(&entry.new_value).into(),
+            (if entry.ok { "ok" } else { "error" }).into(),
+            entry.detail.clone().into(),
+        ],
+    ))
+    .await
+    .map_err(|e| format!("Audit insert failed: {}", e))?;
+    Ok(())
+}
+
+fn where_clause(filter: &AuditFilter) -> (String, Vec<DbValue>) {
+    let mut clauses: Vec<&str> = Vec::new();
+    let mut values: Vec<DbValue> = Vec::new();
+    if let Some(actor) = &filter.actor {
+        clauses.push("actor = ? COLLATE NOCASE");
+        values.push(actor.clone().into());
+    }
+    if let Some(origin) = &filter.origin {
+        clauses.push("origin = ?");
+        values.push(origin.to_ascii_lowercase().into());
+    }
+    if let Some(session_id) = &filter.session_id {
+        clauses.push("session_id = ?");
+        values.push(session_id.clone().into());
+    }
+    if let Some(serial) = filter.serial_number {
+        clauses.push("serial_number = ?");
+        values.push(serial.into());
+    }
+    if let Some(point) = &filter.point {
+        clauses.push("point LIKE ?");
+        values.push(format!("%{}%", point).into());
+    }
+    if let Some(action) = &filter.action {
+        clauses.push("action LIKE ?");
+        values.push(format!("%{}%", action).into());
+    }
+    if let Some(result) = &filter.result {
+        clauses.push("result = ?");
+        values.push(result.clone().into());
+    }
+    if let Some(from) = filter.from {
+        clauses.push("ts >= ?");
+        values.push(from.into());
+    }
+    if let Some(to) = filter.to {
+        clauses.push("ts < ?");
+        values.push(to.into());
+    }
+    if clauses.is_empty() {
+        (String::new(), values)
+    } else {
+        (format!(" WHERE {}", clauses.join(" AND ")), values)
+    }
+}
+
+fn entry_from_row(row: &QueryResult) -> AuditEntry {
+    let json = |col: &str| {
+        row.try_get::<Option<String>>("", col)
+            .ok()
+            .flatten()
+            .map(|s| serde_json::from_str(&s).unwrap_or(Value::String(s)))
+    };
+    AuditEntry {
+        id: row.try_get("", "id").unwrap_or(0),
+        ts: row.try_get("", "ts").unwrap_or(0),
+        actor: row.try_get("", "actor").ok().flatten(),
+        origin: row.try_get("", "origin").unwrap_or_default(),
+        session_id: row.try_get("", "session_id").ok().flatten(),
+        action: row.try_get("", "action").unwrap_or_default(),
+        serial_number: row.try_get("", "serial_number").ok().flatten(),
+        point: row.try_get("", "point").ok().flatten(),
+        old_value: json("old_value"),
+        new_value: json("new_value"),
+        result: row.try_get("", "result").unwrap_or_default(),
+        detail: row.try_get("", "detail").ok().flatten(),
+    }
+}
+
+/// Matching entries, newest first, plus the total match count. `limit: None`
+/// returns everything (used by export).
+pub async fn query(db: &DatabaseConnection, filter: &AuditFilter) -> Result<(Vec<AuditEntry>, u64), String> {
+    let (where_sql, values) = where_clause(filter);
+    let total = db
+        .query_one(stmt(&format!("SELECT COUNT(*) AS n FROM AUDIT_LOG{}", where_sql), values.clone()))
+        .await
+        .map_err(|e| format!("Audit query failed: {}", e))?
+        .and_then(|r| r.try_get::<i64>("", "n").ok())
+        .unwrap_or(0) as u64;
+
+    let mut sql = format!("SELECT * FROM AUDIT_LOG{} ORDER BY ts DESC, id DESC", where_sql);
+    if let Some(limit) = filter.limit {
+        sql.push_str(&format!(" LIMIT {} OFFSET {}", limit, filter.offset.unwrap_or(0)));
+    }
+    let rows = db
+        .query_all(stmt(&sql, values))
+        .await
+        .map_err(|e| format!("Audit query failed: {}", e))?;
+    Ok((rows.iter().map(entry_from_row).collect(), total))
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// Entries as CSV with an RFC 3339 timestamp column.
+pub fn to_csv(entries: &[AuditEntry]) -> String {
+    let mut out = String::from(
+        "id,timestamp,actor,origin,session_id,action,serial_number,point,old_value,new_value,result,detail\n",
+    );
+    for e in entries {
+        let timestamp = chrono::DateTime::from_timestamp_millis(e.ts)
+            .map(|t| t.to_rfc3339())
+            .unwrap_or_default();
+        let json = |v: &Option<Value>| v.as_ref().map(|v| v.to_string()).unwrap_or_default();
+        let fields = [
+            e.id.to_string(),
+            timestamp,
+            e.actor.clone().unwrap_or_default(),
+            e.origin.clone(),
+            e.session_id.clone().unwrap_or_default(),
+            e.action.clone(),
+            e.serial_number.map(|s| s.to_string()).unwrap_or_default(),
+            e.point.clone().unwrap_or_default(),
+            json(&e.old_value),
+            json(&e.new_value),
+            e.result.clone(),
+            e.detail.clone().unwrap_or_default(),
+        ];
+        out.push_str(&fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>().join(","));
+        out.push('\n');
+    }
+    out
+}
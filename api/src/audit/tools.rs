@@ -0,0 +1,169 @@
+//! MCP side of the audit trail — write tools recorded around
+//! [`crate::mcp::execute_tool`], which both MCP clients and the AI chat loop
+//! go through.
+
+use sea_orm::{ConnectionTrait, DatabaseBackend, DatabaseConnection, Statement};
+use serde_json::{json, Map, Value};
+
+use super::{point_name, point_snapshot, redact, NewEntry};
+
+/// Tools that change a device, the local model or configuration. This is the
+/// one write-tool list: the AI chat loop holds these for approval and
+/// read-only MCP tokens are refused them.
+pub const AUDITED_TOOLS: &[&str] = &[
+    "t3000_point_write",
+    "t3000_point_write_batch",
+    "t3000_settings_write",
+    "t3000_device_control",
+    "t3000_device_delete",
+    "t3000_alarm_acknowledge",
+    "t3000_haystack_auto_tag",
+    "t3000_rule_create",
+    "t3000_rule_toggle",
+    "t3000_fdd_rule_create",
+    "t3000_fdd_rule_update",
+    "t3000_fdd_rule_toggle",
+    "t3000_fdd_rule_import",
+];
+
+pub fn is_audited(name: &str) -> bool {
+    AUDITED_TOOLS.contains(&name)
+}
+
+/// One point touched by a point-write tool.
+struct PointWrite {
+    serial: i32,
+    point_type: String,
+    /// 0-based, as stored.
+    index: i32,
+    field: String,
+    value: Value,
+    old: Option<Value>,
+}
+
+/// State captured before an audited tool runs.
+pub struct Before {
+    points: Vec<PointWrite>,
+    /// `t3000_settings_write`: the row's current values of the fields being written.
+    settings: Option<Value>,
+}
+
+fn point_write(point: &Value) -> Option<PointWrite> {
+    Some(PointWrite {
+        serial: point.get("serial_number")?.as_i64()? as i32,
+        point_type: point.get("point_type")?.as_str()?.to_string(),
+        // Tools take 1-based indexes.
+        index: point.get("point_index")?.as_i64()? as i32 - 1,
+        field: point.get("field").and_then(|v| v.as_str()).unwrap_or("value").to_string(),
+        value: point.get("value").cloned().unwrap_or(Value::Null),
+        old: None,
+    })
+}
+
+async fn settings_snapshot(db: &DatabaseConnection, args: &Value) -> Option<Value> {
+    let serial = args.get("serial_number")?.as_i64()?;
+    let (table, allowed) = crate::mcp::dispatch::settings_write_target(args.get("category")?.as_str()?)?;
+    let row = db
+        .query_one(Statement::from_sql_and_values(
+            DatabaseBackend::Sqlite,
+            format!("SELECT * FROM {} WHERE SerialNumber = ?", table),
+            vec![serial.into()],
+        ))
+        .await
+        .ok()??;
+    let mut old = Map::new();
+    for key in args.get("fields")?.as_object()?.keys().filter(|k| allowed.contains(&k.as_str())) {
+        let value = row
+            .try_get::<Option<String>>("", key)
+            .ok()
+            .flatten()
+            .map(Value::String)
+            .or_else(|| row.try_get::<Option<i64>>("", key).ok().flatten().map(|n| json!(n)))
+            .unwrap_or(Value::Null);
+        old.insert(key.clone(), value);
+    }
+    Some(redact(&Value::Object(old)))
+}
+
+/// Capture what an audited tool is about to overwrite. `None` for tools
+/// that aren't audited.
+pub async fn before(name: &str, args: &Value, db: &DatabaseConnection) -> Option<Before> {
+    if !is_audited(name) {
+        return None;
+    }
+    let mut points: Vec<PointWrite> = match name {
+        "t3000_point_write" => point_write(args).into_iter().collect(),
+        "t3000_point_write_batch" => args
+            .get("points")
+            .and_then(|v| v.as_array())
+            .map(|a| a.iter().filter_map(point_write).collect())
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    };
+    for point in &mut points {
+        point.old = point_snapshot(db, &point.point_type, point.serial, point.index)
+            .await
+            .and_then(|s| s.get(&point.field).cloned());
+    }
+    let settings = match name {
+        "t3000_settings_write" => settings_snapshot(db, args).await,
+        _ => None,
+    };
+    Some(Before { points, settings })
+}
+
+/// Record the outcome of an audited tool call. Point-write tools get one
+/// entry per point.
+pub async fn after(name: &str, args: &Value, before: Before, result: &Result<String, String>, db: &DatabaseConnection) {
+    let (ok, detail) = match result {
+        Ok(_) => (true, None),
+        Err(e) => (false, Some(e.clone())),
+    };
+    if before.points.is_empty() {
+        let entry = NewEntry {
+            action: name.to_string(),
+            serial_number: args.get("serial_number").and_then(|v| v.as_i64()),
+            point: match name {
+                "t3000_settings_write" => args
+                    .get("category")
+                    .and_then(|v| v.as_str())
+                    .map(|c| format!("settings:{}", c)),
+                _ => None,
+            },
+            old_value: before.settings,
+            new_value: Some(match name {
+                "t3000_settings_write" => redact(args.get("fields").unwrap_or(&Value::Null)),
+                _ => redact(args),
+            }),
+            ok,
+            detail,
+        };
+        super::record(db, entry).await;
+        return;
+    }
+
+    // A batch reports failed and skipped points as "dev{serial} {type}[{index}]: ..."
+    let batch_report: Option<Value> = result.as_ref().ok().and_then(|r| serde_json::from_str(r).ok());
+    let batch_list = |key: &str| -> Vec<String> {
+        batch_report
+            .as_ref()
+            .and_then(|r| r.get(key).cloned())
+            .and_then(|e| serde_json::from_value(e).ok())
+            .unwrap_or_default()
+    };
+    let batch_errors = [batch_list("errors"), batch_list("skipped")].concat();
+    for point in before.points {
+        let prefix = format!("dev{} {}[{}]:", point.serial, point.point_type, point.index);
+        let point_error = batch_errors.iter().find(|e| e.starts_with(&prefix));
+        let entry = NewEntry {
+            action: name.to_string(),
+            serial_number: Some(point.serial as i64),
+            point: Some(point_name(&point.point_type, point.index)),
+            old_value: Some(json!({ point.field.as_str(): point.old })),
+            new_value: Some(json!({ point.field.as_str(): point.value })),
+            ok: ok && point_error.is_none(),
+            detail: point_error.cloned().or_else(|| detail.clone()),
+        };
+        super::record(db, entry).await;
+    }
+}
@@ -0,0 +1,135 @@
+//! Authentication and role-based access control.
+//!
+//! Accounts live in APP_USERS with argon2 password hashes; `POST /api/auth/login`
+//! issues an expiring session token that clients send as
+//! `Authorization: Bearer <token>`. [`enforce_roles`] checks every `/api` request
+//! against the role table in [`policy`]. Until the first account is created
+//! (`POST /api/auth/bootstrap`) the API stays open, so existing installs keep
+//! working after an upgrade.
+
+pub mod policy;
+pub mod role;
+pub mod routes;
+pub mod store;
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::{self, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sea_orm::DatabaseConnection;
+
+use crate::app_state::T3AppState;
+use crate::error::{Error, Result};
+
+pub use policy::{required_access, Access};
+pub use role::Role;
+pub use store::User;
+
+/// The signed-in user, added to request extensions by [`enforce_roles`].
+#[derive(Debug, Clone)]
+pub struct CurrentUser(pub User);
+
+/// Database holding APP_USERS / APP_SESSIONS.
+pub(crate) async fn auth_db(state: &T3AppState) -> DatabaseConnection {
+    state.local_config_conn.as_ref().unwrap_or(&state.conn).lock().await.clone()
+}
+
+/// Token from an `Authorization: Bearer <token>` header.
+pub fn bearer_token(req: &Request<Body>) -> Option<&str> {
+    req.headers()
+        .get(http::header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+}
+
+/// Middleware enforcing [`policy::required_access`] on every request.
+///
+/// Returns 401 when a route needs a session and none is presented (or it has
+/// expired), 403 when the session's role is too low. A valid session is
+/// attached as [`CurrentUser`] even on public routes.
+pub async fn enforce_roles(State(state): State<T3AppState>, mut req: Request<Body>, next: Next) -> Result<Response> {
+    let access = required_access(req.method(), req.uri().path());
+    let token = bearer_token(&req).map(str::to_owned);
+    if token.is_none() && access == Access::Public {
+        return Ok(next.run(req).await);
+    }
+    // MCP API tokens stand in for a session on the MCP endpoint (every
+    // method); the token is attached for the endpoint to apply its scopes to
+    // tool calls.
+    if req.uri().path() == "/api/mcp" && token.as_deref().is_some_and(|t| t.starts_with(crate::mcp::tokens::TOKEN_PREFIX)) {
+        return match crate::mcp::server::token_from_headers(&state, req.headers()).await {
+            Ok(mcp_token) => {
+                if let Some(mcp_token) = mcp_token {
+                    req.extensions_mut().insert(mcp_token);
+                }
+                Ok(next.run(req).await)
+            }
+            Err(rejection) => Ok(rejection.into_response()),
+        };
+    }
+
+    let db = auth_db(&state).await;
+    let user = match token {
+        Some(token) => store::session_user(&db, &token).await?,
+        None => None,
+    };
+    if let Access::Role(min) = access {
+        match &user {
+            Some(user) if user.role < min => return Err(Error::PermissionDenied),
+            Some(_) => {}
+            // No accounts yet: the site hasn't opted in to authentication.
+            None if !store::has_users(&db).await? => {}
+            None => return Err(Error::Unauthorized),
+        }
+    }
+    if let Some(user) = user {
+        req.extensions_mut().insert(CurrentUser(user));
+    }
+    Ok(next.run(req).await)
+}
+
+/// Middleware function that requires authentication.
+///
+/// Requests already signed in through [`enforce_roles`] pass. Otherwise this
+/// checks the `Authorization` header of the request for a valid API secret key.
+/// If the header is not present or the secret key is incorrect, it returns a `Unauthorized` error.
+/// Otherwise, it calls the next middleware in the chain.
+///
+/// # Arguments
+///
+/// * `req` - The incoming request.
+/// * `next` - The next middleware in the chain.
+///
+/// # Returns
+///
+/// A `Result` containing the response from the next middleware in the chain, or a `Unauthorized` error.
+pub async fn require_auth(req: Request<Body>, next: Next) -> Result<Response> {
+    if req.extensions().get::<CurrentUser>().is_some() {
+        return Ok(next.run(req).await);
+    }
+
+    // Get the `Authorization` header from the request
+    let auth_header = req
+        .headers()
+        .get(http::header::AUTHORIZATION)
+        .and_then(|header| header.to_str().ok())
+        .unwrap_or("");
+
+    // Get the API secret key from the environment variable, or use a default value if not set
+    let secret = option_env!("API_SECRET_KEY").unwrap_or("secret");
+
+    // Check if the `Authorization` header matches the secret key
+    if auth_header != secret {
+        // If not, return a `Unauthorized` error
+        return Err(Error::Unauthorized);
+    }
+
+    // If the `Authorization` header is correct, call the next middleware in the chain
+    Ok(next.run(req).await)
+}
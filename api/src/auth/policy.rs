@@ -0,0 +1,122 @@
+//! Which role each route group needs.
+//!
+//! One ordered table for the whole API, matched on method and path; the
+//! first matching rule wins. Patterns match whole path segments, `*` matches
+//! any single segment, and a pattern also covers everything below it.
+
+use axum::http::Method;
+
+use super::role::Role;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /// No session needed.
+    Public,
+    /// A session with at least this role.
+    Role(Role),
+}
+
+#[derive(Clone, Copy)]
+enum Methods {
+    Any,
+    /// GET, HEAD, OPTIONS
+    Read,
+    /// Everything else
+    Write,
+}
+
+use Access::{Public, Role as R};
+use Methods::{Any, Read, Write};
+use Role::{Admin, Engineer, Operator, Viewer};
+
+const RULES: &[(Methods, &str, Access)] = &[
+    (Any, "/api/health", Public),
+    (Any, "/api/server/time", Public),
+    (Write, "/api/auth/login", Public),
+    (Write, "/api/auth/bootstrap", Public),
+    (Write, "/api/auth/logout", Public),
+    (Read, "/api/auth/me", Public),
+    // PC-to-PC registry traffic (server/client mode) carries no user session
+    (Any, "/api/database/server", Public),
+    (Any, "/api/sync/health/ping", Public),
+    // Admin: accounts, database backend, server maintenance, AI provider keys
+    (Any, "/api/auth/users", R(Admin)),
+    (Read, "/api/database/backend/status", R(Viewer)),
+    (Any, "/api/database/backend", R(Admin)),
+    (Write, "/api/database/trendlog/query", R(Viewer)),
+    (Write, "/api/database/trendlog/export", R(Viewer)),
+    (Write, "/api/database", R(Admin)),
+    (Write, "/api/t3_device/db_management", R(Admin)),
+    (Write, "/api/config/import", R(Admin)),
+    (Write, "/api/ai/settings", R(Admin)),
+    (Any, "/api/ai/usage", R(Admin)),
+    (Write, "/api/ai/mcp-servers", R(Admin)),
+    (Write, "/api/ai/activate-mcp-server", R(Admin)),
+    (Write, "/api/ai/delete-mcp-server", R(Admin)),
+    (Any, "/api/mcp/tokens", R(Admin)),
+    // Engineer: anything that can change a device or the engineering model
+    (Any, "/api/audit", R(Engineer)),
+    (Any, "/api/develop", R(Engineer)),
+    (Any, "/api/t3000/ffi/call", R(Engineer)),
+    // MCP and AI chat tools can write points
+    (Write, "/api/mcp", R(Engineer)),
+    (Write, "/api/ai/chat", R(Engineer)),
+    // Approving an AI write is the same as making it
+    (Any, "/api/ai/approvals", R(Engineer)),
+    (Write, "/api/config", R(Engineer)),
+    (Write, "/api/logs", R(Engineer)),
+    (Write, "/api/haystack/point-tags/read", R(Viewer)),
+    (Write, "/api/haystack/auto-tagging/preview", R(Viewer)),
+    (Write, "/api/haystack", R(Engineer)),
+    (Write, "/api/fdd/analyze", R(Operator)),
+    (Write, "/api/fdd", R(Engineer)),
+    (Write, "/api/modbus/*/poll", R(Operator)),
+    (Write, "/api/modbus", R(Engineer)),
+    // Device reads that happen to be POSTs, and the trend view's realtime cache
+    (Write, "/api/t3_device/devices/*/trendlog-data/smart", R(Viewer)),
+    (Write, "/api/t3_device/devices/*/trendlogs/*/history", R(Viewer)),
+    (Write, "/api/t3_device/trendlog-data/realtime", R(Viewer)),
+    // Refreshing from a device only updates the local copy
+    (Write, "/api/t3_device/*/*/refresh", R(Operator)),
+    (Write, "/api/t3_device/*/*/save-refreshed", R(Operator)),
+    (Write, "/api/t3_device/devices/scan-refresh", R(Operator)),
+    (Write, "/api/t3_device/init", R(Operator)),
+    (Write, "/api/t3_device/trendlogs/*/init", R(Operator)),
+    (Write, "/api/t3_device/sync", R(Operator)),
+    (Write, "/api/t3_device/sync-all", R(Operator)),
+    (Write, "/api/t3_device/sync-detailed", R(Operator)),
+    (Write, "/api/t3_device/sync-ffi", R(Operator)),
+    // Point writes, device settings, programming, raw table edits
+    (Write, "/api/t3_device", R(Engineer)),
+    // Frontend flow/telemetry logging and point-set listing
+    (Write, "/api/flows", R(Viewer)),
+    (Write, "/api/point-sets/list", R(Viewer)),
+    (Read, "/api", R(Viewer)),
+    (Write, "/api", R(Operator)),
+];
+
+fn matches(pattern: &str, path: &str) -> bool {
+    let mut segments = path.split('/').filter(|s| !s.is_empty());
+    pattern
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .all(|p| segments.next().is_some_and(|s| p == "*" || p == s))
+}
+
+/// Access required for `method` on `path`. Paths outside `/api` (the SPA,
+/// static files) are public.
+pub fn required_access(method: &Method, path: &str) -> Access {
+    let read = matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS);
+    RULES
+        .iter()
+        .find(|(methods, pattern, _)| {
+            let method_ok = match methods {
+                Any => true,
+                Read => read,
+                Write => !read,
+            };
+            method_ok && matches(pattern, path)
+        })
+        .map(|(_, _, access)| *access)
+        .unwrap_or(Public)
+}
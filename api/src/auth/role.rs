@@ -0,0 +1,39 @@
+//! User roles, lowest to highest privilege.
+
+use serde::{Deserialize, Serialize};
+
+/// Each role can do everything the roles below it can.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Read-only access to devices, trends, FDD findings and logs.
+    Viewer,
+    /// Day-to-day operation: refresh/sync from devices, run FDD, pause sampling.
+    Operator,
+    /// Point writes, device settings and programming, FDD/Haystack/Modbus
+    /// configuration, developer tools and the AI/MCP tool surface.
+    Engineer,
+    /// User management, database backend and server maintenance.
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Operator => "operator",
+            Role::Engineer => "engineer",
+            Role::Admin => "admin",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "viewer" => Some(Role::Viewer),
+            "operator" => Some(Role::Operator),
+            "engineer" => Some(Role::Engineer),
+            "admin" => Some(Role::Admin),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! `/api/auth` — login/logout, the current session, first-admin bootstrap and
+//! account management.
+
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::Request,
+    response::Json,
+    routing::{get, post, put},
+    Extension, Router,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::role::Role;
+use super::store::{self, UserUpdate};
+use super::{auth_db, bearer_token, CurrentUser};
+use crate::app_state::T3AppState;
+use crate::error::{Error, Result};
+
+#[derive(Debug, Deserialize)]
+pub struct LoginRequest {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateUserRequest {
+    pub username: String,
+    pub password: String,
+    pub role: Role,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateUserRequest {
+    pub role: Option<Role>,
+    pub password: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+async fn db(state: &T3AppState) -> Result<sea_orm::DatabaseConnection> {
+    let db = auth_db(state).await;
+    store::ensure_schema(&db).await?;
+    Ok(db)
+}
+
+/// POST /api/auth/login
+async fn login(State(state): State<T3AppState>, Json(req): Json<LoginRequest>) -> Result<Json<Value>> {
+    let db = db(&state).await?;
+    match store::login(&db, &req.username, &req.password).await? {
+        Some(session) => Ok(Json(json!(session))),
+        None => Err(Error::Unauthorized),
+    }
+}
+
+/// POST /api/auth/logout
+async fn logout(State(state): State<T3AppState>, req: Request<Body>) -> Result<Json<Value>> {
+    if let Some(token) = bearer_token(&req) {
+        store::revoke_session(&db(&state).await?, token).await?;
+    }
+    Ok(Json(json!({ "success": true })))
+}
+
+/// GET /api/auth/me — the signed-in user, and whether sign-in is required at all.
+async fn me(State(state): State<T3AppState>, user: Option<Extension<CurrentUser>>) -> Result<Json<Value>> {
+    let auth_required = store::has_users(&auth_db(&state).await).await?;
+    Ok(Json(json!({
+        "user": user.map(|Extension(CurrentUser(u))| u),
+        "authRequired": auth_required,
+    })))
+}
+
+/// POST /api/auth/bootstrap — create the first account as admin. Only
+/// allowed while no accounts exist; from then on the API requires sign-in.
+async fn bootstrap(State(state): State<T3AppState>, Json(req): Json<LoginRequest>) -> Result<Json<Value>> {
+    let db = db(&state).await?;
+    let already = || Error::Conflict("An account already exists; sign in instead".to_string());
+    if store::has_users(&db).await? {
+        return Err(already());
+    }
+    let user = store::create_first_admin(&db, &req.username, &req.password)
+        .await?
+        .ok_or_else(already)?;
+    Ok(Json(json!(user)))
+}
+
+/// GET /api/auth/users
+async fn list_users(State(state): State<T3AppState>) -> Result<Json<Value>> {
+    let users = store::list_users(&db(&state).await?).await?;
+    Ok(Json(json!({ "users": users })))
+}
+
+/// POST /api/auth/users
+async fn create_user(State(state): State<T3AppState>, Json(req): Json<CreateUserRequest>) -> Result<Json<Value>> {
+    let db = db(&state).await?;
+    // While the API is still open anyone could reach this; the first account
+    // has to go through bootstrap so the site can't end up without an admin.
+    if !store::has_users(&db).await? {
+        return Err(Error::BadRequest("Create the first account with /api/auth/bootstrap".to_string()));
+    }
+    let user = store::create_user(&db, &req.username, &req.password, req.role).await?;
+    Ok(Json(json!(user)))
+}
+
+/// PUT /api/auth/users/:id
+async fn update_user(
+    State(state): State<T3AppState>,
+    Path(id): Path<i64>,
+    Json(req): Json<UpdateUserRequest>,
+) -> Result<Json<Value>> {
+    let update = UserUpdate { role: req.role, password: req.password, enabled: req.enabled };
+    let user = store::update_user(&db(&state).await?, id, update).await?.ok_or(Error::NotFound)?;
+    Ok(Json(json!(user)))
+}
+
+/// DELETE /api/auth/users/:id
+async fn delete_user(State(state): State<T3AppState>, Path(id): Path<i64>) -> Result<Json<Value>> {
+    let user = store::delete_user(&db(&state).await?, id).await?.ok_or(Error::NotFound)?;
+    Ok(Json(json!({ "deleted": user })))
+}
+
+pub fn create_auth_routes() -> Router<T3AppState> {
+    Router::new()
+        .route("/api/auth/login", post(login))
+        .route("/api/auth/logout", post(logout))
+        .route("/api/auth/me", get(me))
+        .route("/api/auth/bootstrap", post(bootstrap))
+        .route("/api/auth/users", get(list_users).post(create_user))
+        .route("/api/auth/users/:id", put(update_user).delete(delete_user))
+}
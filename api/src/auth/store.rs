@@ -0,0 +1,335 @@
+//! APP_USERS / APP_SESSIONS storage — accounts with argon2 password hashes
+//! and expiring session tokens.
+//!
+//! Only a SHA-256 of each session token is stored, so a copy of the database
+//! can't be replayed as a login.
+
+use argon2::password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use sea_orm::{ConnectionTrait, DatabaseBackend, DatabaseConnection, QueryResult, Statement, Value};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use super::role::Role;
+use crate::error::{Error, Result};
+
+const DDL: &str = "
+CREATE TABLE IF NOT EXISTS APP_USERS (
+    id            INTEGER PRIMARY KEY AUTOINCREMENT,
+    username      TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    password_hash TEXT NOT NULL,
+    role          TEXT NOT NULL,
+    enabled       INTEGER NOT NULL DEFAULT 1,
+    created_at    TEXT DEFAULT (datetime('now')),
+    last_login_at TEXT
+);
+CREATE TABLE IF NOT EXISTS APP_SESSIONS (
+    token_hash TEXT PRIMARY KEY,
+    user_id    INTEGER NOT NULL,
+    created_at INTEGER NOT NULL,
+    expires_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_app_sessions_user ON APP_SESSIONS (user_id);
+";
+
+/// How long a login stays valid.
+pub const SESSION_TTL_SECS: i64 = 12 * 60 * 60;
+pub const MIN_PASSWORD_LEN: usize = 8;
+
+/// An account, without its password hash.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct User {
+    pub id: i64,
+    pub username: String,
+    pub role: Role,
+    pub enabled: bool,
+    pub created_at: Option<String>,
+    pub last_login_at: Option<String>,
+}
+
+/// A freshly issued session. `token` is only ever returned here.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Session {
+    pub token: String,
+    pub expires_at: i64,
+    pub user: User,
+}
+
+/// Fields an admin can change on an account.
+#[derive(Debug, Clone, Default)]
+pub struct UserUpdate {
+    pub role: Option<Role>,
+    pub password: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+fn stmt(sql: &str, values: Vec<Value>) -> Statement {
+    Statement::from_sql_and_values(DatabaseBackend::Sqlite, sql, values)
+}
+
+fn db_err(e: sea_orm::DbErr) -> Error {
+    Error::DbError(e.to_string())
+}
+
+/// Create APP_USERS + APP_SESSIONS if missing.
+pub async fn ensure_schema(db: &DatabaseConnection) -> Result<()> {
+    db.execute_unprepared(DDL)
+        .await
+        .map_err(db_err)?;
+    Ok(())
+}
+
+/// Argon2 is deliberately slow, so hashing runs on the blocking pool.
+pub async fn hash_password(password: &str) -> Result<String> {
+    let password = password.to_string();
+    tokio::task::spawn_blocking(move || {
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|h| h.to_string())
+            .map_err(|e| Error::ServerError(format!("Password hashing failed: {}", e)))
+    })
+    .await
+    .map_err(|e| Error::ServerError(format!("Password hashing failed: {}", e)))?
+}
+
+pub async fn verify_password(password: &str, hash: &str) -> bool {
+    let (password, hash) = (password.to_string(), hash.to_string());
+    tokio::task::spawn_blocking(move || {
+        PasswordHash::new(&hash)
+            .map(|parsed| Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok())
+            .unwrap_or(false)
+    })
+    .await
+    .unwrap_or(false)
+}
+
+pub(crate) fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+pub(crate) fn new_token() -> String {
+    // Two v4 UUIDs = 244 random bits from the OS-seeded RNG.
+    format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple())
+}
+
+fn validate_password(password: &str) -> Result<()> {
+    if password.chars().count() < MIN_PASSWORD_LEN {
+        return Err(Error::ValidationError(format!("Password must be at least {} characters", MIN_PASSWORD_LEN)));
+    }
+    Ok(())
+}
+
+fn user_from_row(row: &QueryResult) -> User {
+    let role: String = row.try_get("", "role").unwrap_or_default();
+    User {
+        id: row.try_get("", "id").unwrap_or(0),
+        username: row.try_get("", "username").unwrap_or_default(),
+        // An unreadable role never grants more than read access.
+        role: Role::parse(&role).unwrap_or(Role::Viewer),
+        enabled: row.try_get::<i64>("", "enabled").unwrap_or(0) != 0,
+        created_at: row.try_get("", "created_at").ok().flatten(),
+        last_login_at: row.try_get("", "last_login_at").ok().flatten(),
+    }
+}
+
+const USER_COLUMNS: &str = "id, username, role, enabled, created_at, last_login_at";
+
+/// Whether any account exists. Until one does the API runs unauthenticated
+/// (a missing table counts as none).
+pub async fn has_users(db: &DatabaseConnection) -> Result<bool> {
+    match db.query_one(stmt("SELECT EXISTS(SELECT 1 FROM APP_USERS) AS present", vec![])).await {
+        Ok(row) => Ok(row.and_then(|r| r.try_get::<i64>("", "present").ok()).unwrap_or(0) != 0),
+        Err(e) if e.to_string().contains("no such table") => Ok(false),
+        Err(e) => Err(db_err(e)),
+    }
+}
+
+pub async fn list_users(db: &DatabaseConnection) -> Result<Vec<User>> {
+    let rows = db
+        .query_all(stmt(&format!("SELECT {} FROM APP_USERS ORDER BY username", USER_COLUMNS), vec![]))
+        .await
+        .map_err(db_err)?;
+    Ok(rows.iter().map(user_from_row).collect())
+}
+
+pub async fn get_user(db: &DatabaseConnection, id: i64) -> Result<Option<User>> {
+    let row = db
+        .query_one(stmt(&format!("SELECT {} FROM APP_USERS WHERE id = ?", USER_COLUMNS), vec![id.into()]))
+        .await
+        .map_err(db_err)?;
+    Ok(row.as_ref().map(user_from_row))
+}
+
+pub async fn create_user(db: &DatabaseConnection, username: &str, password: &str, role: Role) -> Result<User> {
+    insert_user(db, username, password, role, "")
+        .await?
+        .ok_or_else(|| Error::ServerError("Created user not found".to_string()))
+}
+
+/// Create the first account as admin. The "no accounts yet" check is part of
+/// the INSERT, so of two concurrent bootstraps only one creates an account;
+/// `None` when an account already exists.
+pub async fn create_first_admin(db: &DatabaseConnection, username: &str, password: &str) -> Result<Option<User>> {
+    insert_user(db, username, password, Role::Admin, " WHERE NOT EXISTS (SELECT 1 FROM APP_USERS)").await
+}
+
+/// Insert an account; `condition` is appended to the INSERT ... SELECT.
+async fn insert_user(db: &DatabaseConnection, username: &str, password: &str, role: Role, condition: &str) -> Result<Option<User>> {
+    let username = username.trim();
+    if username.is_empty() {
+        return Err(Error::ValidationError("Username must not be empty".to_string()));
+    }
+    validate_password(password)?;
+    let hash = hash_password(password).await?;
+    let result = db
+        .execute(stmt(
+            &format!("INSERT INTO APP_USERS (username, password_hash, role) SELECT ?, ?, ?{}", condition),
+            vec![username.into(), hash.into(), role.as_str().into()],
+        ))
+        .await;
+    match result {
+        Ok(r) if r.rows_affected() == 0 => Ok(None),
+        Ok(r) => get_user(db, r.last_insert_id() as i64).await,
+        Err(e) if e.to_string().contains("UNIQUE") => Err(Error::BadRequest(format!("User '{}' already exists", username))),
+        Err(e) => Err(db_err(e)),
+    }
+}
+
+async fn enabled_admins_other_than(db: &DatabaseConnection, id: i64) -> Result<i64> {
+    let row = db
+        .query_one(stmt(
+            "SELECT COUNT(*) AS n FROM APP_USERS WHERE role = 'admin' AND enabled = 1 AND id != ?",
+            vec![id.into()],
+        ))
+        .await
+        .map_err(db_err)?;
+    Ok(row.and_then(|r| r.try_get::<i64>("", "n").ok()).unwrap_or(0))
+}
+
+/// Apply `update` to an account. Refuses to leave the site without an
+/// enabled admin. A password change or disabling signs the user out.
+///
+/// Everything is checked before anything is written, and the columns change
+/// in a single UPDATE, so a rejected request leaves the account untouched.
+pub async fn update_user(db: &DatabaseConnection, id: i64, update: UserUpdate) -> Result<Option<User>> {
+    let Some(current) = get_user(db, id).await? else {
+        return Ok(None);
+    };
+    let hash = match &update.password {
+        Some(password) => {
+            validate_password(password)?;
+            Some(hash_password(password).await?)
+        }
+        None => None,
+    };
+    let loses_admin = current.role == Role::Admin
+        && current.enabled
+        && (update.role.is_some_and(|r| r != Role::Admin) || update.enabled == Some(false));
+    if loses_admin && enabled_admins_other_than(db, id).await? == 0 {
+        return Err(Error::BadRequest("Cannot demote or disable the last admin".to_string()));
+    }
+
+    db.execute(stmt(
+        "UPDATE APP_USERS SET role = COALESCE(?, role), enabled = COALESCE(?, enabled), \
+         password_hash = COALESCE(?, password_hash) WHERE id = ?",
+        vec![
+            update.role.map(|r| r.as_str().to_string()).into(),
+            update.enabled.map(|e| e as i32).into(),
+            hash.into(),
+            id.into(),
+        ],
+    ))
+    .await
+    .map_err(db_err)?;
+    if update.password.is_some() || update.enabled == Some(false) {
+        revoke_user_sessions(db, id).await?;
+    }
+    get_user(db, id).await
+}
+
+/// Delete an account and its sessions. Refuses to delete the last enabled admin.
+pub async fn delete_user(db: &DatabaseConnection, id: i64) -> Result<Option<User>> {
+    let Some(user) = get_user(db, id).await? else {
+        return Ok(None);
+    };
+    if user.role == Role::Admin && user.enabled && enabled_admins_other_than(db, id).await? == 0 {
+        return Err(Error::BadRequest("Cannot delete the last admin".to_string()));
+    }
+    revoke_user_sessions(db, id).await?;
+    db.execute(stmt("DELETE FROM APP_USERS WHERE id = ?", vec![id.into()]))
+        .await
+        .map_err(db_err)?;
+    Ok(Some(user))
+}
+
+/// Check credentials and issue a session. `None` for an unknown user, a
+/// wrong password or a disabled account — callers can't tell which.
+pub async fn login(db: &DatabaseConnection, username: &str, password: &str) -> Result<Option<Session>> {
+    let row = db
+        .query_one(stmt(
+            &format!("SELECT {}, password_hash FROM APP_USERS WHERE username = ?", USER_COLUMNS),
+            vec![username.trim().into()],
+        ))
+        .await
+        .map_err(db_err)?;
+    let Some(row) = row else {
+        return Ok(None);
+    };
+    let user = user_from_row(&row);
+    let hash: String = row.try_get("", "password_hash").unwrap_or_default();
+    if !user.enabled || !verify_password(password, &hash).await {
+        return Ok(None);
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    db.execute(stmt("DELETE FROM APP_SESSIONS WHERE expires_at <= ?", vec![now.into()]))
+        .await
+        .map_err(db_err)?;
+    let token = new_token();
+    let expires_at = now + SESSION_TTL_SECS;
+    db.execute(stmt(
+        "INSERT INTO APP_SESSIONS (token_hash, user_id, created_at, expires_at) VALUES (?, ?, ?, ?)",
+        vec![hash_token(&token).into(), user.id.into(), now.into(), expires_at.into()],
+    ))
+    .await
+    .map_err(db_err)?;
+    db.execute(stmt("UPDATE APP_USERS SET last_login_at = datetime('now') WHERE id = ?", vec![user.id.into()]))
+        .await
+        .map_err(db_err)?;
+
+    Ok(Some(Session { token, expires_at, user }))
+}
+
+/// The enabled user behind an unexpired session token.
+pub async fn session_user(db: &DatabaseConnection, token: &str) -> Result<Option<User>> {
+    let row = db
+        .query_one(stmt(
+            "SELECT u.id, u.username, u.role, u.enabled, u.created_at, u.last_login_at \
+             FROM APP_SESSIONS s JOIN APP_USERS u ON u.id = s.user_id \
+             WHERE s.token_hash = ? AND s.expires_at > ? AND u.enabled = 1",
+            vec![hash_token(token).into(), chrono::Utc::now().timestamp().into()],
+        ))
+        .await;
+    match row {
+        Ok(row) => Ok(row.as_ref().map(user_from_row)),
+        Err(e) if e.to_string().contains("no such table") => Ok(None),
+        Err(e) => Err(db_err(e)),
+    }
+}
+
+pub async fn revoke_session(db: &DatabaseConnection, token: &str) -> Result<()> {
+    db.execute(stmt("DELETE FROM APP_SESSIONS WHERE token_hash = ?", vec![hash_token(token).into()]))
+        .await
+        .map_err(db_err)?;
+    Ok(())
+}
+
+pub async fn revoke_user_sessions(db: &DatabaseConnection, user_id: i64) -> Result<()> {
+    db.execute(stmt("DELETE FROM APP_SESSIONS WHERE user_id = ?", vec![user_id.into()]))
+        .await
+        .map_err(db_err)?;
+    Ok(())
+}
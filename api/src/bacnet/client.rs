@@ -0,0 +1,210 @@
+//! Async BACnet/IP client over a single UDP socket.
+//!
+//! Confirmed requests are sent one at a time: the client holds a lock for the
+//! whole request/reply exchange, so replies can be matched on invoke ID and
+//! source address without a dispatcher task. Lost datagrams are retried
+//! `retries` times before the request times out.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Duration;
+
+use tokio::net::UdpSocket;
+use tokio::sync::Mutex;
+use tokio::time::{timeout_at, Instant};
+
+use super::codec::{
+    self, service, Apdu, ErrorCode, IAm, ObjectId, PropertyRef, ReadAccessResult, ReadAccessSpec, Value,
+    WriteRequest,
+};
+
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(3);
+pub const DEFAULT_RETRIES: u32 = 2;
+
+#[derive(Debug, thiserror::Error)]
+pub enum BacnetError {
+    #[error("BACnet I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("BACnet request to {0} timed out")]
+    Timeout(SocketAddr),
+
+    #[error("BACnet protocol error: {0}")]
+    Protocol(String),
+
+    #[error("BACnet device returned {0}")]
+    Device(ErrorCode),
+
+    #[error("BACnet request rejected (reason {0})")]
+    Reject(u8),
+
+    #[error("BACnet request aborted (reason {0})")]
+    Abort(u8),
+}
+
+impl From<String> for BacnetError {
+    fn from(e: String) -> Self {
+        BacnetError::Protocol(e)
+    }
+}
+
+pub struct BacnetClient {
+    socket: UdpSocket,
+    /// Serialises confirmed requests and hands out invoke IDs.
+    next_invoke_id: Mutex<u8>,
+    timeout: Duration,
+    retries: u32,
+}
+
+impl BacnetClient {
+    /// Bind a client socket. Use port 0 for an ephemeral port — devices reply
+    /// to the source port, so 47808 is only needed when sharing a host with
+    /// devices that insist on it.
+    pub async fn bind(addr: SocketAddr) -> Result<Self, BacnetError> {
+        let socket = UdpSocket::bind(addr).await?;
+        socket.set_broadcast(true)?;
+        Ok(Self {
+            socket,
+            next_invoke_id: Mutex::new(0),
+            timeout: DEFAULT_TIMEOUT,
+            retries: DEFAULT_RETRIES,
+        })
+    }
+
+    /// Bind to an ephemeral port on all interfaces.
+    pub async fn ephemeral() -> Result<Self, BacnetError> {
+        Self::bind(SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0)).await
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration, retries: u32) -> Self {
+        self.timeout = timeout;
+        self.retries = retries;
+        self
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr, BacnetError> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    /// Send Who-Is to `target` (a device or a broadcast address) and collect
+    /// I-Am replies until `wait` elapses.
+    pub async fn who_is(
+        &self,
+        target: SocketAddr,
+        range: Option<(u32, u32)>,
+        wait: Duration,
+    ) -> Result<Vec<(SocketAddr, IAm)>, BacnetError> {
+        let function = match target.ip() {
+            IpAddr::V4(ip) if ip.is_broadcast() || ip.octets()[3] == 255 => codec::BVLC_ORIGINAL_BROADCAST,
+            _ => codec::BVLC_ORIGINAL_UNICAST,
+        };
+        let apdu = codec::unconfirmed_request(service::WHO_IS, &codec::encode_who_is(range));
+        // Hold the lock so a concurrent confirmed request doesn't swallow I-Am replies.
+        let _guard = self.next_invoke_id.lock().await;
+        self.socket.send_to(&codec::packet(function, false, &apdu), target).await?;
+
+        let deadline = Instant::now() + wait;
+        let mut found: Vec<(SocketAddr, IAm)> = Vec::new();
+        let mut buf = [0u8; 1500];
+        while let Ok(received) = timeout_at(deadline, self.socket.recv_from(&mut buf)).await {
+            let (len, from) = received?;
+            let Ok(Apdu::UnconfirmedRequest { service: service::I_AM, body }) =
+                codec::decode_packet(&buf[..len]).and_then(codec::decode_apdu)
+            else {
+                continue;
+            };
+            if let Ok(i_am) = codec::decode_i_am(body) {
+                let in_range = range.is_none_or(|(lo, hi)| (lo..=hi).contains(&i_am.device.instance));
+                if in_range && !found.iter().any(|(_, seen)| seen.device == i_am.device) {
+                    found.push((from, i_am));
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    /// ReadProperty — returns the property's value list (one element for scalars).
+    pub async fn read_property(
+        &self,
+        device: SocketAddr,
+        object: ObjectId,
+        property: PropertyRef,
+    ) -> Result<Vec<Value>, BacnetError> {
+        let body = codec::encode_read_property(object, &property);
+        let reply = self.confirmed(device, service::READ_PROPERTY, &body).await?;
+        let (_, _, values) = codec::decode_read_property_ack(&reply)?;
+        Ok(values)
+    }
+
+    /// ReadPropertyMultiple. Per-property errors come back inside the results;
+    /// only a request-level failure is an `Err`.
+    pub async fn read_property_multiple(
+        &self,
+        device: SocketAddr,
+        specs: &[ReadAccessSpec],
+    ) -> Result<Vec<ReadAccessResult>, BacnetError> {
+        let body = codec::encode_read_property_multiple(specs);
+        let reply = self.confirmed(device, service::READ_PROPERTY_MULTIPLE, &body).await?;
+        Ok(codec::decode_read_property_multiple_ack(&reply)?)
+    }
+
+    /// WriteProperty. `priority` (1–16) only applies to commandable properties;
+    /// devices ignore it elsewhere.
+    pub async fn write_property(
+        &self,
+        device: SocketAddr,
+        object: ObjectId,
+        property: PropertyRef,
+        value: Value,
+        priority: Option<u8>,
+    ) -> Result<(), BacnetError> {
+        if let Some(p) = priority {
+            if !(1..=16).contains(&p) {
+                return Err(BacnetError::Protocol(format!("priority {} is outside 1-16", p)));
+            }
+        }
+        let req = WriteRequest { object, property, value, priority };
+        self.confirmed(device, service::WRITE_PROPERTY, &codec::encode_write_property(&req))
+            .await
+            .map(|_| ())
+    }
+
+    /// Send a confirmed request and wait for its reply. Returns the ComplexACK
+    /// body (empty for a SimpleACK).
+    async fn confirmed(&self, device: SocketAddr, service: u8, body: &[u8]) -> Result<Vec<u8>, BacnetError> {
+        let mut next = self.next_invoke_id.lock().await;
+        let invoke_id = *next;
+        *next = next.wrapping_add(1);
+
+        let frame = codec::packet(
+            codec::BVLC_ORIGINAL_UNICAST,
+            true,
+            &codec::confirmed_request(invoke_id, service, body),
+        );
+        let mut buf = [0u8; 1500];
+        for _ in 0..=self.retries {
+            self.socket.send_to(&frame, device).await?;
+            let deadline = Instant::now() + self.timeout;
+            while let Ok(received) = timeout_at(deadline, self.socket.recv_from(&mut buf)).await {
+                let (len, from) = received?;
+                if from != device {
+                    continue;
+                }
+                let Ok(apdu) = codec::decode_packet(&buf[..len]).and_then(codec::decode_apdu) else {
+                    continue;
+                };
+                if apdu.reply_invoke_id() != Some(invoke_id) {
+                    continue;
+                }
+                return match apdu {
+                    Apdu::SimpleAck { .. } => Ok(Vec::new()),
+                    Apdu::ComplexAck { body, .. } => Ok(body.to_vec()),
+                    Apdu::Error { error, .. } => Err(BacnetError::Device(error)),
+                    Apdu::Reject { reason, .. } => Err(BacnetError::Reject(reason)),
+                    Apdu::Abort { reason, .. } => Err(BacnetError::Abort(reason)),
+                    _ => unreachable!("reply_invoke_id only matches replies"),
+                };
+            }
+        }
+        Err(BacnetError::Timeout(device))
+    }
+}
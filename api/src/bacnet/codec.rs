@@ -0,0 +1,1020 @@
+//! BACnet/IP wire format — BVLC, NPDU and APDU headers plus the tag encoding
+//! used by the services the client speaks.
+//!
+//! Only Who-Is / I-Am, ReadProperty, ReadPropertyMultiple and WriteProperty
+//! are covered, and only unsegmented. A segmented message is reported as an
+//! error instead of being reassembled — every T3000 point read fits in one
+//! 1476-byte APDU.
+
+use std::fmt;
+
+use serde::Serialize;
+
+/// BVLC type byte for BACnet/IP (Annex J).
+pub const BVLC_TYPE: u8 = 0x81;
+pub const BVLC_FORWARDED_NPDU: u8 = 0x04;
+pub const BVLC_ORIGINAL_UNICAST: u8 = 0x0A;
+pub const BVLC_ORIGINAL_BROADCAST: u8 = 0x0B;
+
+/// Default BACnet/IP UDP port (0xBAC0).
+pub const DEFAULT_PORT: u16 = 47808;
+
+/// Largest APDU on BACnet/IP; advertised in every confirmed request.
+pub const MAX_APDU: usize = 1476;
+
+/// Object types used by T3000 panels.
+pub mod object_type {
+    pub const ANALOG_INPUT: u16 = 0;
+    pub const ANALOG_OUTPUT: u16 = 1;
+    pub const ANALOG_VALUE: u16 = 2;
+    pub const BINARY_INPUT: u16 = 3;
+    pub const BINARY_OUTPUT: u16 = 4;
+    pub const BINARY_VALUE: u16 = 5;
+    pub const DEVICE: u16 = 8;
+}
+
+/// Property identifiers.
+pub mod property {
+    pub const DESCRIPTION: u32 = 28;
+    pub const MAX_APDU_LENGTH_ACCEPTED: u32 = 62;
+    pub const OBJECT_IDENTIFIER: u32 = 75;
+    pub const OBJECT_LIST: u32 = 76;
+    pub const OBJECT_NAME: u32 = 77;
+    pub const OBJECT_TYPE: u32 = 79;
+    pub const OUT_OF_SERVICE: u32 = 81;
+    pub const PRESENT_VALUE: u32 = 85;
+    pub const PRIORITY_ARRAY: u32 = 87;
+    pub const RELINQUISH_DEFAULT: u32 = 104;
+    pub const SEGMENTATION_SUPPORTED: u32 = 107;
+    pub const STATUS_FLAGS: u32 = 111;
+    pub const UNITS: u32 = 117;
+    pub const VENDOR_IDENTIFIER: u32 = 120;
+}
+
+/// Service choices.
+pub mod service {
+    pub const READ_PROPERTY: u8 = 12;
+    pub const READ_PROPERTY_MULTIPLE: u8 = 14;
+    pub const WRITE_PROPERTY: u8 = 15;
+
+    pub const I_AM: u8 = 0;
+    pub const WHO_IS: u8 = 8;
+}
+
+/// Error classes and codes returned in Error PDUs.
+pub mod error_code {
+    pub const CLASS_OBJECT: u32 = 1;
+    pub const CLASS_PROPERTY: u32 = 2;
+    pub const CLASS_SERVICES: u32 = 5;
+
+    pub const INVALID_DATA_TYPE: u32 = 9;
+    pub const UNKNOWN_OBJECT: u32 = 31;
+    pub const UNKNOWN_PROPERTY: u32 = 32;
+    pub const VALUE_OUT_OF_RANGE: u32 = 37;
+    pub const WRITE_ACCESS_DENIED: u32 = 40;
+    pub const INVALID_ARRAY_INDEX: u32 = 42;
+    pub const PROPERTY_IS_NOT_AN_ARRAY: u32 = 50;
+}
+
+/// Segmentation-supported enumeration value for "no segmentation".
+pub const NO_SEGMENTATION: u32 = 3;
+
+/// Reject reason for a confirmed service the device does not implement.
+pub const REJECT_UNRECOGNIZED_SERVICE: u8 = 9;
+
+/// A BACnet object identifier (10-bit type, 22-bit instance).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub struct ObjectId {
+    pub object_type: u16,
+    pub instance: u32,
+}
+
+impl ObjectId {
+    pub const MAX_INSTANCE: u32 = 0x3F_FFFF;
+
+    pub fn new(object_type: u16, instance: u32) -> Self {
+        Self { object_type, instance }
+    }
+
+    fn to_u32(self) -> u32 {
+        ((self.object_type as u32 & 0x3FF) << 22) | (self.instance & Self::MAX_INSTANCE)
+    }
+
+    fn from_u32(raw: u32) -> Self {
+        Self {
+            object_type: (raw >> 22) as u16,
+            instance: raw & Self::MAX_INSTANCE,
+        }
+    }
+}
+
+impl fmt::Display for ObjectId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.object_type, self.instance)
+    }
+}
+
+/// An application-tagged primitive value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Boolean(bool),
+    Unsigned(u32),
+    Signed(i32),
+    Real(f32),
+    Double(f64),
+    OctetString(Vec<u8>),
+    CharacterString(String),
+    BitString { unused_bits: u8, bytes: Vec<u8> },
+    Enumerated(u32),
+    Date([u8; 4]),
+    Time([u8; 4]),
+    ObjectId(ObjectId),
+}
+
+impl Value {
+    /// Numeric view of the value (booleans as 0/1), `None` for non-numeric types.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Boolean(b) => Some(if *b { 1.0 } else { 0.0 }),
+            Value::Unsigned(v) | Value::Enumerated(v) => Some(*v as f64),
+            Value::Signed(v) => Some(*v as f64),
+            Value::Real(v) => Some(*v as f64),
+            Value::Double(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::CharacterString(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Boolean(b) => Some(*b),
+            Value::Enumerated(v) | Value::Unsigned(v) => Some(*v != 0),
+            _ => None,
+        }
+    }
+}
+
+/// BACnet error class/code pair.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorCode {
+    pub class: u32,
+    pub code: u32,
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "error class {} code {}", self.class, self.code)
+    }
+}
+
+/// Property reference (identifier plus optional array index).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PropertyRef {
+    pub property: u32,
+    pub array_index: Option<u32>,
+}
+
+impl PropertyRef {
+    pub fn new(property: u32) -> Self {
+        Self { property, array_index: None }
+    }
+
+    pub fn at(property: u32, index: u32) -> Self {
+        Self { property, array_index: Some(index) }
+    }
+}
+
+// ─── Encoding ───────────────────────────────────────────────────────────────
+
+fn unsigned_bytes(v: u32) -> Vec<u8> {
+    let bytes = v.to_be_bytes();
+    let skip = bytes.iter().take(3).take_while(|b| **b == 0).count();
+    bytes[skip..].to_vec()
+}
+
+fn signed_bytes(v: i32) -> Vec<u8> {
+    let bytes = v.to_be_bytes();
+    let mut start = 0;
+    // Drop redundant sign bytes while keeping the sign bit of the next byte.
+    while start < 3 {
+        let (b, next) = (bytes[start], bytes[start + 1]);
+        if (b == 0x00 && next & 0x80 == 0) || (b == 0xFF && next & 0x80 != 0) {
+            start += 1;
+        } else {
+            break;
+        }
+    }
+    bytes[start..].to_vec()
+}
+
+fn encode_tag(buf: &mut Vec<u8>, number: u8, context: bool, len: u32) {
+    let class = if context { 0x08 } else { 0x00 };
+    let extended = number >= 15;
+    let number_bits = if extended { 0xF0 } else { number << 4 };
+    if len <= 4 {
+        buf.push(number_bits | class | len as u8);
+        if extended {
+            buf.push(number);
+        }
+    } else {
+        buf.push(number_bits | class | 5);
+        if extended {
+            buf.push(number);
+        }
+        if len <= 253 {
+            buf.push(len as u8);
+        } else if len <= u16::MAX as u32 {
+            buf.push(254);
+            buf.extend_from_slice(&(len as u16).to_be_bytes());
+        } else {
+            buf.push(255);
+            buf.extend_from_slice(&len.to_be_bytes());
+        }
+    }
+}
+
+fn encode_tagged(buf: &mut Vec<u8>, number: u8, context: bool, content: &[u8]) {
+    encode_tag(buf, number, context, content.len() as u32);
+    buf.extend_from_slice(content);
+}
+
+/// Append an application-tagged value.
+pub fn encode_app_value(buf: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::Null => encode_tag(buf, 0, false, 0),
+        Value::Boolean(b) => encode_tag(buf, 1, false, *b as u32),
+        Value::Unsigned(v) => encode_tagged(buf, 2, false, &unsigned_bytes(*v)),
+        Value::Signed(v) => encode_tagged(buf, 3, false, &signed_bytes(*v)),
+        Value::Real(v) => encode_tagged(buf, 4, false, &v.to_be_bytes()),
+        Value::Double(v) => encode_tagged(buf, 5, false, &v.to_be_bytes()),
+        Value::OctetString(bytes) => encode_tagged(buf, 6, false, bytes),
+        Value::CharacterString(s) => {
+            // Character set 0 = UTF-8 (ANSI X3.4 superset).
+            let mut content = Vec::with_capacity(s.len() + 1);
+            content.push(0);
+            content.extend_from_slice(s.as_bytes());
+            encode_tagged(buf, 7, false, &content);
+        }
+        Value::BitString { unused_bits, bytes } => {
+            let mut content = Vec::with_capacity(bytes.len() + 1);
+            content.push(*unused_bits);
+            content.extend_from_slice(bytes);
+            encode_tagged(buf, 8, false, &content);
+        }
+        Value::Enumerated(v) => encode_tagged(buf, 9, false, &unsigned_bytes(*v)),
+        Value::Date(d) => encode_tagged(buf, 10, false, d),
+        Value::Time(t) => encode_tagged(buf, 11, false, t),
+        Value::ObjectId(id) => encode_tagged(buf, 12, false, &id.to_u32().to_be_bytes()),
+    }
+}
+
+pub fn encode_context_unsigned(buf: &mut Vec<u8>, tag: u8, v: u32) {
+    encode_tagged(buf, tag, true, &unsigned_bytes(v));
+}
+
+pub fn encode_context_object_id(buf: &mut Vec<u8>, tag: u8, id: ObjectId) {
+    encode_tagged(buf, tag, true, &id.to_u32().to_be_bytes());
+}
+
+pub fn encode_opening(buf: &mut Vec<u8>, tag: u8) {
+    encode_bracket(buf, tag, 6);
+}
+
+pub fn encode_closing(buf: &mut Vec<u8>, tag: u8) {
+    encode_bracket(buf, tag, 7);
+}
+
+fn encode_bracket(buf: &mut Vec<u8>, tag: u8, lvt: u8) {
+    if tag >= 15 {
+        buf.push(0xF8 | lvt);
+        buf.push(tag);
+    } else {
+        buf.push((tag << 4) | 0x08 | lvt);
+    }
+}
+
+// ─── Decoding ───────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagKind {
+    /// Content length — or, for the application Boolean tag, the value itself.
+    Length(u32),
+    Opening,
+    Closing,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tag {
+    pub number: u8,
+    pub context: bool,
+    pub kind: TagKind,
+}
+
+impl Tag {
+    fn is_opening(&self, number: u8) -> bool {
+        self.context && self.number == number && self.kind == TagKind::Opening
+    }
+
+    fn is_closing(&self, number: u8) -> bool {
+        self.context && self.number == number && self.kind == TagKind::Closing
+    }
+}
+
+/// Cursor over tagged APDU content.
+pub struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pos >= self.buf.len()
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        let b = *self.buf.get(self.pos).ok_or("truncated APDU")?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        let end = self.pos.checked_add(n).filter(|end| *end <= self.buf.len()).ok_or("truncated APDU")?;
+        let slice = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    pub fn read_tag(&mut self) -> Result<Tag, String> {
+        let first = self.byte()?;
+        let mut number = first >> 4;
+        if number == 15 {
+            number = self.byte()?;
+        }
+        let context = first & 0x08 != 0;
+        let lvt = first & 0x07;
+        let kind = match lvt {
+            6 if context => TagKind::Opening,
+            7 if context => TagKind::Closing,
+            5 => {
+                let ext = self.byte()?;
+                let len = match ext {
+                    254 => u16::from_be_bytes([self.byte()?, self.byte()?]) as u32,
+                    255 => {
+                        let b = self.take(4)?;
+                        u32::from_be_bytes([b[0], b[1], b[2], b[3]])
+                    }
+                    n => n as u32,
+                };
+                TagKind::Length(len)
+            }
+            n => TagKind::Length(n as u32),
+        };
+        Ok(Tag { number, context, kind })
+    }
+
+    pub fn peek_tag(&mut self) -> Result<Option<Tag>, String> {
+        if self.is_empty() {
+            return Ok(None);
+        }
+        let pos = self.pos;
+        let tag = self.read_tag();
+        self.pos = pos;
+        tag.map(Some)
+    }
+
+    fn content(&mut self, tag: &Tag) -> Result<&'a [u8], String> {
+        match tag.kind {
+            TagKind::Length(len) => self.take(len as usize),
+            _ => Err(format!("expected primitive data, found constructed tag {}", tag.number)),
+        }
+    }
+
+    pub fn read_app_value(&mut self) -> Result<Value, String> {
+        let tag = self.read_tag()?;
+        if tag.context {
+            return Err(format!("expected application tag, found context tag {}", tag.number));
+        }
+        if tag.number == 1 {
+            return match tag.kind {
+                TagKind::Length(v) => Ok(Value::Boolean(v != 0)),
+                _ => Err("malformed boolean".into()),
+            };
+        }
+        let data = self.content(&tag)?;
+        Ok(match tag.number {
+            0 => Value::Null,
+            2 => Value::Unsigned(decode_unsigned(data)?),
+            3 => Value::Signed(decode_signed(data)?),
+            4 => Value::Real(f32::from_be_bytes(fixed(data)?)),
+            5 => Value::Double(f64::from_be_bytes(fixed(data)?)),
+            6 => Value::OctetString(data.to_vec()),
+            7 => {
+                let (charset, text) = data.split_first().ok_or("empty character string")?;
+                if *charset != 0 {
+                    return Err(format!("unsupported character set {}", charset));
+                }
+                Value::CharacterString(String::from_utf8_lossy(text).into_owned())
+            }
+            8 => {
+                let (unused, bytes) = data.split_first().ok_or("empty bit string")?;
+                Value::BitString { unused_bits: *unused, bytes: bytes.to_vec() }
+            }
+            9 => Value::Enumerated(decode_unsigned(data)?),
+            10 => Value::Date(fixed(data)?),
+            11 => Value::Time(fixed(data)?),
+            12 => Value::ObjectId(ObjectId::from_u32(u32::from_be_bytes(fixed(data)?))),
+            n => return Err(format!("unsupported application tag {}", n)),
+        })
+    }
+
+    fn expect_context(&mut self, number: u8) -> Result<&'a [u8], String> {
+        let tag = self.read_tag()?;
+        if !tag.context || tag.number != number {
+            return Err(format!("expected context tag {}, found tag {}", number, tag.number));
+        }
+        self.content(&tag)
+    }
+
+    pub fn read_context_unsigned(&mut self, number: u8) -> Result<u32, String> {
+        decode_unsigned(self.expect_context(number)?)
+    }
+
+    pub fn read_context_object_id(&mut self, number: u8) -> Result<ObjectId, String> {
+        Ok(ObjectId::from_u32(u32::from_be_bytes(fixed(self.expect_context(number)?)?)))
+    }
+
+    /// Read context tag `number` if it is next, otherwise leave the cursor alone.
+    pub fn read_optional_context_unsigned(&mut self, number: u8) -> Result<Option<u32>, String> {
+        match self.peek_tag()? {
+            Some(tag) if tag.context && tag.number == number && matches!(tag.kind, TagKind::Length(_)) => {
+                self.read_context_unsigned(number).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    pub fn at_opening(&mut self, number: u8) -> Result<bool, String> {
+        Ok(self.peek_tag()?.is_some_and(|t| t.is_opening(number)))
+    }
+
+    pub fn at_closing(&mut self, number: u8) -> Result<bool, String> {
+        Ok(self.peek_tag()?.is_some_and(|t| t.is_closing(number)))
+    }
+
+    pub fn expect_opening(&mut self, number: u8) -> Result<(), String> {
+        let tag = self.read_tag()?;
+        if tag.is_opening(number) {
+            Ok(())
+        } else {
+            Err(format!("expected opening tag {}", number))
+        }
+    }
+
+    pub fn expect_closing(&mut self, number: u8) -> Result<(), String> {
+        let tag = self.read_tag()?;
+        if tag.is_closing(number) {
+            Ok(())
+        } else {
+            Err(format!("expected closing tag {}", number))
+        }
+    }
+
+    /// Read application values up to and including closing tag `number`.
+    /// Context-tagged (constructed) data inside is skipped — none of the
+    /// properties the client reads use it.
+    pub fn read_values_until_closing(&mut self, number: u8) -> Result<Vec<Value>, String> {
+        let mut values = Vec::new();
+        loop {
+            let tag = self.peek_tag()?.ok_or_else(|| format!("missing closing tag {}", number))?;
+            if tag.is_closing(number) {
+                self.read_tag()?;
+                return Ok(values);
+            }
+            if tag.context {
+                self.skip_element()?;
+            } else {
+                values.push(self.read_app_value()?);
+            }
+        }
+    }
+
+    fn skip_element(&mut self) -> Result<(), String> {
+        let tag = self.read_tag()?;
+        match tag.kind {
+            TagKind::Length(_) if !tag.context && tag.number == 1 => Ok(()),
+            TagKind::Length(len) => self.take(len as usize).map(|_| ()),
+            TagKind::Opening => {
+                while !self.at_closing(tag.number)? {
+                    self.skip_element()?;
+                }
+                self.read_tag().map(|_| ())
+            }
+            TagKind::Closing => Err(format!("unexpected closing tag {}", tag.number)),
+        }
+    }
+}
+
+fn fixed<const N: usize>(data: &[u8]) -> Result<[u8; N], String> {
+    data.try_into().map_err(|_| format!("expected {} bytes, found {}", N, data.len()))
+}
+
+fn decode_unsigned(data: &[u8]) -> Result<u32, String> {
+    if data.is_empty() || data.len() > 4 {
+        return Err(format!("invalid unsigned length {}", data.len()));
+    }
+    Ok(data.iter().fold(0u32, |acc, b| (acc << 8) | *b as u32))
+}
+
+fn decode_signed(data: &[u8]) -> Result<i32, String> {
+    if data.is_empty() || data.len() > 4 {
+        return Err(format!("invalid signed length {}", data.len()));
+    }
+    let fill = if data[0] & 0x80 != 0 { 0xFF } else { 0x00 };
+    let mut bytes = [fill; 4];
+    bytes[4 - data.len()..].copy_from_slice(data);
+    Ok(i32::from_be_bytes(bytes))
+}
+
+// ─── BVLC / NPDU ────────────────────────────────────────────────────────────
+
+/// Wrap an APDU in NPDU + BVLC headers, ready to send.
+pub fn packet(function: u8, expecting_reply: bool, apdu: &[u8]) -> Vec<u8> {
+    let len = 4 + 2 + apdu.len();
+    let mut buf = Vec::with_capacity(len);
+    buf.push(BVLC_TYPE);
+    buf.push(function);
+    buf.extend_from_slice(&(len as u16).to_be_bytes());
+    buf.push(0x01); // NPDU version
+    buf.push(if expecting_reply { 0x04 } else { 0x00 });
+    buf.extend_from_slice(apdu);
+    buf
+}
+
+/// Strip BVLC and NPDU headers and return the APDU. Network-layer messages
+/// and non-BACnet/IP datagrams are errors.
+pub fn decode_packet(buf: &[u8]) -> Result<&[u8], String> {
+    if buf.len() < 6 || buf[0] != BVLC_TYPE {
+        return Err("not a BACnet/IP datagram".into());
+    }
+    let declared = u16::from_be_bytes([buf[2], buf[3]]) as usize;
+    if declared != buf.len() {
+        return Err(format!("BVLC length {} does not match datagram length {}", declared, buf.len()));
+    }
+    let mut pos = match buf[1] {
+        BVLC_ORIGINAL_UNICAST | BVLC_ORIGINAL_BROADCAST => 4,
+        BVLC_FORWARDED_NPDU => 10, // + originating B/IP address
+        f => return Err(format!("unsupported BVLC function 0x{:02X}", f)),
+    };
+    let npdu = buf.get(pos..pos + 2).ok_or("truncated NPDU")?;
+    if npdu[0] != 0x01 {
+        return Err(format!("unsupported NPDU version {}", npdu[0]));
+    }
+    let control = npdu[1];
+    pos += 2;
+    if control & 0x80 != 0 {
+        return Err("network layer message".into());
+    }
+    let skip_address = |pos: usize| -> Result<usize, String> {
+        // NET (2) + LEN (1) + ADR (LEN)
+        let len = *buf.get(pos + 2).ok_or("truncated NPDU address")? as usize;
+        Ok(pos + 3 + len)
+    };
+    if control & 0x20 != 0 {
+        pos = skip_address(pos)?;
+    }
+    if control & 0x08 != 0 {
+        pos = skip_address(pos)?;
+    }
+    if control & 0x20 != 0 {
+        pos += 1; // hop count
+    }
+    buf.get(pos..).ok_or_else(|| "truncated NPDU".into())
+}
+
+// ─── APDU ───────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Apdu<'a> {
+    ConfirmedRequest { invoke_id: u8, service: u8, body: &'a [u8] },
+    UnconfirmedRequest { service: u8, body: &'a [u8] },
+    SimpleAck { invoke_id: u8, service: u8 },
+    ComplexAck { invoke_id: u8, service: u8, body: &'a [u8] },
+    Error { invoke_id: u8, service: u8, error: ErrorCode },
+    Reject { invoke_id: u8, reason: u8 },
+    Abort { invoke_id: u8, reason: u8 },
+}
+
+impl Apdu<'_> {
+    /// Invoke ID of a reply to a confirmed request.
+    pub fn reply_invoke_id(&self) -> Option<u8> {
+        match self {
+            Apdu::SimpleAck { invoke_id, .. }
+            | Apdu::ComplexAck { invoke_id, .. }
+            | Apdu::Error { invoke_id, .. }
+            | Apdu::Reject { invoke_id, .. }
+            | Apdu::Abort { invoke_id, .. } => Some(*invoke_id),
+            _ => None,
+        }
+    }
+}
+
+pub fn decode_apdu(buf: &[u8]) -> Result<Apdu<'_>, String> {
+    let at = |i: usize| buf.get(i).copied().ok_or_else(|| "truncated APDU".to_string());
+    let first = at(0)?;
+    match first >> 4 {
+        0 => {
+            if first & 0x08 != 0 {
+                return Err("segmented requests are not supported".into());
+            }
+            Ok(Apdu::ConfirmedRequest { invoke_id: at(2)?, service: at(3)?, body: &buf[4..] })
+        }
+        1 => Ok(Apdu::UnconfirmedRequest { service: at(1)?, body: &buf[2..] }),
+        2 => Ok(Apdu::SimpleAck { invoke_id: at(1)?, service: at(2)? }),
+        3 => {
+            if first & 0x08 != 0 {
+                return Err("segmented responses are not supported".into());
+            }
+            Ok(Apdu::ComplexAck { invoke_id: at(1)?, service: at(2)?, body: &buf[3..] })
+        }
+        5 => {
+            let (invoke_id, service) = (at(1)?, at(2)?);
+            let mut r = Reader::new(&buf[3..]);
+            let class = r.read_app_value()?.as_f64().ok_or("malformed error class")? as u32;
+            let code = r.read_app_value()?.as_f64().ok_or("malformed error code")? as u32;
+            Ok(Apdu::Error { invoke_id, service, error: ErrorCode { class, code } })
+        }
+        6 => Ok(Apdu::Reject { invoke_id: at(1)?, reason: at(2)? }),
+        7 => Ok(Apdu::Abort { invoke_id: at(1)?, reason: at(2)? }),
+        t => Err(format!("unsupported PDU type {}", t)),
+    }
+}
+
+pub fn confirmed_request(invoke_id: u8, service: u8, body: &[u8]) -> Vec<u8> {
+    // 0x05 = unsegmented reply accepted, max APDU 1476
+    let mut apdu = vec![0x00, 0x05, invoke_id, service];
+    apdu.extend_from_slice(body);
+    apdu
+}
+
+pub fn unconfirmed_request(service: u8, body: &[u8]) -> Vec<u8> {
+    let mut apdu = vec![0x10, service];
+    apdu.extend_from_slice(body);
+    apdu
+}
+
+pub fn simple_ack(invoke_id: u8, service: u8) -> Vec<u8> {
+    vec![0x20, invoke_id, service]
+}
+
+pub fn complex_ack(invoke_id: u8, service: u8, body: &[u8]) -> Vec<u8> {
+    let mut apdu = vec![0x30, invoke_id, service];
+    apdu.extend_from_slice(body);
+    apdu
+}
+
+pub fn reject_pdu(invoke_id: u8, reason: u8) -> Vec<u8> {
+    vec![0x60, invoke_id, reason]
+}
+
+pub fn error_pdu(invoke_id: u8, service: u8, error: ErrorCode) -> Vec<u8> {
+    let mut apdu = vec![0x50, invoke_id, service];
+    encode_app_value(&mut apdu, &Value::Enumerated(error.class));
+    encode_app_value(&mut apdu, &Value::Enumerated(error.code));
+    apdu
+}
+
+// ─── Services ───────────────────────────────────────────────────────────────
+
+/// Who-Is body, optionally limited to a device instance range.
+pub fn encode_who_is(range: Option<(u32, u32)>) -> Vec<u8> {
+    let mut body = Vec::new();
+    if let Some((low, high)) = range {
+        encode_context_unsigned(&mut body, 0, low);
+        encode_context_unsigned(&mut body, 1, high);
+    }
+    body
+}
+
+pub fn decode_who_is(body: &[u8]) -> Result<Option<(u32, u32)>, String> {
+    if body.is_empty() {
+        return Ok(None);
+    }
+    let mut r = Reader::new(body);
+    Ok(Some((r.read_context_unsigned(0)?, r.read_context_unsigned(1)?)))
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IAm {
+    pub device: ObjectId,
+    pub max_apdu: u32,
+    pub segmentation: u32,
+    pub vendor_id: u32,
+}
+
+pub fn encode_i_am(i_am: &IAm) -> Vec<u8> {
+    let mut body = Vec::new();
+    encode_app_value(&mut body, &Value::ObjectId(i_am.device));
+    encode_app_value(&mut body, &Value::Unsigned(i_am.max_apdu));
+    encode_app_value(&mut body, &Value::Enumerated(i_am.segmentation));
+    encode_app_value(&mut body, &Value::Unsigned(i_am.vendor_id));
+    body
+}
+
+pub fn decode_i_am(body: &[u8]) -> Result<IAm, String> {
+    let mut r = Reader::new(body);
+    let device = match r.read_app_value()? {
+        Value::ObjectId(id) => id,
+        _ => return Err("I-Am without device identifier".into()),
+    };
+    let mut number = || -> Result<u32, String> {
+        r.read_app_value()?.as_f64().map(|v| v as u32).ok_or_else(|| "malformed I-Am".to_string())
+    };
+    Ok(IAm {
+        device,
+        max_apdu: number()?,
+        segmentation: number()?,
+        vendor_id: number()?,
+    })
+}
+
+fn encode_property_ref(body: &mut Vec<u8>, prop: &PropertyRef, tag: u8) {
+    encode_context_unsigned(body, tag, prop.property);
+    if let Some(index) = prop.array_index {
+        encode_context_unsigned(body, tag + 1, index);
+    }
+}
+
+fn decode_property_ref(r: &mut Reader<'_>, tag: u8) -> Result<PropertyRef, String> {
+    Ok(PropertyRef {
+        property: r.read_context_unsigned(tag)?,
+        array_index: r.read_optional_context_unsigned(tag + 1)?,
+    })
+}
+
+pub fn encode_read_property(object: ObjectId, prop: &PropertyRef) -> Vec<u8> {
+    let mut body = Vec::new();
+    encode_context_object_id(&mut body, 0, object);
+    encode_property_ref(&mut body, prop, 1);
+    body
+}
+
+pub fn decode_read_property(body: &[u8]) -> Result<(ObjectId, PropertyRef), String> {
+    let mut r = Reader::new(body);
+    Ok((r.read_context_object_id(0)?, decode_property_ref(&mut r, 1)?))
+}
+
+pub fn encode_read_property_ack(object: ObjectId, prop: &PropertyRef, values: &[Value]) -> Vec<u8> {
+    let mut body = encode_read_property(object, prop);
+    encode_opening(&mut body, 3);
+    for v in values {
+        encode_app_value(&mut body, v);
+    }
+    encode_closing(&mut body, 3);
+    body
+}
+
+pub fn decode_read_property_ack(body: &[u8]) -> Result<(ObjectId, PropertyRef, Vec<Value>), String> {
+    let mut r = Reader::new(body);
+    let object = r.read_context_object_id(0)?;
+    let prop = decode_property_ref(&mut r, 1)?;
+    r.expect_opening(3)?;
+    Ok((object, prop, r.read_values_until_closing(3)?))
+}
+
+/// One object's worth of a ReadPropertyMultiple request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReadAccessSpec {
+    pub object: ObjectId,
+    pub properties: Vec<PropertyRef>,
+}
+
+pub fn encode_read_property_multiple(specs: &[ReadAccessSpec]) -> Vec<u8> {
+    let mut body = Vec::new();
+    for spec in specs {
+        encode_context_object_id(&mut body, 0, spec.object);
+        encode_opening(&mut body, 1);
+        for prop in &spec.properties {
+            encode_property_ref(&mut body, prop, 0);
+        }
+        encode_closing(&mut body, 1);
+    }
+    body
+}
+
+pub fn decode_read_property_multiple(body: &[u8]) -> Result<Vec<ReadAccessSpec>, String> {
+    let mut r = Reader::new(body);
+    let mut specs = Vec::new();
+    while !r.is_empty() {
+        let object = r.read_context_object_id(0)?;
+        r.expect_opening(1)?;
+        let mut properties = Vec::new();
+        while !r.at_closing(1)? {
+            properties.push(decode_property_ref(&mut r, 0)?);
+        }
+        r.expect_closing(1)?;
+        specs.push(ReadAccessSpec { object, properties });
+    }
+    Ok(specs)
+}
+
+/// Result of reading one property inside a ReadPropertyMultiple-ACK.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyResult {
+    pub property: PropertyRef,
+    pub value: Result<Vec<Value>, ErrorCode>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReadAccessResult {
+    pub object: ObjectId,
+    pub results: Vec<PropertyResult>,
+}
+
+impl ReadAccessResult {
+    /// First value of `property`, if it was read successfully.
+    pub fn value(&self, property: u32) -> Option<&Value> {
+        self.results
+            .iter()
+            .find(|r| r.property.property == property)
+            .and_then(|r| r.value.as_ref().ok())
+            .and_then(|v| v.first())
+    }
+}
+
+pub fn encode_read_property_multiple_ack(results: &[ReadAccessResult]) -> Vec<u8> {
+    let mut body = Vec::new();
+    for result in results {
+        encode_context_object_id(&mut body, 0, result.object);
+        encode_opening(&mut body, 1);
+        for r in &result.results {
+            encode_property_ref(&mut body, &r.property, 2);
+            match &r.value {
+                Ok(values) => {
+                    encode_opening(&mut body, 4);
+                    for v in values {
+                        encode_app_value(&mut body, v);
+                    }
+                    encode_closing(&mut body, 4);
+                }
+                Err(e) => {
+                    encode_opening(&mut body, 5);
+                    encode_app_value(&mut body, &Value::Enumerated(e.class));
+                    encode_app_value(&mut body, &Value::Enumerated(e.code));
+                    encode_closing(&mut body, 5);
+                }
+            }
+        }
+        encode_closing(&mut body, 1);
+    }
+    body
+}
+
+pub fn decode_read_property_multiple_ack(body: &[u8]) -> Result<Vec<ReadAccessResult>, String> {
+    let mut r = Reader::new(body);
+    let mut out = Vec::new();
+    while !r.is_empty() {
+        let object = r.read_context_object_id(0)?;
+        r.expect_opening(1)?;
+        let mut results = Vec::new();
+        while !r.at_closing(1)? {
+            let property = decode_property_ref(&mut r, 2)?;
+            let value = if r.at_opening(4)? {
+                r.expect_opening(4)?;
+                Ok(r.read_values_until_closing(4)?)
+            } else {
+                r.expect_opening(5)?;
+                let class = r.read_app_value()?.as_f64().ok_or("malformed error class")? as u32;
+                let code = r.read_app_value()?.as_f64().ok_or("malformed error code")? as u32;
+                r.expect_closing(5)?;
+                Err(ErrorCode { class, code })
+            };
+            results.push(PropertyResult { property, value });
+        }
+        r.expect_closing(1)?;
+        out.push(ReadAccessResult { object, results });
+    }
+    Ok(out)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WriteRequest {
+    pub object: ObjectId,
+    pub property: PropertyRef,
+    pub value: Value,
+    /// Command priority 1 (highest) – 16; `None` writes without a priority.
+    pub priority: Option<u8>,
+}
+
+pub fn encode_write_property(req: &WriteRequest) -> Vec<u8> {
+    let mut body = encode_read_property(req.object, &req.property);
+    encode_opening(&mut body, 3);
+    encode_app_value(&mut body, &req.value);
+    encode_closing(&mut body, 3);
+    if let Some(p) = req.priority {
+        encode_context_unsigned(&mut body, 4, p as u32);
+    }
+    body
+}
+
+pub fn decode_write_property(body: &[u8]) -> Result<WriteRequest, String> {
+    let mut r = Reader::new(body);
+    let object = r.read_context_object_id(0)?;
+    let property = decode_property_ref(&mut r, 1)?;
+    r.expect_opening(3)?;
+    let value = r.read_values_until_closing(3)?.into_iter().next().ok_or("WriteProperty without a value")?;
+    let priority = r.read_optional_context_unsigned(4)?.map(|p| p as u8);
+    Ok(WriteRequest { object, property, value, priority })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn application_values_round_trip() {
+        let values = [
+            Value::Null,
+            Value::Boolean(true),
+            Value::Unsigned(0),
+            Value::Unsigned(70000),
+            Value::Signed(-1),
+            Value::Signed(-129),
+            Value::Signed(128),
+            Value::Real(21.5),
+            Value::Double(-3.25),
+            Value::CharacterString("Supply Air Temperature".into()),
+            Value::Enumerated(62),
+            Value::ObjectId(ObjectId::new(object_type::DEVICE, 260001)),
+        ];
+        let mut buf = Vec::new();
+        for v in &values {
+            encode_app_value(&mut buf, v);
+        }
+        let mut r = Reader::new(&buf);
+        for v in &values {
+            assert_eq!(&r.read_app_value().unwrap(), v);
+        }
+        assert!(r.is_empty());
+    }
+
+    #[test]
+    fn read_property_request_matches_reference_bytes() {
+        // ReadProperty(analog-input:1, present-value) as sent by common BACnet stacks.
+        let apdu = confirmed_request(
+            1,
+            service::READ_PROPERTY,
+            &encode_read_property(ObjectId::new(object_type::ANALOG_INPUT, 1), &PropertyRef::new(property::PRESENT_VALUE)),
+        );
+        assert_eq!(apdu, [0x00, 0x05, 0x01, 0x0C, 0x0C, 0x00, 0x00, 0x00, 0x01, 0x19, 0x55]);
+
+        let frame = packet(BVLC_ORIGINAL_UNICAST, true, &apdu);
+        assert_eq!(&frame[..6], &[0x81, 0x0A, 0x00, 0x11, 0x01, 0x04]);
+        assert_eq!(decode_packet(&frame).unwrap(), apdu.as_slice());
+    }
+
+    #[test]
+    fn write_property_carries_priority() {
+        let req = WriteRequest {
+            object: ObjectId::new(object_type::ANALOG_OUTPUT, 3),
+            property: PropertyRef::new(property::PRESENT_VALUE),
+            value: Value::Real(55.0),
+            priority: Some(8),
+        };
+        assert_eq!(decode_write_property(&encode_write_property(&req)).unwrap(), req);
+    }
+
+    #[test]
+    fn rpm_ack_round_trips_values_and_errors() {
+        let results = vec![ReadAccessResult {
+            object: ObjectId::new(object_type::ANALOG_VALUE, 2),
+            results: vec![
+                PropertyResult {
+                    property: PropertyRef::new(property::PRESENT_VALUE),
+                    value: Ok(vec![Value::Real(1.5)]),
+                },
+                PropertyResult {
+                    property: PropertyRef::new(property::DESCRIPTION),
+                    value: Err(ErrorCode {
+                        class: error_code::CLASS_PROPERTY,
+                        code: error_code::UNKNOWN_PROPERTY,
+                    }),
+                },
+            ],
+        }];
+        let decoded = decode_read_property_multiple_ack(&encode_read_property_multiple_ack(&results)).unwrap();
+        assert_eq!(decoded, results);
+        assert_eq!(decoded[0].value(property::PRESENT_VALUE), Some(&Value::Real(1.5)));
+        assert_eq!(decoded[0].value(property::DESCRIPTION), None);
+    }
+}
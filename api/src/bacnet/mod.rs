@@ -0,0 +1,25 @@
+//! BACnet/IP — a pure-Rust client for reading and writing T3000 points
+//! without going through T3000.exe.
+//!
+//! - `codec`  — BVLC/NPDU/APDU framing and tag encoding
+//! - `client` — Who-Is, ReadProperty, ReadPropertyMultiple, WriteProperty
+//! - `points` — T3000 inputs/outputs/variables ↔ BACnet objects, used by the
+//!   refresh/update routes when `transport` is `bacnet`
+//! - `sim`    — a simulated device for development and tests
+//!
+//! ## Usage
+//!
+//! ```rust,ignore
+//! use bacnet::client::BacnetClient;
+//! use bacnet::codec::{object_type, property, ObjectId, PropertyRef};
+//!
+//! let client = BacnetClient::ephemeral().await?;
+//! let pv = client
+//!     .read_property(addr, ObjectId::new(object_type::ANALOG_INPUT, 1), PropertyRef::new(property::PRESENT_VALUE))
+//!     .await?;
+//! ```
+
+pub mod client;
+pub mod codec;
+pub mod points;
+pub mod sim;
@@ -0,0 +1,365 @@
+//! T3000 inputs/outputs/variables over native BACnet/IP.
+//!
+//! T3000 panels publish their points as BACnet objects: input N (0-based
+//! entry index) is analog-input N+1, output N is analog-output N+1 and
+//! variable N is analog-value N+1. Reads return items in the same shape as the
+//! C++ GET_WEBVIEW_LIST response (`inputIndex`, `label`, `fullLabel`, `value`,
+//! `autoManual`, ...) so the existing save-refreshed routes accept them as is.
+//!
+//! Only the fields BACnet exposes are mapped: label ↔ object-name,
+//! fullLabel ↔ description, value ↔ present-value, autoManual ↔
+//! out-of-service. Range, filter and calibration stay FFI-only.
+
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use serde_json::{json, Value as JsonValue};
+
+use super::client::{BacnetClient, BacnetError};
+use super::codec::{self, object_type, property, ObjectId, PropertyRef, ReadAccessSpec, Value};
+use crate::entity::t3_device::{devices, protocol_settings};
+
+/// Default command priority for output/variable writes ("manual operator").
+pub const DEFAULT_WRITE_PRIORITY: u8 = 8;
+
+/// Objects per ReadPropertyMultiple request — keeps each ACK well under one APDU.
+const RPM_BATCH: usize = 16;
+
+/// Most object-list elements read one by one; a larger count is a device fault.
+const MAX_OBJECT_LIST_LEN: u32 = 10_000;
+
+/// How the refresh/update routes reach the device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transport {
+    /// `BacnetWebView_HandleWebViewMsg` in T3000.exe (Windows only).
+    Ffi,
+    /// Native BACnet/IP from this process.
+    Bacnet,
+}
+
+impl Transport {
+    /// Parse the `transport` request field. Without one the routes keep
+    /// going through the device transport; BACnet/IP is used only when asked for.
+    pub fn resolve(requested: Option<&str>) -> Result<Self, String> {
+        match requested.map(|t| t.trim().to_ascii_lowercase()).as_deref() {
+            None | Some("") | Some("ffi") => Ok(Transport::Ffi),
+            Some("bacnet") => Ok(Transport::Bacnet),
+            Some(other) => Err(format!("Unknown transport '{}' (expected 'ffi' or 'bacnet')", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointKind {
+    Input,
+    Output,
+    Variable,
+}
+
+impl PointKind {
+    pub fn object_type(self) -> u16 {
+        match self {
+            PointKind::Input => object_type::ANALOG_INPUT,
+            PointKind::Output => object_type::ANALOG_OUTPUT,
+            PointKind::Variable => object_type::ANALOG_VALUE,
+        }
+    }
+
+    /// Index field name in GET_WEBVIEW_LIST items.
+    pub fn index_key(self) -> &'static str {
+        match self {
+            PointKind::Input => "inputIndex",
+            PointKind::Output => "outputIndex",
+            PointKind::Variable => "variableIndex",
+        }
+    }
+
+    pub fn object_id(self, index: i32) -> Result<ObjectId, String> {
+        let instance = u32::try_from(index)
+            .ok()
+            .and_then(|i| i.checked_add(1))
+            .filter(|i| *i <= ObjectId::MAX_INSTANCE)
+            .ok_or_else(|| format!("Invalid point index {}", index))?;
+        Ok(ObjectId::new(self.object_type(), instance))
+    }
+
+    /// Inputs aren't commandable — their present value is only writable while
+    /// out of service, and a priority would be rejected.
+    fn commandable(self) -> bool {
+        self != PointKind::Input
+    }
+}
+
+/// Where a device answers BACnet/IP.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BacnetTarget {
+    pub addr: SocketAddr,
+    /// Device object instance, when known; discovered with Who-Is otherwise.
+    pub device_instance: Option<u32>,
+}
+
+/// Fields to write. `None` leaves the property untouched.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PointWrite {
+    pub value: Option<f64>,
+    pub label: Option<String>,
+    pub description: Option<String>,
+    pub auto_manual: Option<i32>,
+    /// Command priority for outputs/variables (default [`DEFAULT_WRITE_PRIORITY`]).
+    pub priority: Option<u8>,
+}
+
+/// Refuse an update carrying fields BACnet/IP can't write (range,
+/// calibration, filter, ...): the routes save the whole request to the
+/// database, which would then disagree with the device.
+pub fn ensure_writable(unsupported: &[&str]) -> Result<(), String> {
+    if unsupported.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "BACnet/IP can only write value, label, fullLabel and autoManual; remove {} or use the ffi transport",
+        unsupported.join(", ")
+    ))
+}
+
+/// Resolve a device's BACnet/IP address from the DEVICES table (ip_address +
+/// bacnet_ip_port) and its device instance from DEVICES or PROTOCOL_SETTINGS.
+pub async fn lookup_target(db: &DatabaseConnection, serial: i32) -> Result<BacnetTarget, String> {
+    let device = devices::Entity::find()
+        .filter(devices::Column::SerialNumber.eq(serial))
+        .one(db)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .ok_or_else(|| format!("Device with serial {} not found", serial))?;
+
+    let ip: IpAddr = device
+        .ip_address
+        .as_deref()
+        .map(str::trim)
+        .filter(|ip| !ip.is_empty())
+        .ok_or_else(|| format!("Device with serial {} has no IP address", serial))?
+        .parse()
+        .map_err(|e| format!("Device with serial {} has an invalid IP address: {}", serial, e))?;
+    let port = device.bacnet_ip_port.filter(|p| *p > 0).unwrap_or(codec::DEFAULT_PORT);
+
+    let mut device_instance = device.object_instance.filter(|i| *i > 0).map(|i| i as u32);
+    if device_instance.is_none() {
+        device_instance = protocol_settings::Entity::find()
+            .filter(protocol_settings::Column::SerialNumber.eq(serial))
+            .one(db)
+            .await
+            .ok()
+            .flatten()
+            .and_then(|s| s.object_instance)
+            .filter(|i| *i > 0)
+            .map(|i| i as u32);
+    }
+
+    Ok(BacnetTarget {
+        addr: SocketAddr::new(ip, port),
+        device_instance,
+    })
+}
+
+/// Read one point (`index`) or every point of `kind` on the device.
+pub async fn read_points(
+    client: &BacnetClient,
+    target: &BacnetTarget,
+    kind: PointKind,
+    index: Option<i32>,
+) -> Result<Vec<JsonValue>, BacnetError> {
+    let objects = match index {
+        Some(i) => vec![kind.object_id(i)?],
+        None => {
+            let device = device_object(client, target).await?;
+            object_list(client, target.addr, device)
+                .await?
+                .into_iter()
+                .filter(|o| o.object_type == kind.object_type() && o.instance > 0)
+                .collect()
+        }
+    };
+
+    let properties: Vec<PropertyRef> = [
+        property::OBJECT_NAME,
+        property::DESCRIPTION,
+        property::PRESENT_VALUE,
+        property::OUT_OF_SERVICE,
+        property::UNITS,
+    ]
+    .into_iter()
+    .map(PropertyRef::new)
+    .collect();
+
+    let mut items = Vec::with_capacity(objects.len());
+    for batch in objects.chunks(RPM_BATCH) {
+        let specs: Vec<ReadAccessSpec> = batch
+            .iter()
+            .map(|o| ReadAccessSpec { object: *o, properties: properties.clone() })
+            .collect();
+        for result in client.read_property_multiple(target.addr, &specs).await? {
+            // A single-point read of a missing object is an error; in a full
+            // refresh it just means the object-list and reality disagree.
+            if result.value(property::PRESENT_VALUE).is_none() {
+                if index.is_none() {
+                    continue;
+                }
+                if let Some(e) = result.results.iter().find_map(|r| r.value.as_ref().err()) {
+                    return Err(BacnetError::Device(*e));
+                }
+            }
+            items.push(item_json(kind, &result));
+        }
+    }
+    Ok(items)
+}
+
+/// Write the supplied fields of one point. Out-of-service goes first so that
+/// an input switched to manual accepts its new present value.
+pub async fn write_point(
+    client: &BacnetClient,
+    target: &BacnetTarget,
+    kind: PointKind,
+    index: i32,
+    write: &PointWrite,
+) -> Result<(), BacnetError> {
+    let object = kind.object_id(index)?;
+    let addr = target.addr;
+
+    if let Some(am) = write.auto_manual {
+        client
+            .write_property(addr, object, PropertyRef::new(property::OUT_OF_SERVICE), Value::Boolean(am != 0), None)
+            .await?;
+    }
+    if let Some(v) = write.value {
+        let priority = kind
+            .commandable()
+            .then(|| write.priority.unwrap_or(DEFAULT_WRITE_PRIORITY));
+        client
+            .write_property(addr, object, PropertyRef::new(property::PRESENT_VALUE), Value::Real(v as f32), priority)
+            .await?;
+    }
+    if let Some(label) = &write.label {
+        client
+            .write_property(addr, object, PropertyRef::new(property::OBJECT_NAME), Value::CharacterString(label.clone()), None)
+            .await?;
+    }
+    if let Some(description) = &write.description {
+        client
+            .write_property(
+                addr,
+                object,
+                PropertyRef::new(property::DESCRIPTION),
+                Value::CharacterString(description.clone()),
+                None,
+            )
+            .await?;
+    }
+    Ok(())
+}
+
+/// Route helper: look the device up and read its points over BACnet/IP.
+pub async fn refresh(
+    db: &DatabaseConnection,
+    serial: i32,
+    kind: PointKind,
+    index: Option<i32>,
+) -> Result<Vec<JsonValue>, String> {
+    let target = lookup_target(db, serial).await?;
+    let client = BacnetClient::ephemeral().await.map_err(|e| e.to_string())?;
+    read_points(&client, &target, kind, index).await.map_err(|e| e.to_string())
+}
+
+/// Route helper: look the device up and write one point over BACnet/IP.
+pub async fn update(
+    db: &DatabaseConnection,
+    serial: i32,
+    kind: PointKind,
+    index: i32,
+    write: &PointWrite,
+) -> Result<(), String> {
+    let target = lookup_target(db, serial).await?;
+    let client = BacnetClient::ephemeral().await.map_err(|e| e.to_string())?;
+    write_point(&client, &target, kind, index, write).await.map_err(|e| e.to_string())
+}
+
+/// The device object to read the object-list from, discovering the instance
+/// with a unicast Who-Is when the database doesn't know it.
+async fn device_object(client: &BacnetClient, target: &BacnetTarget) -> Result<ObjectId, BacnetError> {
+    if let Some(instance) = target.device_instance {
+        return Ok(ObjectId::new(object_type::DEVICE, instance));
+    }
+    client
+        .who_is(target.addr, None, Duration::from_secs(2))
+        .await?
+        .into_iter()
+        .find(|(from, _)| from.ip() == target.addr.ip())
+        .map(|(_, i_am)| i_am.device)
+        .ok_or(BacnetError::Timeout(target.addr))
+}
+
+/// Read the device's object-list, element by element if the whole array is
+/// too large for an unsegmented reply.
+async fn object_list(client: &BacnetClient, addr: SocketAddr, device: ObjectId) -> Result<Vec<ObjectId>, BacnetError> {
+    let as_ids = |values: Vec<Value>| {
+        values
+            .into_iter()
+            .filter_map(|v| match v {
+                Value::ObjectId(id) => Some(id),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+    match client.read_property(addr, device, PropertyRef::new(property::OBJECT_LIST)).await {
+        Ok(values) => Ok(as_ids(values)),
+        Err(BacnetError::Abort(_)) | Err(BacnetError::Reject(_)) => {
+            let len = client
+                .read_property(addr, device, PropertyRef::at(property::OBJECT_LIST, 0))
+                .await?
+                .first()
+                .and_then(Value::as_f64)
+                .unwrap_or(0.0) as u32;
+            if len > MAX_OBJECT_LIST_LEN {
+                return Err(BacnetError::Protocol(format!(
+                    "object-list length {} exceeds {}",
+                    len, MAX_OBJECT_LIST_LEN
+                )));
+            }
+            let mut ids = Vec::with_capacity(len as usize);
+            for i in 1..=len {
+                ids.extend(as_ids(client.read_property(addr, device, PropertyRef::at(property::OBJECT_LIST, i)).await?));
+            }
+            Ok(ids)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+fn item_json(kind: PointKind, result: &codec::ReadAccessResult) -> JsonValue {
+    let mut item = json!({ "objectInstance": result.object.instance });
+    item[kind.index_key()] = json!(result.object.instance as i64 - 1);
+    if let Some(name) = result.value(property::OBJECT_NAME).and_then(Value::as_str) {
+        item["label"] = json!(name);
+    }
+    if let Some(description) = result.value(property::DESCRIPTION).and_then(Value::as_str) {
+        item["fullLabel"] = json!(description);
+    }
+    match result.value(property::PRESENT_VALUE) {
+        // Go through the shortest decimal form so 21.1f32 reads back as 21.1, not 21.100000381.
+        Some(Value::Real(v)) => item["value"] = json!(v.to_string().parse::<f64>().unwrap_or(*v as f64)),
+        Some(other) => {
+            if let Some(v) = other.as_f64() {
+                item["value"] = json!(v);
+            }
+        }
+        None => {}
+    }
+    if let Some(oos) = result.value(property::OUT_OF_SERVICE).and_then(Value::as_bool) {
+        item["autoManual"] = json!(oos as i32);
+    }
+    if let Some(units) = result.value(property::UNITS).and_then(Value::as_f64) {
+        item["units"] = json!(units as u32);
+    }
+    item
+}
@@ -0,0 +1,311 @@
+//! In-process simulated BACnet/IP device.
+//!
+//! Answers Who-Is, ReadProperty, ReadPropertyMultiple and WriteProperty for a
+//! device object plus analog inputs/outputs/values — enough to exercise the
+//! client and the BACnet point transport without hardware. Outputs and values
+//! are commandable (16-slot priority array); inputs accept a present-value
+//! write only while out-of-service, as on a real panel.
+
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use tokio::net::UdpSocket;
+use tokio::task::JoinHandle;
+
+use super::codec::{
+    self, error_code, object_type, property, service, Apdu, ErrorCode, IAm, ObjectId, PropertyRef, PropertyResult,
+    ReadAccessResult, Value, WriteRequest,
+};
+
+/// Priority used when a commandable write carries none (BACnet default).
+const DEFAULT_PRIORITY: u8 = 16;
+
+#[derive(Debug, Clone)]
+struct SimObject {
+    properties: HashMap<u32, Value>,
+    /// `Some` for commandable objects.
+    priority_array: Option<[Option<Value>; 16]>,
+}
+
+/// Simulated device definition. Build it up, then [`SimDevice::spawn`] it.
+#[derive(Debug, Clone)]
+pub struct SimDevice {
+    device: ObjectId,
+    name: String,
+    objects: BTreeMap<ObjectId, SimObject>,
+}
+
+impl SimDevice {
+    pub fn new(instance: u32, name: &str) -> Self {
+        Self {
+            device: ObjectId::new(object_type::DEVICE, instance),
+            name: name.to_string(),
+            objects: BTreeMap::new(),
+        }
+    }
+
+    /// Add an analog input, output or value object.
+    pub fn with_analog(mut self, object_type: u16, instance: u32, name: &str, description: &str, value: f32) -> Self {
+        let id = ObjectId::new(object_type, instance);
+        let mut properties = HashMap::from([
+            (property::OBJECT_IDENTIFIER, Value::ObjectId(id)),
+            (property::OBJECT_NAME, Value::CharacterString(name.to_string())),
+            (property::OBJECT_TYPE, Value::Enumerated(object_type as u32)),
+            (property::DESCRIPTION, Value::CharacterString(description.to_string())),
+            (property::PRESENT_VALUE, Value::Real(value)),
+            (property::OUT_OF_SERVICE, Value::Boolean(false)),
+            (property::STATUS_FLAGS, Value::BitString { unused_bits: 4, bytes: vec![0] }),
+            (property::UNITS, Value::Enumerated(95)), // no-units
+        ]);
+        let commandable = object_type != codec::object_type::ANALOG_INPUT;
+        if commandable {
+            properties.insert(property::RELINQUISH_DEFAULT, Value::Real(value));
+        }
+        self.objects.insert(
+            id,
+            SimObject {
+                properties,
+                priority_array: commandable.then(|| std::array::from_fn(|_| None)),
+            },
+        );
+        self
+    }
+
+    fn i_am(&self) -> IAm {
+        IAm {
+            device: self.device,
+            max_apdu: codec::MAX_APDU as u32,
+            segmentation: codec::NO_SEGMENTATION,
+            vendor_id: 148, // Temco Controls
+        }
+    }
+
+    fn object_list(&self) -> Vec<Value> {
+        std::iter::once(self.device)
+            .chain(self.objects.keys().copied())
+            .map(Value::ObjectId)
+            .collect()
+    }
+
+    fn read(&self, object: ObjectId, prop: PropertyRef) -> Result<Vec<Value>, ErrorCode> {
+        if object == self.device {
+            let value = match prop.property {
+                property::OBJECT_LIST => return array_read(self.object_list(), prop.array_index),
+                property::OBJECT_IDENTIFIER => Value::ObjectId(self.device),
+                property::OBJECT_NAME => Value::CharacterString(self.name.clone()),
+                property::OBJECT_TYPE => Value::Enumerated(object_type::DEVICE as u32),
+                property::VENDOR_IDENTIFIER => Value::Unsigned(148),
+                property::MAX_APDU_LENGTH_ACCEPTED => Value::Unsigned(codec::MAX_APDU as u32),
+                property::SEGMENTATION_SUPPORTED => Value::Enumerated(codec::NO_SEGMENTATION),
+                _ => return Err(unknown_property()),
+            };
+            return scalar(value, prop.array_index);
+        }
+        let obj = self.objects.get(&object).ok_or(ErrorCode {
+            class: error_code::CLASS_OBJECT,
+            code: error_code::UNKNOWN_OBJECT,
+        })?;
+        match (prop.property, &obj.priority_array) {
+            (property::PRIORITY_ARRAY, Some(slots)) => {
+                let values = slots.iter().map(|s| s.clone().unwrap_or(Value::Null)).collect();
+                array_read(values, prop.array_index)
+            }
+            (property::PRESENT_VALUE, Some(slots)) => {
+                let active = slots.iter().flatten().next().cloned();
+                scalar(active.unwrap_or_else(|| obj.properties[&property::RELINQUISH_DEFAULT].clone()), prop.array_index)
+            }
+            (p, _) => scalar(obj.properties.get(&p).cloned().ok_or_else(unknown_property)?, prop.array_index),
+        }
+    }
+
+    fn write(&mut self, req: WriteRequest) -> Result<(), ErrorCode> {
+        let denied = ErrorCode {
+            class: error_code::CLASS_PROPERTY,
+            code: error_code::WRITE_ACCESS_DENIED,
+        };
+        let bad_type = ErrorCode {
+            class: error_code::CLASS_PROPERTY,
+            code: error_code::INVALID_DATA_TYPE,
+        };
+        let obj = self.objects.get_mut(&req.object).ok_or(ErrorCode {
+            class: error_code::CLASS_OBJECT,
+            code: error_code::UNKNOWN_OBJECT,
+        })?;
+        if req.property.array_index.is_some() {
+            return Err(denied);
+        }
+        match req.property.property {
+            property::PRESENT_VALUE => {
+                let value = match req.value {
+                    Value::Null => None,
+                    ref v => Some(Value::Real(v.as_f64().ok_or(bad_type)? as f32)),
+                };
+                match &mut obj.priority_array {
+                    Some(slots) => {
+                        let priority = req.priority.unwrap_or(DEFAULT_PRIORITY);
+                        if !(1..=16).contains(&priority) {
+                            return Err(ErrorCode {
+                                class: error_code::CLASS_PROPERTY,
+                                code: error_code::VALUE_OUT_OF_RANGE,
+                            });
+                        }
+                        slots[priority as usize - 1] = value;
+                    }
+                    None => {
+                        let out_of_service = obj.properties[&property::OUT_OF_SERVICE] == Value::Boolean(true);
+                        if !out_of_service {
+                            return Err(denied);
+                        }
+                        obj.properties.insert(property::PRESENT_VALUE, value.ok_or(bad_type)?);
+                    }
+                }
+            }
+            property::OUT_OF_SERVICE => {
+                let v = matches!(req.value, Value::Boolean(_)).then_some(req.value).ok_or(bad_type)?;
+                obj.properties.insert(property::OUT_OF_SERVICE, v);
+            }
+            p @ (property::OBJECT_NAME | property::DESCRIPTION) => {
+                let v = matches!(req.value, Value::CharacterString(_)).then_some(req.value).ok_or(bad_type)?;
+                obj.properties.insert(p, v);
+            }
+            _ => return Err(denied),
+        }
+        Ok(())
+    }
+
+    /// Build the reply to one datagram, if it warrants one.
+    fn handle(&mut self, datagram: &[u8]) -> Option<Vec<u8>> {
+        let apdu = codec::decode_packet(datagram).and_then(codec::decode_apdu).ok()?;
+        let reply = match apdu {
+            Apdu::UnconfirmedRequest { service: service::WHO_IS, body } => {
+                let range = codec::decode_who_is(body).ok()?;
+                if range.is_some_and(|(lo, hi)| !(lo..=hi).contains(&self.device.instance)) {
+                    return None;
+                }
+                return Some(codec::packet(
+                    codec::BVLC_ORIGINAL_UNICAST,
+                    false,
+                    &codec::unconfirmed_request(service::I_AM, &codec::encode_i_am(&self.i_am())),
+                ));
+            }
+            Apdu::ConfirmedRequest { invoke_id, service, body } => match service {
+                service::READ_PROPERTY => match codec::decode_read_property(body) {
+                    Ok((object, prop)) => match self.read(object, prop) {
+                        Ok(values) => codec::complex_ack(
+                            invoke_id,
+                            service,
+                            &codec::encode_read_property_ack(object, &prop, &values),
+                        ),
+                        Err(e) => codec::error_pdu(invoke_id, service, e),
+                    },
+                    Err(_) => codec::reject_pdu(invoke_id, 0),
+                },
+                service::READ_PROPERTY_MULTIPLE => match codec::decode_read_property_multiple(body) {
+                    Ok(specs) => {
+                        let results: Vec<ReadAccessResult> = specs
+                            .into_iter()
+                            .map(|spec| ReadAccessResult {
+                                object: spec.object,
+                                results: spec
+                                    .properties
+                                    .into_iter()
+                                    .map(|p| PropertyResult { property: p, value: self.read(spec.object, p) })
+                                    .collect(),
+                            })
+                            .collect();
+                        codec::complex_ack(invoke_id, service, &codec::encode_read_property_multiple_ack(&results))
+                    }
+                    Err(_) => codec::reject_pdu(invoke_id, 0),
+                },
+                service::WRITE_PROPERTY => match codec::decode_write_property(body) {
+                    Ok(req) => match self.write(req) {
+                        Ok(()) => codec::simple_ack(invoke_id, service),
+                        Err(e) => codec::error_pdu(invoke_id, service, e),
+                    },
+                    Err(_) => codec::reject_pdu(invoke_id, 0),
+                },
+                _ => codec::reject_pdu(invoke_id, codec::REJECT_UNRECOGNIZED_SERVICE),
+            },
+            _ => return None,
+        };
+        Some(codec::packet(codec::BVLC_ORIGINAL_UNICAST, false, &reply))
+    }
+
+    /// Serve the device on `bind` until the returned handle is dropped.
+    pub async fn spawn(self, bind: SocketAddr) -> std::io::Result<SimHandle> {
+        let socket = UdpSocket::bind(bind).await?;
+        let addr = socket.local_addr()?;
+        let state = Arc::new(Mutex::new(self));
+        let served = state.clone();
+        let task = tokio::spawn(async move {
+            let mut buf = [0u8; 1500];
+            while let Ok((len, from)) = socket.recv_from(&mut buf).await {
+                let reply = served.lock().map(|mut dev| dev.handle(&buf[..len])).ok().flatten();
+                if let Some(reply) = reply {
+                    let _ = socket.send_to(&reply, from).await;
+                }
+            }
+        });
+        Ok(SimHandle { addr, state, task })
+    }
+}
+
+/// A running simulated device. Dropping it stops the device.
+pub struct SimHandle {
+    pub addr: SocketAddr,
+    state: Arc<Mutex<SimDevice>>,
+    task: JoinHandle<()>,
+}
+
+impl SimHandle {
+    /// Read a property directly from the simulated state.
+    pub fn get(&self, object: ObjectId, property: PropertyRef) -> Option<Value> {
+        let dev = self.state.lock().ok()?;
+        dev.read(object, property).ok()?.into_iter().next()
+    }
+
+    /// Change a property as if the field value had moved (e.g. a sensor drifting).
+    pub fn set(&self, object: ObjectId, property: u32, value: Value) {
+        if let Ok(mut dev) = self.state.lock() {
+            if let Some(obj) = dev.objects.get_mut(&object) {
+                obj.properties.insert(property, value);
+            }
+        }
+    }
+}
+
+impl Drop for SimHandle {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+fn unknown_property() -> ErrorCode {
+    ErrorCode {
+        class: error_code::CLASS_PROPERTY,
+        code: error_code::UNKNOWN_PROPERTY,
+    }
+}
+
+fn scalar(value: Value, array_index: Option<u32>) -> Result<Vec<Value>, ErrorCode> {
+    match array_index {
+        None => Ok(vec![value]),
+        Some(_) => Err(ErrorCode {
+            class: error_code::CLASS_PROPERTY,
+            code: error_code::PROPERTY_IS_NOT_AN_ARRAY,
+        }),
+    }
+}
+
+/// BACnet array semantics: index 0 is the length, 1..=n the elements.
+fn array_read(values: Vec<Value>, array_index: Option<u32>) -> Result<Vec<Value>, ErrorCode> {
+    match array_index {
+        None => Ok(values),
+        Some(0) => Ok(vec![Value::Unsigned(values.len() as u32)]),
+        Some(i) => values.into_iter().nth(i as usize - 1).map(|v| vec![v]).ok_or(ErrorCode {
+            class: error_code::CLASS_PROPERTY,
+            code: error_code::INVALID_ARRAY_INDEX,
+        }),
+    }
+}
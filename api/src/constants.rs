@@ -4,6 +4,20 @@ use std::path::PathBuf;
 /// Set to false to disable partition checks, WAL cleanup, and size monitoring
 pub const ENABLE_PARTITION_MONITOR_SERVICE: bool = false;
 
+/// Enable/disable the background FDD scheduler (continuous rule evaluation)
+/// Off by default: fault detection runs only on demand (t3000_fdd_analyze);
+/// set to true to evaluate every enabled rule on its poll_seconds
+pub const ENABLE_FDD_SCHEDULER: bool = false;
+
+/// Enable/disable the background Modbus TCP poller
+/// Off by default: when on, every controller whose Product_ID is mapped to a
+/// Modbus register map is polled and its values written to trendlogs
+pub const ENABLE_MODBUS_POLLER: bool = false;
+
+/// Enable/disable the background TRENDLOG_DATA_DETAIL numeric backfill
+/// Fills NumericValue / LoggingTime on existing rows (main DB and partitions) after startup
+pub const ENABLE_TRENDLOG_NUMERIC_BACKFILL: bool = true;
+
 /// Get the base runtime directory where T3000 application stores its files
 /// Checks TEMCO_T3000_PATH environment variable first, then falls back to exe directory
 pub fn get_t3000_runtime_path() -> PathBuf {
@@ -3,6 +3,7 @@
 // Space savings: 32 bytes per record (47% reduction from original schema)
 // Tracking: DataSource always FFI_SYNC (constant), SyncMetadataId tracked at metadata table level
 // NO FOREIGN KEYS - removed for simplicity
+// NumericValue / LoggingTime: typed copies of Value / LoggingTime_Fmt (see server_db::trendlog_detail)
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -19,6 +20,12 @@ pub struct Model {
 
     #[sea_orm(column_name = "LoggingTime_Fmt")]
     pub logging_time_fmt: String,              // C++ Formatted Time (e.g., "2025-10-28 13:35:49")
+
+    #[sea_orm(column_name = "NumericValue")]
+    pub numeric_value: Option<f64>,            // Value as a number (NULL on rows not yet backfilled)
+
+    #[sea_orm(column_name = "LoggingTime")]
+    pub logging_time: Option<i64>,             // LoggingTime_Fmt as Unix seconds (NULL on rows not yet backfilled)
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -17,6 +17,7 @@ pub enum Error {
     BadRequest(String),
     ServerError(String),
     ValidationError(String),
+    Conflict(String),
 }
 
 // Implement the Display trait for the Error enum to enable formatted output.
@@ -66,6 +67,7 @@ impl IntoResponse for Error {
                 StatusCode::BAD_REQUEST,
                 format!("Validation Error: {}", err),
             ),
+            Self::Conflict(err) => (StatusCode::CONFLICT, format!("Conflict: {}", err)),
         };
 
         // Convert the tuple (StatusCode, String) into an HTTP response.
@@ -4,12 +4,27 @@
 //! → fault hours. Rules are configured in the DB (`FDD_RULES`), the logic lives
 //! here.
 
+use chrono::NaiveDateTime;
 use serde_json::{json, Value};
 use std::collections::VecDeque;
 
 use super::rules::Rule;
 use super::series::Sample;
 
+/// Every `rule_kind` understood by `eval_rule`.
+pub const RULE_KINDS: &[&str] = &[
+    "ThresholdAbove",
+    "ThresholdBelow",
+    "FanMismatch",
+    "EconomizerOaFraction",
+    "EconomizerStuckClosed",
+    "RangeBand",
+    "SupplyTempDeviation",
+    "ChwLowDeltaT",
+    "StuckValue",
+    "Expression",
+];
+
 /// Result of evaluating one rule.
 pub struct Finding {
     pub severity: String,
@@ -21,17 +36,62 @@ fn field(s: &Sample, name: &str) -> Option<f64> {
     s.values.get(name).copied()
 }
 
+/// Sample spacing a rule is evaluated with.
+#[derive(Clone, Copy)]
+struct Spacing {
+    /// Time one sample stands for: the resample bucket, or the rule's poll
+    /// interval when the series was not resampled.
+    sample_seconds: u64,
+    /// Longest jump between samples that still counts as continuous.
+    max_gap_seconds: u64,
+}
+
+impl Spacing {
+    fn new(bucket_seconds: u64, poll_seconds: u64) -> Self {
+        let sample_seconds = if bucket_seconds > 0 { bucket_seconds } else { poll_seconds };
+        Self {
+            sample_seconds,
+            max_gap_seconds: sample_seconds.max(poll_seconds),
+        }
+    }
+
+    fn hours(&self) -> f64 {
+        self.sample_seconds as f64 / 3600.0
+    }
+}
+
+/// Whether `s` is not a continuation of the sample before it: a gap marker, or
+/// a timestamp jump longer than both the bucket width and the poll interval.
+fn breaks_continuity(prev: Option<NaiveDateTime>, s: &Sample, spacing: Spacing) -> bool {
+    if s.gap {
+        return true;
+    }
+    match (prev, s.time()) {
+        (Some(p), Some(t)) => (t - p).num_seconds() > spacing.max_gap_seconds as i64,
+        _ => false,
+    }
+}
+
 /// Confirm-window evaluator: only count a fault after N consecutive samples.
-fn fault_hours<F>(series: &[Sample], mut raw_fault: F, confirm_rows: u32, poll_seconds: u64) -> f64
+/// A data gap resets the streak, so fault time is never counted across it.
+fn fault_hours<F>(series: &[Sample], mut raw_fault: F, confirm_rows: u32, spacing: Spacing) -> f64
 where
     F: FnMut(&Sample) -> bool,
 {
     let mut streak = 0u32;
     let mut hours = 0.0;
+    let mut prev = None;
     for s in series {
+        if breaks_continuity(prev, s, spacing) {
+            streak = 0;
+        }
+        prev = s.time();
+        if s.gap {
+            continue;
+        }
         streak = if raw_fault(s) { streak + 1 } else { 0 };
         if streak >= confirm_rows {
-            hours += poll_seconds as f64 / 3600.0;
+            hours += spacing.hours();
             streak = 0;
         }
     }
@@ -45,11 +105,16 @@ fn stuck_hours(
     field_name: &str,
     deadband: f64,
     window_rows: usize,
-    poll_seconds: u64,
+    spacing: Spacing,
 ) -> f64 {
     let mut vals: VecDeque<f64> = VecDeque::new();
     let mut hours = 0.0;
+    let mut prev = None;
     for s in series {
+        if breaks_continuity(prev, s, spacing) {
+            vals.clear();
+        }
+        prev = s.time();
         if let Some(v) = field(s, field_name) {
             vals.push_back(v);
             if vals.len() > window_rows {
@@ -59,7 +124,7 @@ fn stuck_hours(
                 let min = vals.iter().cloned().fold(f64::MAX, f64::min);
                 let max = vals.iter().cloned().fold(f64::MIN, f64::max);
                 if max - min <= deadband {
-                    hours += poll_seconds as f64 / 3600.0;
+                    hours += spacing.hours();
                     vals.clear();
                 }
             }
@@ -69,7 +134,10 @@ fn stuck_hours(
 }
 
 /// Evaluate a rule against the loaded series.
-pub fn eval_rule(rule: &Rule, series: &[Sample]) -> Finding {
+///
+/// `bucket_seconds` is the width the series was resampled onto (0 = not
+/// resampled, samples are one `poll_seconds` apart).
+pub fn eval_rule(rule: &Rule, series: &[Sample], bucket_seconds: u64) -> Finding {
     let params = &rule.params;
     let confirm = params
         .get("confirm_rows")
@@ -79,13 +147,14 @@ pub fn eval_rule(rule: &Rule, series: &[Sample]) -> Finding {
         .get("poll_seconds")
         .and_then(|v| v.as_u64())
         .unwrap_or(300);
+    let spacing = Spacing::new(bucket_seconds, poll);
 
     let (hours, evidence) = match rule.rule_kind.as_str() {
         "ThresholdAbove" => {
             let f = params.get("field").and_then(|v| v.as_str()).unwrap_or("");
             let limit = params.get("limit").and_then(|v| v.as_f64()).unwrap_or(0.0);
             (
-                fault_hours(series, |s| field(s, f).map_or(false, |v| v > limit), confirm, poll),
+                fault_hours(series, |s| field(s, f).is_some_and(|v| v > limit), confirm, spacing),
                 json!({ "field": f, "limit": limit }),
             )
         }
@@ -93,7 +162,7 @@ pub fn eval_rule(rule: &Rule, series: &[Sample]) -> Finding {
             let f = params.get("field").and_then(|v| v.as_str()).unwrap_or("");
             let limit = params.get("limit").and_then(|v| v.as_f64()).unwrap_or(0.0);
             (
-                fault_hours(series, |s| field(s, f).map_or(false, |v| v < limit), confirm, poll),
+                fault_hours(series, |s| field(s, f).is_some_and(|v| v < limit), confirm, spacing),
                 json!({ "field": f, "limit": limit }),
             )
         }
@@ -108,7 +177,7 @@ pub fn eval_rule(rule: &Rule, series: &[Sample]) -> Finding {
                     _ => false,
                 },
                 confirm,
-                poll,
+                spacing,
             );
             (hours, json!({}))
         }
@@ -134,7 +203,7 @@ pub fn eval_rule(rule: &Rule, series: &[Sample]) -> Finding {
                     }
                 },
                 confirm,
-                poll,
+                spacing,
             );
             (hours, json!({ "oa_min_pct": pct }))
         }
@@ -151,7 +220,7 @@ pub fn eval_rule(rule: &Rule, series: &[Sample]) -> Finding {
                     }
                 },
                 confirm,
-                poll,
+                spacing,
             );
             (hours, json!({}))
         }
@@ -160,7 +229,7 @@ pub fn eval_rule(rule: &Rule, series: &[Sample]) -> Finding {
             let lo = params.get("lo").and_then(|v| v.as_f64()).unwrap_or(0.0);
             let hi = params.get("hi").and_then(|v| v.as_f64()).unwrap_or(100.0);
             (
-                fault_hours(series, |s| field(s, f).map_or(false, |v| v < lo || v > hi), confirm, poll),
+                fault_hours(series, |s| field(s, f).is_some_and(|v| v < lo || v > hi), confirm, spacing),
                 json!({ "field": f, "lo": lo, "hi": hi }),
             )
         }
@@ -173,7 +242,7 @@ pub fn eval_rule(rule: &Rule, series: &[Sample]) -> Finding {
                     _ => false,
                 },
                 confirm,
-                poll,
+                spacing,
             );
             (hours, json!({ "max_dev": max_dev }))
         }
@@ -186,7 +255,7 @@ pub fn eval_rule(rule: &Rule, series: &[Sample]) -> Finding {
                     _ => false,
                 },
                 confirm,
-                poll,
+                spacing,
             );
             (hours, json!({ "min_dt": min_dt }))
         }
@@ -195,10 +264,26 @@ pub fn eval_rule(rule: &Rule, series: &[Sample]) -> Finding {
             let deadband = params.get("deadband").and_then(|v| v.as_f64()).unwrap_or(0.1);
             let window = params.get("window_rows").and_then(|v| v.as_u64()).unwrap_or(12) as usize;
             (
-                stuck_hours(series, f, deadband, window, poll),
+                stuck_hours(series, f, deadband, window, spacing),
                 json!({ "field": f, "deadband": deadband, "window_rows": window }),
             )
         }
+        "Expression" => {
+            // Generic condition over role names, e.g. `fan_cmd > 0.05 && abs(sat - sat_sp) > 5`.
+            let src = params.get("expression").and_then(|v| v.as_str()).unwrap_or("");
+            match super::expr::parse(src) {
+                Ok(expr) => (
+                    fault_hours(
+                        series,
+                        |s| expr.eval(&s.values).is_some_and(|v| v != 0.0),
+                        confirm,
+                        spacing,
+                    ),
+                    json!({ "expression": src }),
+                ),
+                Err(e) => (0.0, json!({ "expression": src, "error": e })),
+            }
+        }
         // Unknown / not-yet-implemented rule kinds are skipped.
         _ => (0.0, json!({})),
     };
@@ -245,6 +330,7 @@ mod tests {
         Sample {
             ts: ts.to_string(),
             values,
+            gap: false,
         }
     }
 
@@ -276,7 +362,7 @@ mod tests {
             json!({"field":"sat","limit":100,"confirm_rows":4,"poll_seconds":300}),
             &["sat"],
         );
-        let f = eval_rule(&r, &series);
+        let f = eval_rule(&r, &series, 0);
         assert_eq!(f.fault_hours, 0.0);
     }
 
@@ -295,7 +381,7 @@ mod tests {
             json!({"field":"sat","limit":100,"confirm_rows":4,"poll_seconds":300}),
             &["sat"],
         );
-        let f = eval_rule(&r, &series);
+        let f = eval_rule(&r, &series, 0);
         assert!((f.fault_hours - (300.0 / 3600.0)).abs() < 1e-9, "got {}", f.fault_hours);
     }
 
@@ -313,7 +399,7 @@ mod tests {
             json!({"confirm_rows":4,"poll_seconds":300}),
             &["fan_cmd", "fan_status"],
         );
-        let f = eval_rule(&r, &series);
+        let f = eval_rule(&r, &series, 0);
         assert!(f.fault_hours > 0.0, "expected fault, got {}", f.fault_hours);
     }
 
@@ -332,7 +418,7 @@ mod tests {
             json!({"oa_min_pct":15,"confirm_rows":4,"poll_seconds":300}),
             &["mat", "rat", "oa_t", "fan_cmd"],
         );
-        let f = eval_rule(&r, &series);
+        let f = eval_rule(&r, &series, 0);
         assert_eq!(f.fault_hours, 0.0);
     }
 
@@ -350,7 +436,7 @@ mod tests {
             json!({"oa_min_pct":15,"confirm_rows":4,"poll_seconds":300}),
             &["mat", "rat", "oa_t", "fan_cmd"],
         );
-        let f = eval_rule(&r, &series);
+        let f = eval_rule(&r, &series, 0);
         assert!(f.fault_hours > 0.0, "expected fault, got {}", f.fault_hours);
     }
 
@@ -367,7 +453,7 @@ mod tests {
             json!({"field":"zone_t","lo":70,"hi":75,"confirm_rows":4,"poll_seconds":300}),
             &["zone_t"],
         );
-        let f = eval_rule(&r, &series);
+        let f = eval_rule(&r, &series, 0);
         assert!(f.fault_hours > 0.0, "expected fault, got {}", f.fault_hours);
     }
 
@@ -384,7 +470,7 @@ mod tests {
             json!({"field":"zone_t","lo":70,"hi":75,"confirm_rows":4,"poll_seconds":300}),
             &["zone_t"],
         );
-        let f = eval_rule(&r, &series);
+        let f = eval_rule(&r, &series, 0);
         assert_eq!(f.fault_hours, 0.0);
     }
 
@@ -400,7 +486,7 @@ mod tests {
             json!({"field":"sat","deadband":0.1,"window_rows":12,"poll_seconds":300}),
             &["sat"],
         );
-        let f = eval_rule(&r, &series);
+        let f = eval_rule(&r, &series, 0);
         assert!(f.fault_hours > 0.0, "expected fault, got {}", f.fault_hours);
     }
 
@@ -415,7 +501,7 @@ mod tests {
             json!({"field":"sat","deadband":0.1,"window_rows":12,"poll_seconds":300}),
             &["sat"],
         );
-        let f = eval_rule(&r, &series);
+        let f = eval_rule(&r, &series, 0);
         assert_eq!(f.fault_hours, 0.0);
     }
 
@@ -432,7 +518,7 @@ mod tests {
             json!({"min_dt":5,"confirm_rows":4,"poll_seconds":300}),
             &["chw_s", "chw_r"],
         );
-        let f = eval_rule(&r, &series);
+        let f = eval_rule(&r, &series, 0);
         assert!(f.fault_hours > 0.0, "expected fault, got {}", f.fault_hours);
     }
 
@@ -449,10 +535,119 @@ mod tests {
             json!({"max_dev":5,"confirm_rows":4,"poll_seconds":300}),
             &["sat", "sat_sp"],
         );
-        let f = eval_rule(&r, &series);
+        let f = eval_rule(&r, &series, 0);
         assert!(f.fault_hours > 0.0, "expected fault, got {}", f.fault_hours);
     }
 
+    #[test]
+    fn gaps_break_the_confirm_streak() {
+        let r = rule(
+            "ThresholdAbove",
+            json!({"field":"sat","limit":100,"confirm_rows":4,"poll_seconds":300}),
+            &["sat"],
+        );
+        let hot = |ts: &str| sample(ts, &[("sat", 110.0)]);
+
+        // Two faulty samples either side of a gap marker: no confirmed streak.
+        let mut gap = sample("2026-01-01 10:10:00", &[]);
+        gap.gap = true;
+        let series = vec![
+            hot("2026-01-01 10:00:00"),
+            hot("2026-01-01 10:05:00"),
+            gap,
+            hot("2026-01-01 12:00:00"),
+            hot("2026-01-01 12:05:00"),
+        ];
+        assert_eq!(eval_rule(&r, &series, 0).fault_hours, 0.0);
+
+        // Same without a marker but with a jump longer than poll_seconds.
+        let series = vec![
+            hot("2026-01-01 10:00:00"),
+            hot("2026-01-01 10:05:00"),
+            hot("2026-01-01 12:00:00"),
+            hot("2026-01-01 12:05:00"),
+        ];
+        assert_eq!(eval_rule(&r, &series, 0).fault_hours, 0.0);
+
+        // Contiguous 5-minute samples still confirm.
+        let series = vec![
+            hot("2026-01-01 10:00:00"),
+            hot("2026-01-01 10:05:00"),
+            hot("2026-01-01 10:10:00"),
+            hot("2026-01-01 10:15:00"),
+        ];
+        assert!(eval_rule(&r, &series, 0).fault_hours > 0.0);
+    }
+
+    #[test]
+    fn fault_time_uses_the_bucket_width() {
+        let r = rule(
+            "ThresholdAbove",
+            json!({"field":"sat","limit":100,"confirm_rows":4,"poll_seconds":300}),
+            &["sat"],
+        );
+        let hot = |ts: &str| sample(ts, &[("sat", 110.0)]);
+
+        // 60s buckets under a 300s poll: a confirmed sample is one minute, not five.
+        let series = vec![
+            hot("2026-01-01 10:00:00"),
+            hot("2026-01-01 10:01:00"),
+            hot("2026-01-01 10:02:00"),
+            hot("2026-01-01 10:03:00"),
+        ];
+        let f = eval_rule(&r, &series, 60);
+        assert!((f.fault_hours - (60.0 / 3600.0)).abs() < 1e-9, "got {}", f.fault_hours);
+
+        // 900s buckets over a 300s poll: adjacent buckets are not a gap, and the
+        // confirmed sample stands for a quarter hour.
+        let series = vec![
+            hot("2026-01-01 10:00:00"),
+            hot("2026-01-01 10:15:00"),
+            hot("2026-01-01 10:30:00"),
+            hot("2026-01-01 10:45:00"),
+        ];
+        let f = eval_rule(&r, &series, 900);
+        assert!((f.fault_hours - 0.25).abs() < 1e-9, "got {}", f.fault_hours);
+
+        // ...but a jump past both still breaks the streak.
+        let series = vec![
+            hot("2026-01-01 10:00:00"),
+            hot("2026-01-01 10:15:00"),
+            hot("2026-01-01 11:00:00"),
+            hot("2026-01-01 11:15:00"),
+        ];
+        assert_eq!(eval_rule(&r, &series, 900).fault_hours, 0.0);
+    }
+
+    #[test]
+    fn expression_rule_uses_confirm_streak() {
+        let series = vec![
+            sample("00:00", &[("fan_cmd", 80.0), ("sat", 62.0), ("sat_sp", 55.0)]),
+            sample("00:01", &[("fan_cmd", 80.0), ("sat", 62.0), ("sat_sp", 55.0)]),
+            sample("00:02", &[("fan_cmd", 80.0), ("sat", 62.0), ("sat_sp", 55.0)]),
+            sample("00:03", &[("fan_cmd", 80.0), ("sat", 62.0), ("sat_sp", 55.0)]),
+        ];
+        let mut r = rule(
+            "Expression",
+            json!({"expression":"frac(fan_cmd) > 0.05 && abs(sat - sat_sp) > 5","confirm_rows":4,"poll_seconds":300}),
+            &["fan_cmd", "sat", "sat_sp"],
+        );
+        let f = eval_rule(&r, &series, 0);
+        assert!((f.fault_hours - (300.0 / 3600.0)).abs() < 1e-9, "got {}", f.fault_hours);
+
+        r.params["confirm_rows"] = json!(5);
+        assert_eq!(eval_rule(&r, &series, 0).fault_hours, 0.0);
+    }
+
+    #[test]
+    fn expression_rule_with_bad_expression_reports_error() {
+        let series = vec![sample("00:00", &[("sat", 62.0)])];
+        let r = rule("Expression", json!({"expression":"sat >","confirm_rows":1}), &["sat"]);
+        let f = eval_rule(&r, &series, 0);
+        assert_eq!(f.fault_hours, 0.0);
+        assert!(f.evidence.get("error").is_some());
+    }
+
     #[test]
     fn econ1_stuck_closed_when_fan_running() {
         let series = vec![
@@ -466,7 +661,7 @@ mod tests {
             json!({"confirm_rows":4,"poll_seconds":300}),
             &["oa_t", "mat", "fan_cmd", "damper_pct"],
         );
-        let f = eval_rule(&r, &series);
+        let f = eval_rule(&r, &series, 0);
         assert!(f.fault_hours > 0.0, "expected fault, got {}", f.fault_hours);
     }
 }
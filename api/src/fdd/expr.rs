@@ -0,0 +1,464 @@
+//! Safe condition expressions for the `Expression` rule kind.
+//!
+//! A tiny arithmetic/boolean language over role names, parsed once and then
+//! evaluated per sample, e.g. `fan_cmd > 0.05 && abs(sat - sat_sp) > 5`.
+//! No assignment, no loops, no host access — only numbers, roles, operators
+//! and a fixed set of pure functions.
+//!
+//! Grammar (lowest → highest precedence):
+//! `||`, `&&`, `== !=`, `< <= > >=`, `+ -`, `* / %`, unary `- !`, primary.
+//! Booleans are numbers: comparisons yield 1.0 / 0.0, any non-zero is true.
+
+use std::collections::{BTreeSet, HashMap};
+
+/// Longest expression accepted, to keep rule rows sane.
+const MAX_EXPR_LEN: usize = 1024;
+/// Nesting limit so a hostile rule can't blow the stack.
+const MAX_DEPTH: usize = 64;
+
+/// Parsed expression tree.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Num(f64),
+    Var(String),
+    Neg(Box<Expr>),
+    Not(Box<Expr>),
+    Bin(BinOp, Box<Expr>, Box<Expr>),
+    Call(Func, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+    And,
+    Or,
+}
+
+/// Built-in pure functions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Func {
+    Abs,
+    Min,
+    Max,
+    Clamp,
+    /// Normalizes a 0–100 % command to 0–1 (values ≤ 1 pass through),
+    /// the same scaling the built-in fan rules apply.
+    Frac,
+}
+
+impl Func {
+    fn from_name(name: &str) -> Option<Func> {
+        match name {
+            "abs" => Some(Func::Abs),
+            "min" => Some(Func::Min),
+            "max" => Some(Func::Max),
+            "clamp" => Some(Func::Clamp),
+            "frac" => Some(Func::Frac),
+            _ => None,
+        }
+    }
+
+    /// (min, max) argument count.
+    fn arity(&self) -> (usize, usize) {
+        match self {
+            Func::Abs | Func::Frac => (1, 1),
+            Func::Min | Func::Max => (2, 8),
+            Func::Clamp => (3, 3),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(f64),
+    Ident(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+    Comma,
+}
+
+fn tokenize(src: &str) -> Result<Vec<Token>, String> {
+    const OPS: &[&str] = &[
+        "&&", "||", "==", "!=", "<=", ">=", "<", ">", "+", "-", "*", "/", "%", "!",
+    ];
+    let chars: Vec<char> = src.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    'outer: while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(|d| d.is_ascii_digit())) {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            // Optional exponent: 1e-3
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                let mut j = i + 1;
+                if j < chars.len() && (chars[j] == '+' || chars[j] == '-') {
+                    j += 1;
+                }
+                if j < chars.len() && chars[j].is_ascii_digit() {
+                    i = j;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            let n = text
+                .parse::<f64>()
+                .map_err(|_| format!("invalid number '{}' at {}", text, start))?;
+            tokens.push(Token::Num(n));
+            continue;
+        }
+        if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+            continue;
+        }
+        match c {
+            '(' => tokens.push(Token::LParen),
+            ')' => tokens.push(Token::RParen),
+            ',' => tokens.push(Token::Comma),
+            _ => {
+                for op in OPS {
+                    let len = op.len();
+                    if i + len <= chars.len() && chars[i..i + len].iter().collect::<String>() == *op {
+                        tokens.push(Token::Op(op));
+                        i += len;
+                        continue 'outer;
+                    }
+                }
+                return Err(format!("unexpected character '{}' at {}", c, i));
+            }
+        }
+        i += 1;
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let t = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        t
+    }
+
+    fn binop(op: &str) -> Option<(BinOp, u8)> {
+        Some(match op {
+            "||" => (BinOp::Or, 1),
+            "&&" => (BinOp::And, 2),
+            "==" => (BinOp::Eq, 3),
+            "!=" => (BinOp::Ne, 3),
+            "<" => (BinOp::Lt, 4),
+            "<=" => (BinOp::Le, 4),
+            ">" => (BinOp::Gt, 4),
+            ">=" => (BinOp::Ge, 4),
+            "+" => (BinOp::Add, 5),
+            "-" => (BinOp::Sub, 5),
+            "*" => (BinOp::Mul, 6),
+            "/" => (BinOp::Div, 6),
+            "%" => (BinOp::Rem, 6),
+            _ => return None,
+        })
+    }
+
+    /// Precedence climbing: parse operators binding at least `min_prec`.
+    fn expr(&mut self, min_prec: u8) -> Result<Expr, String> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err("expression nested too deeply".to_string());
+        }
+        let mut lhs = self.unary()?;
+        while let Some(Token::Op(op)) = self.peek() {
+            let Some((bin, prec)) = Self::binop(op) else { break };
+            if prec < min_prec {
+                break;
+            }
+            self.next();
+            let rhs = self.expr(prec + 1)?;
+            lhs = Expr::Bin(bin, Box::new(lhs), Box::new(rhs));
+        }
+        self.depth -= 1;
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        let wrap: fn(Box<Expr>) -> Expr = match self.peek() {
+            Some(Token::Op("-")) => Expr::Neg,
+            Some(Token::Op("!")) => Expr::Not,
+            _ => return self.primary(),
+        };
+        self.next();
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err("expression nested too deeply".to_string());
+        }
+        let inner = self.unary()?;
+        self.depth -= 1;
+        Ok(wrap(Box::new(inner)))
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Num(n)) => Ok(Expr::Num(n)),
+            Some(Token::LParen) => {
+                let e = self.expr(0)?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(e),
+                    _ => Err("missing ')'".to_string()),
+                }
+            }
+            Some(Token::Ident(name)) => {
+                if self.peek() != Some(&Token::LParen) {
+                    return Ok(match name.as_str() {
+                        "true" => Expr::Num(1.0),
+                        "false" => Expr::Num(0.0),
+                        _ => Expr::Var(name),
+                    });
+                }
+                let func = Func::from_name(&name)
+                    .ok_or_else(|| format!("unknown function '{}'", name))?;
+                self.next(); // '('
+                let mut args = Vec::new();
+                if self.peek() == Some(&Token::RParen) {
+                    self.next();
+                } else {
+                    loop {
+                        args.push(self.expr(0)?);
+                        match self.next() {
+                            Some(Token::Comma) => continue,
+                            Some(Token::RParen) => break,
+                            _ => return Err(format!("expected ',' or ')' in call to {}", name)),
+                        }
+                    }
+                }
+                let (lo, hi) = func.arity();
+                if args.len() < lo || args.len() > hi {
+                    return Err(format!(
+                        "{}() takes {} argument(s), got {}",
+                        name,
+                        if lo == hi { lo.to_string() } else { format!("{}–{}", lo, hi) },
+                        args.len()
+                    ));
+                }
+                Ok(Expr::Call(func, args))
+            }
+            Some(t) => Err(format!("unexpected token {:?}", t)),
+            None => Err("unexpected end of expression".to_string()),
+        }
+    }
+}
+
+/// Parse an expression string into an AST.
+pub fn parse(src: &str) -> Result<Expr, String> {
+    if src.trim().is_empty() {
+        return Err("expression is empty".to_string());
+    }
+    if src.len() > MAX_EXPR_LEN {
+        return Err(format!("expression longer than {} characters", MAX_EXPR_LEN));
+    }
+    let mut p = Parser {
+        tokens: tokenize(src)?,
+        pos: 0,
+        depth: 0,
+    };
+    let e = p.expr(0)?;
+    if let Some(t) = p.peek() {
+        return Err(format!("unexpected trailing token {:?}", t));
+    }
+    Ok(e)
+}
+
+impl Expr {
+    /// Every role name the expression reads.
+    pub fn variables(&self) -> BTreeSet<String> {
+        let mut out = BTreeSet::new();
+        self.collect_vars(&mut out);
+        out
+    }
+
+    fn collect_vars(&self, out: &mut BTreeSet<String>) {
+        match self {
+            Expr::Num(_) => {}
+            Expr::Var(v) => {
+                out.insert(v.clone());
+            }
+            Expr::Neg(e) | Expr::Not(e) => e.collect_vars(out),
+            Expr::Bin(_, a, b) => {
+                a.collect_vars(out);
+                b.collect_vars(out);
+            }
+            Expr::Call(_, args) => args.iter().for_each(|a| a.collect_vars(out)),
+        }
+    }
+
+    /// Evaluate against one sample. `None` when a referenced role has no value
+    /// at this timestamp (the sample is then treated as healthy, like the
+    /// built-in rule kinds do).
+    pub fn eval(&self, values: &HashMap<String, f64>) -> Option<f64> {
+        let truth = |b: bool| if b { 1.0 } else { 0.0 };
+        Some(match self {
+            Expr::Num(n) => *n,
+            Expr::Var(v) => *values.get(v)?,
+            Expr::Neg(e) => -e.eval(values)?,
+            Expr::Not(e) => truth(e.eval(values)? == 0.0),
+            Expr::Bin(BinOp::And, a, b) => {
+                // Short-circuit so `fan_cmd > 0 && x` doesn't need x when the fan is off.
+                if a.eval(values)? == 0.0 {
+                    0.0
+                } else {
+                    truth(b.eval(values)? != 0.0)
+                }
+            }
+            Expr::Bin(BinOp::Or, a, b) => {
+                if a.eval(values)? != 0.0 {
+                    1.0
+                } else {
+                    truth(b.eval(values)? != 0.0)
+                }
+            }
+            Expr::Bin(op, a, b) => {
+                let (x, y) = (a.eval(values)?, b.eval(values)?);
+                match op {
+                    BinOp::Add => x + y,
+                    BinOp::Sub => x - y,
+                    BinOp::Mul => x * y,
+                    BinOp::Div | BinOp::Rem if y == 0.0 => return None,
+                    BinOp::Div => x / y,
+                    BinOp::Rem => x % y,
+                    BinOp::Lt => truth(x < y),
+                    BinOp::Le => truth(x <= y),
+                    BinOp::Gt => truth(x > y),
+                    BinOp::Ge => truth(x >= y),
+                    BinOp::Eq => truth((x - y).abs() < 1e-9),
+                    BinOp::Ne => truth((x - y).abs() >= 1e-9),
+                    BinOp::And | BinOp::Or => unreachable!(),
+                }
+            }
+            Expr::Call(f, args) => {
+                let vals = args
+                    .iter()
+                    .map(|a| a.eval(values))
+                    .collect::<Option<Vec<f64>>>()?;
+                match f {
+                    Func::Abs => vals[0].abs(),
+                    Func::Min => vals.iter().cloned().fold(f64::INFINITY, f64::min),
+                    Func::Max => vals.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+                    Func::Clamp => vals[0].max(vals[1]).min(vals[2]),
+                    Func::Frac => {
+                        if vals[0] > 1.0 {
+                            vals[0] / 100.0
+                        } else {
+                            vals[0]
+                        }
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Validate an `Expression` rule: the expression must parse and every role it
+/// reads must be listed in `required_roles` (so `analyze` checks the mapping).
+pub fn validate(src: &str, required_roles: &[String]) -> Result<Expr, String> {
+    let expr = parse(src).map_err(|e| format!("invalid expression: {}", e))?;
+    let missing: Vec<String> = expr
+        .variables()
+        .into_iter()
+        .filter(|v| !required_roles.contains(v))
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!(
+            "expression uses roles not listed in required_roles: {}",
+            missing.join(", ")
+        ));
+    }
+    Ok(expr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vals(pairs: &[(&str, f64)]) -> HashMap<String, f64> {
+        pairs.iter().map(|(k, v)| (k.to_string(), *v)).collect()
+    }
+
+    #[test]
+    fn evaluates_arithmetic_with_precedence() {
+        let e = parse("1 + 2 * 3 - -4 / 2").unwrap();
+        assert_eq!(e.eval(&HashMap::new()), Some(9.0));
+        let e = parse("(1 + 2) * 3 % 4").unwrap();
+        assert_eq!(e.eval(&HashMap::new()), Some(1.0));
+    }
+
+    #[test]
+    fn evaluates_the_documented_example() {
+        let e = parse("fan_cmd > 0.05 && abs(sat - sat_sp) > 5").unwrap();
+        assert_eq!(e.eval(&vals(&[("fan_cmd", 0.8), ("sat", 62.0), ("sat_sp", 55.0)])), Some(1.0));
+        assert_eq!(e.eval(&vals(&[("fan_cmd", 0.8), ("sat", 57.0), ("sat_sp", 55.0)])), Some(0.0));
+        // Fan off short-circuits: sat values are not needed.
+        assert_eq!(e.eval(&vals(&[("fan_cmd", 0.0)])), Some(0.0));
+        // Fan on but a role missing → no verdict.
+        assert_eq!(e.eval(&vals(&[("fan_cmd", 0.8)])), None);
+    }
+
+    #[test]
+    fn supports_functions_and_boolean_ops() {
+        let e = parse("!(max(a, b, 3) < 4) || clamp(frac(a), 0, 0.5) == 0.5").unwrap();
+        assert_eq!(e.eval(&vals(&[("a", 80.0), ("b", 1.0)])), Some(1.0));
+        assert_eq!(e.eval(&vals(&[("a", 0.1), ("b", 1.0)])), Some(0.0));
+    }
+
+    #[test]
+    fn division_by_zero_yields_no_verdict() {
+        assert_eq!(parse("a / b > 1").unwrap().eval(&vals(&[("a", 1.0), ("b", 0.0)])), None);
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        for bad in ["", "a >", "(a > 1", "a > 1)", "a $ b", "system(1)", "abs(1, 2)", "a b"] {
+            assert!(parse(bad).is_err(), "expected parse error for {:?}", bad);
+        }
+        let deep = format!("{}1{}", "(".repeat(200), ")".repeat(200));
+        assert!(parse(&deep).is_err());
+        assert!(parse(&"-".repeat(200)).is_err());
+    }
+
+    #[test]
+    fn validate_requires_roles_to_be_declared() {
+        let roles = vec!["fan_cmd".to_string(), "sat".to_string()];
+        assert!(validate("fan_cmd > 0 && sat > 60", &roles).is_ok());
+        let err = validate("fan_cmd > 0 && sat_sp > 60", &roles).unwrap_err();
+        assert!(err.contains("sat_sp"), "{}", err);
+    }
+}
@@ -0,0 +1,125 @@
+//! Energy / cost impact — what a detected fault is costing the site.
+//!
+//! A rule opts in with an `impact` object in `params_json`:
+//!
+//! ```json
+//! {"impact": {"kw": 3.5}}                          // 3.5 kW wasted while the fault is active
+//! {"impact": {"kw": 3.5, "price_per_kwh": 0.22}}   // with a rule-specific tariff
+//! ```
+//!
+//! Estimated kWh = kW penalty × fault hours; cost = kWh × tariff. The site
+//! tariff is stored in APPLICATION_CONFIG under `fdd.tariff`, the same table the
+//! rest of the app's settings live in. Without a tariff only kWh is estimated.
+
+use sea_orm::{ConnectionTrait, DatabaseConnection};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::server_db::ApplicationConfigService;
+
+/// APPLICATION_CONFIG key holding the site tariff.
+pub const TARIFF_CONFIG_KEY: &str = "fdd.tariff";
+
+/// Site electricity tariff.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tariff {
+    pub price_per_kwh: f64,
+    #[serde(default = "default_currency")]
+    pub currency: String,
+}
+
+fn default_currency() -> String {
+    "USD".into()
+}
+
+/// Per-rule impact model, read from `params_json.impact`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImpactModel {
+    /// Extra electrical load while the fault is active.
+    pub kw: f64,
+    /// Overrides the site tariff for this rule (e.g. a gas-fired load priced per kWh equivalent).
+    pub price_per_kwh: Option<f64>,
+}
+
+/// Estimated impact of one finding.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Impact {
+    pub kwh: f64,
+    /// `None` when no tariff is configured.
+    pub cost: Option<f64>,
+}
+
+impl ImpactModel {
+    /// The rule's impact model, or `None` when it has none (or a non-positive kW).
+    pub fn from_params(params: &Value) -> Option<Self> {
+        let impact = params.get("impact")?;
+        let kw = impact.get("kw").and_then(|v| v.as_f64()).filter(|kw| *kw > 0.0)?;
+        Some(Self {
+            kw,
+            price_per_kwh: impact.get("price_per_kwh").and_then(|v| v.as_f64()),
+        })
+    }
+
+    pub fn estimate(&self, fault_hours: f64, tariff: Option<&Tariff>) -> Impact {
+        let kwh = self.kw * fault_hours;
+        let price = self.price_per_kwh.or(tariff.map(|t| t.price_per_kwh));
+        Impact {
+            kwh,
+            cost: price.map(|p| kwh * p),
+        }
+    }
+}
+
+/// The site tariff, if one has been configured. A missing APPLICATION_CONFIG
+/// table or a malformed value is treated as "no tariff".
+pub async fn load_tariff(db: &DatabaseConnection) -> Option<Tariff> {
+    let sql = format!(
+        "SELECT config_value FROM APPLICATION_CONFIG WHERE config_key = '{}' \
+         AND user_id IS NULL AND device_serial IS NULL AND panel_id IS NULL LIMIT 1",
+        TARIFF_CONFIG_KEY
+    );
+    let row = db
+        .query_one(sea_orm::Statement::from_string(sea_orm::DatabaseBackend::Sqlite, sql))
+        .await
+        .ok()??;
+    let raw: String = row.try_get("", "config_value").ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+/// Store the site tariff (global scope).
+pub async fn save_tariff(db: &DatabaseConnection, tariff: &Tariff) -> Result<(), String> {
+    if !tariff.price_per_kwh.is_finite() || tariff.price_per_kwh < 0.0 {
+        return Err("price_per_kwh must be a non-negative number".into());
+    }
+    let value = serde_json::to_value(tariff).map_err(|e| e.to_string())?;
+    ApplicationConfigService::set_config(db, TARIFF_CONFIG_KEY.to_string(), value, None, None, None, None)
+        .await
+        .map_err(|e| format!("Tariff save failed: {}", e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn rules_without_impact_have_no_model() {
+        assert_eq!(ImpactModel::from_params(&json!({"limit": 100})), None);
+        assert_eq!(ImpactModel::from_params(&json!({"impact": {"kw": 0}})), None);
+    }
+
+    #[test]
+    fn kwh_and_cost_follow_fault_hours() {
+        let model = ImpactModel::from_params(&json!({"impact": {"kw": 4.0}})).unwrap();
+        let tariff = Tariff {
+            price_per_kwh: 0.25,
+            currency: "USD".into(),
+        };
+        assert_eq!(model.estimate(2.5, Some(&tariff)), Impact { kwh: 10.0, cost: Some(2.5) });
+        assert_eq!(model.estimate(2.5, None).cost, None);
+
+        let own_price = ImpactModel::from_params(&json!({"impact": {"kw": 4.0, "price_per_kwh": 0.5}})).unwrap();
+        assert_eq!(own_price.estimate(1.0, Some(&tariff)).cost, Some(2.0));
+    }
+}
@@ -6,38 +6,177 @@
 //! for time-series.
 
 pub mod evaluator;
+pub mod expr;
+pub mod impact;
 pub mod roles;
+pub mod routes;
 pub mod rules;
+pub mod scheduler;
 pub mod series;
 
 use sea_orm::DatabaseConnection;
 use serde_json::{json, Value};
+use std::collections::HashMap;
 
 /// Ensure the FDD_RULES table exists and is seeded (idempotent).
 pub async fn ensure_schema(db: &DatabaseConnection) -> Result<(), String> {
     rules::ensure_schema(db).await
 }
 
+/// Longest history one analysis may cover.
+pub const MAX_RANGE_HOURS: u64 = 24 * 366;
+/// Most resample buckets one analysis may produce.
+pub const MAX_BUCKETS: u64 = 100_000;
+
+/// Reject an analysis window or resample config outside sane limits.
+pub fn check_bounds(range_hours: u64, resample: Option<&series::ResampleConfig>) -> Result<(), String> {
+    if range_hours == 0 || range_hours > MAX_RANGE_HOURS {
+        return Err(format!("range_hours must be between 1 and {}", MAX_RANGE_HOURS));
+    }
+    let Some(cfg) = resample else { return Ok(()) };
+    let range_seconds = range_hours * 3600;
+    if cfg.bucket_seconds > range_seconds {
+        return Err(format!("bucket_seconds must not exceed the range ({} s)", range_seconds));
+    }
+    if cfg.bucket_seconds > 0 && range_seconds / cfg.bucket_seconds > MAX_BUCKETS {
+        return Err(format!(
+            "bucket_seconds must be at least {} for a {} h range",
+            range_seconds.div_ceil(MAX_BUCKETS),
+            range_hours
+        ));
+    }
+    if cfg.max_staleness_seconds > range_seconds {
+        return Err(format!("max_staleness_seconds must not exceed the range ({} s)", range_seconds));
+    }
+    Ok(())
+}
+
+/// The alignment `rule` is evaluated on: `resample` when given, otherwise
+/// buckets of the rule's own `poll_seconds`, held to the same bounds.
+pub fn rule_resample(
+    rule: &rules::Rule,
+    range_hours: u64,
+    resample: Option<series::ResampleConfig>,
+) -> Result<series::ResampleConfig, String> {
+    if let Some(cfg) = resample {
+        return Ok(cfg);
+    }
+    let cfg = series::ResampleConfig::for_interval(scheduler::poll_seconds(rule));
+    check_bounds(range_hours, Some(&cfg)).map_err(|e| format!("rule {} (poll_seconds): {}", rule.rule_id, e))?;
+    Ok(cfg)
+}
+
 /// Run fault detection for a device over `range_hours`.
 ///
+/// With an `equipment` name only that instance is analyzed. With an empty name
+/// every equipment instance found on the device (`equipRef` / `isPointOf`
+/// tags) is analyzed separately and the findings are combined, each tagged with
+/// its equipment; a device without equipment tags is analyzed as one unit.
+/// Once a device is analyzed per instance, its device-wide faults are cleared.
+///
+/// An instance that fails to analyze carries its `error` in `by_equipment`
+/// and the others still report; only when every instance fails is it an error.
+///
 /// `rule_ids` filters which rules to run; empty = all enabled rules.
+/// `resample` sets the series alignment for every rule; `None` buckets each
+/// rule at its own `poll_seconds`.
 pub async fn analyze(
     db: &DatabaseConnection,
     serial: i32,
     equipment: &str,
     range_hours: u64,
     rule_ids: &[String],
+    resample: Option<series::ResampleConfig>,
 ) -> Result<Value, String> {
+    check_bounds(range_hours, resample.as_ref())?;
     rules::ensure_schema(db).await?;
 
-    // 1. tags → roles
-    let role_map = roles::load_role_map(db, serial).await?;
+    let instances = if equipment.is_empty() {
+        roles::list_equipment(db, serial).await?
+    } else {
+        Vec::new()
+    };
+    if instances.is_empty() {
+        return analyze_equipment(db, serial, equipment, range_hours, rule_ids, resample).await;
+    }
+    // Faults from before the device had equipment tags were recorded for the
+    // whole device; nothing analyzes it as one unit any more, so close them.
+    if let Err(e) = rules::clear_equipment_findings(db, serial, "").await {
+        tracing::warn!("FDD: failed to clear device-wide faults on device {}: {}", serial, e);
+    }
+
+    let mut results = Vec::with_capacity(instances.len());
+    let mut errors = Vec::new();
+    for eq in &instances {
+        match analyze_equipment(db, serial, eq, range_hours, rule_ids, resample).await {
+            Ok(result) => results.push(result),
+            Err(e) => {
+                tracing::warn!("FDD: analysis of {} on device {} failed: {}", eq, serial, e);
+                errors.push(format!("{}: {}", eq, e));
+                results.push(json!({ "device": serial, "equipment": eq, "error": e, "findings": [] }));
+            }
+        }
+    }
+    if errors.len() == instances.len() {
+        return Err(errors.join("; "));
+    }
+    let findings: Vec<Value> = results
+        .iter()
+        .flat_map(|r| r["findings"].as_array().cloned().unwrap_or_default())
+        .collect();
+    let roles_found: std::collections::BTreeSet<&str> = results
+        .iter()
+        .flat_map(|r| r["roles_found"].as_array().into_iter().flatten())
+        .filter_map(|v| v.as_str())
+        .collect();
+    let sample_count: u64 = results.iter().filter_map(|r| r["sample_count"].as_u64()).sum();
+    Ok(json!({
+        "device": serial,
+        "equipment": "",
+        "equipment_instances": instances,
+        "range_hours": range_hours,
+        "roles_found": roles_found,
+        "sample_count": sample_count,
+        "findings": findings,
+        "by_equipment": results,
+    }))
+}
+
+/// Run the rule set against one equipment instance ("" = the whole device).
+async fn analyze_equipment(
+    db: &DatabaseConnection,
+    serial: i32,
+    equipment: &str,
+    range_hours: u64,
+    rule_ids: &[String],
+    resample: Option<series::ResampleConfig>,
+) -> Result<Value, String> {
+    // 1. tags → roles (manual overrides in FDD_ROLE_OVERRIDES win over inference)
+    let role_map = roles::resolve_role_map(db, serial, equipment).await?;
+    let overridden: Vec<String> = roles::list_overrides(db, serial, Some(equipment))
+        .await?
+        .into_iter()
+        .map(|o| o.role)
+        .collect();
 
-    // 2. trendlogs → samples (wide, one row per timestamp)
-    let series = series::load_series(db, serial, &role_map, range_hours).await?;
+    // 2. trendlogs → samples (wide, resampled onto fixed buckets). Each rule
+    // is bucketed at its own poll so its confirm window keeps its length.
+    let rules_list = rules::get_rules(db, rule_ids).await?;
+    let rows = series::load_rows(db, serial, &role_map, range_hours).await?;
+    let mut aligned: HashMap<series::ResampleConfig, Vec<series::Sample>> = HashMap::new();
+    for rule in &rules_list {
+        let cfg = rule_resample(rule, range_hours, resample)?;
+        aligned.entry(cfg).or_insert_with(|| series::align(&rows, &cfg));
+    }
+    // Reported counts describe the finest alignment used.
+    let finest = aligned.keys().min_by_key(|c| c.bucket_seconds).copied().unwrap_or_default();
+    let (sample_count, gap_count) = aligned
+        .get(&finest)
+        .map(|s| (s.len(), s.iter().filter(|s| s.gap).count()))
+        .unwrap_or((0, 0));
 
     // 3. rules → findings
-    let rules_list = rules::get_rules(db, rule_ids).await?;
+    let tariff = impact::load_tariff(db).await;
     let mut findings: Vec<Value> = Vec::new();
 
     for rule in rules_list {
@@ -50,41 +189,60 @@ pub async fn analyze(
             .collect();
 
         if !missing.is_empty() {
+            // The rule can't run any more, so a fault it opened earlier is stale.
+            let lifecycle = clear_active(db, serial, equipment, &rule.rule_id).await;
             findings.push(json!({
                 "rule_id": rule.rule_id,
                 "rule_name": rule.rule_name,
+                "equipment": equipment,
                 "severity": rule.severity,
                 "status": "insufficient_roles",
                 "missing_roles": missing,
                 "fault_hours": 0.0,
+                "fault_status": lifecycle,
             }));
             continue;
         }
 
-        let finding = evaluator::eval_rule(&rule, &series);
-        // Persist any active fault so t3000_fdd_faults can query history.
-        if finding.fault_hours > 0.0 {
-            let _ = rules::persist_finding(
-                db,
-                serial,
-                equipment,
-                &rule.rule_id,
-                &rule.rule_name,
-                &finding.severity,
-                finding.fault_hours,
-                &finding.evidence,
-            )
-            .await;
-        }
+        let cfg = rule_resample(&rule, range_hours, resample)?;
+        let finding = evaluator::eval_rule(&rule, &aligned[&cfg], cfg.bucket_seconds);
+        let estimate = impact::ImpactModel::from_params(&rule.params)
+            .map(|m| m.estimate(finding.fault_hours, tariff.as_ref()));
+        // Track the fault lifecycle so t3000_fdd_faults shows what is still active:
+        // a detection opens/extends the record, a healthy pass clears it.
+        let lifecycle = if finding.fault_hours > 0.0 {
+            let record = rules::FindingRecord {
+                rule_id: &rule.rule_id,
+                rule_name: &rule.rule_name,
+                severity: &finding.severity,
+                fault_hours: finding.fault_hours,
+                evidence: &finding.evidence,
+                impact: estimate,
+            };
+            match rules::persist_finding(db, serial, equipment, &record).await {
+                Ok(status) => Some(status.as_str()),
+                Err(e) => {
+                    tracing::warn!("FDD: failed to record {} on device {}: {}", rule.rule_id, serial, e);
+                    None
+                }
+            }
+        } else {
+            clear_active(db, serial, equipment, &rule.rule_id).await
+        };
         findings.push(json!({
             "rule_id": rule.rule_id,
             "rule_name": rule.rule_name,
+            "equipment": equipment,
             "category": rule.category,
             "severity": finding.severity,
             "status": "ok",
+            "bucket_seconds": cfg.bucket_seconds,
             "fault_hours": finding.fault_hours,
             "evidence": finding.evidence,
+            "est_kwh": estimate.map(|i| i.kwh),
+            "est_cost": estimate.and_then(|i| i.cost),
             "suggestion": evaluator::suggestion(&rule.rule_id),
+            "fault_status": lifecycle,
         }));
     }
 
@@ -94,19 +252,60 @@ pub async fn analyze(
         "equipment": equipment,
         "range_hours": range_hours,
         "roles_found": roles_found,
-        "sample_count": series.len(),
+        "roles_overridden": overridden,
+        "sample_count": sample_count,
+        "gap_count": gap_count,
+        "resample": {
+            "bucket_seconds": finest.bucket_seconds,
+            "max_staleness_seconds": finest.max_staleness_seconds,
+        },
         "findings": findings,
     }))
 }
 
-/// Query persisted FDD findings (optionally filtered by device / rule).
+/// Clear the active fault of `rule_id`, if any; the lifecycle status to report.
+async fn clear_active(db: &DatabaseConnection, serial: i32, equipment: &str, rule_id: &str) -> Option<&'static str> {
+    match rules::clear_finding(db, serial, equipment, rule_id).await {
+        Ok(true) => Some(rules::FaultStatus::Cleared.as_str()),
+        Ok(false) => None,
+        Err(e) => {
+            tracing::warn!("FDD: failed to clear {} on device {}: {}", rule_id, serial, e);
+            None
+        }
+    }
+}
+
+/// Query persisted FDD findings (optionally filtered by device / rule / lifecycle status).
 pub async fn list_findings(
     db: &DatabaseConnection,
     serial: Option<i32>,
     rule_id: Option<&str>,
+    status: Option<&str>,
     limit: u64,
 ) -> Result<Value, String> {
     rules::ensure_schema(db).await?;
-    let items = rules::list_findings(db, serial, rule_id, limit).await?;
+    let items = rules::list_findings(db, serial, rule_id, status, limit).await?;
     Ok(json!({ "findings": items, "total": items.len() }))
 }
+
+/// Most findings one cost ranking may return.
+pub const MAX_TOP_FAULTS: u64 = 500;
+
+/// Findings ranked by estimated cost, with the site tariff used for the estimate.
+pub async fn top_faults_by_cost(
+    db: &DatabaseConnection,
+    filter: &rules::FindingsFilter,
+    limit: u64,
+) -> Result<Value, String> {
+    rules::ensure_schema(db).await?;
+    let items = rules::top_faults_by_cost(db, filter, limit).await?;
+    let total_cost: f64 = items.iter().filter_map(|f| f["est_cost"].as_f64()).sum();
+    let total_kwh: f64 = items.iter().filter_map(|f| f["est_kwh"].as_f64()).sum();
+    Ok(json!({
+        "findings": items,
+        "total": items.len(),
+        "total_est_kwh": total_kwh,
+        "total_est_cost": total_cost,
+        "tariff": impact::load_tariff(db).await,
+    }))
+}
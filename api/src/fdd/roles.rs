@@ -2,12 +2,39 @@
 //!
 //! Mirrors open-fdd's `column_map` concept: physical points are mapped to the
 //! semantic roles the rules operate on (oa_t, sat, mat, rat, zone_t, ...).
+//!
+//! A controller can serve several pieces of equipment (two AHUs, a VAV next to
+//! an AHU), so points are grouped into equipment instances by `equipRef` /
+//! Brick `isPointOf` tags and each instance gets its own role map.
+//!
+//! Tag inference is only a first guess. Commissioning engineers can pin a role
+//! to a specific point in FDD_ROLE_OVERRIDES, keyed by (serial, equipment, role);
+//! `resolve_role_map` layers those overrides on top of the inferred map.
 
 use sea_orm::ConnectionTrait;
-use std::collections::HashMap;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+const OVERRIDES_DDL: &str = "
+CREATE TABLE IF NOT EXISTS FDD_ROLE_OVERRIDES (
+    id            INTEGER PRIMARY KEY AUTOINCREMENT,
+    serial_number INTEGER NOT NULL,
+    equipment     TEXT NOT NULL DEFAULT '',
+    role          TEXT NOT NULL,
+    point_type    TEXT NOT NULL,
+    point_index   INTEGER NOT NULL,
+    point_id      TEXT,
+    note          TEXT,
+    updated_at    TEXT DEFAULT (datetime('now')),
+    UNIQUE (serial_number, equipment, role)
+)";
+
+/// Point types a role can be mapped to.
+pub const POINT_TYPES: &[&str] = &["INPUT", "OUTPUT", "VARIABLE"];
 
 /// A point identified on a device by type + index (plus its human point id).
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct RolePoint {
     pub point_type: String, // INPUT | OUTPUT | VARIABLE
     pub point_index: i32,
@@ -43,11 +70,34 @@ pub fn infer_role(tags: &[String]) -> Option<String> {
     }
 }
 
-/// Load the role map for a device: role → first point whose tags infer it.
-pub async fn load_role_map(
+/// Equipment instance a point belongs to, from a Haystack `equipRef`-style tag
+/// (`equipRef:@AHU-1`, `equipRef=AHU-1`) or a Brick `isPointOf` relationship
+/// (`isPointOf:AHU-1`, `brick:isPointOf:AHU-1`).
+pub fn equipment_ref(tags: &[String]) -> Option<String> {
+    tags.iter().find_map(|t| {
+        let t = t.trim();
+        let t = t.strip_prefix("brick:").unwrap_or(t);
+        let (key, value) = t.split_once([':', '='])?;
+        if !key.eq_ignore_ascii_case("equipRef") && !key.eq_ignore_ascii_case("isPointOf") {
+            return None;
+        }
+        let value = value.trim().trim_matches('"').trim_start_matches('@').trim();
+        (!value.is_empty()).then(|| value.to_string())
+    })
+}
+
+/// A tagged point with its equipment reference split out of its marker tags.
+struct TaggedPoint {
+    point: RolePoint,
+    equipment: Option<String>,
+    tags: Vec<String>,
+}
+
+/// All tagged points on a device, ordered by (point_type, index).
+async fn load_tagged_points(
     db: &sea_orm::DatabaseConnection,
     serial: i32,
-) -> Result<HashMap<String, RolePoint>, String> {
+) -> Result<Vec<TaggedPoint>, String> {
     let sql = format!(
         "SELECT point_type, point_index, point_id, tag_name FROM HAYSTACK_POINT_TAGS WHERE serial_number = {}",
         serial
@@ -58,7 +108,7 @@ pub async fn load_role_map(
         .map_err(|e| format!("Role map query failed: {}", e))?;
 
     // Group tags per point (point_type, normalized index).
-    let mut by_point: HashMap<(String, i32), (String, Vec<String>)> = HashMap::new();
+    let mut by_point: BTreeMap<(String, i32), (String, Vec<String>)> = BTreeMap::new();
     for r in &rows {
         let pt: String = r.try_get("", "point_type").unwrap_or_default();
         let idx_str: String = r.try_get("", "point_index").unwrap_or_default();
@@ -78,15 +128,388 @@ pub async fn load_role_map(
             .push(tag);
     }
 
-    let mut map: HashMap<String, RolePoint> = HashMap::new();
-    for ((pt, idx), (point_id, tags)) in by_point {
-        if let Some(role) = infer_role(&tags) {
-            map.entry(role).or_insert(RolePoint {
-                point_type: pt,
-                point_index: idx,
-                point_id,
-            });
+    Ok(by_point
+        .into_iter()
+        .map(|((pt, idx), (point_id, tags))| {
+            let equipment = equipment_ref(&tags);
+            // Ref tags carry ids, not semantics; keep them out of role inference.
+            let tags = tags
+                .into_iter()
+                .filter(|t| equipment_ref(std::slice::from_ref(t)).is_none())
+                .collect();
+            TaggedPoint {
+                point: RolePoint {
+                    point_type: pt,
+                    point_index: idx,
+                    point_id,
+                },
+                equipment,
+                tags,
+            }
+        })
+        .collect())
+}
+
+/// Equipment instances on a device (distinct `equipRef` / `isPointOf` targets).
+pub async fn list_equipment(
+    db: &sea_orm::DatabaseConnection,
+    serial: i32,
+) -> Result<Vec<String>, String> {
+    let set: BTreeSet<String> = load_tagged_points(db, serial)
+        .await?
+        .into_iter()
+        .filter_map(|p| p.equipment)
+        .collect();
+    Ok(set.into_iter().collect())
+}
+
+/// Every point whose tags infer each role for one equipment instance.
+///
+/// Points that reference `equipment` come first; points with no equipment
+/// reference (shared sensors such as a single OA temp) follow as fallback.
+/// Points belonging to other equipment are never used. With `equipment = ""`
+/// only unreferenced points are considered, which on a device without any
+/// equipment tags is every point. Within each group the order is
+/// (point_type, index), so the "first match" picked by `load_role_map` is
+/// stable between runs.
+pub async fn infer_candidates(
+    db: &sea_orm::DatabaseConnection,
+    serial: i32,
+    equipment: &str,
+) -> Result<BTreeMap<String, Vec<RolePoint>>, String> {
+    let points = load_tagged_points(db, serial).await?;
+    let mut candidates: BTreeMap<String, Vec<RolePoint>> = BTreeMap::new();
+    for own in [true, false] {
+        for p in &points {
+            let in_group = match &p.equipment {
+                Some(eq) => own && eq == equipment,
+                None => !own,
+            };
+            if !in_group {
+                continue;
+            }
+            if let Some(role) = infer_role(&p.tags) {
+                candidates.entry(role).or_default().push(p.point.clone());
+            }
         }
     }
+    Ok(candidates)
+}
+
+/// Load the inferred role map for one equipment instance: role → first candidate point.
+pub async fn load_role_map(
+    db: &sea_orm::DatabaseConnection,
+    serial: i32,
+    equipment: &str,
+) -> Result<HashMap<String, RolePoint>, String> {
+    Ok(infer_candidates(db, serial, equipment)
+        .await?
+        .into_iter()
+        .filter_map(|(role, points)| points.into_iter().next().map(|p| (role, p)))
+        .collect())
+}
+
+// ── Manual overrides ──
+
+/// A manual role → point assignment from FDD_ROLE_OVERRIDES.
+#[derive(Debug, Clone, Serialize)]
+pub struct RoleOverride {
+    pub serial_number: i32,
+    /// Empty string = applies to the whole device.
+    pub equipment: String,
+    pub role: String,
+    pub point: RolePoint,
+    pub note: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+/// Create the FDD_ROLE_OVERRIDES table (idempotent).
+pub async fn ensure_schema(db: &sea_orm::DatabaseConnection) -> Result<(), String> {
+    db.execute(sea_orm::Statement::from_string(
+        sea_orm::DatabaseBackend::Sqlite,
+        OVERRIDES_DDL.to_string(),
+    ))
+    .await
+    .map_err(|e| format!("FDD role override schema error: {}", e))?;
+    Ok(())
+}
+
+/// Default point id for a type + index when the caller doesn't supply one (IN3, OUT1, VAR12).
+fn default_point_id(point_type: &str, point_index: i32) -> String {
+    let prefix = match point_type {
+        "INPUT" => "IN",
+        "OUTPUT" => "OUT",
+        _ => "VAR",
+    };
+    format!("{}{}", prefix, point_index)
+}
+
+/// Overrides for a device. `equipment = None` returns every equipment's rows;
+/// `Some(eq)` returns the device-wide rows plus those for `eq`.
+pub async fn list_overrides(
+    db: &sea_orm::DatabaseConnection,
+    serial: i32,
+    equipment: Option<&str>,
+) -> Result<Vec<RoleOverride>, String> {
+    let mut sql = format!(
+        "SELECT serial_number, equipment, role, point_type, point_index, point_id, note, updated_at \
+         FROM FDD_ROLE_OVERRIDES WHERE serial_number = {}",
+        serial
+    );
+    if let Some(eq) = equipment {
+        sql.push_str(&format!(" AND equipment IN ('', '{}')", eq.replace('\'', "''")));
+    }
+    // Device-wide rows first so equipment-specific ones win when layered.
+    sql.push_str(" ORDER BY equipment, role");
+    let rows = db
+        .query_all(sea_orm::Statement::from_string(sea_orm::DatabaseBackend::Sqlite, sql))
+        .await
+        .map_err(|e| format!("Role override query failed: {}", e))?;
+    Ok(rows
+        .iter()
+        .map(|r| {
+            let point_type: String = r.try_get("", "point_type").unwrap_or_default();
+            let point_index: i32 = r.try_get("", "point_index").unwrap_or(0);
+            let point_id: Option<String> = r.try_get("", "point_id").ok().flatten();
+            RoleOverride {
+                serial_number: r.try_get("", "serial_number").unwrap_or(serial),
+                equipment: r.try_get("", "equipment").unwrap_or_default(),
+                role: r.try_get("", "role").unwrap_or_default(),
+                point: RolePoint {
+                    point_id: point_id
+                        .filter(|p| !p.is_empty())
+                        .unwrap_or_else(|| default_point_id(&point_type, point_index)),
+                    point_type,
+                    point_index,
+                },
+                note: r.try_get("", "note").ok().flatten(),
+                updated_at: r.try_get("", "updated_at").ok().flatten(),
+            }
+        })
+        .collect())
+}
+
+/// Pin `role` on (serial, equipment) to a point, replacing any earlier override.
+pub async fn set_override(
+    db: &sea_orm::DatabaseConnection,
+    serial: i32,
+    equipment: &str,
+    role: &str,
+    point: &RolePoint,
+    note: Option<&str>,
+) -> Result<(), String> {
+    if role.trim().is_empty() {
+        return Err("role must not be empty".into());
+    }
+    if !POINT_TYPES.contains(&point.point_type.as_str()) {
+        return Err(format!(
+            "Unknown point_type '{}'. Expected one of: {}",
+            point.point_type,
+            POINT_TYPES.join(", ")
+        ));
+    }
+    let point_id = if point.point_id.is_empty() {
+        default_point_id(&point.point_type, point.point_index)
+    } else {
+        point.point_id.clone()
+    };
+    let sql = format!(
+        "INSERT INTO FDD_ROLE_OVERRIDES (serial_number, equipment, role, point_type, point_index, point_id, note) \
+         VALUES ({}, '{}', '{}', '{}', {}, '{}', {}) \
+         ON CONFLICT (serial_number, equipment, role) DO UPDATE SET \
+         point_type = excluded.point_type, point_index = excluded.point_index, point_id = excluded.point_id, \
+         note = excluded.note, updated_at = datetime('now')",
+        serial,
+        equipment.replace('\'', "''"),
+        role.replace('\'', "''"),
+        point.point_type,
+        point.point_index,
+        point_id.replace('\'', "''"),
+        note.map(|n| format!("'{}'", n.replace('\'', "''")))
+            .unwrap_or_else(|| "NULL".into()),
+    );
+    db.execute(sea_orm::Statement::from_string(sea_orm::DatabaseBackend::Sqlite, sql))
+        .await
+        .map_err(|e| format!("Role override save failed: {}", e))?;
+    Ok(())
+}
+
+/// Remove an override so the role falls back to tag inference. Returns false if none existed.
+pub async fn delete_override(
+    db: &sea_orm::DatabaseConnection,
+    serial: i32,
+    equipment: &str,
+    role: &str,
+) -> Result<bool, String> {
+    let sql = format!(
+        "DELETE FROM FDD_ROLE_OVERRIDES WHERE serial_number = {} AND equipment = '{}' AND role = '{}'",
+        serial,
+        equipment.replace('\'', "''"),
+        role.replace('\'', "''")
+    );
+    let res = db
+        .execute(sea_orm::Statement::from_string(sea_orm::DatabaseBackend::Sqlite, sql))
+        .await
+        .map_err(|e| format!("Role override delete failed: {}", e))?;
+    Ok(res.rows_affected() > 0)
+}
+
+/// The role map analysis should use: inferred roles, then device-wide
+/// overrides, then overrides for `equipment` (most specific wins).
+pub async fn resolve_role_map(
+    db: &sea_orm::DatabaseConnection,
+    serial: i32,
+    equipment: &str,
+) -> Result<HashMap<String, RolePoint>, String> {
+    let mut map = load_role_map(db, serial, equipment).await?;
+    for o in list_overrides(db, serial, Some(equipment)).await? {
+        map.insert(o.role, o.point);
+    }
     Ok(map)
 }
+
+/// Inferred vs. overridden map for commissioning: one entry per role with the
+/// inferred point, every tag candidate, the override (if any) and what analysis
+/// will actually use.
+pub async fn role_map_report(
+    db: &sea_orm::DatabaseConnection,
+    serial: i32,
+    equipment: &str,
+) -> Result<Value, String> {
+    let candidates = infer_candidates(db, serial, equipment).await?;
+    let mut overrides: HashMap<String, RoleOverride> = HashMap::new();
+    for o in list_overrides(db, serial, Some(equipment)).await? {
+        overrides.insert(o.role.clone(), o);
+    }
+
+    let all_roles: BTreeSet<&String> = candidates.keys().chain(overrides.keys()).collect();
+    let roles: Vec<Value> = all_roles
+        .into_iter()
+        .map(|role| {
+            let cands = candidates.get(role).cloned().unwrap_or_default();
+            let inferred = cands.first().cloned();
+            let ov = overrides.get(role);
+            let (effective, source) = match (ov, &inferred) {
+                (Some(o), _) => (Some(o.point.clone()), "override"),
+                (None, Some(p)) => (Some(p.clone()), "inferred"),
+                (None, None) => (None, "none"),
+            };
+            json!({
+                "role": role,
+                "source": source,
+                "effective": effective,
+                "inferred": inferred,
+                "candidates": cands,
+                "override": ov,
+            })
+        })
+        .collect();
+
+    Ok(json!({
+        "serial_number": serial,
+        "equipment": equipment,
+        "equipment_instances": list_equipment(db, serial).await?,
+        "roles": roles,
+        "override_count": overrides.len(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn tagged_db() -> sea_orm::DatabaseConnection {
+        let db = sea_orm::Database::connect("sqlite::memory:").await.unwrap();
+        for sql in [
+            "CREATE TABLE HAYSTACK_POINT_TAGS (serial_number INTEGER, point_type TEXT, point_index TEXT, point_id TEXT, tag_name TEXT)",
+            "INSERT INTO HAYSTACK_POINT_TAGS VALUES \
+             (7,'INPUT','1','IN1','discharge'),(7,'INPUT','1','IN1','temp'), \
+             (7,'INPUT','4','IN4','supply'),(7,'INPUT','4','IN4','temp'), \
+             (7,'INPUT','2','IN2','outside'),(7,'INPUT','2','IN2','temp')",
+        ] {
+            db.execute(sea_orm::Statement::from_string(sea_orm::DatabaseBackend::Sqlite, sql.to_string()))
+                .await
+                .unwrap();
+        }
+        ensure_schema(&db).await.unwrap();
+        db
+    }
+
+    fn input(idx: i32) -> RolePoint {
+        RolePoint {
+            point_type: "INPUT".into(),
+            point_index: idx,
+            point_id: String::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn inference_picks_lowest_point_deterministically() {
+        let db = tagged_db().await;
+        let candidates = infer_candidates(&db, 7, "").await.unwrap();
+        assert_eq!(candidates["sat"].len(), 2);
+        assert_eq!(load_role_map(&db, 7, "").await.unwrap()["sat"].point_index, 1);
+    }
+
+    #[tokio::test]
+    async fn overrides_win_most_specific_first() {
+        let db = tagged_db().await;
+        set_override(&db, 7, "", "sat", &input(4), Some("IN1 is the preheat coil")).await.unwrap();
+        set_override(&db, 7, "AHU-2", "sat", &input(9), None).await.unwrap();
+
+        let device = resolve_role_map(&db, 7, "").await.unwrap();
+        assert_eq!(device["sat"].point_index, 4);
+        assert_eq!(device["sat"].point_id, "IN4");
+        assert_eq!(device["oa_t"].point_index, 2);
+        assert_eq!(resolve_role_map(&db, 7, "AHU-2").await.unwrap()["sat"].point_index, 9);
+
+        // Re-pinning replaces, deleting falls back to inference.
+        set_override(&db, 7, "", "sat", &input(1), None).await.unwrap();
+        assert_eq!(list_overrides(&db, 7, Some("")).await.unwrap().len(), 1);
+        assert!(delete_override(&db, 7, "", "sat").await.unwrap());
+        assert!(!delete_override(&db, 7, "", "sat").await.unwrap());
+        assert_eq!(resolve_role_map(&db, 7, "").await.unwrap()["sat"].point_index, 1);
+    }
+
+    #[test]
+    fn equipment_ref_reads_haystack_and_brick_forms() {
+        let r = |t: &str| equipment_ref(&[t.to_string()]);
+        assert_eq!(r("equipRef:@AHU-1"), Some("AHU-1".into()));
+        assert_eq!(r("equipref=VAV-3"), Some("VAV-3".into()));
+        assert_eq!(r("brick:isPointOf:AHU-2"), Some("AHU-2".into()));
+        assert_eq!(r("supply"), None);
+        assert_eq!(r("equipRef:"), None);
+    }
+
+    #[tokio::test]
+    async fn points_are_grouped_per_equipment() {
+        let db = tagged_db().await;
+        db.execute(sea_orm::Statement::from_string(
+            sea_orm::DatabaseBackend::Sqlite,
+            "INSERT INTO HAYSTACK_POINT_TAGS VALUES \
+             (7,'INPUT','5','IN5','discharge'),(7,'INPUT','5','IN5','temp'),(7,'INPUT','5','IN5','equipRef:@AHU-1'), \
+             (7,'INPUT','6','IN6','discharge'),(7,'INPUT','6','IN6','temp'),(7,'INPUT','6','IN6','equipRef:@AHU-2')"
+                .to_string(),
+        ))
+        .await
+        .unwrap();
+
+        assert_eq!(list_equipment(&db, 7).await.unwrap(), vec!["AHU-1", "AHU-2"]);
+        let ahu1 = load_role_map(&db, 7, "AHU-1").await.unwrap();
+        assert_eq!(ahu1["sat"].point_index, 5);
+        // Unreferenced points are shared: the OA sensor serves both AHUs.
+        assert_eq!(ahu1["oa_t"].point_index, 2);
+        assert_eq!(load_role_map(&db, 7, "AHU-2").await.unwrap()["sat"].point_index, 6);
+        // Another AHU's points never leak into the device-level map.
+        let shared = infer_candidates(&db, 7, "").await.unwrap();
+        assert!(shared["sat"].iter().all(|p| p.point_index < 5));
+    }
+
+    #[tokio::test]
+    async fn rejects_unknown_point_type() {
+        let db = tagged_db().await;
+        let mut p = input(1);
+        p.point_type = "PROGRAM".into();
+        assert!(set_override(&db, 7, "", "sat", &p, None).await.is_err());
+    }
+}
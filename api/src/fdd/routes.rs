@@ -0,0 +1,433 @@
+//! FDD REST API — rule CRUD, on-demand analysis, findings, import/export,
+//! the per-device role map (inferred vs. overridden), and cost ranking.
+//!
+//! Same operations the `t3000_fdd_*` MCP tools expose, as plain JSON routes
+//! under `/api/fdd` so dashboards and scripts don't need JSON-RPC.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+    routing::{get, post, put},
+    Router,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::app_state::T3AppState;
+use crate::fdd::impact::{self, Tariff};
+use crate::fdd::roles::{self, RolePoint};
+use crate::fdd::rules::{self, FindingsFilter, Rule, RuleError};
+use crate::fdd::series::ResampleConfig;
+
+type ApiError = (StatusCode, Json<Value>);
+
+const DEFAULT_PAGE_SIZE: u64 = 50;
+const MAX_PAGE_SIZE: u64 = 500;
+
+/// FDD tables live in the local webview_t3_device.db, same as the MCP tools.
+async fn get_fdd_db(state: &T3AppState) -> Result<sea_orm::DatabaseConnection, ApiError> {
+    let db = match &state.local_config_conn {
+        Some(conn) => conn.lock().await.clone(),
+        None => {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": "Local database connection not available"})),
+            ))
+        }
+    };
+    rules::ensure_schema(&db).await.map_err(internal)?;
+    Ok(db)
+}
+
+fn internal(e: String) -> ApiError {
+    tracing::error!("FDD API error: {}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e })))
+}
+
+fn bad_request(e: String) -> ApiError {
+    (StatusCode::BAD_REQUEST, Json(json!({ "error": e })))
+}
+
+fn rule_error(e: RuleError) -> ApiError {
+    match e {
+        RuleError::Invalid(e) => bad_request(e),
+        RuleError::Db(e) => internal(e),
+    }
+}
+
+fn not_found(rule_id: &str) -> ApiError {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({ "error": format!("FDD rule '{}' not found", rule_id) })),
+    )
+}
+
+// ── Request types ──
+
+#[derive(Debug, Deserialize)]
+struct ListRulesQuery {
+    category: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateRuleRequest {
+    rule_id: String,
+    #[serde(default)]
+    rule_name: String,
+    category: Option<String>,
+    description: Option<String>,
+    rule_kind: String,
+    #[serde(default)]
+    required_roles: Vec<String>,
+    params: Option<Value>,
+    severity: Option<String>,
+    enabled: Option<bool>,
+}
+
+impl From<CreateRuleRequest> for Rule {
+    fn from(r: CreateRuleRequest) -> Self {
+        Rule {
+            rule_id: r.rule_id,
+            rule_name: r.rule_name,
+            category: r.category.unwrap_or_else(|| "custom".into()),
+            description: r.description,
+            rule_kind: r.rule_kind,
+            required_roles: r.required_roles,
+            params: r.params.unwrap_or_else(|| json!({})),
+            severity: r.severity.unwrap_or_else(|| "warning".into()),
+            enabled: r.enabled.unwrap_or(true),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ToggleRequest {
+    enabled: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct ImportRequest {
+    rules: Vec<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnalyzeRequest {
+    serial_number: i32,
+    #[serde(default)]
+    equipment: String,
+    range_hours: Option<u64>,
+    #[serde(default)]
+    rules: Vec<String>,
+    /// Resampling bucket for every rule; omitted = each rule's own poll_seconds.
+    bucket_seconds: Option<u64>,
+    max_staleness_seconds: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EquipmentQuery {
+    #[serde(default)]
+    equipment: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RoleOverrideRequest {
+    #[serde(default)]
+    equipment: String,
+    point_type: String,
+    point_index: i32,
+    #[serde(default)]
+    point_id: String,
+    note: Option<String>,
+}
+
+// Fields are listed out rather than `#[serde(flatten)]`-ing FindingsFilter:
+// flattened query strings lose their numeric types in serde_urlencoded.
+#[derive(Debug, Deserialize)]
+struct FindingsQuery {
+    serial_number: Option<i32>,
+    equipment: Option<String>,
+    rule_id: Option<String>,
+    severity: Option<String>,
+    status: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
+    page: Option<u64>,
+    page_size: Option<u64>,
+    /// Row count for the top-cost ranking (default 10).
+    limit: Option<u64>,
+}
+
+impl FindingsQuery {
+    fn filter(&self) -> FindingsFilter {
+        FindingsFilter {
+            serial_number: self.serial_number,
+            equipment: self.equipment.clone(),
+            rule_id: self.rule_id.clone(),
+            severity: self.severity.clone(),
+            status: self.status.clone(),
+            from: self.from.clone(),
+            to: self.to.clone(),
+        }
+    }
+}
+
+// ── Routes ──
+
+pub fn create_fdd_routes() -> Router<T3AppState> {
+    Router::new()
+        .route("/api/fdd/rules", get(list_rules).post(create_rule))
+        .route("/api/fdd/rules/export", get(export_rules))
+        .route("/api/fdd/rules/import", post(import_rules))
+        .route(
+            "/api/fdd/rules/:rule_id",
+            get(get_rule).put(update_rule).delete(delete_rule),
+        )
+        .route("/api/fdd/rules/:rule_id/toggle", post(toggle_rule))
+        .route("/api/fdd/analyze", post(analyze))
+        .route("/api/fdd/findings", get(list_findings))
+        .route("/api/fdd/findings/top-cost", get(top_faults_by_cost))
+        .route("/api/fdd/tariff", get(get_tariff).put(set_tariff))
+        .route("/api/fdd/roles/:serial_number", get(get_role_map))
+        .route(
+            "/api/fdd/roles/:serial_number/:role",
+            put(set_role_override).delete(delete_role_override),
+        )
+}
+
+// ── Handlers ──
+
+async fn list_rules(
+    State(state): State<T3AppState>,
+    Query(query): Query<ListRulesQuery>,
+) -> Result<Json<Value>, ApiError> {
+    let db = get_fdd_db(&state).await?;
+    let items = rules::list_rules(&db, query.category.as_deref())
+        .await
+        .map_err(internal)?;
+    Ok(Json(json!({ "rules": items, "total": items.len() })))
+}
+
+async fn get_rule(
+    State(state): State<T3AppState>,
+    Path(rule_id): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    let db = get_fdd_db(&state).await?;
+    match rules::get_rule_any(&db, &rule_id).await.map_err(internal)? {
+        Some(rule) => Ok(Json(json!(rule))),
+        None => Err(not_found(&rule_id)),
+    }
+}
+
+async fn create_rule(
+    State(state): State<T3AppState>,
+    Json(payload): Json<CreateRuleRequest>,
+) -> Result<(StatusCode, Json<Value>), ApiError> {
+    let db = get_fdd_db(&state).await?;
+    let rule: Rule = payload.into();
+    if rules::get_rule_any(&db, &rule.rule_id).await.map_err(internal)?.is_some() {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({ "error": format!("FDD rule '{}' already exists", rule.rule_id) })),
+        ));
+    }
+    rules::create_rule(&db, &rule).await.map_err(rule_error)?;
+    Ok((StatusCode::CREATED, Json(json!({ "created": true, "rule": rule }))))
+}
+
+async fn update_rule(
+    State(state): State<T3AppState>,
+    Path(rule_id): Path<String>,
+    Json(changes): Json<Value>,
+) -> Result<Json<Value>, ApiError> {
+    let db = get_fdd_db(&state).await?;
+    match rules::update_rule(&db, &rule_id, &changes).await.map_err(rule_error)? {
+        Some(rule) => Ok(Json(json!({ "updated": true, "rule": rule }))),
+        None => Err(not_found(&rule_id)),
+    }
+}
+
+async fn delete_rule(
+    State(state): State<T3AppState>,
+    Path(rule_id): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    let db = get_fdd_db(&state).await?;
+    if rules::delete_rule(&db, &rule_id).await.map_err(internal)? {
+        Ok(Json(json!({ "deleted": true, "rule_id": rule_id })))
+    } else {
+        Err(not_found(&rule_id))
+    }
+}
+
+async fn toggle_rule(
+    State(state): State<T3AppState>,
+    Path(rule_id): Path<String>,
+    Json(payload): Json<ToggleRequest>,
+) -> Result<Json<Value>, ApiError> {
+    let db = get_fdd_db(&state).await?;
+    match rules::toggle_rule(&db, &rule_id, payload.enabled).await.map_err(rule_error)? {
+        Some(rule) => Ok(Json(json!({ "updated": true, "rule_id": rule.rule_id, "enabled": rule.enabled }))),
+        None => Err(not_found(&rule_id)),
+    }
+}
+
+async fn export_rules(
+    State(state): State<T3AppState>,
+    Query(query): Query<ListRulesQuery>,
+) -> Result<Json<Value>, ApiError> {
+    let db = get_fdd_db(&state).await?;
+    let items = rules::list_rules(&db, query.category.as_deref())
+        .await
+        .map_err(internal)?;
+    Ok(Json(json!({
+        "rules": items,
+        "total": items.len(),
+        "exported_at": chrono::Utc::now().to_rfc3339(),
+    })))
+}
+
+async fn import_rules(
+    State(state): State<T3AppState>,
+    Json(payload): Json<ImportRequest>,
+) -> Result<Json<Value>, ApiError> {
+    let db = get_fdd_db(&state).await?;
+    let report = rules::import_rules(&db, &payload.rules)
+        .await
+        .map_err(internal)?;
+    Ok(Json(json!({
+        "imported": report.imported,
+        "skipped": report.rejected.len(),
+        "rejected": report.rejected,
+    })))
+}
+
+async fn analyze(
+    State(state): State<T3AppState>,
+    Json(payload): Json<AnalyzeRequest>,
+) -> Result<Json<Value>, ApiError> {
+    let range_hours = payload.range_hours.unwrap_or(24);
+    let resample = ResampleConfig::from_options(payload.bucket_seconds, payload.max_staleness_seconds);
+    crate::fdd::check_bounds(range_hours, resample.as_ref()).map_err(bad_request)?;
+    let db = get_fdd_db(&state).await?;
+    // Rules bucketed at their own poll must fit the window as well.
+    for rule in rules::get_rules(&db, &payload.rules).await.map_err(internal)? {
+        crate::fdd::rule_resample(&rule, range_hours, resample).map_err(bad_request)?;
+    }
+    let result = crate::fdd::analyze(
+        &db,
+        payload.serial_number,
+        &payload.equipment,
+        range_hours,
+        &payload.rules,
+        resample,
+    )
+    .await
+    .map_err(internal)?;
+    Ok(Json(result))
+}
+
+async fn list_findings(
+    State(state): State<T3AppState>,
+    Query(query): Query<FindingsQuery>,
+) -> Result<Json<Value>, ApiError> {
+    let db = get_fdd_db(&state).await?;
+    let page = query.page.unwrap_or(1).max(1);
+    let page_size = query
+        .page_size
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let (items, total) = rules::query_findings(&db, &query.filter(), (page - 1) * page_size, page_size)
+        .await
+        .map_err(internal)?;
+    Ok(Json(json!({
+        "findings": items,
+        "total": total,
+        "page": page,
+        "page_size": page_size,
+        "total_pages": total.div_ceil(page_size),
+    })))
+}
+
+async fn top_faults_by_cost(
+    State(state): State<T3AppState>,
+    Query(query): Query<FindingsQuery>,
+) -> Result<Json<Value>, ApiError> {
+    let db = get_fdd_db(&state).await?;
+    let limit = query.limit.unwrap_or(10).clamp(1, crate::fdd::MAX_TOP_FAULTS);
+    let result = crate::fdd::top_faults_by_cost(&db, &query.filter(), limit)
+        .await
+        .map_err(internal)?;
+    Ok(Json(result))
+}
+
+async fn get_tariff(State(state): State<T3AppState>) -> Result<Json<Value>, ApiError> {
+    let db = get_fdd_db(&state).await?;
+    Ok(Json(json!({ "tariff": impact::load_tariff(&db).await })))
+}
+
+async fn set_tariff(
+    State(state): State<T3AppState>,
+    Json(payload): Json<Tariff>,
+) -> Result<Json<Value>, ApiError> {
+    let db = get_fdd_db(&state).await?;
+    impact::save_tariff(&db, &payload).await.map_err(bad_request)?;
+    Ok(Json(json!({ "updated": true, "tariff": payload })))
+}
+
+async fn get_role_map(
+    State(state): State<T3AppState>,
+    Path(serial_number): Path<i32>,
+    Query(query): Query<EquipmentQuery>,
+) -> Result<Json<Value>, ApiError> {
+    let db = get_fdd_db(&state).await?;
+    let report = roles::role_map_report(&db, serial_number, &query.equipment)
+        .await
+        .map_err(internal)?;
+    Ok(Json(report))
+}
+
+async fn set_role_override(
+    State(state): State<T3AppState>,
+    Path((serial_number, role)): Path<(i32, String)>,
+    Json(payload): Json<RoleOverrideRequest>,
+) -> Result<Json<Value>, ApiError> {
+    let db = get_fdd_db(&state).await?;
+    let point = RolePoint {
+        point_type: payload.point_type.to_uppercase(),
+        point_index: payload.point_index,
+        point_id: payload.point_id,
+    };
+    roles::set_override(
+        &db,
+        serial_number,
+        &payload.equipment,
+        &role,
+        &point,
+        payload.note.as_deref(),
+    )
+    .await
+    .map_err(bad_request)?;
+    let report = roles::role_map_report(&db, serial_number, &payload.equipment)
+        .await
+        .map_err(internal)?;
+    Ok(Json(json!({ "updated": true, "role": role, "role_map": report })))
+}
+
+async fn delete_role_override(
+    State(state): State<T3AppState>,
+    Path((serial_number, role)): Path<(i32, String)>,
+    Query(query): Query<EquipmentQuery>,
+) -> Result<Json<Value>, ApiError> {
+    let db = get_fdd_db(&state).await?;
+    if roles::delete_override(&db, serial_number, &query.equipment, &role)
+        .await
+        .map_err(internal)?
+    {
+        Ok(Json(json!({ "deleted": true, "role": role })))
+    } else {
+        Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": format!("No override for role '{}' on device {}", role, serial_number) })),
+        ))
+    }
+}
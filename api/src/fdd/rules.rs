@@ -7,6 +7,8 @@ use sea_orm::ConnectionTrait;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
+use super::impact::Impact;
+
 /// A fault-detection rule, stored as a row in FDD_RULES.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Rule {
@@ -72,13 +74,79 @@ CREATE TABLE IF NOT EXISTS FDD_FINDINGS (
     severity      TEXT,
     fault_hours   REAL,
     evidence      TEXT,
-    created_at    TEXT DEFAULT (datetime('now'))
+    created_at    TEXT DEFAULT (datetime('now')),
+    status        TEXT DEFAULT 'open',
+    first_seen    TEXT,
+    last_seen     TEXT,
+    cleared_at    TEXT,
+    occurrences   INTEGER DEFAULT 1,
+    est_kwh       REAL,
+    est_cost      REAL
 );
 CREATE INDEX IF NOT EXISTS idx_fdd_findings_device ON FDD_FINDINGS (device_serial);
 CREATE INDEX IF NOT EXISTS idx_fdd_findings_rule ON FDD_FINDINGS (rule_id);
 CREATE INDEX IF NOT EXISTS idx_fdd_findings_created ON FDD_FINDINGS (created_at);
 ";
 
+/// Lifecycle columns added after the first FDD_FINDINGS release. Older DBs get
+/// the missing ones via ALTER TABLE.
+const FINDINGS_LIFECYCLE_COLUMNS: &[(&str, &str)] = &[
+    ("status", "TEXT DEFAULT 'open'"),
+    ("first_seen", "TEXT"),
+    ("last_seen", "TEXT"),
+    ("cleared_at", "TEXT"),
+    ("occurrences", "INTEGER DEFAULT 1"),
+    ("est_kwh", "REAL"),
+    ("est_cost", "REAL"),
+];
+
+const FINDINGS_LIFECYCLE_BACKFILL: &str = "
+UPDATE FDD_FINDINGS SET first_seen = created_at WHERE first_seen IS NULL;
+UPDATE FDD_FINDINGS SET last_seen = created_at WHERE last_seen IS NULL;
+";
+
+const FINDINGS_ACTIVE_INDEX: &str =
+    "CREATE INDEX IF NOT EXISTS idx_fdd_findings_active ON FDD_FINDINGS (device_serial, equipment, rule_id, cleared_at)";
+
+/// Rows from before the lifecycle columns are one snapshot per analysis run,
+/// not open faults; close them so none stays active with nothing to clear it.
+const FINDINGS_LEGACY_CLOSE: &str =
+    "UPDATE FDD_FINDINGS SET status = 'cleared', cleared_at = created_at WHERE cleared_at IS NULL";
+
+/// Add whichever lifecycle columns FDD_FINDINGS lacks. Up-to-date tables cost
+/// one `PRAGMA table_info`; the backfills only run when a column was added.
+async fn upgrade_findings(db: &sea_orm::DatabaseConnection) -> Result<(), String> {
+    let upgrade_err = |e: sea_orm::DbErr| format!("FDD findings upgrade error: {}", e);
+    let existing: Vec<String> = db
+        .query_all(sea_orm::Statement::from_string(
+            sea_orm::DatabaseBackend::Sqlite,
+            "PRAGMA table_info(FDD_FINDINGS)".to_string(),
+        ))
+        .await
+        .map_err(upgrade_err)?
+        .iter()
+        .filter_map(|r| r.try_get::<String>("", "name").ok())
+        .collect();
+    let missing: Vec<&(&str, &str)> = FINDINGS_LIFECYCLE_COLUMNS
+        .iter()
+        .filter(|(col, _)| !existing.iter().any(|c| c.eq_ignore_ascii_case(col)))
+        .collect();
+    for (col, ty) in &missing {
+        let sql = format!("ALTER TABLE FDD_FINDINGS ADD COLUMN {} {}", col, ty);
+        db.execute(sea_orm::Statement::from_string(sea_orm::DatabaseBackend::Sqlite, sql))
+            .await
+            .map_err(upgrade_err)?;
+    }
+    if !missing.is_empty() {
+        db.execute_unprepared(FINDINGS_LIFECYCLE_BACKFILL).await.map_err(upgrade_err)?;
+    }
+    if missing.iter().any(|(col, _)| *col == "status") {
+        db.execute_unprepared(FINDINGS_LEGACY_CLOSE).await.map_err(upgrade_err)?;
+    }
+    db.execute_unprepared(FINDINGS_ACTIVE_INDEX).await.map_err(upgrade_err)?;
+    Ok(())
+}
+
 /// Create FDD_RULES + FDD_FINDINGS if missing and seed defaults on first run.
 pub async fn ensure_schema(db: &sea_orm::DatabaseConnection) -> Result<(), String> {
     db.execute(sea_orm::Statement::from_string(
@@ -93,6 +161,8 @@ pub async fn ensure_schema(db: &sea_orm::DatabaseConnection) -> Result<(), Strin
     ))
     .await
     .map_err(|e| format!("FDD findings schema error: {}", e))?;
+    upgrade_findings(db).await?;
+    super::roles::ensure_schema(db).await?;
 
     // Idempotent per-rule seeding: always INSERT OR IGNORE so existing DBs
     // (e.g. those seeded with the Phase-1 3-rule catalog) pick up new rules.
@@ -201,8 +271,61 @@ pub async fn get_rule_any(db: &sea_orm::DatabaseConnection, rule_id: &str) -> Re
     }
 }
 
+/// Why saving a rule failed.
+#[derive(Debug)]
+pub enum RuleError {
+    /// The rule itself is invalid.
+    Invalid(String),
+    /// The database couldn't read or write it.
+    Db(String),
+}
+
+impl std::fmt::Display for RuleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Invalid(e) | Self::Db(e) => f.write_str(e),
+        }
+    }
+}
+
+impl From<String> for RuleError {
+    fn from(e: String) -> Self {
+        Self::Db(e)
+    }
+}
+
+/// Check a rule definition before it is stored.
+pub fn validate_rule(rule: &Rule) -> Result<(), String> {
+    if rule.rule_id.trim().is_empty() {
+        return Err("rule_id is required".to_string());
+    }
+    if !super::evaluator::RULE_KINDS.contains(&rule.rule_kind.as_str()) {
+        return Err(format!(
+            "unknown rule_kind '{}' (expected one of: {})",
+            rule.rule_kind,
+            super::evaluator::RULE_KINDS.join(", ")
+        ));
+    }
+    if rule.rule_kind == "Expression" {
+        let src = rule
+            .params
+            .get("expression")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| "Expression rules need params.expression".to_string())?;
+        super::expr::validate(src, &rule.required_roles)?;
+    }
+    if let Some(poll) = rule.params.get("poll_seconds") {
+        let (lo, hi) = (super::scheduler::MIN_POLL_SECONDS, super::scheduler::MAX_POLL_SECONDS);
+        if !poll.as_u64().is_some_and(|p| (lo..=hi).contains(&p)) {
+            return Err(format!("params.poll_seconds must be an integer between {} and {}", lo, hi));
+        }
+    }
+    Ok(())
+}
+
 /// Insert a new rule. Fails if rule_id already exists (use `update_rule`).
-pub async fn create_rule(db: &sea_orm::DatabaseConnection, rule: &Rule) -> Result<(), String> {
+pub async fn create_rule(db: &sea_orm::DatabaseConnection, rule: &Rule) -> Result<(), RuleError> {
+    validate_rule(rule).map_err(RuleError::Invalid)?;
     let sql = format!(
         "INSERT INTO FDD_RULES (rule_id, rule_name, category, description, rule_kind, required_roles, params_json, severity, enabled) \
          VALUES ('{}','{}','{}','{}','{}','{}','{}','{}',{})",
@@ -229,7 +352,7 @@ pub async fn update_rule(
     db: &sea_orm::DatabaseConnection,
     rule_id: &str,
     changes: &Value,
-) -> Result<Option<Rule>, String> {
+) -> Result<Option<Rule>, RuleError> {
     // Read regardless of enabled state (updating may re-enable/disable the rule).
     let current = get_rule_any(db, rule_id).await?;
     let current = match current {
@@ -237,47 +360,59 @@ pub async fn update_rule(
         None => return Ok(None),
     };
 
+    // Merge the changes into the rule as it will look after the update, and
+    // validate that: an expression must still only use the required roles
+    // whether the request changes the expression, the roles, or both.
+    let mut candidate = current.clone();
     let mut sets: Vec<String> = Vec::new();
     if let Some(v) = changes.get("rule_name").and_then(|v| v.as_str()) {
         sets.push(format!("rule_name = '{}'", v.replace('\'', "''")));
+        candidate.rule_name = v.to_string();
     }
     if let Some(v) = changes.get("category").and_then(|v| v.as_str()) {
         sets.push(format!("category = '{}'", v.replace('\'', "''")));
+        candidate.category = v.to_string();
     }
     if let Some(v) = changes.get("description").and_then(|v| v.as_str()) {
         sets.push(format!("description = '{}'", v.replace('\'', "''")));
+        candidate.description = Some(v.to_string());
     }
     if let Some(v) = changes.get("rule_kind").and_then(|v| v.as_str()) {
         sets.push(format!("rule_kind = '{}'", v.replace('\'', "''")));
+        candidate.rule_kind = v.to_string();
     }
     if let Some(v) = changes.get("severity").and_then(|v| v.as_str()) {
         sets.push(format!("severity = '{}'", v.replace('\'', "''")));
+        candidate.severity = v.to_string();
     }
     if let Some(v) = changes.get("enabled").and_then(|v| v.as_bool()) {
         sets.push(format!("enabled = {}", if v { 1 } else { 0 }));
+        candidate.enabled = v;
     }
     if let Some(v) = changes.get("required_roles").and_then(|v| v.as_array()) {
+        candidate.required_roles = v.iter().filter_map(|r| r.as_str().map(str::to_string)).collect();
         sets.push(format!(
             "required_roles = '{}'",
-            serde_json::to_string(v).unwrap_or_else(|_| "[]".into())
+            serde_json::to_string(&candidate.required_roles).unwrap_or_else(|_| "[]".into())
         ));
     }
     if let Some(v) = changes.get("params").and_then(|v| v.as_object()) {
         // Merge into existing params so partial tuning works.
-        let mut merged = current.params.clone();
-        if let Some(obj) = merged.as_object_mut() {
+        if let Some(obj) = candidate.params.as_object_mut() {
             for (k, val) in v {
                 obj.insert(k.clone(), val.clone());
             }
         }
         sets.push(format!(
             "params_json = '{}'",
-            serde_json::to_string(&merged).unwrap_or_else(|_| "{}".into())
+            serde_json::to_string(&candidate.params).unwrap_or_else(|_| "{}".into())
         ));
     }
     if sets.is_empty() {
         return Ok(Some(current));
     }
+    validate_rule(&candidate).map_err(RuleError::Invalid)?;
+
     sets.push("updated_at = datetime('now')".to_string());
 
     let sql = format!(
@@ -288,7 +423,20 @@ pub async fn update_rule(
     db.execute(sea_orm::Statement::from_string(sea_orm::DatabaseBackend::Sqlite, sql))
         .await
         .map_err(|e| format!("FDD update error: {}", e))?;
-    get_rule_any(db, rule_id).await
+    Ok(get_rule_any(db, rule_id).await?)
+}
+
+/// Delete a rule by ID. Returns false if no such rule existed.
+pub async fn delete_rule(db: &sea_orm::DatabaseConnection, rule_id: &str) -> Result<bool, String> {
+    let sql = format!(
+        "DELETE FROM FDD_RULES WHERE rule_id = '{}'",
+        rule_id.replace('\'', "''")
+    );
+    let res = db
+        .execute(sea_orm::Statement::from_string(sea_orm::DatabaseBackend::Sqlite, sql))
+        .await
+        .map_err(|e| format!("FDD delete error: {}", e))?;
+    Ok(res.rows_affected() > 0)
 }
 
 /// Enable or disable a rule by ID.
@@ -296,14 +444,31 @@ pub async fn toggle_rule(
     db: &sea_orm::DatabaseConnection,
     rule_id: &str,
     enabled: bool,
-) -> Result<Option<Rule>, String> {
+) -> Result<Option<Rule>, RuleError> {
     update_rule(db, rule_id, &json!({ "enabled": enabled })).await
 }
 
-/// Import rules (upsert). `rules` is an array of rule objects.
-pub async fn import_rules(db: &sea_orm::DatabaseConnection, rules: &[Value]) -> Result<usize, String> {
-    let mut count = 0usize;
-    for r in rules {
+/// Outcome of [`import_rules`].
+#[derive(Debug, Default, Serialize)]
+pub struct ImportReport {
+    pub imported: usize,
+    /// Rules that failed validation and were not stored.
+    pub rejected: Vec<RejectedRule>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RejectedRule {
+    /// Position in the imported array, for rules without a usable id.
+    pub index: usize,
+    pub rule_id: String,
+    pub error: String,
+}
+
+/// Import rules (upsert). `rules` is an array of rule objects; invalid ones
+/// are skipped and reported with their validation error.
+pub async fn import_rules(db: &sea_orm::DatabaseConnection, rules: &[Value]) -> Result<ImportReport, String> {
+    let mut report = ImportReport::default();
+    for (index, r) in rules.iter().enumerate() {
         let rule = Rule {
             rule_id: r.get("rule_id").and_then(|v| v.as_str()).unwrap_or("").to_string(),
             rule_name: r.get("rule_name").and_then(|v| v.as_str()).unwrap_or("").to_string(),
@@ -318,7 +483,8 @@ pub async fn import_rules(db: &sea_orm::DatabaseConnection, rules: &[Value]) ->
             severity: r.get("severity").and_then(|v| v.as_str()).unwrap_or("warning").to_string(),
             enabled: r.get("enabled").and_then(|v| v.as_bool()).unwrap_or(true),
         };
-        if rule.rule_id.is_empty() {
+        if let Err(error) = validate_rule(&rule) {
+            report.rejected.push(RejectedRule { index, rule_id: rule.rule_id, error });
             continue;
         }
         // Upsert: delete any existing row with the same rule_id, then insert.
@@ -342,80 +508,468 @@ pub async fn import_rules(db: &sea_orm::DatabaseConnection, rules: &[Value]) ->
         db.execute(sea_orm::Statement::from_string(sea_orm::DatabaseBackend::Sqlite, ins_sql))
             .await
             .map_err(|e| format!("FDD import insert error: {}", e))?;
-        count += 1;
+        report.imported += 1;
     }
-    Ok(count)
+    Ok(report)
 }
 
-/// Persist one finding row.
-pub async fn persist_finding(
+/// Lifecycle state of a persisted fault.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultStatus {
+    /// First detection — no active record existed for (device, equipment, rule).
+    Open,
+    /// Detected again while an active record was still open.
+    Ongoing,
+    /// The rule evaluated healthy after being active; the record is closed.
+    Cleared,
+}
+
+impl FaultStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FaultStatus::Open => "open",
+            FaultStatus::Ongoing => "ongoing",
+            FaultStatus::Cleared => "cleared",
+        }
+    }
+}
+
+async fn active_fault_id(
     db: &sea_orm::DatabaseConnection,
     serial: i32,
     equipment: &str,
     rule_id: &str,
-    rule_name: &str,
-    severity: &str,
-    fault_hours: f64,
-    evidence: &Value,
-) -> Result<(), String> {
+) -> Result<Option<i64>, String> {
     let sql = format!(
-        "INSERT INTO FDD_FINDINGS (device_serial, equipment, rule_id, rule_name, severity, fault_hours, evidence) \
-         VALUES ({}, '{}', '{}', '{}', '{}', {}, '{}')",
+        "SELECT id FROM FDD_FINDINGS WHERE device_serial = {} AND COALESCE(equipment, '') = '{}' \
+         AND rule_id = '{}' AND cleared_at IS NULL ORDER BY id DESC LIMIT 1",
         serial,
         equipment.replace('\'', "''"),
         rule_id.replace('\'', "''"),
-        rule_name.replace('\'', "''"),
-        severity.replace('\'', "''"),
-        fault_hours,
-        serde_json::to_string(evidence).unwrap_or_else(|_| "{}".into()).replace('\'', "''"),
+    );
+    let rows = db
+        .query_all(sea_orm::Statement::from_string(sea_orm::DatabaseBackend::Sqlite, sql))
+        .await
+        .map_err(|e| format!("FDD active fault query error: {}", e))?;
+    Ok(rows.first().and_then(|r| r.try_get::<i64>("", "id").ok()))
+}
+
+/// A detected fault ready to be persisted.
+#[derive(Debug, Clone)]
+pub struct FindingRecord<'a> {
+    pub rule_id: &'a str,
+    pub rule_name: &'a str,
+    pub severity: &'a str,
+    pub fault_hours: f64,
+    pub evidence: &'a Value,
+    /// Estimated energy / cost impact (see `fdd::impact`), when the rule has a model.
+    pub impact: Option<Impact>,
+}
+
+/// Record a detected fault. Opens a new lifecycle record, or — if one is still
+/// active for (device, equipment, rule) — bumps its `last_seen` instead of
+/// appending a duplicate row.
+///
+/// `occurrences` counts fault episodes: a record opened after an earlier one
+/// for the same (device, equipment, rule) cleared carries that count plus one;
+/// repeat detections of an active fault don't change it.
+///
+/// `est_kwh`/`est_cost` accumulate over the record's lifetime. Evaluation
+/// windows overlap, so an update only adds the share of this window's
+/// estimate that falls after the previous `last_seen`.
+pub async fn persist_finding(
+    db: &sea_orm::DatabaseConnection,
+    serial: i32,
+    equipment: &str,
+    f: &FindingRecord<'_>,
+) -> Result<FaultStatus, String> {
+    let evidence_json = serde_json::to_string(f.evidence)
+        .unwrap_or_else(|_| "{}".into())
+        .replace('\'', "''");
+    let sql_num = |v: Option<f64>| v.map(|n| n.to_string()).unwrap_or_else(|| "NULL".into());
+    let est_kwh = sql_num(f.impact.map(|i| i.kwh));
+    let est_cost = sql_num(f.impact.and_then(|i| i.cost));
+
+    if let Some(id) = active_fault_id(db, serial, equipment, f.rule_id).await? {
+        // Fraction of this window's fault time not already counted (SQLite
+        // evaluates every SET expression against the row before the update)
+        let new_share = format!(
+            "MIN(1.0, MAX(0.0, (julianday('now') - julianday(last_seen)) * 24.0 / {}))",
+            f.fault_hours.max(f64::EPSILON)
+        );
+        let accumulate = |col: &str, v: Option<f64>| match v {
+            Some(n) => format!("{col} = COALESCE({col}, 0) + {} * {}", n, new_share),
+            None => format!("{col} = {col}"),
+        };
+        let sql = format!(
+            "UPDATE FDD_FINDINGS SET status = 'ongoing', last_seen = datetime('now'), \
+             severity = '{}', fault_hours = {}, evidence = '{}', {}, {} WHERE id = {}",
+            f.severity.replace('\'', "''"),
+            f.fault_hours,
+            evidence_json,
+            accumulate("est_kwh", f.impact.map(|i| i.kwh)),
+            accumulate("est_cost", f.impact.and_then(|i| i.cost)),
+            id
+        );
+        db.execute(sea_orm::Statement::from_string(sea_orm::DatabaseBackend::Sqlite, sql))
+            .await
+            .map_err(|e| format!("FDD persist finding error: {}", e))?;
+        return Ok(FaultStatus::Ongoing);
+    }
+
+    let (equipment, rule_id) = (equipment.replace('\'', "''"), f.rule_id.replace('\'', "''"));
+    // No active record, so the latest one for this fault (if any) has cleared.
+    let previous = format!(
+        "(SELECT occurrences FROM FDD_FINDINGS WHERE device_serial = {} AND COALESCE(equipment, '') = '{}' \
+         AND rule_id = '{}' ORDER BY id DESC LIMIT 1)",
+        serial, equipment, rule_id
+    );
+    let sql = format!(
+        "INSERT INTO FDD_FINDINGS (device_serial, equipment, rule_id, rule_name, severity, fault_hours, evidence, \
+         status, first_seen, last_seen, occurrences, est_kwh, est_cost) \
+         VALUES ({}, '{}', '{}', '{}', '{}', {}, '{}', 'open', datetime('now'), datetime('now'), \
+         COALESCE({}, 0) + 1, {}, {})",
+        serial,
+        equipment,
+        rule_id,
+        f.rule_name.replace('\'', "''"),
+        f.severity.replace('\'', "''"),
+        f.fault_hours,
+        evidence_json,
+        previous,
+        est_kwh,
+        est_cost,
     );
     db.execute(sea_orm::Statement::from_string(sea_orm::DatabaseBackend::Sqlite, sql))
         .await
         .map_err(|e| format!("FDD persist finding error: {}", e))?;
-    Ok(())
+    Ok(FaultStatus::Open)
+}
+
+/// Close the active fault record for (device, equipment, rule), if any.
+/// Returns true when a record was cleared.
+pub async fn clear_finding(
+    db: &sea_orm::DatabaseConnection,
+    serial: i32,
+    equipment: &str,
+    rule_id: &str,
+) -> Result<bool, String> {
+    let Some(id) = active_fault_id(db, serial, equipment, rule_id).await? else {
+        return Ok(false);
+    };
+    let sql = format!(
+        "UPDATE FDD_FINDINGS SET status = 'cleared', cleared_at = datetime('now') WHERE id = {}",
+        id
+    );
+    db.execute(sea_orm::Statement::from_string(sea_orm::DatabaseBackend::Sqlite, sql))
+        .await
+        .map_err(|e| format!("FDD clear finding error: {}", e))?;
+    Ok(true)
+}
+
+/// Clear every active fault recorded for `equipment` on a device. Returns how
+/// many were cleared.
+pub async fn clear_equipment_findings(
+    db: &sea_orm::DatabaseConnection,
+    serial: i32,
+    equipment: &str,
+) -> Result<u64, String> {
+    let res = db
+        .execute(sea_orm::Statement::from_sql_and_values(
+            sea_orm::DatabaseBackend::Sqlite,
+            "UPDATE FDD_FINDINGS SET status = 'cleared', cleared_at = datetime('now') \
+             WHERE device_serial = ? AND COALESCE(equipment, '') = ? AND cleared_at IS NULL",
+            [serial.into(), equipment.into()],
+        ))
+        .await
+        .map_err(|e| format!("FDD clear equipment findings error: {}", e))?;
+    Ok(res.rows_affected())
+}
+
+/// Filters for querying persisted findings. All fields are optional.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FindingsFilter {
+    pub serial_number: Option<i32>,
+    pub equipment: Option<String>,
+    pub rule_id: Option<String>,
+    pub severity: Option<String>,
+    /// Lifecycle state: "open", "ongoing", "cleared", or "active" (open + ongoing).
+    pub status: Option<String>,
+    /// Only faults active at or after this time ("YYYY-MM-DD HH:MM:SS" or a date).
+    pub from: Option<String>,
+    /// Only faults first seen at or before this time.
+    pub to: Option<String>,
+}
+
+impl FindingsFilter {
+    fn where_clause(&self) -> String {
+        let esc = |v: &str| v.replace('\'', "''");
+        let mut conds: Vec<String> = Vec::new();
+        if let Some(s) = self.serial_number {
+            conds.push(format!("device_serial = {}", s));
+        }
+        if let Some(e) = &self.equipment {
+            conds.push(format!("COALESCE(equipment, '') = '{}'", esc(e)));
+        }
+        if let Some(r) = &self.rule_id {
+            conds.push(format!("rule_id = '{}'", esc(r)));
+        }
+        if let Some(sev) = &self.severity {
+            conds.push(format!("severity = '{}'", esc(sev)));
+        }
+        match self.status.as_deref() {
+            Some("active") => conds.push("cleared_at IS NULL".to_string()),
+            Some(st) => conds.push(format!("status = '{}'", esc(st))),
+            None => {}
+        }
+        if let Some(from) = &self.from {
+            conds.push(format!("COALESCE(last_seen, created_at) >= '{}'", esc(from)));
+        }
+        if let Some(to) = &self.to {
+            conds.push(format!("COALESCE(first_seen, created_at) <= '{}'", esc(to)));
+        }
+        if conds.is_empty() {
+            String::new()
+        } else {
+            format!(" WHERE {}", conds.join(" AND "))
+        }
+    }
+}
+
+/// Query persisted findings with filters and offset/limit paging.
+/// Returns the page of findings plus the total number of matching rows.
+pub async fn query_findings(
+    db: &sea_orm::DatabaseConnection,
+    filter: &FindingsFilter,
+    offset: u64,
+    limit: u64,
+) -> Result<(Vec<Value>, u64), String> {
+    let where_clause = filter.where_clause();
+
+    let count_sql = format!("SELECT COUNT(*) AS cnt FROM FDD_FINDINGS{}", where_clause);
+    let total = db
+        .query_one(sea_orm::Statement::from_string(sea_orm::DatabaseBackend::Sqlite, count_sql))
+        .await
+        .map_err(|e| format!("FDD findings count error: {}", e))?
+        .and_then(|r| r.try_get::<i64>("", "cnt").ok())
+        .unwrap_or(0) as u64;
+
+    let sql = format!(
+        "SELECT {} FROM FDD_FINDINGS{} ORDER BY COALESCE(last_seen, created_at) DESC, id DESC LIMIT {} OFFSET {}",
+        FINDING_COLUMNS, where_clause, limit, offset
+    );
+    let rows = db
+        .query_all(sea_orm::Statement::from_string(sea_orm::DatabaseBackend::Sqlite, sql))
+        .await
+        .map_err(|e| format!("FDD findings query error: {}", e))?;
+    Ok((rows.iter().map(finding_json).collect(), total))
+}
+
+/// Findings ranked by estimated cost (then kWh), most expensive first.
+/// Only rows with an impact estimate are returned.
+pub async fn top_faults_by_cost(
+    db: &sea_orm::DatabaseConnection,
+    filter: &FindingsFilter,
+    limit: u64,
+) -> Result<Vec<Value>, String> {
+    let where_clause = filter.where_clause();
+    let impact_cond = "est_kwh IS NOT NULL";
+    let where_clause = if where_clause.is_empty() {
+        format!(" WHERE {}", impact_cond)
+    } else {
+        format!("{} AND {}", where_clause, impact_cond)
+    };
+    let sql = format!(
+        "SELECT {} FROM FDD_FINDINGS{} ORDER BY COALESCE(est_cost, 0) DESC, est_kwh DESC, id DESC LIMIT {}",
+        FINDING_COLUMNS, where_clause, limit
+    );
+    let rows = db
+        .query_all(sea_orm::Statement::from_string(sea_orm::DatabaseBackend::Sqlite, sql))
+        .await
+        .map_err(|e| format!("FDD top faults query error: {}", e))?;
+    Ok(rows.iter().map(finding_json).collect())
+}
+
+const FINDING_COLUMNS: &str = "id, device_serial, equipment, rule_id, rule_name, severity, fault_hours, evidence, \
+     created_at, status, first_seen, last_seen, cleared_at, occurrences, est_kwh, est_cost";
+
+fn finding_json(r: &sea_orm::QueryResult) -> Value {
+    json!({
+        "id": r.try_get::<i64>("", "id").unwrap_or(0),
+        "device_serial": r.try_get::<i32>("", "device_serial").unwrap_or(0),
+        "equipment": r.try_get::<String>("", "equipment").ok(),
+        "rule_id": r.try_get::<String>("", "rule_id").unwrap_or_default(),
+        "rule_name": r.try_get::<String>("", "rule_name").ok(),
+        "severity": r.try_get::<String>("", "severity").unwrap_or_default(),
+        "fault_hours": r.try_get::<f64>("", "fault_hours").unwrap_or(0.0),
+        "evidence": r.try_get::<String>("", "evidence").ok().and_then(|s| serde_json::from_str::<Value>(&s).ok()),
+        "created_at": r.try_get::<String>("", "created_at").unwrap_or_default(),
+        "status": r.try_get::<String>("", "status").unwrap_or_else(|_| "open".into()),
+        "first_seen": r.try_get::<String>("", "first_seen").ok(),
+        "last_seen": r.try_get::<String>("", "last_seen").ok(),
+        "cleared_at": r.try_get::<String>("", "cleared_at").ok(),
+        "occurrences": r.try_get::<i64>("", "occurrences").unwrap_or(1),
+        "est_kwh": r.try_get::<Option<f64>>("", "est_kwh").ok().flatten(),
+        "est_cost": r.try_get::<Option<f64>>("", "est_cost").ok().flatten(),
+    })
 }
 
 /// Query persisted findings.
+///
+/// `status` filters the lifecycle state: "open", "ongoing", "cleared", or
+/// "active" (open + ongoing).
 pub async fn list_findings(
     db: &sea_orm::DatabaseConnection,
     serial: Option<i32>,
     rule_id: Option<&str>,
+    status: Option<&str>,
     limit: u64,
 ) -> Result<Vec<Value>, String> {
-    let mut sql = String::from(
-        "SELECT id, device_serial, equipment, rule_id, rule_name, severity, fault_hours, evidence, created_at FROM FDD_FINDINGS",
-    );
-    let mut conds: Vec<String> = Vec::new();
-    if let Some(s) = serial {
-        conds.push(format!("device_serial = {}", s));
+    let filter = FindingsFilter {
+        serial_number: serial,
+        rule_id: rule_id.map(String::from),
+        status: status.map(String::from),
+        ..Default::default()
+    };
+    query_findings(db, &filter, 0, limit).await.map(|(items, _)| items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn memory_db() -> sea_orm::DatabaseConnection {
+        let db = sea_orm::Database::connect("sqlite::memory:").await.unwrap();
+        ensure_schema(&db).await.unwrap();
+        db
     }
-    if let Some(r) = rule_id {
-        conds.push(format!("rule_id = '{}'", r.replace('\'', "''")));
+
+    fn record(ev: &Value, fault_hours: f64, impact: Option<Impact>) -> FindingRecord<'_> {
+        FindingRecord {
+            rule_id: "SAT-HIGH",
+            rule_name: "SAT high",
+            severity: "critical",
+            fault_hours,
+            evidence: ev,
+            impact,
+        }
     }
-    if !conds.is_empty() {
-        sql.push_str(&format!(" WHERE {}", conds.join(" AND ")));
+
+    #[tokio::test]
+    async fn upgrade_closes_pre_lifecycle_findings() {
+        let db = sea_orm::Database::connect("sqlite::memory:").await.unwrap();
+        db.execute_unprepared(
+            "CREATE TABLE FDD_FINDINGS (id INTEGER PRIMARY KEY AUTOINCREMENT, device_serial INTEGER NOT NULL, \
+             equipment TEXT, rule_id TEXT NOT NULL, rule_name TEXT, severity TEXT, fault_hours REAL, evidence TEXT, \
+             created_at TEXT DEFAULT (datetime('now'))); \
+             INSERT INTO FDD_FINDINGS (device_serial, equipment, rule_id) VALUES (100, '', 'SAT-HIGH');",
+        )
+        .await
+        .unwrap();
+        ensure_schema(&db).await.unwrap();
+        assert_eq!(active_fault_id(&db, 100, "", "SAT-HIGH").await.unwrap(), None);
+
+        // Later upgrades leave lifecycle rows alone.
+        let ev = json!({});
+        persist_finding(&db, 100, "", &record(&ev, 0.5, None)).await.unwrap();
+        ensure_schema(&db).await.unwrap();
+        assert!(active_fault_id(&db, 100, "", "SAT-HIGH").await.unwrap().is_some());
     }
-    sql.push_str(" ORDER BY created_at DESC");
-    sql.push_str(&format!(" LIMIT {}", limit));
 
-    let rows = db
-        .query_all(sea_orm::Statement::from_string(sea_orm::DatabaseBackend::Sqlite, sql))
+    #[tokio::test]
+    async fn fault_lifecycle_open_ongoing_cleared() {
+        let db = memory_db().await;
+        let ev = json!({});
+
+        let s1 = persist_finding(&db, 100, "AHU-1", &record(&ev, 0.5, None)).await.unwrap();
+        let s2 = persist_finding(&db, 100, "AHU-1", &record(&ev, 0.75, None)).await.unwrap();
+        assert_eq!(s1, FaultStatus::Open);
+        assert_eq!(s2, FaultStatus::Ongoing);
+
+        let active = list_findings(&db, Some(100), None, Some("active"), 10).await.unwrap();
+        assert_eq!(active.len(), 1, "repeat detections must not duplicate rows");
+        assert_eq!(active[0]["occurrences"], 1, "repeat detections are one episode");
+        assert_eq!(active[0]["status"], "ongoing");
+
+        assert!(clear_finding(&db, 100, "AHU-1", "SAT-HIGH").await.unwrap());
+        assert!(!clear_finding(&db, 100, "AHU-1", "SAT-HIGH").await.unwrap());
+        assert!(list_findings(&db, Some(100), None, Some("active"), 10).await.unwrap().is_empty());
+        let cleared = list_findings(&db, Some(100), None, Some("cleared"), 10).await.unwrap();
+        assert_eq!(cleared.len(), 1);
+        assert!(cleared[0]["cleared_at"].is_string());
+
+        // A recurrence after clearing opens a fresh record as the second episode.
+        let s3 = persist_finding(&db, 100, "AHU-1", &record(&ev, 0.25, None)).await.unwrap();
+        assert_eq!(s3, FaultStatus::Open);
+        assert_eq!(list_findings(&db, Some(100), None, None, 10).await.unwrap().len(), 2);
+        let active = list_findings(&db, Some(100), None, Some("active"), 10).await.unwrap();
+        assert_eq!(active[0]["occurrences"], 2);
+        // Other equipment counts its own episodes.
+        persist_finding(&db, 100, "AHU-2", &record(&ev, 0.25, None)).await.unwrap();
+        let other = list_findings(&db, Some(100), None, Some("active"), 10).await.unwrap();
+        assert!(other.iter().any(|f| f["equipment"] == "AHU-2" && f["occurrences"] == 1));
+    }
+
+    #[tokio::test]
+    async fn ongoing_faults_accumulate_impact_over_new_time_only() {
+        let db = memory_db().await;
+        let ev = json!({});
+        let est = |hours: f64| Some(Impact { kwh: 10.0 * hours, cost: Some(2.0 * hours) });
+        persist_finding(&db, 100, "AHU-1", &record(&ev, 2.0, est(2.0))).await.unwrap();
+
+        // An hour later a 2 h window repeats one already counted hour
+        db.execute_unprepared("UPDATE FDD_FINDINGS SET last_seen = datetime('now', '-1 hour')").await.unwrap();
+        persist_finding(&db, 100, "AHU-1", &record(&ev, 2.0, est(2.0))).await.unwrap();
+        // Re-evaluating right away adds nothing new
+        persist_finding(&db, 100, "AHU-1", &record(&ev, 2.0, est(2.0))).await.unwrap();
+
+        let active = list_findings(&db, Some(100), None, Some("active"), 10).await.unwrap();
+        assert!((active[0]["est_kwh"].as_f64().unwrap() - 30.0).abs() < 0.01, "{}", active[0]);
+        assert!((active[0]["est_cost"].as_f64().unwrap() - 6.0).abs() < 0.01, "{}", active[0]);
+    }
+
+    #[tokio::test]
+    async fn top_faults_rank_by_estimated_cost() {
+        let db = memory_db().await;
+        let ev = json!({});
+        let cheap = Impact { kwh: 10.0, cost: Some(1.5) };
+        let dear = Impact { kwh: 40.0, cost: Some(6.0) };
+        persist_finding(&db, 1, "AHU-1", &record(&ev, 1.0, Some(cheap))).await.unwrap();
+        persist_finding(&db, 2, "AHU-1", &record(&ev, 2.0, Some(dear))).await.unwrap();
+        persist_finding(&db, 3, "AHU-1", &record(&ev, 5.0, None)).await.unwrap();
+
+        let top = top_faults_by_cost(&db, &FindingsFilter::default(), 10).await.unwrap();
+        assert_eq!(top.len(), 2, "findings without an estimate are not ranked");
+        assert_eq!(top[0]["device_serial"], 2);
+        assert_eq!(top[0]["est_cost"], 6.0);
+        assert_eq!(top[1]["est_kwh"], 10.0);
+
+        let filter = FindingsFilter {
+            serial_number: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(top_faults_by_cost(&db, &filter, 10).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn ensure_schema_upgrades_legacy_findings_table() {
+        let db = sea_orm::Database::connect("sqlite::memory:").await.unwrap();
+        db.execute(sea_orm::Statement::from_string(
+            sea_orm::DatabaseBackend::Sqlite,
+            "CREATE TABLE FDD_FINDINGS (id INTEGER PRIMARY KEY AUTOINCREMENT, device_serial INTEGER NOT NULL, \
+             equipment TEXT, rule_id TEXT NOT NULL, rule_name TEXT, severity TEXT, fault_hours REAL, evidence TEXT, \
+             created_at TEXT DEFAULT (datetime('now')));
+             INSERT INTO FDD_FINDINGS (device_serial, equipment, rule_id) VALUES (7, '', 'CHW-1');"
+                .to_string(),
+        ))
         .await
-        .map_err(|e| format!("FDD findings query error: {}", e))?;
-    Ok(rows
-        .iter()
-        .map(|r| {
-            json!({
-                "id": r.try_get::<i64>("", "id").unwrap_or(0),
-                "device_serial": r.try_get::<i32>("", "device_serial").unwrap_or(0),
-                "equipment": r.try_get::<String>("", "equipment").ok(),
-                "rule_id": r.try_get::<String>("", "rule_id").unwrap_or_default(),
-                "rule_name": r.try_get::<String>("", "rule_name").ok(),
-                "severity": r.try_get::<String>("", "severity").unwrap_or_default(),
-                "fault_hours": r.try_get::<f64>("", "fault_hours").unwrap_or(0.0),
-                "evidence": r.try_get::<String>("", "evidence").ok().and_then(|s| serde_json::from_str::<Value>(&s).ok()),
-                "created_at": r.try_get::<String>("", "created_at").unwrap_or_default(),
-            })
-        })
-        .collect())
+        .unwrap();
+
+        ensure_schema(&db).await.unwrap();
+        ensure_schema(&db).await.unwrap();
+
+        let rows = list_findings(&db, Some(7), None, None, 10).await.unwrap();
+        assert_eq!(rows.len(), 1);
+        assert!(rows[0]["first_seen"].is_string(), "legacy rows get first_seen backfilled");
+    }
 }
@@ -0,0 +1,215 @@
+//! Background FDD scheduler — continuous evaluation of enabled rules.
+//!
+//! Every tick the scheduler walks the devices that have Haystack point tags and
+//! runs each enabled FDD rule whose `poll_seconds` has elapsed since its last
+//! run on that device. Results go through the same `fdd::analyze` path as the
+//! MCP tool, so findings are tracked as open → ongoing → cleared lifecycle
+//! records rather than appended on every pass.
+
+use sea_orm::{ConnectionTrait, DatabaseConnection};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+use super::rules::{self, Rule};
+use crate::db_connection::establish_t3_device_connection;
+
+/// Default rule interval when `poll_seconds` is missing from `params_json`.
+const DEFAULT_POLL_SECONDS: u64 = 300;
+/// Shortest `poll_seconds` a rule may declare.
+pub const MIN_POLL_SECONDS: u64 = 10;
+/// Longest `poll_seconds` a rule may declare; a rule must fit the default
+/// scheduler window at least once.
+pub const MAX_POLL_SECONDS: u64 = 3600;
+
+static SCHEDULER_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Scheduler tuning.
+#[derive(Debug, Clone)]
+pub struct FddSchedulerConfig {
+    /// How often the scheduler wakes up to look for due rules.
+    pub tick_secs: u64,
+    /// History window handed to each evaluation.
+    pub window_hours: u64,
+    /// Delay before the first cycle so startup sync can populate trendlogs.
+    pub initial_delay_secs: u64,
+}
+
+impl Default for FddSchedulerConfig {
+    fn default() -> Self {
+        Self {
+            tick_secs: 60,
+            window_hours: 2,
+            initial_delay_secs: 120,
+        }
+    }
+}
+
+/// Per-(device, rule) bookkeeping of the last evaluation time.
+#[derive(Debug, Default)]
+pub struct ScheduleState {
+    last_run: HashMap<(i32, String), Instant>,
+}
+
+impl ScheduleState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rules on `serial` whose poll interval has elapsed at `now`.
+    pub fn due_rules(&self, serial: i32, rules: &[Rule], now: Instant) -> Vec<String> {
+        rules
+            .iter()
+            .filter(|r| r.enabled)
+            .filter(|r| match self.last_run.get(&(serial, r.rule_id.clone())) {
+                Some(last) => now.duration_since(*last) >= Duration::from_secs(poll_seconds(r)),
+                None => true,
+            })
+            .map(|r| r.rule_id.clone())
+            .collect()
+    }
+
+    pub fn mark_run(&mut self, serial: i32, rule_ids: &[String], now: Instant) {
+        for id in rule_ids {
+            self.last_run.insert((serial, id.clone()), now);
+        }
+    }
+}
+
+/// A rule's evaluation interval, taken from `params_json.poll_seconds`.
+pub fn poll_seconds(rule: &Rule) -> u64 {
+    rule.params
+        .get("poll_seconds")
+        .and_then(|v| v.as_u64())
+        .filter(|v| *v > 0)
+        .unwrap_or(DEFAULT_POLL_SECONDS)
+}
+
+/// Whether the background scheduler loop is currently running.
+pub fn is_running() -> bool {
+    SCHEDULER_RUNNING.load(Ordering::SeqCst)
+}
+
+/// Devices FDD can run on: every serial with at least one tagged point.
+async fn tagged_devices(db: &DatabaseConnection) -> Result<Vec<i32>, String> {
+    let rows = db
+        .query_all(sea_orm::Statement::from_string(
+            sea_orm::DatabaseBackend::Sqlite,
+            "SELECT DISTINCT serial_number FROM HAYSTACK_POINT_TAGS ORDER BY serial_number".to_string(),
+        ))
+        .await
+        .map_err(|e| format!("FDD device query failed: {}", e))?;
+    Ok(rows
+        .iter()
+        .filter_map(|r| r.try_get::<i32>("", "serial_number").ok())
+        .collect())
+}
+
+/// Run one scheduler pass: evaluate every due rule on every tagged device.
+/// Returns the number of (device, rule) evaluations performed.
+pub async fn run_cycle(
+    db: &DatabaseConnection,
+    state: &mut ScheduleState,
+    window_hours: u64,
+) -> Result<usize, String> {
+    rules::ensure_schema(db).await?;
+    let enabled = rules::get_rules(db, &[]).await?;
+    if enabled.is_empty() {
+        return Ok(0);
+    }
+
+    let mut evaluated = 0usize;
+    for serial in tagged_devices(db).await? {
+        let now = Instant::now();
+        let due = state.due_rules(serial, &enabled, now);
+        if due.is_empty() {
+            continue;
+        }
+        match super::analyze(db, serial, "", window_hours, &due, None).await {
+            Ok(_) => evaluated += due.len(),
+            Err(e) => warn!("FDD scheduler: device {} evaluation failed: {}", serial, e),
+        }
+        // Mark as run even on failure so a broken device does not spin every tick.
+        state.mark_run(serial, &due, now);
+    }
+    Ok(evaluated)
+}
+
+/// Spawn the background FDD scheduler. A second call while it is running is a no-op.
+pub async fn start_fdd_scheduler(config: FddSchedulerConfig) -> Result<(), String> {
+    if SCHEDULER_RUNNING.swap(true, Ordering::SeqCst) {
+        return Ok(());
+    }
+
+    tokio::spawn(async move {
+        info!(
+            "FDD scheduler started (tick {}s, window {}h)",
+            config.tick_secs, config.window_hours
+        );
+        tokio::time::sleep(Duration::from_secs(config.initial_delay_secs)).await;
+
+        let mut state = ScheduleState::new();
+        loop {
+            match establish_t3_device_connection().await.map_err(|e| e.to_string()) {
+                Ok(db) => match run_cycle(&db, &mut state, config.window_hours).await {
+                    Ok(n) if n > 0 => debug!("FDD scheduler: {} rule evaluations", n),
+                    Ok(_) => {}
+                    Err(e) => warn!("FDD scheduler cycle failed: {}", e),
+                },
+                Err(e) => warn!("FDD scheduler: database unavailable: {}", e),
+            }
+            tokio::time::sleep(Duration::from_secs(config.tick_secs.max(1))).await;
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rule(id: &str, poll: u64) -> Rule {
+        Rule {
+            rule_id: id.into(),
+            rule_name: id.into(),
+            category: "test".into(),
+            description: None,
+            rule_kind: "ThresholdAbove".into(),
+            required_roles: vec![],
+            params: json!({ "poll_seconds": poll }),
+            severity: "warning".into(),
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn every_rule_is_due_on_first_pass() {
+        let state = ScheduleState::new();
+        let rules = vec![rule("A", 60), rule("B", 600)];
+        assert_eq!(state.due_rules(1, &rules, Instant::now()), vec!["A", "B"]);
+    }
+
+    #[test]
+    fn rules_wait_for_their_own_poll_interval() {
+        let mut state = ScheduleState::new();
+        let rules = vec![rule("A", 60), rule("B", 600)];
+        let t0 = Instant::now();
+        state.mark_run(1, &["A".into(), "B".into()], t0);
+
+        assert!(state.due_rules(1, &rules, t0 + Duration::from_secs(30)).is_empty());
+        assert_eq!(state.due_rules(1, &rules, t0 + Duration::from_secs(61)), vec!["A"]);
+        assert_eq!(state.due_rules(1, &rules, t0 + Duration::from_secs(601)), vec!["A", "B"]);
+        // Other devices keep their own schedule.
+        assert_eq!(state.due_rules(2, &rules, t0).len(), 2);
+    }
+
+    #[test]
+    fn missing_poll_seconds_falls_back_to_default() {
+        let mut r = rule("A", 0);
+        r.params = json!({});
+        assert_eq!(poll_seconds(&r), DEFAULT_POLL_SECONDS);
+    }
+}
@@ -1,45 +1,105 @@
 //! Time-series loader — TRENDLOG_DATA_DETAIL → aligned `Vec<Sample>`.
 //!
 //! Pivots the long trendlog rows into a "wide" set of samples keyed by role,
-//! exactly the shape the evaluator consumes.
+//! exactly the shape the evaluator consumes. Points are rarely logged on the
+//! same second, so rows are resampled onto fixed buckets with last-value
+//! carry-forward (bounded by a max staleness) and explicit gap markers.
 
-use chrono::{Duration, Utc};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
 use sea_orm::ConnectionTrait;
+use serde::Deserialize;
 use std::collections::{BTreeMap, HashMap};
 
 use super::roles::RolePoint;
+use crate::server_db::trendlog_detail::DetailLayout;
+
+/// Format of `LoggingTime_Fmt` and of every `Sample::ts`.
+pub const TS_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
 
 /// One timestamp with the values of every role that was present.
 #[derive(Debug, Clone)]
 pub struct Sample {
-    /// LoggingTime_Fmt from the trendlog (e.g. "2025-10-28 13:35:49").
+    /// LoggingTime_Fmt from the trendlog (e.g. "2025-10-28 13:35:49"), or the
+    /// bucket start when the series was resampled.
     pub ts: String,
     /// role → numeric value at this timestamp.
     pub values: HashMap<String, f64>,
+    /// Gap marker: no role had data here, not even a carried-forward value.
+    /// `values` is empty and evaluators break their confirm streak.
+    pub gap: bool,
+}
+
+impl Sample {
+    pub fn time(&self) -> Option<NaiveDateTime> {
+        NaiveDateTime::parse_from_str(&self.ts, TS_FORMAT).ok()
+    }
+}
+
+/// How trendlog rows are aligned into samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub struct ResampleConfig {
+    /// Bucket width in seconds. 0 = no resampling: rows are pivoted on exact
+    /// `LoggingTime_Fmt` equality (the original behaviour).
+    pub bucket_seconds: u64,
+    /// How long a role's last value may be carried forward into later buckets
+    /// before the role counts as missing. 0 = per role, three of its
+    /// trendlog's own logging intervals (never less than three buckets), so a
+    /// slowly logged point isn't mistaken for an outage.
+    pub max_staleness_seconds: u64,
+}
+
+impl ResampleConfig {
+    /// Buckets of `secs`, carrying each role forward for three of its logging
+    /// intervals.
+    pub fn for_interval(secs: u64) -> Self {
+        Self {
+            bucket_seconds: secs,
+            max_staleness_seconds: 0,
+        }
+    }
+
+    /// Config from optional request fields; `None` when neither is given.
+    pub fn from_options(bucket_seconds: Option<u64>, max_staleness_seconds: Option<u64>) -> Option<Self> {
+        if bucket_seconds.is_none() && max_staleness_seconds.is_none() {
+            return None;
+        }
+        let mut cfg = Self::for_interval(bucket_seconds.unwrap_or(300));
+        if let Some(st) = max_staleness_seconds {
+            cfg.max_staleness_seconds = st;
+        }
+        Some(cfg)
+    }
+}
+
+impl Default for ResampleConfig {
+    fn default() -> Self {
+        Self::for_interval(300)
+    }
 }
 
-/// Load history for the mapped points of a device over `range_hours`.
-pub async fn load_series(
+/// Load the `(role, time, value)` history of the mapped points of a device
+/// over `range_hours`, in trendlog order. [`align`] turns it into samples.
+pub async fn load_rows(
     db: &sea_orm::DatabaseConnection,
     serial: i32,
     role_map: &HashMap<String, RolePoint>,
     range_hours: u64,
-) -> Result<Vec<Sample>, String> {
-    let start = (Utc::now() - Duration::hours(range_hours as i64))
-        .format("%Y-%m-%d %H:%M:%S")
-        .to_string();
-
-    // time → role → value
-    let mut by_time: BTreeMap<String, HashMap<String, f64>> = BTreeMap::new();
+) -> Result<Vec<(String, String, f64)>, String> {
+    let start = (Utc::now() - Duration::hours(range_hours as i64)).naive_utc();
+    let layout = DetailLayout::detect(db, "main")
+        .await
+        .map_err(|e| format!("Trendlog layout check failed: {}", e))?;
 
+    // (role, time, value) in trendlog order
+    let mut rows_out: Vec<(String, String, f64)> = Vec::new();
     for (role, point) in role_map {
         // Locate the TRENDLOG_DATA parent row for this point.
-        let parent_sql = format!(
-            "SELECT id FROM TRENDLOG_DATA WHERE SerialNumber = {} AND PointType = '{}' AND PointIndex = {} LIMIT 1",
-            serial, point.point_type, point.point_index
-        );
         let parent_rows = db
-            .query_all(sea_orm::Statement::from_string(sea_orm::DatabaseBackend::Sqlite, parent_sql))
+            .query_all(sea_orm::Statement::from_sql_and_values(
+                sea_orm::DatabaseBackend::Sqlite,
+                "SELECT id FROM TRENDLOG_DATA WHERE SerialNumber = ? AND PointType = ? AND PointIndex = ? LIMIT 1",
+                [serial.into(), point.point_type.as_str().into(), point.point_index.into()],
+            ))
             .await
             .map_err(|e| format!("Trendlog parent query failed: {}", e))?;
         let Some(parent_row) = parent_rows.first() else { continue };
@@ -49,25 +109,226 @@ pub async fn load_series(
 
         // Pull the history for this point.
         let detail_sql = format!(
-            "SELECT Value, LoggingTime_Fmt FROM TRENDLOG_DATA_DETAIL WHERE ParentId = {} AND LoggingTime_Fmt >= '{}' ORDER BY LoggingTime_Fmt",
-            parent_id, start
+            "SELECT d.Value, {} AS NumericValue, d.LoggingTime_Fmt FROM TRENDLOG_DATA_DETAIL d WHERE d.ParentId = ? AND {} ORDER BY d.LoggingTime_Fmt",
+            layout.numeric_sql("d"),
+            layout.time_range_sql("d", Some(start), None)
         );
         let detail_rows = db
-            .query_all(sea_orm::Statement::from_string(sea_orm::DatabaseBackend::Sqlite, detail_sql))
+            .query_all(sea_orm::Statement::from_sql_and_values(
+                sea_orm::DatabaseBackend::Sqlite,
+                detail_sql,
+                [parent_id.into()],
+            ))
             .await
             .map_err(|e| format!("Trendlog detail query failed: {}", e))?;
 
         for r in &detail_rows {
-            let v: String = r.try_get("", "Value").unwrap_or_default();
             let t: String = r.try_get("", "LoggingTime_Fmt").unwrap_or_default();
-            if let Ok(num) = v.trim().parse::<f64>() {
-                by_time.entry(t).or_default().insert(role.clone(), num);
+            let stored: Option<f64> = r.try_get("", "NumericValue").ok().flatten();
+            let num = stored.or_else(|| {
+                let v: String = r.try_get("", "Value").unwrap_or_default();
+                v.trim().parse::<f64>().ok()
+            });
+            if let Some(num) = num {
+                rows_out.push((role.clone(), t, num));
             }
         }
     }
 
-    Ok(by_time
+    Ok(rows_out)
+}
+
+/// Align rows from [`load_rows`] into samples per `cfg`.
+pub fn align(rows: &[(String, String, f64)], cfg: &ResampleConfig) -> Vec<Sample> {
+    if cfg.bucket_seconds == 0 {
+        return pivot_exact(rows);
+    }
+    resample(rows, cfg)
+}
+
+/// Pivot on exact timestamp strings — rows only share a sample when they were
+/// logged at the same second.
+fn pivot_exact(rows: &[(String, String, f64)]) -> Vec<Sample> {
+    // time → role → value
+    let mut by_time: BTreeMap<String, HashMap<String, f64>> = BTreeMap::new();
+    for (role, ts, v) in rows {
+        by_time.entry(ts.clone()).or_default().insert(role.clone(), *v);
+    }
+    by_time
         .into_iter()
-        .map(|(ts, values)| Sample { ts, values })
-        .collect())
+        .map(|(ts, values)| Sample { ts, values, gap: false })
+        .collect()
+}
+
+/// Align (role, time, value) rows onto fixed buckets.
+///
+/// Each bucket takes the latest value of each role logged inside it; a role
+/// with nothing in the bucket carries its last value forward while that value
+/// is at most its staleness limit older than the bucket start (see
+/// [`ResampleConfig::max_staleness_seconds`]). A bucket in
+/// which no role has a value becomes a single gap marker (consecutive empty
+/// buckets collapse into one), so evaluators can tell an outage from data.
+pub fn resample(rows: &[(String, String, f64)], cfg: &ResampleConfig) -> Vec<Sample> {
+    let bucket = cfg.bucket_seconds.max(1) as i64;
+
+    // bucket index → role → (epoch, value), latest reading in the bucket wins
+    let mut fresh: BTreeMap<i64, HashMap<&str, (i64, f64)>> = BTreeMap::new();
+    let mut times: HashMap<&str, Vec<i64>> = HashMap::new();
+    for (role, ts, v) in rows {
+        let Ok(t) = NaiveDateTime::parse_from_str(ts, TS_FORMAT) else { continue };
+        let epoch = t.and_utc().timestamp();
+        times.entry(role.as_str()).or_default().push(epoch);
+        let slot = fresh.entry(epoch.div_euclid(bucket)).or_default();
+        match slot.get(role.as_str()) {
+            Some((prev, _)) if *prev > epoch => {}
+            _ => {
+                slot.insert(role.as_str(), (epoch, *v));
+            }
+        }
+    }
+    let (Some(&first), Some(&last)) = (fresh.keys().next(), fresh.keys().next_back()) else {
+        return Vec::new();
+    };
+    let staleness: HashMap<&str, i64> = times
+        .into_iter()
+        .map(|(role, epochs)| {
+            let limit = match cfg.max_staleness_seconds {
+                0 => (3 * logging_interval(epochs)).max(3 * bucket),
+                st => st as i64,
+            };
+            (role, limit)
+        })
+        .collect();
+
+    let mut out: Vec<Sample> = Vec::new();
+    let mut carried: HashMap<&str, (i64, f64)> = HashMap::new();
+    let mut b = first;
+    while b <= last {
+        if let Some(slot) = fresh.get(&b) {
+            carried.extend(slot.iter().map(|(k, v)| (*k, *v)));
+        }
+        let bucket_start = b * bucket;
+        carried.retain(|role, (t, _)| bucket_start - *t <= staleness[role]);
+        let ts = DateTime::from_timestamp(bucket_start, 0)
+            .map(|d| d.naive_utc().format(TS_FORMAT).to_string())
+            .unwrap_or_default();
+
+        if carried.is_empty() {
+            if !out.last().is_some_and(|s| s.gap) {
+                out.push(Sample {
+                    ts,
+                    values: HashMap::new(),
+                    gap: true,
+                });
+            }
+            // Nothing left to carry: skip straight to the next bucket with data.
+            match fresh.range(b + 1..).next() {
+                Some((next, _)) => b = *next,
+                None => break,
+            }
+            continue;
+        }
+
+        out.push(Sample {
+            ts,
+            values: carried.iter().map(|(k, (_, v))| (k.to_string(), *v)).collect(),
+            gap: false,
+        });
+        b += 1;
+    }
+    out
+}
+
+/// Typical spacing of one trendlog's readings: the median step between
+/// consecutive timestamps, so an outage doesn't stretch it. 0 when unknown.
+fn logging_interval(mut epochs: Vec<i64>) -> i64 {
+    epochs.sort_unstable();
+    epochs.dedup();
+    let mut steps: Vec<i64> = epochs.windows(2).map(|w| w[1] - w[0]).collect();
+    if steps.is_empty() {
+        return 0;
+    }
+    let mid = steps.len() / 2;
+    *steps.select_nth_unstable(mid).1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(role: &str, ts: &str, v: f64) -> (String, String, f64) {
+        (role.to_string(), ts.to_string(), v)
+    }
+
+    #[test]
+    fn rows_seconds_apart_share_a_bucket() {
+        let rows = vec![
+            row("mat", "2026-01-01 10:00:03", 60.0),
+            row("rat", "2026-01-01 10:00:07", 70.0),
+            row("oa_t", "2026-01-01 10:00:11", 40.0),
+        ];
+        let out = resample(&rows, &ResampleConfig::for_interval(60));
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].ts, "2026-01-01 10:00:00");
+        assert_eq!(out[0].values.len(), 3);
+        // Exact pivoting keeps them apart.
+        assert_eq!(pivot_exact(&rows).len(), 3);
+    }
+
+    #[test]
+    fn carry_forward_is_bounded_by_staleness() {
+        let rows = vec![
+            row("sat", "2026-01-01 10:00:00", 55.0),
+            row("oa_t", "2026-01-01 10:00:00", 40.0),
+            row("sat", "2026-01-01 10:01:00", 56.0),
+            row("sat", "2026-01-01 10:02:00", 57.0),
+            row("sat", "2026-01-01 10:03:00", 58.0),
+        ];
+        let cfg = ResampleConfig {
+            bucket_seconds: 60,
+            max_staleness_seconds: 120,
+        };
+        let out = resample(&rows, &cfg);
+        assert_eq!(out.len(), 4);
+        assert_eq!(out[2].values.get("oa_t"), Some(&40.0));
+        assert_eq!(out[3].values.get("oa_t"), None);
+        assert_eq!(out[3].values.get("sat"), Some(&58.0));
+    }
+
+    #[test]
+    fn outages_become_a_single_gap_marker() {
+        let rows = vec![
+            row("sat", "2026-01-01 10:00:00", 55.0),
+            row("sat", "2026-01-01 10:05:00", 55.0),
+            row("sat", "2026-01-01 10:10:00", 55.0),
+            row("sat", "2026-01-01 14:00:00", 56.0),
+        ];
+        let out = resample(&rows, &ResampleConfig::for_interval(300));
+        let gaps: Vec<&Sample> = out.iter().filter(|s| s.gap).collect();
+        assert_eq!(gaps.len(), 1);
+        assert!(gaps[0].values.is_empty());
+        assert_eq!(out.first().unwrap().ts, "2026-01-01 10:00:00");
+        assert_eq!(out.last().unwrap().ts, "2026-01-01 14:00:00");
+        // Three logged buckets, three carried (one 5-minute interval each), the gap, then 14:00.
+        assert_eq!(out.len(), 8);
+    }
+
+    #[test]
+    fn slow_trendlogs_carry_across_their_own_interval() {
+        // A 15-minute monitor under 60 s buckets stays continuous.
+        let rows: Vec<_> = (0..4)
+            .map(|i| row("oa_t", &format!("2026-01-01 10:{:02}:00", i * 15), 40.0))
+            .chain((0..46).map(|m| row("sat", &format!("2026-01-01 10:{:02}:00", m), 55.0)))
+            .collect();
+        let out = resample(&rows, &ResampleConfig::for_interval(60));
+        assert_eq!(out.len(), 46);
+        assert!(out.iter().all(|s| !s.gap && s.values.contains_key("oa_t")));
+        // An explicit limit still applies as given.
+        let cfg = ResampleConfig {
+            bucket_seconds: 60,
+            max_staleness_seconds: 180,
+        };
+        let out = resample(&rows, &cfg);
+        assert_eq!(out[4].values.get("oa_t"), None);
+    }
 }
@@ -4,6 +4,7 @@ use db_connection::establish_t3_device_connection;
 use server_db::partition_monitor_service;
 
 pub mod app_state;
+pub mod audit; // Audit trail for point writes and configuration changes
 pub mod auth;
 pub mod server_db_writer; // Global server DB state for server/client dual-write
 pub mod constants;
@@ -32,6 +33,12 @@ pub mod mcp;
 // LAN scan — pure-Rust UDP broadcast scanner (0x64/0x65 protocol)
 pub mod lan_scan;
 
+// BACnet/IP — pure-Rust client used as an alternative to the T3000.exe FFI
+pub mod bacnet;
+
+// Modbus TCP — register poller driven by the modbus_register definitions
+pub mod modbus;
+
 // T3000 device modules
 pub mod t3_device;
 pub mod haystack;
@@ -260,6 +267,31 @@ pub async fn start_all_services() -> Result<(), Box<dyn std::error::Error>> {
                 &format!("poll every {}s ({}min) — from APPLICATION_CONFIG", sync_interval_secs, sync_interval_secs / 60), None).await;
     }
 
+    // Select the device transport (T3_DEVICE_TRANSPORT) before anything talks to panels
+    match t3_device::transport::select_from_env() {
+        Ok(name) => {
+            emit_service_log("info", "T3_Webview_Initialize", &format!("Device transport: {}", name)).await;
+            if let Some((ref fh, ref db)) = flow_opt {
+                fh.step(db, "device_transport", "info", "lib", "ok", 0,
+                        &format!("transport={}", name), None).await;
+            }
+            // The simulator also answers LAN scans so discovery finds its panels
+            if name == "sim" {
+                match t3_device::transport::sim::start_lan_responder().await {
+                    Ok(addr) => emit_service_log("info", "T3_Webview_Initialize", &format!("Simulator LAN-scan responder listening on {}", addr)).await,
+                    Err(e) => emit_service_log("warn", "T3_Webview_Initialize", &format!("{} - simulator continues without LAN-scan answers", e)).await,
+                }
+            }
+        }
+        Err(e) => {
+            let error_msg = format!("{} - using platform default device transport", e);
+            emit_service_log("warn", "T3_Webview_Initialize", &error_msg).await;
+            if let Some((ref fh, ref db)) = flow_opt {
+                fh.step(db, "device_transport", "warn", "lib", "error", 0, &error_msg, None).await;
+            }
+        }
+    }
+
     let t_ffi = std::time::Instant::now();
     let main_service_config = T3000MainConfig {
         sync_interval_secs,
@@ -380,6 +412,46 @@ pub async fn start_all_services() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
+    // Start background FDD scheduler (evaluates enabled FDD_RULES on each rule's poll_seconds)
+    if crate::constants::ENABLE_FDD_SCHEDULER {
+        match crate::fdd::scheduler::start_fdd_scheduler(Default::default()).await {
+            Ok(_) => {
+                emit_service_log("info", "T3_Webview_Initialize", "✅ FDD scheduler started").await;
+                if let Some((ref fh, ref db)) = flow_opt {
+                    fh.step(db, "fdd_scheduler", "info", "lib", "ok", 0,
+                            "FDD scheduler started", None).await;
+                }
+            }
+            Err(e) => {
+                emit_service_log("warn", "T3_Webview_Initialize",
+                                 &format!("FDD scheduler failed to start: {}", e)).await;
+            }
+        }
+    } else if let Some((ref fh, ref db)) = flow_opt {
+        fh.step(db, "fdd_scheduler", "info", "lib", "skip", 0,
+                "disabled — ENABLE_FDD_SCHEDULER=false", None).await;
+    }
+
+    // Start background Modbus TCP poller (reads mapped modbus_register definitions into trendlogs)
+    if crate::constants::ENABLE_MODBUS_POLLER {
+        match crate::modbus::poller::start_modbus_poller(Default::default()).await {
+            Ok(_) => {
+                emit_service_log("info", "T3_Webview_Initialize", "✅ Modbus poller started").await;
+                if let Some((ref fh, ref db)) = flow_opt {
+                    fh.step(db, "modbus_poller", "info", "lib", "ok", 0,
+                            "Modbus poller started", None).await;
+                }
+            }
+            Err(e) => {
+                emit_service_log("warn", "T3_Webview_Initialize",
+                                 &format!("Modbus poller failed to start: {}", e)).await;
+            }
+        }
+    } else if let Some((ref fh, ref db)) = flow_opt {
+        fh.step(db, "modbus_poller", "info", "lib", "skip", 0,
+                "disabled — ENABLE_MODBUS_POLLER=false", None).await;
+    }
+
     // Schedule startup partition migration check (5 minute delay to allow database stabilization)
     tokio::spawn(async {
         let wait_secs = 300; // 5 minutes
@@ -420,6 +492,53 @@ pub async fn start_all_services() -> Result<(), Box<dyn std::error::Error>> {
         }
     });
 
+    // Backfill TRENDLOG_DATA_DETAIL numeric value / epoch columns in the background
+    // (2 minute delay so it starts after the initial FFI sync)
+    if crate::constants::ENABLE_TRENDLOG_NUMERIC_BACKFILL {
+        tokio::spawn(async {
+            tokio::time::sleep(tokio::time::Duration::from_secs(120)).await;
+
+            let db = match crate::db_connection::establish_t3_device_connection()
+                .await
+                .map_err(|e| e.to_string())
+            {
+                Ok(db) => db,
+                Err(e) => {
+                    emit_service_log(
+                        "warn",
+                        "T3_Webview_Initialize",
+                        &format!("[WARN] Trendlog numeric backfill skipped - database connection failed: {}", e),
+                    )
+                    .await;
+                    return;
+                }
+            };
+            let opts = crate::t3_device::migrate_trendlog_numeric::BackfillOptions::default();
+            match crate::t3_device::migrate_trendlog_numeric::migrate_trendlog_numeric(&db, &opts).await {
+                Ok(summary) => {
+                    emit_service_log(
+                        "info",
+                        "T3_Webview_Initialize",
+                        &format!(
+                            "✅ Trendlog numeric backfill completed ({} rows across {} databases)",
+                            summary.rows_updated(),
+                            summary.databases.len()
+                        ),
+                    )
+                    .await;
+                }
+                Err(e) => {
+                    emit_service_log(
+                        "warn",
+                        "T3_Webview_Initialize",
+                        &format!("[WARN] Trendlog numeric backfill failed: {}", e),
+                    )
+                    .await;
+                }
+            }
+        });
+    }
+
     // Start WebSocket service in background
     let websocket_handle = tokio::spawn(async move {
         let websocket_result = crate::t3_socket::start_websocket_service()
@@ -9,6 +9,8 @@ use std::collections::HashMap;
 
 use crate::mcp::types::{JsonRpcRequest, JsonRpcResponse, JsonRpcError};
 use crate::mcp::tools::TOOLS;
+use crate::mcp::{prompts, resources};
+use crate::mcp::tokens::McpToken;
 use crate::mcp::storage::*;
 use crate::mcp::server::{mcp_log, track_current_device, SERVER_NAME, SERVER_VERSION, PROTOCOL_VERSION};
 use crate::haystack::auto_tagging_service as ats;
@@ -18,12 +20,62 @@ use crate::t3_device::trendlog_data_service::{T3TrendlogDataService, TrendlogHis
 
 // ═══ Request Dispatch ═══ 
 
-pub(crate) async fn handle_request(req: &JsonRpcRequest, db: &sea_orm::DatabaseConnection) -> JsonRpcResponse {
+/// `token` is the caller's MCP API token, if it authenticated with one;
+/// tool calls and resource reads are limited to its scope.
+pub(crate) async fn handle_request(
+    req: &JsonRpcRequest,
+    db: &sea_orm::DatabaseConnection,
+    token: Option<&McpToken>,
+) -> JsonRpcResponse {
     match req.method.as_str() {
         "initialize" => handle_initialize(req),
         "t3000_ping" => handle_ping(req),
-        "tools/list" => handle_tools_list(req),
-        "tools/call" => handle_tools_call(req, db).await,
+        "tools/list" => handle_tools_list(req, token),
+        "tools/call" => handle_tools_call(req, db, token).await,
+        "resources/list" => match resources::list(db).await {
+            Ok(list) => {
+                let list: Vec<Value> = list
+                    .into_iter()
+                    .filter(|r| resource_allowed(token, r["uri"].as_str().unwrap_or("")).is_ok())
+                    .collect();
+                rpc_result(req, json!({ "resources": list }))
+            }
+            Err(e) => rpc_error(req, -32603, e),
+        },
+        "resources/templates/list" => rpc_result(req, json!({ "resourceTemplates": resources::templates() })),
+        "resources/read" => match param_str(req, "uri") {
+            Some(uri) => match resource_allowed(token, uri).and(take_rate(token).await) {
+                Err(e) => rpc_error(req, -32003, e),
+                Ok(()) => match resources::read(uri, db).await {
+                    Ok(contents) => rpc_result(req, json!({ "contents": [contents] })),
+                    Err(e) if e.starts_with("Unknown resource") => rpc_error(req, -32002, e),
+                    Err(e) => rpc_error(req, -32603, e),
+                },
+            },
+            None => rpc_error(req, -32602, "Invalid params: uri required".into()),
+        },
+        // The session side of (un)subscribe is kept by the transport (server.rs)
+        "resources/subscribe" | "resources/unsubscribe" => match param_str(req, "uri") {
+            Some(uri) if resources::Resource::parse(uri).is_some() => match resource_allowed(token, uri) {
+                Ok(()) => rpc_result(req, json!({})),
+                Err(e) => rpc_error(req, -32003, e),
+            },
+            Some(uri) => rpc_error(req, -32002, format!("Unknown resource: {}", uri)),
+            None => rpc_error(req, -32602, "Invalid params: uri required".into()),
+        },
+        "prompts/list" => rpc_result(req, json!({ "prompts": prompts::list() })),
+        "prompts/get" => {
+            let name = param_str(req, "name").unwrap_or("");
+            let arguments = req.params.as_ref()
+                .and_then(|p| p.get("arguments"))
+                .and_then(|a| a.as_object())
+                .cloned()
+                .unwrap_or_default();
+            match prompts::render(name, &arguments) {
+                Ok(prompt) => rpc_result(req, prompt),
+                Err(e) => rpc_error(req, -32602, e),
+            }
+        }
         _ => JsonRpcResponse {
             jsonrpc: "2.0".into(),
             id: req.id.clone(),
@@ -37,6 +89,41 @@ pub(crate) async fn handle_request(req: &JsonRpcRequest, db: &sea_orm::DatabaseC
     }
 }
 
+fn rpc_result(req: &JsonRpcRequest, result: Value) -> JsonRpcResponse {
+    JsonRpcResponse { jsonrpc: "2.0".into(), id: req.id.clone(), result: Some(result), error: None }
+}
+
+fn rpc_error(req: &JsonRpcRequest, code: i32, message: String) -> JsonRpcResponse {
+    JsonRpcResponse {
+        jsonrpc: "2.0".into(),
+        id: req.id.clone(),
+        result: None,
+        error: Some(JsonRpcError { code, message, data: None }),
+    }
+}
+
+/// Whether `token` may read the resource at `uri` (unknown URIs pass; reading them fails later).
+fn resource_allowed(token: Option<&McpToken>, uri: &str) -> Result<(), String> {
+    match (token, resources::Resource::parse(uri)) {
+        (Some(token), Some(resource)) => {
+            let (tool, args) = resource.tool_call();
+            token.authorize(tool, &args)
+        }
+        _ => Ok(()),
+    }
+}
+
+async fn take_rate(token: Option<&McpToken>) -> Result<(), String> {
+    match token {
+        Some(token) => token.take_rate().await,
+        None => Ok(()),
+    }
+}
+
+fn param_str<'a>(req: &'a JsonRpcRequest, key: &str) -> Option<&'a str> {
+    req.params.as_ref()?.get(key)?.as_str()
+}
+
 fn handle_initialize(req: &JsonRpcRequest) -> JsonRpcResponse {
     JsonRpcResponse {
         jsonrpc: "2.0".into(),
@@ -50,6 +137,13 @@ fn handle_initialize(req: &JsonRpcRequest) -> JsonRpcResponse {
             "capabilities": {
                 "tools": {
                     "listChanged": true
+                },
+                "resources": {
+                    "subscribe": true,
+                    "listChanged": false
+                },
+                "prompts": {
+                    "listChanged": false
                 }
             }
         })),
@@ -67,9 +161,10 @@ fn handle_ping(req: &JsonRpcRequest) -> JsonRpcResponse {
     }
 }
 
-fn handle_tools_list(req: &JsonRpcRequest) -> JsonRpcResponse {
+fn handle_tools_list(req: &JsonRpcRequest, token: Option<&McpToken>) -> JsonRpcResponse {
     let tools: Vec<Value> = TOOLS
         .iter()
+        .filter(|t| token.is_none_or(|token| token.allows_tool(t.name)))
         .map(|t| {
             json!({
                 "name": t.name,
@@ -88,7 +183,7 @@ fn handle_tools_list(req: &JsonRpcRequest) -> JsonRpcResponse {
     }
 }
 
-async fn handle_tools_call(req: &JsonRpcRequest, db: &sea_orm::DatabaseConnection) -> JsonRpcResponse {
+async fn handle_tools_call(req: &JsonRpcRequest, db: &sea_orm::DatabaseConnection, token: Option<&McpToken>) -> JsonRpcResponse {
     let params = match &req.params {
         Some(Value::Object(obj)) => obj.clone(),
         _ => {
@@ -104,7 +199,18 @@ async fn handle_tools_call(req: &JsonRpcRequest, db: &sea_orm::DatabaseConnectio
     let tool_name = params.get("name").and_then(|v| v.as_str()).unwrap_or("");
     let arguments = params.get("arguments").cloned().unwrap_or(Value::Null);
 
-    let result = execute_tool(tool_name, &arguments, db).await;
+    // Scoped MCP tokens: allow-lists, read-only and rate limit, reported like tool errors
+    let allowed = match token {
+        Some(token) => match token.authorize(tool_name, &arguments) {
+            Ok(()) => token.take_rate().await,
+            Err(e) => Err(e),
+        },
+        None => Ok(()),
+    };
+    let result = match allowed {
+        Ok(()) => execute_tool(tool_name, &arguments, db).await,
+        Err(e) => Err(e),
+    };
 
     match result {
         Ok(content) => JsonRpcResponse {
@@ -501,6 +607,17 @@ fn get_nav_pages() -> Vec<NavPage> {
     ]
 }
 
+/// Table and writable columns for a `t3000_settings_write` category.
+pub(crate) fn settings_write_target(category: &str) -> Option<(&'static str, &'static [&'static str])> {
+    Some(match category {
+        "network" => ("NETWORK_SETTINGS", &["IP_Address", "Subnet", "Gateway", "MAC_Address", "TCP_Type"]),
+        "communication" => ("COMMUNICATION_SETTINGS", &["COM0_Config", "COM1_Config", "COM2_Config", "COM_Baudrate0", "COM_Baudrate1", "COM_Baudrate2", "UART_Parity0", "UART_Parity1", "UART_Parity2", "UART_Stopbit0", "UART_Stopbit1", "UART_Stopbit2", "Fix_COM_Config"]),
+        "time" => ("TIME_SETTINGS", &["Time_Zone", "Time_Zone_Summer_Daytime", "Enable_SNTP", "SNTP_Server", "Flag_Time_Sync_PC", "Time_Sync_Auto_Manual", "Start_Month", "Start_Day", "End_Month", "End_Day"]),
+        "email" => ("EMAIL_ALARMS", &["SMTP_Type", "SMTP_IP", "SMTP_Domain", "SMTP_Port", "Email_Address", "User_Name", "Password", "Secure_Connection_Type", "To1_Addr", "To2_Addr"]),
+        _ => return None,
+    })
+}
+
 pub async fn execute_tool(
     name: &str,
     args: &Value,
@@ -512,7 +629,11 @@ pub async fn execute_tool(
     // Track the device the user is working with for device_current
     track_current_device(args).await;
 
-    let result = match name {
+    // Audited write tools: capture what's about to change
+    let audit_before = crate::audit::tools::before(name, args, db).await;
+
+    // Async block so early returns (`?`) still reach the audit record below
+    let result: Result<String, String> = async { match name {
         "t3000_haystack_list_tags" => {
             let filter = args.get("filter")
                 .and_then(|v| v.as_str())
@@ -1331,18 +1452,22 @@ pub async fn execute_tool(
 
             let mut updated = 0;
             let mut errors: Vec<String> = Vec::new();
+            let mut skipped: Vec<String> = Vec::new();
             for point in &points {
                 let sn = point.get("serial_number").and_then(|v| v.as_i64()).map(|n| n as i32);
                 let pt = point.get("point_type").and_then(|v| v.as_str());
                 let idx = point.get("point_index").and_then(|v| v.as_i64()).map(|n| n as i32 - 1);
                 let val = point.get("value");
                 let field = point.get("field").and_then(|v| v.as_str()).unwrap_or("value");
-                if let (Some(sn), Some(pt), Some(idx), Some(val)) = (sn, pt, idx, val) {
+                if let (Some(sn), Some(pt), Some(idx)) = (sn, pt, idx) {
                     let value_str = match val {
-                        Value::Number(n) => n.to_string(),
-                        Value::Bool(b) => (if *b { "1" } else { "0" }).to_string(),
-                        Value::String(s) => s.clone(),
-                        _ => continue,
+                        Some(Value::Number(n)) => n.to_string(),
+                        Some(Value::Bool(b)) => (if *b { "1" } else { "0" }).to_string(),
+                        Some(Value::String(s)) => s.clone(),
+                        _ => {
+                            skipped.push(format!("dev{} {}[{}]: skipped, value must be a number, boolean or string", sn, pt, idx));
+                            continue;
+                        }
                     };
                     match point_write_ffi(db, sn, pt, idx, field, &value_str).await {
                         Ok(_) => updated += 1,
@@ -1352,9 +1477,10 @@ pub async fn execute_tool(
             }
 
             let result = json!({
-                "success": errors.is_empty(),
+                "success": errors.is_empty() && skipped.is_empty(),
                 "count": updated,
                 "errors": errors,
+                "skipped": skipped,
                 "timestamp": Utc::now().to_rfc3339(),
             });
             Ok(result.to_string())
@@ -1858,12 +1984,19 @@ pub async fn execute_tool(
 
             let now = Utc::now().to_rfc3339();
             let sql = format!(
-                "UPDATE ALARMS SET Acknowledged = '1' WHERE SerialNumber = {} AND Alarm_ID = '{}'",
+                "UPDATE ALARMS SET Acknowledged = '1' WHERE SerialNumber = {} AND Alarm_ID = '{}'
+                 AND (Acknowledged IS NULL OR Acknowledged != '1')",
                 serial, alarm_id
             );
-            db.execute(sea_orm::Statement::from_string(sea_orm::DatabaseBackend::Sqlite, &sql))
+            let res = db.execute(sea_orm::Statement::from_string(sea_orm::DatabaseBackend::Sqlite, &sql))
                 .await
                 .map_err(|e| format!("Acknowledge failed: {}", e))?;
+            if res.rows_affected() > 0 {
+                crate::mcp::notifications::publish(crate::mcp::notifications::Event::AlarmAcknowledged {
+                    serial_number: serial,
+                    alarm_id: alarm_id.to_string(),
+                });
+            }
 
             Ok(json!({
                 "success": true,
@@ -1918,6 +2051,7 @@ pub async fn execute_tool(
                 limit: Some(limit),
                 point_types: None,
                 specific_points: None,
+                aggregation: None,
             };
 
             let result = T3TrendlogDataService::get_trendlog_history(db, request)
@@ -2024,6 +2158,7 @@ pub async fn execute_tool(
                 limit: Some(limit),
                 point_types: None,
                 specific_points: Some(specific_points),
+                aggregation: None,
             };
 
             let result = T3TrendlogDataService::get_trendlog_history(db, request)
@@ -2227,13 +2362,8 @@ pub async fn execute_tool(
                 return Err("settings_write requires confirm: true for safety".to_string());
             }
 
-            let (table, allowed_fields): (&str, &[&str]) = match category {
-                "network" => ("NETWORK_SETTINGS", &["IP_Address", "Subnet", "Gateway", "MAC_Address", "TCP_Type"]),
-                "communication" => ("COMMUNICATION_SETTINGS", &["COM0_Config", "COM1_Config", "COM2_Config", "COM_Baudrate0", "COM_Baudrate1", "COM_Baudrate2", "UART_Parity0", "UART_Parity1", "UART_Parity2", "UART_Stopbit0", "UART_Stopbit1", "UART_Stopbit2", "Fix_COM_Config"]),
-                "time" => ("TIME_SETTINGS", &["Time_Zone", "Time_Zone_Summer_Daytime", "Enable_SNTP", "SNTP_Server", "Flag_Time_Sync_PC", "Time_Sync_Auto_Manual", "Start_Month", "Start_Day", "End_Month", "End_Day"]),
-                "email" => ("EMAIL_ALARMS", &["SMTP_Type", "SMTP_IP", "SMTP_Domain", "SMTP_Port", "Email_Address", "User_Name", "Password", "Secure_Connection_Type", "To1_Addr", "To2_Addr"]),
-                _ => return Err(format!("Unknown category: {}. Valid: network, communication, time, email", category)),
-            };
+            let (table, allowed_fields) = settings_write_target(category)
+                .ok_or_else(|| format!("Unknown category: {}. Valid: network, communication, time, email", category))?;
 
             // Check record exists
             let check_sql = format!("SELECT SerialNumber FROM {} WHERE SerialNumber = {}", table, serial);
@@ -2987,7 +3117,12 @@ pub async fn execute_tool(
                 .map(|a| a.iter().filter_map(|x| x.as_str().map(String::from)).collect())
                 .unwrap_or_default();
 
-            let result = crate::fdd::analyze(db, serial, &equipment, range_hours, &rule_ids)
+            let resample = crate::fdd::series::ResampleConfig::from_options(
+                args.get("bucket_seconds").and_then(|v| v.as_u64()),
+                args.get("max_staleness_seconds").and_then(|v| v.as_u64()),
+            );
+
+            let result = crate::fdd::analyze(db, serial, &equipment, range_hours, &rule_ids, resample)
                 .await
                 .map_err(|e| format!("FDD analyze failed: {}", e))?;
             serde_json::to_string_pretty(&result)
@@ -3107,10 +3242,10 @@ pub async fn execute_tool(
             let rules = args.get("rules").and_then(|v| v.as_array())
                 .ok_or_else(|| "rules array required".to_string())?
                 .clone();
-            let n = crate::fdd::rules::import_rules(db, &rules)
+            let report = crate::fdd::rules::import_rules(db, &rules)
                 .await
                 .map_err(|e| format!("FDD rule import failed: {}", e))?;
-            serde_json::to_string_pretty(&json!({ "imported": n }))
+            serde_json::to_string_pretty(&json!({ "imported": report.imported, "rejected": report.rejected }))
                 .map_err(|e| format!("Serialize error: {}", e))
         }
 
@@ -3118,14 +3253,34 @@ pub async fn execute_tool(
             crate::fdd::ensure_schema(db).await.map_err(|e| format!("FDD init failed: {}", e))?;
             let serial = args.get("serial_number").and_then(|v| v.as_i64()).map(|n| n as i32);
             let rule_id = args.get("rule_id").and_then(|v| v.as_str());
+            let status = args.get("status").and_then(|v| v.as_str());
             let limit: u64 = args.get("limit").and_then(|v| v.as_u64()).unwrap_or(50);
-            let result = crate::fdd::list_findings(db, serial, rule_id, limit)
+            let result = crate::fdd::list_findings(db, serial, rule_id, status, limit)
                 .await
                 .map_err(|e| format!("FDD faults failed: {}", e))?;
             serde_json::to_string_pretty(&result)
                 .map_err(|e| format!("Serialize error: {}", e))
         }
 
+        "t3000_fdd_top_faults" => {
+            let filter = crate::fdd::rules::FindingsFilter {
+                serial_number: args.get("serial_number").and_then(|v| v.as_i64()).map(|n| n as i32),
+                equipment: args.get("equipment").and_then(|v| v.as_str()).map(String::from),
+                status: args.get("status").and_then(|v| v.as_str()).map(String::from),
+                ..Default::default()
+            };
+            let limit: u64 = args
+                .get("limit")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(10)
+                .clamp(1, crate::fdd::MAX_TOP_FAULTS);
+            let result = crate::fdd::top_faults_by_cost(db, &filter, limit)
+                .await
+                .map_err(|e| format!("FDD top faults failed: {}", e))?;
+            serde_json::to_string_pretty(&result)
+                .map_err(|e| format!("Serialize error: {}", e))
+        }
+
         "t3000_device_diagnostics" => {
             let serial: i32 = args.get("serial_number")
                 .and_then(|v| v.as_i64()).map(|n| n as i32)
@@ -3383,10 +3538,13 @@ pub async fn execute_tool(
         }
 
         _ => Err(format!("Unknown tool: {}", name)),
-    };
+    } }.await;
     match &result {
         Ok(_) => { /* routes.rs logs tool results */ },
         Err(e) => { error!("[MCP] {} FAILED: {}", name, e); },
     }
+    if let Some(before) = audit_before {
+        crate::audit::tools::after(name, args, before, &result, db).await;
+    }
     result
 }
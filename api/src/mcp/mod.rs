@@ -1,6 +1,6 @@
 // MCP (Model Context Protocol) Server — Streamable HTTP transport
 // Exposes 50+ tools for LLM agents via POST /api/mcp (JSON-RPC 2.0)
-// SSE server→client streaming via GET /api/mcp
+// SSE server→client streaming via GET /api/mcp (resource subscription notifications)
 // Session termination via DELETE /api/mcp
 //
 // Protocol spec: https://spec.modelcontextprotocol.io/
@@ -23,12 +23,26 @@
 //   Docs (2):       doc_list, doc_read
 //   Device (12):    trendlog_list, trendlog_export, device_refresh, schedule_list, settings_read, settings_write, device_control,
 //                   program_list, program_read, pid_list, holiday_list, building_summary
+//
+// Resources: t3000://devices, t3000://device/{serial}[/points|/trendlogs|/faults|/alarms],
+//            t3000://device/{serial}/point/{type}/{index}, t3000://device/{serial}/trendlog/{type}/{index},
+//            t3000://alarms, t3000://fdd/findings, t3000://docs[/{path}]
+// Prompts:   commission_ahu, explain_fault, building_health_check, point_trend_review
+//
+// Tokens: `Authorization: Bearer t3mcp_…` scopes a client to a tool allow-list,
+// a device allow-list, read-only mode and a per-minute rate limit.
+// Managed by admins via /api/mcp/tokens.
 
 pub mod types;
 pub mod storage;
 pub mod server;
 pub mod tools;
 pub mod dispatch;
+pub mod resources;
+pub mod prompts;
+pub mod notifications;
+pub mod tokens;
+pub mod token_routes;
 
 // Re-export commonly used items for convenience
 pub use server::{create_mcp_routes, mcp_post_handler, mcp_sse_handler, mcp_delete_handler};
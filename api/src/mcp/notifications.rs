@@ -0,0 +1,166 @@
+//! MCP Notifications — server push over the session's GET /api/mcp SSE stream.
+//!
+//! Alarm, device status and point value changes are published on one bounded
+//! broadcast bus. Each SSE stream forwards the events matching its session's
+//! `resources/subscribe` URIs as `notifications/resources/updated`. A client
+//! that can't keep up loses the oldest events (and is told how many) instead
+//! of holding anything up on the publishing side.
+
+use std::convert::Infallible;
+
+use axum::response::sse::Event as SseEvent;
+use futures::Stream;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+
+use crate::entity::t3_device::alarms;
+use crate::mcp::resources::Resource;
+use crate::mcp::types::SessionStore;
+
+/// Events buffered per subscriber before the oldest are dropped.
+pub const BUS_CAPACITY: usize = 1024;
+
+static BUS: Lazy<broadcast::Sender<Event>> = Lazy::new(|| broadcast::channel(BUS_CAPACITY).0);
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    AlarmRaised { serial_number: i32, alarm_id: String, message: Option<String>, priority: Option<String> },
+    AlarmAcknowledged { serial_number: i32, alarm_id: String },
+    DeviceStatus { serial_number: i32, online: bool },
+    /// `point_index` is 1-based like the tools; input values are in display units.
+    PointValue { serial_number: i32, point_type: String, point_index: i32, value: f64 },
+}
+
+impl Event {
+    /// Canonical URIs (`Resource::uri`) of the resources this event updates.
+    pub fn uris(&self) -> Vec<String> {
+        let resources = match self {
+            Event::AlarmRaised { serial_number, .. } | Event::AlarmAcknowledged { serial_number, .. } => {
+                vec![Resource::Alarms(None), Resource::Alarms(Some(*serial_number as i64))]
+            }
+            Event::DeviceStatus { serial_number, .. } => {
+                vec![Resource::Devices, Resource::Device(*serial_number as i64)]
+            }
+            Event::PointValue { serial_number, point_type, point_index, .. } => vec![
+                Resource::Points(*serial_number as i64),
+                Resource::Point {
+                    serial: *serial_number as i64,
+                    point_type: point_type.to_uppercase(),
+                    point_index: *point_index as i64,
+                },
+            ],
+        };
+        resources.iter().map(Resource::uri).collect()
+    }
+
+    /// JSON-RPC notification for one URI, as the client subscribed it; the
+    /// event rides along so clients don't have to re-read the resource.
+    pub fn notification(&self, uri: &str) -> Value {
+        json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/resources/updated",
+            "params": { "uri": uri, "change": self }
+        })
+    }
+}
+
+/// Publish to every open SSE stream. Never blocks; no-op without listeners.
+pub fn publish(event: Event) {
+    let _ = BUS.send(event);
+}
+
+pub fn subscribe() -> broadcast::Receiver<Event> {
+    BUS.subscribe()
+}
+
+fn is_set(flag: Option<&str>) -> bool {
+    matches!(flag.map(str::trim), Some(s) if !s.is_empty() && s != "0")
+}
+
+/// Publish the transitions between a stored ALARMS row and the status /
+/// acknowledged values written over it (`None` = left unchanged).
+pub fn alarm_update(before: &alarms::Model, status: Option<&str>, acknowledged: Option<&str>) {
+    let alarm_id = before.alarm_id.clone().unwrap_or_default();
+    let was_active = is_set(before.status.as_deref());
+    if !was_active && is_set(status.or(before.status.as_deref())) {
+        publish(Event::AlarmRaised {
+            serial_number: before.serial_number,
+            alarm_id: alarm_id.clone(),
+            message: before.message.clone(),
+            priority: before.priority.clone(),
+        });
+    }
+    if !is_set(before.acknowledged.as_deref()) && is_set(acknowledged) {
+        publish(Event::AlarmAcknowledged { serial_number: before.serial_number, alarm_id });
+    }
+}
+
+/// Publish a device online/offline transition; `before` is the stored is_online.
+pub fn device_status(serial_number: i32, before: Option<i32>, online: bool) {
+    if before != Some(online as i32) {
+        publish(Event::DeviceStatus { serial_number, online });
+    }
+}
+
+/// Publish a point value change between stored fValue text and the synced
+/// raw value. `index` is 0-based as stored.
+pub fn point_value(serial_number: i32, point_type: &str, index: u32, before: Option<&str>, raw: f64) {
+    if before.and_then(|v| v.trim().parse::<f64>().ok()) == Some(raw) {
+        return;
+    }
+    let value = if point_type == "INPUT" { raw / 1000.0 } else { raw };
+    publish(Event::PointValue {
+        serial_number,
+        point_type: point_type.to_string(),
+        point_index: index as i32 + 1,
+        value,
+    });
+}
+
+fn message(level: &str, text: String) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "notifications/message",
+        "params": { "level": level, "logger": "t3000", "data": text }
+    })
+}
+
+fn sse(value: &Value) -> Result<SseEvent, Infallible> {
+    Ok(SseEvent::default().event("message").data(value.to_string()))
+}
+
+/// Notifications for one session until the session is deleted.
+pub(crate) fn session_stream(session_id: String, sessions: SessionStore) -> impl Stream<Item = Result<SseEvent, Infallible>> {
+    let rx = subscribe();
+    futures::stream::unfold((rx, Vec::<Value>::new()), move |(mut rx, mut pending)| {
+        let session_id = session_id.clone();
+        let sessions = sessions.clone();
+        async move {
+            loop {
+                if !pending.is_empty() {
+                    let next = pending.remove(0);
+                    return Some((sse(&next), (rx, pending)));
+                }
+                let event = match rx.recv().await {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        let warning = message("warning", format!("{} notifications dropped: client fell behind", missed));
+                        return Some((sse(&warning), (rx, pending)));
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                };
+                let map = sessions.lock().await;
+                let session = map.get(&session_id)?;
+                pending = event
+                    .uris()
+                    .into_iter()
+                    .filter_map(|uri| session.subscriptions.get(&uri))
+                    .map(|subscribed| event.notification(subscribed))
+                    .collect();
+            }
+        }
+    })
+}
@@ -0,0 +1,120 @@
+//! MCP Prompts — reusable task templates for external agents.
+
+use serde_json::{json, Map, Value};
+
+pub struct PromptArg {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub required: bool,
+}
+
+pub struct PromptDef {
+    pub name: &'static str,
+    pub title: &'static str,
+    pub description: &'static str,
+    pub arguments: &'static [PromptArg],
+    /// User message; `{arg}` placeholders are filled from `prompts/get` arguments.
+    pub template: &'static str,
+}
+
+const SERIAL: PromptArg = PromptArg { name: "serial", description: "Device serial number", required: true };
+
+pub const PROMPTS: &[PromptDef] = &[
+    PromptDef {
+        name: "commission_ahu",
+        title: "Commission an AHU",
+        description: "Walk through a functional check of an air handling unit controller.",
+        arguments: &[
+            SERIAL,
+            PromptArg { name: "notes", description: "Optional: anything already known about the unit", required: false },
+        ],
+        template: "Commission the air handling unit controlled by device {serial}. Notes from site: {notes}\n\n\
+            1. Read t3000://device/{serial} and t3000://device/{serial}/points. Identify supply/return/mixed air \
+            temperatures, fan command and status, damper and valve outputs, and setpoints (use Haystack tags where present).\n\
+            2. Flag points that are in manual, out of range, unlabeled or missing units.\n\
+            3. Check t3000://device/{serial}/trendlogs and the last 24 hours of the key temperatures for \
+            sensor faults, hunting or flatlines.\n\
+            4. Check t3000://device/{serial}/faults for open FDD findings.\n\
+            5. Produce a commissioning checklist with pass/fail per item and the corrective actions needed. \
+            Do not write to any point without asking me first.",
+    },
+    PromptDef {
+        name: "explain_fault",
+        title: "Explain this fault",
+        description: "Explain an FDD finding in plain language, with likely causes and what to check.",
+        arguments: &[
+            SERIAL,
+            PromptArg { name: "rule_id", description: "FDD rule ID of the finding", required: true },
+        ],
+        template: "Explain the fault detection finding for rule {rule_id} on device {serial}.\n\n\
+            Read t3000://device/{serial}/faults to find the finding (first seen, last seen, occurrences), \
+            list the rule with t3000_fdd_rules_list, then read the points the rule evaluates from \
+            t3000://device/{serial}/points and their recent history from the trendlog series resources. \
+            Explain what the rule detected, whether the data supports it, the most likely root causes, \
+            and the field checks a technician should do, in that order.",
+    },
+    PromptDef {
+        name: "building_health_check",
+        title: "Building health check",
+        description: "Summarise device status, alarms and active faults across the building.",
+        arguments: &[],
+        template: "Give me a health check of the building.\n\n\
+            Read t3000://devices and t3000://fdd/findings, and use t3000_alarm_list for active alarms. \
+            Report offline devices, the most costly or longest-running faults (t3000_fdd_top_faults), \
+            and recommend the three things to look at first.",
+    },
+    PromptDef {
+        name: "point_trend_review",
+        title: "Review a point's trend",
+        description: "Look at the last 24 hours of one point and describe its behaviour.",
+        arguments: &[
+            SERIAL,
+            PromptArg { name: "point_type", description: "INPUT, OUTPUT or VARIABLE", required: true },
+            PromptArg { name: "point_index", description: "1-based point index", required: true },
+        ],
+        template: "Read t3000://device/{serial}/trendlog/{point_type}/{point_index} and the point's metadata from \
+            t3000://device/{serial}/points. Describe its range, typical value, daily pattern and any \
+            anomalies (spikes, flatlines, oscillation), and say whether it looks healthy for what it measures.",
+    },
+];
+
+pub fn find(name: &str) -> Option<&'static PromptDef> {
+    PROMPTS.iter().find(|p| p.name == name)
+}
+
+/// `prompts/list` entries.
+pub fn list() -> Vec<Value> {
+    PROMPTS
+        .iter()
+        .map(|p| {
+            let arguments: Vec<Value> = p
+                .arguments
+                .iter()
+                .map(|a| json!({ "name": a.name, "description": a.description, "required": a.required }))
+                .collect();
+            json!({ "name": p.name, "title": p.title, "description": p.description, "arguments": arguments })
+        })
+        .collect()
+}
+
+/// `prompts/get` — the template rendered with `arguments`. Missing required
+/// arguments are an error; missing optional ones render as "none".
+pub fn render(name: &str, arguments: &Map<String, Value>) -> Result<Value, String> {
+    let prompt = find(name).ok_or_else(|| format!("Unknown prompt: {}", name))?;
+    let mut text = prompt.template.to_string();
+    for arg in prompt.arguments {
+        let value = match arguments.get(arg.name) {
+            Some(Value::String(s)) if !s.is_empty() => s.clone(),
+            Some(Value::Number(n)) => n.to_string(),
+            _ if arg.required => return Err(format!("Missing required argument '{}' for prompt {}", arg.name, name)),
+            _ => "none".to_string(),
+        };
+        text = text.replace(&format!("{{{}}}", arg.name), &value);
+    }
+    Ok(json!({
+        "description": prompt.description,
+        "messages": [
+            { "role": "user", "content": { "type": "text", "text": text } }
+        ]
+    }))
+}
@@ -0,0 +1,224 @@
+//! MCP Resources — read-only views of the building under stable `t3000://` URIs.
+//!
+//! Every resource is served by the tool that already produces that data, so
+//! a resource read and the equivalent `tools/call` return the same JSON.
+
+use chrono::{Duration, Utc};
+use serde_json::{json, Value};
+
+use crate::mcp::dispatch::execute_tool;
+use crate::t3_device::services::T3DeviceService;
+
+/// Resource URI scheme.
+pub const SCHEME: &str = "t3000://";
+
+/// Hours of history served by a trendlog series resource.
+const SERIES_HOURS: i64 = 24;
+
+/// Fixed resources, listed as-is by `resources/list`.
+const STATIC_RESOURCES: &[(&str, &str, &str)] = &[
+    ("t3000://devices", "Devices", "All devices with point counts, location and online status."),
+    ("t3000://alarms", "Active alarms", "Unacknowledged alarms across all devices."),
+    ("t3000://fdd/findings", "Active FDD findings", "Open and ongoing fault detection findings across the building."),
+    ("t3000://docs", "Documentation index", "T3000 documentation topics by section."),
+];
+
+/// Parameterised resources, listed by `resources/templates/list`.
+const TEMPLATES: &[(&str, &str, &str)] = &[
+    ("t3000://device/{serial}", "Device", "Full record for one device."),
+    ("t3000://device/{serial}/points", "Device points", "Inputs, outputs and variables with labels, units, ranges and tags."),
+    ("t3000://device/{serial}/point/{point_type}/{point_index}", "Point",
+        "Current value and metadata of one point (point_type INPUT/OUTPUT/VARIABLE, 1-based index)."),
+    ("t3000://device/{serial}/alarms", "Device alarms", "All alarms for one device."),
+    ("t3000://device/{serial}/trendlogs", "Device trendlogs", "Trendlogs configured on the device."),
+    ("t3000://device/{serial}/trendlog/{point_type}/{point_index}", "Trendlog series",
+        "Last 24 hours of history for one point (point_type INPUT/OUTPUT/VARIABLE, 1-based index)."),
+    ("t3000://device/{serial}/faults", "Device FDD findings", "Fault detection findings for one device."),
+    ("t3000://docs/{path}", "Documentation page", "Markdown for a documentation path from t3000://docs."),
+];
+
+/// A parsed resource URI.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Resource {
+    Devices,
+    Device(i64),
+    Points(i64),
+    Point { serial: i64, point_type: String, point_index: i64 },
+    Alarms(Option<i64>),
+    Trendlogs(i64),
+    Series { serial: i64, point_type: String, point_index: i64 },
+    Faults(Option<i64>),
+    Docs,
+    Doc(String),
+}
+
+impl Resource {
+    pub fn parse(uri: &str) -> Option<Resource> {
+        let rest = uri.strip_prefix(SCHEME)?;
+        let parts: Vec<&str> = rest.split('/').collect();
+        Some(match parts.as_slice() {
+            ["devices"] => Resource::Devices,
+            ["alarms"] => Resource::Alarms(None),
+            ["fdd", "findings"] => Resource::Faults(None),
+            ["docs"] => Resource::Docs,
+            ["docs", ..] if parts.len() > 1 && !rest.contains("..") => Resource::Doc(parts[1..].join("/")),
+            ["device", serial, tail @ ..] => {
+                let serial: i64 = serial.parse().ok()?;
+                match tail {
+                    [] => Resource::Device(serial),
+                    ["points"] => Resource::Points(serial),
+                    ["trendlogs"] => Resource::Trendlogs(serial),
+                    ["faults"] => Resource::Faults(Some(serial)),
+                    ["alarms"] => Resource::Alarms(Some(serial)),
+                    ["point", point_type, index] => {
+                        let (point_type, point_index) = point_ref(point_type, index)?;
+                        Resource::Point { serial, point_type, point_index }
+                    }
+                    ["trendlog", point_type, index] => {
+                        let (point_type, point_index) = point_ref(point_type, index)?;
+                        Resource::Series { serial, point_type, point_index }
+                    }
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        })
+    }
+
+    /// The canonical URI, e.g. point types upper-cased — what notifications
+    /// are published under.
+    pub fn uri(&self) -> String {
+        match self {
+            Resource::Devices => format!("{}devices", SCHEME),
+            Resource::Device(serial) => format!("{}device/{}", SCHEME, serial),
+            Resource::Points(serial) => format!("{}device/{}/points", SCHEME, serial),
+            Resource::Point { serial, point_type, point_index } => {
+                format!("{}device/{}/point/{}/{}", SCHEME, serial, point_type, point_index)
+            }
+            Resource::Alarms(None) => format!("{}alarms", SCHEME),
+            Resource::Alarms(Some(serial)) => format!("{}device/{}/alarms", SCHEME, serial),
+            Resource::Trendlogs(serial) => format!("{}device/{}/trendlogs", SCHEME, serial),
+            Resource::Series { serial, point_type, point_index } => {
+                format!("{}device/{}/trendlog/{}/{}", SCHEME, serial, point_type, point_index)
+            }
+            Resource::Faults(None) => format!("{}fdd/findings", SCHEME),
+            Resource::Faults(Some(serial)) => format!("{}device/{}/faults", SCHEME, serial),
+            Resource::Docs => format!("{}docs", SCHEME),
+            Resource::Doc(path) => format!("{}docs/{}", SCHEME, path),
+        }
+    }
+
+    /// The tool and arguments that produce this resource.
+    pub(crate) fn tool_call(&self) -> (&'static str, Value) {
+        match self {
+            Resource::Devices => ("t3000_device_list", json!({})),
+            Resource::Device(serial) => ("t3000_device_get", json!({ "serial_number": serial })),
+            Resource::Points(serial) => ("t3000_device_get_points", json!({ "serial_number": serial })),
+            Resource::Point { serial, point_type, point_index } => (
+                "t3000_point_read",
+                json!({ "serial_number": serial, "point_type": point_type, "point_index": point_index }),
+            ),
+            Resource::Alarms(None) => ("t3000_alarm_list", json!({ "active_only": true })),
+            Resource::Alarms(Some(serial)) => ("t3000_alarm_list", json!({ "serial_numbers": [serial] })),
+            Resource::Trendlogs(serial) => ("t3000_trendlog_list", json!({ "serial_number": serial })),
+            Resource::Series { serial, point_type, point_index } => (
+                "t3000_trendlog_query",
+                json!({
+                    "serial_number": serial,
+                    "point_type": point_type,
+                    "point_index": point_index,
+                    "start": (Utc::now() - Duration::hours(SERIES_HOURS)).to_rfc3339(),
+                }),
+            ),
+            Resource::Faults(None) => ("t3000_fdd_faults", json!({ "status": "active" })),
+            Resource::Faults(Some(serial)) => ("t3000_fdd_faults", json!({ "serial_number": serial })),
+            Resource::Docs => ("t3000_doc_list", json!({})),
+            Resource::Doc(path) => ("t3000_doc_read", json!({ "path": path })),
+        }
+    }
+
+    /// The device this resource belongs to, if any.
+    pub fn serial(&self) -> Option<i64> {
+        match self {
+            Resource::Device(s)
+            | Resource::Points(s)
+            | Resource::Trendlogs(s)
+            | Resource::Faults(Some(s))
+            | Resource::Alarms(Some(s)) => Some(*s),
+            Resource::Series { serial, .. } | Resource::Point { serial, .. } => Some(*serial),
+            _ => None,
+        }
+    }
+
+    fn mime_type(&self) -> &'static str {
+        match self {
+            Resource::Doc(_) => "text/markdown",
+            _ => "application/json",
+        }
+    }
+}
+
+/// "input", "3" → ("INPUT", 3)
+fn point_ref(point_type: &str, index: &str) -> Option<(String, i64)> {
+    let point_type = point_type.to_uppercase();
+    if !["INPUT", "OUTPUT", "VARIABLE"].contains(&point_type.as_str()) {
+        return None;
+    }
+    Some((point_type, index.parse().ok()?))
+}
+
+fn entry(uri: String, name: String, description: &str) -> Value {
+    json!({ "uri": uri, "name": name, "description": description, "mimeType": "application/json" })
+}
+
+/// `resources/list` — the fixed resources plus per-device points and findings.
+pub async fn list(db: &sea_orm::DatabaseConnection) -> Result<Vec<Value>, String> {
+    let mut resources: Vec<Value> = STATIC_RESOURCES
+        .iter()
+        .map(|(uri, name, description)| entry(uri.to_string(), name.to_string(), description))
+        .collect();
+    let devices = T3DeviceService::get_all_devices_with_stats(db)
+        .await
+        .map_err(|e| format!("Failed to list devices: {}", e))?;
+    for d in devices {
+        let serial = d.device.serial_number;
+        let label = d.device.product_name.clone().filter(|n| !n.is_empty()).unwrap_or_else(|| format!("Device {}", serial));
+        resources.push(entry(format!("t3000://device/{}", serial), label.clone(), "Device record."));
+        resources.push(entry(
+            format!("t3000://device/{}/points", serial),
+            format!("{} points", label),
+            "Inputs, outputs and variables with labels, units, ranges and tags.",
+        ));
+        resources.push(entry(
+            format!("t3000://device/{}/faults", serial),
+            format!("{} FDD findings", label),
+            "Fault detection findings for this device.",
+        ));
+    }
+    Ok(resources)
+}
+
+/// `resources/templates/list`
+pub fn templates() -> Vec<Value> {
+    TEMPLATES
+        .iter()
+        .map(|(uri, name, description)| {
+            let mime = if uri.starts_with("t3000://docs/") { "text/markdown" } else { "application/json" };
+            json!({ "uriTemplate": uri, "name": name, "description": description, "mimeType": mime })
+        })
+        .collect()
+}
+
+/// `resources/read` — one text content block with the resource body.
+pub async fn read(uri: &str, db: &sea_orm::DatabaseConnection) -> Result<Value, String> {
+    let resource = Resource::parse(uri).ok_or_else(|| format!("Unknown resource: {}", uri))?;
+    let (tool, args) = resource.tool_call();
+    let mut text = execute_tool(tool, &args, db).await?;
+    if let Resource::Doc(_) = resource {
+        // doc_read wraps the markdown in {path, title, content}
+        if let Some(content) = serde_json::from_str::<Value>(&text).ok().and_then(|v| v["content"].as_str().map(String::from)) {
+            text = content;
+        }
+    }
+    Ok(json!({ "uri": uri, "mimeType": resource.mime_type(), "text": text }))
+}
@@ -3,10 +3,14 @@
 use axum::{
     extract::State,
     http::{StatusCode, HeaderMap},
-    response::IntoResponse,
+    response::{
+        sse::{Event as SseEvent, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
     routing::{delete, get, post},
-    Json, Router,
+    Extension, Json, Router,
 };
+use futures::StreamExt;
 use serde_json::{json, Value};
 use tracing::{info, error};
 use uuid::Uuid;
@@ -15,6 +19,8 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use crate::app_state::T3AppState;
+use crate::mcp::notifications;
+use crate::mcp::tokens::{self, McpToken};
 use crate::mcp::types::{JsonRpcRequest, McpSession, SessionStore};
 use crate::mcp::storage::{current_device_file, load_json_file, save_json_file};
 
@@ -71,6 +77,36 @@ pub fn create_mcp_routes() -> Router<T3AppState> {
         .route("/api/mcp", delete(mcp_delete_handler))
         .route("/api/mcp/current-device", post(set_current_device_handler))
         .route("/api/mcp/current-device", get(get_current_device_handler))
+        .merge(crate::mcp::token_routes::create_token_routes())
+}
+
+/// The MCP API token presented as `Authorization: Bearer t3mcp_…`, if any.
+/// Other bearer tokens (user sessions) are left to `auth::enforce_roles`.
+pub(crate) async fn token_from_headers(
+    state: &T3AppState,
+    headers: &HeaderMap,
+) -> Result<Option<McpToken>, (StatusCode, Json<Value>)> {
+    let secret = headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|t| t.starts_with(tokens::TOKEN_PREFIX));
+    let Some(secret) = secret else { return Ok(None) };
+
+    let unauthorized = |message: String| {
+        (StatusCode::UNAUTHORIZED, Json(json!({
+            "jsonrpc": "2.0",
+            "id": null,
+            "error": { "code": -32001, "message": message }
+        })))
+    };
+    let db = crate::auth::auth_db(state).await;
+    match tokens::authenticate(&db, secret).await {
+        Ok(Some(token)) => Ok(Some(token)),
+        Ok(None) => Err(unauthorized("Invalid or disabled MCP token".into())),
+        Err(e) => Err(unauthorized(format!("MCP token check failed: {}", e))),
+    }
 }
 
 // ═══ POST /api/mcp/current-device ═══
@@ -132,9 +168,12 @@ async fn get_current_device_handler() -> impl IntoResponse {
 
 pub async fn mcp_post_handler(
     State(state): State<T3AppState>,
+    token: Option<Extension<McpToken>>,
     headers: HeaderMap,
     Json(body): Json<Value>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<Value>)> {
+    // Authenticated by `auth::enforce_roles`
+    let token = token.map(|Extension(token)| token);
     let db = get_db(&state).await?;
     let sessions = get_session_store(&state);
 
@@ -156,6 +195,7 @@ pub async fn mcp_post_handler(
                     id: new_id.clone(),
                     created_at: Utc::now().to_rfc3339(),
                     initialized: false,
+                    subscriptions: Default::default(),
                 });
                 new_id
             }
@@ -167,6 +207,7 @@ pub async fn mcp_post_handler(
                 id: new_id.clone(),
                 created_at: Utc::now().to_rfc3339(),
                 initialized: false,
+                subscriptions: Default::default(),
             });
             new_id
         }
@@ -204,7 +245,30 @@ pub async fn mcp_post_handler(
         }
     }
 
-    let resp = crate::mcp::dispatch::handle_request(&req, &db).await;
+    // Tool calls are audited as this MCP session, on behalf of the signed-in user
+    // or, for token clients, of the token
+    let mut audit_ctx = crate::audit::current().via(crate::audit::Origin::Mcp, session_id.clone());
+    if let Some(token) = &token {
+        audit_ctx.actor = Some(format!("token:{}", token.name));
+    }
+    let resp = crate::audit::scope(audit_ctx, crate::mcp::dispatch::handle_request(&req, &db, token.as_ref())).await;
+
+    // Subscriptions live on the session; dispatch has already validated the URI
+    if resp.error.is_none() && matches!(req.method.as_str(), "resources/subscribe" | "resources/unsubscribe") {
+        if let Some(uri) = req.params.as_ref().and_then(|p| p.get("uri")).and_then(|v| v.as_str()) {
+            // Keyed by the canonical URI, so `point/input/3` matches the
+            // `point/INPUT/3` events are published under
+            let canonical = crate::mcp::resources::Resource::parse(uri).map(|r| r.uri()).unwrap_or_else(|| uri.to_string());
+            let mut map = sessions.lock().await;
+            if let Some(session) = map.get_mut(&session_id) {
+                if req.method == "resources/subscribe" {
+                    session.subscriptions.insert(canonical, uri.to_string());
+                } else {
+                    session.subscriptions.remove(&canonical);
+                }
+            }
+        }
+    }
 
     let mut response_headers = HeaderMap::new();
     response_headers.insert(
@@ -225,10 +289,13 @@ pub async fn mcp_post_handler(
 
 // ═══ GET /api/mcp — SSE ═══
 
+/// Opens the server→client stream. With an `Mcp-Session-Id` the stream stays
+/// open and carries notifications for the session's resource subscriptions
+/// (see `notifications.rs`); without one it only announces the endpoint.
 pub async fn mcp_sse_handler(
     State(state): State<T3AppState>,
     headers: HeaderMap,
-) -> Result<impl IntoResponse, (StatusCode, Json<Value>)> {
+) -> Result<Response, (StatusCode, Json<Value>)> {
     let sessions = get_session_store(&state);
 
     let session_id = headers
@@ -247,6 +314,13 @@ pub async fn mcp_sse_handler(
     }
 
     let endpoint = format!("/api/mcp?session={}", session_id.unwrap_or(""));
+
+    if let Some(id) = session_id {
+        let first = futures::stream::once(async move { Ok(SseEvent::default().event("endpoint").data(endpoint)) });
+        let stream = first.chain(notifications::session_stream(id.to_string(), sessions));
+        return Ok(Sse::new(stream).keep_alive(KeepAlive::default()).into_response());
+    }
+
     let body = format!(
         "event: endpoint\ndata: {}\n\n",
         endpoint
@@ -256,7 +330,7 @@ pub async fn mcp_sse_handler(
     response_headers.insert("Content-Type", "text/event-stream".parse().unwrap());
     response_headers.insert("Cache-Control", "no-cache".parse().unwrap());
 
-    Ok((response_headers, body))
+    Ok((response_headers, body).into_response())
 }
 
 // ═══ DELETE /api/mcp — Session termination ═══
@@ -0,0 +1,59 @@
+//! `/api/mcp/tokens` — manage MCP API tokens (admin only, see `auth::policy`).
+
+use axum::{
+    extract::{Path, State},
+    response::Json,
+    routing::{get, put},
+    Extension, Router,
+};
+use serde_json::{json, Value};
+
+use crate::app_state::T3AppState;
+use crate::auth::{auth_db, CurrentUser};
+use crate::error::{Error, Result};
+use crate::mcp::tokens::{self, TokenSpec};
+
+async fn db(state: &T3AppState) -> Result<sea_orm::DatabaseConnection> {
+    let db = auth_db(state).await;
+    tokens::ensure_schema(&db).await?;
+    Ok(db)
+}
+
+/// GET /api/mcp/tokens
+async fn list_tokens(State(state): State<T3AppState>) -> Result<Json<Value>> {
+    let tokens = tokens::list(&db(&state).await?).await?;
+    Ok(Json(json!({ "tokens": tokens })))
+}
+
+/// POST /api/mcp/tokens — the response carries the only copy of the secret.
+async fn create_token(
+    State(state): State<T3AppState>,
+    user: Option<Extension<CurrentUser>>,
+    Json(spec): Json<TokenSpec>,
+) -> Result<Json<Value>> {
+    let created_by = user.map(|Extension(CurrentUser(u))| u.username);
+    let (token, secret) = tokens::create(&db(&state).await?, &spec, created_by.as_deref()).await?;
+    Ok(Json(json!({ "token": token, "secret": secret })))
+}
+
+/// PUT /api/mcp/tokens/:id — replaces the token's scope.
+async fn update_token(
+    State(state): State<T3AppState>,
+    Path(id): Path<i64>,
+    Json(spec): Json<TokenSpec>,
+) -> Result<Json<Value>> {
+    let token = tokens::update(&db(&state).await?, id, &spec).await?.ok_or(Error::NotFound)?;
+    Ok(Json(json!(token)))
+}
+
+/// DELETE /api/mcp/tokens/:id
+async fn delete_token(State(state): State<T3AppState>, Path(id): Path<i64>) -> Result<Json<Value>> {
+    let token = tokens::delete(&db(&state).await?, id).await?.ok_or(Error::NotFound)?;
+    Ok(Json(json!({ "deleted": token })))
+}
+
+pub fn create_token_routes() -> Router<T3AppState> {
+    Router::new()
+        .route("/api/mcp/tokens", get(list_tokens).post(create_token))
+        .route("/api/mcp/tokens/:id", put(update_token).delete(delete_token))
+}
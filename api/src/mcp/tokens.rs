@@ -0,0 +1,339 @@
+//! MCP API tokens — scoped credentials for external MCP clients.
+//!
+//! A token is sent as `Authorization: Bearer t3mcp_…` on `POST /api/mcp` and
+//! limits what that client can do: which tools it may call, which devices it
+//! may touch, whether it may write at all, and how many calls per minute it
+//! gets. Only a SHA-256 of the token is stored; the plaintext is returned once,
+//! at creation.
+
+use std::collections::HashMap;
+
+use once_cell::sync::Lazy;
+use sea_orm::{ConnectionTrait, DatabaseBackend, DatabaseConnection, QueryResult, Statement};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::Mutex;
+
+use crate::auth::store::{hash_token, new_token};
+use crate::error::{Error, Result};
+
+/// Prefix that marks a bearer token as an MCP token rather than a user session.
+pub const TOKEN_PREFIX: &str = "t3mcp_";
+
+const DDL: &str = "
+CREATE TABLE IF NOT EXISTS MCP_API_TOKENS (
+    id                    INTEGER PRIMARY KEY AUTOINCREMENT,
+    name                  TEXT NOT NULL,
+    token_hash            TEXT NOT NULL UNIQUE,
+    token_hint            TEXT NOT NULL,
+    allowed_tools         TEXT,
+    allowed_serials       TEXT,
+    read_only             INTEGER NOT NULL DEFAULT 1,
+    rate_limit_per_minute INTEGER,
+    enabled               INTEGER NOT NULL DEFAULT 1,
+    created_by            TEXT,
+    created_at            TEXT DEFAULT (datetime('now')),
+    last_used_at          TEXT
+);
+";
+
+/// Tools a read-only token can't call: everything the audit trail records as
+/// a change, plus the shared task and memory stores.
+const LOCAL_WRITE_TOOLS: &[&str] = &[
+    "t3000_task_create",
+    "t3000_task_update",
+    "t3000_task_delete",
+    "t3000_memory_save",
+    "t3000_memory_delete",
+];
+
+/// Tools that report on every device at once and have no serial filter, so a
+/// token limited to some devices can't use them.
+const SITE_WIDE_TOOLS: &[&str] = &["t3000_device_list", "t3000_building_summary", "t3000_scan_network"];
+
+/// Device tools that pick their device themselves instead of taking a serial
+/// number, so there is nothing to check against a device allow-list.
+const SERIAL_LESS_DEVICE_TOOLS: &[&str] = &["t3000_device_current"];
+
+pub fn is_write_tool(name: &str) -> bool {
+    crate::audit::tools::is_audited(name) || LOCAL_WRITE_TOOLS.contains(&name)
+}
+
+/// A token's scope, as stored (the secret itself is never kept).
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpToken {
+    pub id: i64,
+    pub name: String,
+    /// First characters of the token, to tell tokens apart in a list.
+    pub token_hint: String,
+    /// `None` = every tool.
+    pub allowed_tools: Option<Vec<String>>,
+    /// `None` = every device.
+    pub allowed_serials: Option<Vec<i64>>,
+    pub read_only: bool,
+    /// `None` = unlimited.
+    pub rate_limit_per_minute: Option<u32>,
+    pub enabled: bool,
+    pub created_by: Option<String>,
+    pub created_at: Option<String>,
+    pub last_used_at: Option<String>,
+}
+
+/// Scope for a new token, or the full replacement scope on update.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenSpec {
+    pub name: String,
+    #[serde(default)]
+    pub allowed_tools: Option<Vec<String>>,
+    #[serde(default)]
+    pub allowed_serials: Option<Vec<i64>>,
+    #[serde(default = "default_true")]
+    pub read_only: bool,
+    #[serde(default)]
+    pub rate_limit_per_minute: Option<u32>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn stmt(sql: &str, values: Vec<sea_orm::Value>) -> Statement {
+    Statement::from_sql_and_values(DatabaseBackend::Sqlite, sql, values)
+}
+
+fn db_err(e: sea_orm::DbErr) -> Error {
+    Error::DbError(e.to_string())
+}
+
+pub async fn ensure_schema(db: &DatabaseConnection) -> Result<()> {
+    db.execute_unprepared(DDL).await.map_err(db_err)?;
+    Ok(())
+}
+
+fn json_list<T: serde::de::DeserializeOwned>(row: &QueryResult, col: &str) -> Option<Vec<T>> {
+    row.try_get::<Option<String>>("", col)
+        .ok()
+        .flatten()
+        .and_then(|s| serde_json::from_str(&s).ok())
+}
+
+fn token_from_row(row: &QueryResult) -> McpToken {
+    McpToken {
+        id: row.try_get("", "id").unwrap_or(0),
+        name: row.try_get("", "name").unwrap_or_default(),
+        token_hint: row.try_get("", "token_hint").unwrap_or_default(),
+        allowed_tools: json_list(row, "allowed_tools"),
+        allowed_serials: json_list(row, "allowed_serials"),
+        // An unreadable flag never grants writes.
+        read_only: row.try_get::<i64>("", "read_only").unwrap_or(1) != 0,
+        rate_limit_per_minute: row
+            .try_get::<Option<i64>>("", "rate_limit_per_minute")
+            .ok()
+            .flatten()
+            .map(|n| n.max(0) as u32),
+        enabled: row.try_get::<i64>("", "enabled").unwrap_or(0) != 0,
+        created_by: row.try_get("", "created_by").ok().flatten(),
+        created_at: row.try_get("", "created_at").ok().flatten(),
+        last_used_at: row.try_get("", "last_used_at").ok().flatten(),
+    }
+}
+
+const COLUMNS: &str = "id, name, token_hint, allowed_tools, allowed_serials, read_only, \
+    rate_limit_per_minute, enabled, created_by, created_at, last_used_at";
+
+fn validate(spec: &TokenSpec) -> Result<()> {
+    if spec.name.trim().is_empty() {
+        return Err(Error::ValidationError("Token name must not be empty".to_string()));
+    }
+    if let Some(tools) = &spec.allowed_tools {
+        if let Some(unknown) = tools.iter().find(|t| !crate::mcp::TOOLS.iter().any(|d| d.name == t.as_str())) {
+            return Err(Error::ValidationError(format!("Unknown tool '{}'", unknown)));
+        }
+    }
+    Ok(())
+}
+
+fn spec_values(spec: &TokenSpec) -> Vec<sea_orm::Value> {
+    let list = |v: Option<String>| -> sea_orm::Value { v.into() };
+    vec![
+        spec.name.trim().into(),
+        list(spec.allowed_tools.as_ref().map(|t| serde_json::to_string(t).unwrap_or_default())),
+        list(spec.allowed_serials.as_ref().map(|s| serde_json::to_string(s).unwrap_or_default())),
+        (spec.read_only as i32).into(),
+        spec.rate_limit_per_minute.map(|n| n as i64).into(),
+        (spec.enabled as i32).into(),
+    ]
+}
+
+pub async fn list(db: &DatabaseConnection) -> Result<Vec<McpToken>> {
+    let rows = db
+        .query_all(stmt(&format!("SELECT {} FROM MCP_API_TOKENS ORDER BY name", COLUMNS), vec![]))
+        .await
+        .map_err(db_err)?;
+    Ok(rows.iter().map(token_from_row).collect())
+}
+
+pub async fn get(db: &DatabaseConnection, id: i64) -> Result<Option<McpToken>> {
+    let row = db
+        .query_one(stmt(&format!("SELECT {} FROM MCP_API_TOKENS WHERE id = ?", COLUMNS), vec![id.into()]))
+        .await
+        .map_err(db_err)?;
+    Ok(row.as_ref().map(token_from_row))
+}
+
+/// Issue a token. The plaintext is only ever returned here.
+pub async fn create(db: &DatabaseConnection, spec: &TokenSpec, created_by: Option<&str>) -> Result<(McpToken, String)> {
+    validate(spec)?;
+    let secret = format!("{}{}", TOKEN_PREFIX, new_token());
+    let mut values = vec![hash_token(&secret).into(), secret[..TOKEN_PREFIX.len() + 6].to_string().into()];
+    values.extend(spec_values(spec));
+    values.push(created_by.map(String::from).into());
+    let result = db
+        .execute(stmt(
+            "INSERT INTO MCP_API_TOKENS (token_hash, token_hint, name, allowed_tools, allowed_serials, \
+             read_only, rate_limit_per_minute, enabled, created_by) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            values,
+        ))
+        .await
+        .map_err(db_err)?;
+    let token = get(db, result.last_insert_id() as i64)
+        .await?
+        .ok_or_else(|| Error::ServerError("Created token not found".to_string()))?;
+    Ok((token, secret))
+}
+
+/// Replace a token's scope. The secret stays the same.
+pub async fn update(db: &DatabaseConnection, id: i64, spec: &TokenSpec) -> Result<Option<McpToken>> {
+    validate(spec)?;
+    let mut values = spec_values(spec);
+    values.push(id.into());
+    db.execute(stmt(
+        "UPDATE MCP_API_TOKENS SET name = ?, allowed_tools = ?, allowed_serials = ?, read_only = ?, \
+         rate_limit_per_minute = ?, enabled = ? WHERE id = ?",
+        values,
+    ))
+    .await
+    .map_err(db_err)?;
+    get(db, id).await
+}
+
+pub async fn delete(db: &DatabaseConnection, id: i64) -> Result<Option<McpToken>> {
+    let Some(token) = get(db, id).await? else {
+        return Ok(None);
+    };
+    db.execute(stmt("DELETE FROM MCP_API_TOKENS WHERE id = ?", vec![id.into()]))
+        .await
+        .map_err(db_err)?;
+    Ok(Some(token))
+}
+
+/// The enabled token matching `secret`, if any.
+pub async fn authenticate(db: &DatabaseConnection, secret: &str) -> Result<Option<McpToken>> {
+    ensure_schema(db).await?;
+    let hash = hash_token(secret);
+    let row = db
+        .query_one(stmt(
+            &format!("SELECT {} FROM MCP_API_TOKENS WHERE token_hash = ? AND enabled = 1", COLUMNS),
+            vec![hash.clone().into()],
+        ))
+        .await
+        .map_err(db_err)?;
+    let Some(token) = row.as_ref().map(token_from_row) else {
+        return Ok(None);
+    };
+    db.execute(stmt(
+        "UPDATE MCP_API_TOKENS SET last_used_at = datetime('now') WHERE token_hash = ?",
+        vec![hash.into()],
+    ))
+    .await
+    .map_err(db_err)?;
+    Ok(Some(token))
+}
+
+/// Calls counted per token in the current one-minute window. Tokens whose
+/// window has passed are dropped on the next call.
+static WINDOWS: Lazy<Mutex<HashMap<i64, (i64, u32)>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Every serial number a tool call refers to.
+fn serials_in(args: &Value) -> Vec<i64> {
+    let mut serials: Vec<i64> = Vec::new();
+    serials.extend(args.get("serial_number").and_then(|v| v.as_i64()));
+    for key in ["serial_numbers", "points"] {
+        if let Some(items) = args.get(key).and_then(|v| v.as_array()) {
+            serials.extend(items.iter().filter_map(|v| v.as_i64().or_else(|| v.get("serial_number")?.as_i64())));
+        }
+    }
+    serials
+}
+
+/// Whether the tool's schema takes a device filter it would otherwise run without.
+fn has_device_filter(name: &str) -> bool {
+    crate::mcp::TOOLS.iter().find(|t| t.name == name).is_some_and(|t| {
+        let props = &t.input_schema["properties"];
+        ["serial_number", "serial_numbers", "points"].iter().any(|k| props.get(k).is_some())
+    })
+}
+
+impl McpToken {
+    /// Whether the tool is visible to this token at all (allow-list + read-only).
+    pub fn allows_tool(&self, name: &str) -> bool {
+        let listed = self.allowed_tools.as_ref().is_none_or(|tools| tools.iter().any(|t| t == name));
+        listed && !(self.read_only && is_write_tool(name))
+    }
+
+    pub fn allows_serial(&self, serial: i64) -> bool {
+        self.allowed_serials.as_ref().is_none_or(|s| s.contains(&serial))
+    }
+
+    /// Check one tool call against this token's scope. The message is shown to the caller.
+    pub fn authorize(&self, name: &str, args: &Value) -> std::result::Result<(), String> {
+        if !self.allows_tool(name) {
+            return Err(if self.read_only && is_write_tool(name) {
+                format!("{} is a write tool and this MCP token is read-only", name)
+            } else {
+                format!("{} is not in this MCP token's tool allow-list", name)
+            });
+        }
+        if self.allowed_serials.is_some() {
+            let serials = serials_in(args);
+            if let Some(denied) = serials.iter().find(|s| !self.allows_serial(**s)) {
+                return Err(format!("Device {} is not in this MCP token's device allow-list", denied));
+            }
+            if SERIAL_LESS_DEVICE_TOOLS.contains(&name) {
+                return Err(format!(
+                    "{} doesn't take a serial_number; this MCP token is limited to devices {:?}",
+                    name,
+                    self.allowed_serials.as_deref().unwrap_or_default()
+                ));
+            }
+            if serials.is_empty() && (SITE_WIDE_TOOLS.contains(&name) || has_device_filter(name)) {
+                return Err(format!(
+                    "{} would cover every device; this MCP token is limited to devices {:?} — pass serial_number(s)",
+                    name,
+                    self.allowed_serials.as_deref().unwrap_or_default()
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Count one call against the per-minute limit.
+    pub async fn take_rate(&self) -> std::result::Result<(), String> {
+        let Some(limit) = self.rate_limit_per_minute else {
+            return Ok(());
+        };
+        let minute = chrono::Utc::now().timestamp() / 60;
+        let mut windows = WINDOWS.lock().await;
+        windows.retain(|_, (m, _)| *m == minute);
+        let window = windows.entry(self.id).or_insert((minute, 0));
+        if window.1 >= limit {
+            return Err(format!("Rate limit of {} calls per minute reached for this MCP token", limit));
+        }
+        window.1 += 1;
+        Ok(())
+    }
+}
@@ -1075,18 +1075,20 @@ lazy_static::lazy_static! {
     ToolDef {
         name: "t3000_fdd_analyze",
         title: "Run Fault Detection",
-        description: "Run FDD rules against a device's trendlog history and Haystack-tagged points over a time range. Returns fault findings with severity, fault hours, and evidence. Optionally restrict to specific rule IDs. Use to proactively detect equipment faults (economizer, sensor, fan, chilled water).",
+        description: "Run FDD rules against a device's trendlog history and Haystack-tagged points over a time range. Returns fault findings with severity, fault hours, and evidence. Devices serving several AHUs/VAVs (points tagged equipRef or Brick isPointOf) are analyzed per equipment and each finding names its equipment. Optionally restrict to specific rule IDs. Use to proactively detect equipment faults (economizer, sensor, fan, chilled water).",
         input_schema: json!({
             "type": "object",
             "properties": {
                 "serial_number": { "type": "integer", "description": "Device serial number" },
-                "equipment": { "type": "string", "description": "Optional: equipment name (e.g. AHU-1) for context" },
+                "equipment": { "type": "string", "description": "Optional: analyze only this equipment instance (equipRef, e.g. AHU-1); empty = every equipment on the device" },
                 "range_hours": { "type": "integer", "description": "Optional: hours of history to analyze (default 24)" },
                 "rules": {
                     "type": "array",
                     "items": { "type": "string" },
                     "description": "Optional: restrict to these rule IDs (e.g. [\"ECON-4\",\"SAT-HIGH\"]); empty = all enabled rules"
-                }
+                },
+                "bucket_seconds": { "type": "integer", "description": "Optional: resampling bucket in seconds (default = each rule's own poll_seconds; 0 = exact timestamps only)" },
+                "max_staleness_seconds": { "type": "integer", "description": "Optional: how long a point's last value is carried forward into later buckets (default 3 of its trendlog's logging intervals, at least 3 buckets)" }
             },
             "required": ["serial_number"]
         }),
@@ -1094,16 +1096,16 @@ lazy_static::lazy_static! {
     ToolDef {
         name: "t3000_fdd_rule_create",
         title: "Create FDD Rule",
-        description: "Create a new fault detection rule in the FDD_RULES table. Requires confirm:true. Fields: rule_id (unique), rule_name, category, rule_kind (ThresholdAbove|ThresholdBelow|RangeBand|StuckValue|FanMismatch|EconomizerOaFraction|EconomizerStuckClosed|SupplyTempDeviation|ChwLowDeltaT), required_roles, params, severity, enabled.",
+        description: "Create a new fault detection rule in the FDD_RULES table. Requires confirm:true. Fields: rule_id (unique), rule_name, category, rule_kind (ThresholdAbove|ThresholdBelow|RangeBand|StuckValue|FanMismatch|EconomizerOaFraction|EconomizerStuckClosed|SupplyTempDeviation|ChwLowDeltaT|Expression), required_roles, params, severity, enabled. Expression rules take params.expression, a condition over role names such as 'fan_cmd > 0.05 && abs(sat - sat_sp) > 5' (operators + - * / % < <= > >= == != && || !, functions abs/min/max/clamp/frac); every role it uses must be in required_roles. Any rule may add params.impact {kw, price_per_kwh?} so its findings carry estimated kWh and cost.",
         input_schema: json!({
             "type": "object",
             "properties": {
                 "rule_id": { "type": "string", "description": "Unique rule ID, e.g. 'MY-RULE-1'" },
                 "rule_name": { "type": "string", "description": "Human-readable rule name" },
                 "category": { "type": "string", "description": "Category (economizer, sensor, fan, zone, chw, control, custom)" },
-                "rule_kind": { "type": "string", "description": "Evaluator kind: ThresholdAbove, ThresholdBelow, RangeBand, StuckValue, FanMismatch, EconomizerOaFraction, EconomizerStuckClosed, SupplyTempDeviation, ChwLowDeltaT" },
+                "rule_kind": { "type": "string", "description": "Evaluator kind: ThresholdAbove, ThresholdBelow, RangeBand, StuckValue, FanMismatch, EconomizerOaFraction, EconomizerStuckClosed, SupplyTempDeviation, ChwLowDeltaT, Expression" },
                 "required_roles": { "type": "array", "items": { "type": "string" }, "description": "Semantic roles needed, e.g. [\"mat\",\"rat\",\"oa_t\",\"fan_cmd\"]" },
-                "params": { "type": "object", "description": "Tuning parameters, e.g. {\"field\":\"mat\",\"limit\":2,\"confirm_rows\":4,\"poll_seconds\":300}" },
+                "params": { "type": "object", "description": "Tuning parameters, e.g. {\"field\":\"mat\",\"limit\":2,\"confirm_rows\":4,\"poll_seconds\":300} or {\"expression\":\"fan_cmd > 0.05 && abs(sat - sat_sp) > 5\",\"confirm_rows\":4}" },
                 "severity": { "type": "string", "description": "info, warning, or critical" },
                 "enabled": { "type": "boolean", "description": "Enabled by default" },
                 "confirm": { "type": "boolean", "description": "Must be true" }
@@ -1177,16 +1179,31 @@ lazy_static::lazy_static! {
     ToolDef {
         name: "t3000_fdd_faults",
         title: "List FDD Findings",
-        description: "List persisted fault detection findings (faults found by t3000_fdd_analyze), optionally filtered by device serial and rule ID. Returns the most recent findings first.",
+        description: "List persisted fault detection findings (faults found by t3000_fdd_analyze or the background FDD scheduler), optionally filtered by device serial, rule ID and lifecycle status. Each fault is one lifecycle record with first_seen, last_seen, cleared_at and occurrences (how many times the fault has opened, counting earlier cleared episodes). Returns the most recently seen faults first.",
         input_schema: json!({
             "type": "object",
             "properties": {
                 "serial_number": { "type": "integer", "description": "Optional: only findings for this device" },
                 "rule_id": { "type": "string", "description": "Optional: only findings for this rule" },
+                "status": { "type": "string", "description": "Optional: lifecycle filter — active (open + ongoing), open, ongoing, cleared" },
                 "limit": { "type": "integer", "description": "Optional: max findings (default 50)" }
             }
         }),
     },
+    ToolDef {
+        name: "t3000_fdd_top_faults",
+        title: "Top Faults by Cost",
+        description: "Rank persisted FDD findings by estimated energy cost (then kWh), most expensive first. Estimates come from rules with an impact model in params (e.g. {\"impact\":{\"kw\":3.5}} = kW wasted while the fault is active) times fault hours times the site tariff, accumulated over the lifetime of each finding. Use to answer 'which faults are costing us the most?'. Findings from rules without an impact model are not ranked.",
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "serial_number": { "type": "integer", "description": "Optional: only findings for this device" },
+                "equipment": { "type": "string", "description": "Optional: only findings for this equipment (e.g. AHU-1)" },
+                "status": { "type": "string", "description": "Optional: lifecycle filter — active (open + ongoing), open, ongoing, cleared" },
+                "limit": { "type": "integer", "description": "Optional: max findings (default 10, max 500)" }
+            }
+        }),
+    },
     // ═══ v5: Navigation ═══ 
     ToolDef {
         name: "t3000_nav_list",
@@ -48,6 +48,9 @@ pub(crate) struct McpSession {
     #[allow(dead_code)]
     pub(crate) created_at: String,
     pub(crate) initialized: bool,
+    /// Resource URIs this client subscribed to (`resources/subscribe`), by
+    /// canonical URI (`Resource::uri`) → the URI as the client wrote it
+    pub(crate) subscriptions: HashMap<String, String>,
 }
 
 /// Shared session store (in-memory, lost on restart)
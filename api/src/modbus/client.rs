@@ -0,0 +1,234 @@
+//! Modbus TCP master.
+//!
+//! One TCP connection per client; requests are serialized through a mutex so
+//! the transaction id in each MBAP header always matches the reply being read.
+//! A connection that fails mid-request is dropped and re-opened on the next call.
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+
+/// Standard Modbus TCP port.
+pub const DEFAULT_PORT: u16 = 502;
+/// Largest register count a single read (fn 3/4) may ask for.
+pub const MAX_READ_REGISTERS: u16 = 125;
+/// Largest coil count a single read (fn 1/2) may ask for.
+pub const MAX_READ_COILS: u16 = 2000;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ModbusError {
+    #[error("Modbus I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Modbus request to {0} timed out")]
+    Timeout(SocketAddr),
+    #[error("Modbus exception {code:#04x} for function {function}")]
+    Exception { function: u8, code: u8 },
+    #[error("Modbus protocol error: {0}")]
+    Protocol(String),
+}
+
+impl From<ModbusError> for String {
+    fn from(e: ModbusError) -> Self {
+        e.to_string()
+    }
+}
+
+struct Connection {
+    stream: Option<TcpStream>,
+    transaction_id: u16,
+}
+
+pub struct ModbusTcpClient {
+    addr: SocketAddr,
+    unit_id: u8,
+    timeout: Duration,
+    conn: Mutex<Connection>,
+}
+
+impl ModbusTcpClient {
+    pub fn new(addr: SocketAddr, unit_id: u8) -> Self {
+        Self {
+            addr,
+            unit_id,
+            timeout: Duration::from_secs(3),
+            conn: Mutex::new(Connection { stream: None, transaction_id: 0 }),
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Send one PDU and return the reply PDU (function code first).
+    async fn request(&self, pdu: &[u8]) -> Result<Vec<u8>, ModbusError> {
+        let mut conn = self.conn.lock().await;
+        conn.transaction_id = conn.transaction_id.wrapping_add(1);
+        let tid = conn.transaction_id;
+
+        let result = tokio::time::timeout(self.timeout, async {
+            if conn.stream.is_none() {
+                conn.stream = Some(TcpStream::connect(self.addr).await?);
+            }
+            let stream = conn.stream.as_mut().expect("connected above");
+
+            let mut frame = Vec::with_capacity(7 + pdu.len());
+            frame.extend_from_slice(&tid.to_be_bytes());
+            frame.extend_from_slice(&0u16.to_be_bytes()); // protocol id
+            frame.extend_from_slice(&(pdu.len() as u16 + 1).to_be_bytes());
+            frame.push(self.unit_id);
+            frame.extend_from_slice(pdu);
+            stream.write_all(&frame).await?;
+
+            loop {
+                let mut header = [0u8; 7];
+                stream.read_exact(&mut header).await?;
+                let len = u16::from_be_bytes([header[4], header[5]]) as usize;
+                if len < 2 {
+                    return Err(ModbusError::Protocol(format!("MBAP length {} too short", len)));
+                }
+                let mut body = vec![0u8; len - 1];
+                stream.read_exact(&mut body).await?;
+                // Skip stale replies from a request that previously timed out.
+                if u16::from_be_bytes([header[0], header[1]]) == tid {
+                    return Ok(body);
+                }
+            }
+        })
+        .await;
+
+        let reply = match result {
+            Ok(Ok(reply)) => reply,
+            Ok(Err(e)) => {
+                conn.stream = None;
+                return Err(e);
+            }
+            Err(_) => {
+                conn.stream = None;
+                return Err(ModbusError::Timeout(self.addr));
+            }
+        };
+
+        let function = pdu[0];
+        match reply.first() {
+            Some(&f) if f == function => Ok(reply),
+            Some(&f) if f == function | 0x80 => Err(ModbusError::Exception {
+                function,
+                code: reply.get(1).copied().unwrap_or(0),
+            }),
+            _ => Err(ModbusError::Protocol(format!(
+                "unexpected reply to function {}: {:02x?}",
+                function, reply
+            ))),
+        }
+    }
+
+    async fn read_bits(&self, function: u8, address: u16, count: u16) -> Result<Vec<bool>, ModbusError> {
+        if count == 0 || count > MAX_READ_COILS {
+            return Err(ModbusError::Protocol(format!("invalid coil count {}", count)));
+        }
+        let mut pdu = vec![function];
+        pdu.extend_from_slice(&address.to_be_bytes());
+        pdu.extend_from_slice(&count.to_be_bytes());
+        let reply = self.request(&pdu).await?;
+        let bytes = reply.get(2..).unwrap_or_default();
+        if bytes.len() * 8 < count as usize {
+            return Err(ModbusError::Protocol("short coil reply".into()));
+        }
+        Ok((0..count as usize).map(|i| bytes[i / 8] & (1 << (i % 8)) != 0).collect())
+    }
+
+    async fn read_words(&self, function: u8, address: u16, count: u16) -> Result<Vec<u16>, ModbusError> {
+        if count == 0 || count > MAX_READ_REGISTERS {
+            return Err(ModbusError::Protocol(format!("invalid register count {}", count)));
+        }
+        let mut pdu = vec![function];
+        pdu.extend_from_slice(&address.to_be_bytes());
+        pdu.extend_from_slice(&count.to_be_bytes());
+        let reply = self.request(&pdu).await?;
+        let bytes = reply.get(2..).unwrap_or_default();
+        if bytes.len() != count as usize * 2 {
+            return Err(ModbusError::Protocol(format!(
+                "expected {} register bytes, got {}",
+                count as usize * 2,
+                bytes.len()
+            )));
+        }
+        Ok(bytes.chunks(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect())
+    }
+
+    /// Function 1.
+    pub async fn read_coils(&self, address: u16, count: u16) -> Result<Vec<bool>, ModbusError> {
+        self.read_bits(1, address, count).await
+    }
+
+    /// Function 2.
+    pub async fn read_discrete_inputs(&self, address: u16, count: u16) -> Result<Vec<bool>, ModbusError> {
+        self.read_bits(2, address, count).await
+    }
+
+    /// Function 3.
+    pub async fn read_holding_registers(&self, address: u16, count: u16) -> Result<Vec<u16>, ModbusError> {
+        self.read_words(3, address, count).await
+    }
+
+    /// Function 4.
+    pub async fn read_input_registers(&self, address: u16, count: u16) -> Result<Vec<u16>, ModbusError> {
+        self.read_words(4, address, count).await
+    }
+
+    /// Function 5.
+    pub async fn write_single_coil(&self, address: u16, value: bool) -> Result<(), ModbusError> {
+        let mut pdu = vec![5];
+        pdu.extend_from_slice(&address.to_be_bytes());
+        pdu.extend_from_slice(&(if value { 0xFF00u16 } else { 0 }).to_be_bytes());
+        self.request(&pdu).await.map(|_| ())
+    }
+
+    /// Function 6.
+    pub async fn write_single_register(&self, address: u16, value: u16) -> Result<(), ModbusError> {
+        let mut pdu = vec![6];
+        pdu.extend_from_slice(&address.to_be_bytes());
+        pdu.extend_from_slice(&value.to_be_bytes());
+        self.request(&pdu).await.map(|_| ())
+    }
+
+    /// Function 15.
+    pub async fn write_multiple_coils(&self, address: u16, values: &[bool]) -> Result<(), ModbusError> {
+        if values.is_empty() || values.len() > 0x7B0 {
+            return Err(ModbusError::Protocol(format!("invalid coil count {}", values.len())));
+        }
+        let mut packed = vec![0u8; values.len().div_ceil(8)];
+        for (i, _) in values.iter().enumerate().filter(|(_, v)| **v) {
+            packed[i / 8] |= 1 << (i % 8);
+        }
+        let mut pdu = vec![15];
+        pdu.extend_from_slice(&address.to_be_bytes());
+        pdu.extend_from_slice(&(values.len() as u16).to_be_bytes());
+        pdu.push(packed.len() as u8);
+        pdu.extend_from_slice(&packed);
+        self.request(&pdu).await.map(|_| ())
+    }
+
+    /// Function 16.
+    pub async fn write_multiple_registers(&self, address: u16, values: &[u16]) -> Result<(), ModbusError> {
+        if values.is_empty() || values.len() > 123 {
+            return Err(ModbusError::Protocol(format!("invalid register count {}", values.len())));
+        }
+        let mut pdu = vec![16];
+        pdu.extend_from_slice(&address.to_be_bytes());
+        pdu.extend_from_slice(&(values.len() as u16).to_be_bytes());
+        pdu.push((values.len() * 2) as u8);
+        for v in values {
+            pdu.extend_from_slice(&v.to_be_bytes());
+        }
+        self.request(&pdu).await.map(|_| ())
+    }
+}
@@ -0,0 +1,382 @@
+//! `modbus_register.data_format` / `operation` decoding.
+//!
+//! The strings are the ones the Modbus register editor offers
+//! (`dataFormatOptions` / `operationOptions` in the frontend), e.g.
+//! "16 Bit Signed Integer/10", "32 Bit Float_CDAB", "03_06 Read Holding and
+//! Write Single". Short aliases (`int16`, `uint32`, `float32_cdab`, ...) are
+//! accepted too for registers created through the API.
+//!
+//! Temco register maps often spread a multi-byte value over one register per
+//! byte (serial number = registers 0–3, one byte each). When a register's
+//! `register_length` is larger than the format needs, each register is taken
+//! as one byte of the value, low byte first unless the format says HI_LO.
+
+use serde::{Deserialize, Serialize};
+
+/// Order of the four bytes of a 32-bit value across two registers, written as
+/// the position of the most significant byte first (ABCD = big-endian).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteOrder {
+    Abcd,
+    Cdab,
+    Badc,
+    Dcba,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    F32,
+    Text,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DataFormat {
+    pub kind: ValueKind,
+    pub order: ByteOrder,
+    /// Raw value is divided by this (1 for unscaled formats).
+    pub divisor: f64,
+}
+
+/// A decoded register value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RegisterValue {
+    Number(f64),
+    Text(String),
+}
+
+impl RegisterValue {
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            RegisterValue::Number(v) => Some(*v),
+            RegisterValue::Text(_) => None,
+        }
+    }
+}
+
+impl DataFormat {
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        let s = raw.trim();
+        let lower = s.to_ascii_lowercase();
+        let order = if lower.contains("lo_hi") || lower.ends_with("cdab") {
+            ByteOrder::Cdab
+        } else if lower.ends_with("badc") {
+            ByteOrder::Badc
+        } else if lower.ends_with("dcba") {
+            ByteOrder::Dcba
+        } else {
+            ByteOrder::Abcd
+        };
+        let divisor = match lower.rsplit_once('/') {
+            Some((_, d)) => d
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|d| *d > 0.0)
+                .ok_or_else(|| format!("Invalid divisor in data format '{}'", raw))?,
+            None => 1.0,
+        };
+        let signed = lower.contains("signed") && !lower.contains("unsigned") || lower.starts_with("int");
+
+        let kind = if lower.starts_with("character string") || lower == "string" {
+            ValueKind::Text
+        } else if lower.starts_with("32 bit float") || lower.starts_with("float32") {
+            ValueKind::F32
+        } else if lower.starts_with("floating") {
+            // "Floating HI_LO/10": 32-bit signed integer with an implied decimal point.
+            ValueKind::I32
+        } else if lower.starts_with("8 bit") || lower.starts_with("int8") || lower.starts_with("uint8") {
+            if signed { ValueKind::I8 } else { ValueKind::U8 }
+        } else if lower.starts_with("16 bit") || lower.starts_with("int16") || lower.starts_with("uint16") {
+            if signed { ValueKind::I16 } else { ValueKind::U16 }
+        } else if lower.starts_with("32 bit") || lower.starts_with("int32") || lower.starts_with("uint32") {
+            if signed { ValueKind::I32 } else { ValueKind::U32 }
+        } else {
+            return Err(format!("Unsupported data format '{}'", raw));
+        };
+        Ok(Self { kind, order, divisor })
+    }
+
+    /// Registers a value of this format occupies when packed two bytes per register.
+    pub fn word_count(&self) -> usize {
+        match self.kind {
+            ValueKind::U8 | ValueKind::I8 | ValueKind::U16 | ValueKind::I16 => 1,
+            ValueKind::U32 | ValueKind::I32 | ValueKind::F32 => 2,
+            ValueKind::Text => 1,
+        }
+    }
+
+    fn byte_width(&self) -> usize {
+        match self.kind {
+            ValueKind::U8 | ValueKind::I8 => 1,
+            ValueKind::U16 | ValueKind::I16 => 2,
+            ValueKind::U32 | ValueKind::I32 | ValueKind::F32 => 4,
+            ValueKind::Text => 0,
+        }
+    }
+
+    /// Big-endian bytes of the value held in `regs`.
+    fn value_bytes(&self, regs: &[u16]) -> Result<Vec<u8>, String> {
+        let width = self.byte_width();
+        if regs.len() > self.word_count() {
+            // One byte per register.
+            let mut bytes: Vec<u8> = regs.iter().take(width).map(|r| (*r & 0xFF) as u8).collect();
+            if bytes.len() < width {
+                return Err(format!("expected {} registers, got {}", width, regs.len()));
+            }
+            if self.order != ByteOrder::Abcd {
+                bytes.reverse();
+            }
+            return Ok(bytes);
+        }
+        match width {
+            1 => Ok(vec![(regs[0] & 0xFF) as u8]),
+            2 => Ok(regs[0].to_be_bytes().to_vec()),
+            _ => {
+                if regs.len() < 2 {
+                    return Err(format!("expected 2 registers, got {}", regs.len()));
+                }
+                let [a, b] = regs[0].to_be_bytes();
+                let [c, d] = regs[1].to_be_bytes();
+                Ok(match self.order {
+                    ByteOrder::Abcd => vec![a, b, c, d],
+                    ByteOrder::Cdab => vec![c, d, a, b],
+                    ByteOrder::Badc => vec![b, a, d, c],
+                    ByteOrder::Dcba => vec![d, c, b, a],
+                })
+            }
+        }
+    }
+
+    pub fn decode(&self, regs: &[u16]) -> Result<RegisterValue, String> {
+        if regs.is_empty() {
+            return Err("no registers to decode".into());
+        }
+        if self.kind == ValueKind::Text {
+            let mut bytes = Vec::with_capacity(regs.len() * 2);
+            for r in regs {
+                let [hi, lo] = r.to_be_bytes();
+                match self.order {
+                    ByteOrder::Abcd => bytes.extend([hi, lo]),
+                    _ => bytes.extend([lo, hi]),
+                }
+            }
+            let text = String::from_utf8_lossy(&bytes);
+            return Ok(RegisterValue::Text(text.trim_end_matches(['\0', ' ']).to_string()));
+        }
+        let b = self.value_bytes(regs)?;
+        let raw = match self.kind {
+            ValueKind::U8 => b[0] as f64,
+            ValueKind::I8 => b[0] as i8 as f64,
+            ValueKind::U16 => u16::from_be_bytes([b[0], b[1]]) as f64,
+            ValueKind::I16 => i16::from_be_bytes([b[0], b[1]]) as f64,
+            ValueKind::U32 => u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as f64,
+            ValueKind::I32 => i32::from_be_bytes([b[0], b[1], b[2], b[3]]) as f64,
+            ValueKind::F32 => f32::from_be_bytes([b[0], b[1], b[2], b[3]]) as f64,
+            ValueKind::Text => unreachable!(),
+        };
+        Ok(RegisterValue::Number(raw / self.divisor))
+    }
+
+    /// Encode a value into `length` registers — the inverse of [`decode`](Self::decode).
+    pub fn encode(&self, value: &RegisterValue, length: usize) -> Result<Vec<u16>, String> {
+        let length = length.max(1);
+        if self.kind == ValueKind::Text {
+            let text = match value {
+                RegisterValue::Text(t) => t,
+                RegisterValue::Number(_) => return Err("text register needs a string value".into()),
+            };
+            let mut bytes = text.as_bytes().to_vec();
+            if bytes.len() > length * 2 {
+                return Err(format!("'{}' does not fit in {} registers", text, length));
+            }
+            bytes.resize(length * 2, 0);
+            return Ok(bytes
+                .chunks(2)
+                .map(|c| match self.order {
+                    ByteOrder::Abcd => u16::from_be_bytes([c[0], c[1]]),
+                    _ => u16::from_be_bytes([c[1], c[0]]),
+                })
+                .collect());
+        }
+
+        let v = value.as_f64().ok_or("numeric register needs a number")? * self.divisor;
+        let round = |min: f64, max: f64| -> Result<f64, String> {
+            let r = v.round();
+            if r < min || r > max {
+                Err(format!("{} is out of range for this register", v / self.divisor))
+            } else {
+                Ok(r)
+            }
+        };
+        let bytes: Vec<u8> = match self.kind {
+            ValueKind::U8 => vec![round(0.0, u8::MAX as f64)? as u8],
+            ValueKind::I8 => vec![round(i8::MIN as f64, i8::MAX as f64)? as i8 as u8],
+            ValueKind::U16 => (round(0.0, u16::MAX as f64)? as u16).to_be_bytes().to_vec(),
+            ValueKind::I16 => (round(i16::MIN as f64, i16::MAX as f64)? as i16).to_be_bytes().to_vec(),
+            ValueKind::U32 => (round(0.0, u32::MAX as f64)? as u32).to_be_bytes().to_vec(),
+            ValueKind::I32 => (round(i32::MIN as f64, i32::MAX as f64)? as i32).to_be_bytes().to_vec(),
+            ValueKind::F32 => (v as f32).to_be_bytes().to_vec(),
+            ValueKind::Text => unreachable!(),
+        };
+
+        if length > self.word_count() {
+            let mut per_register: Vec<u16> = bytes.iter().map(|b| *b as u16).collect();
+            if self.order != ByteOrder::Abcd {
+                per_register.reverse();
+            }
+            per_register.resize(length, 0);
+            return Ok(per_register);
+        }
+        Ok(match bytes.len() {
+            1 => vec![bytes[0] as u16],
+            2 => vec![u16::from_be_bytes([bytes[0], bytes[1]])],
+            _ => {
+                let (a, b, c, d) = (bytes[0], bytes[1], bytes[2], bytes[3]);
+                let (w0, w1) = match self.order {
+                    ByteOrder::Abcd => ([a, b], [c, d]),
+                    ByteOrder::Cdab => ([c, d], [a, b]),
+                    ByteOrder::Badc => ([b, a], [d, c]),
+                    ByteOrder::Dcba => ([d, c], [b, a]),
+                };
+                vec![u16::from_be_bytes(w0), u16::from_be_bytes(w1)]
+            }
+        })
+    }
+}
+
+/// Modbus function codes a register is read and written with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Operation {
+    /// 1 (coils), 2 (discrete inputs), 3 (holding) or 4 (input registers).
+    pub read: Option<u8>,
+    /// 5 / 15 (coils) or 6 / 16 (registers).
+    pub write: Option<u8>,
+}
+
+impl Operation {
+    /// Parse "03_06 Read Holding and Write Single" style strings — the leading
+    /// code(s) carry the function numbers.
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        let codes = raw.split_whitespace().next().unwrap_or("");
+        let mut op = Operation { read: None, write: None };
+        for code in codes.split('_') {
+            match code.parse::<u8>() {
+                Ok(f @ 1..=4) => op.read = Some(f),
+                Ok(f @ (5 | 6 | 15 | 16)) => op.write = Some(f),
+                _ => return Err(format!("Unsupported Modbus operation '{}'", raw)),
+            }
+        }
+        // "06 Read Write Single Register" / "16 Read Write Multiple" read back
+        // through holding registers; the coil writes through coils.
+        if op.read.is_none() {
+            op.read = match op.write {
+                Some(6 | 16) => Some(3),
+                Some(5 | 15) => Some(1),
+                _ => None,
+            };
+        }
+        Ok(op)
+    }
+
+    pub fn is_coil(&self) -> bool {
+        matches!(self.read, Some(1 | 2)) || matches!(self.write, Some(5 | 15))
+    }
+
+    /// Whether either side goes through 16-bit registers and so needs a data
+    /// format — also true for mixed operations like "03_05" or "01_06".
+    pub fn uses_registers(&self) -> bool {
+        matches!(self.read, Some(3 | 4)) || matches!(self.write, Some(6 | 16))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn num(v: f64) -> RegisterValue {
+        RegisterValue::Number(v)
+    }
+
+    #[test]
+    fn parses_editor_formats() {
+        let f = DataFormat::parse("16 Bit Signed Integer/10").unwrap();
+        assert_eq!((f.kind, f.divisor), (ValueKind::I16, 10.0));
+        let f = DataFormat::parse("32 Bit Unsigned Integer LO_HI").unwrap();
+        assert_eq!((f.kind, f.order), (ValueKind::U32, ByteOrder::Cdab));
+        let f = DataFormat::parse("32 Bit Float_BADC").unwrap();
+        assert_eq!((f.kind, f.order), (ValueKind::F32, ByteOrder::Badc));
+        assert_eq!(DataFormat::parse("uint32").unwrap().kind, ValueKind::U32);
+        assert!(DataFormat::parse("64 Bit Double").is_err());
+    }
+
+    #[test]
+    fn decodes_word_orders() {
+        // 21.5f32 = 0x41AC0000
+        let abcd = [0x41AC, 0x0000];
+        assert_eq!(DataFormat::parse("32 Bit Float_ABCD").unwrap().decode(&abcd).unwrap(), num(21.5));
+        assert_eq!(DataFormat::parse("32 Bit Float_CDAB").unwrap().decode(&[0x0000, 0x41AC]).unwrap(), num(21.5));
+        assert_eq!(DataFormat::parse("32 Bit Float_BADC").unwrap().decode(&[0xAC41, 0x0000]).unwrap(), num(21.5));
+        assert_eq!(DataFormat::parse("32 Bit Float_DCBA").unwrap().decode(&[0x0000, 0xAC41]).unwrap(), num(21.5));
+
+        let i16_10 = DataFormat::parse("16 Bit Signed Integer/10").unwrap();
+        assert_eq!(i16_10.decode(&[0xFF9C]).unwrap(), num(-10.0));
+        let u32_lohi = DataFormat::parse("32 Bit Unsigned Integer LO_HI").unwrap();
+        assert_eq!(u32_lohi.decode(&[0x0002, 0x0001]).unwrap(), num(65538.0));
+    }
+
+    #[test]
+    fn byte_per_register_maps_decode_low_byte_first() {
+        // Temco serial number: registers 0-3 hold one byte each, LSB first.
+        let serial = DataFormat::parse("32 Bit Unsigned Integer LO_HI").unwrap();
+        assert_eq!(serial.decode(&[0x78, 0x56, 0x34, 0x12]).unwrap(), num(0x1234_5678 as f64));
+        assert_eq!(serial.encode(&num(0x1234_5678 as f64), 4).unwrap(), vec![0x78, 0x56, 0x34, 0x12]);
+    }
+
+    #[test]
+    fn encode_inverts_decode() {
+        for (fmt, value, len) in [
+            ("16 Bit Signed Integer/100", -12.34, 1),
+            ("32 Bit Signed Integer HI_LO", -70000.0, 2),
+            ("32 Bit Float_CDAB", 1.5, 2),
+            ("Floating LO_HI/1000", 123.456, 2),
+            ("8 Bit Unsigned Integer", 200.0, 1),
+        ] {
+            let f = DataFormat::parse(fmt).unwrap();
+            let regs = f.encode(&num(value), len).unwrap();
+            let back = f.decode(&regs).unwrap().as_f64().unwrap();
+            assert!((back - value).abs() < 1e-6, "{}: {} != {}", fmt, back, value);
+        }
+        let text = DataFormat::parse("Character String HI_LO").unwrap();
+        let regs = text.encode(&RegisterValue::Text("AHU1".into()), 4).unwrap();
+        assert_eq!(text.decode(&regs).unwrap(), RegisterValue::Text("AHU1".into()));
+        assert!(DataFormat::parse("16 Bit Unsigned Integer").unwrap().encode(&num(70000.0), 1).is_err());
+    }
+
+    #[test]
+    fn operations_carry_read_and_write_functions() {
+        assert_eq!(
+            Operation::parse("03_06 Read Holding and Write Single").unwrap(),
+            Operation { read: Some(3), write: Some(6) }
+        );
+        assert_eq!(
+            Operation::parse("16 Read Write Multiple Registers").unwrap(),
+            Operation { read: Some(3), write: Some(16) }
+        );
+        assert_eq!(
+            Operation::parse("04 Read Input Registers (3x)").unwrap(),
+            Operation { read: Some(4), write: None }
+        );
+        assert!(Operation::parse("05 Write Single Coil").unwrap().is_coil());
+        assert!(!Operation::parse("05 Write Single Coil").unwrap().uses_registers());
+        assert!(Operation::parse("03_05").unwrap().uses_registers());
+        assert!(Operation::parse("01_06").unwrap().uses_registers());
+        assert!(Operation::parse("Read everything").is_err());
+    }
+}
@@ -0,0 +1,14 @@
+//! Modbus TCP — a pure-Rust master that polls the registers defined in the
+//! Modbus register editor (`modbus_register`) without going through T3000.exe.
+//!
+//! - `format` — `data_format` / `operation` strings ↔ register words
+//! - `client` — Modbus TCP functions 1–6, 15 and 16
+//! - `poller` — register maps per product, polling, writes and trendlog storage
+//! - `routes` — `/api/modbus` REST endpoints
+//! - `sim`    — a Modbus TCP stand-in for development and tests
+
+pub mod client;
+pub mod format;
+pub mod poller;
+pub mod routes;
+pub mod sim;
@@ -0,0 +1,525 @@
+//! Modbus TCP poller driven by the `modbus_register` definitions.
+//!
+//! A device's register map is the `modbus_register_devices` row its
+//! `Product_ID` is mapped to in `modbus_register_product_device_mapping`.
+//! Each cycle reads every readable register of every mapped device at the
+//! device's `ip_address` / `modbus_port`, decodes it per `data_format`, and
+//! stores numeric values through the realtime trendlog path (point type
+//! `MODBUS`, point index = register address) so they trend like T3000 points.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseBackend, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder,
+    Statement,
+};
+use serde::Serialize;
+use tracing::{debug, info, warn};
+
+use super::client::{ModbusError, ModbusTcpClient, DEFAULT_PORT};
+use super::format::{DataFormat, Operation, RegisterValue};
+use crate::db_connection::{establish_connection, establish_t3_device_connection};
+use crate::entity::{modbus_register, modbus_register_product_device_mapping};
+use crate::t3_device::trendlog_data_service::{CreateTrendlogDataRequest, T3TrendlogDataService};
+
+/// `point_type` used for Modbus values in TRENDLOG_DATA.
+pub const POINT_TYPE: &str = "MODBUS";
+/// `data_source` recorded on stored samples.
+pub const DATA_SOURCE: &str = "MODBUS_TCP";
+/// Unit id for devices without a `modbus_address`; 255 addresses the TCP device itself.
+const DEFAULT_UNIT_ID: u8 = 255;
+
+static POLLER_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Poller tuning.
+#[derive(Debug, Clone)]
+pub struct ModbusPollerConfig {
+    /// Seconds between poll cycles.
+    pub interval_secs: u64,
+    /// Per-request timeout.
+    pub timeout_ms: u64,
+    /// Delay before the first cycle so startup sync can populate DEVICES.
+    pub initial_delay_secs: u64,
+}
+
+impl Default for ModbusPollerConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: 60,
+            timeout_ms: 2000,
+            initial_delay_secs: 90,
+        }
+    }
+}
+
+/// One readable/writable register from `modbus_register`.
+#[derive(Debug, Clone, Serialize)]
+pub struct RegisterDef {
+    pub id: i32,
+    pub name: String,
+    pub address: u16,
+    pub length: u16,
+    pub operation: Operation,
+    pub data_format: String,
+    pub unit: Option<String>,
+    #[serde(skip)]
+    format: Option<DataFormat>,
+}
+
+impl RegisterDef {
+    pub fn from_model(m: &modbus_register::Model) -> Result<Self, String> {
+        let address = m
+            .register_address
+            .and_then(|a| u16::try_from(a).ok())
+            .ok_or_else(|| format!("register {} has no valid address", m.id))?;
+        let operation = Operation::parse(m.operation.as_deref().unwrap_or(""))?;
+        let data_format = m.data_format.clone().unwrap_or_default();
+        // Coils carry bits, so the data format only matters when a side of
+        // the operation goes through registers.
+        let format = if operation.uses_registers() {
+            Some(DataFormat::parse(&data_format)?)
+        } else {
+            None
+        };
+        Ok(Self {
+            id: m.id,
+            name: m
+                .register_name
+                .clone()
+                .filter(|n| !n.trim().is_empty())
+                .unwrap_or_else(|| format!("MB{}", address)),
+            address,
+            length: m.register_length.clamp(1, 125) as u16,
+            operation,
+            data_format,
+            unit: m.unit.clone(),
+            format,
+        })
+    }
+
+    pub fn is_writable(&self) -> bool {
+        self.operation.write.is_some()
+    }
+}
+
+/// Where and how to reach a device over Modbus TCP.
+#[derive(Debug, Clone, Serialize)]
+pub struct PollTarget {
+    pub serial_number: i32,
+    pub panel_id: i32,
+    pub product_id: Option<i32>,
+    pub addr: SocketAddr,
+    pub unit_id: u8,
+}
+
+/// One register's poll result.
+#[derive(Debug, Clone, Serialize)]
+pub struct RegisterReading {
+    pub id: i32,
+    pub name: String,
+    pub address: u16,
+    pub unit: Option<String>,
+    pub writable: bool,
+    pub value: Option<RegisterValue>,
+    pub error: Option<String>,
+}
+
+/// Why a device lookup, poll or write failed.
+#[derive(Debug, thiserror::Error)]
+pub enum DeviceError {
+    /// The device, its register map or the register doesn't exist.
+    #[error("{0}")]
+    NotFound(String),
+    /// The request can't be carried out: a read-only register or a value the
+    /// register can't hold.
+    #[error("{0}")]
+    Rejected(String),
+    /// The device has no usable Modbus TCP endpoint.
+    #[error("{0}")]
+    NoEndpoint(String),
+    /// The device didn't answer or answered with an error.
+    #[error(transparent)]
+    Modbus(#[from] ModbusError),
+    /// Reading the register map or storing values failed.
+    #[error("{0}")]
+    Db(String),
+}
+
+/// Whether the background poller loop is currently running.
+pub fn is_running() -> bool {
+    POLLER_RUNNING.load(Ordering::SeqCst)
+}
+
+fn target_from_row(row: &sea_orm::QueryResult) -> Result<PollTarget, String> {
+    let serial: i32 = row.try_get("", "SerialNumber").map_err(|e| e.to_string())?;
+    let ip: IpAddr = row
+        .try_get::<Option<String>>("", "ip_address")
+        .ok()
+        .flatten()
+        .map(|ip| ip.trim().to_string())
+        .filter(|ip| !ip.is_empty())
+        .ok_or_else(|| format!("Device with serial {} has no IP address", serial))?
+        .parse()
+        .map_err(|e| format!("Device with serial {} has an invalid IP address: {}", serial, e))?;
+    let port = row
+        .try_get::<Option<i32>>("", "modbus_port")
+        .ok()
+        .flatten()
+        .and_then(|p| u16::try_from(p).ok())
+        .filter(|p| *p > 0)
+        .unwrap_or(DEFAULT_PORT);
+    let unit_id = row
+        .try_get::<Option<i32>>("", "modbus_address")
+        .ok()
+        .flatten()
+        .and_then(|a| u8::try_from(a).ok())
+        .filter(|a| *a > 0)
+        .unwrap_or(DEFAULT_UNIT_ID);
+    Ok(PollTarget {
+        serial_number: serial,
+        panel_id: row.try_get::<Option<i32>>("", "PanelId").ok().flatten().unwrap_or(0),
+        product_id: row.try_get::<Option<i32>>("", "Product_ID").ok().flatten(),
+        addr: SocketAddr::new(ip, port),
+        unit_id,
+    })
+}
+
+const TARGET_COLUMNS: &str = "SerialNumber, PanelId, Product_ID, ip_address, modbus_address, modbus_port";
+
+/// Resolve a device's Modbus TCP endpoint from DEVICES.
+pub async fn lookup_target(t3_db: &DatabaseConnection, serial: i32) -> Result<PollTarget, DeviceError> {
+    let row = t3_db
+        .query_one(Statement::from_sql_and_values(
+            DatabaseBackend::Sqlite,
+            format!("SELECT {} FROM DEVICES WHERE SerialNumber = ?", TARGET_COLUMNS),
+            [serial.into()],
+        ))
+        .await
+        .map_err(|e| DeviceError::Db(format!("Database error: {}", e)))?
+        .ok_or_else(|| DeviceError::NotFound(format!("Device with serial {} not found", serial)))?;
+    target_from_row(&row).map_err(DeviceError::NoEndpoint)
+}
+
+/// Active registers of the register map `product_id` is mapped to.
+pub async fn register_map(
+    webview_db: &DatabaseConnection,
+    product_id: i32,
+) -> Result<Vec<modbus_register::Model>, DeviceError> {
+    let mapping = modbus_register_product_device_mapping::Entity::find_by_id(product_id)
+        .one(webview_db)
+        .await
+        .map_err(|e| DeviceError::Db(format!("Database error: {}", e)))?;
+    let Some(mapping) = mapping else {
+        return Ok(Vec::new());
+    };
+    modbus_register::Entity::find()
+        .filter(modbus_register::Column::DeviceId.eq(mapping.device_id))
+        .filter(modbus_register::Column::Status.ne("DELETED"))
+        .order_by_asc(modbus_register::Column::RegisterAddress)
+        .all(webview_db)
+        .await
+        .map_err(|e| DeviceError::Db(format!("Database error: {}", e)))
+}
+
+/// Register definitions for a device; unparseable rows are logged and skipped.
+async fn register_defs(webview_db: &DatabaseConnection, target: &PollTarget) -> Result<Vec<RegisterDef>, DeviceError> {
+    let Some(product_id) = target.product_id else {
+        return Ok(Vec::new());
+    };
+    Ok(register_map(webview_db, product_id)
+        .await?
+        .iter()
+        .filter_map(|m| match RegisterDef::from_model(m) {
+            Ok(def) => Some(def),
+            Err(e) => {
+                warn!("Modbus register {} skipped: {}", m.id, e);
+                None
+            }
+        })
+        .collect())
+}
+
+pub fn client_for(target: &PollTarget, timeout: Duration) -> ModbusTcpClient {
+    ModbusTcpClient::new(target.addr, target.unit_id).with_timeout(timeout)
+}
+
+/// Bits as an integer, first bit least significant.
+fn bits_value(bits: &[bool]) -> f64 {
+    bits.iter().rev().fold(0u64, |acc, b| (acc << 1) | *b as u64) as f64
+}
+
+/// The data format of a register read or written through 16-bit registers.
+fn format_of(def: &RegisterDef) -> Result<&DataFormat, String> {
+    def.format
+        .as_ref()
+        .ok_or_else(|| format!("register '{}' has no data format", def.name))
+}
+
+/// Read and decode one register.
+pub async fn read_register(client: &ModbusTcpClient, def: &RegisterDef) -> Result<RegisterValue, ModbusError> {
+    let value = match def.operation.read {
+        Some(f @ (1 | 2)) => {
+            let bits = if f == 1 {
+                client.read_coils(def.address, def.length).await?
+            } else {
+                client.read_discrete_inputs(def.address, def.length).await?
+            };
+            RegisterValue::Number(bits_value(&bits))
+        }
+        Some(f @ (3 | 4)) => {
+            let words = if f == 3 {
+                client.read_holding_registers(def.address, def.length).await?
+            } else {
+                client.read_input_registers(def.address, def.length).await?
+            };
+            format_of(def).and_then(|f| f.decode(&words)).map_err(ModbusError::Protocol)?
+        }
+        _ => return Err(ModbusError::Protocol(format!("register '{}' is write-only", def.name))),
+    };
+    Ok(value)
+}
+
+/// A register write encoded for the wire.
+enum Encoded {
+    Bits(Vec<bool>),
+    Words(Vec<u16>),
+}
+
+/// Encode `value` for the register's write function, before any device I/O.
+fn encode_write(def: &RegisterDef, value: &RegisterValue) -> Result<Encoded, String> {
+    match def.operation.write {
+        Some(f @ (5 | 15)) => {
+            let n = value.as_f64().ok_or("coil register needs a numeric value")?;
+            let n = n.round().max(0.0) as u64;
+            if f == 5 {
+                Ok(Encoded::Bits(vec![n != 0]))
+            } else {
+                Ok(Encoded::Bits((0..def.length).map(|i| i < 64 && n & (1 << i) != 0).collect()))
+            }
+        }
+        Some(6 | 16) => Ok(Encoded::Words(format_of(def)?.encode(value, def.length as usize)?)),
+        _ => Err(format!("register '{}' is read-only", def.name)),
+    }
+}
+
+async fn send_write(client: &ModbusTcpClient, def: &RegisterDef, encoded: &Encoded) -> Result<(), ModbusError> {
+    match (def.operation.write, encoded) {
+        (Some(5), Encoded::Bits(bits)) => client.write_single_coil(def.address, bits[0]).await,
+        (_, Encoded::Bits(bits)) => client.write_multiple_coils(def.address, bits).await,
+        (Some(6), Encoded::Words(words)) if words.len() == 1 => client.write_single_register(def.address, words[0]).await,
+        (_, Encoded::Words(words)) => client.write_multiple_registers(def.address, words).await,
+    }
+}
+
+/// Encode and write one register with its operation's write function.
+pub async fn write_register(client: &ModbusTcpClient, def: &RegisterDef, value: &RegisterValue) -> Result<(), ModbusError> {
+    let encoded = encode_write(def, value).map_err(ModbusError::Protocol)?;
+    send_write(client, def, &encoded).await
+}
+
+/// Read every readable register in `defs`. Per-register failures are reported
+/// in the reading rather than aborting the poll.
+pub async fn poll_registers(client: &ModbusTcpClient, defs: &[RegisterDef]) -> Vec<RegisterReading> {
+    let mut readings = Vec::with_capacity(defs.len());
+    for def in defs {
+        let result = if def.operation.read.is_some() {
+            read_register(client, def).await.map(Some)
+        } else {
+            Ok(None)
+        };
+        let (value, error) = match result {
+            Ok(v) => (v, None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        readings.push(RegisterReading {
+            id: def.id,
+            name: def.name.clone(),
+            address: def.address,
+            unit: def.unit.clone(),
+            writable: def.is_writable(),
+            value,
+            error,
+        });
+    }
+    readings
+}
+
+/// Store numeric readings as realtime trendlog samples. Returns rows written.
+pub async fn store_values(
+    t3_db: &DatabaseConnection,
+    target: &PollTarget,
+    readings: &[RegisterReading],
+    interval_secs: Option<i32>,
+) -> Result<u64, DeviceError> {
+    let batch: Vec<CreateTrendlogDataRequest> = readings
+        .iter()
+        .filter_map(|r| {
+            let value = r.value.as_ref()?.as_f64()?;
+            Some(CreateTrendlogDataRequest {
+                serial_number: target.serial_number,
+                panel_id: target.panel_id,
+                point_id: r.name.clone(),
+                point_index: r.address as i32,
+                point_type: POINT_TYPE.to_string(),
+                value: value.to_string(),
+                range_field: None,
+                digital_analog: Some("1".to_string()),
+                units: r.unit.clone(),
+                data_source: Some(DATA_SOURCE.to_string()),
+                sync_interval: interval_secs,
+                created_by: Some("BACKEND".to_string()),
+                poll_cycle_id: None,
+            })
+        })
+        .collect();
+    if batch.is_empty() {
+        return Ok(0);
+    }
+    T3TrendlogDataService::save_realtime_batch(t3_db, batch)
+        .await
+        .map_err(|e| DeviceError::Db(format!("Failed to store Modbus values: {}", e)))
+}
+
+/// Poll one device by serial and store its values.
+pub async fn poll_device(
+    webview_db: &DatabaseConnection,
+    t3_db: &DatabaseConnection,
+    serial: i32,
+    timeout: Duration,
+) -> Result<Vec<RegisterReading>, DeviceError> {
+    let target = lookup_target(t3_db, serial).await?;
+    let defs = register_defs(webview_db, &target).await?;
+    if defs.is_empty() {
+        return Err(DeviceError::NotFound(format!("Device with serial {} has no Modbus register map", serial)));
+    }
+    let readings = poll_registers(&client_for(&target, timeout), &defs).await;
+    store_values(t3_db, &target, &readings, None).await?;
+    Ok(readings)
+}
+
+/// Write `value` to register `register_id` of device `serial`, then read it
+/// back and store the new value.
+pub async fn write_device_register(
+    webview_db: &DatabaseConnection,
+    t3_db: &DatabaseConnection,
+    serial: i32,
+    register_id: i32,
+    value: &RegisterValue,
+    timeout: Duration,
+) -> Result<RegisterReading, DeviceError> {
+    let target = lookup_target(t3_db, serial).await?;
+    let def = register_defs(webview_db, &target)
+        .await?
+        .into_iter()
+        .find(|d| d.id == register_id)
+        .ok_or_else(|| {
+            DeviceError::NotFound(format!("Register {} is not in the register map of device {}", register_id, serial))
+        })?;
+    let encoded = encode_write(&def, value).map_err(DeviceError::Rejected)?;
+    let client = client_for(&target, timeout);
+    send_write(&client, &def, &encoded).await?;
+    let reading = poll_registers(&client, std::slice::from_ref(&def)).await.remove(0);
+    store_values(t3_db, &target, std::slice::from_ref(&reading), None).await?;
+    Ok(reading)
+}
+
+/// Devices whose product has a register map and that have an IP address.
+async fn mapped_targets(webview_db: &DatabaseConnection, t3_db: &DatabaseConnection) -> Result<Vec<PollTarget>, String> {
+    let products: Vec<i32> = modbus_register_product_device_mapping::Entity::find()
+        .all(webview_db)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .into_iter()
+        .map(|m| m.product_id)
+        .collect();
+    if products.is_empty() {
+        return Ok(Vec::new());
+    }
+    let ids = products.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(",");
+    let rows = t3_db
+        .query_all(Statement::from_string(
+            DatabaseBackend::Sqlite,
+            format!(
+                "SELECT {} FROM DEVICES WHERE Product_ID IN ({}) AND ip_address IS NOT NULL AND ip_address != '' \
+                 ORDER BY SerialNumber",
+                TARGET_COLUMNS, ids
+            ),
+        ))
+        .await
+        .map_err(|e| format!("Modbus device query failed: {}", e))?;
+    Ok(rows.iter().filter_map(|r| target_from_row(r).ok()).collect())
+}
+
+/// Run one poll pass over every mapped device. Returns the number of values stored.
+pub async fn run_cycle(
+    webview_db: &DatabaseConnection,
+    t3_db: &DatabaseConnection,
+    config: &ModbusPollerConfig,
+) -> Result<u64, String> {
+    let timeout = Duration::from_millis(config.timeout_ms);
+    let mut defs_by_product: HashMap<i32, Vec<RegisterDef>> = HashMap::new();
+    let mut stored = 0;
+    for target in mapped_targets(webview_db, t3_db).await? {
+        let product = target.product_id.unwrap_or_default();
+        if let Entry::Vacant(slot) = defs_by_product.entry(product) {
+            // A map that fails to load skips its devices, not the whole cycle.
+            let defs = register_defs(webview_db, &target).await.unwrap_or_else(|e| {
+                warn!("Modbus poller: register map of product {} unavailable: {}", product, e);
+                Vec::new()
+            });
+            slot.insert(defs);
+        }
+        let defs = &defs_by_product[&product];
+        if defs.is_empty() {
+            continue;
+        }
+        let readings = poll_registers(&client_for(&target, timeout), defs).await;
+        if readings.iter().all(|r| r.error.is_some()) {
+            warn!("Modbus poller: device {} at {} did not answer", target.serial_number, target.addr);
+            continue;
+        }
+        match store_values(t3_db, &target, &readings, Some(config.interval_secs as i32)).await {
+            Ok(n) => stored += n,
+            Err(e) => warn!("Modbus poller: device {}: {}", target.serial_number, e),
+        }
+    }
+    Ok(stored)
+}
+
+/// Spawn the background Modbus poller. A second call while it is running is a no-op.
+pub async fn start_modbus_poller(config: ModbusPollerConfig) -> Result<(), String> {
+    if POLLER_RUNNING.swap(true, Ordering::SeqCst) {
+        return Ok(());
+    }
+
+    tokio::spawn(async move {
+        info!("Modbus poller started (interval {}s)", config.interval_secs);
+        tokio::time::sleep(Duration::from_secs(config.initial_delay_secs)).await;
+
+        loop {
+            let webview = establish_connection().await.map_err(|e| e.to_string());
+            let t3 = establish_t3_device_connection().await.map_err(|e| e.to_string());
+            let dbs = match (webview, t3) {
+                (Ok(webview), Ok(t3)) => Some((webview, t3)),
+                (Err(e), _) | (_, Err(e)) => {
+                    warn!("Modbus poller: database unavailable: {}", e);
+                    None
+                }
+            };
+            if let Some((webview_db, t3_db)) = dbs {
+                match run_cycle(&webview_db, &t3_db, &config).await {
+                    Ok(n) if n > 0 => debug!("Modbus poller: stored {} values", n),
+                    Ok(_) => {}
+                    Err(e) => warn!("Modbus poller cycle failed: {}", e),
+                }
+            }
+            tokio::time::sleep(Duration::from_secs(config.interval_secs.max(1))).await;
+        }
+    });
+
+    Ok(())
+}
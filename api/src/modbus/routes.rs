@@ -0,0 +1,113 @@
+//! Modbus TCP REST API — a device's register map with live values, on-demand
+//! poll, register writes and poller status, under `/api/modbus`.
+//!
+//! Register maps live in the webview database (`state.conn`); device
+//! addresses and stored values in the T3000 device database.
+
+use std::time::Duration;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    routing::{get, post, put},
+    Router,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::format::RegisterValue;
+use super::poller::{self, DeviceError, ModbusPollerConfig, RegisterDef};
+use crate::app_state::T3AppState;
+
+type ApiError = (StatusCode, Json<Value>);
+
+#[derive(Debug, Deserialize)]
+struct WriteRegisterRequest {
+    value: RegisterValue,
+}
+
+pub fn create_modbus_routes() -> Router<T3AppState> {
+    Router::new()
+        .route("/api/modbus/poller/status", get(poller_status))
+        .route("/api/modbus/:serial/registers", get(list_registers))
+        .route("/api/modbus/:serial/poll", post(poll_now))
+        .route("/api/modbus/:serial/registers/:register_id", put(write_register))
+}
+
+async fn get_dbs(state: &T3AppState) -> Result<(sea_orm::DatabaseConnection, sea_orm::DatabaseConnection), ApiError> {
+    let webview_db = state.conn.lock().await.clone();
+    let t3_db = match &state.t3_device_conn {
+        Some(conn) => conn.lock().await.clone(),
+        None => {
+            return Err((
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(json!({"error": "T3000 device database unavailable"})),
+            ))
+        }
+    };
+    Ok((webview_db, t3_db))
+}
+
+fn timeout() -> Duration {
+    Duration::from_millis(ModbusPollerConfig::default().timeout_ms)
+}
+
+fn device_error(e: DeviceError) -> ApiError {
+    let status = match e {
+        DeviceError::NotFound(_) => StatusCode::NOT_FOUND,
+        DeviceError::Rejected(_) => StatusCode::BAD_REQUEST,
+        DeviceError::NoEndpoint(_) | DeviceError::Modbus(_) => StatusCode::BAD_GATEWAY,
+        DeviceError::Db(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, Json(json!({ "error": e.to_string() })))
+}
+
+async fn poller_status() -> Json<Value> {
+    let config = ModbusPollerConfig::default();
+    Json(json!({
+        "enabled": crate::constants::ENABLE_MODBUS_POLLER,
+        "running": poller::is_running(),
+        "interval_secs": config.interval_secs,
+        "timeout_ms": config.timeout_ms,
+    }))
+}
+
+/// The device's register definitions and Modbus endpoint (no device I/O).
+async fn list_registers(
+    State(state): State<T3AppState>,
+    Path(serial): Path<i32>,
+) -> Result<Json<Value>, ApiError> {
+    let (webview_db, t3_db) = get_dbs(&state).await?;
+    let target = poller::lookup_target(&t3_db, serial).await.map_err(device_error)?;
+    let models = match target.product_id {
+        Some(product_id) => poller::register_map(&webview_db, product_id).await.map_err(device_error)?,
+        None => Vec::new(),
+    };
+    let (registers, invalid): (Vec<_>, Vec<_>) = models.iter().map(RegisterDef::from_model).partition(Result::is_ok);
+    Ok(Json(json!({
+        "target": target,
+        "registers": registers.into_iter().map(Result::unwrap).collect::<Vec<_>>(),
+        "invalid": invalid.into_iter().map(|e| e.unwrap_err()).collect::<Vec<_>>(),
+    })))
+}
+
+async fn poll_now(State(state): State<T3AppState>, Path(serial): Path<i32>) -> Result<Json<Value>, ApiError> {
+    let (webview_db, t3_db) = get_dbs(&state).await?;
+    let readings = poller::poll_device(&webview_db, &t3_db, serial, timeout())
+        .await
+        .map_err(device_error)?;
+    Ok(Json(json!({ "serial_number": serial, "readings": readings })))
+}
+
+async fn write_register(
+    State(state): State<T3AppState>,
+    Path((serial, register_id)): Path<(i32, i32)>,
+    Json(payload): Json<WriteRegisterRequest>,
+) -> Result<Json<Value>, ApiError> {
+    let (webview_db, t3_db) = get_dbs(&state).await?;
+    let reading = poller::write_device_register(&webview_db, &t3_db, serial, register_id, &payload.value, timeout())
+        .await
+        .map_err(device_error)?;
+    Ok(Json(json!({ "serial_number": serial, "reading": reading })))
+}
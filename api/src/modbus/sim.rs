@@ -0,0 +1,231 @@
+//! In-process Modbus TCP stand-in.
+//!
+//! Serves holding/input registers and coils/discrete inputs from in-memory
+//! banks for functions 1–6, 15 and 16. Reads or writes outside the defined
+//! addresses get exception 02 (illegal data address), unknown functions get
+//! exception 01 — the same answers a Temco controller gives.
+
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+const ILLEGAL_FUNCTION: u8 = 0x01;
+const ILLEGAL_DATA_ADDRESS: u8 = 0x02;
+const ILLEGAL_DATA_VALUE: u8 = 0x03;
+
+#[derive(Debug, Clone, Default)]
+pub struct SimBanks {
+    pub coils: BTreeMap<u16, bool>,
+    pub discrete_inputs: BTreeMap<u16, bool>,
+    pub holding: BTreeMap<u16, u16>,
+    pub input: BTreeMap<u16, u16>,
+}
+
+/// Simulated Modbus device definition. Build it up, then [`SimDevice::spawn`] it.
+#[derive(Debug, Clone, Default)]
+pub struct SimDevice {
+    banks: SimBanks,
+}
+
+impl SimDevice {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_holding(mut self, address: u16, values: &[u16]) -> Self {
+        for (i, v) in values.iter().enumerate() {
+            self.banks.holding.insert(address + i as u16, *v);
+        }
+        self
+    }
+
+    pub fn with_input(mut self, address: u16, values: &[u16]) -> Self {
+        for (i, v) in values.iter().enumerate() {
+            self.banks.input.insert(address + i as u16, *v);
+        }
+        self
+    }
+
+    pub fn with_coils(mut self, address: u16, values: &[bool]) -> Self {
+        for (i, v) in values.iter().enumerate() {
+            self.banks.coils.insert(address + i as u16, *v);
+        }
+        self
+    }
+
+    pub fn with_discrete_inputs(mut self, address: u16, values: &[bool]) -> Self {
+        for (i, v) in values.iter().enumerate() {
+            self.banks.discrete_inputs.insert(address + i as u16, *v);
+        }
+        self
+    }
+
+    /// Serve the device on `bind` until the returned handle is dropped.
+    pub async fn spawn(self, bind: SocketAddr) -> std::io::Result<SimHandle> {
+        let listener = TcpListener::bind(bind).await?;
+        let addr = listener.local_addr()?;
+        let banks = Arc::new(Mutex::new(self.banks));
+        let served = banks.clone();
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, served.clone()));
+            }
+        });
+        Ok(SimHandle { addr, banks, task })
+    }
+}
+
+/// A running simulated device. Dropping it stops the listener.
+pub struct SimHandle {
+    pub addr: SocketAddr,
+    banks: Arc<Mutex<SimBanks>>,
+    task: JoinHandle<()>,
+}
+
+impl SimHandle {
+    pub fn holding(&self, address: u16) -> Option<u16> {
+        self.banks.lock().ok()?.holding.get(&address).copied()
+    }
+
+    pub fn coil(&self, address: u16) -> Option<bool> {
+        self.banks.lock().ok()?.coils.get(&address).copied()
+    }
+
+    /// Change a holding register as if the field value had moved.
+    pub fn set_holding(&self, address: u16, value: u16) {
+        if let Ok(mut banks) = self.banks.lock() {
+            banks.holding.insert(address, value);
+        }
+    }
+
+    pub fn set_input(&self, address: u16, value: u16) {
+        if let Ok(mut banks) = self.banks.lock() {
+            banks.input.insert(address, value);
+        }
+    }
+}
+
+impl Drop for SimHandle {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn serve(mut stream: TcpStream, banks: Arc<Mutex<SimBanks>>) {
+    loop {
+        let mut header = [0u8; 7];
+        if stream.read_exact(&mut header).await.is_err() {
+            return;
+        }
+        let len = u16::from_be_bytes([header[4], header[5]]) as usize;
+        let mut pdu = vec![0u8; len.saturating_sub(1)];
+        if stream.read_exact(&mut pdu).await.is_err() {
+            return;
+        }
+        let reply = match banks.lock() {
+            Ok(mut banks) => handle(&mut banks, &pdu),
+            Err(_) => return,
+        };
+        let mut frame = header[..4].to_vec();
+        frame.extend_from_slice(&(reply.len() as u16 + 1).to_be_bytes());
+        frame.push(header[6]);
+        frame.extend_from_slice(&reply);
+        if stream.write_all(&frame).await.is_err() {
+            return;
+        }
+    }
+}
+
+fn exception(function: u8, code: u8) -> Vec<u8> {
+    vec![function | 0x80, code]
+}
+
+fn word(pdu: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([*pdu.get(at)?, *pdu.get(at + 1)?]))
+}
+
+/// Answer one request PDU.
+fn handle(banks: &mut SimBanks, pdu: &[u8]) -> Vec<u8> {
+    let Some(&function) = pdu.first() else {
+        return exception(0, ILLEGAL_FUNCTION);
+    };
+    let (Some(address), Some(arg)) = (word(pdu, 1), word(pdu, 3)) else {
+        return exception(function, ILLEGAL_DATA_VALUE);
+    };
+    let range = || address..address.saturating_add(arg);
+
+    match function {
+        1 | 2 => {
+            let bank = if function == 1 { &banks.coils } else { &banks.discrete_inputs };
+            let Some(bits) = range().map(|a| bank.get(&a).copied()).collect::<Option<Vec<bool>>>() else {
+                return exception(function, ILLEGAL_DATA_ADDRESS);
+            };
+            let mut packed = vec![0u8; bits.len().div_ceil(8)];
+            for (i, _) in bits.iter().enumerate().filter(|(_, b)| **b) {
+                packed[i / 8] |= 1 << (i % 8);
+            }
+            let mut reply = vec![function, packed.len() as u8];
+            reply.extend(packed);
+            reply
+        }
+        3 | 4 => {
+            let bank = if function == 3 { &banks.holding } else { &banks.input };
+            let Some(words) = range().map(|a| bank.get(&a).copied()).collect::<Option<Vec<u16>>>() else {
+                return exception(function, ILLEGAL_DATA_ADDRESS);
+            };
+            let mut reply = vec![function, (words.len() * 2) as u8];
+            reply.extend(words.iter().flat_map(|w| w.to_be_bytes()));
+            reply
+        }
+        5 => {
+            if !banks.coils.contains_key(&address) {
+                return exception(function, ILLEGAL_DATA_ADDRESS);
+            }
+            let value = match arg {
+                0xFF00 => true,
+                0x0000 => false,
+                _ => return exception(function, ILLEGAL_DATA_VALUE),
+            };
+            banks.coils.insert(address, value);
+            pdu[..5].to_vec()
+        }
+        6 => {
+            if !banks.holding.contains_key(&address) {
+                return exception(function, ILLEGAL_DATA_ADDRESS);
+            }
+            banks.holding.insert(address, arg);
+            pdu[..5].to_vec()
+        }
+        15 | 16 => {
+            if range().any(|a| {
+                if function == 15 {
+                    !banks.coils.contains_key(&a)
+                } else {
+                    !banks.holding.contains_key(&a)
+                }
+            }) {
+                return exception(function, ILLEGAL_DATA_ADDRESS);
+            }
+            let data = pdu.get(6..).unwrap_or_default();
+            for (i, a) in range().enumerate() {
+                if function == 15 {
+                    let Some(byte) = data.get(i / 8) else {
+                        return exception(function, ILLEGAL_DATA_VALUE);
+                    };
+                    banks.coils.insert(a, byte & (1 << (i % 8)) != 0);
+                } else {
+                    let Some(v) = word(data, i * 2) else {
+                        return exception(function, ILLEGAL_DATA_VALUE);
+                    };
+                    banks.holding.insert(a, v);
+                }
+            }
+            pdu[..5].to_vec()
+        }
+        _ => exception(function, ILLEGAL_FUNCTION),
+    }
+}
@@ -178,6 +178,8 @@ pub async fn create_t3_app(app_state: T3AppState) -> Result<Router, Box<dyn Erro
     // Start heartbeat task for server/client registry
     crate::server_db::registry_service::start_heartbeat_task(app_state.clone());
 
+    let auth_state = app_state.clone();
+
     Ok(Router::new()
         .nest(
             "/api",
@@ -218,18 +220,30 @@ pub async fn create_t3_app(app_state: T3AppState) -> Result<Router, Box<dyn Erro
         .merge(crate::haystack::tags_routes::create_haystack_tags_routes())
         // Haystack Auto-tagging routes (v3)
         .merge(crate::haystack::auto_tagging_routes::create_auto_tagging_routes())
+        // FDD REST API routes (rules, analyze, findings)
+        .merge(crate::fdd::routes::create_fdd_routes())
+        // Modbus TCP poller routes (register reads, writes, on-demand poll)
+        .merge(crate::modbus::routes::create_modbus_routes())
         // MCP Server routes (JSON-RPC over HTTP)
         .merge(crate::mcp::server::create_mcp_routes())
         // AI Chat routes (SSE streaming + tool-call loop)
         .merge(crate::ai::create_ai_routes())
         // Point Sets API routes (DB-backed)
         .merge(crate::t3_device::point_sets_routes::create_point_sets_routes())
+        // User accounts, sessions and role management
+        .merge(crate::auth::routes::create_auth_routes())
+        // Audit trail query + export
+        .merge(crate::audit::routes::create_audit_routes())
         // Server local-time endpoint (for client timezone alignment)
         .route("/api/server/time", get(server_time_handler))
         // Real-time trend data routes - TEMPORARILY DISABLED
         // .nest("/api", crate::t3_device::trend_routes::trend_data_routes())
         .with_state(app_state)
         .fallback_service(routes_static())
+        // Audit context for every request; records engineer/admin-level writes
+        .layer(middleware::from_fn_with_state(auth_state.clone(), crate::audit::middleware::audit_changes))
+        // Role checks for every /api route (open until the first account exists)
+        .layer(middleware::from_fn_with_state(auth_state, crate::auth::enforce_roles))
         .layer(middleware::from_fn(propagate_flow_id))
         .layer(cors))
 }
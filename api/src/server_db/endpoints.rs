@@ -50,6 +50,21 @@ use super::{
         // Trendlog Query endpoints (multi-partition support)
         .route("/api/database/trendlog/query", post(query_trendlog_across_partitions))
 
+        // Trendlog rollup endpoints (hourly/daily summaries)
+        .route("/api/database/trendlog/rollups", get(query_trendlog_rollups))
+        .route("/api/database/trendlog/rollups/rebuild", post(rebuild_trendlog_rollups))
+        .route("/api/database/trendlog/rollups/retention", get(get_rollup_retention))
+        .route("/api/database/trendlog/rollups/retention", put(update_rollup_retention))
+
+        // Columnar trendlog export (Parquet / Arrow IPC)
+        .route("/api/database/trendlog/export", post(export_trendlog))
+
+        // Trendlog CSV import (streamed multipart upload, no size limit)
+        .route(
+            "/api/database/trendlog/import",
+            post(import_trendlog).layer(axum::extract::DefaultBodyLimit::disable()),
+        )
+
         // Database Partition endpoints
         .route("/db_management/partitions", post(create_partition))
         .route("/db_management/partitions", get(get_partitions))
@@ -737,6 +752,9 @@ struct TrendlogQueryRequest {
     panel_id: Option<i32>,
     point_id: Option<String>,
     point_type: Option<String>,
+    /// Return per-point buckets (or LTTB samples) instead of raw rows
+    #[serde(default)]
+    aggregation: Option<super::trendlog_aggregation::AggregationRequest>,
 }
 
 /// Query trendlog data across multiple SQLite partition files and main database.
@@ -747,7 +765,7 @@ struct TrendlogQueryRequest {
 async fn query_trendlog_across_partitions(
     State(app_state): State<T3AppState>,
     Json(request): Json<TrendlogQueryRequest>,
-) -> Result<Json<Vec<super::partition_query_service::TrendlogDataRecord>>> {
+) -> Result<Json<serde_json::Value>> {
     use super::partition_query_service::{query_trendlog_data, TrendlogFilters};
     use chrono::NaiveDateTime;
 
@@ -771,7 +789,7 @@ async fn query_trendlog_across_partitions(
         )
         .await
         {
-            Ok(results) => return Ok(Json(results)),
+            Ok(results) => return Ok(Json(trendlog_query_response(results, &request, start_date, end_date))),
             Err(e) => {
                 eprintln!("[trendlog/query] MSSQL query failed, falling back to SQLite: {}", e);
             }
@@ -782,11 +800,279 @@ async fn query_trendlog_across_partitions(
     let filters = TrendlogFilters {
         serial_number: request.serial_number,
         panel_id: request.panel_id,
-        point_id: request.point_id,
-        point_type: request.point_type,
+        point_id: request.point_id.clone(),
+        point_type: request.point_type.clone(),
     };
 
     let results = query_trendlog_data(start_date, end_date, filters).await?;
-    Ok(Json(results))
+    Ok(Json(trendlog_query_response(results, &request, start_date, end_date)))
+}
+
+/// Raw records as a plain array; aggregated ones as `{ data, aggregation }`.
+fn trendlog_query_response(
+    records: Vec<super::partition_query_service::TrendlogDataRecord>,
+    request: &TrendlogQueryRequest,
+    start_date: chrono::NaiveDateTime,
+    end_date: chrono::NaiveDateTime,
+) -> serde_json::Value {
+    match &request.aggregation {
+        Some(aggregation) => {
+            let (data, summary) = super::partition_query_service::aggregate_trendlog_records(
+                &records, aggregation, start_date, end_date,
+            );
+            serde_json::json!({ "data": data, "aggregation": summary })
+        }
+        None => serde_json::json!(records),
+    }
+}
+
+// ============================================================================
+// Trendlog Rollup Endpoints
+// ============================================================================
+
+/// Hourly or daily rollup buckets per point.
+///
+/// Routing follows the trendlog query: MSSQL center DB when the pool is
+/// active, local SQLite otherwise.
+async fn query_trendlog_rollups(
+    State(app_state): State<T3AppState>,
+    Query(query): Query<super::trendlog_rollup::RollupQuery>,
+) -> Result<Json<Vec<super::trendlog_rollup::RollupRow>>> {
+    if let Some(pool) = &app_state.mssql_pool {
+        let rows = super::mssql_queries::query_trendlog_rollups(pool, &query)
+            .await
+            .map_err(crate::error::Error::DbError)?;
+        return Ok(Json(rows));
+    }
+    let db = match &app_state.local_config_conn {
+        Some(conn) => &*conn.lock().await,
+        None => return Err(crate::error::Error::ServerError("T3 device database not available".to_string()))
+    };
+    Ok(Json(super::trendlog_rollup::query(db, &query).await?))
+}
+
+#[derive(Debug, Deserialize)]
+struct RollupRebuildRequest {
+    start_date: Option<String>, // "2025-10-01"; both dates or neither
+    end_date: Option<String>,   // "2025-10-31" (inclusive)
 }
 
+/// Recompute rollups from raw detail rows for a date range (or everything).
+async fn rebuild_trendlog_rollups(
+    State(app_state): State<T3AppState>,
+    Json(request): Json<RollupRebuildRequest>,
+) -> Result<Json<serde_json::Value>> {
+    use chrono::NaiveDate;
+
+    let parse = |s: &str| {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .map_err(|e| crate::error::Error::ValidationError(format!("Invalid date '{}': {}", s, e)))
+    };
+    let range = match (request.start_date.as_deref(), request.end_date.as_deref()) {
+        (Some(start), Some(end)) => Some((parse(start)?, parse(end)?)),
+        (None, None) => None,
+        _ => return Err(crate::error::Error::ValidationError("Give both start_date and end_date, or neither".to_string())),
+    };
+
+    if let Some(pool) = &app_state.mssql_pool {
+        let (start, end) = match range {
+            Some((start, end)) => (
+                format!("{} 00:00:00", start.format("%Y-%m-%d")),
+                format!("{} 00:00:00", (end + chrono::Duration::days(1)).format("%Y-%m-%d")),
+            ),
+            None => ("0000".to_string(), "9999".to_string()),
+        };
+        let (hourly, daily) = super::mssql_queries::rebuild_trendlog_rollups(pool, &start, &end)
+            .await
+            .map_err(crate::error::Error::DbError)?;
+        return Ok(Json(serde_json::json!({
+            "sources": ["mssql"],
+            "skipped": [],
+            "hourly_buckets": hourly,
+            "daily_buckets": daily,
+        })));
+    }
+
+    let db = match &app_state.local_config_conn {
+        Some(conn) => &*conn.lock().await,
+        None => return Err(crate::error::Error::ServerError("T3 device database not available".to_string()))
+    };
+    let summary = super::trendlog_rollup::rebuild(db, range).await?;
+    crate::logging::service::emit_app_log(
+        db,
+        "info",
+        "MAINTENANCE",
+        Some("trendlog_rollup"),
+        None,
+        "Trendlog rollups rebuilt",
+        Some(&format!(
+            "sources={}, skipped={}, hourly_buckets={}, daily_buckets={}",
+            summary.sources.len(),
+            summary.skipped.len(),
+            summary.hourly_buckets,
+            summary.daily_buckets
+        )),
+    )
+    .await;
+    Ok(Json(serde_json::to_value(summary).unwrap_or_default()))
+}
+
+/// Get rollup retention (independent of partition retention)
+async fn get_rollup_retention(
+    State(app_state): State<T3AppState>,
+) -> Result<Json<super::trendlog_rollup::RollupRetention>> {
+    let db = match &app_state.local_config_conn {
+        Some(conn) => &*conn.lock().await,
+        None => return Err(crate::error::Error::ServerError("T3 device database not available".to_string()))
+    };
+    Ok(Json(super::trendlog_rollup::load_retention(db).await))
+}
+
+/// Save rollup retention and prune right away
+async fn update_rollup_retention(
+    State(app_state): State<T3AppState>,
+    Json(retention): Json<super::trendlog_rollup::RollupRetention>,
+) -> Result<Json<serde_json::Value>> {
+    {
+        let db = match &app_state.local_config_conn {
+            Some(conn) => &*conn.lock().await,
+            None => return Err(crate::error::Error::ServerError("T3 device database not available".to_string()))
+        };
+        super::trendlog_rollup::save_retention(db, &retention).await?;
+    }
+    let pruned = super::trendlog_rollup::enforce_retention().await?;
+    Ok(Json(serde_json::json!({
+        "retention": retention,
+        "pruned": pruned,
+    })))
+}
+
+// ============================================================================
+// Trendlog Export Endpoint
+// ============================================================================
+
+/// Stream selected points and a time range as Parquet or Arrow IPC.
+///
+/// Reads the local T3 database and its partition files (archived ones
+/// included). Request errors come back as JSON before streaming starts; a
+/// failure mid-stream aborts the body, so a truncated file never looks complete.
+async fn export_trendlog(
+    State(app_state): State<T3AppState>,
+    Json(request): Json<super::trendlog_export::ExportRequest>,
+) -> Result<axum::response::Response> {
+    use axum::http::header;
+
+    let db = match &app_state.local_config_conn {
+        Some(conn) => conn.lock().await.clone(),
+        None => return Err(crate::error::Error::ServerError("T3 device database not available".to_string()))
+    };
+    let plan = super::trendlog_export::ExportPlan::prepare(&db, &request).await?;
+    let filename = format!(
+        "trendlog_{}_{}.{}",
+        plan.start.format("%Y%m%d%H%M%S"),
+        plan.end.format("%Y%m%d%H%M%S"),
+        plan.format.extension()
+    );
+    let content_type = plan.format.content_type();
+
+    let (tx, rx) = tokio::sync::mpsc::channel::<std::io::Result<Vec<u8>>>(4);
+    tokio::spawn(async move {
+        let (level, message, details) = match plan.run(&tx).await {
+            Ok(summary) => (
+                "info",
+                "Trendlog export completed",
+                format!(
+                    "rows={}, points={}, sources={}, skipped={}, bytes={}",
+                    summary.rows,
+                    summary.points,
+                    summary.sources.len(),
+                    summary.skipped.len(),
+                    summary.bytes
+                ),
+            ),
+            Err(e) => {
+                let _ = tx.send(Err(std::io::Error::other(e.to_string()))).await;
+                ("error", "Trendlog export failed", e.to_string())
+            }
+        };
+        crate::logging::service::emit_app_log(&db, level, "TRENDLOG", Some("trendlog_export"), None, message, Some(&details))
+            .await;
+    });
+
+    axum::response::Response::builder()
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename))
+        .body(axum::body::Body::from_stream(tokio_stream::wrappers::ReceiverStream::new(rx)))
+        .map_err(|e| crate::error::Error::ServerError(format!("Failed to build export response: {}", e)))
+}
+
+// ============================================================================
+// Trendlog Import Endpoint
+// ============================================================================
+
+/// Import trendlog history from a CSV upload.
+///
+/// Multipart form: a `mapping` part (JSON [`ImportMapping`]) followed by the
+/// `file` part. The file is read as it arrives, so its size is not limited.
+///
+/// [`ImportMapping`]: super::trendlog_import::ImportMapping
+async fn import_trendlog(
+    State(app_state): State<T3AppState>,
+    mut multipart: axum::extract::Multipart,
+) -> Result<Json<super::trendlog_import::ImportSummary>> {
+    use futures::TryStreamExt;
+    use super::trendlog_import::{import_csv, ImportMapping, ImportOptions};
+
+    let db = match &app_state.local_config_conn {
+        Some(conn) => conn.lock().await.clone(),
+        None => return Err(crate::error::Error::ServerError("T3 device database not available".to_string()))
+    };
+
+    let mut mapping: Option<ImportMapping> = None;
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| crate::error::Error::BadRequest(format!("Invalid multipart upload: {}", e)))?
+    {
+        match field.name() {
+            Some("mapping") => {
+                let text = field
+                    .text()
+                    .await
+                    .map_err(|e| crate::error::Error::BadRequest(format!("Failed to read mapping: {}", e)))?;
+                mapping = Some(serde_json::from_str(&text).map_err(|e| {
+                    crate::error::Error::ValidationError(format!("Invalid import mapping: {}", e))
+                })?);
+            }
+            Some("file") => {
+                let mapping = mapping.ok_or_else(|| {
+                    crate::error::Error::BadRequest("The mapping part must come before the file".to_string())
+                })?;
+                let reader = tokio_util::io::StreamReader::new(field.map_err(std::io::Error::other));
+                let result = import_csv(&db, &mapping, reader, &ImportOptions::default()).await;
+
+                let (level, message, details) = match &result {
+                    Ok(summary) => (
+                        "info",
+                        "Trendlog import completed",
+                        format!(
+                            "records={}, imported={}, duplicates={}, rejected={}, points={}, partitions_created={}",
+                            summary.records,
+                            summary.imported,
+                            summary.duplicates,
+                            summary.rejected,
+                            summary.points.len(),
+                            summary.partitions_created.len()
+                        ),
+                    ),
+                    Err(e) => ("error", "Trendlog import failed", e.to_string()),
+                };
+                crate::logging::service::emit_app_log(&db, level, "TRENDLOG", Some("trendlog_import"), None, message, Some(&details))
+                    .await;
+                return result.map(Json);
+            }
+            _ => {}
+        }
+    }
+    Err(crate::error::Error::BadRequest("No file part in the upload".to_string()))
+}
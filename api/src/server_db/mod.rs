@@ -29,6 +29,11 @@ pub mod db_backend_routes;
 pub mod mssql_queries;
 pub mod mssql_generic_crud;
 pub mod mssql_trendlog_service;
+pub mod trendlog_aggregation;
+pub mod trendlog_detail;
+pub mod trendlog_export;
+pub mod trendlog_import;
+pub mod trendlog_rollup;
 pub mod network_scan;
 pub mod registry_service;
 
@@ -100,6 +105,8 @@ pub struct SmartQueryPlan {
     pub partition_files: Vec<PartitionMetadata>,
     pub estimated_records: i64,
     pub cache_hit: bool,
+    /// Bucket width the rows should be aggregated to, for aggregated plans.
+    pub bucket_seconds: Option<i64>,
 }
 
 /// Application Configuration Service
@@ -3069,19 +3076,27 @@ impl DatabaseConfigService {
     }
 
     /// Smart query engine that automatically determines which partitions to query
-    /// based on requested date range and timebase
+    /// based on requested date range and timebase.
+    ///
+    /// With `aggregation`, rows are bucketed server-side, so every overlapping
+    /// partition (archived ones included) is planned instead of a sample of
+    /// them, and the plan carries the bucket width to aggregate to.
     pub async fn query_historical_data_smart(
         db: &DatabaseConnection,
         timebase_minutes: i32, // 5, 1440 (1day), 5760 (4days), etc.
         start_date: DateTime<Utc>,
         end_date: DateTime<Utc>,
         runtime_config: &PartitioningRuntimeConfig,
+        aggregation: Option<&trendlog_aggregation::AggregationRequest>,
     ) -> Result<SmartQueryPlan> {
         let mut query_plan = SmartQueryPlan {
             query_main_db: false,
             partition_files: Vec::new(),
             estimated_records: 0,
             cache_hit: false,
+            bucket_seconds: aggregation
+                .filter(|a| a.mode == trendlog_aggregation::AggregationMode::Buckets)
+                .map(|a| a.bucket_seconds((end_date - start_date).num_seconds())),
         };
 
         crate::logging::service::emit_app_log(
@@ -3119,7 +3134,8 @@ impl DatabaseConfigService {
         }
 
         // Determine which partitions contain needed historical data
-        if runtime_config.enable_caching {
+        // (the cache only holds live partitions, so aggregated plans scan the DB)
+        if runtime_config.enable_caching && aggregation.is_none() {
             if let Some(cache) = PARTITION_CACHE.get() {
                 if let Ok(cache_guard) = cache.read() {
                     query_plan.cache_hit = true;
@@ -3148,11 +3164,12 @@ impl DatabaseConfigService {
             )
             .await;
 
-            let partitions = database_files::Entity::find()
-                .filter(database_files::Column::IsArchived.eq(false))
-                .filter(database_files::Column::IsActive.eq(false))
-                .all(db)
-                .await?;
+            let mut partitions_query = database_files::Entity::find()
+                .filter(database_files::Column::IsActive.eq(false));
+            if aggregation.is_none() {
+                partitions_query = partitions_query.filter(database_files::Column::IsArchived.eq(false));
+            }
+            let partitions = partitions_query.all(db).await?;
 
             for partition in partitions {
                 if let (Some(partition_id), Some(start), Some(end)) =
@@ -3178,8 +3195,11 @@ impl DatabaseConfigService {
             }
         }
 
-        // Optimize query plan based on timebase
-        Self::optimize_query_plan(&mut query_plan, timebase_minutes);
+        // Optimize query plan based on timebase (sampling partitions would drop
+        // whole periods from aggregated buckets)
+        if aggregation.is_none() {
+            Self::optimize_query_plan(&mut query_plan, timebase_minutes);
+        }
 
         crate::logging::service::emit_app_log(
             db,
@@ -3189,11 +3209,12 @@ impl DatabaseConfigService {
             None,
             "Smart query plan ready",
             Some(&format!(
-                "query_main_db={}, partitions={}, estimated_records={}, cache_hit={}",
+                "query_main_db={}, partitions={}, estimated_records={}, cache_hit={}, bucket_seconds={:?}",
                 query_plan.query_main_db,
                 query_plan.partition_files.len(),
                 query_plan.estimated_records,
-                query_plan.cache_hit
+                query_plan.cache_hit,
+                query_plan.bucket_seconds
             )),
         )
         .await;
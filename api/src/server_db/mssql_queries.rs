@@ -7,6 +7,7 @@
 //! - PROGRAMS, SCHEDULES: upsert (MERGE)
 //! - TRENDLOG_DATA: get-or-create parent
 //! - TRENDLOG_DATA_DETAIL: insert detail row
+//! - TRENDLOG_ROLLUP_HOURLY, TRENDLOG_ROLLUP_DAILY: fold in / rebuild / prune / query
 //! - TRENDLOGS: upsert metadata
 //! - TRENDLOG_INPUTS: upsert input mapping
 //! - DATA_SYNC_METADATA: insert sync record
@@ -17,6 +18,9 @@ use bb8::Pool;
 use bb8_tiberius::ConnectionManager;
 use serde_json::{json, Value};
 
+use super::trendlog_detail;
+use super::trendlog_rollup::{Resolution, RollupQuery, RollupRow, DEFAULT_QUERY_LIMIT};
+
 /// Type alias for a bb8-managed tiberius connection pool.
 pub type MssqlPool = Pool<ConnectionManager>;
 
@@ -517,7 +521,10 @@ pub async fn get_or_create_trendlog_parent(
 // TRENDLOG_DATA_DETAIL — insert detail row
 // ============================================================================
 
-/// Insert a single trendlog detail (value + timestamp).
+/// Insert a single trendlog detail (value + timestamp), with the numeric
+/// value and Unix-seconds time derived from them, and fold it into the
+/// hourly/daily rollups. Rollups are best-effort: a failed MERGE is logged,
+/// not returned, and `trendlog_rollup::rebuild` repairs the bucket.
 pub async fn insert_trendlog_detail(
     pool: &MssqlPool,
     parent_id: i32,
@@ -525,18 +532,188 @@ pub async fn insert_trendlog_detail(
     logging_time_fmt: &str,
 ) -> Result<(), String> {
     let mut conn = pool.get().await.map_err(|e| format!("Pool error: {}", e))?;
+    let numeric_value = trendlog_detail::numeric_value(value);
+    let logging_time = trendlog_detail::epoch_from_fmt(logging_time_fmt);
 
     conn.execute(
-        "INSERT INTO TRENDLOG_DATA_DETAIL (ParentId, Value, LoggingTime_Fmt) \
-         VALUES (@P1, @P2, @P3)",
-        &[&parent_id, &value, &logging_time_fmt],
+        "INSERT INTO TRENDLOG_DATA_DETAIL (ParentId, Value, LoggingTime_Fmt, NumericValue, LoggingTime) \
+         VALUES (@P1, @P2, @P3, @P4, @P5)",
+        &[&parent_id, &value, &logging_time_fmt, &numeric_value, &logging_time],
     )
     .await
     .map_err(|e| format!("TRENDLOG_DATA_DETAIL INSERT failed: {}", e))?;
+    drop(conn);
+
+    if let Some(v) = numeric_value {
+        if let Err(e) = upsert_trendlog_rollups(pool, parent_id, v, logging_time_fmt).await {
+            tracing::warn!("Trendlog rollup update failed - Parent: {}, Time: {}, Error: {}", parent_id, logging_time_fmt, e);
+        }
+    }
 
     Ok(())
 }
 
+// ============================================================================
+// TRENDLOG_ROLLUP_HOURLY / TRENDLOG_ROLLUP_DAILY — summaries of detail rows
+// ============================================================================
+
+/// Fold one detail sample into the hourly and daily rollups (MERGE per table).
+pub async fn upsert_trendlog_rollups(
+    pool: &MssqlPool,
+    parent_id: i32,
+    value: f64,
+    logging_time_fmt: &str,
+) -> Result<(), String> {
+    if !value.is_finite() {
+        return Ok(());
+    }
+    let mut conn = pool.get().await.map_err(|e| format!("Pool error: {}", e))?;
+
+    for resolution in Resolution::ALL {
+        let Some(bucket) = resolution.bucket_start(logging_time_fmt) else {
+            return Ok(());
+        };
+        let table = resolution.table();
+        let sql = format!(
+            "MERGE INTO [{table}] WITH (HOLDLOCK) AS target \
+             USING (SELECT @P1 AS ParentId, @P2 AS BucketStart, @P3 AS v, @P4 AS t) AS source \
+             ON target.ParentId = source.ParentId AND target.BucketStart = source.BucketStart \
+             WHEN MATCHED THEN UPDATE SET \
+               MinValue = CASE WHEN source.v < target.MinValue THEN source.v ELSE target.MinValue END, \
+               MaxValue = CASE WHEN source.v > target.MaxValue THEN source.v ELSE target.MaxValue END, \
+               SumValue = target.SumValue + source.v, \
+               SampleCount = target.SampleCount + 1, \
+               LastValue = CASE WHEN source.t >= target.LastTime THEN source.v ELSE target.LastValue END, \
+               LastTime = CASE WHEN source.t >= target.LastTime THEN source.t ELSE target.LastTime END \
+             WHEN NOT MATCHED THEN INSERT \
+               (ParentId, BucketStart, MinValue, MaxValue, SumValue, SampleCount, LastValue, LastTime) \
+             VALUES (source.ParentId, source.BucketStart, source.v, source.v, source.v, 1, source.v, source.t);"
+        );
+        conn.execute(sql.as_str(), &[&parent_id, &bucket.as_str(), &value, &logging_time_fmt])
+            .await
+            .map_err(|e| format!("{} MERGE failed: {}", table, e))?;
+    }
+
+    Ok(())
+}
+
+/// Recompute rollups from TRENDLOG_DATA_DETAIL rows with
+/// `start <= LoggingTime_Fmt < end`, replacing the buckets found.
+/// Returns (hourly, daily) buckets written.
+pub async fn rebuild_trendlog_rollups(
+    pool: &MssqlPool,
+    start: &str,
+    end: &str,
+) -> Result<(u64, u64), String> {
+    let mut conn = pool.get().await.map_err(|e| format!("Pool error: {}", e))?;
+    let mut written = [0u64; 2];
+
+    for (slot, resolution) in Resolution::ALL.into_iter().enumerate() {
+        let table = resolution.table();
+        let bucket = match resolution {
+            Resolution::Hourly => "LEFT(LoggingTime_Fmt, 13) + ':00:00'",
+            Resolution::Daily => "LEFT(LoggingTime_Fmt, 10) + ' 00:00:00'",
+        };
+        let sql = format!(
+            "MERGE INTO [{table}] WITH (HOLDLOCK) AS target \
+             USING ( \
+               SELECT ParentId, BucketStart, MIN(v) AS MinValue, MAX(v) AS MaxValue, SUM(v) AS SumValue, \
+                      COUNT(*) AS SampleCount, MAX(CASE WHEN rn = 1 THEN v END) AS LastValue, \
+                      MAX(LoggingTime_Fmt) AS LastTime \
+               FROM ( \
+                 SELECT ParentId, LoggingTime_Fmt, TRY_CAST(Value AS FLOAT) AS v, {bucket} AS BucketStart, \
+                        ROW_NUMBER() OVER (PARTITION BY ParentId, {bucket} ORDER BY LoggingTime_Fmt DESC) AS rn \
+                 FROM TRENDLOG_DATA_DETAIL \
+                 WHERE LoggingTime_Fmt >= @P1 AND LoggingTime_Fmt < @P2 AND TRY_CAST(Value AS FLOAT) IS NOT NULL \
+               ) d \
+               GROUP BY ParentId, BucketStart \
+             ) AS source \
+             ON target.ParentId = source.ParentId AND target.BucketStart = source.BucketStart \
+             WHEN MATCHED THEN UPDATE SET \
+               MinValue = source.MinValue, MaxValue = source.MaxValue, SumValue = source.SumValue, \
+               SampleCount = source.SampleCount, LastValue = source.LastValue, LastTime = source.LastTime \
+             WHEN NOT MATCHED THEN INSERT \
+               (ParentId, BucketStart, MinValue, MaxValue, SumValue, SampleCount, LastValue, LastTime) \
+             VALUES (source.ParentId, source.BucketStart, source.MinValue, source.MaxValue, source.SumValue, \
+                     source.SampleCount, source.LastValue, source.LastTime);"
+        );
+        let result = conn
+            .execute(sql.as_str(), &[&start, &end])
+            .await
+            .map_err(|e| format!("{} rebuild failed: {}", table, e))?;
+        written[slot] = result.rows_affected().iter().sum();
+    }
+
+    Ok((written[0], written[1]))
+}
+
+/// Delete rollup buckets that start before `cutoff`. Returns rows deleted.
+pub async fn prune_trendlog_rollups(
+    pool: &MssqlPool,
+    resolution: Resolution,
+    cutoff: &str,
+) -> Result<u64, String> {
+    let mut conn = pool.get().await.map_err(|e| format!("Pool error: {}", e))?;
+    let table = resolution.table();
+
+    let result = conn
+        .execute(format!("DELETE FROM [{table}] WHERE BucketStart < @P1").as_str(), &[&cutoff])
+        .await
+        .map_err(|e| format!("{} prune failed: {}", table, e))?;
+
+    Ok(result.rows_affected().iter().sum())
+}
+
+/// Rollup buckets joined with their point, oldest first.
+pub async fn query_trendlog_rollups(pool: &MssqlPool, q: &RollupQuery) -> Result<Vec<RollupRow>, String> {
+    let mut conn = pool.get().await.map_err(|e| format!("Pool error: {}", e))?;
+
+    let start = q.start.as_ref().map(|s| s.replace('T', " "));
+    let end = q.end.as_ref().map(|s| s.replace('T', " "));
+    let sql = format!(
+        "SELECT TOP ({limit}) td.SerialNumber, td.PanelId, td.PointId, td.PointIndex, td.PointType, td.Units, \
+                r.BucketStart, r.MinValue, r.MaxValue, r.SumValue / r.SampleCount AS AvgValue, \
+                r.SampleCount, r.LastValue, r.LastTime \
+         FROM [{table}] r INNER JOIN TRENDLOG_DATA td ON td.id = r.ParentId \
+         WHERE (@P1 IS NULL OR r.BucketStart >= @P1) AND (@P2 IS NULL OR r.BucketStart <= @P2) \
+           AND (@P3 IS NULL OR td.SerialNumber = @P3) AND (@P4 IS NULL OR td.PanelId = @P4) \
+           AND (@P5 IS NULL OR td.PointId = @P5) AND (@P6 IS NULL OR td.PointType = @P6) \
+         ORDER BY r.BucketStart ASC, td.PointType, td.PointIndex",
+        limit = q.limit.unwrap_or(DEFAULT_QUERY_LIMIT),
+        table = q.resolution.table(),
+    );
+
+    let rows = conn
+        .query(
+            sql.as_str(),
+            &[&start, &end, &q.serial_number, &q.panel_id, &q.point_id, &q.point_type],
+        )
+        .await
+        .map_err(|e| format!("Rollup query failed: {}", e))?
+        .into_first_result()
+        .await
+        .map_err(|e| format!("Rollup row fetch failed: {}", e))?;
+
+    Ok(rows
+        .iter()
+        .map(|row| RollupRow {
+            serial_number: row.get("SerialNumber").unwrap_or(0),
+            panel_id: row.get("PanelId").unwrap_or(0),
+            point_id: row.get::<&str, _>("PointId").unwrap_or("").to_string(),
+            point_index: row.get("PointIndex").unwrap_or(0),
+            point_type: row.get::<&str, _>("PointType").unwrap_or("").to_string(),
+            units: row.get::<&str, _>("Units").map(str::to_string),
+            bucket_start: row.get::<&str, _>("BucketStart").unwrap_or("").to_string(),
+            min_value: row.get("MinValue").unwrap_or(0.0),
+            max_value: row.get("MaxValue").unwrap_or(0.0),
+            avg_value: row.get("AvgValue").unwrap_or(0.0),
+            sample_count: row.get::<i32, _>("SampleCount").unwrap_or(0) as i64,
+            last_value: row.get("LastValue").unwrap_or(0.0),
+            last_time: row.get::<&str, _>("LastTime").unwrap_or("").to_string(),
+        })
+        .collect())
+}
+
 // ============================================================================
 // TRENDLOGS — upsert metadata
 // ============================================================================
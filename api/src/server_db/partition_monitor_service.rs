@@ -27,7 +27,8 @@ fn emit_partition_log_bg(level: &'static str, category: &'static str, message: S
     });
 }
 
-/// Start background partition monitor service (checks every hour)
+/// Start background partition monitor service (checks every hour, and
+/// prunes trendlog rollups past their own retention)
 pub async fn start_partition_monitor_service() -> Result<()> {
     // Spawn hourly partition migration task
     tokio::spawn(async {
@@ -60,6 +61,21 @@ pub async fn start_partition_monitor_service() -> Result<()> {
                     logger.error(&format!("[FAIL] Partition check failed: {}", e));
                 }
             }
+
+            // Rollup retention is independent of partition retention
+            match crate::server_db::trendlog_rollup::enforce_retention().await {
+                Ok(pruned) => {
+                    if pruned.hourly_deleted + pruned.daily_deleted > 0 {
+                        logger.info(&format!(
+                            "[OK] Pruned rollups past retention: {} hourly, {} daily",
+                            pruned.hourly_deleted, pruned.daily_deleted
+                        ));
+                    }
+                }
+                Err(e) => {
+                    logger.error(&format!("[FAIL] Rollup retention failed: {}", e));
+                }
+            }
         }
     });
 
@@ -368,7 +384,7 @@ fn generate_missing_periods(
 }
 
 /// Generate partition identifier based on strategy and date
-fn generate_partition_identifier(
+pub(crate) fn generate_partition_identifier(
     config: &database_partition_config::DatabasePartitionConfig,
     date: &NaiveDate,
 ) -> String {
@@ -387,7 +403,7 @@ fn generate_partition_identifier(
 }
 
 /// Calculate exact start and end datetime for a period
-fn calculate_period_boundaries(
+pub(crate) fn calculate_period_boundaries(
     config: &database_partition_config::DatabasePartitionConfig,
     period_date: NaiveDate,
 ) -> (chrono::NaiveDateTime, chrono::NaiveDateTime) {
@@ -7,6 +7,8 @@ use sea_orm::*;
 use chrono::{NaiveDateTime, NaiveDate, Utc, Datelike};
 use serde::{Deserialize, Serialize};
 use std::path::Path;
+use super::trendlog_aggregation::{self, AggregationRequest, AggregationSummary, Series};
+use super::trendlog_detail::DetailLayout;
 
 async fn emit_query_log(db: &DatabaseConnection, level: &str, message: &str) {
     crate::logging::service::emit_app_log(
@@ -35,6 +37,26 @@ pub struct TrendlogDataRecord {
     pub units: Option<String>,
 }
 
+/// One aggregated bucket (or LTTB sample) of a point, from
+/// [`aggregate_trendlog_records`]. `value` keeps the raw stored scale.
+#[derive(Debug, Clone, Serialize)]
+pub struct AggregatedTrendlogRecord {
+    pub serial_number: i32,
+    pub panel_id: i32,
+    pub point_id: String,
+    pub point_index: i32,
+    pub point_type: String,
+    /// Bucket start (or sample time), formatted like `LoggingTime_Fmt`.
+    pub logging_time_fmt: String,
+    pub value: f64,
+    pub count: usize,
+    #[serde(skip_serializing_if = "serde_json::Value::is_null")]
+    pub aggregates: serde_json::Value,
+    pub digital_analog: Option<String>,
+    pub range_field: Option<String>,
+    pub units: Option<String>,
+}
+
 #[derive(Debug, Clone)]
 pub struct TrendlogFilters {
     pub serial_number: Option<i32>,
@@ -127,6 +149,16 @@ pub async fn query_trendlog_data(
             emit_query_log(&db, "info", &format!("Got {} records from main DB", results.len())).await;
             all_results.extend(results);
         } else {
+            // Archived partitions can outlive their files; skip rather than fail the whole range
+            if !Path::new(&partition_info.file_path).exists() {
+                emit_query_log(
+                    &db,
+                    "warn",
+                    &format!("Skipping partition {}: file not found at {}", partition_info.partition_id, partition_info.file_path),
+                )
+                .await;
+                continue;
+            }
             emit_query_log(
                 &db,
                 "info",
@@ -170,6 +202,64 @@ pub async fn query_trendlog_data(
     Ok(all_results)
 }
 
+/// Bucket or LTTB-downsample trendlog records per point (serial, panel,
+/// point type and id). Works on the merged output of
+/// [`query_trendlog_data`], so archived partitions are covered too.
+pub fn aggregate_trendlog_records(
+    records: &[TrendlogDataRecord],
+    aggregation: &AggregationRequest,
+    start_date: NaiveDateTime,
+    end_date: NaiveDateTime,
+) -> (Vec<AggregatedTrendlogRecord>, AggregationSummary) {
+    let mut index: std::collections::HashMap<(i32, i32, &str, &str), usize> = std::collections::HashMap::new();
+    let mut series: Vec<Series<&TrendlogDataRecord>> = Vec::new();
+    for record in records {
+        let (Some(t), Ok(v)) = (
+            trendlog_aggregation::parse_time(&record.logging_time_fmt),
+            record.value.trim().parse::<f64>(),
+        ) else {
+            continue;
+        };
+        let key = (record.serial_number, record.panel_id, record.point_type.as_str(), record.point_id.as_str());
+        let slot = *index.entry(key).or_insert_with(|| {
+            series.push(Series {
+                meta: record,
+                is_analog: record.digital_analog.as_deref().map(|da| da == "1").unwrap_or(true),
+                samples: Vec::new(),
+            });
+            series.len() - 1
+        });
+        series[slot].samples.push((t, v));
+    }
+
+    let range = Some((start_date.and_utc().timestamp(), end_date.and_utc().timestamp()));
+    let (points, summary) = trendlog_aggregation::aggregate(series, aggregation, range);
+    let mut out: Vec<AggregatedTrendlogRecord> = points
+        .into_iter()
+        .map(|(meta, p)| AggregatedTrendlogRecord {
+            serial_number: meta.serial_number,
+            panel_id: meta.panel_id,
+            point_id: meta.point_id.clone(),
+            point_index: meta.point_index,
+            point_type: meta.point_type.clone(),
+            logging_time_fmt: trendlog_aggregation::format_time(p.time),
+            value: p.value,
+            count: p.count,
+            aggregates: if p.aggregates.is_empty() {
+                serde_json::Value::Null
+            } else {
+                trendlog_aggregation::aggregates_json(&p.aggregates)
+            },
+            digital_analog: meta.digital_analog.clone(),
+            range_field: meta.range_field.clone(),
+            units: meta.units.clone(),
+        })
+        .collect();
+    // Same order as the raw query
+    out.sort_by(|a, b| a.logging_time_fmt.cmp(&b.logging_time_fmt));
+    (out, summary)
+}
+
 /// Identify which partitions contain data for the date range
 async fn identify_required_partitions(
     db: &DatabaseConnection,
@@ -272,7 +362,8 @@ async fn query_main_database(
     filters: &TrendlogFilters,
     log_db: &DatabaseConnection,
 ) -> Result<Vec<TrendlogDataRecord>> {
-    let query_sql = build_trendlog_query("main", start_date, end_date, filters);
+    let layout = DetailLayout::detect(db, "main").await?;
+    let query_sql = build_trendlog_query("main", layout, start_date, end_date, filters);
 
     emit_query_log(log_db, "info", "Executing query on main database...").await;
 
@@ -311,14 +402,15 @@ async fn query_partition_file(
 
     emit_query_log(log_db, "info", "Partition attached successfully").await;
 
-    // Query with filters
-    let query_sql = build_trendlog_query("partition_db", start_date, end_date, filters);
-    emit_query_log(log_db, "info", "Executing query on partition...").await;
-
-    let results = db.query_all(Statement::from_string(
-        DatabaseBackend::Sqlite,
-        query_sql
-    )).await?;
+    // Query with filters (partitions from older versions lack the numeric columns)
+    let results = match DetailLayout::detect(&db, "partition_db").await {
+        Ok(layout) => {
+            let query_sql = build_trendlog_query("partition_db", layout, start_date, end_date, filters);
+            emit_query_log(log_db, "info", &format!("Executing query on partition ({:?} layout)...", layout)).await;
+            db.query_all(Statement::from_string(DatabaseBackend::Sqlite, query_sql)).await
+        }
+        Err(e) => Err(e),
+    };
 
     // Detach partition
     emit_query_log(log_db, "info", "Detaching partition").await;
@@ -328,20 +420,18 @@ async fn query_partition_file(
     )).await?;
 
     // Parse results
-    parse_query_results(results)
+    parse_query_results(results?)
 }
 
 /// Build SQL query for trendlog data
 fn build_trendlog_query(
     db_alias: &str,
+    layout: DetailLayout,
     start_date: NaiveDateTime,
     end_date: NaiveDateTime,
     filters: &TrendlogFilters,
 ) -> String {
-    let mut where_clauses = vec![
-        format!("datetime(tdd.LoggingTime_Fmt) >= datetime('{}')", start_date.format("%Y-%m-%d %H:%M:%S")),
-        format!("datetime(tdd.LoggingTime_Fmt) <= datetime('{}')", end_date.format("%Y-%m-%d %H:%M:%S")),
-    ];
+    let mut where_clauses = vec![layout.time_range_sql("tdd", Some(start_date), Some(end_date))];
 
     if let Some(serial) = filters.serial_number {
         where_clauses.push(format!("td.SerialNumber = {}", serial));
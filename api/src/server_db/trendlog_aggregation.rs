@@ -0,0 +1,418 @@
+//! Trendlog aggregation — server-side downsampling for history queries.
+//!
+//! Charting a long range of raw TRENDLOG_DATA_DETAIL rows ships far more
+//! points than a chart can draw. History APIs accept an optional
+//! [`AggregationRequest`] and return one of:
+//!
+//! - **Buckets**: fixed time buckets per point with avg/min/max/first/last/
+//!   count, a time-weighted average (each sample holds until the next one —
+//!   right for analog values logged at uneven intervals) and the on-time
+//!   fraction (share of the bucket a digital point was non-zero). The bucket
+//!   width is explicit or picked from a target point count. Buckets without
+//!   samples are left out, so gaps stay visible.
+//! - **LTTB**: Largest-Triangle-Three-Buckets selection of raw samples, which
+//!   keeps the visual shape (peaks, steps) for display.
+//!
+//! The functions here are pure; callers group their rows into [`Series`] and
+//! map the results back to their own response shape.
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Points per series when the request gives neither bucket nor target.
+pub const DEFAULT_TARGET_POINTS: usize = 1000;
+/// Row cap for the raw query behind an aggregated response (raw queries
+/// keep their own, much lower, default limit). Rows are streamed into the
+/// aggregator; a query that hits the cap reports `truncated`.
+pub const AGGREGATION_ROW_LIMIT: u64 = 5_000_000;
+
+/// Bucket widths picked from, so auto buckets land on round times.
+const NICE_BUCKETS: [i64; 16] = [
+    1, 5, 10, 15, 30, 60, 120, 300, 600, 900, 1800, 3600, 7200, 21_600, 43_200, 86_400,
+];
+
+/// Per-bucket aggregate function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AggFn {
+    Avg,
+    Min,
+    Max,
+    First,
+    Last,
+    Count,
+    /// Average weighted by how long each value held.
+    #[serde(alias = "twa")]
+    TimeWeightedAvg,
+    /// Share of the bucket (0–1) the value was non-zero.
+    OnFraction,
+}
+
+impl AggFn {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AggFn::Avg => "avg",
+            AggFn::Min => "min",
+            AggFn::Max => "max",
+            AggFn::First => "first",
+            AggFn::Last => "last",
+            AggFn::Count => "count",
+            AggFn::TimeWeightedAvg => "time_weighted_avg",
+            AggFn::OnFraction => "on_fraction",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AggregationMode {
+    #[default]
+    Buckets,
+    Lttb,
+}
+
+/// Aggregation options accepted by the trendlog history APIs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AggregationRequest {
+    #[serde(default)]
+    pub mode: AggregationMode,
+    /// Bucket width in seconds; picked from `target_points` when absent.
+    pub bucket_seconds: Option<i64>,
+    /// Approximate points per series (bucket count, or LTTB threshold).
+    pub target_points: Option<usize>,
+    /// Bucket functions; defaults to avg/min/max for analog points and
+    /// last/on_fraction for digital ones. The first one fills `value`.
+    #[serde(default)]
+    pub functions: Vec<AggFn>,
+}
+
+impl AggregationRequest {
+    pub fn target_points(&self) -> usize {
+        self.target_points.filter(|&n| n > 0).unwrap_or(DEFAULT_TARGET_POINTS)
+    }
+
+    /// Bucket width for a query spanning `span_seconds`.
+    pub fn bucket_seconds(&self, span_seconds: i64) -> i64 {
+        if let Some(b) = self.bucket_seconds.filter(|&b| b > 0) {
+            return b;
+        }
+        let wanted = (span_seconds.max(1) + self.target_points() as i64 - 1) / self.target_points() as i64;
+        NICE_BUCKETS
+            .iter()
+            .copied()
+            .find(|&b| b >= wanted)
+            // Beyond a day: whole days
+            .unwrap_or_else(|| (wanted + 86_399) / 86_400 * 86_400)
+    }
+
+    fn functions_for(&self, is_analog: bool) -> Vec<AggFn> {
+        if !self.functions.is_empty() {
+            return self.functions.clone();
+        }
+        if is_analog {
+            vec![AggFn::Avg, AggFn::Min, AggFn::Max]
+        } else {
+            vec![AggFn::Last, AggFn::OnFraction]
+        }
+    }
+}
+
+/// One point's samples, `(epoch seconds, value)`, plus caller metadata.
+#[derive(Debug, Clone)]
+pub struct Series<M> {
+    pub meta: M,
+    pub is_analog: bool,
+    pub samples: Vec<(i64, f64)>,
+}
+
+/// One output point: a bucket, or a sample kept by LTTB.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AggregatedPoint {
+    /// Bucket start, or the sample time for LTTB.
+    pub time: i64,
+    /// Value of the first requested function (the raw value for LTTB).
+    pub value: f64,
+    /// Raw samples in the bucket (1 for LTTB).
+    pub count: usize,
+    pub aggregates: BTreeMap<AggFn, f64>,
+}
+
+/// What an aggregation did, for the response.
+#[derive(Debug, Clone, Serialize)]
+pub struct AggregationSummary {
+    pub mode: AggregationMode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bucket_seconds: Option<i64>,
+    pub raw_points: usize,
+    pub points: usize,
+    pub series: usize,
+    /// The raw query hit its row cap, so the oldest part of the range is
+    /// missing from the buckets.
+    pub truncated: bool,
+}
+
+/// Parse a `LoggingTime_Fmt` value ("YYYY-MM-DD HH:MM:SS", or with a `T`).
+pub fn parse_time(s: &str) -> Option<i64> {
+    let s = s.trim();
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S"))
+        .ok()
+        .map(|t| t.and_utc().timestamp())
+}
+
+/// Format epoch seconds the way `LoggingTime_Fmt` is stored.
+pub fn format_time(epoch: i64) -> String {
+    DateTime::from_timestamp(epoch, 0)
+        .map(|t| t.naive_utc().format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
+
+/// Aggregate every series. `range` is the queried `(start, end)` in epoch
+/// seconds; without it the auto bucket width comes from the data itself.
+/// Results are grouped per series, oldest first.
+pub fn aggregate<M: Clone>(
+    mut series: Vec<Series<M>>,
+    req: &AggregationRequest,
+    range: Option<(i64, i64)>,
+) -> (Vec<(M, AggregatedPoint)>, AggregationSummary) {
+    for s in &mut series {
+        s.samples.sort_by_key(|&(t, _)| t);
+    }
+    let raw_points = series.iter().map(|s| s.samples.len()).sum();
+    let span = range.or_else(|| {
+        let first = series.iter().filter_map(|s| s.samples.first()).map(|p| p.0).min()?;
+        let last = series.iter().filter_map(|s| s.samples.last()).map(|p| p.0).max()?;
+        Some((first, last))
+    });
+    let bucket = match req.mode {
+        AggregationMode::Buckets => Some(req.bucket_seconds(span.map(|(a, b)| b - a).unwrap_or(0))),
+        AggregationMode::Lttb => None,
+    };
+
+    let mut out = Vec::new();
+    for s in &series {
+        let points = match bucket {
+            Some(b) => buckets(&s.samples, b, &req.functions_for(s.is_analog)),
+            None => lttb(&s.samples, req.target_points())
+                .into_iter()
+                .map(|(time, value)| AggregatedPoint { time, value, count: 1, aggregates: BTreeMap::new() })
+                .collect(),
+        };
+        out.extend(points.into_iter().map(|p| (s.meta.clone(), p)));
+    }
+    let summary = AggregationSummary {
+        mode: req.mode,
+        bucket_seconds: bucket,
+        raw_points,
+        points: out.len(),
+        series: series.len(),
+        truncated: false,
+    };
+    (out, summary)
+}
+
+/// Fixed-width buckets over time-sorted samples. For the time-weighted
+/// functions a sample holds until the next one (the last sample until its
+/// bucket ends), and the value before a bucket carries into its start.
+pub fn buckets(samples: &[(i64, f64)], width: i64, functions: &[AggFn]) -> Vec<AggregatedPoint> {
+    let width = width.max(1);
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < samples.len() {
+        let start = samples[i].0.div_euclid(width) * width;
+        let end = start + width;
+        let j = i + samples[i..].iter().take_while(|&&(t, _)| t < end).count();
+        let inside = &samples[i..j];
+
+        // Step-interpolated durations: (value, seconds held inside the bucket)
+        let mut held: Vec<(f64, i64)> = Vec::with_capacity(inside.len() + 1);
+        if i > 0 {
+            held.push((samples[i - 1].1, inside[0].0 - start));
+        }
+        for k in i..j {
+            let until = samples.get(k + 1).map(|&(t, _)| t.min(end)).unwrap_or(end);
+            held.push((samples[k].1, until - samples[k].0));
+        }
+        let total: i64 = held.iter().map(|&(_, d)| d).sum();
+
+        let values = inside.iter().map(|&(_, v)| v);
+        let mut aggregates = BTreeMap::new();
+        for &f in functions {
+            let v = match f {
+                AggFn::Avg => values.clone().sum::<f64>() / inside.len() as f64,
+                AggFn::Min => values.clone().fold(f64::INFINITY, f64::min),
+                AggFn::Max => values.clone().fold(f64::NEG_INFINITY, f64::max),
+                AggFn::First => inside[0].1,
+                AggFn::Last => inside[inside.len() - 1].1,
+                AggFn::Count => inside.len() as f64,
+                AggFn::TimeWeightedAvg if total > 0 => {
+                    held.iter().map(|&(v, d)| v * d as f64).sum::<f64>() / total as f64
+                }
+                AggFn::TimeWeightedAvg => values.clone().sum::<f64>() / inside.len() as f64,
+                AggFn::OnFraction if total > 0 => {
+                    held.iter().filter(|&&(v, _)| v != 0.0).map(|&(_, d)| d).sum::<i64>() as f64 / total as f64
+                }
+                AggFn::OnFraction => {
+                    values.clone().filter(|&v| v != 0.0).count() as f64 / inside.len() as f64
+                }
+            };
+            aggregates.insert(f, v);
+        }
+        let value = functions.first().and_then(|f| aggregates.get(f)).copied().unwrap_or(f64::NAN);
+        out.push(AggregatedPoint { time: start, value, count: inside.len(), aggregates });
+        i = j;
+    }
+    out
+}
+
+/// Largest-Triangle-Three-Buckets: keep `threshold` samples that preserve
+/// the visual shape. The first and last samples are always kept.
+pub fn lttb(samples: &[(i64, f64)], threshold: usize) -> Vec<(i64, f64)> {
+    if threshold >= samples.len() || threshold < 3 {
+        return samples.to_vec();
+    }
+    let mut out = Vec::with_capacity(threshold);
+    out.push(samples[0]);
+    // Buckets between the fixed first and last samples
+    let every = (samples.len() - 2) as f64 / (threshold - 2) as f64;
+    let mut a = 0;
+    for b in 0..threshold - 2 {
+        let start = (b as f64 * every) as usize + 1;
+        let end = (((b + 1) as f64 * every) as usize + 1).min(samples.len() - 1);
+        // Average of the next bucket (or the last sample) is the third vertex
+        let next_start = end;
+        let next_end = (((b + 2) as f64 * every) as usize + 1).min(samples.len());
+        let next = &samples[next_start..next_end.max(next_start + 1)];
+        let avg_t = next.iter().map(|p| p.0 as f64).sum::<f64>() / next.len() as f64;
+        let avg_v = next.iter().map(|p| p.1).sum::<f64>() / next.len() as f64;
+
+        let (at, av) = (samples[a].0 as f64, samples[a].1);
+        let mut best = start;
+        let mut best_area = -1.0;
+        for (k, &(t, v)) in samples.iter().enumerate().take(end).skip(start) {
+            let area = ((at - avg_t) * (v - av) - (at - t as f64) * (avg_v - av)).abs();
+            if area > best_area {
+                best_area = area;
+                best = k;
+            }
+        }
+        out.push(samples[best]);
+        a = best;
+    }
+    out.push(samples[samples.len() - 1]);
+    out
+}
+
+/// `aggregates` as a JSON object keyed by function name.
+pub fn aggregates_json(aggregates: &BTreeMap<AggFn, f64>) -> Value {
+    Value::Object(aggregates.iter().map(|(f, v)| (f.as_str().to_string(), json!(v))).collect())
+}
+
+/// One raw row of a trendlog history query.
+#[derive(Debug, Clone, Copy)]
+pub struct HistoryRow<'a> {
+    pub time: &'a str,
+    pub value: f64,
+    pub point_id: &'a str,
+    pub point_type: &'a str,
+    pub point_index: i32,
+    pub units: Option<&'a str>,
+    pub range: Option<&'a str>,
+    pub is_analog: bool,
+}
+
+impl<'a> HistoryRow<'a> {
+    /// Read a row in the history response shape (`time`, `value`,
+    /// `point_id`, `point_type`, `point_index`, `units`, `range`, `is_analog`).
+    pub fn from_json(row: &'a Value) -> Option<Self> {
+        Some(Self {
+            time: row["time"].as_str()?,
+            value: row["value"].as_f64()?,
+            point_id: row["point_id"].as_str().unwrap_or(""),
+            point_type: row["point_type"].as_str().unwrap_or(""),
+            point_index: row["point_index"].as_i64().unwrap_or(0) as i32,
+            units: row["units"].as_str(),
+            range: row["range"].as_str(),
+            is_analog: row["is_analog"].as_bool().unwrap_or(true),
+        })
+    }
+}
+
+/// Collects history rows one at a time, so a caller can stream a query
+/// into it instead of holding every raw row, then aggregates per point.
+#[derive(Debug, Default)]
+pub struct HistoryAggregator {
+    index: HashMap<(String, String), usize>,
+    series: Vec<Series<Value>>,
+}
+
+impl HistoryAggregator {
+    pub fn push(&mut self, row: HistoryRow<'_>) {
+        let Some(t) = parse_time(row.time) else { return };
+        let key = (row.point_type.to_string(), row.point_id.to_string());
+        let slot = match self.index.get(&key) {
+            Some(&slot) => slot,
+            None => {
+                self.series.push(Series {
+                    meta: json!({
+                        "point_id": row.point_id,
+                        "point_type": row.point_type,
+                        "point_index": row.point_index,
+                        "units": row.units,
+                        "range": row.range,
+                        "is_analog": row.is_analog,
+                    }),
+                    is_analog: row.is_analog,
+                    samples: Vec::new(),
+                });
+                self.index.insert(key, self.series.len() - 1);
+                self.series.len() - 1
+            }
+        };
+        self.series[slot].samples.push((t, row.value));
+    }
+
+    /// Rows in the history response shape, newest first like the raw query,
+    /// with `count` and `aggregates` added per bucket.
+    pub fn finish(self, req: &AggregationRequest, range: Option<(i64, i64)>) -> (Vec<Value>, AggregationSummary) {
+        let (points, summary) = aggregate(self.series, req, range);
+        let mut out: Vec<(i64, Value)> = points
+            .into_iter()
+            .map(|(meta, p)| {
+                let time = format_time(p.time);
+                let mut row = meta;
+                row["time"] = json!(time);
+                row["timestamp"] = json!(time);
+                row["value"] = json!(p.value);
+                row["count"] = json!(p.count);
+                if !p.aggregates.is_empty() {
+                    row["aggregates"] = aggregates_json(&p.aggregates);
+                }
+                (p.time, row)
+            })
+            .collect();
+        out.sort_by_key(|row| std::cmp::Reverse(row.0));
+        (out.into_iter().map(|(_, row)| row).collect(), summary)
+    }
+}
+
+/// Aggregate history rows per point; see [`HistoryAggregator::finish`].
+pub fn aggregate_history_rows<'a>(
+    rows: impl IntoIterator<Item = HistoryRow<'a>>,
+    req: &AggregationRequest,
+    range: Option<(i64, i64)>,
+) -> (Vec<Value>, AggregationSummary) {
+    let mut aggregator = HistoryAggregator::default();
+    for row in rows {
+        aggregator.push(row);
+    }
+    aggregator.finish(req, range)
+}
+
+/// `(start, end)` epoch range from optional query bounds, when both parse.
+pub fn query_range(start: Option<&str>, end: Option<&str>) -> Option<(i64, i64)> {
+    Some((parse_time(start?)?, parse_time(end?)?))
+}
@@ -0,0 +1,172 @@
+//! Numeric storage for TRENDLOG_DATA_DETAIL.
+//!
+//! Detail rows were written as `Value` TEXT plus the local-time string
+//! `LoggingTime_Fmt`, so every reader re-parsed strings and range-scanned
+//! text. Newer databases add `NumericValue` (REAL) and `LoggingTime` (Unix
+//! seconds) with a covering `(ParentId, LoggingTime, NumericValue)` index.
+//! Writers fill both forms; `t3_device::migrate_trendlog_numeric` backfills
+//! existing rows and upgrades old partition files.
+//!
+//! Until that job has run everywhere a reader can meet a partition without
+//! the columns, or rows whose columns are still NULL. [`DetailLayout`] builds
+//! SQL that returns the same rows in every state.
+
+use chrono::{Local, NaiveDateTime, TimeZone};
+use sea_orm::{ConnectionTrait, DbErr, EntityTrait, Set, Statement};
+
+use crate::entity::t3_device::trendlog_data_detail;
+
+/// Format of `LoggingTime_Fmt`.
+pub const FMT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Index covering per-point range reads on the numeric columns.
+pub const COVERING_INDEX: &str = "IDX_TRENDLOG_DETAIL_PARENT_EPOCH";
+
+/// Which columns a database's TRENDLOG_DATA_DETAIL has.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DetailLayout {
+    /// `Value` and `LoggingTime_Fmt` only (partitions from older versions).
+    Legacy,
+    /// Also `NumericValue` and `LoggingTime`; both may be NULL on rows the
+    /// backfill hasn't reached yet.
+    Numeric,
+}
+
+impl DetailLayout {
+    /// Inspect the table in `schema` (`main`, or an ATTACHed alias).
+    pub async fn detect<C: ConnectionTrait>(db: &C, schema: &str) -> Result<Self, DbErr> {
+        let rows = db
+            .query_all(Statement::from_string(
+                db.get_database_backend(),
+                format!("PRAGMA {}.table_info(TRENDLOG_DATA_DETAIL)", schema),
+            ))
+            .await?;
+        let mut has_value = false;
+        let mut has_time = false;
+        for row in rows {
+            match row.try_get::<String>("", "name")?.as_str() {
+                "NumericValue" => has_value = true,
+                "LoggingTime" => has_time = true,
+                _ => {}
+            }
+        }
+        Ok(if has_value && has_time { Self::Numeric } else { Self::Legacy })
+    }
+
+    /// The stored `NumericValue` of `alias` (NULL on legacy layouts). Callers
+    /// that must skip non-numeric values fall back to parsing `Value`.
+    pub fn numeric_sql(self, alias: &str) -> String {
+        match self {
+            Self::Legacy => "NULL".to_string(),
+            Self::Numeric => format!("{}.NumericValue", alias),
+        }
+    }
+
+    /// Numeric value of a detail row aliased `alias` (0 for non-numeric text).
+    pub fn value_sql(self, alias: &str) -> String {
+        match self {
+            Self::Legacy => format!("CAST({a}.Value AS REAL)", a = alias),
+            Self::Numeric => format!("COALESCE({a}.NumericValue, CAST({a}.Value AS REAL))", a = alias),
+        }
+    }
+
+    /// Condition keeping rows of `alias` logged within `[start, end]` (local
+    /// time, either bound optional). Always a single parenthesised term, or
+    /// `1 = 1` when neither bound is given.
+    pub fn time_range_sql(self, alias: &str, start: Option<NaiveDateTime>, end: Option<NaiveDateTime>) -> String {
+        let text = text_range(alias, start, end);
+        if self == Self::Legacy || text.is_empty() {
+            return paren(&text);
+        }
+        let mut epoch = Vec::new();
+        if let Some(s) = start {
+            epoch.push(format!("{}.LoggingTime >= {}", alias, local_epoch(s)));
+        }
+        if let Some(e) = end {
+            epoch.push(format!("{}.LoggingTime <= {}", alias, local_epoch(e)));
+        }
+        // Rows the backfill hasn't reached still have only the text column
+        format!(
+            "(({}) OR ({a}.LoggingTime IS NULL AND {}))",
+            epoch.join(" AND "),
+            text.join(" AND "),
+            a = alias
+        )
+    }
+}
+
+fn text_range(alias: &str, start: Option<NaiveDateTime>, end: Option<NaiveDateTime>) -> Vec<String> {
+    let mut terms = Vec::new();
+    if let Some(s) = start {
+        terms.push(format!("{}.LoggingTime_Fmt >= '{}'", alias, s.format(FMT)));
+    }
+    if let Some(e) = end {
+        terms.push(format!("{}.LoggingTime_Fmt <= '{}'", alias, e.format(FMT)));
+    }
+    terms
+}
+
+fn paren(terms: &[String]) -> String {
+    if terms.is_empty() {
+        "1 = 1".to_string()
+    } else {
+        format!("({})", terms.join(" AND "))
+    }
+}
+
+/// Parse a `LoggingTime_Fmt` value ("YYYY-MM-DD HH:MM:SS", or with a `T`).
+pub fn parse_fmt(s: &str) -> Option<NaiveDateTime> {
+    let s = s.trim();
+    NaiveDateTime::parse_from_str(s, FMT)
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S"))
+        .ok()
+}
+
+/// Unix seconds of a local wall-clock time. Times repeated when clocks go
+/// back take the first occurrence; times skipped when they go forward take
+/// the instant an hour later.
+pub fn local_epoch(t: NaiveDateTime) -> i64 {
+    Local
+        .from_local_datetime(&t)
+        .earliest()
+        .or_else(|| Local.from_local_datetime(&(t + chrono::Duration::hours(1))).earliest())
+        .map(|dt| dt.timestamp())
+        .unwrap_or_else(|| t.and_utc().timestamp())
+}
+
+/// `LoggingTime` for a `LoggingTime_Fmt` value.
+pub fn epoch_from_fmt(logging_time_fmt: &str) -> Option<i64> {
+    parse_fmt(logging_time_fmt).map(local_epoch)
+}
+
+/// `NumericValue` for a stored `Value`; `None` when it isn't a finite number.
+pub fn numeric_value(value: &str) -> Option<f64> {
+    value.trim().parse::<f64>().ok().filter(|v| v.is_finite())
+}
+
+/// A detail row with both the text and the numeric forms filled.
+pub fn detail_model(parent_id: i32, value: &str, logging_time_fmt: &str) -> trendlog_data_detail::ActiveModel {
+    trendlog_data_detail::ActiveModel {
+        parent_id: Set(parent_id),
+        value: Set(value.to_string()),
+        logging_time_fmt: Set(logging_time_fmt.to_string()),
+        numeric_value: Set(numeric_value(value)),
+        logging_time: Set(epoch_from_fmt(logging_time_fmt)),
+    }
+}
+
+/// Insert one detail row and fold it into the hourly/daily rollups. Local
+/// detail writers go through here (or [`super::trendlog_rollup::record_detail`]
+/// after a batch insert), so no write path leaves the rollups behind.
+pub async fn insert_detail<C: ConnectionTrait>(
+    db: &C,
+    parent_id: i32,
+    value: &str,
+    logging_time_fmt: &str,
+) -> Result<(), DbErr> {
+    trendlog_data_detail::Entity::insert(detail_model(parent_id, value, logging_time_fmt))
+        .exec(db)
+        .await?;
+    super::trendlog_rollup::record_detail(db, parent_id, value, logging_time_fmt).await;
+    Ok(())
+}
@@ -0,0 +1,803 @@
+//! Columnar trendlog export — Parquet or Arrow IPC stream.
+//!
+//! Analytics tools (pandas, DuckDB, Polars) read months of history far
+//! faster from a typed columnar file than from the CSV/JSON of the MCP
+//! export. [`ExportPlan::prepare`] validates a request, opens the sources
+//! (partition files overlapping the range, oldest first, then the main
+//! database) and resolves point metadata; [`ExportPlan::run`] then reads
+//! detail rows in pages, merging the sources in time order, and sends the
+//! encoded file in chunks, so neither the rows nor the file are ever held in
+//! memory as a whole.
+//!
+//! - **Long** layout: one row per sample —
+//!   `timestamp, serial_number, point_type, point_index, point_id, value`.
+//! - **Wide** layout: one row per timestamp and one `value` column per point,
+//!   named `<serial>_<point_id>` and carrying that point's metadata.
+//!
+//! `timestamp` is UTC (`Timestamp(Second, "UTC")`) and `value` is a nullable
+//! Float64 in the raw stored scale (NULL for non-numeric text). The schema
+//! metadata holds `t3.export` (range, layout, sources) and `t3.points` (label,
+//! units and Haystack tags of every exported point) as JSON.
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use arrow_array::{ArrayRef, Float64Array, Int32Array, RecordBatch, StringArray, TimestampSecondArray};
+use arrow_ipc::writer::StreamWriter;
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+use crate::entity::database_files;
+use crate::error::{Error, Result};
+use super::trendlog_detail::{self, DetailLayout};
+
+/// Rows per record batch (and per detail page) unless the request says otherwise.
+pub const DEFAULT_BATCH_ROWS: usize = 10_000;
+/// Upper bound for a requested `batch_rows`.
+pub const MAX_BATCH_ROWS: usize = 100_000;
+/// Parquet row group size; the writer buffers one group before emitting it.
+const PARQUET_ROW_GROUP_ROWS: usize = 100_000;
+
+/// Output file format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Parquet,
+    /// Arrow IPC stream format.
+    Arrow,
+}
+
+impl ExportFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ExportFormat::Parquet => "application/vnd.apache.parquet",
+            ExportFormat::Arrow => "application/vnd.apache.arrow.stream",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Parquet => "parquet",
+            ExportFormat::Arrow => "arrows",
+        }
+    }
+}
+
+/// Table layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportLayout {
+    #[default]
+    Long,
+    Wide,
+}
+
+/// A trendlog point, as stored on TRENDLOG_DATA.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct PointKey {
+    pub serial_number: i32,
+    /// "INPUT", "OUTPUT" or "VARIABLE".
+    pub point_type: String,
+    pub point_index: i32,
+}
+
+/// Export request. `start` / `end` are local "YYYY-MM-DD HH:MM:SS" (or with a
+/// `T`), or plain dates covering the whole day. Without `points`, every point
+/// (of `serial_number`, when given) is exported.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ExportRequest {
+    #[serde(default)]
+    pub format: ExportFormat,
+    #[serde(default)]
+    pub layout: ExportLayout,
+    pub start: String,
+    pub end: String,
+    pub serial_number: Option<i32>,
+    #[serde(default)]
+    pub points: Vec<PointKey>,
+    pub batch_rows: Option<usize>,
+}
+
+/// Metadata of one exported point.
+#[derive(Debug, Clone, Serialize)]
+pub struct PointMeta {
+    pub serial_number: i32,
+    pub panel_id: i32,
+    pub point_type: String,
+    pub point_index: i32,
+    pub point_id: String,
+    pub label: Option<String>,
+    pub units: Option<String>,
+    /// Haystack tag names.
+    pub tags: Vec<String>,
+}
+
+impl PointMeta {
+    fn key(&self) -> PointKey {
+        PointKey {
+            serial_number: self.serial_number,
+            point_type: self.point_type.clone(),
+            point_index: self.point_index,
+        }
+    }
+
+    /// Column name in the wide layout.
+    pub fn column_name(&self) -> String {
+        format!("{}_{}", self.serial_number, self.point_id)
+    }
+
+    fn field_metadata(&self) -> HashMap<String, String> {
+        let mut metadata = HashMap::from([
+            ("serial_number".to_string(), self.serial_number.to_string()),
+            ("panel_id".to_string(), self.panel_id.to_string()),
+            ("point_type".to_string(), self.point_type.clone()),
+            ("point_index".to_string(), self.point_index.to_string()),
+            ("point_id".to_string(), self.point_id.clone()),
+            ("tags".to_string(), serde_json::to_string(&self.tags).unwrap_or_default()),
+        ]);
+        if let Some(label) = &self.label {
+            metadata.insert("label".to_string(), label.clone());
+        }
+        if let Some(units) = &self.units {
+            metadata.insert("units".to_string(), units.clone());
+        }
+        metadata
+    }
+}
+
+/// What an export wrote.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ExportSummary {
+    /// Rows written (samples in the long layout, timestamps in the wide one).
+    pub rows: u64,
+    /// Detail rows skipped because their `LoggingTime_Fmt` didn't parse.
+    pub rows_unparseable: u64,
+    pub points: usize,
+    /// Sources read: partition identifiers, then "main".
+    pub sources: Vec<String>,
+    /// Partitions that could not be read, with the reason.
+    pub skipped: Vec<String>,
+    pub bytes: u64,
+}
+
+/// One database read by the export.
+struct Source {
+    id: String,
+    conn: DatabaseConnection,
+    layout: DetailLayout,
+    /// Detail `ParentId` -> index into [`ExportPlan::points`].
+    parents: HashMap<i32, usize>,
+    /// Partition connections are closed after the export; main is shared.
+    is_partition: bool,
+}
+
+/// TRENDLOG_DATA parent row.
+#[derive(Debug, FromQueryResult)]
+struct ParentRow {
+    id: i32,
+    serial_number: i32,
+    panel_id: i32,
+    point_id: String,
+    point_index: i32,
+    point_type: String,
+    units: Option<String>,
+}
+
+/// One page of detail rows.
+#[derive(Debug, FromQueryResult)]
+struct DetailRow {
+    row_id: i64,
+    parent_id: i32,
+    logging_time_fmt: String,
+    numeric_value: Option<f64>,
+    value: String,
+}
+
+/// Label / units from INPUTS, OUTPUTS or VARIABLES.
+#[derive(Debug, FromQueryResult)]
+struct LabelRow {
+    full_label: Option<String>,
+    label: Option<String>,
+    units: Option<String>,
+}
+
+/// A validated export, ready to stream.
+pub struct ExportPlan {
+    pub format: ExportFormat,
+    pub layout: ExportLayout,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    pub batch_rows: usize,
+    pub points: Vec<PointMeta>,
+    sources: Vec<Source>,
+    skipped: Vec<String>,
+}
+
+impl ExportPlan {
+    /// Validate `request`, open its sources and resolve the exported points.
+    /// Errors here happen before any byte is sent.
+    pub async fn prepare(db: &DatabaseConnection, request: &ExportRequest) -> Result<Self> {
+        let start = parse_bound(&request.start, NaiveTime::MIN)?;
+        let end = parse_bound(&request.end, NaiveTime::from_hms_opt(23, 59, 59).unwrap_or(NaiveTime::MIN))?;
+        if start > end {
+            return Err(Error::ValidationError("start must not be after end".to_string()));
+        }
+        let batch_rows = request.batch_rows.unwrap_or(DEFAULT_BATCH_ROWS).clamp(1, MAX_BATCH_ROWS);
+
+        let mut plan = ExportPlan {
+            format: request.format,
+            layout: request.layout,
+            start,
+            end,
+            batch_rows,
+            points: Vec::new(),
+            sources: Vec::new(),
+            skipped: Vec::new(),
+        };
+
+        // Partition files overlapping the range (archived ones included), oldest first
+        let mut partitions = database_files::Entity::find()
+            .filter(database_files::Column::PartitionIdentifier.is_not_null())
+            .filter(database_files::Column::IsActive.eq(false))
+            .all(db)
+            .await?;
+        partitions.retain(|p| match (p.start_date, p.end_date) {
+            (Some(p_start), Some(p_end)) => p_start <= end && start <= p_end,
+            _ => true,
+        });
+        partitions.sort_by_key(|p| p.start_date);
+
+        let mut parent_rows: Vec<(usize, Vec<ParentRow>)> = Vec::new();
+        for partition in partitions {
+            let id = partition.partition_identifier.clone().unwrap_or_default();
+            if !Path::new(&partition.file_path).exists() {
+                plan.skipped.push(format!("{}: file not found at {}", id, partition.file_path));
+                continue;
+            }
+            let conn = match Database::connect(format!("sqlite://{}?mode=ro", partition.file_path)).await {
+                Ok(conn) => conn,
+                Err(e) => {
+                    plan.skipped.push(format!("{}: {}", id, e));
+                    continue;
+                }
+            };
+            match open_source(&conn, request).await {
+                Ok((layout, parents)) => {
+                    parent_rows.push((plan.sources.len(), parents));
+                    plan.sources.push(Source { id, conn, layout, parents: HashMap::new(), is_partition: true });
+                }
+                Err(e) => {
+                    conn.close().await.ok();
+                    plan.skipped.push(format!("{}: {}", id, e));
+                }
+            }
+        }
+        let (layout, parents) = open_source(db, request).await?;
+        parent_rows.push((plan.sources.len(), parents));
+        plan.sources.push(Source {
+            id: "main".to_string(),
+            conn: db.clone(),
+            layout,
+            parents: HashMap::new(),
+            is_partition: false,
+        });
+
+        // Points, in request order when listed, otherwise by serial/type/index.
+        // The newest source wins for panel, point id and units.
+        let mut by_key: HashMap<PointKey, PointMeta> = HashMap::new();
+        for (_, parents) in &parent_rows {
+            for p in parents {
+                let meta = PointMeta {
+                    serial_number: p.serial_number,
+                    panel_id: p.panel_id,
+                    point_type: p.point_type.clone(),
+                    point_index: p.point_index,
+                    point_id: p.point_id.clone(),
+                    label: None,
+                    units: p.units.clone().filter(|u| !u.is_empty()),
+                    tags: Vec::new(),
+                };
+                by_key.insert(meta.key(), meta);
+            }
+        }
+        let mut keys: Vec<PointKey> = if request.points.is_empty() {
+            let mut keys: Vec<PointKey> = by_key.keys().cloned().collect();
+            keys.sort();
+            keys
+        } else {
+            request.points.iter().filter(|k| by_key.contains_key(k)).cloned().collect()
+        };
+        let mut seen = HashSet::new();
+        keys.retain(|k| seen.insert(k.clone()));
+        let slots: HashMap<PointKey, usize> = keys.iter().cloned().enumerate().map(|(i, k)| (k, i)).collect();
+        for key in &keys {
+            if let Some(mut meta) = by_key.remove(key) {
+                load_point_labels(db, &mut meta).await;
+                plan.points.push(meta);
+            }
+        }
+
+        for (source, parents) in parent_rows {
+            plan.sources[source].parents = parents
+                .iter()
+                .filter_map(|p| {
+                    let key = PointKey {
+                        serial_number: p.serial_number,
+                        point_type: p.point_type.clone(),
+                        point_index: p.point_index,
+                    };
+                    slots.get(&key).map(|slot| (p.id, *slot))
+                })
+                .collect();
+        }
+
+        Ok(plan)
+    }
+
+    /// Arrow schema of the export, metadata included.
+    pub fn schema(&self) -> SchemaRef {
+        let timestamp = Field::new("timestamp", DataType::Timestamp(TimeUnit::Second, Some("UTC".into())), false);
+        let fields = match self.layout {
+            ExportLayout::Long => vec![
+                timestamp,
+                Field::new("serial_number", DataType::Int32, false),
+                Field::new("point_type", DataType::Utf8, false),
+                Field::new("point_index", DataType::Int32, false),
+                Field::new("point_id", DataType::Utf8, false),
+                Field::new("value", DataType::Float64, true),
+            ],
+            ExportLayout::Wide => std::iter::once(timestamp)
+                .chain(self.points.iter().map(|p| {
+                    Field::new(p.column_name(), DataType::Float64, true).with_metadata(p.field_metadata())
+                }))
+                .collect(),
+        };
+        let export = serde_json::json!({
+            "start": self.start.format(trendlog_detail::FMT).to_string(),
+            "end": self.end.format(trendlog_detail::FMT).to_string(),
+            "layout": self.layout,
+            "sources": self.sources.iter().map(|s| s.id.as_str()).collect::<Vec<_>>(),
+        });
+        let metadata = HashMap::from([
+            ("t3.export".to_string(), export.to_string()),
+            ("t3.points".to_string(), serde_json::to_string(&self.points).unwrap_or_default()),
+        ]);
+        Arc::new(Schema::new_with_metadata(fields, metadata))
+    }
+
+    /// Read every source and send the encoded file to `tx` chunk by chunk.
+    /// Stops with an error when the receiver goes away.
+    pub async fn run(self, tx: &mpsc::Sender<std::io::Result<Vec<u8>>>) -> Result<ExportSummary> {
+        let schema = self.schema();
+        let buffer = SharedBuffer::default();
+        let mut writer = BatchWriter::new(self.format, buffer.clone(), &schema)?;
+        let mut builder = match self.layout {
+            ExportLayout::Long => Rows::Long(LongRows::default()),
+            ExportLayout::Wide => Rows::Wide(WideRows::new(self.points.len())),
+        };
+        let mut summary = ExportSummary { points: self.points.len(), skipped: self.skipped.clone(), ..Default::default() };
+
+        let mut outcome = self.read_sources(&schema, &mut builder, &mut writer, &buffer, tx, &mut summary).await;
+        if outcome.is_ok() {
+            summary.sources = self.sources.iter().map(|s| s.id.clone()).collect();
+            outcome = async {
+                if let Some(batch) = builder.finish(&schema, &self.points)? {
+                    summary.rows += batch.num_rows() as u64;
+                    writer.write(&batch)?;
+                }
+                writer.finish()?;
+                send(&buffer, tx, &mut summary).await
+            }
+            .await;
+        }
+
+        for source in self.sources {
+            if source.is_partition {
+                source.conn.close().await.ok();
+            }
+        }
+        outcome.map(|_| summary)
+    }
+
+    /// Read the sources together, in time order: partitions may overlap each
+    /// other and main, so each row goes to the builder along with the earliest
+    /// second any source can still produce.
+    async fn read_sources(
+        &self,
+        schema: &SchemaRef,
+        builder: &mut Rows,
+        writer: &mut BatchWriter,
+        buffer: &SharedBuffer,
+        tx: &mpsc::Sender<std::io::Result<Vec<u8>>>,
+        summary: &mut ExportSummary,
+    ) -> Result<()> {
+        let mut cursors = Vec::new();
+        for source in self.sources.iter().filter(|s| !s.parents.is_empty()) {
+            let mut cursor = Cursor::open(source, self.start, self.end).await?;
+            cursor.fill(self.batch_rows).await?;
+            cursors.push(cursor);
+        }
+        loop {
+            cursors.retain(|c| !c.page.is_empty());
+            let Some(next) = (0..cursors.len()).min_by_key(|&i| cursors[i].watermark()) else { break };
+            let Some(row) = cursors[next].page.pop_front() else { break };
+            cursors[next].fill(self.batch_rows).await?;
+            let watermark = cursors.iter().filter_map(Cursor::watermark).min().unwrap_or(i64::MAX);
+
+            let Some(slot) = cursors[next].source.parents.get(&row.parent_id).copied() else { continue };
+            let Some(epoch) = trendlog_detail::epoch_from_fmt(&row.logging_time_fmt) else {
+                summary.rows_unparseable += 1;
+                continue;
+            };
+            let value = row.numeric_value.or_else(|| trendlog_detail::numeric_value(&row.value));
+            if let Some(batch) = builder.push(epoch, slot, value, watermark, self.batch_rows, schema, &self.points)? {
+                summary.rows += batch.num_rows() as u64;
+                writer.write(&batch)?;
+                send(buffer, tx, summary).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Detail rows of one source, read a page at a time in
+/// `LoggingTime_Fmt, rowid` order.
+struct Cursor<'a> {
+    source: &'a Source,
+    sql: String,
+    /// Some rows spell the time with a `T` (see [`Cursor::watermark`]).
+    t_spelled: bool,
+    page: VecDeque<DetailRow>,
+    last_time: String,
+    last_rowid: i64,
+    done: bool,
+}
+
+impl<'a> Cursor<'a> {
+    async fn open(source: &'a Source, start: NaiveDateTime, end: NaiveDateTime) -> Result<Cursor<'a>> {
+        let parents = source.parents.keys().map(|id| id.to_string()).collect::<Vec<_>>().join(",");
+        let range = source.layout.time_range_sql("d", Some(start), Some(end));
+        let t_spelled = source
+            .conn
+            .query_one(Statement::from_string(
+                DbBackend::Sqlite,
+                format!(
+                    "SELECT EXISTS(SELECT 1 FROM TRENDLOG_DATA_DETAIL d \
+                     WHERE d.ParentId IN ({parents}) AND {range} AND instr(d.LoggingTime_Fmt, 'T') > 0) AS t_spelled"
+                ),
+            ))
+            .await?
+            .map(|row| row.try_get::<bool>("", "t_spelled"))
+            .transpose()?
+            .unwrap_or(false);
+        let sql = format!(
+            "SELECT d.rowid AS row_id, d.ParentId AS parent_id, d.LoggingTime_Fmt AS logging_time_fmt, \
+                    {numeric} AS numeric_value, d.Value AS value \
+             FROM TRENDLOG_DATA_DETAIL d \
+             WHERE d.ParentId IN ({parents}) AND {range} \
+               AND (d.LoggingTime_Fmt > ? OR (d.LoggingTime_Fmt = ? AND d.rowid > ?)) \
+             ORDER BY d.LoggingTime_Fmt, d.rowid LIMIT ?",
+            numeric = source.layout.numeric_sql("d"),
+        );
+        Ok(Self {
+            source,
+            sql,
+            t_spelled,
+            page: VecDeque::new(),
+            last_time: String::new(),
+            last_rowid: 0,
+            done: false,
+        })
+    }
+
+    /// Load the next page once the current one is used up.
+    async fn fill(&mut self, batch_rows: usize) -> Result<()> {
+        if !self.page.is_empty() || self.done {
+            return Ok(());
+        }
+        let page = DetailRow::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::Sqlite,
+            &self.sql,
+            [
+                self.last_time.clone().into(),
+                self.last_time.clone().into(),
+                self.last_rowid.into(),
+                (batch_rows as i64).into(),
+            ],
+        ))
+        .all(&self.source.conn)
+        .await?;
+        self.done = page.len() < batch_rows;
+        if let Some(last) = page.last() {
+            self.last_time = last.logging_time_fmt.clone();
+            self.last_rowid = last.row_id;
+        }
+        self.page = page.into();
+        Ok(())
+    }
+
+    /// Earliest second this source can still produce (`None` once it's read).
+    /// That is the next row's, except that in text order a time spelled with
+    /// a `T` sorts after every time of its day spelled with a space — so
+    /// when the source has both, a space-spelled row only bounds the rest by
+    /// the start of its day.
+    fn watermark(&self) -> Option<i64> {
+        let row = self.page.front()?;
+        let Some(time) = trendlog_detail::parse_fmt(&row.logging_time_fmt) else { return Some(i64::MIN) };
+        let bound = if self.t_spelled && !row.logging_time_fmt.contains('T') {
+            time.date().and_time(NaiveTime::MIN)
+        } else {
+            time
+        };
+        Some(trendlog_detail::local_epoch(bound))
+    }
+}
+
+/// Parse a request bound; plain dates take `day_time`.
+fn parse_bound(s: &str, day_time: NaiveTime) -> Result<NaiveDateTime> {
+    trendlog_detail::parse_fmt(s)
+        .or_else(|| NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d").ok().map(|d| d.and_time(day_time)))
+        .ok_or_else(|| Error::ValidationError(format!("Invalid date '{}'", s)))
+}
+
+/// Detail layout and the parents `request` selects in one database.
+async fn open_source(db: &DatabaseConnection, request: &ExportRequest) -> Result<(DetailLayout, Vec<ParentRow>)> {
+    let layout = DetailLayout::detect(db, "main").await?;
+    let mut sql = "SELECT id, SerialNumber AS serial_number, PanelId AS panel_id, PointId AS point_id, \
+                          PointIndex AS point_index, PointType AS point_type, Units AS units \
+                   FROM TRENDLOG_DATA WHERE 1 = 1"
+        .to_string();
+    let mut values: Vec<Value> = Vec::new();
+    if let Some(serial) = request.serial_number {
+        sql.push_str(" AND SerialNumber = ?");
+        values.push(serial.into());
+    }
+    let mut parents = ParentRow::find_by_statement(Statement::from_sql_and_values(DbBackend::Sqlite, sql, values))
+        .all(db)
+        .await?;
+    if !request.points.is_empty() {
+        parents.retain(|p| {
+            request.points.iter().any(|k| {
+                k.serial_number == p.serial_number && k.point_type == p.point_type && k.point_index == p.point_index
+            })
+        });
+    }
+    Ok((layout, parents))
+}
+
+/// Fill label, units (when TRENDLOG_DATA has none) and Haystack tags from
+/// the main database. Missing tables just leave the fields empty.
+async fn load_point_labels(db: &DatabaseConnection, meta: &mut PointMeta) {
+    let table = match meta.point_type.as_str() {
+        "INPUT" => Some(("INPUTS", "Input_Index")),
+        "OUTPUT" => Some(("OUTPUTS", "Output_Index")),
+        "VARIABLE" => Some(("VARIABLES", "Variable_Index")),
+        _ => None,
+    };
+    if let Some((table, index_column)) = table {
+        let row = LabelRow::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::Sqlite,
+            format!(
+                "SELECT Full_Label AS full_label, Label AS label, Units AS units FROM {} \
+                 WHERE SerialNumber = ? AND CAST({} AS INTEGER) = ?",
+                table, index_column
+            ),
+            [meta.serial_number.into(), meta.point_index.into()],
+        ))
+        .one(db)
+        .await
+        .ok()
+        .flatten();
+        if let Some(row) = row {
+            let non_empty = |s: Option<String>| s.filter(|s| !s.trim().is_empty());
+            meta.label = non_empty(row.full_label).or(non_empty(row.label));
+            if meta.units.is_none() {
+                meta.units = non_empty(row.units);
+            }
+        }
+    }
+
+    let tags = db
+        .query_all(Statement::from_sql_and_values(
+            DbBackend::Sqlite,
+            "SELECT tag_name FROM HAYSTACK_POINT_TAGS \
+             WHERE serial_number = ? AND point_type = ? AND point_index = ? ORDER BY tag_name",
+            [meta.serial_number.into(), meta.point_type.clone().into(), meta.point_index.to_string().into()],
+        ))
+        .await
+        .unwrap_or_default();
+    meta.tags = tags.iter().filter_map(|r| r.try_get::<String>("", "tag_name").ok()).collect();
+}
+
+/// Move whatever the writer has produced so far to the receiver.
+async fn send(
+    buffer: &SharedBuffer,
+    tx: &mpsc::Sender<std::io::Result<Vec<u8>>>,
+    summary: &mut ExportSummary,
+) -> Result<()> {
+    let chunk = buffer.take();
+    if chunk.is_empty() {
+        return Ok(());
+    }
+    summary.bytes += chunk.len() as u64;
+    tx.send(Ok(chunk))
+        .await
+        .map_err(|_| Error::ServerError("Export cancelled: receiver closed".to_string()))
+}
+
+fn write_error(e: impl std::fmt::Display) -> Error {
+    Error::ServerError(format!("Export write failed: {}", e))
+}
+
+/// In-memory sink the format writers append to; drained after every batch.
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+    fn take(&self) -> Vec<u8> {
+        std::mem::take(&mut *self.0.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+enum BatchWriter {
+    Arrow(StreamWriter<SharedBuffer>),
+    Parquet(ArrowWriter<SharedBuffer>),
+}
+
+impl BatchWriter {
+    fn new(format: ExportFormat, sink: SharedBuffer, schema: &SchemaRef) -> Result<Self> {
+        Ok(match format {
+            ExportFormat::Arrow => BatchWriter::Arrow(StreamWriter::try_new(sink, schema).map_err(write_error)?),
+            ExportFormat::Parquet => {
+                let props = WriterProperties::builder()
+                    .set_compression(Compression::SNAPPY)
+                    .set_max_row_group_size(PARQUET_ROW_GROUP_ROWS)
+                    .build();
+                BatchWriter::Parquet(ArrowWriter::try_new(sink, schema.clone(), Some(props)).map_err(write_error)?)
+            }
+        })
+    }
+
+    fn write(&mut self, batch: &RecordBatch) -> Result<()> {
+        match self {
+            BatchWriter::Arrow(w) => w.write(batch).map_err(write_error),
+            BatchWriter::Parquet(w) => w.write(batch).map_err(write_error),
+        }
+    }
+
+    fn finish(self) -> Result<()> {
+        match self {
+            BatchWriter::Arrow(mut w) => w.finish().map_err(write_error),
+            BatchWriter::Parquet(w) => w.close().map(|_| ()).map_err(write_error),
+        }
+    }
+}
+
+/// Rows collected for the next record batch.
+enum Rows {
+    Long(LongRows),
+    Wide(WideRows),
+}
+
+#[derive(Default)]
+struct LongRows {
+    times: Vec<i64>,
+    slots: Vec<usize>,
+    values: Vec<Option<f64>>,
+}
+
+struct WideRows {
+    points: usize,
+    /// Value of every point at each timestamp collected so far.
+    rows: BTreeMap<i64, Vec<Option<f64>>>,
+}
+
+impl WideRows {
+    fn new(points: usize) -> Self {
+        Self { points, rows: BTreeMap::new() }
+    }
+}
+
+impl Rows {
+    /// Add a sample; returns a full batch when one is ready. `watermark` is
+    /// the earliest second a later sample can have: wide rows are only cut
+    /// before it, so samples sharing a second land in one row even when they
+    /// arrive apart — on another page, from another source or spelled with a
+    /// `T`. A batch can then hold more than `batch_rows` rows.
+    #[allow(clippy::too_many_arguments)]
+    fn push(
+        &mut self,
+        epoch: i64,
+        slot: usize,
+        value: Option<f64>,
+        watermark: i64,
+        batch_rows: usize,
+        schema: &SchemaRef,
+        points: &[PointMeta],
+    ) -> Result<Option<RecordBatch>> {
+        let full = match self {
+            Rows::Long(rows) => {
+                rows.times.push(epoch);
+                rows.slots.push(slot);
+                rows.values.push(value);
+                rows.times.len() >= batch_rows
+            }
+            Rows::Wide(rows) => {
+                let width = rows.points;
+                rows.rows.entry(epoch).or_insert_with(|| vec![None; width])[slot] = value;
+                rows.rows.len() >= batch_rows && rows.rows.first_key_value().is_some_and(|(&t, _)| t < watermark)
+            }
+        };
+        if full {
+            return self.cut(watermark, schema, points);
+        }
+        Ok(None)
+    }
+
+    /// Drain the collected rows into a batch (`None` when empty).
+    fn finish(&mut self, schema: &SchemaRef, points: &[PointMeta]) -> Result<Option<RecordBatch>> {
+        self.cut(i64::MAX, schema, points)
+    }
+
+    /// Drain the collected rows — for the wide layout those before `before` —
+    /// into a batch (`None` when empty).
+    fn cut(&mut self, before: i64, schema: &SchemaRef, points: &[PointMeta]) -> Result<Option<RecordBatch>> {
+        let (times, values): (Vec<i64>, Vec<ArrayRef>) = match self {
+            Rows::Long(rows) => {
+                let slots = std::mem::take(&mut rows.slots);
+                let point = |slot: &usize| &points[*slot];
+                (
+                    std::mem::take(&mut rows.times),
+                    vec![
+                        Arc::new(Int32Array::from_iter_values(slots.iter().map(|s| point(s).serial_number))) as ArrayRef,
+                        Arc::new(StringArray::from_iter_values(slots.iter().map(|s| point(s).point_type.as_str()))),
+                        Arc::new(Int32Array::from_iter_values(slots.iter().map(|s| point(s).point_index))),
+                        Arc::new(StringArray::from_iter_values(slots.iter().map(|s| point(s).point_id.as_str()))),
+                        Arc::new(Float64Array::from(std::mem::take(&mut rows.values))),
+                    ],
+                )
+            }
+            Rows::Wide(rows) => {
+                let rest = rows.rows.split_off(&before);
+                let ready = std::mem::replace(&mut rows.rows, rest);
+                let mut columns = vec![Vec::new(); rows.points];
+                let times: Vec<i64> = ready
+                    .into_iter()
+                    .map(|(time, row)| {
+                        for (column, value) in columns.iter_mut().zip(row) {
+                            column.push(value);
+                        }
+                        time
+                    })
+                    .collect();
+                (times, columns.into_iter().map(|c| Arc::new(Float64Array::from(c)) as ArrayRef).collect())
+            }
+        };
+        if times.is_empty() {
+            return Ok(None);
+        }
+        let timestamp: ArrayRef = Arc::new(TimestampSecondArray::from(times).with_timezone("UTC"));
+        let columns: Vec<ArrayRef> = std::iter::once(timestamp).chain(values).collect();
+        RecordBatch::try_new(schema.clone(), columns).map(Some).map_err(write_error)
+    }
+}
@@ -0,0 +1,926 @@
+//! Trendlog import — bulk-load history from CSV.
+//!
+//! History collected elsewhere (an old T3000.db exported to CSV, a
+//! third-party logger, a replaced controller) can be loaded into the same
+//! tables the FFI sync writes. An [`ImportMapping`] ties CSV columns to
+//! points, either **wide** (a time column plus one value column per point) or
+//! **long** (time, serial, point type, point index and value columns).
+//!
+//! [`import_csv`] reads the file record by record from any async reader, so
+//! multi-GB uploads never sit in memory:
+//!
+//! - **Parents** are resolved in the main database through
+//!   [`TrendlogParentCache`]; partition files get the same parent ids, as
+//!   they do when the partition monitor copies the main database.
+//! - **Routing**: a sample goes to the registered partition file covering its
+//!   local time. Samples older than every partition get a new partition file
+//!   for their period (seeded with the main database's rows for it, which
+//!   move out of main); anything else stays in the main database, where the
+//!   partition monitor picks it up.
+//! - **Dedupe**: a sample whose point already has a row at the same
+//!   `LoggingTime_Fmt` in its target database (or earlier in the file) is
+//!   skipped.
+//! - **Values** must be numeric; cells like "n/a" or "1,5" are rejected.
+//!
+//! Rollups for the imported days are rebuilt at the end unless disabled.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local, NaiveDateTime};
+use sea_orm::*;
+use sea_orm::prelude::Expr;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+
+use crate::constants::get_t3000_database_path;
+use crate::db_schema::EMBEDDED_SCHEMA;
+use crate::entity::database_files;
+use crate::entity::database_partition_config::DatabasePartitionConfig;
+use crate::error::{Error, Result};
+use crate::t3_device::trendlog_parent_cache::{ParentKey, TrendlogParentCache};
+use super::partition_monitor_service::{calculate_period_boundaries, generate_partition_identifier};
+use super::trendlog_detail::{self, DetailLayout};
+use super::{trendlog_rollup, DatabaseConfigService};
+
+/// Samples buffered (across all target databases) before they are written.
+pub const DEFAULT_IMPORT_BATCH: usize = 1000;
+/// Rejected records quoted in the summary; the rest are only counted.
+const MAX_REJECT_SAMPLES: usize = 20;
+/// Detail rows per multi-row INSERT (5 parameters each).
+const INSERT_CHUNK: usize = 500;
+/// Largest CSV record accepted, in bytes, so an unterminated quote can't pull
+/// the rest of the upload into memory.
+pub const MAX_RECORD_BYTES: usize = 1 << 20;
+/// Most fields one CSV record may have.
+pub const MAX_RECORD_FIELDS: usize = 10_000;
+
+/// How CSV columns map to points. Give `columns` (wide) or `long`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ImportMapping {
+    /// Column holding the sample time.
+    pub time_column: String,
+    /// chrono format of the time column, in local time. Without it
+    /// "YYYY-MM-DD HH:MM[:SS]" (or with a `T`), RFC 3339 and Unix seconds
+    /// are accepted.
+    pub time_format: Option<String>,
+    /// Field delimiter, `,` by default.
+    pub delimiter: Option<char>,
+    /// Wide files: one value column per point.
+    #[serde(default)]
+    pub columns: Vec<ColumnMapping>,
+    /// Long files: one sample per record.
+    pub long: Option<LongMapping>,
+    /// PanelId for points that have no TRENDLOG_DATA row yet (default: the
+    /// device's PanelId, else 0).
+    pub panel_id: Option<i32>,
+    /// Rebuild hourly/daily rollups for the imported days (default true).
+    pub rebuild_rollups: Option<bool>,
+}
+
+/// A value column of a wide file.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ColumnMapping {
+    pub column: String,
+    pub serial_number: i32,
+    pub point_type: String,
+    pub point_index: i32,
+}
+
+/// Key columns of a long file.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LongMapping {
+    pub serial_column: String,
+    pub point_type_column: String,
+    pub point_index_column: String,
+    pub value_column: String,
+}
+
+/// Where new partition files go and how much is buffered.
+#[derive(Debug, Clone)]
+pub struct ImportOptions {
+    pub partition_dir: PathBuf,
+    pub batch_size: usize,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self { partition_dir: get_t3000_database_path(), batch_size: DEFAULT_IMPORT_BATCH }
+    }
+}
+
+/// Import result for one point.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct PointImportSummary {
+    pub serial_number: i32,
+    pub point_type: String,
+    pub point_index: i32,
+    pub point_id: String,
+    /// TRENDLOG_DATA id in the main database (and every partition).
+    pub parent_id: i32,
+    pub imported: u64,
+    pub duplicates: u64,
+    pub first_time: Option<String>,
+    pub last_time: Option<String>,
+    /// Rows written per database: "main" or the partition identifier.
+    pub targets: BTreeMap<String, u64>,
+}
+
+/// What an import did.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ImportSummary {
+    /// CSV data records read (header excluded).
+    pub records: u64,
+    pub imported: u64,
+    pub duplicates: u64,
+    /// Empty value cells.
+    pub blank: u64,
+    /// Samples with an unparseable time or point.
+    pub rejected: u64,
+    /// The first few rejections, with their row numbers (header is row 1).
+    pub rejected_samples: Vec<String>,
+    /// Partition identifiers created for periods that had no file.
+    pub partitions_created: Vec<String>,
+    pub rollups_rebuilt: bool,
+    pub points: Vec<PointImportSummary>,
+}
+
+impl ImportSummary {
+    fn reject(&mut self, row: u64, reason: String) {
+        self.rejected += 1;
+        if self.rejected_samples.len() < MAX_REJECT_SAMPLES {
+            self.rejected_samples.push(format!("row {}: {}", row, reason));
+        }
+    }
+}
+
+/// Import a CSV file (header row first) according to `mapping`.
+pub async fn import_csv<R: AsyncBufRead + Unpin>(
+    db: &DatabaseConnection,
+    mapping: &ImportMapping,
+    reader: R,
+    opts: &ImportOptions,
+) -> Result<ImportSummary> {
+    let delimiter = u8::try_from(mapping.delimiter.unwrap_or(','))
+        .map_err(|_| Error::ValidationError("delimiter must be a single-byte character".to_string()))?;
+    let mut records = CsvRecords::new(reader, delimiter);
+    let header = records
+        .next_record()
+        .await?
+        .ok_or_else(|| Error::ValidationError("CSV file is empty".to_string()))?;
+    let layout = RecordLayout::from_header(mapping, &header)?;
+
+    let mut importer = Importer::new(db, mapping, opts).await?;
+    let mut row = 1u64;
+    while let Some(record) = records.next_record().await? {
+        row += 1;
+        importer.summary.records += 1;
+        let Some(time) = record.get(layout.time).and_then(|s| parse_time(s, mapping.time_format.as_deref())) else {
+            let raw = record.get(layout.time).map(String::as_str).unwrap_or("");
+            importer.summary.reject(row, format!("invalid time '{}'", raw));
+            continue;
+        };
+        for sample in layout.samples(&record) {
+            match sample {
+                Ok((_, "")) => importer.summary.blank += 1,
+                Ok((_, value)) if trendlog_detail::numeric_value(value).is_none() => {
+                    importer.summary.reject(row, format!("non-numeric value '{}'", value))
+                }
+                Ok((key, value)) => importer.push(key, time, value).await?,
+                Err(reason) => importer.summary.reject(row, reason),
+            }
+        }
+        if importer.pending >= opts.batch_size.max(1) {
+            importer.flush().await?;
+        }
+    }
+    importer.finish().await
+}
+
+// ============================================================================
+// CSV reading
+// ============================================================================
+
+/// Async CSV record reader on top of `csv_core` (quoted fields may span lines).
+struct CsvRecords<R> {
+    reader: R,
+    parser: csv_core::Reader,
+    output: Vec<u8>,
+    ends: Vec<usize>,
+}
+
+impl<R: AsyncBufRead + Unpin> CsvRecords<R> {
+    fn new(reader: R, delimiter: u8) -> Self {
+        Self {
+            reader,
+            parser: csv_core::ReaderBuilder::new().delimiter(delimiter).build(),
+            output: vec![0; 4096],
+            ends: vec![0; 64],
+        }
+    }
+
+    async fn next_record(&mut self) -> Result<Option<Vec<String>>> {
+        let (mut out_len, mut ends_len) = (0, 0);
+        loop {
+            let input = self
+                .reader
+                .fill_buf()
+                .await
+                .map_err(|e| Error::ServerError(format!("Failed to read CSV: {}", e)))?;
+            let (result, read, written, ended) =
+                self.parser.read_record(input, &mut self.output[out_len..], &mut self.ends[ends_len..]);
+            self.reader.consume(read);
+            out_len += written;
+            ends_len += ended;
+            match result {
+                csv_core::ReadRecordResult::InputEmpty => {}
+                csv_core::ReadRecordResult::OutputFull => {
+                    let len = self.output.len();
+                    if len >= MAX_RECORD_BYTES {
+                        return Err(Error::ValidationError(format!(
+                            "CSV record longer than {} bytes (unterminated quote?)",
+                            MAX_RECORD_BYTES
+                        )));
+                    }
+                    self.output.resize((len * 2).min(MAX_RECORD_BYTES), 0);
+                }
+                csv_core::ReadRecordResult::OutputEndsFull => {
+                    let len = self.ends.len();
+                    if len >= MAX_RECORD_FIELDS {
+                        return Err(Error::ValidationError(format!(
+                            "CSV record has more than {} fields",
+                            MAX_RECORD_FIELDS
+                        )));
+                    }
+                    self.ends.resize((len * 2).min(MAX_RECORD_FIELDS), 0);
+                }
+                csv_core::ReadRecordResult::Record => {
+                    let mut start = 0;
+                    let fields = self.ends[..ends_len]
+                        .iter()
+                        .map(|&end| {
+                            let field = String::from_utf8_lossy(&self.output[start..end]).trim().to_string();
+                            start = end;
+                            field
+                        })
+                        .collect();
+                    return Ok(Some(fields));
+                }
+                csv_core::ReadRecordResult::End => return Ok(None),
+            }
+        }
+    }
+}
+
+/// Column positions resolved from the header.
+struct RecordLayout {
+    time: usize,
+    points: Points,
+}
+
+enum Points {
+    Wide(Vec<(usize, PointKey)>),
+    Long { serial: usize, point_type: usize, point_index: usize, value: usize },
+}
+
+/// A point as imported: TRENDLOG_DATA serial, type and index.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct PointKey {
+    serial_number: i32,
+    point_type: String,
+    point_index: i32,
+}
+
+impl RecordLayout {
+    fn from_header(mapping: &ImportMapping, header: &[String]) -> Result<Self> {
+        let column = |name: &str| {
+            header
+                .iter()
+                .position(|h| h.eq_ignore_ascii_case(name.trim()))
+                .ok_or_else(|| Error::ValidationError(format!("Column '{}' not found in the CSV header", name)))
+        };
+        let points = match (&mapping.long, mapping.columns.is_empty()) {
+            (Some(long), true) => Points::Long {
+                serial: column(&long.serial_column)?,
+                point_type: column(&long.point_type_column)?,
+                point_index: column(&long.point_index_column)?,
+                value: column(&long.value_column)?,
+            },
+            (None, false) => Points::Wide(
+                mapping
+                    .columns
+                    .iter()
+                    .map(|c| {
+                        let point_type = normalize_point_type(&c.point_type).ok_or_else(|| {
+                            Error::ValidationError(format!("Unknown point type '{}' for column '{}'", c.point_type, c.column))
+                        })?;
+                        let key = PointKey { serial_number: c.serial_number, point_type, point_index: c.point_index };
+                        Ok((column(&c.column)?, key))
+                    })
+                    .collect::<Result<_>>()?,
+            ),
+            _ => {
+                return Err(Error::ValidationError(
+                    "Give either value columns (wide) or a long mapping, not both".to_string(),
+                ))
+            }
+        };
+        Ok(Self { time: column(&mapping.time_column)?, points })
+    }
+
+    /// `(point, value)` pairs of a record, or why a sample is unusable.
+    fn samples<'a>(&'a self, record: &'a [String]) -> Vec<std::result::Result<(PointKey, &'a str), String>> {
+        let cell = |i: usize| record.get(i).map(String::as_str).unwrap_or("");
+        match &self.points {
+            Points::Wide(columns) => columns.iter().map(|(i, key)| Ok((key.clone(), cell(*i)))).collect(),
+            Points::Long { serial, point_type, point_index, value } => {
+                let key = (|| {
+                    Some(PointKey {
+                        serial_number: cell(*serial).parse().ok()?,
+                        point_type: normalize_point_type(cell(*point_type))?,
+                        point_index: cell(*point_index).parse().ok()?,
+                    })
+                })();
+                vec![key.map(|k| (k, cell(*value))).ok_or_else(|| {
+                    format!("invalid point '{}/{}/{}'", cell(*serial), cell(*point_type), cell(*point_index))
+                })]
+            }
+        }
+    }
+}
+
+/// "INPUT" / "OUTPUT" / "VARIABLE" from the usual spellings.
+fn normalize_point_type(s: &str) -> Option<String> {
+    let point_type = match s.trim().to_ascii_uppercase().as_str() {
+        "INPUT" | "INPUTS" | "IN" => "INPUT",
+        "OUTPUT" | "OUTPUTS" | "OUT" => "OUTPUT",
+        "VARIABLE" | "VARIABLES" | "VAR" => "VARIABLE",
+        _ => return None,
+    };
+    Some(point_type.to_string())
+}
+
+/// Parse a CSV time into local wall-clock time.
+fn parse_time(s: &str, format: Option<&str>) -> Option<NaiveDateTime> {
+    let s = s.trim();
+    if let Some(format) = format {
+        return NaiveDateTime::parse_from_str(s, format).ok();
+    }
+    trendlog_detail::parse_fmt(s)
+        .or_else(|| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").ok())
+        .or_else(|| DateTime::parse_from_rfc3339(s).ok().map(|t| t.with_timezone(&Local).naive_local()))
+        .or_else(|| {
+            let secs = s.parse::<i64>().ok()?;
+            DateTime::from_timestamp(secs, 0).map(|t| t.with_timezone(&Local).naive_local())
+        })
+}
+
+// ============================================================================
+// Writing
+// ============================================================================
+
+/// Database a sample is written to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Target {
+    Main,
+    Partition(usize),
+}
+
+struct PartitionTarget {
+    id: String,
+    file_path: String,
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    conn: Option<DatabaseConnection>,
+    layout: DetailLayout,
+    /// Parent ids known to exist in the file.
+    parents: HashSet<i32>,
+    imported: u64,
+}
+
+/// One buffered sample.
+struct Sample {
+    slot: usize,
+    time: String,
+    value: String,
+}
+
+struct Importer<'a> {
+    db: &'a DatabaseConnection,
+    opts: &'a ImportOptions,
+    default_panel: Option<i32>,
+    config: DatabasePartitionConfig,
+    cache: TrendlogParentCache,
+    main_layout: DetailLayout,
+    partitions: Vec<PartitionTarget>,
+    /// Samples older than this go to a new partition when none covers them.
+    earliest_partition: Option<NaiveDateTime>,
+    slots: HashMap<PointKey, usize>,
+    buffers: HashMap<Target, Vec<Sample>>,
+    pending: usize,
+    range: Option<(NaiveDateTime, NaiveDateTime)>,
+    rebuild_rollups: bool,
+    summary: ImportSummary,
+}
+
+impl<'a> Importer<'a> {
+    async fn new(db: &'a DatabaseConnection, mapping: &ImportMapping, opts: &'a ImportOptions) -> Result<Self> {
+        let config = DatabaseConfigService::get_config(db).await?;
+        let mut partitions: Vec<PartitionTarget> = database_files::Entity::find()
+            .filter(database_files::Column::PartitionIdentifier.is_not_null())
+            .filter(database_files::Column::IsActive.eq(false))
+            .all(db)
+            .await?
+            .into_iter()
+            .filter_map(|p| {
+                Some(PartitionTarget {
+                    id: p.partition_identifier?,
+                    file_path: p.file_path,
+                    start: p.start_date?,
+                    end: p.end_date?,
+                    conn: None,
+                    layout: DetailLayout::Numeric,
+                    parents: HashSet::new(),
+                    imported: 0,
+                })
+            })
+            .collect();
+        partitions.sort_by_key(|p| p.start);
+
+        Ok(Self {
+            db,
+            opts,
+            default_panel: mapping.panel_id,
+            earliest_partition: partitions.first().map(|p| p.start),
+            config,
+            cache: TrendlogParentCache::new(10_000),
+            main_layout: DetailLayout::detect(db, "main").await?,
+            partitions,
+            slots: HashMap::new(),
+            buffers: HashMap::new(),
+            pending: 0,
+            range: None,
+            rebuild_rollups: mapping.rebuild_rollups.unwrap_or(true),
+            summary: ImportSummary::default(),
+        })
+    }
+
+    /// Buffer one sample for its target database.
+    async fn push(&mut self, key: PointKey, time: NaiveDateTime, value: &str) -> Result<()> {
+        let slot = match self.slots.get(&key) {
+            Some(slot) => *slot,
+            None => self.resolve_point(key).await?,
+        };
+        let target = self.route(time).await?;
+        self.buffers.entry(target).or_default().push(Sample {
+            slot,
+            time: time.format(trendlog_detail::FMT).to_string(),
+            value: value.to_string(),
+        });
+        self.pending += 1;
+        self.range = Some(match self.range {
+            Some((start, end)) => (start.min(time), end.max(time)),
+            None => (time, time),
+        });
+        Ok(())
+    }
+
+    /// Find or create the point's parent in the main database.
+    async fn resolve_point(&mut self, key: PointKey) -> Result<usize> {
+        #[derive(FromQueryResult)]
+        struct Existing {
+            panel_id: i32,
+            point_id: String,
+            digital_analog: Option<String>,
+            range_field: Option<String>,
+            units: Option<String>,
+        }
+
+        let existing = Existing::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::Sqlite,
+            "SELECT PanelId AS panel_id, PointId AS point_id, Digital_Analog AS digital_analog, \
+                    Range_Field AS range_field, Units AS units \
+             FROM TRENDLOG_DATA WHERE SerialNumber = ? AND PointType = ? AND PointIndex = ? ORDER BY id LIMIT 1",
+            [key.serial_number.into(), key.point_type.clone().into(), key.point_index.into()],
+        ))
+        .one(self.db)
+        .await?;
+
+        let (parent_key, digital_analog, range_field, units) = match existing {
+            Some(p) => (
+                ParentKey {
+                    serial_number: key.serial_number,
+                    panel_id: p.panel_id,
+                    point_id: p.point_id,
+                    point_index: key.point_index,
+                    point_type: key.point_type.clone(),
+                },
+                p.digital_analog,
+                p.range_field,
+                p.units,
+            ),
+            None => {
+                let panel_id = match self.default_panel {
+                    Some(panel) => panel,
+                    None => self.device_panel(key.serial_number).await,
+                };
+                let prefix = match key.point_type.as_str() {
+                    "INPUT" => "IN",
+                    "OUTPUT" => "OUT",
+                    _ => "VAR",
+                };
+                let parent_key = ParentKey {
+                    serial_number: key.serial_number,
+                    panel_id,
+                    point_id: format!("{}{}", prefix, key.point_index),
+                    point_index: key.point_index,
+                    point_type: key.point_type.clone(),
+                };
+                (parent_key, None, None, None)
+            }
+        };
+
+        let point_id = parent_key.point_id.clone();
+        let parent_id = self
+            .cache
+            .get_or_create_parent(self.db, parent_key, digital_analog, range_field, units)
+            .await
+            .map_err(|e| Error::ServerError(format!("Failed to resolve trendlog parent: {}", e)))?;
+
+        let slot = self.summary.points.len();
+        self.summary.points.push(PointImportSummary {
+            serial_number: key.serial_number,
+            point_type: key.point_type.clone(),
+            point_index: key.point_index,
+            point_id,
+            parent_id,
+            ..Default::default()
+        });
+        self.slots.insert(key, slot);
+        Ok(slot)
+    }
+
+    async fn device_panel(&self, serial_number: i32) -> i32 {
+        self.db
+            .query_one(Statement::from_sql_and_values(
+                DbBackend::Sqlite,
+                "SELECT PanelId FROM DEVICES WHERE SerialNumber = ?",
+                [serial_number.into()],
+            ))
+            .await
+            .ok()
+            .flatten()
+            .and_then(|row| row.try_get::<Option<i32>>("", "PanelId").ok().flatten())
+            .unwrap_or(0)
+    }
+
+    /// Pick the database for a sample logged at `time`.
+    async fn route(&mut self, time: NaiveDateTime) -> Result<Target> {
+        if !self.config.is_active {
+            return Ok(Target::Main);
+        }
+        if let Some(i) = self.partitions.iter().position(|p| p.start <= time && time <= p.end) {
+            return Ok(Target::Partition(i));
+        }
+        let Some(earliest) = self.earliest_partition else {
+            return Ok(Target::Main);
+        };
+        let (start, end) = calculate_period_boundaries(&self.config, time.date());
+        if end >= earliest {
+            // Period the partition monitor still owns (or one that overlaps a file)
+            return Ok(Target::Main);
+        }
+        let id = generate_partition_identifier(&self.config, &time.date());
+        let partition = create_partition(self.db, self.main_layout, &self.opts.partition_dir, &id, start, end).await?;
+        self.summary.partitions_created.push(id);
+        self.partitions.push(partition);
+        Ok(Target::Partition(self.partitions.len() - 1))
+    }
+
+    /// Write every buffered sample.
+    async fn flush(&mut self) -> Result<()> {
+        let buffers = std::mem::take(&mut self.buffers);
+        self.pending = 0;
+        for (target, samples) in buffers {
+            let parent_ids: Vec<i32> = samples.iter().map(|s| self.summary.points[s.slot].parent_id).collect();
+            let (conn, layout, name) = match target {
+                Target::Main => (self.db.clone(), self.main_layout, "main".to_string()),
+                Target::Partition(i) => {
+                    let partition = &mut self.partitions[i];
+                    if partition.conn.is_none() {
+                        let conn = Database::connect(format!("sqlite://{}?mode=rw", partition.file_path))
+                            .await
+                            .map_err(|e| Error::ServerError(format!("Failed to open partition {}: {}", partition.id, e)))?;
+                        partition.layout = DetailLayout::detect(&conn, "main").await?;
+                        partition.conn = Some(conn);
+                    }
+                    let missing: Vec<i32> = parent_ids
+                        .iter()
+                        .copied()
+                        .filter(|id| !partition.parents.contains(id))
+                        .collect::<HashSet<_>>()
+                        .into_iter()
+                        .collect();
+                    let conn = partition.conn.clone().unwrap_or_else(|| self.db.clone());
+                    if !missing.is_empty() {
+                        mirror_parents(self.db, &conn, Some(&missing)).await?;
+                        partition.parents.extend(missing);
+                    }
+                    (conn, partition.layout, partition.id.clone())
+                }
+            };
+
+            let written = self.write_samples(&conn, layout, &name, &samples, &parent_ids).await?;
+            if let Target::Partition(i) = target {
+                self.partitions[i].imported += written;
+            }
+        }
+        Ok(())
+    }
+
+    /// Insert samples that aren't already stored; returns how many were written.
+    async fn write_samples(
+        &mut self,
+        conn: &DatabaseConnection,
+        layout: DetailLayout,
+        name: &str,
+        samples: &[Sample],
+        parent_ids: &[i32],
+    ) -> Result<u64> {
+        // Existing times per parent within the batch's range
+        let mut seen: HashSet<(i32, String)> = HashSet::new();
+        let mut bounds: HashMap<i32, (&str, &str)> = HashMap::new();
+        for (sample, &parent) in samples.iter().zip(parent_ids) {
+            let entry = bounds.entry(parent).or_insert((sample.time.as_str(), sample.time.as_str()));
+            entry.0 = entry.0.min(sample.time.as_str());
+            entry.1 = entry.1.max(sample.time.as_str());
+        }
+        for (parent, (start, end)) in &bounds {
+            let rows = conn
+                .query_all(Statement::from_sql_and_values(
+                    DbBackend::Sqlite,
+                    "SELECT LoggingTime_Fmt FROM TRENDLOG_DATA_DETAIL \
+                     WHERE ParentId = ? AND LoggingTime_Fmt >= ? AND LoggingTime_Fmt <= ?",
+                    [(*parent).into(), (*start).into(), (*end).into()],
+                ))
+                .await?;
+            for row in rows {
+                seen.insert((*parent, row.try_get::<String>("", "LoggingTime_Fmt")?));
+            }
+        }
+
+        let mut rows: Vec<(i32, &Sample)> = Vec::new();
+        for (sample, &parent) in samples.iter().zip(parent_ids) {
+            let point = &mut self.summary.points[sample.slot];
+            if !seen.insert((parent, sample.time.clone())) {
+                point.duplicates += 1;
+                self.summary.duplicates += 1;
+                continue;
+            }
+            point.imported += 1;
+            *point.targets.entry(name.to_string()).or_default() += 1;
+            if point.first_time.as_deref().is_none_or(|t| sample.time.as_str() < t) {
+                point.first_time = Some(sample.time.clone());
+            }
+            if point.last_time.as_deref().is_none_or(|t| sample.time.as_str() > t) {
+                point.last_time = Some(sample.time.clone());
+            }
+            rows.push((parent, sample));
+        }
+
+        let txn = conn.begin().await?;
+        for chunk in rows.chunks(INSERT_CHUNK) {
+            let detail: Vec<(i32, &str, &str)> = chunk.iter().map(|(p, s)| (*p, s.value.as_str(), s.time.as_str())).collect();
+            insert_detail_rows(&txn, layout, &detail).await?;
+        }
+        txn.commit().await?;
+
+        self.summary.imported += rows.len() as u64;
+        Ok(rows.len() as u64)
+    }
+
+    /// Flush, update partition bookkeeping, rebuild rollups and close files.
+    async fn finish(mut self) -> Result<ImportSummary> {
+        self.flush().await?;
+
+        for partition in &mut self.partitions {
+            let Some(conn) = partition.conn.take() else { continue };
+            conn.close().await.ok();
+            if partition.imported == 0 {
+                continue;
+            }
+            let file_size = std::fs::metadata(&partition.file_path).map(|m| m.len() as i64).unwrap_or(0);
+            database_files::Entity::update_many()
+                .col_expr(
+                    database_files::Column::RecordCount,
+                    Expr::col(database_files::Column::RecordCount).add(partition.imported as i64),
+                )
+                .col_expr(database_files::Column::FileSizeBytes, Expr::value(file_size))
+                .filter(database_files::Column::PartitionIdentifier.eq(partition.id.as_str()))
+                .exec(self.db)
+                .await?;
+        }
+
+        if let (true, Some((start, end))) = (self.rebuild_rollups && self.summary.imported > 0, self.range) {
+            trendlog_rollup::rebuild(self.db, Some((start.date(), end.date()))).await?;
+            self.summary.rollups_rebuilt = true;
+        }
+        Ok(self.summary)
+    }
+}
+
+/// Multi-row insert of `(ParentId, Value, LoggingTime_Fmt)` detail rows.
+async fn insert_detail_rows<C: ConnectionTrait>(db: &C, layout: DetailLayout, rows: &[(i32, &str, &str)]) -> Result<()> {
+    if rows.is_empty() {
+        return Ok(());
+    }
+    let mut values: Vec<Value> = Vec::with_capacity(rows.len() * 5);
+    let sql = match layout {
+        DetailLayout::Legacy => {
+            for (parent, value, time) in rows {
+                values.extend([(*parent).into(), (*value).into(), (*time).into()]);
+            }
+            format!(
+                "INSERT INTO TRENDLOG_DATA_DETAIL (ParentId, Value, LoggingTime_Fmt) VALUES {}",
+                vec!["(?, ?, ?)"; rows.len()].join(", ")
+            )
+        }
+        DetailLayout::Numeric => {
+            for (parent, value, time) in rows {
+                values.extend([
+                    (*parent).into(),
+                    (*value).into(),
+                    (*time).into(),
+                    trendlog_detail::numeric_value(value).into(),
+                    trendlog_detail::epoch_from_fmt(time).into(),
+                ]);
+            }
+            format!(
+                "INSERT INTO TRENDLOG_DATA_DETAIL (ParentId, Value, LoggingTime_Fmt, NumericValue, LoggingTime) VALUES {}",
+                vec!["(?, ?, ?, ?, ?)"; rows.len()].join(", ")
+            )
+        }
+    };
+    db.execute(Statement::from_sql_and_values(DbBackend::Sqlite, sql, values)).await?;
+    Ok(())
+}
+
+/// Copy TRENDLOG_DATA rows (all of them when `ids` is `None`) from the main
+/// database into a partition file, keeping their ids.
+async fn mirror_parents(main: &DatabaseConnection, partition: &DatabaseConnection, ids: Option<&[i32]>) -> Result<()> {
+    #[derive(FromQueryResult)]
+    struct Parent {
+        id: i32,
+        serial_number: i32,
+        panel_id: i32,
+        point_id: String,
+        point_index: i32,
+        point_type: String,
+        digital_analog: Option<String>,
+        range_field: Option<String>,
+        units: Option<String>,
+    }
+
+    let filter = match ids {
+        Some(ids) => format!("WHERE id IN ({})", ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(",")),
+        None => String::new(),
+    };
+    let parents = Parent::find_by_statement(Statement::from_string(
+        DbBackend::Sqlite,
+        format!(
+            "SELECT id, SerialNumber AS serial_number, PanelId AS panel_id, PointId AS point_id, \
+                    PointIndex AS point_index, PointType AS point_type, Digital_Analog AS digital_analog, \
+                    Range_Field AS range_field, Units AS units \
+             FROM TRENDLOG_DATA {}",
+            filter
+        ),
+    ))
+    .all(main)
+    .await?;
+
+    let txn = partition.begin().await?;
+    for p in parents {
+        txn.execute(Statement::from_sql_and_values(
+            DbBackend::Sqlite,
+            "INSERT OR IGNORE INTO TRENDLOG_DATA \
+             (id, SerialNumber, PanelId, PointId, PointIndex, PointType, Digital_Analog, Range_Field, Units) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            [
+                p.id.into(),
+                p.serial_number.into(),
+                p.panel_id.into(),
+                p.point_id.into(),
+                p.point_index.into(),
+                p.point_type.into(),
+                p.digital_analog.into(),
+                p.range_field.into(),
+                p.units.into(),
+            ],
+        ))
+        .await?;
+    }
+    txn.commit().await?;
+    Ok(())
+}
+
+/// Create and register a partition file for `[start, end]`, seeded like the
+/// partition monitor's: every parent, plus the main database's detail rows
+/// for the period. Those rows are moved, not copied: once the file is
+/// registered they are deleted from main, so no sample is in both.
+async fn create_partition(
+    db: &DatabaseConnection,
+    main_layout: DetailLayout,
+    dir: &Path,
+    id: &str,
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+) -> Result<PartitionTarget> {
+    #[derive(FromQueryResult)]
+    struct Row {
+        row_id: i64,
+        parent_id: i32,
+        value: String,
+        logging_time_fmt: String,
+    }
+
+    std::fs::create_dir_all(dir)
+        .map_err(|e| Error::ServerError(format!("Failed to create partition folder {}: {}", dir.display(), e)))?;
+    let file_name = format!("webview_t3_device_{}.db", id);
+    let file_path = dir.join(&file_name);
+    let conn = Database::connect(format!("sqlite://{}?mode=rwc", file_path.display()))
+        .await
+        .map_err(|e| Error::ServerError(format!("Failed to create partition {}: {}", id, e)))?;
+    conn.execute_unprepared(EMBEDDED_SCHEMA).await?;
+    mirror_parents(db, &conn, None).await?;
+
+    // Main database rows for the period, by rowid
+    let sql = format!(
+        "SELECT d.rowid AS row_id, d.ParentId AS parent_id, d.Value AS value, d.LoggingTime_Fmt AS logging_time_fmt \
+         FROM TRENDLOG_DATA_DETAIL d WHERE {} AND d.rowid > ? ORDER BY d.rowid LIMIT ?",
+        main_layout.time_range_sql("d", Some(start), Some(end))
+    );
+    let mut last_rowid = 0i64;
+    let mut copied = 0i64;
+    loop {
+        let page = Row::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::Sqlite,
+            &sql,
+            [last_rowid.into(), (INSERT_CHUNK as i64).into()],
+        ))
+        .all(db)
+        .await?;
+        let Some(last) = page.last() else { break };
+        last_rowid = last.row_id;
+        let rows: Vec<(i32, &str, &str)> =
+            page.iter().map(|r| (r.parent_id, r.value.as_str(), r.logging_time_fmt.as_str())).collect();
+        insert_detail_rows(&conn, DetailLayout::Numeric, &rows).await?;
+        copied += rows.len() as i64;
+    }
+
+    let now = chrono::Utc::now().naive_utc();
+    database_files::ActiveModel {
+        file_name: Set(file_name),
+        file_path: Set(file_path.to_string_lossy().to_string()),
+        partition_identifier: Set(Some(id.to_string())),
+        file_size_bytes: Set(std::fs::metadata(&file_path).map(|m| m.len() as i64).unwrap_or(0)),
+        record_count: Set(copied),
+        start_date: Set(Some(start)),
+        end_date: Set(Some(end)),
+        is_active: Set(false),
+        is_archived: Set(false),
+        created_at: Set(now),
+        last_accessed_at: Set(now),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    // Only rows that were copied: anything logged into the period since has a
+    // higher rowid and stays in main for the partition monitor.
+    let delete = format!(
+        "DELETE FROM TRENDLOG_DATA_DETAIL AS d WHERE {} AND d.rowid <= ?",
+        main_layout.time_range_sql("d", Some(start), Some(end))
+    );
+    db.execute(Statement::from_sql_and_values(DbBackend::Sqlite, &delete, [last_rowid.into()]))
+        .await?;
+
+    let parents = parent_ids(db).await?;
+    Ok(PartitionTarget {
+        id: id.to_string(),
+        file_path: file_path.to_string_lossy().to_string(),
+        start,
+        end,
+        conn: Some(conn),
+        layout: DetailLayout::Numeric,
+        parents,
+        imported: 0,
+    })
+}
+
+/// Ids of every TRENDLOG_DATA row.
+async fn parent_ids(db: &DatabaseConnection) -> Result<HashSet<i32>> {
+    let rows = db
+        .query_all(Statement::from_string(DbBackend::Sqlite, "SELECT id FROM TRENDLOG_DATA".to_string()))
+        .await?;
+    Ok(rows.iter().filter_map(|r| r.try_get::<i32>("", "id").ok()).collect())
+}
@@ -0,0 +1,619 @@
+//! Trendlog rollups — hourly and daily summaries of TRENDLOG_DATA_DETAIL.
+//!
+//! Dashboards and FDD runs mostly need per-hour or per-day figures, but raw
+//! detail rows store `Value` as text and have to be re-parsed and regrouped
+//! on every read. TRENDLOG_ROLLUP_HOURLY and TRENDLOG_ROLLUP_DAILY keep
+//! min/max/sum/count/last per ParentId and local-clock bucket instead:
+//!
+//! - **Incremental**: every detail write folds the row in as it is written
+//!   (`trendlog_detail::insert_detail` / [`record_detail`] locally,
+//!   `mssql_queries::insert_trendlog_detail` on the MSSQL center DB). This is
+//!   best-effort: a failed upsert is logged and never fails the raw write, and
+//!   the buckets it missed are repaired by a rebuild.
+//! - **Rebuild**: [`rebuild`] recomputes buckets from the raw rows still
+//!   available (main DB and partition files). Buckets without raw data are
+//!   left alone, so purged history is never lost by a rebuild.
+//! - **Retention**: [`RollupRetention`] is stored in APPLICATION_CONFIG and
+//!   is independent of partition retention, so rollups outlive raw data.
+//!
+//! Values keep the raw stored scale, like the partition query API.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+
+use crate::db_connection::establish_t3_device_connection;
+use crate::entity::database_files;
+use crate::error::Result;
+use super::trendlog_detail::DetailLayout;
+use super::{mssql_queries, ApplicationConfigService};
+
+/// APPLICATION_CONFIG key holding the [`RollupRetention`] JSON.
+pub const RETENTION_CONFIG_KEY: &str = "trendlog.rollup_retention";
+/// Default row cap for [`query`].
+pub const DEFAULT_QUERY_LIMIT: u64 = 50_000;
+/// Buckets per multi-row INSERT (8 parameters each).
+const BUCKET_CHUNK: usize = 500;
+
+/// Rollup bucket size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Resolution {
+    #[default]
+    Hourly,
+    Daily,
+}
+
+impl Resolution {
+    pub const ALL: [Resolution; 2] = [Resolution::Hourly, Resolution::Daily];
+
+    pub fn table(self) -> &'static str {
+        match self {
+            Resolution::Hourly => "TRENDLOG_ROLLUP_HOURLY",
+            Resolution::Daily => "TRENDLOG_ROLLUP_DAILY",
+        }
+    }
+
+    /// Bucket a `LoggingTime_Fmt` value falls in:
+    /// "YYYY-MM-DD HH:00:00" (hourly) or "YYYY-MM-DD 00:00:00" (daily).
+    pub fn bucket_start(self, logging_time_fmt: &str) -> Option<String> {
+        let t = parse_logging_time(logging_time_fmt)?;
+        Some(match self {
+            Resolution::Hourly => t.format("%Y-%m-%d %H:00:00").to_string(),
+            Resolution::Daily => t.format("%Y-%m-%d 00:00:00").to_string(),
+        })
+    }
+
+    /// SQLite expression for the bucket of a detail row.
+    fn bucket_sql(self) -> &'static str {
+        match self {
+            Resolution::Hourly => "replace(substr(LoggingTime_Fmt, 1, 13), 'T', ' ') || ':00:00'",
+            Resolution::Daily => "substr(LoggingTime_Fmt, 1, 10) || ' 00:00:00'",
+        }
+    }
+}
+
+fn parse_logging_time(s: &str) -> Option<NaiveDateTime> {
+    let s = s.trim();
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S"))
+        .ok()
+}
+
+/// How long rollups are kept, in days; 0 keeps them forever.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RollupRetention {
+    #[serde(default = "default_hourly_days")]
+    pub hourly_days: u32,
+    #[serde(default)]
+    pub daily_days: u32,
+}
+
+fn default_hourly_days() -> u32 {
+    // Thirteen months: enough for year-over-year hourly comparisons
+    400
+}
+
+impl Default for RollupRetention {
+    fn default() -> Self {
+        Self { hourly_days: default_hourly_days(), daily_days: 0 }
+    }
+}
+
+impl RollupRetention {
+    pub fn days(&self, resolution: Resolution) -> u32 {
+        match resolution {
+            Resolution::Hourly => self.hourly_days,
+            Resolution::Daily => self.daily_days,
+        }
+    }
+
+    /// Oldest bucket start kept at `now`, or `None` when kept forever.
+    pub fn cutoff(&self, resolution: Resolution, now: NaiveDateTime) -> Option<String> {
+        match self.days(resolution) {
+            0 => None,
+            days => Some((now - Duration::days(days as i64)).format("%Y-%m-%d %H:%M:%S").to_string()),
+        }
+    }
+}
+
+// ============================================================================
+// Incremental maintenance
+// ============================================================================
+
+/// Fold one detail sample into the hourly and daily rollups.
+/// Works on SQLite, PostgreSQL and MySQL connections (and transactions).
+pub async fn record<C: ConnectionTrait>(
+    db: &C,
+    parent_id: i32,
+    value: f64,
+    logging_time_fmt: &str,
+) -> std::result::Result<(), DbErr> {
+    if !value.is_finite() {
+        return Ok(());
+    }
+    let sample = Bucket {
+        parent_id,
+        bucket_start: String::new(),
+        min_value: value,
+        max_value: value,
+        sum_value: value,
+        sample_count: 1,
+        last_value: value,
+        last_time: logging_time_fmt.to_string(),
+    };
+    for resolution in Resolution::ALL {
+        let Some(bucket_start) = resolution.bucket_start(logging_time_fmt) else {
+            return Ok(());
+        };
+        upsert_samples(db, resolution, &[Bucket { bucket_start, ..sample.clone() }]).await?;
+    }
+    Ok(())
+}
+
+/// Fold a detail row that was just written into the rollups, skipping
+/// non-numeric values like a rebuild does. Best-effort: a failure is logged,
+/// not returned, so it never fails the raw write; [`rebuild`] repairs it.
+pub async fn record_detail<C: ConnectionTrait>(db: &C, parent_id: i32, value: &str, logging_time_fmt: &str) {
+    let Some(value) = super::trendlog_detail::numeric_value(value) else { return };
+    if let Err(e) = record(db, parent_id, value, logging_time_fmt).await {
+        tracing::warn!("Trendlog rollup update failed - Parent: {}, Time: {}, Error: {}", parent_id, logging_time_fmt, e);
+    }
+}
+
+/// [`record_detail`] for a batch of `(parent, value)` rows written at one
+/// `logging_time_fmt`: the rows are aggregated per parent first, then each
+/// resolution gets one multi-row upsert. Best-effort like [`record_detail`].
+pub async fn record_details<C: ConnectionTrait>(db: &C, rows: &[(i32, &str)], logging_time_fmt: &str) {
+    let mut per_parent: BTreeMap<i32, Bucket> = BTreeMap::new();
+    for &(parent_id, value) in rows {
+        let Some(value) = super::trendlog_detail::numeric_value(value).filter(|v| v.is_finite()) else { continue };
+        per_parent
+            .entry(parent_id)
+            .and_modify(|b| {
+                b.min_value = b.min_value.min(value);
+                b.max_value = b.max_value.max(value);
+                b.sum_value += value;
+                b.sample_count += 1;
+                b.last_value = value;
+            })
+            .or_insert_with(|| Bucket {
+                parent_id,
+                bucket_start: String::new(),
+                min_value: value,
+                max_value: value,
+                sum_value: value,
+                sample_count: 1,
+                last_value: value,
+                last_time: logging_time_fmt.to_string(),
+            });
+    }
+    if per_parent.is_empty() {
+        return;
+    }
+    for resolution in Resolution::ALL {
+        let Some(bucket_start) = resolution.bucket_start(logging_time_fmt) else { return };
+        let samples: Vec<Bucket> = per_parent
+            .values()
+            .map(|b| Bucket { bucket_start: bucket_start.clone(), ..b.clone() })
+            .collect();
+        if let Err(e) = upsert_samples(db, resolution, &samples).await {
+            tracing::warn!(
+                "Trendlog rollup batch update failed - {} parents, Time: {}, Error: {}",
+                samples.len(),
+                logging_time_fmt,
+                e
+            );
+        }
+    }
+}
+
+/// Merge pre-aggregated samples into their buckets, `BUCKET_CHUNK` per
+/// multi-row upsert.
+async fn upsert_samples<C: ConnectionTrait>(
+    db: &C,
+    resolution: Resolution,
+    samples: &[Bucket],
+) -> std::result::Result<(), DbErr> {
+    let backend = db.get_database_backend();
+    for chunk in samples.chunks(BUCKET_CHUNK) {
+        let mut values: Vec<sea_orm::Value> = Vec::with_capacity(chunk.len() * 8);
+        for b in chunk {
+            values.extend([
+                b.parent_id.into(),
+                b.bucket_start.clone().into(),
+                b.min_value.into(),
+                b.max_value.into(),
+                b.sum_value.into(),
+                b.sample_count.into(),
+                b.last_value.into(),
+                b.last_time.clone().into(),
+            ]);
+        }
+        db.execute(Statement::from_sql_and_values(
+            backend,
+            upsert_samples_sql(backend, resolution.table(), chunk.len()),
+            values,
+        ))
+        .await?;
+    }
+    Ok(())
+}
+
+/// Upsert of `rows` samples (parent, bucket, min, max, sum, count, last, last time).
+fn upsert_samples_sql(backend: DatabaseBackend, table: &str, rows: usize) -> String {
+    let columns = "ParentId, BucketStart, MinValue, MaxValue, SumValue, SampleCount, LastValue, LastTime";
+    let placeholders = match backend {
+        DatabaseBackend::Postgres => (0..rows)
+            .map(|r| format!("({})", (1..=8).map(|i| format!("${}", r * 8 + i)).collect::<Vec<_>>().join(", ")))
+            .collect::<Vec<_>>()
+            .join(", "),
+        _ => vec!["(?, ?, ?, ?, ?, ?, ?, ?)"; rows].join(", "),
+    };
+    match backend {
+        DatabaseBackend::Sqlite => format!(
+            "INSERT INTO {t} ({columns}) VALUES {placeholders} \
+             ON CONFLICT (ParentId, BucketStart) DO UPDATE SET \
+               MinValue = min(MinValue, excluded.MinValue), \
+               MaxValue = max(MaxValue, excluded.MaxValue), \
+               SumValue = SumValue + excluded.SumValue, \
+               SampleCount = SampleCount + excluded.SampleCount, \
+               LastValue = CASE WHEN excluded.LastTime >= LastTime THEN excluded.LastValue ELSE LastValue END, \
+               LastTime = max(LastTime, excluded.LastTime)",
+            t = table
+        ),
+        DatabaseBackend::Postgres => format!(
+            "INSERT INTO {t} ({columns}) VALUES {placeholders} \
+             ON CONFLICT (ParentId, BucketStart) DO UPDATE SET \
+               MinValue = LEAST({t}.MinValue, EXCLUDED.MinValue), \
+               MaxValue = GREATEST({t}.MaxValue, EXCLUDED.MaxValue), \
+               SumValue = {t}.SumValue + EXCLUDED.SumValue, \
+               SampleCount = {t}.SampleCount + EXCLUDED.SampleCount, \
+               LastValue = CASE WHEN EXCLUDED.LastTime >= {t}.LastTime THEN EXCLUDED.LastValue ELSE {t}.LastValue END, \
+               LastTime = GREATEST({t}.LastTime, EXCLUDED.LastTime)",
+            t = table
+        ),
+        // MySQL applies assignments left to right, so LastValue is decided
+        // before LastTime moves
+        DatabaseBackend::MySql => format!(
+            "INSERT INTO {t} ({columns}) VALUES {placeholders} \
+             ON DUPLICATE KEY UPDATE \
+               MinValue = LEAST(MinValue, VALUES(MinValue)), \
+               MaxValue = GREATEST(MaxValue, VALUES(MaxValue)), \
+               SumValue = SumValue + VALUES(SumValue), \
+               SampleCount = SampleCount + VALUES(SampleCount), \
+               LastValue = IF(VALUES(LastTime) >= LastTime, VALUES(LastValue), LastValue), \
+               LastTime = GREATEST(LastTime, VALUES(LastTime))",
+            t = table
+        ),
+    }
+}
+
+// ============================================================================
+// Rebuild from raw data (local SQLite)
+// ============================================================================
+
+/// One recomputed bucket.
+#[derive(Debug, Clone, FromQueryResult)]
+struct Bucket {
+    parent_id: i32,
+    bucket_start: String,
+    min_value: f64,
+    max_value: f64,
+    sum_value: f64,
+    sample_count: i64,
+    last_value: f64,
+    last_time: String,
+}
+
+/// What a [`rebuild`] did.
+#[derive(Debug, Default, Serialize)]
+pub struct RebuildSummary {
+    /// Sources read: "main" and partition identifiers, in order.
+    pub sources: Vec<String>,
+    /// Partitions that could not be read, with the reason.
+    pub skipped: Vec<String>,
+    pub hourly_buckets: u64,
+    pub daily_buckets: u64,
+}
+
+/// Recompute rollups from raw detail rows for the days in `range` (all data
+/// when `None`). The main database is read first, then every partition file
+/// (archived ones included) from oldest to newest; each source replaces the
+/// buckets it has rows for, so a closed period's partition wins over a main
+/// database that has since been trimmed.
+pub async fn rebuild(
+    db: &DatabaseConnection,
+    range: Option<(NaiveDate, NaiveDate)>,
+) -> Result<RebuildSummary> {
+    let bounds = range.map(|(start, end)| {
+        (
+            start.and_time(NaiveTime::MIN),
+            end.and_time(NaiveTime::MIN) + Duration::days(1) - Duration::seconds(1),
+        )
+    });
+    let mut summary = RebuildSummary::default();
+
+    rebuild_from(db, db, &bounds, &mut summary).await?;
+    summary.sources.push("main".to_string());
+
+    let mut partitions = database_files::Entity::find()
+        .filter(database_files::Column::PartitionIdentifier.is_not_null())
+        .filter(database_files::Column::IsActive.eq(false))
+        .all(db)
+        .await?;
+    partitions.retain(|p| match (range, p.start_date, p.end_date) {
+        (Some((start, end)), Some(p_start), Some(p_end)) => p_start.date() <= end && start <= p_end.date(),
+        _ => true,
+    });
+    partitions.sort_by_key(|p| p.start_date);
+
+    for partition in partitions {
+        let id = partition.partition_identifier.clone().unwrap_or_default();
+        if !Path::new(&partition.file_path).exists() {
+            summary.skipped.push(format!("{}: file not found at {}", id, partition.file_path));
+            continue;
+        }
+        let url = format!("sqlite://{}?mode=ro", partition.file_path);
+        let source = match Database::connect(&url).await {
+            Ok(conn) => conn,
+            Err(e) => {
+                summary.skipped.push(format!("{}: {}", id, e));
+                continue;
+            }
+        };
+        let result = rebuild_from(&source, db, &bounds, &mut summary).await;
+        source.close().await.ok();
+        match result {
+            Ok(()) => summary.sources.push(id),
+            Err(e) => summary.skipped.push(format!("{}: {}", id, e)),
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Aggregate `source`'s detail rows in `bounds` and write them to `target`.
+async fn rebuild_from(
+    source: &DatabaseConnection,
+    target: &DatabaseConnection,
+    bounds: &Option<(NaiveDateTime, NaiveDateTime)>,
+    summary: &mut RebuildSummary,
+) -> Result<()> {
+    let layout = DetailLayout::detect(source, "main").await?;
+    let filter = layout.time_range_sql("d", bounds.map(|b| b.0), bounds.map(|b| b.1));
+    for resolution in Resolution::ALL {
+        let sql = format!(
+            "SELECT ParentId AS parent_id, Bucket AS bucket_start, \
+                    MIN(v) AS min_value, MAX(v) AS max_value, SUM(v) AS sum_value, \
+                    COUNT(*) AS sample_count, MAX(CASE WHEN rn = 1 THEN v END) AS last_value, \
+                    MAX(LoggingTime_Fmt) AS last_time \
+             FROM ( \
+                 SELECT d.ParentId, d.LoggingTime_Fmt, {value} AS v, {bucket} AS Bucket, \
+                        ROW_NUMBER() OVER (PARTITION BY d.ParentId, {bucket} \
+                                           ORDER BY d.LoggingTime_Fmt DESC, d.rowid DESC) AS rn \
+                 FROM TRENDLOG_DATA_DETAIL d \
+                 WHERE {filter} \
+             ) \
+             GROUP BY ParentId, Bucket",
+            bucket = resolution.bucket_sql(),
+            value = layout.value_sql("d"),
+        );
+        let buckets = Bucket::find_by_statement(Statement::from_sql_and_values(
+            DatabaseBackend::Sqlite,
+            sql,
+            [],
+        ))
+        .all(source)
+        .await?;
+
+        let written = replace_buckets(target, resolution, &buckets).await?;
+        match resolution {
+            Resolution::Hourly => summary.hourly_buckets += written,
+            Resolution::Daily => summary.daily_buckets += written,
+        }
+    }
+    Ok(())
+}
+
+/// Insert or overwrite whole buckets, in one transaction.
+async fn replace_buckets(db: &DatabaseConnection, resolution: Resolution, buckets: &[Bucket]) -> Result<u64> {
+    let txn = db.begin().await?;
+    let mut written = 0;
+    for chunk in buckets.chunks(BUCKET_CHUNK) {
+        let placeholders = vec!["(?, ?, ?, ?, ?, ?, ?, ?)"; chunk.len()].join(", ");
+        let sql = format!(
+            "INSERT INTO main.{t} (ParentId, BucketStart, MinValue, MaxValue, SumValue, SampleCount, LastValue, LastTime) \
+             VALUES {placeholders} \
+             ON CONFLICT (ParentId, BucketStart) DO UPDATE SET \
+               MinValue = excluded.MinValue, MaxValue = excluded.MaxValue, SumValue = excluded.SumValue, \
+               SampleCount = excluded.SampleCount, LastValue = excluded.LastValue, LastTime = excluded.LastTime",
+            t = resolution.table()
+        );
+        let mut values: Vec<sea_orm::Value> = Vec::with_capacity(chunk.len() * 8);
+        for b in chunk {
+            values.extend([
+                b.parent_id.into(),
+                b.bucket_start.clone().into(),
+                b.min_value.into(),
+                b.max_value.into(),
+                b.sum_value.into(),
+                b.sample_count.into(),
+                b.last_value.into(),
+                b.last_time.clone().into(),
+            ]);
+        }
+        written += txn
+            .execute(Statement::from_sql_and_values(DatabaseBackend::Sqlite, sql, values))
+            .await?
+            .rows_affected();
+    }
+    txn.commit().await?;
+    Ok(written)
+}
+
+// ============================================================================
+// Retention
+// ============================================================================
+
+/// Saved retention, or the default when none is saved (or it is unreadable).
+pub async fn load_retention(db: &DatabaseConnection) -> RollupRetention {
+    ApplicationConfigService::get_config(db, RETENTION_CONFIG_KEY, None, None, None)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|config| serde_json::from_str(&config.config_value).ok())
+        .unwrap_or_default()
+}
+
+pub async fn save_retention(db: &DatabaseConnection, retention: &RollupRetention) -> Result<()> {
+    let value = serde_json::to_value(retention)
+        .map_err(|e| crate::error::Error::ServerError(format!("Failed to encode rollup retention: {}", e)))?;
+    ApplicationConfigService::set_config(db, RETENTION_CONFIG_KEY.to_string(), value, None, None, None, None).await?;
+    Ok(())
+}
+
+/// Rows deleted by [`apply_retention`].
+#[derive(Debug, Default, Serialize)]
+pub struct PruneSummary {
+    pub hourly_deleted: u64,
+    pub daily_deleted: u64,
+}
+
+/// Delete rollup buckets older than `retention` allows at `now`.
+pub async fn apply_retention(
+    db: &DatabaseConnection,
+    retention: &RollupRetention,
+    now: NaiveDateTime,
+) -> Result<PruneSummary> {
+    let mut summary = PruneSummary::default();
+    for resolution in Resolution::ALL {
+        let Some(cutoff) = retention.cutoff(resolution, now) else { continue };
+        let deleted = db
+            .execute(Statement::from_sql_and_values(
+                DatabaseBackend::Sqlite,
+                format!("DELETE FROM {} WHERE BucketStart < ?", resolution.table()),
+                [cutoff.into()],
+            ))
+            .await?
+            .rows_affected();
+        match resolution {
+            Resolution::Hourly => summary.hourly_deleted = deleted,
+            Resolution::Daily => summary.daily_deleted = deleted,
+        }
+    }
+    Ok(summary)
+}
+
+/// Prune with the saved retention: the local rollups, plus the MSSQL center
+/// DB's when it is active. Run hourly by the partition monitor.
+pub async fn enforce_retention() -> Result<PruneSummary> {
+    let db = establish_t3_device_connection()
+        .await
+        .map_err(|e| crate::error::Error::ServerError(format!("Database connection failed: {}", e)))?;
+    let retention = load_retention(&db).await;
+    // Bucket starts are local time, like LoggingTime_Fmt
+    let now = chrono::Local::now().naive_local();
+    let mut summary = apply_retention(&db, &retention, now).await?;
+
+    if let Some(pool) = crate::server_db_writer::get_server_mssql_pool() {
+        for resolution in Resolution::ALL {
+            let Some(cutoff) = retention.cutoff(resolution, now) else { continue };
+            let deleted = mssql_queries::prune_trendlog_rollups(pool, resolution, &cutoff)
+                .await
+                .map_err(crate::error::Error::DbError)?;
+            match resolution {
+                Resolution::Hourly => summary.hourly_deleted += deleted,
+                Resolution::Daily => summary.daily_deleted += deleted,
+            }
+        }
+    }
+    Ok(summary)
+}
+
+// ============================================================================
+// Query
+// ============================================================================
+
+/// Filters for [`query`]. Times are "YYYY-MM-DD HH:MM:SS" (or with a `T`)
+/// and match bucket starts.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RollupQuery {
+    #[serde(default)]
+    pub resolution: Resolution,
+    pub start: Option<String>,
+    pub end: Option<String>,
+    pub serial_number: Option<i32>,
+    pub panel_id: Option<i32>,
+    pub point_id: Option<String>,
+    pub point_type: Option<String>,
+    pub limit: Option<u64>,
+}
+
+/// One rollup bucket of one point.
+#[derive(Debug, Clone, Serialize, FromQueryResult)]
+pub struct RollupRow {
+    pub serial_number: i32,
+    pub panel_id: i32,
+    pub point_id: String,
+    pub point_index: i32,
+    pub point_type: String,
+    pub units: Option<String>,
+    pub bucket_start: String,
+    pub min_value: f64,
+    pub max_value: f64,
+    pub avg_value: f64,
+    pub sample_count: i64,
+    pub last_value: f64,
+    pub last_time: String,
+}
+
+/// Rollup buckets joined with their point, oldest first.
+pub async fn query(db: &DatabaseConnection, q: &RollupQuery) -> Result<Vec<RollupRow>> {
+    let mut sql = format!(
+        "SELECT td.SerialNumber AS serial_number, td.PanelId AS panel_id, td.PointId AS point_id, \
+                td.PointIndex AS point_index, td.PointType AS point_type, td.Units AS units, \
+                r.BucketStart AS bucket_start, r.MinValue AS min_value, r.MaxValue AS max_value, \
+                r.SumValue / r.SampleCount AS avg_value, r.SampleCount AS sample_count, \
+                r.LastValue AS last_value, r.LastTime AS last_time \
+         FROM {} r INNER JOIN TRENDLOG_DATA td ON td.id = r.ParentId \
+         WHERE 1 = 1",
+        q.resolution.table()
+    );
+    let mut values: Vec<sea_orm::Value> = Vec::new();
+    if let Some(start) = &q.start {
+        sql.push_str(" AND r.BucketStart >= ?");
+        values.push(start.replace('T', " ").into());
+    }
+    if let Some(end) = &q.end {
+        sql.push_str(" AND r.BucketStart <= ?");
+        values.push(end.replace('T', " ").into());
+    }
+    if let Some(serial) = q.serial_number {
+        sql.push_str(" AND td.SerialNumber = ?");
+        values.push(serial.into());
+    }
+    if let Some(panel) = q.panel_id {
+        sql.push_str(" AND td.PanelId = ?");
+        values.push(panel.into());
+    }
+    if let Some(point_id) = &q.point_id {
+        sql.push_str(" AND td.PointId = ?");
+        values.push(point_id.clone().into());
+    }
+    if let Some(point_type) = &q.point_type {
+        sql.push_str(" AND td.PointType = ?");
+        values.push(point_type.clone().into());
+    }
+    sql.push_str(&format!(
+        " ORDER BY r.BucketStart ASC, td.PointType, td.PointIndex LIMIT {}",
+        q.limit.unwrap_or(DEFAULT_QUERY_LIMIT)
+    ));
+
+    Ok(RollupRow::find_by_statement(Statement::from_sql_and_values(DatabaseBackend::Sqlite, sql, values))
+        .all(db)
+        .await?)
+}
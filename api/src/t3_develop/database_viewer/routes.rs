@@ -4,9 +4,10 @@
  * Endpoints for querying SQLite databases
  */
 
-use axum::{extract::Query, Json, http::StatusCode, response::IntoResponse};
+use axum::{extract::Query, Extension, Json, http::StatusCode, response::IntoResponse};
 use serde::{Deserialize, Serialize};
-use rusqlite::Connection;
+use rusqlite::{Connection, OpenFlags};
+use crate::auth::{CurrentUser, Role};
 use std::path::PathBuf;
 use std::fs;
 
@@ -142,7 +143,11 @@ pub async fn list_tables(Query(query): Query<serde_json::Value>) -> impl IntoRes
 }
 
 /// Execute SQL query
-pub async fn execute_query(Json(body): Json<QueryRequest>) -> impl IntoResponse {
+///
+/// Only admins may run statements that write: the account, session and MCP
+/// token tables live in these files, so a writable query would let anyone
+/// with engineer access grant themselves admin.
+pub async fn execute_query(user: Option<Extension<CurrentUser>>, Json(body): Json<QueryRequest>) -> impl IntoResponse {
     let db_path = get_database_path().join(&body.database);
 
     if !db_path.exists() || !db_path.is_file() {
@@ -153,7 +158,13 @@ pub async fn execute_query(Json(body): Json<QueryRequest>) -> impl IntoResponse
 
     let start = std::time::Instant::now();
 
-    let conn = match Connection::open(&db_path) {
+    let read_only = user.is_some_and(|Extension(CurrentUser(u))| u.role < Role::Admin);
+    let opened = if read_only {
+        Connection::open_with_flags(&db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+    } else {
+        Connection::open(&db_path)
+    };
+    let conn = match opened {
         Ok(c) => c,
         Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
             "error": format!("Failed to open database: {}", e)
@@ -167,6 +178,11 @@ pub async fn execute_query(Json(body): Json<QueryRequest>) -> impl IntoResponse
             "error": format!("SQL error: {}", e)
         }))),
     };
+    if read_only && !stmt.readonly() {
+        return (StatusCode::FORBIDDEN, Json(serde_json::json!({
+            "error": "Only admins can run statements that modify the database"
+        })));
+    }
 
     // Get column names
     let columns: Vec<String> = stmt.column_names().iter().map(|s| s.to_string()).collect();
@@ -237,6 +237,7 @@ async fn save_alarms_to_db(db: &DatabaseConnection, serial: i32, items: &[Value]
             .map_err(|e| format!("Database query error: {}", e))?;
 
         if let Some(alarm_model) = existing_alarm {
+            let before = alarm_model.clone();
             let mut active_model: alarms::ActiveModel = alarm_model.into();
 
             if let Some(val) = item.get("panel").and_then(|v| v.as_str()) {
@@ -259,6 +260,11 @@ async fn save_alarms_to_db(db: &DatabaseConnection, serial: i32, items: &[Value]
             }
 
             active_model.update(db).await.map_err(|e| format!("Failed to update alarm: {}", e))?;
+            crate::mcp::notifications::alarm_update(
+                &before,
+                item.get("status").and_then(|v| v.as_i64()).map(|v| v.to_string()).as_deref(),
+                item.get("acknowledged").and_then(|v| v.as_i64()).map(|v| v.to_string()).as_deref(),
+            );
             saved_count += 1;
         } else {
             error!("⚠️ Alarm record not found: serial={}, alarm_id={}", serial, alarm_index);
@@ -269,59 +275,17 @@ async fn save_alarms_to_db(db: &DatabaseConnection, serial: i32, items: &[Value]
 }
 
 async fn call_refresh_ffi(action: i32, refresh_json: Value) -> Result<String, String> {
-    use crate::t3_device::t3_ffi_sync_service::load_t3000_function;
+    use crate::t3_device::transport;
 
     let input_str = refresh_json.to_string();
     info!("📤 Sending to C++ (Action {}): {}", action, input_str);
 
-    tokio::task::spawn_blocking(|| {
-        unsafe {
-            if !load_t3000_function() {
-                return Err("T3000 functions not loaded".to_string());
-            }
-        }
-        Ok(())
-    }).await
-    .map_err(|e| format!("Failed to check T3000 functions: {}", e))?
-    .map_err(|e| e)?;
-
-    tokio::task::spawn_blocking(move || {
-        use crate::t3_device::t3_ffi_sync_service::BACNETWEBVIEW_HANDLE_WEBVIEW_MSG_FN;
+    let response = transport::current().call(action, &input_str).await?;
+    info!("📥 C++ Response (Action {}): {}", action, response);
 
-        const BUFFER_SIZE: usize = 1048576;
-        let mut buffer: Vec<u8> = vec![0; BUFFER_SIZE];
-        let input_bytes = input_str.as_bytes();
-
-        if input_bytes.len() >= BUFFER_SIZE {
-            return Err("Input JSON too large for buffer".to_string());
-        }
+    if response.is_empty() || response == "{}" {
+        return Err("Action not implemented in C++ - empty response".to_string());
+    }
 
-        buffer[..input_bytes.len()].copy_from_slice(input_bytes);
-        buffer[input_bytes.len()] = 0;
-
-        unsafe {
-            if let Some(func) = BACNETWEBVIEW_HANDLE_WEBVIEW_MSG_FN {
-                let result = func(action, buffer.as_mut_ptr() as *mut std::os::raw::c_char, buffer.len() as i32);
-
-                match result {
-                    0 => {
-                        let null_pos = buffer.iter().position(|&b| b == 0).unwrap_or(buffer.len());
-                        let response = String::from_utf8_lossy(&buffer[..null_pos]).to_string();
-                        info!("📥 C++ Response (Action {}): {}", action, response);
-
-                        if response.is_empty() || response == "{}" {
-                            return Err("Action not implemented in C++ - empty response".to_string());
-                        }
-                        Ok(response)
-                    }
-                    -2 => Err("MFC application not initialized".to_string()),
-                    code => Err(format!("FFI call failed with code: {}", code)),
-                }
-            } else {
-                Err("BacnetWebView_HandleWebViewMsg function not loaded".to_string())
-            }
-        }
-    })
-    .await
-    .map_err(|e| format!("Task spawn error: {}", e))?
+    Ok(response)
 }
@@ -298,69 +298,17 @@ async fn save_tables_to_db(
 
 /// Call FFI function for refresh operations
 async fn call_refresh_ffi(action: i32, refresh_json: Value) -> Result<String, String> {
-    use crate::t3_device::t3_ffi_sync_service::load_t3000_function;
+    use crate::t3_device::transport;
 
     let input_str = refresh_json.to_string();
     info!("📤 Sending to C++ (Action {}): {}", action, input_str);
 
-    // Ensure T3000 functions are loaded
-    tokio::task::spawn_blocking(|| {
-        unsafe {
-            if !load_t3000_function() {
-                return Err("T3000 functions not loaded".to_string());
-            }
-        }
-        Ok(())
-    }).await
-    .map_err(|e| format!("Failed to check T3000 functions: {}", e))?
-    .map_err(|e| e)?;
-
-    // Run FFI call in blocking task
-    tokio::task::spawn_blocking(move || {
-        use crate::t3_device::t3_ffi_sync_service::BACNETWEBVIEW_HANDLE_WEBVIEW_MSG_FN;
-
-        const BUFFER_SIZE: usize = 1048576; // 1MB buffer
-        let mut buffer: Vec<u8> = vec![0; BUFFER_SIZE];
-
-        // Write input JSON to buffer
-        let input_bytes = input_str.as_bytes();
-        if input_bytes.len() >= BUFFER_SIZE {
-            return Err("Input JSON too large for buffer".to_string());
-        }
+    let response = transport::current().call(action, &input_str).await?;
+    info!("📥 C++ Response (Action {}): {}", action, response);
 
-        buffer[..input_bytes.len()].copy_from_slice(input_bytes);
-        buffer[input_bytes.len()] = 0; // Null terminator
-
-        unsafe {
-            if let Some(func) = BACNETWEBVIEW_HANDLE_WEBVIEW_MSG_FN {
-                let result = func(
-                    action,
-                    buffer.as_mut_ptr() as *mut std::os::raw::c_char,
-                    buffer.len() as i32,
-                );
-
-                match result {
-                    0 => {
-                        // Success - read response from buffer
-                        let null_pos = buffer.iter().position(|&b| b == 0).unwrap_or(buffer.len());
-                        let response = String::from_utf8_lossy(&buffer[..null_pos]).to_string();
-                        info!("📥 C++ Response (Action {}): {}", action, response);
-
-                        // Check if response is empty or minimal (C++ not implemented)
-                        if response.is_empty() || response == "{}" {
-                            return Err("Action not implemented in C++ - empty response".to_string());
-                        }
-
-                        Ok(response)
-                    }
-                    -2 => Err("MFC application not initialized".to_string()),
-                    code => Err(format!("FFI call failed with code: {}", code)),
-                }
-            } else {
-                Err("BacnetWebView_HandleWebViewMsg function not loaded".to_string())
-            }
-        }
-    })
-    .await
-    .map_err(|e| format!("Task spawn error: {}", e))?
+    if response.is_empty() || response == "{}" {
+        return Err("Action not implemented in C++ - empty response".to_string());
+    }
+
+    Ok(response)
 }
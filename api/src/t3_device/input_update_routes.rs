@@ -12,6 +12,7 @@ use serde_json::{json, Value};
 use tracing::{error, info};
 
 use crate::app_state::T3AppState;
+use crate::bacnet::points::{self as bacnet_points, PointKind, PointWrite, Transport};
 use crate::entity::t3_device::{devices, input_points};
 use crate::t3_device::t3_ffi_sync_service::WebViewMessageType;
 use crate::logger::ServiceLogger;
@@ -47,6 +48,10 @@ pub struct UpdateInputFullRequest {
     pub calibration_h: Option<i32>,
     pub calibration_l: Option<i32>,
     pub decom: Option<i32>,
+    /// "ffi" (T3000.exe) or "bacnet" (native BACnet/IP); defaults per platform
+    pub transport: Option<String>,
+    /// BACnet command priority (1-16) for the value write; outputs/variables only
+    pub priority: Option<u8>,
 }
 
 /// Standard API response structure
@@ -66,6 +71,12 @@ pub async fn update_input_full(
     Json(payload): Json<UpdateInputFullRequest>,
 ) -> Result<Json<Value>, (StatusCode, String)> {
     let index = index_str.parse::<i32>().unwrap_or(0);
+    let transport = Transport::resolve(payload.transport.as_deref())
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let point_write = bacnet_write(&payload);
+    if transport == Transport::Bacnet {
+        bacnet_points::ensure_writable(&bacnet_unsupported(&payload)).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    }
 
     if let Ok(mut logger) = ServiceLogger::api_inputs() {
         logger.info(&format!("📥 UPDATE_WEBVIEW_LIST: Updating full input record - Serial: {}, Index: {}", serial, index));
@@ -179,9 +190,15 @@ pub async fn update_input_full(
         "decom": payload.decom.unwrap_or(0),
     });
 
-    // Call FFI function
+    // Send to device (T3000.exe FFI or BACnet/IP)
     let updated_fields_clone = updated_fields.clone();
-    match call_update_ffi(WebViewMessageType::UPDATE_WEBVIEW_LIST as i32, input_json).await {
+    let device_result = match transport {
+        Transport::Ffi => call_update_ffi(WebViewMessageType::UPDATE_WEBVIEW_LIST as i32, input_json).await,
+        Transport::Bacnet => bacnet_points::update(&db_connection, serial, PointKind::Input, index, &point_write)
+            .await
+            .map(|_| String::new()),
+    };
+    match device_result {
         Ok(_response) => {
             if let Ok(mut logger) = ServiceLogger::api_inputs() {
                 logger.info(&format!("✅ Full input record updated in device - serial: {}, index: {}", serial, index));
@@ -237,6 +254,12 @@ pub async fn update_input_device_only(
     Json(payload): Json<UpdateInputFullRequest>,
 ) -> Result<Json<Value>, (StatusCode, String)> {
     let index = index_str.parse::<i32>().unwrap_or(0);
+    let transport = Transport::resolve(payload.transport.as_deref())
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let point_write = bacnet_write(&payload);
+    if transport == Transport::Bacnet {
+        bacnet_points::ensure_writable(&bacnet_unsupported(&payload)).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    }
 
     if let Ok(mut logger) = ServiceLogger::api_inputs() {
         logger.info(&format!("🔧 DEVICE_ONLY: Updating input in device - Serial: {}, Index: {}", serial, index));
@@ -329,8 +352,14 @@ pub async fn update_input_device_only(
         "decom": payload.decom.unwrap_or(0),
     });
 
-    // Call FFI function to update device only (NO database save)
-    match call_update_ffi(WebViewMessageType::UPDATE_WEBVIEW_LIST as i32, input_json).await {
+    // Send to device (FFI or BACnet/IP) to update device only (NO database save)
+    let device_result = match transport {
+        Transport::Ffi => call_update_ffi(WebViewMessageType::UPDATE_WEBVIEW_LIST as i32, input_json).await,
+        Transport::Bacnet => bacnet_points::update(&db_connection, serial, PointKind::Input, index, &point_write)
+            .await
+            .map(|_| String::new()),
+    };
+    match device_result {
         Ok(_response) => {
             if let Ok(mut logger) = ServiceLogger::api_inputs() {
                 logger.info(&format!("✅ Input updated in device only (database NOT modified) - serial: {}, index: {}", serial, index));
@@ -524,9 +553,37 @@ async fn save_input_to_db(
     }
 }
 
+/// Fields the BACnet/IP transport can write (label, description, value, auto/manual)
+fn bacnet_write(payload: &UpdateInputFullRequest) -> PointWrite {
+    PointWrite {
+        value: payload.value.map(f64::from),
+        label: payload.label.clone(),
+        description: payload.full_label.clone(),
+        auto_manual: payload.auto_manual,
+        priority: payload.priority,
+    }
+}
+
+/// Request fields the BACnet/IP transport can't write
+fn bacnet_unsupported(payload: &UpdateInputFullRequest) -> Vec<&'static str> {
+    [
+        ("range", payload.range.is_some()),
+        ("control", payload.control.is_some()),
+        ("filter", payload.filter.is_some()),
+        ("digitalAnalog", payload.digital_analog.is_some()),
+        ("decom", payload.decom.is_some()),
+        ("calibrationSign", payload.calibration_sign.is_some()),
+        ("calibrationH", payload.calibration_h.is_some()),
+        ("calibrationL", payload.calibration_l.is_some()),
+    ]
+    .into_iter()
+    .filter_map(|(field, set)| set.then_some(field))
+    .collect()
+}
+
 /// Call FFI function for update operations
 async fn call_update_ffi(action: i32, input_json: Value) -> Result<String, String> {
-    use crate::t3_device::t3_ffi_sync_service::load_t3000_function;
+    use crate::t3_device::transport;
 
     let input_str = input_json.to_string();
 
@@ -535,80 +592,20 @@ async fn call_update_ffi(action: i32, input_json: Value) -> Result<String, Strin
     }
     info!("📤 Sending to C++ (Action {}): {}", action, input_str);
 
-    // Ensure T3000 functions are loaded
-    tokio::task::spawn_blocking(|| {
-        unsafe {
-            if !load_t3000_function() {
-                return Err("T3000 functions not loaded".to_string());
-            }
+    let response = transport::current().call(action, &input_str).await.map_err(|e| {
+        let err_msg = e.to_string();
+        if let Ok(mut logger) = ServiceLogger::api_inputs() {
+            logger.error(&format!("❌ FFI Error: {}", err_msg));
         }
-        Ok(())
-    }).await
-    .map_err(|e| format!("Failed to check T3000 functions: {}", e))?
-    .map_err(|e| e)?;
-
-    // Run FFI call in blocking task
-    tokio::task::spawn_blocking(move || {
-        use crate::t3_device::t3_ffi_sync_service::BACNETWEBVIEW_HANDLE_WEBVIEW_MSG_FN;
-
-        const BUFFER_SIZE: usize = 1048576; // 1MB buffer
-        let mut buffer: Vec<u8> = vec![0; BUFFER_SIZE];
-
-        // Write input JSON to buffer
-        let input_bytes = input_str.as_bytes();
-        if input_bytes.len() >= BUFFER_SIZE {
-            return Err("Input JSON too large for buffer".to_string());
-        }
-
-        buffer[..input_bytes.len()].copy_from_slice(input_bytes);
-        buffer[input_bytes.len()] = 0; // Null terminator
-
-        unsafe {
-            if let Some(func) = BACNETWEBVIEW_HANDLE_WEBVIEW_MSG_FN {
-                let result = func(
-                    action,
-                    buffer.as_mut_ptr() as *mut std::os::raw::c_char,
-                    buffer.len() as i32,
-                );
-
-                match result {
-                    0 => {
-                        // Success - read response from buffer
-                        let null_pos = buffer.iter().position(|&b| b == 0).unwrap_or(buffer.len());
-                        let response = String::from_utf8_lossy(&buffer[..null_pos]).to_string();
-
-                        if let Ok(mut logger) = ServiceLogger::api_inputs() {
-                            logger.info(&format!("📥 C++ Response (Action {}): {}", action, response));
-                        }
-                        info!("📥 C++ Response (Action {}): {}", action, response);
-                        Ok(response)
-                    }
-                    -2 => {
-                        let err_msg = "MFC application not initialized".to_string();
-                        if let Ok(mut logger) = ServiceLogger::api_inputs() {
-                            logger.error(&format!("❌ FFI Error: {}", err_msg));
-                        }
-                        Err(err_msg)
-                    }
-                    code => {
-                        let err_msg = format!("FFI call failed with code: {}", code);
-                        if let Ok(mut logger) = ServiceLogger::api_inputs() {
-                            logger.error(&format!("❌ {}", err_msg));
-                        }
-                        Err(err_msg)
-                    }
-                }
-            } else {
-                let err_msg = "BacnetWebView_HandleWebViewMsg function not loaded".to_string();
-                if let Ok(mut logger) = ServiceLogger::api_inputs() {
-                    logger.error(&format!("❌ {}", err_msg));
-                }
-                Err(err_msg)
-            }
-        }
-    })
-    .await
-    .map_err(|e| format!("Task spawn error: {}", e))?
+        err_msg
+    })?;
+
+    if let Ok(mut logger) = ServiceLogger::api_inputs() {
+        logger.info(&format!("📥 C++ Response (Action {}): {}", action, response));
+    }
+    info!("📥 C++ Response (Action {}): {}", action, response);
+
+    Ok(response)
 }
 
 /// Get input record by serial number and index
@@ -0,0 +1,201 @@
+// Migration job to fill TRENDLOG_DATA_DETAIL.NumericValue / LoggingTime from Value / LoggingTime_Fmt
+// Runs online: rows are updated in small batches with a pause between them so the FFI sync keeps
+// writing. Covers the main database and every partition file; partitions from older versions get
+// the columns added first. Safe to rerun - only rows with a NULL LoggingTime are touched.
+
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, Database, DatabaseConnection, DbBackend, DbErr, EntityTrait, FromQueryResult,
+    QueryFilter, Statement, TransactionTrait,
+};
+use serde::Serialize;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::entity::database_files;
+use crate::logger::ServiceLogger;
+use crate::server_db::trendlog_detail::{self, DetailLayout, COVERING_INDEX};
+
+/// Batch size and pause between batches.
+#[derive(Debug, Clone, Copy)]
+pub struct BackfillOptions {
+    pub batch_size: u64,
+    pub pause: Duration,
+}
+
+impl Default for BackfillOptions {
+    fn default() -> Self {
+        Self { batch_size: 1000, pause: Duration::from_millis(50) }
+    }
+}
+
+/// Outcome for one database file.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct DatabaseBackfill {
+    /// "main" or the partition identifier.
+    pub source: String,
+    /// The columns were added by this run (legacy partition).
+    pub upgraded: bool,
+    pub rows_updated: u64,
+    /// Rows whose LoggingTime_Fmt couldn't be parsed; they stay text-only.
+    pub rows_unparseable: u64,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct NumericMigrationSummary {
+    pub databases: Vec<DatabaseBackfill>,
+}
+
+impl NumericMigrationSummary {
+    pub fn rows_updated(&self) -> u64 {
+        self.databases.iter().map(|d| d.rows_updated).sum()
+    }
+}
+
+// Detail row still missing its numeric columns
+#[derive(Debug, FromQueryResult)]
+struct PendingRow {
+    row_id: i64,
+    value: String,
+    logging_time_fmt: String,
+}
+
+/// Add the numeric columns to a legacy TRENDLOG_DATA_DETAIL. Returns whether
+/// anything was added.
+pub async fn upgrade_detail_table(db: &DatabaseConnection) -> Result<bool, DbErr> {
+    if DetailLayout::detect(db, "main").await? == DetailLayout::Numeric {
+        return Ok(false);
+    }
+    for (col, col_def) in [("NumericValue", "REAL DEFAULT NULL"), ("LoggingTime", "INTEGER DEFAULT NULL")] {
+        let sql = format!("ALTER TABLE TRENDLOG_DATA_DETAIL ADD COLUMN {} {}", col, col_def);
+        // Ignore error if column already exists (only one of the two was added)
+        let _ = db.execute_unprepared(&sql).await;
+    }
+    Ok(DetailLayout::detect(db, "main").await? == DetailLayout::Numeric)
+}
+
+/// Fill NumericValue / LoggingTime on every row that lacks them, oldest rowid
+/// first. Returns `(updated, unparseable)`.
+pub async fn backfill_detail_rows(db: &DatabaseConnection, opts: &BackfillOptions) -> Result<(u64, u64), DbErr> {
+    let mut last_rowid = 0i64;
+    let mut updated = 0u64;
+    let mut unparseable = 0u64;
+
+    loop {
+        let batch = PendingRow::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::Sqlite,
+            "SELECT rowid AS row_id, Value AS value, LoggingTime_Fmt AS logging_time_fmt \
+             FROM TRENDLOG_DATA_DETAIL WHERE rowid > ? AND LoggingTime IS NULL ORDER BY rowid LIMIT ?",
+            [last_rowid.into(), (opts.batch_size.max(1) as i64).into()],
+        ))
+        .all(db)
+        .await?;
+
+        let Some(last) = batch.last() else { break };
+        last_rowid = last.row_id;
+
+        let txn = db.begin().await?;
+        for row in &batch {
+            let Some(epoch) = trendlog_detail::epoch_from_fmt(&row.logging_time_fmt) else {
+                unparseable += 1;
+                continue;
+            };
+            txn.execute(Statement::from_sql_and_values(
+                DbBackend::Sqlite,
+                "UPDATE TRENDLOG_DATA_DETAIL SET NumericValue = ?, LoggingTime = ? WHERE rowid = ?",
+                [trendlog_detail::numeric_value(&row.value).into(), epoch.into(), row.row_id.into()],
+            ))
+            .await?;
+            updated += 1;
+        }
+        txn.commit().await?;
+
+        // Let writers in between batches
+        tokio::time::sleep(opts.pause).await;
+    }
+
+    Ok((updated, unparseable))
+}
+
+/// Upgrade, backfill and index one database.
+pub async fn migrate_database(db: &DatabaseConnection, source: &str, opts: &BackfillOptions) -> DatabaseBackfill {
+    let mut result = DatabaseBackfill { source: source.to_string(), ..Default::default() };
+    let outcome: Result<(), DbErr> = async {
+        result.upgraded = upgrade_detail_table(db).await?;
+        let (updated, unparseable) = backfill_detail_rows(db, opts).await?;
+        result.rows_updated = updated;
+        result.rows_unparseable = unparseable;
+        // Built after the backfill so it isn't maintained row by row
+        db.execute_unprepared(&format!(
+            "CREATE INDEX IF NOT EXISTS {} ON TRENDLOG_DATA_DETAIL (ParentId, LoggingTime, NumericValue)",
+            COVERING_INDEX
+        ))
+        .await?;
+        Ok(())
+    }
+    .await;
+    if let Err(e) = outcome {
+        result.error = Some(e.to_string());
+    }
+    result
+}
+
+/// Run the job over the main database, then every partition file.
+pub async fn migrate_trendlog_numeric(
+    db: &DatabaseConnection,
+    opts: &BackfillOptions,
+) -> Result<NumericMigrationSummary, DbErr> {
+    let mut logger = ServiceLogger::new("T3_TrendlogNumeric").unwrap_or_else(|_| ServiceLogger::noop());
+    let mut summary = NumericMigrationSummary::default();
+
+    // Step 1: Main database
+    logger.info("[NUMERIC] Step 1: Backfilling main database...");
+    let main = migrate_database(db, "main", opts).await;
+    log_result(&mut logger, &main);
+    summary.databases.push(main);
+
+    // Step 2: Partition files (archived ones included - they stay queryable)
+    let partitions = database_files::Entity::find()
+        .filter(database_files::Column::PartitionIdentifier.is_not_null())
+        .all(db)
+        .await?;
+    logger.info(&format!("[NUMERIC] Step 2: Checking {} partition file(s)...", partitions.len()));
+
+    for partition in partitions {
+        let id = partition.partition_identifier.clone().unwrap_or_default();
+        if !Path::new(&partition.file_path).exists() {
+            logger.warn(&format!("[NUMERIC] {}: file not found at {}", id, partition.file_path));
+            continue;
+        }
+        let result = match Database::connect(format!("sqlite://{}?mode=rw", partition.file_path)).await {
+            Ok(conn) => {
+                let result = migrate_database(&conn, &id, opts).await;
+                conn.close().await.ok();
+                result
+            }
+            Err(e) => DatabaseBackfill { source: id, error: Some(e.to_string()), ..Default::default() },
+        };
+        log_result(&mut logger, &result);
+        summary.databases.push(result);
+    }
+
+    logger.info(&format!(
+        "[NUMERIC] Complete: {} row(s) backfilled across {} database(s)",
+        summary.rows_updated(),
+        summary.databases.len()
+    ));
+    Ok(summary)
+}
+
+fn log_result(logger: &mut ServiceLogger, result: &DatabaseBackfill) {
+    match &result.error {
+        Some(e) => logger.warn(&format!("[NUMERIC] {}: failed - {}", result.source, e)),
+        None => logger.info(&format!(
+            "[NUMERIC] {}: {} row(s) backfilled, {} unparseable{}",
+            result.source,
+            result.rows_updated,
+            result.rows_unparseable,
+            if result.upgraded { ", columns added" } else { "" }
+        )),
+    }
+}
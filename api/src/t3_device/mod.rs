@@ -13,8 +13,10 @@ pub mod trendlog_enhanced_routes; // ✅ T3000 TrendLog Enhanced API Routes for
 pub mod trendlog_data_service;  // ✅ T3000 TrendLog Historical Data Service (TRENDLOG_DATA table)
 pub mod trendlog_parent_cache;  // ✅ Parent ID cache for split-table optimization
 pub mod migrate_trendlog_split; // ✅ Migration script for TRENDLOG_DATA split-table optimization
+pub mod migrate_trendlog_numeric; // ✅ Online backfill of TRENDLOG_DATA_DETAIL numeric value / epoch columns
 pub mod sync_writer;          // ✅ SyncWriter — direct-to-centerDB or local SQLite abstraction for FFI sync
 pub mod t3_ffi_sync_service;  // ✅ MAIN T3000 SERVICE - Primary T3000 FFI & Sync integration service (collects ALL data)
+pub mod transport;            // ✅ DeviceTransport trait - FFI/offline/simulator backends selected at startup (T3_DEVICE_TRANSPORT)
 pub mod t3_ffi_api_service;     // ✅ T3000 FFI API Service - HTTP API endpoints with FFI integration (same JSON as WebSocket)
 pub mod trendlog_webmsg_service; // ✅ T3000 TrendLog via HandleWebViewMsg (working approach instead of direct FFI)
 pub mod trendlog_webmsg_routes;  // ✅ T3000 TrendLog WebMsg API Routes (working HandleWebViewMsg endpoints)
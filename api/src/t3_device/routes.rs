@@ -1027,6 +1027,18 @@ async fn batch_update_device_online_status(
     debug_log(&format!("[batch-online] Payload: onlineSerials={:?}, offlineSerials={:?}",
         payload.online_serials, payload.offline_serials));
 
+    // Previous status, so only real transitions are pushed to MCP subscribers
+    use crate::entity::t3_device::devices;
+    let serials: Vec<i32> = payload.online_serials.iter().chain(&payload.offline_serials).copied().collect();
+    let previous: std::collections::HashMap<i32, Option<i32>> = devices::Entity::find()
+        .filter(devices::Column::SerialNumber.is_in(serials))
+        .all(db)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|d| (d.serial_number, d.is_online))
+        .collect();
+
     // Mark online devices
     for serial in &payload.online_serials {
         let sql = format!(
@@ -1039,6 +1051,9 @@ async fn batch_update_device_online_status(
                 let rows = res.rows_affected();
                 debug_log(&format!("[batch-online] Marked SN {} as online (rows: {})", serial, rows));
                 updated += rows as i32;
+                if rows > 0 {
+                    crate::mcp::notifications::device_status(*serial, previous.get(serial).copied().flatten(), true);
+                }
             }
             Err(e) => {
                 let msg = format!("Failed to update SN {}: {}", serial, e);
@@ -1059,6 +1074,9 @@ async fn batch_update_device_online_status(
                 let rows = res.rows_affected();
                 debug_log(&format!("[batch-online] Marked SN {} as offline (rows: {})", serial, rows));
                 updated += rows as i32;
+                if rows > 0 {
+                    crate::mcp::notifications::device_status(*serial, previous.get(serial).copied().flatten(), false);
+                }
             }
             Err(e) => {
                 let msg = format!("Failed to update SN {}: {}", serial, e);
@@ -2314,14 +2332,19 @@ async fn get_trendlog_history(
                     .collect()
             });
 
-        let result = mssql_trendlog_service::get_trendlog_history(
+        // Aggregation needs the whole range, not the default raw row cap
+        let limit = match payload.aggregation {
+            Some(_) => Some(payload.limit.unwrap_or(crate::server_db::trendlog_aggregation::AGGREGATION_ROW_LIMIT)),
+            None => payload.limit,
+        };
+        let mut result = mssql_trendlog_service::get_trendlog_history(
             pool,
             device_id,
             payload.panel_id,
             &trendlog_id,
             payload.start_time.as_deref(),
             payload.end_time.as_deref(),
-            payload.limit,
+            limit,
             payload.point_types.as_deref(),
             specific_points.as_deref(),
         )
@@ -2331,6 +2354,15 @@ async fn get_trendlog_history(
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
+        if let Some(aggregation) = &payload.aggregation {
+            crate::t3_device::trendlog_data_service::apply_aggregation(
+                &mut result,
+                aggregation,
+                payload.start_time.as_deref(),
+                payload.end_time.as_deref(),
+                limit,
+            );
+        }
         return Ok(Json(result));
     }
 
@@ -509,9 +509,10 @@ impl T3DeviceService {
 
             let existing = devices::Entity::find_by_id(sn).one(db).await?;
 
-            if existing.is_some() {
+            if let Some(existing) = existing {
+                let was_online = existing.is_online;
                 // Update existing device
-                let mut active: devices::ActiveModel = existing.unwrap().into();
+                let mut active: devices::ActiveModel = existing.into();
                 active.product_name = Set(Some(dev.product_name.clone()));
                 active.product_id = Set(Some(dev.product_id as i32));
                 active.product_class_id = Set(Some(dev.product_id as i32));
@@ -532,7 +533,9 @@ impl T3DeviceService {
                 active.last_checked = Set(Some(now.clone()));
                 active.bautrate = Set(Some(dev.ip_address.clone()));
                 active.pc_ip_address = Set(Self::pick_pc_ip(&dev.ip_address, &local_ips));
-                let _ = active.update(db).await;
+                if active.update(db).await.is_ok() {
+                    crate::mcp::notifications::device_status(sn, was_online, true);
+                }
             } else {
                 // Insert new device
                 let active = devices::ActiveModel {
@@ -560,7 +563,9 @@ impl T3DeviceService {
                     pc_ip_address: Set(Self::pick_pc_ip(&dev.ip_address, &local_ips)),
                     ..Default::default()
                 };
-                let _ = devices::Entity::insert(active).exec(db).await;
+                if devices::Entity::insert(active).exec(db).await.is_ok() {
+                    crate::mcp::notifications::device_status(sn, None, true);
+                }
             }
         }
 
@@ -569,10 +574,13 @@ impl T3DeviceService {
             let all_devices = devices::Entity::find().all(db).await?;
             for device in all_devices {
                 if !seen_serials.contains(&device.serial_number) {
+                    let (serial, was_online) = (device.serial_number, device.is_online);
                     let mut active: devices::ActiveModel = device.into();
                     active.is_online = Set(Some(0));
                     active.last_checked = Set(Some(now.clone()));
-                    let _ = active.update(db).await;
+                    if active.update(db).await.is_ok() {
+                        crate::mcp::notifications::device_status(serial, was_online, false);
+                    }
                 }
             }
         }
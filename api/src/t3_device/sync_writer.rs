@@ -206,7 +206,8 @@ impl SyncWriter {
     // -----------------------------------------------------------------------
 
     /// Insert trend log detail rows for all points in the device snapshot.
-    /// Parent rows (TRENDLOG_DATA) are get-or-created; detail rows are always inserted.
+    /// Parent rows (TRENDLOG_DATA) are get-or-created; detail rows are always inserted
+    /// and folded into the hourly/daily rollups.
     /// Center DB mode: MSSQL only. Standalone: SQLite only.
     pub async fn insert_trendlogs(
         &self,
@@ -230,7 +231,8 @@ impl SyncWriter {
 // ---------------------------------------------------------------------------
 
 /// Write trendlog history directly into the MSSQL center DB.
-/// For each point: get-or-create the parent row, then INSERT one detail row.
+/// For each point: get-or-create the parent row, INSERT one detail row, then
+/// fold the value into the hourly/daily rollups.
 async fn mssql_insert_trendlogs(
     pool: &MssqlPool,
     serial_number: i32,
@@ -4,8 +4,6 @@
 // - All message formatting and response parsing handled in TypeScript layer
 // - Simple pass-through architecture for easy WebSocket/FFI switching
 
-use std::ffi::CString;
-use std::os::raw::c_char;
 use std::collections::HashMap;
 use axum::{
     extract::State,
@@ -22,7 +20,7 @@ use crate::error::Error;
 use crate::logger::ServiceLogger;
 use crate::app_state::T3AppState;
 use crate::logging::service::emit_app_log;
-use winapi::um::libloaderapi::{GetProcAddress, LoadLibraryA};
+use crate::t3_device::transport::{self, TransportError};
 
 /// Pending realtime trendlog flows awaiting their batch_save step.
 /// action=15 (LOGGING_DATA) stores a TRENDLOG_POLL flow here when called via HTTP.
@@ -48,23 +46,15 @@ pub struct PendingFfiPollStep {
 pub static PENDING_ACTION17_STEPS: Lazy<tokio::sync::Mutex<HashMap<String, Vec<PendingFfiPollStep>>>> =
     Lazy::new(|| tokio::sync::Mutex::new(HashMap::new()));
 
-// FFI function type
-type BacnetWebViewHandleWebViewMsgFn = unsafe extern "C" fn(action: i32, msg: *mut c_char, len: i32) -> i32;
-
-// Global function pointer
-static mut BACNETWEBVIEW_HANDLE_WEBVIEW_MSG_FN: Option<BacnetWebViewHandleWebViewMsgFn> = None;
-static mut T3000_LOADED: bool = false;
-static T3000_LOADED_AT: OnceLock<Instant> = OnceLock::new();
-
 fn action17_warmup_active() -> bool {
-    if let Some(loaded_at) = T3000_LOADED_AT.get() {
+    if let Some(loaded_at) = transport::ffi::loaded_at() {
         return loaded_at.elapsed() < Duration::from_secs(4);
     }
     false
 }
 
 fn action4_warmup_active() -> bool {
-    if let Some(loaded_at) = T3000_LOADED_AT.get() {
+    if let Some(loaded_at) = transport::ffi::loaded_at() {
         return loaded_at.elapsed() < Duration::from_secs(3);
     }
     false
@@ -112,53 +102,6 @@ impl T3000FfiApiService {
         }
     }
 
-    /// Load T3000 FFI function
-    fn load_t3000_function() -> bool {
-        unsafe {
-            if T3000_LOADED {
-                return std::ptr::addr_of!(BACNETWEBVIEW_HANDLE_WEBVIEW_MSG_FN)
-                    .read()
-                    .is_some();
-            }
-
-            let mut api_logger = ServiceLogger::api().unwrap_or_else(|_| ServiceLogger::new("fallback_api").unwrap());
-
-            // Try to find T3000.exe - look in same directory as Rust API first
-            let t3000_paths = vec![
-                "T3000.exe",  // Same directory as Rust API
-                "./T3000.exe",  // Current directory explicitly
-                "../T3000.exe",  // Parent directory
-            ];
-
-            for path in &t3000_paths {
-                api_logger.info(&format!("🔍 API Service - Looking for T3000.exe at: {}", path));
-
-                if std::path::Path::new(path).exists() {
-                    let path_cstring = CString::new(*path).unwrap();
-                    let t3000_module = LoadLibraryA(path_cstring.as_ptr());
-
-                    if !t3000_module.is_null() {
-                        api_logger.info("✅ API Service - Successfully loaded T3000.exe");
-                        let func_name = CString::new("BacnetWebView_HandleWebViewMsg").unwrap();
-                        let func_ptr = GetProcAddress(t3000_module, func_name.as_ptr());
-
-                        if !func_ptr.is_null() {
-                            BACNETWEBVIEW_HANDLE_WEBVIEW_MSG_FN = Some(std::mem::transmute(func_ptr));
-                            T3000_LOADED = true;
-                            let _ = T3000_LOADED_AT.set(Instant::now());
-                            return true;
-                        } else {
-                            api_logger.error("❌ API Service - BacnetWebView_HandleWebViewMsg not found in T3000.exe");
-                        }
-                    }
-                }
-            }
-
-            T3000_LOADED = true;
-            false
-        }
-    }
-
     /// Simple FFI call - just pass the message to C++
     pub async fn call_ffi(&self, message: &str) -> Result<String, Error> {
         let mut api_logger = ServiceLogger::api().unwrap_or_else(|_| ServiceLogger::new("fallback_api").unwrap());
@@ -197,89 +140,53 @@ impl T3000FfiApiService {
         };
 
         api_logger.info(&format!("📡 FFI Call - Final Action: {}, Calling C++ now...", action));
-        let max_buffer_size = self.max_buffer_size;
-        let message_owned = message.to_string();
-
-        // Run heavy FFI work on blocking pool so request handling threads stay responsive.
-        let ffi_result = tokio::task::spawn_blocking(move || {
-            let _guard = ffi_call_lock_timeout(60)
-                .ok_or_else(|| Error::ServerError("FFI lock timed out — prior C++ call may be stuck".to_string()))?;
-
-            unsafe {
-                if !Self::load_t3000_function() {
-                    return Err(Error::ServerError("T3000 FFI functions not loaded".to_string()));
-                }
-
-                // Guard first-load startup window: C++ can assert on early Action 17 calls
-                // right after DLL/reload. Fail fast so frontend retries instead of crashing T3000.exe.
-                if action == 17 && action17_warmup_active() {
-                    let warmup = serde_json::json!({
-                        "error": "T3000 initialization in progress, please retry shortly",
-                        "code": "T3000_WARMUP"
-                    })
-                    .to_string();
-                    return Ok((-1, warmup.len(), warmup));
-                }
-
-                // Guard startup window: GET_PANELS_LIST has been observed to
-                // hit vector assertions in T3000.exe when called too early.
-                if action == 4 && action4_warmup_active() {
-                    let warmup = serde_json::json!({
-                        "error": "T3000 panel data still initializing, retry shortly",
-                        "code": "T3000_PANELS_WARMUP"
-                    })
-                    .to_string();
-                    return Ok((-1, warmup.len(), warmup));
-                }
-
-                if let Some(func) = BACNETWEBVIEW_HANDLE_WEBVIEW_MSG_FN {
-                    // Allocate buffer large enough for both input and output
-                    let mut buffer: Vec<u8> = vec![0; max_buffer_size];
-                    let input_bytes = message_owned.as_bytes();
-
-                    if input_bytes.len() >= max_buffer_size {
-                        return Err(Error::ServerError("Input message too large for buffer".to_string()));
-                    }
+        let transport = transport::current();
+        if !transport.is_available().await {
+            return Err(Error::ServerError("T3000 FFI functions not loaded".to_string()));
+        }
 
-                    // Copy input message into buffer
-                    buffer[..input_bytes.len()].copy_from_slice(input_bytes);
-                    buffer[input_bytes.len()] = 0; // Null terminate
+        // Guard first-load startup window: C++ can assert on early Action 17 calls
+        // right after DLL/reload. Fail fast so frontend retries instead of crashing T3000.exe.
+        if action == 17 && action17_warmup_active() {
+            let warmup = serde_json::json!({
+                "error": "T3000 initialization in progress, please retry shortly",
+                "code": "T3000_WARMUP"
+            })
+            .to_string();
+            return Ok(warmup);
+        }
 
-                    // Call FFI - buffer contains input, will be modified to contain output
-                    let result = func(
-                        action,
-                        buffer.as_mut_ptr() as *mut c_char,
-                        buffer.len() as i32,
-                    );
+        // Guard startup window: GET_PANELS_LIST has been observed to
+        // hit vector assertions in T3000.exe when called too early.
+        if action == 4 && action4_warmup_active() {
+            let warmup = serde_json::json!({
+                "error": "T3000 panel data still initializing, retry shortly",
+                "code": "T3000_PANELS_WARMUP"
+            })
+            .to_string();
+            return Ok(warmup);
+        }
 
-                    match result {
-                        code if code >= 0 => {
-                            // Non-negative codes (0, 1, 2, etc.) are success or "no data" states
-                            // Extract response from buffer
-                            let null_pos = buffer.iter().position(|&b| b == 0).unwrap_or(buffer.len());
-                            let response = String::from_utf8_lossy(&buffer[..null_pos]).to_string();
-                            Ok((code, null_pos, response))
-                        }
-                        -2 => Err(Error::ServerError("MFC application not initialized".to_string())),
-                        -1 => {
-                            // C++ returned -1, but buffer may contain error JSON (e.g., device offline)
-                            let null_pos = buffer.iter().position(|&b| b == 0).unwrap_or(buffer.len());
-                            let response = String::from_utf8_lossy(&buffer[..null_pos]).to_string();
-                            if !response.is_empty() && (response.starts_with('{') || response.starts_with('[')) {
-                                Ok((-1, null_pos, response))
-                            } else {
-                                Err(Error::ServerError("FFI call returned error code: -1".to_string()))
-                            }
-                        }
-                        code => Err(Error::ServerError(format!("FFI call returned error code: {}", code))),
-                    }
+        let reply = transport.send(action, message).await.map_err(|e| match e {
+            TransportError::Unavailable(msg) => Error::ServerError(format!("T3000 FFI functions not loaded: {}", msg)),
+            other => Error::ServerError(other.to_string()),
+        })?;
+
+        let ffi_result = match reply.code {
+            // Non-negative codes (0, 1, 2, etc.) are success or "no data" states
+            code if code >= 0 => Ok((code, reply.body.len(), reply.body)),
+            -2 => Err(Error::ServerError("MFC application not initialized".to_string())),
+            -1 => {
+                // C++ returned -1, but buffer may contain error JSON (e.g., device offline)
+                let response = reply.body;
+                if !response.is_empty() && (response.starts_with('{') || response.starts_with('[')) {
+                    Ok((-1, response.len(), response))
                 } else {
-                    Err(Error::ServerError("FFI function not loaded".to_string()))
+                    Err(Error::ServerError("FFI call returned error code: -1".to_string()))
                 }
             }
-        })
-        .await
-        .map_err(|e| Error::ServerError(format!("FFI blocking task join error: {}", e)))?;
+            code => Err(Error::ServerError(format!("FFI call returned error code: {}", code))),
+        };
 
         let (code, null_pos, response) = ffi_result?;
         if code == 0 {
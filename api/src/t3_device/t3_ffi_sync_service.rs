@@ -8,11 +8,12 @@
 
 use crate::db_connection::{establish_t3_device_connection, establish_device_conn_for_sync};
 use crate::entity::t3_device::{
-    devices, input_points, output_points, trendlog_data_detail, trendlog_data_sync_metadata,
+    devices, input_points, output_points, trendlog_data_sync_metadata,
     variable_points,
 };
 use crate::server_db::data_sync_service::{DataSyncMetadataService, InsertSyncMetadataRequest};
 use crate::server_db::mssql_queries;
+use crate::server_db::trendlog_detail;
 use crate::error::AppError;
 use crate::logger::ServiceLogger;
 use crate::t3_device::trendlog_parent_cache::{ParentKey, TrendlogParentCache};
@@ -21,17 +22,23 @@ use sea_orm::*;
 use sea_orm::sea_query::Expr;
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
+#[cfg(windows)]
 use std::env;
+#[cfg(windows)]
 use std::ffi::CString;
 use std::os::raw::c_char;
 use std::sync::{
     atomic::{AtomicBool, Ordering},
     Arc,
 };
-use std::time::{Duration, Instant};
+use std::time::Duration;
+#[cfg(windows)]
+use std::time::Instant;
 use tokio::time::sleep;
 use tracing::{debug, error, info, warn};
+#[cfg(windows)]
 use winapi::shared::minwindef::HINSTANCE;
+#[cfg(windows)]
 use winapi::um::libloaderapi::{GetProcAddress, LoadLibraryA};
 
 // Runtime function pointer type for BacnetWebView_HandleWebViewMsg
@@ -75,13 +82,17 @@ pub enum WebViewMessageType {
 pub static mut BACNETWEBVIEW_HANDLE_WEBVIEW_MSG_FN: Option<BacnetWebViewHandleWebViewMsgFn> = None;
 static mut GET_DEVICE_BASIC_SETTINGS_FN: Option<GetDeviceBasicSettingsFn> = None;
 static mut GET_DEVICE_NETWORK_CONFIG_FN: Option<GetDeviceNetworkConfigFn> = None;
+#[cfg(windows)]
 static mut T3000_LOADED: bool = false;
+#[cfg(windows)]
 static FFI_STARTUP_AT: OnceCell<Instant> = OnceCell::new();
+#[cfg(windows)]
 const FFI_MIN_STARTUP_DELAY_SECS: u64 = 30;
 /// Guards one-time T3000.exe load result write to Activity Log
 static T3000_LOAD_LOGGED: AtomicBool = AtomicBool::new(false);
 
 // Load the BacnetWebView_HandleWebViewMsg function from the current executable (T3000.exe)
+#[cfg(windows)]
 pub unsafe fn load_t3000_function() -> bool {
     // Global startup guard for all FFI callers (not only sync service).
     // Runtime logs showed Action 4 (GET_PANELS_LIST) can be called before the sync service delay,
@@ -236,37 +247,30 @@ pub unsafe fn load_t3000_function() -> bool {
     false
 }
 
+/// T3000.exe only exists on Windows; other platforms reach devices through
+/// another `transport::DeviceTransport`.
+///
+/// # Safety
+///
+/// Nothing to uphold on this platform; `unsafe` only mirrors the Windows
+/// signature, which writes the global function pointers.
+#[cfg(not(windows))]
+pub unsafe fn load_t3000_function() -> bool {
+    false
+}
+
 /// Maximum time to wait for the FFI global lock before giving up.
 const FFI_LOCK_TIMEOUT_SECS: u64 = 60;
 
 // Re-export the timeout-based lock from t3_ffi_api_service for sync service use.
 use crate::t3_device::t3_ffi_api_service::ffi_call_lock_timeout as try_acquire_ffi_lock;
 
-// Safe wrapper to call BacnetWebView_HandleWebViewMsg
-fn call_handle_webview_msg(action: i32, buffer: &mut [u8]) -> Result<i32, String> {
-    // Acquire the global FFI serialization lock with TIMEOUT.
-    // Previously used .lock() which blocks forever - if a prior spawn_blocking
-    // FFI call hangs, all subsequent calls deadlock waiting for the lock.
-    let _guard = try_acquire_ffi_lock(FFI_LOCK_TIMEOUT_SECS)
-        .ok_or_else(|| "FFI lock acquisition timed out - prior C++ FFI call may be stuck".to_string())?;
-    unsafe {
-        if !load_t3000_function() {
-            return Err(
-                "BacnetWebView_HandleWebViewMsg function not found in T3000.exe".to_string(),
-            );
-        }
-
-        if let Some(func) = BACNETWEBVIEW_HANDLE_WEBVIEW_MSG_FN {
-            let result = func(
-                action,
-                buffer.as_mut_ptr() as *mut c_char,
-                buffer.len() as i32,
-            );
-            Ok(result)
-        } else {
-            Err("BacnetWebView_HandleWebViewMsg function not loaded".to_string())
-        }
-    }
+// Send `request` through the installed device transport, HandleWebViewMsg-style.
+// Returns the raw code and reply body; the transport sizes its own buffer.
+async fn call_handle_webview_msg(action: i32, request: &str) -> Result<(i32, String), String> {
+    let transport = crate::t3_device::transport::current();
+    let reply = transport.send(action, request).await.map_err(|e| e.to_string())?;
+    Ok((reply.code, reply.body))
 }
 
 // Safe wrapper to call GetDeviceBasicSettings (new function)
@@ -2698,20 +2702,13 @@ impl T3000MainService {
                 }
             };
 
-            // Step 2: Insert detail record only
+            // Step 2: Insert the detail record; the shared insert also folds it
+            // into the hourly/daily rollups
             let logging_time_fmt =
                 Self::format_unix_timestamp_to_local(&device_data.device_info.input_logging_time);
 
-            let trend_detail = trendlog_data_detail::ActiveModel {
-                parent_id: Set(parent_id),
-                value: Set(point.value.to_string()),
-                logging_time_fmt: Set(logging_time_fmt.clone()),
-                ..Default::default()
-            };
-
-            if let Err(e) = trendlog_data_detail::Entity::insert(trend_detail)
-                .exec(txn)
-                .await
+            if let Err(e) =
+                trendlog_detail::insert_detail(txn, parent_id, &point.value.to_string(), &logging_time_fmt).await
             {
                 sync_logger.error(&format!(
                     "? INPUT trend detail insert failed - Serial: {}, Index: {}, Error: {}",
@@ -2779,20 +2776,13 @@ impl T3000MainService {
                 }
             };
 
-            // Step 2: Insert detail record only
+            // Step 2: Insert the detail record; the shared insert also folds it
+            // into the hourly/daily rollups
             let logging_time_fmt =
                 Self::format_unix_timestamp_to_local(&device_data.device_info.output_logging_time);
 
-            let trend_detail = trendlog_data_detail::ActiveModel {
-                parent_id: Set(parent_id),
-                value: Set(point.value.to_string()),
-                logging_time_fmt: Set(logging_time_fmt.clone()),
-                ..Default::default()
-            };
-
-            if let Err(e) = trendlog_data_detail::Entity::insert(trend_detail)
-                .exec(txn)
-                .await
+            if let Err(e) =
+                trendlog_detail::insert_detail(txn, parent_id, &point.value.to_string(), &logging_time_fmt).await
             {
                 sync_logger.error(&format!(
                     "? OUTPUT trend detail insert failed - Serial: {}, Index: {}, Error: {}",
@@ -2858,21 +2848,14 @@ impl T3000MainService {
                 }
             };
 
-            // Step 2: Insert detail record only
+            // Step 2: Insert the detail record; the shared insert also folds it
+            // into the hourly/daily rollups
             let logging_time_fmt = Self::format_unix_timestamp_to_local(
                 &device_data.device_info.variable_logging_time,
             );
 
-            let trend_detail = trendlog_data_detail::ActiveModel {
-                parent_id: Set(parent_id),
-                value: Set(point.value.to_string()),
-                logging_time_fmt: Set(logging_time_fmt.clone()),
-                ..Default::default()
-            };
-
-            if let Err(e) = trendlog_data_detail::Entity::insert(trend_detail)
-                .exec(txn)
-                .await
+            if let Err(e) =
+                trendlog_detail::insert_detail(txn, parent_id, &point.value.to_string(), &logging_time_fmt).await
             {
                 sync_logger.error(&format!(
                     "? VARIABLE trend detail insert failed - Serial: {}, Index: {}, Error: {}",
@@ -2921,7 +2904,7 @@ impl T3000MainService {
 
             let spawn_result = tokio::time::timeout(
                 Duration::from_secs(config.timeout_seconds),
-                tokio::task::spawn_blocking(move || {
+                tokio::spawn(async move {
                     let input_json = serde_json::json!({
                         "action": WebViewMessageType::GET_WEBVIEW_LIST as i32,  // 17
                         "panelId": panel_id_clone,
@@ -2935,20 +2918,8 @@ impl T3000MainService {
 
                     info!("?? Sending JSON to C++ (action 17): {}", input_str);
 
-                    // 2MB buffer is sufficient for 64 points of one type
-                    const BUFFER_SIZE: usize = 2097152;
-                    let mut buffer: Vec<u8> = vec![0; BUFFER_SIZE];
-
-                    let input_bytes = input_str.as_bytes();
-                    if input_bytes.len() < BUFFER_SIZE {
-                        buffer[..input_bytes.len()].copy_from_slice(input_bytes);
-                        buffer[input_bytes.len()] = 0;
-                    } else {
-                        return Err("Input JSON too large for buffer".to_string());
-                    }
-
-                    let result = match call_handle_webview_msg(WebViewMessageType::GET_WEBVIEW_LIST as i32, &mut buffer) {
-                        Ok(code) => code,
+                    let (result, response) = match call_handle_webview_msg(WebViewMessageType::GET_WEBVIEW_LIST as i32, &input_str).await {
+                        Ok(reply) => reply,
                         Err(err) => {
                             error!("? Failed to call BacnetWebView_HandleWebViewMsg (action 17): {}", err);
                             return Err(format!("Failed to call BacnetWebView_HandleWebViewMsg: {}", err));
@@ -2961,16 +2932,13 @@ impl T3000MainService {
                     if result == -2 {
                         return Err("MFC application not initialized".to_string());
                     } else if result < 0 {
-                        let null_pos = buffer.iter().position(|&x| x == 0).unwrap_or(buffer.len());
-                        let error_response = String::from_utf8_lossy(&buffer[..null_pos]).to_string();
-                        return Err(format!("BacnetWebView HandleWebViewMsg (action 17) returned error code: {} - Response: {}", result, error_response));
+                        return Err(format!("BacnetWebView HandleWebViewMsg (action 17) returned error code: {} - Response: {}", result, response));
                     } else if result > 0 {
                         // Non-zero but non-negative: log as informational (e.g. device offline, partial data)
                         info!("??  GET_WEBVIEW_LIST action 17 returned code {} (non-fatal, treating as success)", result);
                     }
 
-                    let null_pos = buffer.iter().position(|&x| x == 0).unwrap_or(buffer.len());
-                    let result_str = String::from_utf8_lossy(&buffer[..null_pos]).to_string();
+                    let result_str = response;
 
                     if result_str.is_empty() || result_str == "{}" {
                         if result > 0 {
@@ -3079,7 +3047,7 @@ impl T3000MainService {
             // Run FFI call in a blocking task with timeout
             let spawn_result = tokio::time::timeout(
                 Duration::from_secs(config.timeout_seconds),
-                tokio::task::spawn_blocking(move || {
+                tokio::spawn(async move {
                     info!("?? Calling HandleWebViewMsg(15) via direct FFI for Panel: {}, Serial: {}...", panel_id_clone, serial_number_clone);
 
                     // Prepare input JSON with panel_id and serial_number
@@ -3094,35 +3062,10 @@ impl T3000MainService {
                     info!("?? About to call HandleWebViewMsg with LOGGING_DATA action - Panel: {}, Serial: {}", panel_id_clone, serial_number_clone);
                     info!("?? Sending JSON to C++: {}", input_str);
 
-                    // Prepare buffer for response. This DLL is built for i686 (32-bit),
-                    // so a 100MB allocation inside the ~2GB address space intermittently
-                    // fails and aborts T3000.exe via std::alloc::rust_oom. Actual
-                    // LOGGING_DATA responses are a few MB at most; the C++ bridge
-                    // returns -1 ("buffer too small") if a response ever exceeds this.
-                    // Allocate fallibly so a tight-memory failure returns an error
-                    // instead of killing the whole process.
-                    const BUFFER_SIZE: usize = 16 * 1024 * 1024; // 16MB
-                    let mut buffer: Vec<u8> = Vec::new();
-                    if buffer.try_reserve_exact(BUFFER_SIZE).is_err() {
-                        error!("Out of memory allocating FFI response buffer ({} bytes)", BUFFER_SIZE);
-                        return Err("Out of memory allocating FFI response buffer".to_string());
-                    }
-                    buffer.resize(BUFFER_SIZE, 0);
-
-                    // Write input JSON to buffer
-                    let input_bytes = input_str.as_bytes();
-                    if input_bytes.len() < BUFFER_SIZE {
-                        buffer[..input_bytes.len()].copy_from_slice(input_bytes);
-                        buffer[input_bytes.len()] = 0; // Null terminator
-                    } else {
-                        error!("? Input JSON too large for buffer");
-                        return Err("Input JSON too large".to_string());
-                    }
-
                     // Call the T3000 HandleWebViewMsg function via runtime loading
                     // Action 15 = LOGGING_DATA case in BacnetWebView.cpp
-                    let result = match call_handle_webview_msg(WebViewMessageType::LOGGING_DATA as i32, &mut buffer) {
-                        Ok(code) => code,
+                    let (result, response) = match call_handle_webview_msg(WebViewMessageType::LOGGING_DATA as i32, &input_str).await {
+                        Ok(reply) => reply,
                         Err(err) => {
                             error!("? Failed to call BacnetWebView_HandleWebViewMsg: {}", err);
                             if let Ok(mut sync_logger) = ServiceLogger::ffi() {
@@ -3136,9 +3079,8 @@ impl T3000MainService {
                         // MFC not ready - this is expected during startup
                         return Err("MFC application not initialized".to_string());
                     } else if result != 0 {
-                        // Check if we got any error message in the buffer despite the error code
-                        let null_pos = buffer.iter().position(|&x| x == 0).unwrap_or(buffer.len());
-                        let error_response = String::from_utf8_lossy(&buffer[..null_pos]).to_string();
+                        // Check if we got any error message in the reply despite the error code
+                        let error_response = response;
 
                         error!("? BacnetWebView_HandleWebViewMsg returned error code: {} with response: '{}'", result, error_response);
                         if let Ok(mut sync_logger) = ServiceLogger::ffi() {
@@ -3147,9 +3089,7 @@ impl T3000MainService {
                         return Err(format!("BacnetWebView HandleWebViewMsg returned error code: {} - Response: {}", result, error_response));
                     }
 
-                    // Find the null terminator to get the actual string length
-                    let null_pos = buffer.iter().position(|&x| x == 0).unwrap_or(buffer.len());
-                    let result_str = String::from_utf8_lossy(&buffer[..null_pos]).to_string();
+                    let result_str = response;
 
                     if result_str.is_empty() || result_str == "{}" {
                         warn!("?? HandleWebViewMsg returned empty or minimal response - T3000 data might not be ready");
@@ -3294,19 +3234,17 @@ impl T3000MainService {
             // Run FFI call in blocking task with timeout
             let spawn_result = tokio::time::timeout(
                 Duration::from_secs(GET_PANELS_TIMEOUT_SECS),
-                tokio::task::spawn_blocking(move || {
+                tokio::spawn(async move {
                     info!("?? Calling HandleWebViewMsg(GET_PANELS_LIST) for device list...");
 
-                    // Prepare buffer for response - increased size for large device lists
-                    const BUFFER_SIZE: usize = 1048576; // 1MB buffer for large device lists (was 10KB)
-                    let mut buffer: Vec<u8> = vec![0; BUFFER_SIZE];
-
                     // Call HandleWebViewMsg with Action 4 (GET_PANELS_LIST)
-                    let result = match call_handle_webview_msg(
+                    let (result, response) = match call_handle_webview_msg(
                         WebViewMessageType::GET_PANELS_LIST as i32,
-                        &mut buffer,
-                    ) {
-                        Ok(code) => code,
+                        "",
+                    )
+                    .await
+                    {
+                        Ok(reply) => reply,
                         Err(err) => {
                             error!(
                                 "? Failed to call HandleWebViewMsg(GET_PANELS_LIST): {}",
@@ -3319,8 +3257,6 @@ impl T3000MainService {
                     if result == -2 {
                         return Err("MFC application not initialized".to_string());
                     } else if result != 0 {
-                        let null_pos = buffer.iter().position(|&x| x == 0).unwrap_or(buffer.len());
-                        let _error_response = String::from_utf8_lossy(&buffer[..null_pos]).to_string();
                         error!(
                             "? HandleWebViewMsg(GET_PANELS_LIST) returned error code: {}",
                             result
@@ -3329,8 +3265,7 @@ impl T3000MainService {
                     }
 
                     // Parse response
-                    let null_pos = buffer.iter().position(|&x| x == 0).unwrap_or(buffer.len());
-                    let result_str = String::from_utf8_lossy(&buffer[..null_pos]).to_string();
+                    let result_str = response;
 
                     if result_str.is_empty() || result_str == "{}" {
                         warn!("?? GET_PANELS_LIST returned empty response");
@@ -4050,7 +3985,7 @@ impl T3000MainService {
         let derived_units = Self::derive_units_from_range(point.range);
 
         match existing {
-            Some(_) => {
+            Some(existing) => {
                 // UPDATE existing input point using update_many + col_expr
                 // (Safe pattern: PK doesn't include Input_Index, so Entity::update() could target wrong rows)
                 info!(
@@ -4089,6 +4024,7 @@ impl T3000MainService {
                     })?;
 
                 info!("? INPUT point {}:{} UPDATED", serial_number, point.index);
+                crate::mcp::notifications::point_value(serial_number, "INPUT", point.index, existing.f_value.as_deref(), point.value);
                 if let Err(e) = crate::haystack::tags_service::auto_tag_point(
                     txn,
                     "INPUTS",
@@ -4194,7 +4130,7 @@ impl T3000MainService {
         let derived_units = Self::derive_units_from_range(point.range);
 
         match existing {
-            Some(_) => {
+            Some(existing) => {
                 // UPDATE existing output point using update_many + col_expr
                 // (Safe pattern: PK doesn't include Output_Index, so Entity::update() could target wrong rows)
                 info!(
@@ -4233,6 +4169,7 @@ impl T3000MainService {
                     })?;
 
                 info!("? OUTPUT point {}:{} UPDATED", serial_number, point.index);
+                crate::mcp::notifications::point_value(serial_number, "OUTPUT", point.index, existing.f_value.as_deref(), point.value);
                 if let Err(e) = crate::haystack::tags_service::auto_tag_point(
                     txn,
                     "OUTPUTS",
@@ -4340,7 +4277,7 @@ impl T3000MainService {
         let derived_units = Self::derive_units_from_range(point.range);
 
         match existing {
-            Some(_) => {
+            Some(existing) => {
                 // UPDATE existing variable point using update_many + col_expr
                 // (Safe pattern: PK doesn't include Variable_Index, so Entity::update() could target wrong rows)
                 info!(
@@ -4382,6 +4319,7 @@ impl T3000MainService {
                     "? VARIABLE point {}:{} UPDATED",
                     serial_number, point.index
                 );
+                crate::mcp::notifications::point_value(serial_number, "VARIABLE", point.index, existing.f_value.as_deref(), point.value);
                 if let Err(e) = crate::haystack::tags_service::auto_tag_point(
                     txn,
                     "VARIABLES",
@@ -4509,14 +4447,8 @@ impl T3000MainService {
             .await?;
 
         // Insert detail record with time-series data
-        let detail_model = trendlog_data_detail::ActiveModel {
-            parent_id: Set(parent_id),
-            value: Set(point.value.to_string()),
-            logging_time_fmt: Set(Self::format_unix_timestamp_to_local(logging_time)),
-        };
-
-        trendlog_data_detail::Entity::insert(detail_model)
-            .exec(txn)
+        let logging_time_fmt = Self::format_unix_timestamp_to_local(logging_time);
+        trendlog_detail::insert_detail(txn, parent_id, &point.value.to_string(), &logging_time_fmt)
             .await
             .map_err(|e| {
                 AppError::DatabaseError(format!("Failed to insert trend log detail: {}", e))
@@ -0,0 +1,234 @@
+//! T3000.exe transport — `BacnetWebView_HandleWebViewMsg` plus the
+//! `BacnetWebView_GetTrendlog*` / `BacnetWebView_SyncMonitorData` exports.
+//!
+//! All calls run on the blocking pool under the global FFI lock
+//! (`t3_ffi_api_service::ffi_call_lock_timeout`), since T3000 is not safe to
+//! enter concurrently.
+
+use std::os::raw::c_char;
+use std::sync::OnceLock;
+use std::time::Instant;
+
+use async_trait::async_trait;
+
+use super::{DeviceTransport, TransportError, TransportReply};
+use crate::t3_device::t3_ffi_api_service::ffi_call_lock_timeout;
+use crate::t3_device::t3_ffi_sync_service::{load_t3000_function, WebViewMessageType, BACNETWEBVIEW_HANDLE_WEBVIEW_MSG_FN};
+
+/// LOGGING_DATA replies (every device with its points) are a few MB at most;
+/// the C++ bridge returns -1 ("buffer too small") if one ever exceeds this.
+const LOGGING_DATA_BUFFER_SIZE: usize = 16 * 1024 * 1024;
+/// Graphic data and library payloads carry embedded images.
+const GRAPHICS_BUFFER_SIZE: usize = 10 * 1024 * 1024;
+/// Enough for 64 points of one type.
+const WEBVIEW_LIST_BUFFER_SIZE: usize = 2 * 1024 * 1024;
+/// Everything else (panel lists, updates, settings).
+const DEFAULT_BUFFER_SIZE: usize = 1024 * 1024;
+/// Trendlog list/entry JSON is small.
+const TRENDLOG_BUFFER_SIZE: usize = 65536;
+const FFI_LOCK_TIMEOUT_SECS: u64 = 60;
+
+static LOADED_AT: OnceLock<Instant> = OnceLock::new();
+
+/// When HandleWebViewMsg was first resolved, for callers that hold off
+/// specific actions while T3000 warms up.
+pub fn loaded_at() -> Option<Instant> {
+    LOADED_AT.get().copied()
+}
+
+fn ensure_loaded() -> Result<(), TransportError> {
+    if unsafe { load_t3000_function() } {
+        let _ = LOADED_AT.set(Instant::now());
+        Ok(())
+    } else {
+        Err(TransportError::Unavailable("T3000 functions not loaded".to_string()))
+    }
+}
+
+fn lock() -> Result<std::sync::MutexGuard<'static, ()>, TransportError> {
+    ffi_call_lock_timeout(FFI_LOCK_TIMEOUT_SECS)
+        .ok_or_else(|| TransportError::Other("FFI lock acquisition timed out - prior C++ FFI call may be stuck".to_string()))
+}
+
+/// Size of the shared in/out HandleWebViewMsg buffer for `action`.
+fn message_buffer_size(action: i32) -> usize {
+    match action {
+        a if a == WebViewMessageType::LOGGING_DATA as i32 => LOGGING_DATA_BUFFER_SIZE,
+        a if a == WebViewMessageType::GET_WEBVIEW_LIST as i32 => WEBVIEW_LIST_BUFFER_SIZE,
+        a if a == WebViewMessageType::GET_PANEL_DATA as i32
+            || a == WebViewMessageType::GET_INITIAL_DATA as i32
+            || a == WebViewMessageType::SAVE_GRAPHIC_DATA as i32
+            || a == WebViewMessageType::LOAD_GRAPHIC_ENTRY as i32
+            || a == WebViewMessageType::SAVE_IMAGE as i32
+            || a == WebViewMessageType::SAVE_LIBRAY_DATA as i32
+            || a == WebViewMessageType::SAVE_NEW_LIBRARY_DATA as i32 =>
+        {
+            GRAPHICS_BUFFER_SIZE
+        }
+        _ => DEFAULT_BUFFER_SIZE,
+    }
+}
+
+/// Zeroed buffer, allocated fallibly: the DLL is built for i686, where a
+/// large allocation in the ~2GB address space can fail, and an infallible
+/// one would abort T3000.exe via `rust_oom`.
+fn alloc_buffer(size: usize) -> Result<Vec<u8>, TransportError> {
+    let mut buffer: Vec<u8> = Vec::new();
+    buffer
+        .try_reserve_exact(size)
+        .map_err(|_| TransportError::Other(format!("Out of memory allocating FFI buffer ({} bytes)", size)))?;
+    buffer.resize(size, 0);
+    Ok(buffer)
+}
+
+fn read_until_nul(buffer: &[u8]) -> String {
+    let end = buffer.iter().position(|&b| b == 0).unwrap_or(buffer.len());
+    String::from_utf8_lossy(&buffer[..end]).to_string()
+}
+
+/// The first `len` bytes of a buffer T3000 filled; a length outside the
+/// buffer is a failure, not something to slice by.
+fn read_len(buffer: &[u8], len: i32) -> Result<String, TransportError> {
+    match usize::try_from(len) {
+        Ok(n) if n > 0 && n <= buffer.len() => Ok(String::from_utf8_lossy(&buffer[..n]).to_string()),
+        _ => Err(TransportError::Failed(len)),
+    }
+}
+
+async fn blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T, TransportError> + Send + 'static,
+) -> Result<T, TransportError> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| TransportError::Other(format!("Task spawn error: {}", e)))?
+}
+
+/// T3000.exe, loaded in-process.
+pub struct FfiTransport;
+
+#[async_trait]
+impl DeviceTransport for FfiTransport {
+    fn name(&self) -> &'static str {
+        "ffi"
+    }
+
+    async fn is_available(&self) -> bool {
+        blocking(ensure_loaded).await.is_ok()
+    }
+
+    async fn send(&self, action: i32, request: &str) -> Result<TransportReply, TransportError> {
+        let request = request.to_string();
+        blocking(move || {
+            let _guard = lock()?;
+            ensure_loaded()?;
+
+            let input = request.as_bytes();
+            let size = message_buffer_size(action).max(input.len() + 1);
+            let mut buffer = alloc_buffer(size)?;
+            buffer[..input.len()].copy_from_slice(input);
+
+            let func = unsafe { std::ptr::addr_of!(BACNETWEBVIEW_HANDLE_WEBVIEW_MSG_FN).read() }
+                .ok_or_else(|| TransportError::Unavailable("BacnetWebView_HandleWebViewMsg function not loaded".to_string()))?;
+            let code = unsafe { func(action, buffer.as_mut_ptr() as *mut c_char, buffer.len() as i32) };
+
+            Ok(TransportReply { code, body: read_until_nul(&buffer) })
+        })
+        .await
+    }
+
+    async fn trendlog_list(&self, panel_id: i32) -> Result<String, TransportError> {
+        blocking(move || {
+            let _guard = lock()?;
+            let func = exports::trendlog_list()
+                .ok_or_else(|| TransportError::Unavailable("BacnetWebView_GetTrendlogList export not available".to_string()))?;
+            let mut buffer = alloc_buffer(TRENDLOG_BUFFER_SIZE)?;
+            let len = unsafe { func(panel_id, buffer.as_mut_ptr() as *mut c_char, buffer.len() as i32) };
+            read_len(&buffer, len)
+        })
+        .await
+    }
+
+    async fn trendlog_entry(&self, panel_id: i32, monitor_index: i32) -> Result<String, TransportError> {
+        blocking(move || {
+            let _guard = lock()?;
+            let func = exports::trendlog_entry()
+                .ok_or_else(|| TransportError::Unavailable("BacnetWebView_GetTrendlogEntry export not available".to_string()))?;
+            let mut buffer = alloc_buffer(TRENDLOG_BUFFER_SIZE)?;
+            let len = unsafe { func(panel_id, monitor_index, buffer.as_mut_ptr() as *mut c_char, buffer.len() as i32) };
+            read_len(&buffer, len)
+        })
+        .await
+    }
+
+    async fn sync_monitor_data(&self, panel_id: i32) -> Result<i32, TransportError> {
+        blocking(move || {
+            let _guard = lock()?;
+            let Some(func) = exports::sync_monitor_data() else {
+                // Older T3000 builds don't export it; the trendlog exports still work.
+                return Ok(0);
+            };
+            match unsafe { func(panel_id) } {
+                count if count >= 0 => Ok(count),
+                code => Err(TransportError::Failed(code)),
+            }
+        })
+        .await
+    }
+}
+
+/// Trendlog exports, resolved from the running process.
+#[cfg(windows)]
+mod exports {
+    use std::os::raw::{c_char, c_int};
+
+    use winapi::um::libloaderapi::{GetModuleHandleA, GetProcAddress};
+
+    pub type TrendlogListFn = unsafe extern "C" fn(c_int, *mut c_char, c_int) -> c_int;
+    pub type TrendlogEntryFn = unsafe extern "C" fn(c_int, c_int, *mut c_char, c_int) -> c_int;
+    pub type SyncMonitorDataFn = unsafe extern "C" fn(c_int) -> c_int;
+
+    fn resolve(name: &[u8]) -> Option<*mut std::ffi::c_void> {
+        unsafe {
+            // GetModuleHandleA(NULL) is the current process (T3000.exe).
+            let module = GetModuleHandleA(std::ptr::null());
+            if module.is_null() {
+                return None;
+            }
+            let ptr = GetProcAddress(module, name.as_ptr() as *const c_char);
+            (!ptr.is_null()).then_some(ptr as *mut std::ffi::c_void)
+        }
+    }
+
+    pub fn trendlog_list() -> Option<TrendlogListFn> {
+        resolve(b"BacnetWebView_GetTrendlogList\0").map(|p| unsafe { std::mem::transmute(p) })
+    }
+
+    pub fn trendlog_entry() -> Option<TrendlogEntryFn> {
+        resolve(b"BacnetWebView_GetTrendlogEntry\0").map(|p| unsafe { std::mem::transmute(p) })
+    }
+
+    pub fn sync_monitor_data() -> Option<SyncMonitorDataFn> {
+        resolve(b"BacnetWebView_SyncMonitorData\0").map(|p| unsafe { std::mem::transmute(p) })
+    }
+}
+
+#[cfg(not(windows))]
+mod exports {
+    use std::os::raw::{c_char, c_int};
+
+    pub type TrendlogListFn = unsafe extern "C" fn(c_int, *mut c_char, c_int) -> c_int;
+    pub type TrendlogEntryFn = unsafe extern "C" fn(c_int, c_int, *mut c_char, c_int) -> c_int;
+    pub type SyncMonitorDataFn = unsafe extern "C" fn(c_int) -> c_int;
+
+    pub fn trendlog_list() -> Option<TrendlogListFn> {
+        None
+    }
+
+    pub fn trendlog_entry() -> Option<TrendlogEntryFn> {
+        None
+    }
+
+    pub fn sync_monitor_data() -> Option<SyncMonitorDataFn> {
+        None
+    }
+}
@@ -0,0 +1,194 @@
+//! Device transport — how the API reaches T3000 panels.
+//!
+//! Every refresh/update route, the sync service, the trendlog monitor and the
+//! `/api/t3000/ffi/call` pass-through talk to devices through the
+//! [`DeviceTransport`] installed here instead of calling T3000.exe function
+//! pointers directly.
+//!
+//! - `ffi`     — `BacnetWebView_HandleWebViewMsg` and the trendlog exports in
+//!   T3000.exe (Windows only)
+//! - `offline` — no device access; every call fails with `Unavailable`
+//! - `sim`     — a simulated site loaded from `T3_SIM_SITE` (see [`sim`])
+//!
+//! The transport is chosen once at startup from `T3_DEVICE_TRANSPORT`
+//! (`select_from_env`); tests and alternative backends call `install`.
+
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
+
+use super::t3_ffi_sync_service::WebViewMessageType;
+
+pub mod ffi;
+pub mod sim;
+
+/// Environment variable naming the transport to install at startup.
+pub const TRANSPORT_ENV: &str = "T3_DEVICE_TRANSPORT";
+
+/// Raw result of one HandleWebViewMsg-style exchange.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransportReply {
+    /// Return code as T3000 reports it: 0 = success, -1 = error (body may
+    /// still carry JSON), -2 = MFC application not initialized.
+    pub code: i32,
+    pub body: String,
+}
+
+impl TransportReply {
+    pub fn ok(body: impl Into<String>) -> Self {
+        Self { code: 0, body: body.into() }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TransportError {
+    #[error("{0}")]
+    Unavailable(String),
+    #[error("MFC application not initialized")]
+    NotInitialized,
+    #[error("Transport call failed with code: {0}")]
+    Failed(i32),
+    #[error("{0}")]
+    Other(String),
+}
+
+impl From<TransportError> for String {
+    fn from(e: TransportError) -> Self {
+        e.to_string()
+    }
+}
+
+/// A way of exchanging WebViewMessageType requests and trendlog queries with
+/// T3000 panels.
+#[async_trait]
+pub trait DeviceTransport: Send + Sync {
+    /// Short name used in logs and `T3_DEVICE_TRANSPORT` ("ffi", "offline", "sim", ...).
+    fn name(&self) -> &'static str;
+
+    /// Whether calls can currently reach a device.
+    async fn is_available(&self) -> bool;
+
+    /// Send one JSON request for `action` and return the raw code and body.
+    async fn send(&self, action: i32, request: &str) -> Result<TransportReply, TransportError>;
+
+    /// `BacnetWebView_GetTrendlogList` — JSON list of a panel's monitors.
+    async fn trendlog_list(&self, panel_id: i32) -> Result<String, TransportError>;
+
+    /// `BacnetWebView_GetTrendlogEntry` — JSON for one monitor and its inputs.
+    async fn trendlog_entry(&self, panel_id: i32, monitor_index: i32) -> Result<String, TransportError>;
+
+    /// `BacnetWebView_SyncMonitorData` — copy the panel's monitors into the
+    /// global table the trendlog exports read. Returns the monitor count;
+    /// transports without that split have nothing to do.
+    async fn sync_monitor_data(&self, _panel_id: i32) -> Result<i32, TransportError> {
+        Ok(0)
+    }
+
+    /// `send`, mapping non-zero codes to errors and returning the body.
+    async fn call(&self, action: i32, request: &str) -> Result<String, TransportError> {
+        let reply = self.send(action, request).await?;
+        match reply.code {
+            0 => Ok(reply.body),
+            -2 => Err(TransportError::NotInitialized),
+            code => Err(TransportError::Failed(code)),
+        }
+    }
+
+    /// GET_PANEL_DATA (action 0) for one panel.
+    async fn get_panel_data(&self, panel_id: i32) -> Result<Value, TransportError> {
+        let request = json!({ "action": WebViewMessageType::GET_PANEL_DATA as i32, "panelId": panel_id });
+        self.call_json(WebViewMessageType::GET_PANEL_DATA, &request).await
+    }
+
+    /// LOGGING_DATA (action 15) — every device with its points.
+    async fn logging_data(&self) -> Result<Value, TransportError> {
+        let request = json!({ "action": WebViewMessageType::LOGGING_DATA as i32 });
+        self.call_json(WebViewMessageType::LOGGING_DATA, &request).await
+    }
+
+    /// GET_WEBVIEW_LIST (action 17) — read entries of one type from a device.
+    async fn get_webview_list(&self, request: &Value) -> Result<Value, TransportError> {
+        self.call_json(WebViewMessageType::GET_WEBVIEW_LIST, request).await
+    }
+
+    /// UPDATE_WEBVIEW_LIST (action 16) — write full records back to a device.
+    async fn update_webview_list(&self, request: &Value) -> Result<Value, TransportError> {
+        self.call_json(WebViewMessageType::UPDATE_WEBVIEW_LIST, request).await
+    }
+
+    async fn call_json(&self, action: WebViewMessageType, request: &Value) -> Result<Value, TransportError> {
+        let body = self.call(action as i32, &request.to_string()).await?;
+        serde_json::from_str(&body)
+            .map_err(|e| TransportError::Other(format!("Invalid JSON from {} transport (action {}): {}", self.name(), action as i32, e)))
+    }
+}
+
+/// No device access — the default on platforms without T3000.exe.
+pub struct OfflineTransport;
+
+const OFFLINE_MESSAGE: &str = "No device transport available (T3000.exe is not loaded on this platform)";
+
+#[async_trait]
+impl DeviceTransport for OfflineTransport {
+    fn name(&self) -> &'static str {
+        "offline"
+    }
+
+    async fn is_available(&self) -> bool {
+        false
+    }
+
+    async fn send(&self, _action: i32, _request: &str) -> Result<TransportReply, TransportError> {
+        Err(TransportError::Unavailable(OFFLINE_MESSAGE.to_string()))
+    }
+
+    async fn trendlog_list(&self, _panel_id: i32) -> Result<String, TransportError> {
+        Err(TransportError::Unavailable(OFFLINE_MESSAGE.to_string()))
+    }
+
+    async fn trendlog_entry(&self, _panel_id: i32, _monitor_index: i32) -> Result<String, TransportError> {
+        Err(TransportError::Unavailable(OFFLINE_MESSAGE.to_string()))
+    }
+}
+
+static CURRENT: Lazy<RwLock<Arc<dyn DeviceTransport>>> = Lazy::new(|| RwLock::new(default_transport()));
+
+fn default_transport() -> Arc<dyn DeviceTransport> {
+    if cfg!(windows) {
+        Arc::new(ffi::FfiTransport)
+    } else {
+        Arc::new(OfflineTransport)
+    }
+}
+
+/// The installed transport.
+pub fn current() -> Arc<dyn DeviceTransport> {
+    CURRENT.read().unwrap_or_else(|p| p.into_inner()).clone()
+}
+
+/// Replace the installed transport; later calls to `current()` use it.
+pub fn install(transport: Arc<dyn DeviceTransport>) {
+    *CURRENT.write().unwrap_or_else(|p| p.into_inner()) = transport;
+}
+
+/// Build a transport by name.
+pub fn from_name(name: &str) -> Result<Arc<dyn DeviceTransport>, String> {
+    match name.trim().to_ascii_lowercase().as_str() {
+        "" => Ok(default_transport()),
+        "ffi" => Ok(Arc::new(ffi::FfiTransport)),
+        "offline" => Ok(Arc::new(OfflineTransport)),
+        "sim" => Ok(Arc::new(sim::SimTransport::from_env()?)),
+        other => Err(format!("Unknown device transport '{}' (expected 'ffi', 'offline' or 'sim')", other)),
+    }
+}
+
+/// Install the transport named by `T3_DEVICE_TRANSPORT` (platform default
+/// when unset) and return its name.
+pub fn select_from_env() -> Result<&'static str, String> {
+    let transport = from_name(&std::env::var(TRANSPORT_ENV).unwrap_or_default())?;
+    let name = transport.name();
+    install(transport);
+    Ok(name)
+}
@@ -0,0 +1,109 @@
+//! Simulated LAN-scan responder — answers the 0x64 UDP query with one 0x65
+//! packet per panel, laid out the way `lan_scan::protocol::parse_scan_response`
+//! reads them.
+
+use std::net::{Ipv4Addr, SocketAddr};
+
+use tokio::net::UdpSocket;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use super::site::{PanelSpec, Site};
+use crate::lan_scan::protocol::{RESPONSE_MSG, UPD_BROADCAST_QRY_MSG};
+
+/// Length of a full response (through the minitype byte).
+const RESPONSE_LEN: usize = 68;
+
+/// Encode `panel` as a scan response packet.
+pub fn scan_response(panel: &PanelSpec) -> Vec<u8> {
+    let mut buf = vec![0u8; RESPONSE_LEN];
+    buf[0] = RESPONSE_MSG;
+
+    // Interleaved fields: value byte at even offsets, reserve byte after.
+    let serial = panel.serial_number.to_le_bytes();
+    buf[4] = serial[0];
+    buf[6] = serial[1];
+    buf[8] = serial[2];
+    buf[10] = serial[3];
+    buf[12] = panel.product_id;
+    buf[14] = panel.modbus_id;
+
+    let ip: Ipv4Addr = panel.ip.parse().unwrap_or(Ipv4Addr::LOCALHOST);
+    let [a, b, c, d] = ip.octets();
+    buf[16] = a;
+    buf[18] = b;
+    buf[20] = c;
+    buf[22] = d;
+
+    buf[24..26].copy_from_slice(&panel.modbus_port.to_le_bytes());
+    buf[26..28].copy_from_slice(&panel.firmware.to_le_bytes());
+    buf[28..30].copy_from_slice(&panel.hardware.to_le_bytes());
+    // 30..34: parent serial, 0 = top-level device
+
+    let oi = panel.object_instance.to_le_bytes();
+    buf[35] = oi[0];
+    buf[34] = oi[1];
+    buf[58] = oi[2];
+    buf[57] = oi[3];
+    buf[36] = panel.panel_number;
+
+    let name = panel.name.as_bytes();
+    let len = name.len().min(20);
+    buf[37..37 + len].copy_from_slice(&name[..len]);
+
+    // 59: isp_mode 0 = application
+    buf[60..62].copy_from_slice(&panel.bacnet_port.to_le_bytes());
+    buf[62] = 1; // hardware_info present
+    buf[63] = 1; // subnet_protocol: Modbus TCP/IP
+    buf
+}
+
+/// A running responder; stops when dropped.
+pub struct LanResponder {
+    local_addr: SocketAddr,
+    task: JoinHandle<()>,
+}
+
+impl LanResponder {
+    /// Bind `addr` (port 0 for an ephemeral port) and answer scan queries
+    /// for every panel in `site`.
+    pub async fn spawn(site: &Site, addr: SocketAddr) -> std::io::Result<Self> {
+        let socket = UdpSocket::bind(addr).await?;
+        let local_addr = socket.local_addr()?;
+        let packets: Vec<Vec<u8>> = site.panels.iter().map(scan_response).collect();
+
+        let task = tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            loop {
+                let (len, peer) = match socket.recv_from(&mut buf).await {
+                    Ok(received) => received,
+                    Err(e) => {
+                        warn!("Simulator LAN responder receive failed: {}", e);
+                        continue;
+                    }
+                };
+                if len == 0 || buf[0] != UPD_BROADCAST_QRY_MSG {
+                    continue;
+                }
+                debug!("Simulator answering LAN scan from {} with {} panel(s)", peer, packets.len());
+                for packet in &packets {
+                    if let Err(e) = socket.send_to(packet, peer).await {
+                        warn!("Simulator LAN responder send to {} failed: {}", peer, e);
+                    }
+                }
+            }
+        });
+
+        Ok(Self { local_addr, task })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+impl Drop for LanResponder {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
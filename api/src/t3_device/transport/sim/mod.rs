@@ -0,0 +1,621 @@
+//! Simulated T3000 site — a [`DeviceTransport`] that answers the
+//! WebViewMessageType actions and trendlog exports from a site file instead of
+//! real panels, so sync, FDD and trendlog pipelines can run headless.
+//!
+//! Point values follow the [`Signal`] configured for each point and are a pure
+//! function of simulated time, so two runs over the same site and clock read
+//! the same values. The clock is wall time since the transport was created, or
+//! a manual clock that only moves on [`SimTransport::advance`].
+//!
+//! Writes (UPDATE_WEBVIEW_LIST) behave like a panel: `auto_manual = 1` pins a
+//! point at the written value until it is put back in auto, labels and
+//! descriptions stick, and constant points (setpoints) take the written value.
+//!
+//! Selected with `T3_DEVICE_TRANSPORT=sim`; the site comes from `T3_SIM_SITE`
+//! (YAML, or JSON by extension) and defaults to a built-in single-AHU site.
+
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use once_cell::sync::OnceCell;
+use serde_json::{json, Value};
+
+use super::{DeviceTransport, TransportError, TransportReply};
+use crate::lan_scan::protocol::BROADCAST_DEST_PORT;
+use crate::t3_device::t3_ffi_sync_service::WebViewMessageType;
+use crate::t3_device::trendlog_monitor_service::{
+    TrendlogEntryResponse, TrendlogInputData, TrendlogListResponse, TrendlogMonitorData,
+};
+
+pub mod lan;
+pub mod site;
+
+pub use lan::LanResponder;
+pub use site::{PanelSpec, PointKind, PointRef, PointSpec, ProgramSpec, ScheduleSpec, Signal, Site, TrendlogSpec};
+
+/// Path of the site file to simulate.
+pub const SITE_ENV: &str = "T3_SIM_SITE";
+/// UDP port the LAN-scan responder listens on (defaults to 1234).
+pub const LAN_PORT_ENV: &str = "T3_SIM_LAN_PORT";
+
+// Entry types matching the C++ defines.
+const BAC_SCH: i32 = 4;
+const BAC_PRG: i32 = 6;
+const BAC_AMON: i32 = 9;
+
+/// The site named by `T3_SIM_SITE`, or the built-in demo site.
+pub fn site_from_env() -> Result<Site, String> {
+    match std::env::var(SITE_ENV) {
+        Ok(path) if !path.trim().is_empty() => Site::load(path.trim()),
+        _ => Ok(Site::demo()),
+    }
+}
+
+static LAN_RESPONDER: OnceCell<LanResponder> = OnceCell::new();
+
+/// Start the process-wide LAN-scan responder for the `T3_SIM_SITE` site on
+/// `T3_SIM_LAN_PORT`. Later calls return the running responder's address.
+pub async fn start_lan_responder() -> Result<SocketAddr, String> {
+    if let Some(responder) = LAN_RESPONDER.get() {
+        return Ok(responder.local_addr());
+    }
+    let port = match std::env::var(LAN_PORT_ENV) {
+        Ok(port) => port.trim().parse::<u16>().map_err(|e| format!("Invalid {} '{}': {}", LAN_PORT_ENV, port, e))?,
+        Err(_) => BROADCAST_DEST_PORT,
+    };
+    let site = site_from_env()?;
+    let responder = LanResponder::spawn(&site, SocketAddr::from((Ipv4Addr::UNSPECIFIED, port)))
+        .await
+        .map_err(|e| format!("Failed to bind simulator LAN responder on port {}: {}", port, e))?;
+    let addr = responder.local_addr();
+    // A concurrent caller may have won; its responder stays, ours is dropped.
+    let _ = LAN_RESPONDER.set(responder);
+    Ok(LAN_RESPONDER.get().map(|r| r.local_addr()).unwrap_or(addr))
+}
+
+enum Clock {
+    Real(Instant),
+    Manual(Mutex<Duration>),
+}
+
+/// Pinned values and mutable copies of the site's panels.
+struct SimState {
+    site: Site,
+    /// (serial, entry type, index) → value held in manual mode.
+    pinned: HashMap<(u32, i32, usize), f64>,
+}
+
+pub struct SimTransport {
+    clock: Clock,
+    state: Mutex<SimState>,
+}
+
+impl SimTransport {
+    /// Simulate `site` on wall time.
+    pub fn new(site: Site) -> Self {
+        Self::with_clock(site, Clock::Real(Instant::now()))
+    }
+
+    /// Simulate `site` on a clock that starts at zero and only moves on `advance`.
+    pub fn with_manual_clock(site: Site) -> Self {
+        Self::with_clock(site, Clock::Manual(Mutex::new(Duration::ZERO)))
+    }
+
+    fn with_clock(site: Site, clock: Clock) -> Self {
+        Self { clock, state: Mutex::new(SimState { site, pinned: HashMap::new() }) }
+    }
+
+    /// Simulate the site named by `T3_SIM_SITE` on wall time.
+    pub fn from_env() -> Result<Self, String> {
+        Ok(Self::new(site_from_env()?))
+    }
+
+    /// Move the manual clock forward. No effect on a wall-time simulator.
+    pub fn advance(&self, by: Duration) {
+        if let Clock::Manual(elapsed) = &self.clock {
+            *elapsed.lock().unwrap_or_else(|p| p.into_inner()) += by;
+        }
+    }
+
+    /// Simulated time since start.
+    pub fn elapsed(&self) -> Duration {
+        match &self.clock {
+            Clock::Real(start) => start.elapsed(),
+            Clock::Manual(elapsed) => *elapsed.lock().unwrap_or_else(|p| p.into_inner()),
+        }
+    }
+
+    /// Snapshot of the site, including any labels changed by writes.
+    pub fn site(&self) -> Site {
+        self.state().site.clone()
+    }
+
+    /// Current value of one point, as a read would report it.
+    pub fn value(&self, serial_number: u32, kind: PointKind, index: usize) -> Option<f64> {
+        let t = self.elapsed().as_secs_f64();
+        let state = self.state();
+        let panel = state.site.panels.iter().find(|p| p.serial_number == serial_number)?;
+        let spec = panel.points(kind).get(index)?;
+        Some(state.point_value(panel, kind, index, spec, t))
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, SimState> {
+        self.state.lock().unwrap_or_else(|p| p.into_inner())
+    }
+
+    fn handle(&self, action: i32, request: &Value) -> TransportReply {
+        let t = self.elapsed().as_secs_f64();
+        let mut state = self.state();
+        let result = match action {
+            a if a == WebViewMessageType::GET_PANEL_DATA as i32 => state.panel_data(request, t),
+            a if a == WebViewMessageType::GET_PANELS_LIST as i32 => Ok(state.panels_list()),
+            a if a == WebViewMessageType::LOGGING_DATA as i32 => Ok(state.logging_data(request, t)),
+            a if a == WebViewMessageType::UPDATE_WEBVIEW_LIST as i32 => state.update_webview_list(request),
+            a if a == WebViewMessageType::GET_WEBVIEW_LIST as i32 => state.get_webview_list(request, t),
+            other => Err(format!("Action {} is not simulated", other)),
+        };
+        match result {
+            Ok(body) => TransportReply::ok(body.to_string()),
+            Err(error) => TransportReply { code: -1, body: json!({ "success": false, "error": error }).to_string() },
+        }
+    }
+}
+
+#[async_trait]
+impl DeviceTransport for SimTransport {
+    fn name(&self) -> &'static str {
+        "sim"
+    }
+
+    async fn is_available(&self) -> bool {
+        true
+    }
+
+    async fn send(&self, action: i32, request: &str) -> Result<TransportReply, TransportError> {
+        // GET_PANELS_LIST is sent with an empty buffer.
+        let request = if request.trim().is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_str(request)
+                .map_err(|e| TransportError::Other(format!("Invalid request JSON for action {}: {}", action, e)))?
+        };
+        Ok(self.handle(action, &request))
+    }
+
+    async fn trendlog_list(&self, panel_id: i32) -> Result<String, TransportError> {
+        let t = self.elapsed().as_secs_f64();
+        let state = self.state();
+        let panel = state.panel_by_number(panel_id).ok_or(TransportError::Failed(-1))?;
+        let trendlogs: Vec<TrendlogMonitorData> = panel
+            .trendlogs
+            .iter()
+            .enumerate()
+            .map(|(num, trendlog)| monitor_data(panel, num, trendlog, t))
+            .collect();
+        let response = TrendlogListResponse {
+            success: true,
+            panel_id,
+            total_monitors: trendlogs.len() as i32,
+            trendlogs,
+            timestamp: chrono::Utc::now().timestamp(),
+        };
+        serde_json::to_string(&response).map_err(|e| TransportError::Other(e.to_string()))
+    }
+
+    async fn trendlog_entry(&self, panel_id: i32, monitor_index: i32) -> Result<String, TransportError> {
+        let t = self.elapsed().as_secs_f64();
+        let state = self.state();
+        let panel = state.panel_by_number(panel_id).ok_or(TransportError::Failed(-1))?;
+        let trendlog = usize::try_from(monitor_index)
+            .ok()
+            .and_then(|i| panel.trendlogs.get(i))
+            .ok_or(TransportError::Failed(-1))?;
+        let inputs = trendlog
+            .inputs
+            .iter()
+            .enumerate()
+            .map(|(index, input)| TrendlogInputData {
+                index: index as i32,
+                panel: panel.panel_number as i32,
+                sub_panel: panel.panel_number as i32,
+                point_type: input.kind.monitor_point_type(),
+                point_number: input.index as i32,
+                network: 0,
+                range: panel.points(input.kind)[input.index].range,
+            })
+            .collect();
+        let response = TrendlogEntryResponse {
+            success: true,
+            panel_id,
+            monitor_index,
+            trendlog: monitor_data(panel, monitor_index as usize, trendlog, t),
+            inputs,
+            timestamp: chrono::Utc::now().timestamp(),
+        };
+        serde_json::to_string(&response).map_err(|e| TransportError::Other(e.to_string()))
+    }
+
+    async fn sync_monitor_data(&self, panel_id: i32) -> Result<i32, TransportError> {
+        let state = self.state();
+        let panel = state.panel_by_number(panel_id).ok_or(TransportError::Failed(-1))?;
+        Ok(panel.trendlogs.len() as i32)
+    }
+}
+
+impl SimState {
+    fn panel_by_number(&self, panel_number: i32) -> Option<&PanelSpec> {
+        self.site.panels.iter().find(|p| p.panel_number as i32 == panel_number)
+    }
+
+    /// Panel addressed by a request: `serialNumber` when given, else `panelId`.
+    fn target(&self, request: &Value) -> Option<usize> {
+        let serial = request.get("serialNumber").and_then(|v| v.as_u64()).filter(|&s| s > 0);
+        let panel_id = request.get("panelId").and_then(|v| v.as_i64());
+        self.site.panels.iter().position(|p| match (serial, panel_id) {
+            (Some(serial), _) => p.serial_number as u64 == serial,
+            (None, Some(panel_id)) => p.panel_number as i64 == panel_id,
+            (None, None) => false,
+        })
+    }
+
+    fn require_target(&self, request: &Value) -> Result<usize, String> {
+        self.target(request).ok_or_else(|| {
+            format!(
+                "No simulated panel for serialNumber {} / panelId {}",
+                request.get("serialNumber").unwrap_or(&Value::Null),
+                request.get("panelId").unwrap_or(&Value::Null)
+            )
+        })
+    }
+
+    fn point_value(&self, panel: &PanelSpec, kind: PointKind, index: usize, spec: &PointSpec, t: f64) -> f64 {
+        let raw = self
+            .pinned
+            .get(&(panel.serial_number, kind.entry_type(), index))
+            .copied()
+            .unwrap_or_else(|| spec.signal.at(t));
+        if spec.digital {
+            if raw >= 0.5 { 1.0 } else { 0.0 }
+        } else {
+            (raw * 1000.0).round() / 1000.0
+        }
+    }
+
+    fn is_pinned(&self, panel: &PanelSpec, entry_type: i32, index: usize) -> bool {
+        self.pinned.contains_key(&(panel.serial_number, entry_type, index))
+    }
+
+    /// LOGGING_DATA / GET_WEBVIEW_LIST `device_data` record.
+    fn point_record(&self, panel: &PanelSpec, kind: PointKind, index: usize, spec: &PointSpec, t: f64) -> Value {
+        let value = self.point_value(panel, kind, index, spec, t);
+        json!({
+            "type": kind.type_name(),
+            "index": index,
+            "pid": panel.panel_number,
+            "panel": panel.panel_number,
+            "description": spec.description,
+            "label": spec.label,
+            "value": value,
+            "unit": spec.unit,
+            "range": spec.range,
+            "auto_manual": self.is_pinned(panel, kind.entry_type(), index) as i32,
+            "decom": 0,
+            "digital_analog": if spec.digital { 0 } else { 1 },
+            "calibration_h": 0,
+            "calibration_l": 0,
+            "calibration_sign": 0,
+            "filter": 0,
+            "control": if spec.digital { value as i32 } else { 0 },
+        })
+    }
+
+    /// GET_WEBVIEW_LIST `items` record, in the refresh routes' key style.
+    fn point_item(&self, panel: &PanelSpec, kind: PointKind, index: usize, spec: &PointSpec, t: f64) -> Value {
+        let index_key = match kind {
+            PointKind::Input => "inputIndex",
+            PointKind::Output => "outputIndex",
+            PointKind::Variable => "variableIndex",
+        };
+        let mut item = json!({
+            "label": spec.label,
+            "fullLabel": spec.description,
+            "value": self.point_value(panel, kind, index, spec, t),
+            "range": spec.range,
+            "autoManual": self.is_pinned(panel, kind.entry_type(), index) as i32,
+            "digitalAnalog": if spec.digital { 0 } else { 1 },
+            "units": spec.unit,
+            "calibration": 0,
+            "sign": 0,
+            "filter": 0,
+            "status": 0,
+        });
+        item[index_key] = json!(index);
+        item
+    }
+
+    fn all_points(&self, panel: &PanelSpec, t: f64) -> Vec<Value> {
+        [PointKind::Input, PointKind::Output, PointKind::Variable]
+            .into_iter()
+            .flat_map(|kind| {
+                panel
+                    .points(kind)
+                    .iter()
+                    .enumerate()
+                    .map(move |(index, spec)| (kind, index, spec))
+            })
+            .map(|(kind, index, spec)| self.point_record(panel, kind, index, spec, t))
+            .collect()
+    }
+
+    fn panel_data(&self, request: &Value, t: f64) -> Result<Value, String> {
+        let panel = &self.site.panels[self.require_target(request)?];
+        Ok(json!({
+            "action": "GET_PANEL_DATA_RES",
+            "panel_id": panel.panel_number,
+            "panel_name": panel.name,
+            "panel_serial_number": panel.serial_number,
+            "data": self.all_points(panel, t),
+        }))
+    }
+
+    fn panels_list(&self) -> Value {
+        let now = chrono::Utc::now().timestamp();
+        let data: Vec<Value> = self
+            .site
+            .panels
+            .iter()
+            .map(|panel| {
+                json!({
+                    "panel_number": panel.panel_number,
+                    "serial_number": panel.serial_number,
+                    "panel_name": panel.name,
+                    "pid": panel.product_id,
+                    "object_instance": panel.object_instance,
+                    "online_time": now,
+                })
+            })
+            .collect();
+        json!({ "action": "GET_PANELS_LIST_RES", "data": data })
+    }
+
+    fn logging_data(&self, request: &Value, t: f64) -> Value {
+        let now = chrono::Utc::now().timestamp();
+        let target = self.target(request);
+        let data: Vec<Value> = self
+            .site
+            .panels
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| target.is_none_or(|target| target == *i))
+            .map(|(_, panel)| {
+                json!({
+                    "panel_id": panel.panel_number,
+                    "panel_name": panel.name,
+                    "panel_serial_number": panel.serial_number,
+                    "panel_ipaddress": panel.ip,
+                    "input_logging_time": now,
+                    "output_logging_time": now,
+                    "variable_logging_time": now,
+                    "device_data": self.all_points(panel, t),
+                })
+            })
+            .collect();
+        json!({ "action": "LOGGING_DATA_RES", "data": data })
+    }
+
+    fn get_webview_list(&self, request: &Value, t: f64) -> Result<Value, String> {
+        let panel = &self.site.panels[self.require_target(request)?];
+        let entry_type = request.get("entryType").and_then(|v| v.as_i64()).unwrap_or(-1) as i32;
+        let in_range = |index: usize| -> bool {
+            if let Some(single) = request.get("entryIndex").and_then(|v| v.as_u64()) {
+                return index as u64 == single;
+            }
+            let start = request.get("entryIndexStart").and_then(|v| v.as_u64()).unwrap_or(0);
+            let end = request.get("entryIndexEnd").and_then(|v| v.as_u64()).unwrap_or(u64::MAX);
+            (start..=end).contains(&(index as u64))
+        };
+
+        let (items, device_data): (Vec<Value>, Vec<Value>) = if let Some(kind) = PointKind::from_entry_type(entry_type) {
+            panel
+                .points(kind)
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| in_range(*index))
+                .map(|(index, spec)| {
+                    (self.point_item(panel, kind, index, spec, t), self.point_record(panel, kind, index, spec, t))
+                })
+                .unzip()
+        } else {
+            let items = match entry_type {
+                BAC_PRG => panel
+                    .programs
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, _)| in_range(*index))
+                    .map(|(index, program)| {
+                        json!({
+                            "programIndex": index,
+                            "programLabel": program.label,
+                            "fullLabel": program.description,
+                            "programStatus": program.enabled as i32,
+                            "autoManual": self.is_pinned(panel, BAC_PRG, index) as i32,
+                            "programSize": program.size,
+                            "programPointer": 0,
+                        })
+                    })
+                    .collect(),
+                BAC_SCH => panel
+                    .schedules
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, _)| in_range(*index))
+                    .map(|(index, schedule)| {
+                        let output = self
+                            .pinned
+                            .get(&(panel.serial_number, BAC_SCH, index))
+                            .copied()
+                            .unwrap_or_else(|| schedule.signal.at(t));
+                        json!({
+                            "scheduleId": index,
+                            "label": schedule.label,
+                            "fullLabel": schedule.description,
+                            "autoManual": self.is_pinned(panel, BAC_SCH, index) as i32,
+                            "outputField": (output >= 0.5) as i32,
+                            "variableField": 0,
+                        })
+                    })
+                    .collect(),
+                BAC_AMON => panel
+                    .trendlogs
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, _)| in_range(*index))
+                    .map(|(index, trendlog)| {
+                        let inputs: Vec<Value> = trendlog
+                            .inputs
+                            .iter()
+                            .map(|input| {
+                                json!({
+                                    "panel": panel.panel_number,
+                                    "point_type": input.kind.monitor_point_type(),
+                                    "point_number": input.index,
+                                })
+                            })
+                            .collect();
+                        json!({
+                            "trendlogId": index,
+                            "pid": panel.panel_number,
+                            "label": trendlog.label,
+                            "intervalSeconds": trendlog.interval_secs,
+                            "numInputs": trendlog.inputs.len(),
+                            "bufferSize": buffer_size(trendlog, t),
+                            "inputs": inputs,
+                        })
+                    })
+                    .collect(),
+                // Other entry types exist on real panels but the site file has none.
+                _ => Vec::new(),
+            };
+            (items, Vec::new())
+        };
+
+        Ok(json!({
+            "action": "GET_WEBVIEW_LIST_RES",
+            "success": true,
+            "panelId": panel.panel_number,
+            "serialNumber": panel.serial_number,
+            "entryType": entry_type,
+            "items": items,
+            "data": { "device_data": device_data },
+        }))
+    }
+
+    fn update_webview_list(&mut self, request: &Value) -> Result<Value, String> {
+        let panel_idx = self.require_target(request)?;
+        let entry_type = request.get("entryType").and_then(|v| v.as_i64()).ok_or("UPDATE_WEBVIEW_LIST without entryType")? as i32;
+        let index = request.get("entryIndex").and_then(|v| v.as_u64()).ok_or("UPDATE_WEBVIEW_LIST without entryIndex")? as usize;
+        let auto_manual = request.get("auto_manual").and_then(|v| v.as_i64());
+        let text = |key: &str| request.get(key).and_then(|v| v.as_str()).filter(|s| !s.is_empty()).map(str::to_string);
+        let serial = self.site.panels[panel_idx].serial_number;
+        let key = (serial, entry_type, index);
+
+        if let Some(kind) = PointKind::from_entry_type(entry_type) {
+            let value = request.get("value").and_then(|v| v.as_f64());
+            let point = self.site.panels[panel_idx]
+                .points_mut(kind)
+                .get_mut(index)
+                .ok_or_else(|| format!("No simulated {} {} on panel {}", kind.type_name(), index, serial))?;
+            if let Some(label) = text("label") {
+                point.label = label;
+            }
+            if let Some(description) = text("description") {
+                point.description = description;
+            }
+            if let Some(range) = request.get("range").and_then(|v| v.as_i64()) {
+                point.range = range as i32;
+            }
+            match (auto_manual, value) {
+                (Some(1), Some(value)) => {
+                    self.pinned.insert(key, value);
+                }
+                (Some(0), _) => {
+                    self.pinned.remove(&key);
+                    if let (Signal::Constant { value: current }, Some(value)) = (&mut point.signal, value) {
+                        *current = value;
+                    }
+                }
+                _ => {}
+            }
+        } else {
+            match entry_type {
+                BAC_PRG => {
+                    let program = self.site.panels[panel_idx]
+                        .programs
+                        .get_mut(index)
+                        .ok_or_else(|| format!("No simulated program {} on panel {}", index, serial))?;
+                    if let Some(label) = text("program_label") {
+                        program.label = label;
+                    }
+                    if let Some(status) = request.get("program_status").and_then(|v| v.as_i64()) {
+                        program.enabled = status != 0;
+                    }
+                }
+                BAC_SCH => {
+                    if index >= self.site.panels[panel_idx].schedules.len() {
+                        return Err(format!("No simulated schedule {} on panel {}", index, serial));
+                    }
+                    if auto_manual == Some(1) {
+                        let output = request.get("output").and_then(|v| v.as_f64()).unwrap_or(0.0);
+                        self.pinned.insert(key, output);
+                    } else if auto_manual == Some(0) {
+                        self.pinned.remove(&key);
+                    }
+                }
+                // Accepted and ignored, like a panel without that table.
+                _ => {}
+            }
+        }
+
+        Ok(json!({ "success": true, "message": "Updated simulated entry" }))
+    }
+}
+
+/// Bytes a monitor has logged by `t`: one 4-byte sample per input per interval.
+fn buffer_size(trendlog: &TrendlogSpec, t: f64) -> i64 {
+    let samples = (t / trendlog.interval_secs.max(1) as f64).floor() as i64;
+    samples * trendlog.inputs.len() as i64 * 4
+}
+
+fn interval_text(secs: i32) -> String {
+    let (h, m, s) = (secs / 3600, (secs % 3600) / 60, secs % 60);
+    [(h, "h"), (m, "m"), (s, "s")]
+        .iter()
+        .filter(|(n, _)| *n > 0)
+        .map(|(n, unit)| format!("{}{}", n, unit))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn monitor_data(panel: &PanelSpec, num: usize, trendlog: &TrendlogSpec, t: f64) -> TrendlogMonitorData {
+    let data_size_kb = buffer_size(trendlog, t) as f32 / 1024.0;
+    TrendlogMonitorData {
+        num: num as i32,
+        id: format!("MON{}", num + 1),
+        label: trendlog.label.clone(),
+        interval_seconds: trendlog.interval_secs,
+        interval_text: interval_text(trendlog.interval_secs),
+        status: "ON".to_string(),
+        status_code: 1,
+        data_size_kb,
+        data_size_text: format!("{:.2}", data_size_kb),
+        num_inputs: trendlog.inputs.len() as i32,
+        an_inputs: trendlog
+            .inputs
+            .iter()
+            .filter(|input| !panel.points(input.kind)[input.index].digital)
+            .count() as i32,
+    }
+}
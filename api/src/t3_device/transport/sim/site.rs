@@ -0,0 +1,326 @@
+//! Simulator site file — the panels, points and trendlogs a simulated site
+//! exposes, loaded from YAML or JSON.
+
+use std::collections::HashSet;
+use std::f64::consts::PI;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// How a point's value evolves with simulated time (seconds since start).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "model", rename_all = "snake_case")]
+pub enum Signal {
+    Constant {
+        value: f64,
+    },
+    Sine {
+        base: f64,
+        amplitude: f64,
+        period_secs: f64,
+        #[serde(default)]
+        phase_secs: f64,
+    },
+    /// Sawtooth from `min` to `max` over `period_secs`.
+    Ramp {
+        min: f64,
+        max: f64,
+        period_secs: f64,
+    },
+    /// `high` for the first `duty` fraction of each period, then `low`.
+    Square {
+        low: f64,
+        high: f64,
+        period_secs: f64,
+        #[serde(default = "default_duty")]
+        duty: f64,
+    },
+}
+
+fn default_duty() -> f64 {
+    0.5
+}
+
+impl Default for Signal {
+    fn default() -> Self {
+        Signal::Constant { value: 0.0 }
+    }
+}
+
+impl Signal {
+    /// Value at `t` seconds. Pure, so the same site and clock always read the same.
+    pub fn at(&self, t: f64) -> f64 {
+        match *self {
+            Signal::Constant { value } => value,
+            Signal::Sine { base, amplitude, period_secs, phase_secs } => {
+                base + amplitude * (2.0 * PI * (t + phase_secs) / period_secs.max(f64::EPSILON)).sin()
+            }
+            Signal::Ramp { min, max, period_secs } => {
+                let period = period_secs.max(f64::EPSILON);
+                min + (max - min) * (t.rem_euclid(period) / period)
+            }
+            Signal::Square { low, high, period_secs, duty } => {
+                let period = period_secs.max(f64::EPSILON);
+                if t.rem_euclid(period) < period * duty { high } else { low }
+            }
+        }
+    }
+}
+
+/// An input, output or variable.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PointSpec {
+    pub label: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub unit: String,
+    /// T3000 range code.
+    #[serde(default)]
+    pub range: i32,
+    /// Binary point (reported with digital_analog = 0 and a 0/1 value).
+    #[serde(default)]
+    pub digital: bool,
+    #[serde(default)]
+    pub signal: Signal,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProgramSpec {
+    pub label: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub size: i32,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// A weekly schedule; its output is on whenever `signal` reads >= 0.5.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScheduleSpec {
+    pub label: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub signal: Signal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PointKind {
+    Input,
+    Output,
+    Variable,
+}
+
+impl PointKind {
+    /// `type` string in LOGGING_DATA / GET_WEBVIEW_LIST point records.
+    pub fn type_name(self) -> &'static str {
+        match self {
+            PointKind::Input => "INPUT",
+            PointKind::Output => "OUTPUT",
+            PointKind::Variable => "VARIABLE",
+        }
+    }
+
+    /// Monitor input `point_type` (BACnet object type + 1: 1=OUT, 2=IN, 3=VAR).
+    pub fn monitor_point_type(self) -> i32 {
+        match self {
+            PointKind::Output => 1,
+            PointKind::Input => 2,
+            PointKind::Variable => 3,
+        }
+    }
+
+    /// GET_WEBVIEW_LIST / UPDATE_WEBVIEW_LIST entry type.
+    pub fn entry_type(self) -> i32 {
+        match self {
+            PointKind::Output => 0,
+            PointKind::Input => 1,
+            PointKind::Variable => 2,
+        }
+    }
+
+    pub fn from_entry_type(entry_type: i32) -> Option<Self> {
+        match entry_type {
+            0 => Some(PointKind::Output),
+            1 => Some(PointKind::Input),
+            2 => Some(PointKind::Variable),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PointRef {
+    pub kind: PointKind,
+    pub index: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TrendlogSpec {
+    pub label: String,
+    #[serde(default = "default_interval")]
+    pub interval_secs: i32,
+    #[serde(default)]
+    pub inputs: Vec<PointRef>,
+}
+
+fn default_interval() -> i32 {
+    900
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PanelSpec {
+    pub serial_number: u32,
+    pub panel_number: u8,
+    pub name: String,
+    #[serde(default = "default_product_id")]
+    pub product_id: u8,
+    #[serde(default = "default_ip")]
+    pub ip: String,
+    #[serde(default)]
+    pub object_instance: u32,
+    #[serde(default = "default_modbus_id")]
+    pub modbus_id: u8,
+    #[serde(default = "default_modbus_port")]
+    pub modbus_port: u16,
+    #[serde(default = "default_bacnet_port")]
+    pub bacnet_port: u16,
+    /// Raw firmware version as the device reports it.
+    #[serde(default)]
+    pub firmware: u16,
+    #[serde(default)]
+    pub hardware: u16,
+    #[serde(default)]
+    pub inputs: Vec<PointSpec>,
+    #[serde(default)]
+    pub outputs: Vec<PointSpec>,
+    #[serde(default)]
+    pub variables: Vec<PointSpec>,
+    #[serde(default)]
+    pub programs: Vec<ProgramSpec>,
+    #[serde(default)]
+    pub schedules: Vec<ScheduleSpec>,
+    #[serde(default)]
+    pub trendlogs: Vec<TrendlogSpec>,
+}
+
+fn default_product_id() -> u8 {
+    88 // ESP32 T3 series
+}
+
+fn default_ip() -> String {
+    "127.0.0.1".to_string()
+}
+
+fn default_modbus_id() -> u8 {
+    1
+}
+
+fn default_modbus_port() -> u16 {
+    502
+}
+
+fn default_bacnet_port() -> u16 {
+    47808
+}
+
+impl PanelSpec {
+    pub fn points(&self, kind: PointKind) -> &[PointSpec] {
+        match kind {
+            PointKind::Input => &self.inputs,
+            PointKind::Output => &self.outputs,
+            PointKind::Variable => &self.variables,
+        }
+    }
+
+    pub fn points_mut(&mut self, kind: PointKind) -> &mut Vec<PointSpec> {
+        match kind {
+            PointKind::Input => &mut self.inputs,
+            PointKind::Output => &mut self.outputs,
+            PointKind::Variable => &mut self.variables,
+        }
+    }
+}
+
+/// A simulated site.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Site {
+    #[serde(default)]
+    pub name: String,
+    pub panels: Vec<PanelSpec>,
+}
+
+const DEMO_SITE: &str = include_str!("demo_site.yaml");
+
+impl Site {
+    /// Load a site file; `.json` is read as JSON, anything else as YAML.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read simulator site {}: {}", path.display(), e))?;
+        let is_json = path
+            .extension()
+            .map(|ext| ext.eq_ignore_ascii_case("json"))
+            .unwrap_or(false);
+        if is_json {
+            Self::from_json_str(&text)
+        } else {
+            Self::from_yaml_str(&text)
+        }
+        .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn from_yaml_str(text: &str) -> Result<Self, String> {
+        let site: Site = serde_yaml::from_str(text).map_err(|e| format!("Invalid simulator site YAML: {}", e))?;
+        site.validate()?;
+        Ok(site)
+    }
+
+    pub fn from_json_str(text: &str) -> Result<Self, String> {
+        let site: Site = serde_json::from_str(text).map_err(|e| format!("Invalid simulator site JSON: {}", e))?;
+        site.validate()?;
+        Ok(site)
+    }
+
+    /// The built-in single-panel site.
+    pub fn demo() -> Self {
+        Self::from_yaml_str(DEMO_SITE).expect("built-in demo site is valid")
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let mut serials = HashSet::new();
+        let mut numbers = HashSet::new();
+        for panel in &self.panels {
+            if panel.serial_number == 0 {
+                return Err(format!("Panel '{}' has serial_number 0", panel.name));
+            }
+            if !serials.insert(panel.serial_number) {
+                return Err(format!("Duplicate panel serial_number {}", panel.serial_number));
+            }
+            if !numbers.insert(panel.panel_number) {
+                return Err(format!("Duplicate panel_number {}", panel.panel_number));
+            }
+            for trendlog in &panel.trendlogs {
+                for input in &trendlog.inputs {
+                    if input.index >= panel.points(input.kind).len() {
+                        return Err(format!(
+                            "Trendlog '{}' on panel {} references missing {} {}",
+                            trendlog.label,
+                            panel.serial_number,
+                            input.kind.type_name(),
+                            input.index
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
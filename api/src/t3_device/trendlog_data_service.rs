@@ -7,6 +7,10 @@ use chrono::{Duration, Utc};
 use crate::entity::t3_device::{trendlog_data, trendlog_data_detail};
 use crate::t3_device::trendlog_parent_cache::{TrendlogParentCache, ParentKey};
 use crate::error::AppError;
+use crate::server_db::trendlog_aggregation::{self, AggregationRequest, AGGREGATION_ROW_LIMIT};
+use crate::server_db::trendlog_rollup;
+use crate::server_db::trendlog_detail::{self, DetailLayout};
+use futures_util::TryStreamExt;
 use std::sync::Arc;
 
 
@@ -116,6 +120,9 @@ pub struct TrendlogHistoryRequest {
     pub limit: Option<u64>,          // Optional limit for pagination
     pub point_types: Option<Vec<String>>, // Optional point types filter ["INPUT", "OUTPUT", "VARIABLE"]
     pub specific_points: Option<Vec<SpecificPoint>>, // NEW: Specific points to filter
+    /// Bucket or LTTB-downsample the rows server-side instead of returning every one
+    #[serde(default)]
+    pub aggregation: Option<AggregationRequest>,
 }
 
 pub struct T3TrendlogDataService;
@@ -285,11 +292,18 @@ impl T3TrendlogDataService {
 
         // PERFORMANCE OPTIMIZATION: Apply time range filter if provided
         // If no time filter provided, default to last 24 hours to prevent scanning all historical data
+        // Parseable bounds go through the numeric columns when this database has them
         let mut applied_time_filter = false;
+        let mut range = (None, None);
 
         if let Some(start_time) = &request.start_time {
-            sql.push_str(" AND d.LoggingTime_Fmt >= ?");
-            params.push(start_time.clone().into());
+            match trendlog_detail::parse_fmt(start_time) {
+                Some(t) => range.0 = Some(t),
+                None => {
+                    sql.push_str(" AND d.LoggingTime_Fmt >= ?");
+                    params.push(start_time.clone().into());
+                }
+            }
             applied_time_filter = true;
 
             let time_filter_info = format!(
@@ -300,8 +314,13 @@ impl T3TrendlogDataService {
         }
 
         if let Some(end_time) = &request.end_time {
-            sql.push_str(" AND d.LoggingTime_Fmt <= ?");
-            params.push(end_time.clone().into());
+            match trendlog_detail::parse_fmt(end_time) {
+                Some(t) => range.1 = Some(t),
+                None => {
+                    sql.push_str(" AND d.LoggingTime_Fmt <= ?");
+                    params.push(end_time.clone().into());
+                }
+            }
             applied_time_filter = true;
 
             let time_filter_info = format!(
@@ -315,8 +334,7 @@ impl T3TrendlogDataService {
         if !applied_time_filter {
             let default_start = chrono::Local::now() - chrono::Duration::hours(24);
             let default_start_str = default_start.format("%Y-%m-%d %H:%M:%S").to_string();
-            sql.push_str(" AND d.LoggingTime_Fmt >= ?");
-            params.push(default_start_str.clone().into());
+            range.0 = Some(default_start.naive_local());
 
             let safety_info = format!(
                 "🛡️ [TrendlogDataService] No time filter provided - applying 24-hour safety limit from: {}",
@@ -325,45 +343,88 @@ impl T3TrendlogDataService {
             emit_api_log(db, "warn", &safety_info).await;
         }
 
+        if range.0.is_some() || range.1.is_some() {
+            let layout = DetailLayout::detect(db, "main").await.unwrap_or(DetailLayout::Legacy);
+            sql.push_str(&format!(" AND {}", layout.time_range_sql("d", range.0, range.1)));
+        }
+
         // Order by logging time (newest first)
         sql.push_str(" ORDER BY d.LoggingTime_Fmt DESC");
 
         // PERFORMANCE: Apply default limit if not provided to prevent huge result sets
-        let default_limit = 50000; // Safety limit to prevent OOM on huge datasets
+        // (aggregated queries read the whole range; only the buckets go out)
+        let default_limit = if request.aggregation.is_some() { AGGREGATION_ROW_LIMIT } else { 50000 }; // Safety limit to prevent OOM on huge datasets
+        let row_cap = request.limit.unwrap_or(default_limit);
         if let Some(limit) = request.limit {
-            sql.push_str(&format!(" LIMIT {}", limit));
             let limit_info = format!(
                 "📊 [TrendlogDataService] Applied result limit: {}",
                 limit
             );
             emit_api_log(db, "info", &limit_info).await;
         } else {
-            sql.push_str(&format!(" LIMIT {}", default_limit));
             let safety_info = format!(
                 "🛡️ [TrendlogDataService] No limit provided - applying safety limit: {}",
                 default_limit
             );
             emit_api_log(db, "warn", &safety_info).await;
         }
+        // One row past the cap tells an aggregated query it was cut off
+        let sql_limit = if request.aggregation.is_some() { row_cap.saturating_add(1) } else { row_cap };
+        sql.push_str(&format!(" LIMIT {}", sql_limit));
 
         // Log query execution start
         emit_api_log(db, "info", "🔄 [TrendlogDataService] Executing JOIN query...").await;
 
         let query_start_time = std::time::Instant::now();
 
-        let trendlog_data_list = JoinedTrendlogData::find_by_statement(
-            Statement::from_sql_and_values(db.get_database_backend(), &adapt_placeholders(db.get_database_backend(), &sql), params)
-        )
-        .all(db)
-        .await?;
+        let statement = Statement::from_sql_and_values(db.get_database_backend(), adapt_placeholders(db.get_database_backend(), &sql), params);
+        let mut aggregated = None;
+        let trendlog_data_list = if let Some(aggregation) = &request.aggregation {
+            // Stream rows straight into the aggregator; raw rows are never held or formatted
+            let mut aggregator = trendlog_aggregation::HistoryAggregator::default();
+            let mut read = 0u64;
+            let mut stream = JoinedTrendlogData::find_by_statement(statement).stream(db).await?;
+            while let Some(data) = stream.try_next().await? {
+                read += 1;
+                if read > row_cap {
+                    break;
+                }
+                aggregator.push(trendlog_aggregation::HistoryRow {
+                    time: &data.logging_time_fmt,
+                    value: Self::scale_value_from_db(&data.value).0,
+                    point_id: &data.point_id,
+                    point_type: &data.point_type,
+                    point_index: data.point_index,
+                    units: data.units.as_deref(),
+                    range: data.range_field.as_deref(),
+                    is_analog: data.digital_analog.as_ref().map(|da| da == "1").unwrap_or(true),
+                });
+            }
+            drop(stream);
+            let range = trendlog_aggregation::query_range(request.start_time.as_deref(), request.end_time.as_deref());
+            let (data, mut summary) = aggregator.finish(aggregation, range);
+            summary.truncated = read > row_cap;
+            if summary.truncated {
+                let truncated_info = format!(
+                    "⚠️ [TrendlogDataService] Aggregation hit the {} row cap; older rows in the range were not aggregated",
+                    row_cap
+                );
+                emit_api_log(db, "warn", &truncated_info).await;
+            }
+            aggregated = Some((data, summary));
+            Vec::new()
+        } else {
+            JoinedTrendlogData::find_by_statement(statement).all(db).await?
+        };
 
         let query_duration = query_start_time.elapsed();
+        let retrieved = aggregated.as_ref().map_or(trendlog_data_list.len(), |(_, summary)| summary.raw_points);
 
         // Log query completion with performance metrics
         let query_result_info = format!(
             "📈 [TrendlogDataService] JOIN query completed in {:.3}ms - Retrieved {} records",
             query_duration.as_millis(),
-            trendlog_data_list.len()
+            retrieved
         );
         emit_api_log(db, "info", &query_result_info).await;
 
@@ -389,24 +450,30 @@ impl T3TrendlogDataService {
         // Format the data for the TrendLogChart component
         let format_start_time = std::time::Instant::now();
 
-        let formatted_data: Vec<serde_json::Value> = trendlog_data_list.iter().map(|data| {
-            // Always divide by 1000 when reading from database
-            let (scaled_value, original_value) = Self::scale_value_from_db(&data.value);
-
-            serde_json::json!({
-                "time": data.logging_time_fmt,
-                "timestamp": data.logging_time_fmt,
-                "value": scaled_value,
-                "point_id": data.point_id,
-                "point_type": data.point_type,
-                "point_index": data.point_index,
-                "units": data.units,
-                "range": data.range_field,
-                "raw_value": data.value,
-                "original_value": original_value,
-                "is_analog": data.digital_analog.as_ref().map(|da| da == "1").unwrap_or(true)
-            })
-        }).collect();
+        let mut aggregation_summary = None;
+        let formatted_data: Vec<serde_json::Value> = if let Some((data, summary)) = aggregated {
+            aggregation_summary = Some(summary);
+            data
+        } else {
+            trendlog_data_list.iter().map(|data| {
+                // Always divide by 1000 when reading from database
+                let (scaled_value, original_value) = Self::scale_value_from_db(&data.value);
+
+                serde_json::json!({
+                    "time": data.logging_time_fmt,
+                    "timestamp": data.logging_time_fmt,
+                    "value": scaled_value,
+                    "point_id": data.point_id,
+                    "point_type": data.point_type,
+                    "point_index": data.point_index,
+                    "units": data.units,
+                    "range": data.range_field,
+                    "raw_value": data.value,
+                    "original_value": original_value,
+                    "is_analog": data.digital_analog.as_ref().map(|da| da == "1").unwrap_or(true)
+                })
+            }).collect()
+        };
         let format_duration = format_start_time.elapsed();
 
 
@@ -461,7 +528,7 @@ impl T3TrendlogDataService {
             }
         }
 
-        Ok(serde_json::json!({
+        let mut response = serde_json::json!({
             "device_id": request.serial_number,
             "panel_id": request.panel_id,
             "trendlog_id": request.trendlog_id,
@@ -476,7 +543,20 @@ impl T3TrendlogDataService {
                 "start_time": request.start_time,
                 "end_time": request.end_time
             }
-        }))
+        });
+        if let Some(summary) = aggregation_summary {
+            emit_api_log(
+                db,
+                "info",
+                &format!(
+                    "📉 [TrendlogDataService] Aggregated {} raw records into {} points ({:?}, bucket={:?}s)",
+                    summary.raw_points, summary.points, summary.mode, summary.bucket_seconds
+                ),
+            )
+            .await;
+            response["aggregation"] = serde_json::json!(summary);
+        }
+        Ok(response)
     }
 
     /// Save realtime data to database (from socket port 9104)
@@ -517,16 +597,9 @@ impl T3TrendlogDataService {
             data_point.units.clone(),
         ).await?;
 
-        // Step 2: Insert detail record only
-        let detail_record = trendlog_data_detail::ActiveModel {
-            parent_id: Set(parent_id),
-            value: Set(data_point.value.clone()),
-            logging_time_fmt: Set(logging_time_fmt.clone()),
-            ..Default::default()
-        };
-
-        match detail_record.insert(db).await {
-            Ok(_saved_detail) => {
+        // Step 2: Insert the detail record (folded into the hourly/daily rollups too)
+        match trendlog_detail::insert_detail(db, parent_id, &data_point.value, &logging_time_fmt).await {
+            Ok(()) => {
                 // Log successful save
                 let success_info = format!(
                     "✅ [TrendlogDataService] Realtime data saved - Parent ID: {}, Point: {}, Time: {}",
@@ -609,16 +682,16 @@ impl T3TrendlogDataService {
                     return None; // Skip this point
                 }
 
-                Some(trendlog_data_detail::ActiveModel {
-                    parent_id: Set(parent_id),
-                    value: Set(dp.value.clone()),
-                    logging_time_fmt: Set(logging_time_fmt.clone()),
-                    ..Default::default()
-                })
+                Some(trendlog_detail::detail_model(parent_id, &dp.value, &logging_time_fmt))
             })
             .collect();
 
         let count = detail_records.len() as u64;
+        let rollup_rows: Vec<(i32, &str)> = data_points.iter()
+            .zip(parent_ids.iter())
+            .filter(|(dp, _)| !dp.value.is_empty())
+            .map(|(dp, &parent_id)| (parent_id, dp.value.as_str()))
+            .collect();
 
         // Step 3: Batch insert with retry logic for database locks
         // Fast-fail strategy: 3 attempts with short delays (100ms, 200ms)
@@ -629,8 +702,17 @@ impl T3TrendlogDataService {
         let mut last_error = None;
 
         while retry_count < max_retries {
-            match trendlog_data_detail::Entity::insert_many(detail_records.clone()).exec(db).await {
-                Ok(_result) => {
+            // The raw rows and their hourly/daily rollups are written in one
+            // transaction, the rollups as one upsert per resolution
+            let result: Result<(), DbErr> = async {
+                let txn = db.begin().await?;
+                trendlog_data_detail::Entity::insert_many(detail_records.clone()).exec(&txn).await?;
+                trendlog_rollup::record_details(&txn, &rollup_rows, &logging_time_fmt).await;
+                txn.commit().await
+            }
+            .await;
+            match result {
+                Ok(()) => {
                     let batch_duration = batch_start_time.elapsed();
 
                     // Log retry info if applicable
@@ -771,7 +853,7 @@ impl T3TrendlogDataService {
             sql.push_str(&format!(" LIMIT {}", limit_val));
         }
 
-        let stmt = Statement::from_sql_and_values(db.get_database_backend(), &adapt_placeholders(db.get_database_backend(), &sql), params);
+        let stmt = Statement::from_sql_and_values(db.get_database_backend(), adapt_placeholders(db.get_database_backend(), &sql), params);
 
         match TrendlogDataPoint::find_by_statement(stmt).all(db).await {
             Ok(recent_data) => {
@@ -820,7 +902,7 @@ impl T3TrendlogDataService {
 
         let result = db.execute(Statement::from_sql_and_values(
             db.get_database_backend(),
-            &adapt_placeholders(db.get_database_backend(), sql),
+            adapt_placeholders(db.get_database_backend(), sql),
             vec![serial_number.into(), cutoff_timestamp.into()],
         )).await?;
 
@@ -874,7 +956,7 @@ impl T3TrendlogDataService {
 
         let counts = CountResult::find_by_statement(Statement::from_sql_and_values(
             db.get_database_backend(),
-            &adapt_placeholders(db.get_database_backend(), count_sql),
+            adapt_placeholders(db.get_database_backend(), count_sql),
             vec![serial_number.into(), panel_id.into()],
         ))
         .one(db)
@@ -905,7 +987,7 @@ impl T3TrendlogDataService {
 
         let tracked_per_type = TrackedPerTypeResult::find_by_statement(Statement::from_sql_and_values(
             db.get_database_backend(),
-            &adapt_placeholders(db.get_database_backend(), tracked_per_type_sql),
+            adapt_placeholders(db.get_database_backend(), tracked_per_type_sql),
             vec![serial_number.into(), panel_id.into()],
         ))
         .one(db)
@@ -929,7 +1011,7 @@ impl T3TrendlogDataService {
 
         let latest_timestamp = LatestResult::find_by_statement(Statement::from_sql_and_values(
             db.get_database_backend(),
-            &adapt_placeholders(db.get_database_backend(), latest_sql),
+            adapt_placeholders(db.get_database_backend(), latest_sql),
             vec![serial_number.into(), panel_id.into()],
         ))
         .one(db)
@@ -955,7 +1037,7 @@ impl T3TrendlogDataService {
 
         let latest_sync_meta = LatestSyncMetaResult::find_by_statement(Statement::from_sql_and_values(
             db.get_database_backend(),
-            &adapt_placeholders(db.get_database_backend(), latest_sync_meta_sql),
+            adapt_placeholders(db.get_database_backend(), latest_sync_meta_sql),
             vec![serial_number.into(), panel_id.into()],
         ))
         .one(db)
@@ -1032,7 +1114,7 @@ impl T3TrendlogDataService {
         let rows: Vec<TrendlogUsageRow> = TrendlogUsageRow::find_by_statement(
             Statement::from_sql_and_values(
                 db.get_database_backend(),
-                &adapt_placeholders(db.get_database_backend(), per_trendlog_sql),
+                adapt_placeholders(db.get_database_backend(), per_trendlog_sql),
                 vec![serial_number.into(), panel_id.into()],
             )
         )
@@ -1136,7 +1218,7 @@ impl T3TrendlogDataService {
             sql.push_str(" LIMIT 10000");
         }
 
-        let stmt = Statement::from_sql_and_values(db.get_database_backend(), &adapt_placeholders(db.get_database_backend(), &sql), params);
+        let stmt = Statement::from_sql_and_values(db.get_database_backend(), adapt_placeholders(db.get_database_backend(), &sql), params);
         let raw_data = TrendlogDataPoint::find_by_statement(stmt).all(db).await?;
 
         let has_historical_data = !raw_data.is_empty();
@@ -1222,3 +1304,29 @@ impl T3TrendlogDataService {
         consolidated
     }
 }
+
+/// Replace the raw `data` rows of a history response with aggregated ones
+/// and describe the aggregation under `aggregation`. Used by the MSSQL
+/// history path, which returns the same row shape; `row_cap` is the limit
+/// the rows were queried with, so reaching it marks the result truncated.
+pub fn apply_aggregation(
+    response: &mut serde_json::Value,
+    aggregation: &AggregationRequest,
+    start_time: Option<&str>,
+    end_time: Option<&str>,
+    row_cap: Option<u64>,
+) {
+    let rows = response["data"].take();
+    let rows = rows.as_array().map(Vec::as_slice).unwrap_or_default();
+    let truncated = row_cap.is_some_and(|cap| rows.len() as u64 >= cap);
+    let (data, mut summary) = trendlog_aggregation::aggregate_history_rows(
+        rows.iter().filter_map(trendlog_aggregation::HistoryRow::from_json),
+        aggregation,
+        trendlog_aggregation::query_range(start_time, end_time),
+    );
+    summary.truncated = truncated;
+    response["count"] = serde_json::json!(data.len());
+    response["total_records"] = serde_json::json!(data.len());
+    response["data"] = serde_json::json!(data);
+    response["aggregation"] = serde_json::json!(summary);
+}
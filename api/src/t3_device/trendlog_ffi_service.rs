@@ -4,7 +4,6 @@
 // - Related input/output/variable points (from Point_Net inputs array)
 // - Real-time status and data management
 
-use std::os::raw::{c_char, c_int};
 use sea_orm::*;
 use sea_orm::prelude::Expr;
 use sea_orm::ActiveValue::NotSet;
@@ -15,6 +14,7 @@ use chrono::Utc;
 use crate::entity::t3_device::{trendlogs, trendlog_inputs, trendlog_views};
 use crate::error::AppError;
 use crate::logging::types::LogLevel;
+use crate::t3_device::transport;
 
 fn emit_ffi_log_sync(category: &str, message: &str, level: LogLevel) {
     let handle = match tokio::runtime::Handle::try_current() {
@@ -44,106 +44,19 @@ fn emit_ffi_log_sync(category: &str, message: &str, level: LogLevel) {
     });
 }
 
-// Link to T3000.exe functions - requires T3000.exe to be running
-#[link(name = "kernel32")]
-extern "system" {
-    // Windows API for dynamic library loading
-    #[allow(non_snake_case)]
-    #[allow(dead_code)]
-    fn LoadLibraryA(name: *const u8) -> *mut std::ffi::c_void;
-    fn GetProcAddress(handle: *mut std::ffi::c_void, name: *const u8) -> *mut std::ffi::c_void;
-    fn GetModuleHandleA(name: *const u8) -> *mut std::ffi::c_void;
-}
-
-// T3000 FFI function types
-type PostRefreshMessageFn = unsafe extern "C" fn(c_int, c_int, c_int, c_int) -> c_int;
-type BacnetWebViewGetTrendlogEntryFn = unsafe extern "C" fn(c_int, c_int, *mut c_char, c_int) -> c_int;
-type BacnetWebViewGetTrendlogListFn = unsafe extern "C" fn(c_int, *mut c_char, c_int) -> c_int;
-
-/// Check if T3000.exe is running and accessible for FFI operations
-fn check_t3000_availability() -> Result<(), String> {
-    unsafe {
-        let handle = GetModuleHandleA(b"T3000.exe\0".as_ptr());
-        if handle.is_null() {
-            return Err("T3000.exe is not running or not accessible. Please ensure T3000 Building Automation software is started and connected to your device.".to_string());
-        }
-
-        // Check that the exported trendlog bridge is available. We probe the
-        // symbol that is actually present in T3000.exe's export table
-        // (T3000_IsDeviceOnline / T3000_ConnectToDevice are NOT exported).
-        let entry_func = GetProcAddress(handle, b"BacnetWebView_GetTrendlogEntry\0".as_ptr());
-
-        if entry_func.is_null() {
-            return Err("T3000.exe is running but the BacnetWebView_GetTrendlogEntry export is not available. Please update T3000 software or check compatibility.".to_string());
-        }
-
-        let _ = emit_ffi_log_sync("T3_FFI", "[OK] T3000.exe is available and ready for FFI operations", LogLevel::Info);
-        Ok(())
-    }
-}
-
-// T3000 FFI function wrappers with dynamic loading
-#[allow(dead_code)]
-#[allow(non_snake_case)]
-unsafe fn Post_Refresh_Message(device_id: c_int, point_type: c_int, start_instance: c_int, end_instance: c_int) -> c_int {
-    let handle = GetModuleHandleA(b"T3000.exe\0".as_ptr());
-    if handle.is_null() {
-        println!("T3000.exe not found - returning error");
-        return 0;
-    }
-
-    let func_ptr = GetProcAddress(handle, b"Post_Refresh_Message\0".as_ptr());
-    if func_ptr.is_null() {
-        println!("Post_Refresh_Message function not found");
-        return 0;
-    }
-
-    let func: PostRefreshMessageFn = std::mem::transmute(func_ptr);
-    func(device_id, point_type, start_instance, end_instance)
-}
-
-/// Fetch a single TrendLog entry from T3000.exe as JSON via the exported
-/// `BacnetWebView_GetTrendlogEntry` bridge. Matches the C++ signature:
-///   int BacnetWebView_GetTrendlogEntry(int panel_id, int monitor_index, char* buffer, int buffer_size)
-/// Returns the JSON byte length (>0) on success, or <=0 on failure.
-#[allow(non_snake_case)]
-unsafe fn BacnetWebView_GetTrendlogEntry(panel_id: c_int, monitor_index: c_int, buffer: *mut c_char, buffer_size: c_int) -> c_int {
-    let handle = GetModuleHandleA(b"T3000.exe\0".as_ptr());
-    if handle.is_null() {
-        println!("T3000.exe not found - returning error");
-        return -1;
+/// Check that the installed device transport can reach T3000 (on Windows:
+/// T3000.exe is loaded and exports the trendlog bridge).
+async fn check_t3000_availability() -> Result<(), String> {
+    let transport = transport::current();
+    if !transport.is_available().await {
+        return Err(format!(
+            "Device transport '{}' is not available. Please ensure T3000 Building Automation software is started and connected to your device.",
+            transport.name()
+        ));
     }
 
-    let func_ptr = GetProcAddress(handle, b"BacnetWebView_GetTrendlogEntry\0".as_ptr());
-    if func_ptr.is_null() {
-        println!("BacnetWebView_GetTrendlogEntry function not found");
-        return -1;
-    }
-
-    let func: BacnetWebViewGetTrendlogEntryFn = std::mem::transmute(func_ptr);
-    func(panel_id, monitor_index, buffer, buffer_size)
-}
-
-/// Fetch the full TrendLog list from T3000.exe as JSON via the exported
-/// `BacnetWebView_GetTrendlogList` bridge. Matches the C++ signature:
-///   int BacnetWebView_GetTrendlogList(int panel_id, char* result_buffer, int buffer_size)
-/// Returns the JSON byte length (>0) on success, or <=0 on failure.
-#[allow(non_snake_case)]
-unsafe fn BacnetWebView_GetTrendlogList(panel_id: c_int, buffer: *mut c_char, buffer_size: c_int) -> c_int {
-    let handle = GetModuleHandleA(b"T3000.exe\0".as_ptr());
-    if handle.is_null() {
-        println!("T3000.exe not found - returning error");
-        return -1;
-    }
-
-    let func_ptr = GetProcAddress(handle, b"BacnetWebView_GetTrendlogList\0".as_ptr());
-    if func_ptr.is_null() {
-        println!("BacnetWebView_GetTrendlogList function not found");
-        return -1;
-    }
-
-    let func: BacnetWebViewGetTrendlogListFn = std::mem::transmute(func_ptr);
-    func(panel_id, buffer, buffer_size)
+    emit_ffi_log_sync("T3_FFI", &format!("[OK] Device transport '{}' is available and ready for FFI operations", transport.name()), LogLevel::Info);
+    Ok(())
 }
 
 // Processed TrendLog information for database storage
@@ -856,7 +769,7 @@ impl TrendLogFFIService {
         let _ = emit_ffi_log_sync("T3_Webview_TRL_FFI", &format!("Device ID: {}, TrendLog ID: {}", device_id, trendlog_id), LogLevel::Info);
 
         // 0. Check T3000.exe availability first - use fallback if not available
-        if let Err(availability_error) = check_t3000_availability() {
+        if let Err(availability_error) = check_t3000_availability().await {
             let _ = emit_ffi_log_sync("T3_Webview_TRL_FFI", &format!("[WARN] T3000 FFI not available: {}", availability_error), LogLevel::Warn);
             let _ = emit_ffi_log_sync("T3_Webview_TRL_FFI", "[INFO] Falling back to database-based TrendLog info (limited functionality)", LogLevel::Info);
 
@@ -883,26 +796,17 @@ impl TrendLogFFIService {
         //    a 7-arg Bacnet network send — not a StrMonitorPoint filler.)
         let panel_id: i32 = 1; // Default panel ID (matches legacy FFI behavior)
         let _ = emit_ffi_log_sync("T3_Webview_TRL_FFI", &format!("[CALL] Calling BacnetWebView_GetTrendlogEntry(panel={}, monitor={})", panel_id, monitor_index), LogLevel::Info);
-        const ENTRY_BUFFER_SIZE: usize = 65536; // 64KB is plenty for a single monitor entry
-        let mut entry_buffer: Vec<u8> = vec![0; ENTRY_BUFFER_SIZE];
-        let ffi_result = unsafe {
-            BacnetWebView_GetTrendlogEntry(
-                panel_id as c_int,
-                monitor_index,
-                entry_buffer.as_mut_ptr() as *mut c_char,
-                ENTRY_BUFFER_SIZE as c_int,
-            )
-        };
-
-        if ffi_result <= 0 {
-            let _ = emit_ffi_log_sync("T3_Webview_TRL_FFI", &format!("[ERROR] BacnetWebView_GetTrendlogEntry failed for device {} monitor {} (result {})", device_id, monitor_index, ffi_result), LogLevel::Error);
-            return Err(AppError::FfiError("Failed to retrieve TrendLog data from T3000".to_string()));
-        }
-        let _ = emit_ffi_log_sync("T3_Webview_TRL_FFI", &format!("[OK] BacnetWebView_GetTrendlogEntry returned {} bytes", ffi_result), LogLevel::Info);
+        let entry_json = transport::current()
+            .trendlog_entry(panel_id, monitor_index)
+            .await
+            .map_err(|e| {
+                emit_ffi_log_sync("T3_Webview_TRL_FFI", &format!("[ERROR] BacnetWebView_GetTrendlogEntry failed for device {} monitor {} ({})", device_id, monitor_index, e), LogLevel::Error);
+                AppError::FfiError("Failed to retrieve TrendLog data from T3000".to_string())
+            })?;
+        emit_ffi_log_sync("T3_Webview_TRL_FFI", &format!("[OK] BacnetWebView_GetTrendlogEntry returned {} bytes", entry_json.len()), LogLevel::Info);
 
         // 4. Parse the JSON response into TrendLogInfo
         let _ = emit_ffi_log_sync("T3_Webview_TRL_FFI", "[PROCESS] Parsing monitor entry JSON into TrendLogInfo...", LogLevel::Info);
-        let entry_json = String::from_utf8_lossy(&entry_buffer[..ffi_result as usize]).to_string();
         let trendlog_info = Self::parse_trendlog_entry_json(device_id as i32, trendlog_id, &entry_json)?;
 
         // 5. Save to database
@@ -1122,21 +1026,14 @@ impl TrendLogFFIService {
     pub async fn get_available_trendlogs(device_id: u32) -> Result<Vec<String>, AppError> {
         // (device-online check removed — T3000_IsDeviceOnline is not exported;
         //  BacnetWebView_GetTrendlogList below reports device state via its return code.)
-        const LIST_BUFFER_SIZE: usize = 65536; // 64KB — plenty for a monitor list
-        let mut list_buffer: Vec<u8> = vec![0; LIST_BUFFER_SIZE];
-        let ffi_result = unsafe {
-            BacnetWebView_GetTrendlogList(
-                1, // Default panel ID (matches legacy behavior)
-                list_buffer.as_mut_ptr() as *mut c_char,
-                LIST_BUFFER_SIZE as c_int,
-            )
-        };
-        if ffi_result <= 0 {
-            let _ = emit_ffi_log_sync("T3_Webview_TRL_FFI", &format!("[ERROR] BacnetWebView_GetTrendlogList failed (result {})", ffi_result), LogLevel::Error);
-            return Err(AppError::FfiError("Failed to retrieve TrendLog list from T3000".to_string()));
-        }
+        let json_str = transport::current()
+            .trendlog_list(1) // Default panel ID (matches legacy behavior)
+            .await
+            .map_err(|e| {
+                emit_ffi_log_sync("T3_Webview_TRL_FFI", &format!("[ERROR] BacnetWebView_GetTrendlogList failed ({})", e), LogLevel::Error);
+                AppError::FfiError("Failed to retrieve TrendLog list from T3000".to_string())
+            })?;
 
-        let json_str = String::from_utf8_lossy(&list_buffer[..ffi_result as usize]).to_string();
         let parsed: serde_json::Value = serde_json::from_str(&json_str)
             .map_err(|e| {
                 let _ = emit_ffi_log_sync("T3_Webview_TRL_FFI", &format!("[ERROR] Failed to parse trendlog list JSON: {}", e), LogLevel::Error);
@@ -6,17 +6,16 @@
 // SCOPE: Only calls new export functions, does not duplicate existing FFI infrastructure
 // DATA FLOW: New C++ exports → This service → TRENDLOG table
 
-use std::os::raw::{c_char, c_int};
 use sea_orm::*;
 use sea_orm::ActiveValue::NotSet;
 use serde::{Deserialize, Serialize};
 use chrono::Utc;
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use std::ffi::CString;
 
 use crate::entity::t3_device::trendlogs;
 use crate::error::AppError;
+use crate::t3_device::transport::{self, TransportError};
 
 async fn emit_monitor_log(db: &DatabaseConnection, level: &str, message: &str) {
     crate::logging::service::emit_app_log(
@@ -31,14 +30,7 @@ async fn emit_monitor_log(db: &DatabaseConnection, level: &str, message: &str) {
     .await;
 }
 
-// Dynamic loading approach to avoid linking errors
-#[cfg(target_os = "windows")]
-use winapi::um::libloaderapi::{GetProcAddress, GetModuleHandleA};
-
-// Function pointer types for dynamic loading
-type GetTrendlogListFn = unsafe extern "C" fn(panel_id: c_int, result_buffer: *mut c_char, buffer_size: c_int) -> c_int;
-type GetTrendlogEntryFn = unsafe extern "C" fn(panel_id: c_int, monitor_index: c_int, result_buffer: *mut c_char, buffer_size: c_int) -> c_int;
-type SyncMonitorDataFn = unsafe extern "C" fn(panel_id: c_int) -> c_int;/// Trendlog data structure matching C++ Fresh_Monitor_List output
+/// Trendlog data structure matching C++ Fresh_Monitor_List output
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TrendlogMonitorData {
     pub num: i32,                    // Monitor index (NUM column)
@@ -89,13 +81,11 @@ pub struct TrendlogInputData {
 }
 
 /// Lightweight TrendLog Monitor Service
+///
+/// The list/entry/sync exports are reached through the installed
+/// `transport::DeviceTransport`.
 pub struct TrendlogMonitorService {
     db_connection: Arc<Mutex<DatabaseConnection>>,
-    buffer_size: usize,
-    // Optional function pointers loaded dynamically
-    get_trendlog_list_fn: Option<GetTrendlogListFn>,
-    get_trendlog_entry_fn: Option<GetTrendlogEntryFn>,
-    sync_monitor_data_fn: Option<SyncMonitorDataFn>,
 }
 
 impl TrendlogMonitorService {
@@ -106,99 +96,23 @@ impl TrendlogMonitorService {
 
     /// Create new service instance
     pub fn new(db_connection: Arc<Mutex<DatabaseConnection>>) -> Self {
-        // Try to dynamically load the C++ functions
-        let (get_trendlog_list_fn, get_trendlog_entry_fn, sync_monitor_data_fn) = Self::try_load_ffi_functions();
-
-        Self {
-            db_connection,
-            buffer_size: 65536, // 64KB buffer for JSON responses
-            get_trendlog_list_fn,
-            get_trendlog_entry_fn,
-            sync_monitor_data_fn,
-        }
-    }
-
-    /// Attempt to dynamically load T3000 FFI functions
-    #[cfg(target_os = "windows")]
-    fn try_load_ffi_functions() -> (Option<GetTrendlogListFn>, Option<GetTrendlogEntryFn>, Option<SyncMonitorDataFn>) {
-        unsafe {
-            // Try to get handle to current process (where T3000.exe functions should be)
-            // LoadLibraryA(NULL) does not return the current module; use GetModuleHandleA(NULL)
-            let module = GetModuleHandleA(std::ptr::null()); // Current process
-            if module.is_null() {
-                return (None, None, None);
-            }
-
-            // Try to get function pointers
-            let list_fn_name = CString::new("BacnetWebView_GetTrendlogList").unwrap();
-            let entry_fn_name = CString::new("BacnetWebView_GetTrendlogEntry").unwrap();
-            let sync_fn_name = CString::new("BacnetWebView_SyncMonitorData").unwrap();
-
-            let list_fn = GetProcAddress(module, list_fn_name.as_ptr());
-            let entry_fn = GetProcAddress(module, entry_fn_name.as_ptr());
-            let sync_fn = GetProcAddress(module, sync_fn_name.as_ptr());
-
-            let list_fn = if !list_fn.is_null() {
-                Some(std::mem::transmute::<_, GetTrendlogListFn>(list_fn))
-            } else {
-                None
-            };
-
-            let entry_fn = if !entry_fn.is_null() {
-                Some(std::mem::transmute::<_, GetTrendlogEntryFn>(entry_fn))
-            } else {
-                None
-            };
-
-            let sync_fn = if !sync_fn.is_null() {
-                Some(std::mem::transmute::<_, SyncMonitorDataFn>(sync_fn))
-            } else {
-                None
-            };
-
-            (list_fn, entry_fn, sync_fn)
-        }
-    }
-
-    /// Non-Windows platforms - no FFI available
-    #[cfg(not(target_os = "windows"))]
-    fn try_load_ffi_functions() -> (Option<GetTrendlogListFn>, Option<GetTrendlogEntryFn>, Option<SyncMonitorDataFn>) {
-        (None, None, None)
+        Self { db_connection }
     }
 
     /// Get trendlog list for a device using new C++ export function
     pub async fn get_trendlog_list(&self, panel_id: i32) -> Result<TrendlogListResponse, AppError> {
         self.emit_log("info", &format!("🔍 Getting trendlog list for panel_id: {}", panel_id)).await;
 
-        // Check if FFI function is available
-        if let Some(ffi_fn) = self.get_trendlog_list_fn {
-            self.emit_log("info", "✅ Using NEW C++ export function: BacnetWebView_GetTrendlogList").await;
-            // Prepare buffer for C++ response
-            let mut buffer = vec![0u8; self.buffer_size];
-
-            // Call the dynamically loaded C++ function
-            let result = unsafe {
-                ffi_fn(
-                    panel_id,
-                    buffer.as_mut_ptr() as *mut c_char,
-                    self.buffer_size as c_int,
-                )
-            };
-
-            if result > 0 {
-                self.emit_log("info", &format!("✅ C++ export function returned {} bytes", result)).await;
-
-                // Convert buffer to string (result contains actual length)
-                let json_str = unsafe {
-                    std::str::from_utf8_unchecked(&buffer[..result as usize])
-                };
-
+        let transport = transport::current();
+        match transport.trendlog_list(panel_id).await {
+            Ok(json_str) => {
+                self.emit_log("info", &format!("✅ {} transport returned {} bytes", transport.name(), json_str.len())).await;
                 self.emit_log("info", &format!("📋 C++ Response: {}", json_str)).await;
 
                 // Parse JSON response from C++
-                match serde_json::from_str::<TrendlogListResponse>(json_str) {
+                match serde_json::from_str::<TrendlogListResponse>(&json_str) {
                     Ok(response) => {
-                        self.emit_log("info", &format!("🎉 Successfully retrieved {} trendlogs for panel_id {} via NEW C++ exports", response.trendlogs.len(), panel_id)).await;
+                        self.emit_log("info", &format!("🎉 Successfully retrieved {} trendlogs for panel_id {} via {} transport", response.trendlogs.len(), panel_id, transport.name())).await;
                         return Ok(response);
                     },
                     Err(e) => {
@@ -206,6 +120,9 @@ impl TrendlogMonitorService {
                     }
                 }
             }
+            Err(e) => {
+                self.emit_log("warn", &format!("BacnetWebView_GetTrendlogList via {} transport failed: {}", transport.name(), e)).await;
+            }
         }
 
         // Fallback: return mock/empty response when FFI is not available
@@ -220,29 +137,10 @@ impl TrendlogMonitorService {
         })
     }    /// Get specific trendlog entry using new C++ export function
     pub async fn get_trendlog_entry(&self, panel_id: i32, monitor_index: i32) -> Result<TrendlogEntryResponse, AppError> {
-        // Check if FFI function is available
-        if let Some(ffi_fn) = self.get_trendlog_entry_fn {
-            // Prepare buffer for C++ response
-            let mut buffer = vec![0u8; self.buffer_size];
-
-            // Call the dynamically loaded C++ function
-            let result = unsafe {
-                ffi_fn(
-                    panel_id,
-                    monitor_index,
-                    buffer.as_mut_ptr() as *mut c_char,
-                    self.buffer_size as c_int,
-                )
-            };
-
-            if result > 0 {
-                // Convert buffer to string
-                let json_str = unsafe {
-                    std::str::from_utf8_unchecked(&buffer[..result as usize])
-                };
-
+        match transport::current().trendlog_entry(panel_id, monitor_index).await {
+            Ok(json_str) => {
                 // Parse JSON response from C++
-                match serde_json::from_str::<TrendlogEntryResponse>(json_str) {
+                match serde_json::from_str::<TrendlogEntryResponse>(&json_str) {
                     Ok(response) => {
                         // Log successful retrieval
                         self.emit_log("info", &format!("Retrieved trendlog entry panel_id {} monitor {} with {} inputs", panel_id, monitor_index, response.inputs.len())).await;
@@ -253,6 +151,9 @@ impl TrendlogMonitorService {
                     }
                 }
             }
+            Err(e) => {
+                self.emit_log("warn", &format!("BacnetWebView_GetTrendlogEntry failed for panel_id {} monitor {}: {}", panel_id, monitor_index, e)).await;
+            }
         }
 
         // Fallback: return mock/empty response when FFI is not available
@@ -283,20 +184,20 @@ impl TrendlogMonitorService {
     /// Sync m_monitor_data to g_monitor_data[panel_id] to ensure data is accessible
     /// This should be called BEFORE getting trendlog data
     pub async fn sync_monitor_data_to_global(&self, panel_id: i32) -> Result<i32, AppError> {
-        if let Some(sync_fn) = self.sync_monitor_data_fn {
-            let result = unsafe { sync_fn(panel_id) };
-
-            if result >= 0 {
-                self.emit_log("info", &format!("✅ Synced {} monitors to g_monitor_data[{}]", result, panel_id)).await;
-                Ok(result)
-            } else {
-                self.emit_log("warn", &format!("⚠️ Failed to sync monitor data for panel_id {}: returned {}", panel_id, result)).await;
+        match transport::current().sync_monitor_data(panel_id).await {
+            Ok(count) => {
+                self.emit_log("info", &format!("✅ Synced {} monitors to g_monitor_data[{}]", count, panel_id)).await;
+                Ok(count)
+            }
+            Err(TransportError::Unavailable(reason)) => {
+                self.emit_log("warn", &format!("⚠️ BacnetWebView_SyncMonitorData not available: {}", reason)).await;
+                // Not a fatal error, just means the function isn't available
+                Ok(0)
+            }
+            Err(e) => {
+                self.emit_log("warn", &format!("⚠️ Failed to sync monitor data for panel_id {}: {}", panel_id, e)).await;
                 Err(AppError::NotFound(format!("Failed to sync monitor data for panel_id {}", panel_id)))
             }
-        } else {
-            self.emit_log("warn", "⚠️ BacnetWebView_SyncMonitorData function not available").await;
-            // Not a fatal error, just means the function isn't available
-            Ok(0)
         }
     }
 
@@ -562,11 +463,8 @@ impl TrendlogMonitorService {
 
     /// Test connectivity to new C++ export functions
     pub async fn test_ffi_connectivity(&self) -> Result<bool, AppError> {
-        // Check if FFI functions are dynamically loaded
-        let has_list_fn = self.get_trendlog_list_fn.is_some();
-        let has_entry_fn = self.get_trendlog_entry_fn.is_some();
-
-        if has_list_fn && has_entry_fn {
+        let transport = transport::current();
+        if transport.is_available().await {
             // Test with a basic call to panel_id 1
             match self.get_trendlog_list(1).await {
                 Ok(response) => {
@@ -584,7 +482,7 @@ impl TrendlogMonitorService {
                 }
             }
         } else {
-            self.emit_log("info", &format!("FFI functions not available - list_fn: {}, entry_fn: {}", has_list_fn, has_entry_fn)).await;
+            self.emit_log("info", &format!("Device transport '{}' not available", transport.name())).await;
             Ok(false)
         }
     }
@@ -12,6 +12,7 @@ use serde_json::{json, Value};
 use tracing::{error, info};
 
 use crate::app_state::T3AppState;
+use crate::bacnet::points::{self as bacnet_points, PointKind, Transport};
 use crate::entity::t3_device::variable_points;
 use crate::t3_device::action17_refresh_helper::lookup_action17_target;
 use crate::t3_device::t3_ffi_sync_service::WebViewMessageType;
@@ -27,6 +28,8 @@ const BAC_VAR: i32 = 2;
 pub struct RefreshVariableRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub index: Option<i32>,
+    /// "ffi" (T3000.exe) or "bacnet" (native BACnet/IP); defaults per platform
+    pub transport: Option<String>,
 }
 
 /// Response structure for refresh operations
@@ -67,6 +70,7 @@ pub fn create_variable_refresh_routes() -> Router<T3AppState> {
 /// Refresh variable(s) from device using GET_WEBVIEW_LIST action (Action 17)
 /// POST /api/t3-device/variables/:serial/refresh
 /// Body: { "index": 5 } for single item, or {} for all items
+/// Optional "transport": "bacnet" reads over native BACnet/IP instead of T3000.exe
 /// Returns the raw data from device without saving to database
 pub async fn refresh_variables(
     State(state): State<T3AppState>,
@@ -95,6 +99,26 @@ pub async fn refresh_variables(
         }
     };
 
+    let transport = Transport::resolve(payload.transport.as_deref())
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    if transport == Transport::Bacnet {
+        let items = bacnet_points::refresh(&db_connection, serial, PointKind::Variable, payload.index)
+            .await
+            .map_err(|e| {
+                error!("❌ BACnet/IP refresh failed: {}", e);
+                (StatusCode::BAD_GATEWAY, format!("Failed to refresh variables over BACnet/IP: {}", e))
+            })?;
+        let count = items.len() as i32;
+        info!("✅ Refreshed {} variable(s) from device over BACnet/IP", count);
+        return Ok(Json(RefreshResponse {
+            success: true,
+            message: format!("Refreshed {} variable(s) from device", count),
+            items,
+            count,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        }));
+    }
+
     let (panel_id, object_instance) = lookup_action17_target(&db_connection, serial).await?;
 
     // Prepare refresh JSON for GET_WEBVIEW_LIST action (Action 17)
@@ -326,69 +350,17 @@ async fn save_variables_to_db(
 
 /// Call FFI function for refresh operations
 async fn call_refresh_ffi(action: i32, refresh_json: Value) -> Result<String, String> {
-    use crate::t3_device::t3_ffi_sync_service::load_t3000_function;
+    use crate::t3_device::transport;
 
     let input_str = refresh_json.to_string();
     info!("📤 Sending to C++ (Action {}): {}", action, input_str);
 
-    // Ensure T3000 functions are loaded
-    tokio::task::spawn_blocking(|| {
-        unsafe {
-            if !load_t3000_function() {
-                return Err("T3000 functions not loaded".to_string());
-            }
-        }
-        Ok(())
-    }).await
-    .map_err(|e| format!("Failed to check T3000 functions: {}", e))?
-    .map_err(|e| e)?;
-
-    // Run FFI call in blocking task
-    tokio::task::spawn_blocking(move || {
-        use crate::t3_device::t3_ffi_sync_service::BACNETWEBVIEW_HANDLE_WEBVIEW_MSG_FN;
-
-        const BUFFER_SIZE: usize = 1048576; // 1MB buffer
-        let mut buffer: Vec<u8> = vec![0; BUFFER_SIZE];
-
-        // Write input JSON to buffer
-        let input_bytes = input_str.as_bytes();
-        if input_bytes.len() >= BUFFER_SIZE {
-            return Err("Input JSON too large for buffer".to_string());
-        }
+    let response = transport::current().call(action, &input_str).await?;
+    info!("📥 C++ Response (Action {}): {}", action, response);
 
-        buffer[..input_bytes.len()].copy_from_slice(input_bytes);
-        buffer[input_bytes.len()] = 0; // Null terminator
-
-        unsafe {
-            if let Some(func) = BACNETWEBVIEW_HANDLE_WEBVIEW_MSG_FN {
-                let result = func(
-                    action,
-                    buffer.as_mut_ptr() as *mut std::os::raw::c_char,
-                    buffer.len() as i32,
-                );
-
-                match result {
-                    0 => {
-                        // Success - read response from buffer
-                        let null_pos = buffer.iter().position(|&b| b == 0).unwrap_or(buffer.len());
-                        let response = String::from_utf8_lossy(&buffer[..null_pos]).to_string();
-                        info!("📥 C++ Response (Action {}): {}", action, response);
-
-                        // Check if response is empty or minimal (C++ not implemented)
-                        if response.is_empty() || response == "{}" {
-                            return Err("Action not implemented in C++ - empty response".to_string());
-                        }
-
-                        Ok(response)
-                    }
-                    -2 => Err("MFC application not initialized".to_string()),
-                    code => Err(format!("FFI call failed with code: {}", code)),
-                }
-            } else {
-                Err("BacnetWebView_HandleWebViewMsg function not loaded".to_string())
-            }
-        }
-    })
-    .await
-    .map_err(|e| format!("Task spawn error: {}", e))?
+    if response.is_empty() || response == "{}" {
+        return Err("Action not implemented in C++ - empty response".to_string());
+    }
+
+    Ok(response)
 }
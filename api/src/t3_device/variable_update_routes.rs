@@ -12,6 +12,7 @@ use serde_json::{json, Value};
 use tracing::{error, info};
 
 use crate::app_state::T3AppState;
+use crate::bacnet::points::{self as bacnet_points, PointKind, PointWrite, Transport};
 use crate::entity::t3_device::{devices, variable_points};
 use crate::t3_device::t3_ffi_sync_service::WebViewMessageType;
 use sea_orm::*;
@@ -42,6 +43,10 @@ pub struct UpdateVariableFullRequest {
     pub calibration_sign: Option<i32>,
     pub calibration_h: Option<i32>,
     pub calibration_l: Option<i32>,
+    /// "ffi" (T3000.exe) or "bacnet" (native BACnet/IP); defaults per platform
+    pub transport: Option<String>,
+    /// BACnet command priority (1-16) for the value write; outputs/variables only
+    pub priority: Option<u8>,
 }
 
 /// Standard API response structure
@@ -110,6 +115,12 @@ pub async fn update_variable_full(
     Json(payload): Json<UpdateVariableFullRequest>,
 ) -> Result<Json<Value>, (StatusCode, String)> {
     let index = index_str.parse::<i32>().unwrap_or(0);
+    let transport = Transport::resolve(payload.transport.as_deref())
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let point_write = bacnet_write(&payload);
+    if transport == Transport::Bacnet {
+        bacnet_points::ensure_writable(&bacnet_unsupported(&payload)).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    }
     info!("UPDATE_WEBVIEW_LIST: Updating full variable record - Serial: {}, Index: {}", serial, index);
 
     // Get database connection from state
@@ -203,9 +214,15 @@ pub async fn update_variable_full(
         "decom": payload.decom.unwrap_or(0),
     });
 
-    // Call FFI function
+    // Send to device (T3000.exe FFI or BACnet/IP)
     let updated_fields_clone = updated_fields.clone();
-    match call_update_ffi(WebViewMessageType::UPDATE_WEBVIEW_LIST as i32, input_json).await {
+    let device_result = match transport {
+        Transport::Ffi => call_update_ffi(WebViewMessageType::UPDATE_WEBVIEW_LIST as i32, input_json).await,
+        Transport::Bacnet => bacnet_points::update(&db_connection, serial, PointKind::Variable, index, &point_write)
+            .await
+            .map(|_| String::new()),
+    };
+    match device_result {
         Ok(_response) => {
             info!("✅ Full variable record updated in device");
 
@@ -314,65 +331,43 @@ async fn save_variable_to_db(
     }
 }
 
+/// Fields the BACnet/IP transport can write (label, description, value, auto/manual)
+fn bacnet_write(payload: &UpdateVariableFullRequest) -> PointWrite {
+    PointWrite {
+        value: payload.value.map(f64::from),
+        label: payload.label.clone(),
+        description: payload.full_label.clone(),
+        auto_manual: payload.auto_manual,
+        priority: payload.priority,
+    }
+}
+
+/// Request fields the BACnet/IP transport can't write
+fn bacnet_unsupported(payload: &UpdateVariableFullRequest) -> Vec<&'static str> {
+    [
+        ("range", payload.range.is_some()),
+        ("control", payload.control.is_some()),
+        ("filter", payload.filter.is_some()),
+        ("digitalAnalog", payload.digital_analog.is_some()),
+        ("decom", payload.decom.is_some()),
+        ("calibrationSign", payload.calibration_sign.is_some()),
+        ("calibrationH", payload.calibration_h.is_some()),
+        ("calibrationL", payload.calibration_l.is_some()),
+    ]
+    .into_iter()
+    .filter_map(|(field, set)| set.then_some(field))
+    .collect()
+}
+
 /// Helper function to call C++ FFI for update operations
 async fn call_update_ffi(action: i32, input_json: Value) -> Result<String, String> {
-    use crate::t3_device::t3_ffi_sync_service::load_t3000_function;
+    use crate::t3_device::transport;
 
     let input_str = input_json.to_string();
     info!("📤 Sending to C++ (Action {}): {}", action, input_str);
 
-    // Ensure T3000 functions are loaded
-    tokio::task::spawn_blocking(|| {
-        unsafe {
-            if !load_t3000_function() {
-                return Err("T3000 functions not loaded".to_string());
-            }
-        }
-        Ok(())
-    }).await
-    .map_err(|e| format!("Failed to check T3000 functions: {}", e))?
-    .map_err(|e| e)?;
-
-    // Run FFI call in blocking task
-    tokio::task::spawn_blocking(move || {
-        use crate::t3_device::t3_ffi_sync_service::BACNETWEBVIEW_HANDLE_WEBVIEW_MSG_FN;
-
-        const BUFFER_SIZE: usize = 1048576; // 1MB buffer
-        let mut buffer: Vec<u8> = vec![0; BUFFER_SIZE];
-
-        // Write input JSON to buffer
-        let input_bytes = input_str.as_bytes();
-        if input_bytes.len() >= BUFFER_SIZE {
-            return Err("Input JSON too large for buffer".to_string());
-        }
-
-        buffer[..input_bytes.len()].copy_from_slice(input_bytes);
-        buffer[input_bytes.len()] = 0; // Null terminator
-
-        unsafe {
-            if let Some(func) = BACNETWEBVIEW_HANDLE_WEBVIEW_MSG_FN {
-                let result = func(
-                    action,
-                    buffer.as_mut_ptr() as *mut std::os::raw::c_char,
-                    buffer.len() as i32,
-                );
+    let response = transport::current().call(action, &input_str).await?;
+    info!("📥 C++ Response (Action {}): {}", action, response);
 
-                match result {
-                    0 => {
-                        // Success - read response from buffer
-                        let null_pos = buffer.iter().position(|&b| b == 0).unwrap_or(buffer.len());
-                        let response = String::from_utf8_lossy(&buffer[..null_pos]).to_string();
-                        info!("📥 C++ Response (Action {}): {}", action, response);
-                        Ok(response)
-                    }
-                    -2 => Err("MFC application not initialized".to_string()),
-                    code => Err(format!("FFI call failed with code: {}", code)),
-                }
-            } else {
-                Err("BacnetWebView_HandleWebViewMsg function not loaded".to_string())
-            }
-        }
-    })
-    .await
-    .map_err(|e| format!("Task spawn error: {}", e))?
+    Ok(response)
 }
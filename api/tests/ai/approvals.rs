@@ -0,0 +1,146 @@
+// AI write approval queue — approve/reject through the routes, timeouts,
+// leftovers from a restart, and the persisted decision records.
+
+use std::time::Duration;
+
+use axum::{http::StatusCode, Router};
+use sea_orm::{ConnectionTrait, Database, DatabaseConnection};
+use serde_json::{json, Value};
+
+use t3_webview_api::ai::approvals::{self, Decision};
+use t3_webview_api::ai::types::StreamEvent;
+
+use crate::common::{self, send};
+
+async fn setup() -> (Router, DatabaseConnection) {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    (approvals::approval_routes().with_state(common::app_state(&db)), db)
+}
+
+async fn queue(db: &DatabaseConnection, timeout: Duration) -> tokio::task::JoinHandle<(String, Decision)> {
+    let args = json!({ "serial_number": 4001, "point_type": "OUTPUT", "point_index": 1, "value": 1 });
+    let (action, rx) = approvals::create(db, "chat-1", Some("call-1"), "t3000_point_write", &args, Some("otto"), timeout)
+        .await
+        .unwrap();
+    assert_eq!(action.status, "pending");
+    let db = db.clone();
+    tokio::spawn(async move {
+        let decision = approvals::wait(&db, &action, rx, timeout).await;
+        (action.id, decision)
+    })
+}
+
+async fn pending_id(app: &Router) -> String {
+    let (status, body) = send(app, "GET", "/api/ai/approvals?status=pending", Value::Null).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    body["actions"][0]["id"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn approve_wakes_the_waiting_call_and_is_recorded() {
+    let (app, db) = setup().await;
+    let waiting = queue(&db, Duration::from_secs(30)).await;
+    let id = pending_id(&app).await;
+
+    let (status, body) = send(&app, "POST", &format!("/api/ai/approvals/{}/approve", id), Value::Null).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["status"], "approved");
+    let (waited_id, decision) = waiting.await.unwrap();
+    assert_eq!((waited_id, decision), (id.clone(), Decision::Approved { by: None }));
+
+    let record = approvals::get_action(&db, &id).await.unwrap().unwrap();
+    assert_eq!(record.requested_by.as_deref(), Some("otto"));
+    assert_eq!(record.arguments["serial_number"], 4001);
+    assert!(record.decided_at.is_some());
+
+    // A decision is final.
+    let (status, _) = send(&app, "POST", &format!("/api/ai/approvals/{}/reject", id), Value::Null).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = send(&app, "POST", "/api/ai/approvals/nope/approve", Value::Null).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn reject_carries_the_reason() {
+    let (app, db) = setup().await;
+    let waiting = queue(&db, Duration::from_secs(30)).await;
+    let id = pending_id(&app).await;
+
+    let (status, body) = send(&app, "POST", &format!("/api/ai/approvals/{}/reject", id),
+        json!({ "reason": "fan is locked out" })).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let (_, decision) = waiting.await.unwrap();
+    assert_eq!(decision, Decision::Rejected { by: None, reason: Some("fan is locked out".into()) });
+
+    let (_, body) = send(&app, "GET", "/api/ai/approvals?status=rejected&sessionId=chat-1", Value::Null).await;
+    assert_eq!(body["actions"][0]["reason"], "fan is locked out");
+}
+
+#[tokio::test]
+async fn approving_an_action_settled_elsewhere_does_not_run_it() {
+    let (app, db) = setup().await;
+    let waiting = queue(&db, Duration::from_secs(30)).await;
+    let id = pending_id(&app).await;
+    // Expired in the database while its chat loop is still waiting.
+    db.execute_unprepared(&format!("UPDATE AI_PENDING_ACTIONS SET status = 'expired' WHERE id = '{}'", id))
+        .await
+        .unwrap();
+
+    let (status, body) = send(&app, "POST", &format!("/api/ai/approvals/{}/approve", id), Value::Null).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+    let (_, decision) = waiting.await.unwrap();
+    assert_eq!(decision, Decision::Expired);
+    assert_eq!(approvals::get_action(&db, &id).await.unwrap().unwrap().status, "expired");
+}
+
+#[tokio::test]
+async fn unanswered_calls_expire() {
+    let (app, db) = setup().await;
+    let waiting = queue(&db, Duration::from_millis(50)).await;
+    let (id, decision) = waiting.await.unwrap();
+    assert_eq!(decision, Decision::Expired);
+    assert_eq!(approvals::get_action(&db, &id).await.unwrap().unwrap().status, "expired");
+    let (status, _) = send(&app, "POST", &format!("/api/ai/approvals/{}/approve", id), Value::Null).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Pending rows nothing waits for (e.g. from before a restart) expire on listing.
+    db.execute_unprepared(
+        "INSERT INTO AI_PENDING_ACTIONS (id, session_id, tool_name, arguments, created_at, expires_at) \
+         VALUES ('stale', 'chat-0', 't3000_settings_write', '{}', 0, 1)",
+    )
+    .await
+    .unwrap();
+    let (_, body) = send(&app, "GET", "/api/ai/approvals?status=pending", Value::Null).await;
+    assert_eq!(body["actions"], json!([]));
+    assert_eq!(approvals::get_action(&db, "stale").await.unwrap().unwrap().status, "expired");
+}
+
+#[test]
+fn approval_events_serialize_for_the_ui() {
+    let event = StreamEvent::ApprovalRequired {
+        action_id: "a1".into(),
+        id: "call-1".into(),
+        name: "t3000_point_write".into(),
+        arguments: json!({ "value": 1 }),
+        expires_at: 42,
+    };
+    let json: Value = serde_json::from_str(&serde_json::to_string(&event).unwrap()).unwrap();
+    assert_eq!(json["event"], "approval_required");
+    assert_eq!(json["data"]["args"]["value"], 1);
+    assert_eq!(json["data"]["action_id"], "a1");
+}
+
+#[test]
+fn every_audited_write_tool_requires_approval() {
+    use t3_webview_api::audit::tools::AUDITED_TOOLS;
+    use t3_webview_api::mcp::tokens::is_write_tool;
+
+    for tool in AUDITED_TOOLS {
+        assert!(approvals::requires_approval(tool), "{} runs without approval", tool);
+        assert!(is_write_tool(tool), "{} is allowed for read-only MCP tokens", tool);
+    }
+    for tool in ["t3000_alarm_acknowledge", "t3000_rule_create", "t3000_rule_toggle", "t3000_haystack_auto_tag"] {
+        assert!(approvals::requires_approval(tool), "{}", tool);
+    }
+    assert!(!approvals::requires_approval("t3000_point_read"));
+}
@@ -0,0 +1,214 @@
+// Conversation compaction — old turns folded into a summary note once the
+// prompt outgrows the model's budget, digested tool results, and the
+// summary surviving a save/load of the session file.
+
+use t3_webview_api::ai::compaction::{self, Compaction, SUMMARY_HEADING};
+use t3_webview_api::ai::session_store::SessionFile;
+use t3_webview_api::ai::types::{FunctionCall, Message, ToolCall};
+
+fn msg(role: &str, content: &str) -> Message {
+    Message {
+        role: role.into(),
+        content: content.into(),
+        name: None,
+        tool_calls: None,
+        tool_call_id: None,
+        ui: None,
+        usage: None,
+    }
+}
+
+fn call(id: &str, name: &str, arguments: &str) -> Message {
+    Message {
+        tool_calls: Some(vec![ToolCall {
+            id: id.into(),
+            call_type: "function".into(),
+            function: FunctionCall {
+                name: name.into(),
+                arguments: arguments.into(),
+                thought_signature: None,
+            },
+        }]),
+        ..msg("assistant", "")
+    }
+}
+
+fn result(id: &str, content: &str) -> Message {
+    Message { tool_call_id: Some(id.into()), ..msg("tool", content) }
+}
+
+fn is_write(name: &str) -> bool {
+    name == "t3000_write_point"
+}
+
+/// System prompt plus `turns` user turns, each running one read tool with a
+/// bulky result and one write tool.
+fn conversation(turns: usize) -> Vec<Message> {
+    let mut messages = vec![msg("system", "You are the T3000 assistant.")];
+    for t in 0..turns {
+        messages.push(msg("user", &format!("Question {} about device {}", t, 1000 + t)));
+        messages.push(call(&format!("r{}", t), "t3000_get_points", &format!(r#"{{"serial_number":{}}}"#, 1000 + t)));
+        messages.push(result(&format!("r{}", t), &"x".repeat(4000)));
+        messages.push(call(
+            &format!("w{}", t),
+            "t3000_write_point",
+            &format!(r#"{{"serial_number":{},"point":"OUT{}","value":1,"confirm":true}}"#, 1000 + t, t),
+        ));
+        messages.push(result(&format!("w{}", t), r#"{"tool":"t3000_write_point","ok":true}"#));
+        messages.push(msg("assistant", &format!("Answer {}", t)));
+    }
+    messages
+}
+
+#[test]
+fn small_conversations_are_sent_verbatim() {
+    let messages = conversation(3);
+    let mut state = None;
+    let (out, outcome) = compaction::fit(&messages, &mut state, 1_000_000, is_write);
+    assert_eq!(out.len(), messages.len());
+    assert_eq!(outcome.turns_compacted, 0);
+    assert_eq!(outcome.tool_results_digested, 0);
+    assert!(state.is_none());
+}
+
+#[test]
+fn old_turns_are_folded_into_the_summary() {
+    let messages = conversation(5);
+    let mut state = None;
+    let (out, outcome) = compaction::fit(&messages, &mut state, 3_000, is_write);
+
+    // The last two turns stay verbatim, the first three are summarized
+    assert_eq!(outcome.turns_compacted, 3);
+    assert!(outcome.tokens_after < outcome.tokens_before);
+    let state = state.expect("summary");
+    assert_eq!(state.turns, 3);
+    assert!(state.summary.contains("Question 0"));
+    assert!(state.summary.contains("Answer 2"));
+    assert!(state.summary.contains("t3000_write_point(") && state.summary.contains("serial_number=1001"));
+    assert!(!state.summary.contains("Question 3"));
+
+    assert_eq!(out[0].role, "system");
+    assert!(out[0].content.starts_with("You are the T3000 assistant."));
+    assert!(out[0].content.contains(SUMMARY_HEADING));
+    assert_eq!(out[1].content, "Question 3 about device 1003");
+    assert_eq!(out.len(), 1 + 2 * 6);
+
+    // Devices and successful writes of the summarized turns become facts
+    assert!(outcome.facts.iter().any(|f| f == "devices 1000"));
+    assert!(outcome.facts.iter().any(|f| f.starts_with("t3000_write_point(") && f.contains("OUT2")));
+    assert!(!outcome.facts.iter().any(|f| f.contains("confirm")));
+}
+
+#[test]
+fn summary_is_extended_not_rebuilt() {
+    let mut messages = conversation(5);
+    let mut state = None;
+    compaction::fit(&messages, &mut state, 3_000, is_write);
+    let first = state.clone().unwrap();
+
+    messages.extend(conversation(7).into_iter().skip(1 + 5 * 6));
+    let (_, outcome) = compaction::fit(&messages, &mut state, 3_000, is_write);
+    let second = state.unwrap();
+    assert_eq!(outcome.turns_compacted, 2);
+    assert_eq!(second.turns, 5);
+    assert!(second.summary.starts_with(&first.summary));
+    assert!(second.summary.contains("Question 4"));
+}
+
+#[test]
+fn a_long_single_turn_digests_older_tool_results() {
+    let mut messages = vec![msg("system", "sys"), msg("user", "Scan everything")];
+    for i in 0..8 {
+        messages.push(call(&format!("c{}", i), "t3000_get_points", r#"{"serial_number":1}"#));
+        messages.push(result(&format!("c{}", i), &"y".repeat(4000)));
+    }
+    let mut state = None;
+    let (out, outcome) = compaction::fit(&messages, &mut state, 3_000, is_write);
+
+    assert_eq!(outcome.turns_compacted, 0);
+    assert_eq!(outcome.tool_results_digested, 4);
+    let tool_results: Vec<&Message> = out.iter().filter(|m| m.role == "tool").collect();
+    assert!(tool_results[..4].iter().all(|m| m.content.contains("[digested: 4000 chars")));
+    assert!(tool_results[4..].iter().all(|m| m.content.len() == 4000));
+    // The full history itself is untouched
+    assert!(messages.iter().filter(|m| m.role == "tool").all(|m| m.content.len() == 4000));
+}
+
+#[test]
+fn view_falls_back_to_full_history_when_it_no_longer_matches() {
+    let messages = conversation(2);
+    let stale = Compaction { summary: "- User: gone".into(), turns: 5 };
+    assert_eq!(compaction::view(&messages, Some(&stale)).len(), messages.len());
+}
+
+#[test]
+fn budget_reserves_room_for_the_reply_and_tools() {
+    assert_eq!(compaction::prompt_budget(32_000, 0), 24_000);
+    assert_eq!(compaction::prompt_budget(32_000, 4_000), 20_000);
+    assert_eq!(compaction::prompt_budget(1_000, 5_000), 0);
+    assert_eq!(compaction::default_context_tokens("anthropic", "claude-sonnet-4"), 200_000);
+    assert_eq!(compaction::default_context_tokens("local", "llama3.1:8b"), 32_768);
+}
+
+#[test]
+fn summary_round_trips_through_the_session_file() {
+    let messages = conversation(5);
+    let mut state = None;
+    compaction::fit(&messages, &mut state, 3_000, is_write);
+
+    let file = SessionFile {
+        id: "abc".into(),
+        title: "Question 0".into(),
+        provider: "local".into(),
+        model: "llama3.1:8b".into(),
+        created_at: "2026-01-01T00:00:00Z".into(),
+        messages: messages.clone(),
+        usage: Default::default(),
+        compaction: state.clone(),
+    };
+    let json = serde_json::to_string(&file).unwrap();
+    let loaded: SessionFile = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded.compaction, state);
+
+    // A resumed session sends the same compacted view straight away
+    let resumed = compaction::view(&loaded.messages, loaded.compaction.as_ref());
+    let expected = compaction::view(&messages, state.as_ref());
+    assert_eq!(serde_json::to_value(&resumed).unwrap(), serde_json::to_value(&expected).unwrap());
+
+    // Session files written before compaction existed still load
+    let mut old: serde_json::Value = serde_json::from_str(&json).unwrap();
+    old.as_object_mut().unwrap().remove("compaction");
+    let loaded: SessionFile = serde_json::from_value(old).unwrap();
+    assert!(loaded.compaction.is_none());
+}
+
+#[test]
+fn memory_entries_keep_title_and_facts_as_fields() {
+    let facts = |f: &[&str]| f.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+    let first = compaction::memory_entry(None, "chat-1", "AHU-1: SAT alarm", &facts(&["device 4001: read SAT"]), "t0");
+    assert_eq!(first["title"], "AHU-1: SAT alarm");
+    assert_eq!(first["content"], "Chat \"AHU-1: SAT alarm\": device 4001: read SAT");
+
+    let second = compaction::memory_entry(Some(&first), "chat-1", "AHU-1: SAT alarm",
+        &facts(&["device 4001: read SAT", "wrote IN2"]), "t1");
+    assert_eq!(second["facts"], serde_json::json!(["device 4001: read SAT", "wrote IN2"]));
+    assert_eq!((second["created_at"].clone(), second["updated_at"].clone()), (serde_json::json!("t0"), serde_json::json!("t1")));
+}
+
+#[test]
+fn credentials_in_tool_arguments_never_reach_summary_or_memory() {
+    let mut messages = conversation(5);
+    messages[4] = call("w0", "t3000_settings_write", r#"{"fields":{"smtp_password":"hunter2","smtp_host":"mail"},"confirm":true}"#);
+    messages[5] = result("w0", r#"{"tool":"t3000_settings_write","ok":true}"#);
+    let mut state = None;
+    let (_, outcome) = compaction::fit(&messages, &mut state, 3_000, |name| name == "t3000_settings_write");
+
+    let summary = state.expect("summary").summary;
+    assert!(summary.contains("t3000_settings_write(") && summary.contains("smtp_host"));
+    assert!(!summary.contains("hunter2"));
+    let fact = outcome.facts.iter().find(|f| f.starts_with("t3000_settings_write(")).expect("settings write fact");
+    assert!(!fact.contains("hunter2"));
+
+    let entry = compaction::memory_entry(None, "chat-1", "SMTP setup", &outcome.facts, "t0");
+    assert!(!entry.to_string().contains("hunter2"));
+}
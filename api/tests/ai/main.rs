@@ -11,3 +11,10 @@ mod tool_coverage;
 mod doc_guide;
 mod llm_plain_english;
 mod gemini;
+mod approvals;
+mod usage;
+mod compaction;
+mod providers;
+
+#[path = "../mcp/common.rs"]
+mod common;
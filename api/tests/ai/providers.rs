@@ -0,0 +1,328 @@
+// OpenAI-compatible provider and fallback chains against a local mock server.
+//
+// The mock replays recorded SSE streams (tests/ai/fixtures/*.sse) and
+// scripted HTTP failures, so these run without a model. Covers parallel tool
+// calls, tool calls with missing ids or object arguments, tool calls written
+// into the message text (also after a reasoning model's thinking), retry with backoff on transient errors, and falling
+// back to the next provider in the chain.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, routing::post, Router};
+use serde_json::json;
+use tokio::sync::mpsc;
+
+use t3_webview_api::ai::providers::fallback::FallbackChain;
+use t3_webview_api::ai::providers::local::{content_tool_calls, LocalProvider};
+use t3_webview_api::ai::providers::{LlmProvider, ToolDef};
+use t3_webview_api::ai::types::{AiError, Message, ProviderTarget, StreamEvent};
+
+const PARALLEL: &str = include_str!("fixtures/openai_parallel_tool_calls.sse");
+const MISSING_IDS: &str = include_str!("fixtures/ollama_missing_ids.sse");
+const IN_CONTENT: &str = include_str!("fixtures/hermes_tool_call_in_content.sse");
+const REASONING_IN_CONTENT: &str = include_str!("fixtures/reasoning_tool_call_in_content.sse");
+const PLAIN: &str = include_str!("fixtures/plain_answer.sse");
+
+/// Responses served in order; the last one repeats.
+#[derive(Clone)]
+struct Script {
+    replies: Arc<Vec<(u16, &'static str)>>,
+    hits: Arc<AtomicUsize>,
+}
+
+async fn replay(State(script): State<Script>) -> impl IntoResponse {
+    let n = script.hits.fetch_add(1, Ordering::SeqCst);
+    let (status, body) = script.replies[n.min(script.replies.len() - 1)];
+    (
+        StatusCode::from_u16(status).unwrap(),
+        [("content-type", "text/event-stream")],
+        body,
+    )
+}
+
+/// Start a mock `/v1/chat/completions` server; returns its endpoint and hit counter.
+async fn mock(replies: Vec<(u16, &'static str)>) -> (String, Arc<AtomicUsize>) {
+    let script = Script { replies: Arc::new(replies), hits: Arc::new(AtomicUsize::new(0)) };
+    let hits = script.hits.clone();
+    let app = Router::new().route("/v1/chat/completions", post(replay)).with_state(script);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (format!("http://{}/v1", addr), hits)
+}
+
+/// An endpoint nothing listens on.
+async fn dead_endpoint() -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+    format!("http://{}/v1", addr)
+}
+
+fn tools() -> Vec<ToolDef> {
+    ["t3000_get_points", "t3000_get_device_info", "t3000_get_alarms"]
+        .into_iter()
+        .map(|name| ToolDef {
+            name: name.into(),
+            description: String::new(),
+            input_schema: json!({"type": "object"}),
+        })
+        .collect()
+}
+
+fn question() -> Vec<Message> {
+    vec![Message {
+        role: "user".into(),
+        content: "Check device 1234".into(),
+        name: None,
+        tool_calls: None,
+        tool_call_id: None,
+        ui: None,
+        usage: None,
+    }]
+}
+
+fn target(endpoint: &str, model: &str) -> ProviderTarget {
+    ProviderTarget { provider: "local".into(), model: model.into(), endpoint: endpoint.into(), api_key: None }
+}
+
+fn drain(mut rx: mpsc::UnboundedReceiver<StreamEvent>) -> Vec<StreamEvent> {
+    let mut events = Vec::new();
+    while let Ok(event) = rx.try_recv() {
+        events.push(event);
+    }
+    events
+}
+
+/// (id, name, arguments) of every tool call event.
+fn tool_calls(events: &[StreamEvent]) -> Vec<(String, String, String)> {
+    events
+        .iter()
+        .filter_map(|e| match e {
+            StreamEvent::ToolCall { id, name, arguments, .. } => Some((id.clone(), name.clone(), arguments.clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+fn text(events: &[StreamEvent]) -> String {
+    events
+        .iter()
+        .filter_map(|e| match e {
+            StreamEvent::TextDelta { content } => Some(content.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+async fn stream_local(endpoint: &str) -> (Result<Option<String>, AiError>, Vec<StreamEvent>) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let result = LocalProvider.stream_chat(endpoint, None, "test-model", &question(), &tools(), &tx).await;
+    drop(tx);
+    (result, drain(rx))
+}
+
+// ── Tool-call variants ──
+
+#[tokio::test]
+async fn parallel_tool_calls_are_kept_apart() {
+    let (endpoint, _) = mock(vec![(200, PARALLEL)]).await;
+    let (result, events) = stream_local(&endpoint).await;
+
+    assert_eq!(result.unwrap().as_deref(), Some("tool_calls"));
+    assert_eq!(
+        tool_calls(&events),
+        vec![
+            ("call_a1".into(), "t3000_get_points".into(), r#"{"serial_number":1234}"#.into()),
+            ("call_b2".into(), "t3000_get_device_info".into(), r#"{"serial_number":5678}"#.into()),
+        ]
+    );
+    assert!(events.iter().any(|e| matches!(e, StreamEvent::Usage { input_tokens: 812, output_tokens: 41 })));
+}
+
+#[tokio::test]
+async fn missing_ids_are_generated_and_object_arguments_serialized() {
+    let (endpoint, _) = mock(vec![(200, MISSING_IDS)]).await;
+    let (result, events) = stream_local(&endpoint).await;
+    result.unwrap();
+
+    let calls = tool_calls(&events);
+    assert_eq!(calls.len(), 2);
+    assert_eq!(calls[0].1, "t3000_get_points");
+    assert_eq!(calls[0].2, r#"{"serial_number":1234}"#);
+    assert_eq!(calls[1].1, "t3000_get_alarms");
+    assert_eq!(calls[1].2, "{}");
+    assert!(calls.iter().all(|(id, _, _)| id.starts_with("call_")));
+    assert_ne!(calls[0].0, calls[1].0);
+}
+
+#[tokio::test]
+async fn tool_calls_written_into_content_are_recovered() {
+    let (endpoint, _) = mock(vec![(200, IN_CONTENT)]).await;
+    let (result, events) = stream_local(&endpoint).await;
+
+    assert_eq!(result.unwrap().as_deref(), Some("tool_calls"));
+    let calls = tool_calls(&events);
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].1, "t3000_get_points");
+    assert_eq!(calls[0].2, r#"{"serial_number":1234}"#);
+    // The call markup isn't shown as the answer; the lead-in becomes a thinking step
+    assert!(text(&events).is_empty());
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, StreamEvent::ThinkingDelta { content } if content == "Let me check that device.")));
+}
+
+#[tokio::test]
+async fn tool_calls_after_native_reasoning_are_recovered() {
+    let (endpoint, _) = mock(vec![(200, REASONING_IN_CONTENT)]).await;
+    let (result, events) = stream_local(&endpoint).await;
+
+    assert_eq!(result.unwrap().as_deref(), Some("tool_calls"));
+    let calls = tool_calls(&events);
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].1, "t3000_get_points");
+    assert_eq!(calls[0].2, r#"{"serial_number":1234}"#);
+    // The answer streams as it comes, but the call markup is held back
+    assert_eq!(text(&events), "Checking the device.\n");
+}
+
+#[tokio::test]
+async fn plain_answers_stream_as_text() {
+    let (endpoint, _) = mock(vec![(200, PLAIN)]).await;
+    let (result, events) = stream_local(&endpoint).await;
+
+    assert_eq!(result.unwrap().as_deref(), Some("stop"));
+    assert!(tool_calls(&events).is_empty());
+    assert_eq!(text(&events), "Device 1234 is online.");
+}
+
+#[test]
+fn content_tool_call_formats() {
+    let names = ["t3000_get_points", "t3000_get_alarms"];
+
+    let (calls, rest) = content_tool_calls(
+        "```json\n{\"name\": \"t3000_get_points\", \"parameters\": {\"serial_number\": 1}}\n```",
+        &names,
+    )
+    .unwrap();
+    assert_eq!(calls, vec![("t3000_get_points".into(), r#"{"serial_number":1}"#.into())]);
+    assert!(rest.is_empty());
+
+    let (calls, _) = content_tool_calls(
+        r#"[{"function": {"name": "t3000_get_points", "arguments": "{\"serial_number\":1}"}}, {"name": "t3000_get_alarms"}]"#,
+        &names,
+    )
+    .unwrap();
+    assert_eq!(
+        calls,
+        vec![
+            ("t3000_get_points".into(), r#"{"serial_number":1}"#.into()),
+            ("t3000_get_alarms".into(), "{}".into()),
+        ]
+    );
+
+    // JSON that isn't a call to a known tool is an ordinary answer
+    assert!(content_tool_calls(r#"{"name": "AHU-1", "status": "ok"}"#, &names).is_none());
+    assert!(content_tool_calls("```json\n{\"setpoint\": 72}\n```", &names).is_none());
+    assert!(content_tool_calls("The device is online.", &names).is_none());
+}
+
+// ── Fallback chains ──
+
+#[tokio::test]
+async fn transient_errors_are_retried_with_backoff() {
+    let (endpoint, hits) = mock(vec![(503, "overloaded"), (429, "slow down"), (200, PLAIN)]).await;
+    let chain = FallbackChain::new(vec![target(&endpoint, "primary")], 2)
+        .unwrap()
+        .with_base_delay(Duration::from_millis(20));
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    let started = std::time::Instant::now();
+    let reply = chain.stream_chat(&question(), &tools(), &tx).await.unwrap();
+    drop(tx);
+
+    assert_eq!(hits.load(Ordering::SeqCst), 3);
+    assert_eq!(reply.index, 0);
+    // 20ms, then 40ms
+    assert!(started.elapsed() >= Duration::from_millis(60));
+    let events = drain(rx);
+    assert_eq!(text(&events), "Device 1234 is online.");
+    assert!(!events.iter().any(|e| matches!(e, StreamEvent::ProviderFallback { .. })));
+}
+
+#[tokio::test]
+async fn exhausted_retries_fall_back_to_the_next_provider() {
+    let (primary, primary_hits) = mock(vec![(500, "internal error")]).await;
+    let (backup, backup_hits) = mock(vec![(200, PARALLEL)]).await;
+    let chain = FallbackChain::new(vec![target(&primary, "llama3.1:8b"), target(&backup, "backup-model")], 1)
+        .unwrap()
+        .with_base_delay(Duration::from_millis(1));
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    let reply = chain.stream_chat(&question(), &tools(), &tx).await.unwrap();
+    drop(tx);
+
+    assert_eq!(primary_hits.load(Ordering::SeqCst), 2);
+    assert_eq!(backup_hits.load(Ordering::SeqCst), 1);
+    assert_eq!(reply.index, 1);
+    assert_eq!(reply.target.model, "backup-model");
+    let events = drain(rx);
+    match &events[0] {
+        StreamEvent::ProviderFallback { from, to, reason } => {
+            assert_eq!(from, "local/llama3.1:8b");
+            assert_eq!(to, "local/backup-model");
+            assert!(reason.contains("500"), "{}", reason);
+        }
+        other => panic!("expected ProviderFallback first, got {:?}", other),
+    }
+    assert_eq!(tool_calls(&events).len(), 2);
+}
+
+#[tokio::test]
+async fn connection_failures_fall_back() {
+    let dead = dead_endpoint().await;
+    let (backup, _) = mock(vec![(200, PLAIN)]).await;
+    let chain = FallbackChain::new(vec![target(&dead, "offline"), target(&backup, "backup")], 0).unwrap();
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    let reply = chain.stream_chat(&question(), &tools(), &tx).await.unwrap();
+    drop(tx);
+
+    assert_eq!(reply.index, 1);
+    assert_eq!(text(&drain(rx)), "Device 1234 is online.");
+}
+
+#[tokio::test]
+async fn permanent_errors_skip_retries() {
+    let (primary, primary_hits) = mock(vec![(401, "invalid api key")]).await;
+    let (backup, _) = mock(vec![(200, PLAIN)]).await;
+    let chain = FallbackChain::new(vec![target(&primary, "a"), target(&backup, "b")], 3).unwrap();
+
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let reply = chain.stream_chat(&question(), &tools(), &tx).await.unwrap();
+
+    assert_eq!(primary_hits.load(Ordering::SeqCst), 1);
+    assert_eq!(reply.index, 1);
+}
+
+#[tokio::test]
+async fn the_last_error_is_returned_when_every_provider_fails() {
+    let (primary, _) = mock(vec![(503, "down")]).await;
+    let (backup, _) = mock(vec![(400, "bad request")]).await;
+    let chain = FallbackChain::new(vec![target(&primary, "a"), target(&backup, "b")], 0).unwrap();
+
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let err = chain.stream_chat(&question(), &tools(), &tx).await.unwrap_err();
+    assert!(matches!(err, AiError::Provider(ref m) if m.contains("400")), "{}", err);
+}
+
+#[test]
+fn unknown_providers_are_rejected_up_front() {
+    let mut bad = target("http://localhost", "m");
+    bad.provider = "openrouter".into();
+    assert!(FallbackChain::new(vec![target("http://localhost", "m"), bad], 0).is_err());
+    assert!(matches!(FallbackChain::new(vec![], 0), Err(AiError::NoProvider)));
+}
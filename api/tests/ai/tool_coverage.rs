@@ -8,11 +8,11 @@
 use t3_webview_api::mcp::TOOLS;
 
 #[test]
-fn test_tool_count_is_60() {
+fn test_tool_count_is_71() {
     let count = TOOLS.len();
     assert_eq!(
-        count, 60,
-        "Expected 60 MCP tools, found {}. If you added/removed tools, update this test.",
+        count, 71,
+        "Expected 71 MCP tools, found {}. If you added/removed tools, update this test.",
         count
     );
 }
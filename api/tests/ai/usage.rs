@@ -0,0 +1,160 @@
+// AI usage accounting — the per-call ledger, session totals, aggregates,
+// prices, monthly budgets and the chat refusal once a budget is used up.
+
+use axum::{http::StatusCode, Router};
+use sea_orm::{Database, DatabaseConnection};
+use serde_json::{json, Value};
+
+use t3_webview_api::ai::providers::gemini::GeminiProvider;
+use t3_webview_api::ai::usage::{self, GroupBy, MessageUsage, ModelPrice};
+
+use crate::common::{self, send, send_text};
+
+async fn setup() -> (Router, DatabaseConnection) {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    (t3_webview_api::ai::create_ai_routes().with_state(common::app_state(&db)), db)
+}
+
+fn call(provider: &str, model: &str, input: u64, output: u64, tool_calls: u64) -> MessageUsage {
+    MessageUsage {
+        provider: provider.into(),
+        model: model.into(),
+        input_tokens: input,
+        output_tokens: output,
+        tool_calls,
+        latency_ms: 1200,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn records_prices_and_totals_calls() {
+    let (_, db) = setup().await;
+    usage::set_prices(&db, &[ModelPrice { model: "claude-x".into(), input_per_mtok: 3.0, output_per_mtok: 15.0 }])
+        .await
+        .unwrap();
+
+    let priced = usage::record_call(&db, "chat-1", Some("ana"), call("anthropic", "claude-x", 100_000, 10_000, 2)).await;
+    assert!((priced.cost_usd.unwrap() - 0.45).abs() < 1e-9, "{:?}", priced);
+    let unpriced = usage::record_call(&db, "chat-1", Some("ana"), call("local", "llama3", 5_000, 500, 0)).await;
+    assert_eq!(unpriced.cost_usd, None);
+    usage::record_call(&db, "chat-2", None, call("local", "llama3", 1_000, 100, 1)).await;
+
+    let totals = usage::session_totals(&db, "chat-1").await.unwrap();
+    assert_eq!((totals.llm_calls, totals.input_tokens, totals.output_tokens, totals.tool_calls), (2, 105_000, 10_500, 2));
+    assert_eq!(totals.latency_ms, 2400);
+    assert!((totals.cost_usd - 0.45).abs() < 1e-9);
+
+    let by_model = usage::summarize(&db, GroupBy::Model, None, None).await.unwrap();
+    assert_eq!(by_model.len(), 2);
+    let llama = by_model.iter().find(|r| r.model.as_deref() == Some("llama3")).unwrap();
+    assert_eq!((llama.llm_calls, llama.input_tokens, llama.provider.as_deref()), (2, 6_000, Some("local")));
+
+    let by_user = usage::summarize(&db, GroupBy::User, None, None).await.unwrap();
+    assert_eq!(by_user.iter().find(|r| r.user.as_deref() == Some("ana")).unwrap().llm_calls, 2);
+
+    let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+    let by_day = usage::summarize(&db, GroupBy::Day, Some(&today), Some(&today)).await.unwrap();
+    assert_eq!(by_day.len(), 1);
+    assert_eq!(by_day[0].day.as_deref(), Some(today.as_str()));
+    assert_eq!(by_day[0].llm_calls, 3);
+    assert!(usage::summarize(&db, GroupBy::Day, Some("2000-01-01"), Some("2000-01-31")).await.unwrap().is_empty());
+    assert!(usage::summarize(&db, GroupBy::Day, Some("yesterday"), None).await.is_err());
+}
+
+#[tokio::test]
+async fn aggregate_and_budget_routes() {
+    let (app, db) = setup().await;
+    usage::record_call(&db, "chat-1", Some("ana"), call("local", "llama3", 900, 200, 0)).await;
+
+    let (status, body) = send(&app, "GET", "/api/ai/usage?groupBy=user", Value::Null).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["rows"][0]["user"], "ana");
+    assert_eq!(body["rows"][0]["inputTokens"], 900);
+    let (_, body) = send(&app, "GET", "/api/ai/usage/sessions/chat-1", Value::Null).await;
+    assert_eq!(body["usage"]["output_tokens"], 200);
+
+    let (status, _) = send(&app, "POST", "/api/ai/usage/budgets", json!({ "name": "no limit" })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, budget) = send(&app, "POST", "/api/ai/usage/budgets",
+        json!({ "name": "Ana", "user": "ana", "monthlyTokens": 1000 })).await;
+    assert_eq!(status, StatusCode::OK, "{}", budget);
+    assert_eq!(budget["usedTokens"], 1100);
+    assert_eq!(budget["exhausted"], true);
+    let id = budget["id"].as_i64().unwrap();
+
+    // Only Ana's chats are covered
+    assert!(usage::exhausted_budget(&db, "local", "llama3", Some("ana")).await.unwrap().is_some());
+    assert!(usage::exhausted_budget(&db, "local", "llama3", Some("bo")).await.unwrap().is_none());
+    assert!(usage::exhausted_budget(&db, "local", "llama3", None).await.unwrap().is_none());
+
+    let (_, budget) = send(&app, "PUT", &format!("/api/ai/usage/budgets/{}", id),
+        json!({ "name": "Ana", "user": "ana", "monthlyTokens": 5000 })).await;
+    assert_eq!(budget["exhausted"], false);
+    let (status, _) = send(&app, "DELETE", &format!("/api/ai/usage/budgets/{}", id), Value::Null).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, list) = send(&app, "GET", "/api/ai/usage/budgets", Value::Null).await;
+    assert_eq!(list["budgets"], json!([]));
+}
+
+#[tokio::test]
+async fn chat_refused_when_budget_exhausted() {
+    let (app, db) = setup().await;
+    usage::set_prices(&db, &[ModelPrice { model: "gemini-x".into(), input_per_mtok: 1.0, output_per_mtok: 1.0 }])
+        .await
+        .unwrap();
+    usage::record_call(&db, "chat-1", None, call("gemini", "gemini-x", 1_500_000, 600_000, 0)).await;
+    usage::create_budget(&db, &serde_json::from_value(json!({ "name": "Gemini", "provider": "gemini", "monthlyCostUsd": 2.0 })).unwrap())
+        .await
+        .unwrap();
+
+    let (status, body) = send_text(&app, "POST", "/api/ai/chat", None, json!({
+        "provider": "gemini",
+        "model": "gemini-x",
+        "messages": [{ "role": "user", "content": "hi" }],
+        "settings": { "endpoint": "http://127.0.0.1:9", "api_key": "k" }
+    })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains("\"event\":\"error\""), "{}", body);
+    assert!(body.contains("monthly AI budget \\\"Gemini\\\" is exhausted ($2.10 of $2.00"), "{}", body);
+}
+
+#[tokio::test]
+async fn exhausted_fallbacks_are_skipped() {
+    let (app, db) = setup().await;
+    usage::record_call(&db, "chat-1", None, call("gemini", "gemini-x", 900, 200, 0)).await;
+    usage::record_call(&db, "chat-1", None, call("anthropic", "claude-x", 900, 200, 0)).await;
+    usage::create_budget(&db, &serde_json::from_value(json!({ "name": "Gemini", "provider": "gemini", "monthlyTokens": 1000 })).unwrap())
+        .await
+        .unwrap();
+
+    let chat = |fallbacks: Value| json!({
+        "provider": "gemini",
+        "model": "gemini-x",
+        "messages": [{ "role": "user", "content": "hi" }],
+        "settings": { "endpoint": "http://127.0.0.1:9", "api_key": "k", "fallbacks": fallbacks, "max_retries": 0 }
+    });
+    let anthropic = json!([{ "provider": "anthropic", "model": "claude-x", "endpoint": "http://127.0.0.1:9" }]);
+
+    // The exhausted primary is skipped and the fallback is tried
+    let (_, body) = send_text(&app, "POST", "/api/ai/chat", None, chat(anthropic.clone())).await;
+    assert!(!body.contains("monthly AI budget"), "{}", body);
+
+    // With the fallback over budget too, the chat is refused up front
+    usage::create_budget(&db, &serde_json::from_value(json!({ "name": "Claude", "provider": "anthropic", "monthlyTokens": 1000 })).unwrap())
+        .await
+        .unwrap();
+    let (_, body) = send_text(&app, "POST", "/api/ai/chat", None, chat(anthropic)).await;
+    assert!(body.contains("monthly AI budget \\\"Gemini\\\" is exhausted"), "{}", body);
+    assert!(body.contains("every fallback provider"), "{}", body);
+}
+
+#[test]
+fn gemini_usage_counts_thinking_as_output() {
+    let chunk = json!({
+        "candidates": [],
+        "usageMetadata": { "promptTokenCount": 1200, "candidatesTokenCount": 80, "thoughtsTokenCount": 327 }
+    });
+    assert_eq!(GeminiProvider::usage(&chunk), Some((1200, 407)));
+    assert_eq!(GeminiProvider::usage(&json!({ "candidates": [] })), None);
+}
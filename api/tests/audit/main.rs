@@ -0,0 +1,204 @@
+//! Audit trail — REST point writes with before/after values, MCP tool writes
+//! under an MCP session, filtering, export and credential redaction.
+
+use axum::{http::StatusCode, middleware, Router};
+use sea_orm::{ConnectionTrait, Database, Schema};
+use serde_json::{json, Value};
+
+use t3_webview_api::app_state::T3AppState;
+use t3_webview_api::audit::{self, store, AuditContext, AuditFilter, Origin};
+use t3_webview_api::auth;
+use t3_webview_api::entity::t3_device::{devices, input_points};
+
+#[path = "../mcp/common.rs"]
+mod common;
+use common::send_text;
+
+async fn state() -> T3AppState {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    let backend = db.get_database_backend();
+    let schema = Schema::new(backend);
+    db.execute(backend.build(&schema.create_table_from_entity(devices::Entity))).await.unwrap();
+    db.execute(backend.build(&schema.create_table_from_entity(input_points::Entity))).await.unwrap();
+    db.execute_unprepared(
+        "INSERT INTO DEVICES (SerialNumber, PanelId, Panel_Number) VALUES (4001, 7, 7); \
+         INSERT INTO INPUTS (SerialNumber, Input_Index, Panel, Label, Full_Label, fValue, Range_Field, Auto_Manual) \
+         VALUES (4001, '2', '7', 'SAT', 'Supply Air Temp', '55000', '4', '0'); \
+         CREATE TABLE NETWORK_SETTINGS (SerialNumber INTEGER PRIMARY KEY, IP_Address TEXT, Subnet TEXT, \
+             Gateway TEXT, MAC_Address TEXT, TCP_Type INTEGER, created_at TEXT, updated_at TEXT); \
+         INSERT INTO NETWORK_SETTINGS (SerialNumber, IP_Address, TCP_Type) VALUES (4001, '10.0.0.7', 1);",
+    )
+    .await
+    .unwrap();
+    common::app_state(&db)
+}
+
+/// Input update routes + audit and auth routes, layered like `create_t3_app`.
+fn app(state: T3AppState) -> Router {
+    Router::new()
+        .nest(
+            "/api/t3_device",
+            t3_webview_api::t3_device::input_update_routes::create_input_update_routes()
+                .merge(t3_webview_api::t3_device::input_batch_routes::create_input_batch_routes()),
+        )
+        .merge(auth::routes::create_auth_routes())
+        .merge(audit::routes::create_audit_routes())
+        .with_state(state.clone())
+        .layer(middleware::from_fn_with_state(state.clone(), audit::middleware::audit_changes))
+        .layer(middleware::from_fn_with_state(state, auth::enforce_roles))
+}
+
+#[tokio::test]
+async fn test_rest_point_write_is_audited_with_actor_and_old_value() {
+    let state = state().await;
+    let app = app(state.clone());
+    send_text(&app, "POST", "/api/auth/bootstrap", None, json!({ "username": "admin", "password": "admin-pass" })).await;
+    let (_, login) = send_text(&app, "POST", "/api/auth/login", None, json!({ "username": "admin", "password": "admin-pass" })).await;
+    let token = serde_json::from_str::<Value>(&login).unwrap()["token"].as_str().unwrap().to_string();
+
+    let (status, body) = send_text(&app, "PUT", "/api/t3_device/inputs/4001/2/db", Some(&token),
+        json!({ "value": 60.5, "label": "SAT2" })).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    // Reads and failed lookups: the read isn't recorded, the failure is.
+    send_text(&app, "GET", "/api/t3_device/inputs/4001/2", Some(&token), Value::Null).await;
+    send_text(&app, "PUT", "/api/t3_device/inputs/4001/9/db", Some(&token), json!({ "value": 1.0 })).await;
+
+    let (status, body) = send_text(&app, "GET", "/api/audit?serialNumber=4001", Some(&token), Value::Null).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let body: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["total"], 2);
+    let ok = &body["entries"][1];
+    assert_eq!(ok["actor"], "admin");
+    assert_eq!(ok["origin"], "ui");
+    assert_eq!(ok["action"], "PUT /api/t3_device/inputs/4001/2/db");
+    assert_eq!(ok["point"], "INPUT 3");
+    assert_eq!(ok["oldValue"]["label"], "SAT");
+    assert_eq!(ok["oldValue"]["value"], 55.0);
+    assert_eq!(ok["newValue"]["value"], 60.5);
+    assert_eq!(ok["result"], "ok");
+    assert_eq!(body["entries"][0]["result"], "error");
+
+    // Account changes are recorded with the password redacted.
+    send_text(&app, "POST", "/api/auth/users", Some(&token),
+        json!({ "username": "erin", "password": "erin-secret", "role": "engineer" })).await;
+    let (_, body) = send_text(&app, "GET", "/api/audit?action=/api/auth/users", Some(&token), Value::Null).await;
+    let body: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["entries"][0]["newValue"]["password"], "***");
+    assert_eq!(body["entries"][0]["newValue"]["username"], "erin");
+
+    // Viewers can't read the audit trail.
+    send_text(&app, "POST", "/api/auth/users", Some(&token),
+        json!({ "username": "vera", "password": "vera-pass", "role": "viewer" })).await;
+    let (_, login) = send_text(&app, "POST", "/api/auth/login", None, json!({ "username": "vera", "password": "vera-pass" })).await;
+    let viewer = serde_json::from_str::<Value>(&login).unwrap()["token"].as_str().unwrap().to_string();
+    assert_eq!(send_text(&app, "GET", "/api/audit", Some(&viewer), Value::Null).await.0, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_batch_saves_record_skipped_points_and_oversized_bodies_are_refused() {
+    let state = state().await;
+    let app = app(state.clone());
+    send_text(&app, "POST", "/api/auth/bootstrap", None, json!({ "username": "admin", "password": "admin-pass" })).await;
+    let (_, login) = send_text(&app, "POST", "/api/auth/login", None, json!({ "username": "admin", "password": "admin-pass" })).await;
+    let token = serde_json::from_str::<Value>(&login).unwrap()["token"].as_str().unwrap().to_string();
+
+    let batch = json!({ "inputs": [{ "inputIndex": "2", "label": "SAT3" }, { "inputIndex": "x", "label": "??" }] });
+    let (status, body) = send_text(&app, "POST", "/api/t3_device/inputs/4001/batch_save", Some(&token), batch).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let (_, body) = send_text(&app, "GET", "/api/audit?serialNumber=4001", Some(&token), Value::Null).await;
+    let body: Value = serde_json::from_str(&body).unwrap();
+    let entry = &body["entries"][0];
+    assert_eq!(entry["point"], "INPUT batch");
+    assert_eq!(entry["oldValue"]["INPUT 3"]["label"], "SAT");
+    assert_eq!(entry["result"], "error");
+    assert!(entry["detail"].as_str().unwrap().contains("1 point(s) skipped"), "{}", entry["detail"]);
+
+    // Bodies past the JSON limit aren't buffered for the record.
+    let label = "x".repeat(3 * 1024 * 1024);
+    let (status, _) = send_text(&app, "PUT", "/api/t3_device/inputs/4001/2/db", Some(&token), json!({ "label": label })).await;
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn test_mcp_writes_are_audited_per_session() {
+    let state = state().await;
+    let db = state.conn.lock().await.clone();
+    let ctx = AuditContext { actor: Some("erin".into()), origin: Origin::Ui, session_id: None }
+        .via(Origin::Mcp, "mcp-session-1");
+
+    let settings = json!({ "serial_number": 4001, "category": "network", "confirm": true,
+                           "fields": { "IP_Address": "10.0.0.8" } });
+    audit::scope(ctx.clone(), t3_webview_api::mcp::execute_tool("t3000_settings_write", &settings, &db))
+        .await
+        .unwrap();
+    // Validation failures are recorded too.
+    let unconfirmed = json!({ "serial_number": 4001, "category": "network", "fields": { "IP_Address": "x" } });
+    assert!(audit::scope(ctx.clone(), t3_webview_api::mcp::execute_tool("t3000_settings_write", &unconfirmed, &db))
+        .await
+        .is_err());
+    // Reads aren't.
+    audit::scope(ctx, t3_webview_api::mcp::execute_tool("t3000_settings_read", &json!({ "serial_number": 4001 }), &db))
+        .await
+        .ok();
+
+    let filter = AuditFilter { session_id: Some("mcp-session-1".into()), ..Default::default() };
+    let (entries, total) = store::query(&db, &filter).await.unwrap();
+    assert_eq!(total, 2);
+    let ok = &entries[1];
+    assert_eq!((ok.actor.as_deref(), ok.origin.as_str()), (Some("erin"), "mcp"));
+    assert_eq!(ok.action, "t3000_settings_write");
+    assert_eq!(ok.point.as_deref(), Some("settings:network"));
+    assert_eq!(ok.old_value, Some(json!({ "IP_Address": "10.0.0.7" })));
+    assert_eq!(ok.new_value, Some(json!({ "IP_Address": "10.0.0.8" })));
+    assert_eq!(entries[0].result, "error");
+    assert!(entries[0].detail.as_deref().unwrap().contains("confirm"));
+
+    let filter = AuditFilter { origin: Some("ui".into()), ..Default::default() };
+    assert_eq!(store::query(&db, &filter).await.unwrap().1, 0);
+}
+
+#[tokio::test]
+async fn test_mcp_batch_points_without_a_scalar_value_are_recorded_as_skipped() {
+    let state = state().await;
+    let db = state.conn.lock().await.clone();
+    let ctx = AuditContext { actor: Some("erin".into()), origin: Origin::Ui, session_id: None }
+        .via(Origin::Mcp, "mcp-session-2");
+
+    let batch = json!({ "confirm": true, "points": [
+        { "serial_number": 4001, "point_type": "INPUT", "point_index": 3, "value": null },
+        { "serial_number": 4001, "point_type": "INPUT", "point_index": 3, "field": "label", "value": { "text": "SAT" } },
+    ] });
+    let result = audit::scope(ctx, t3_webview_api::mcp::execute_tool("t3000_point_write_batch", &batch, &db))
+        .await
+        .unwrap();
+    let result: Value = serde_json::from_str(&result).unwrap();
+    assert_eq!((result["success"].clone(), result["count"].clone()), (json!(false), json!(0)));
+
+    let filter = AuditFilter { session_id: Some("mcp-session-2".into()), ..Default::default() };
+    let (entries, total) = store::query(&db, &filter).await.unwrap();
+    assert_eq!(total, 2);
+    for entry in &entries {
+        assert_eq!(entry.result, "error");
+        assert!(entry.detail.as_deref().unwrap().contains("skipped"), "{:?}", entry.detail);
+    }
+}
+
+#[tokio::test]
+async fn test_export_csv_and_json() {
+    let state = state().await;
+    let app = app(state.clone());
+    send_text(&app, "PUT", "/api/t3_device/inputs/4001/2/db", None, json!({ "label": "A, \"quoted\"" })).await;
+
+    let (status, csv) = send_text(&app, "GET", "/api/audit/export?format=csv&serialNumber=4001", None, Value::Null).await;
+    assert_eq!(status, StatusCode::OK);
+    let mut lines = csv.lines();
+    assert!(lines.next().unwrap().starts_with("id,timestamp,actor,origin"));
+    let row = lines.next().unwrap();
+    assert!(row.contains(",ui,"), "{}", row);
+    assert!(row.contains("\"{\"\"label\"\":\"\"A, \\\"\"quoted\\\"\"\"\"}\""), "{}", row);
+
+    let (_, json_body) = send_text(&app, "GET", "/api/audit/export?format=json", None, Value::Null).await;
+    assert_eq!(serde_json::from_str::<Value>(&json_body).unwrap().as_array().unwrap().len(), 1);
+    assert_eq!(send_text(&app, "GET", "/api/audit/export?format=xml", None, Value::Null).await.0, StatusCode::BAD_REQUEST);
+}
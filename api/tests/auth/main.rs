@@ -0,0 +1,216 @@
+//! Accounts, sessions and role enforcement — bootstrap, login/logout, the
+//! per-route role table and the last-admin guards.
+
+use axum::{
+    http::{Method, StatusCode},
+    middleware,
+    routing::{get, post, put},
+    Router,
+};
+use sea_orm::{ConnectionTrait, Database};
+use serde_json::{json, Value};
+
+use t3_webview_api::app_state::T3AppState;
+use t3_webview_api::auth::{self, required_access, store, Access, Role};
+
+#[path = "../mcp/common.rs"]
+mod common;
+use common::send_as;
+
+async fn state() -> T3AppState {
+    common::app_state(&Database::connect("sqlite::memory:").await.unwrap())
+}
+
+/// Auth routes plus stand-ins for a few protected routes, behind the role
+/// middleware the way `create_t3_app` layers it.
+fn app(state: T3AppState) -> Router {
+    let ok = || async { "ok" };
+    Router::new()
+        .merge(auth::routes::create_auth_routes())
+        .route("/api/t3_device/devices", get(ok))
+        .route("/api/t3_device/inputs/:serial/:index", put(ok))
+        .route("/api/t3_device/inputs/:serial/refresh", post(ok))
+        .route("/api/develop/database/query", post(ok))
+        .route("/api/database/backend/config", post(ok))
+        .route("/api/health", get(ok))
+        .with_state(state.clone())
+        .layer(middleware::from_fn_with_state(state, auth::enforce_roles))
+}
+
+async fn login(app: &Router, username: &str, password: &str) -> String {
+    let (status, body) = send_as(app, "POST", "/api/auth/login", None,
+        json!({ "username": username, "password": password })).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    body["token"].as_str().unwrap().to_string()
+}
+
+#[test]
+fn test_policy_table() {
+    let put = Method::PUT;
+    let post = Method::POST;
+    let get = Method::GET;
+    assert_eq!(required_access(&get, "/api/health"), Access::Public);
+    assert_eq!(required_access(&get, "/index.html"), Access::Public);
+    assert_eq!(required_access(&post, "/api/auth/login"), Access::Public);
+    assert_eq!(required_access(&get, "/api/t3_device/devices"), Access::Role(Role::Viewer));
+    assert_eq!(required_access(&put, "/api/t3_device/inputs/4001/0"), Access::Role(Role::Engineer));
+    assert_eq!(required_access(&post, "/api/t3_device/inputs/4001/refresh"), Access::Role(Role::Operator));
+    assert_eq!(required_access(&post, "/api/develop/database/query"), Access::Role(Role::Engineer));
+    assert_eq!(required_access(&post, "/api/database/backend/config"), Access::Role(Role::Admin));
+    assert_eq!(required_access(&get, "/api/database/backend/status"), Access::Role(Role::Viewer));
+    assert_eq!(required_access(&get, "/api/auth/users"), Access::Role(Role::Admin));
+    // History reads that are POSTs under the admin-only /api/database
+    assert_eq!(required_access(&post, "/api/database/trendlog/query"), Access::Role(Role::Viewer));
+    assert_eq!(required_access(&post, "/api/database/trendlog/export"), Access::Role(Role::Viewer));
+    // Whole segments only: "/api/developer" is not under "/api/develop".
+    assert_eq!(required_access(&get, "/api/developer"), Access::Role(Role::Viewer));
+}
+
+#[tokio::test]
+async fn test_open_until_bootstrap_then_sessions_required() {
+    let app = app(state().await);
+
+    // No accounts: everything passes, and /me says sign-in isn't required.
+    assert_eq!(send_as(&app, "PUT", "/api/t3_device/inputs/4001/0", None, json!({})).await.0, StatusCode::OK);
+    let (_, me) = send_as(&app, "GET", "/api/auth/me", None, Value::Null).await;
+    assert_eq!(me["authRequired"], false);
+
+    // The first account must come from bootstrap, and only once.
+    let (status, _) = send_as(&app, "POST", "/api/auth/users", None,
+        json!({ "username": "eve", "password": "password1", "role": "viewer" })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = send_as(&app, "POST", "/api/auth/bootstrap", None,
+        json!({ "username": "admin", "password": "short" })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, admin) = send_as(&app, "POST", "/api/auth/bootstrap", None,
+        json!({ "username": "admin", "password": "correct horse" })).await;
+    assert_eq!(status, StatusCode::OK, "{}", admin);
+    assert_eq!(admin["role"], "admin");
+    let (status, _) = send_as(&app, "POST", "/api/auth/bootstrap", None,
+        json!({ "username": "mallory", "password": "correct horse" })).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    // Now sign-in is required.
+    assert_eq!(send_as(&app, "GET", "/api/t3_device/devices", None, Value::Null).await.0, StatusCode::UNAUTHORIZED);
+    assert_eq!(send_as(&app, "GET", "/api/health", None, Value::Null).await.0, StatusCode::OK);
+    let (status, _) = send_as(&app, "POST", "/api/auth/login", None,
+        json!({ "username": "admin", "password": "wrong password" })).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let token = login(&app, "ADMIN", "correct horse").await;
+    let (_, me) = send_as(&app, "GET", "/api/auth/me", Some(&token), Value::Null).await;
+    assert_eq!((me["authRequired"].clone(), me["user"]["username"].clone()), (json!(true), json!("admin")));
+    assert_eq!(send_as(&app, "GET", "/api/t3_device/devices", Some(&token), Value::Null).await.0, StatusCode::OK);
+
+    send_as(&app, "POST", "/api/auth/logout", Some(&token), Value::Null).await;
+    assert_eq!(send_as(&app, "GET", "/api/t3_device/devices", Some(&token), Value::Null).await.0, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_concurrent_bootstraps_create_one_admin() {
+    let app = app(state().await);
+    let bootstrap = |username: &'static str| send_as(&app, "POST", "/api/auth/bootstrap", None,
+        json!({ "username": username, "password": "correct horse" }));
+    let ((a, _), (b, _)) = tokio::join!(bootstrap("alice"), bootstrap("bob"));
+    let mut statuses = [a, b];
+    statuses.sort();
+    assert_eq!(statuses, [StatusCode::OK, StatusCode::CONFLICT]);
+}
+
+#[tokio::test]
+async fn test_roles_enforced_per_route_group() {
+    let state = state().await;
+    let app = app(state.clone());
+    send_as(&app, "POST", "/api/auth/bootstrap", None, json!({ "username": "admin", "password": "admin-pass" })).await;
+    let admin = login(&app, "admin", "admin-pass").await;
+    for (name, role) in [("vera", "viewer"), ("otto", "operator"), ("erin", "engineer")] {
+        let (status, body) = send_as(&app, "POST", "/api/auth/users", Some(&admin),
+            json!({ "username": name, "password": format!("{}-pass", name), "role": role })).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+    }
+    let viewer = login(&app, "vera", "vera-pass").await;
+    let operator = login(&app, "otto", "otto-pass").await;
+    let engineer = login(&app, "erin", "erin-pass").await;
+
+    let cases: [(&str, &str, [StatusCode; 4]); 5] = [
+        ("GET", "/api/t3_device/devices", [StatusCode::OK; 4]),
+        ("POST", "/api/t3_device/inputs/4001/refresh",
+            [StatusCode::FORBIDDEN, StatusCode::OK, StatusCode::OK, StatusCode::OK]),
+        ("PUT", "/api/t3_device/inputs/4001/0",
+            [StatusCode::FORBIDDEN, StatusCode::FORBIDDEN, StatusCode::OK, StatusCode::OK]),
+        ("POST", "/api/develop/database/query",
+            [StatusCode::FORBIDDEN, StatusCode::FORBIDDEN, StatusCode::OK, StatusCode::OK]),
+        ("POST", "/api/database/backend/config",
+            [StatusCode::FORBIDDEN, StatusCode::FORBIDDEN, StatusCode::FORBIDDEN, StatusCode::OK]),
+    ];
+    for (method, uri, expected) in cases {
+        for (token, want) in [&viewer, &operator, &engineer, &admin].into_iter().zip(expected) {
+            assert_eq!(send_as(&app, method, uri, Some(token), json!({})).await.0, want, "{} {}", method, uri);
+        }
+    }
+    assert_eq!(send_as(&app, "GET", "/api/auth/users", Some(&engineer), Value::Null).await.0, StatusCode::FORBIDDEN);
+
+    // Disabling a user ends their sessions; the last admin can't be removed.
+    let (_, users) = send_as(&app, "GET", "/api/auth/users", Some(&admin), Value::Null).await;
+    let id_of = |name: &str| users["users"].as_array().unwrap().iter()
+        .find(|u| u["username"] == name).unwrap()["id"].as_i64().unwrap();
+    let (status, _) = send_as(&app, "PUT", &format!("/api/auth/users/{}", id_of("erin")), Some(&admin),
+        json!({ "enabled": false })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(send_as(&app, "GET", "/api/t3_device/devices", Some(&engineer), Value::Null).await.0, StatusCode::UNAUTHORIZED);
+    // A rejected update changes nothing, not even the fields that were valid.
+    let (status, _) = send_as(&app, "PUT", &format!("/api/auth/users/{}", id_of("otto")), Some(&admin),
+        json!({ "role": "viewer", "enabled": false, "password": "x" })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (_, users) = send_as(&app, "GET", "/api/auth/users", Some(&admin), Value::Null).await;
+    let otto = users["users"].as_array().unwrap().iter().find(|u| u["username"] == "otto").unwrap();
+    assert_eq!((otto["role"].clone(), otto["enabled"].clone()), (json!("operator"), json!(true)));
+    assert_eq!(send_as(&app, "POST", "/api/t3_device/inputs/4001/refresh", Some(&operator), json!({})).await.0, StatusCode::OK);
+    let admin_path = format!("/api/auth/users/{}", id_of("admin"));
+    assert_eq!(send_as(&app, "DELETE", &admin_path, Some(&admin), Value::Null).await.0, StatusCode::BAD_REQUEST);
+    assert_eq!(send_as(&app, "PUT", &admin_path, Some(&admin), json!({ "role": "viewer" })).await.0, StatusCode::BAD_REQUEST);
+
+    // Expired sessions are rejected.
+    let db = state.conn.lock().await.clone();
+    db.execute_unprepared("UPDATE APP_SESSIONS SET expires_at = 0").await.unwrap();
+    assert_eq!(send_as(&app, "GET", "/api/t3_device/devices", Some(&viewer), Value::Null).await.0, StatusCode::UNAUTHORIZED);
+    assert!(store::has_users(&db).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_database_query_is_read_only_below_admin() {
+    let dir = std::env::temp_dir().join(format!("t3-auth-query-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    rusqlite::Connection::open(dir.join("site.db"))
+        .unwrap()
+        .execute_batch("CREATE TABLE IF NOT EXISTS APP_USERS (username TEXT, role TEXT); \
+                        DELETE FROM APP_USERS; INSERT INTO APP_USERS VALUES ('erin', 'engineer');")
+        .unwrap();
+    std::env::set_var("T3000_DATABASE_PATH", &dir);
+
+    let state = state().await;
+    let app = Router::new()
+        .merge(auth::routes::create_auth_routes())
+        .nest("/api/develop", t3_webview_api::t3_develop::create_develop_routes())
+        .with_state(state.clone())
+        .layer(middleware::from_fn_with_state(state, auth::enforce_roles));
+    send_as(&app, "POST", "/api/auth/bootstrap", None, json!({ "username": "admin", "password": "admin-pass" })).await;
+    let admin = login(&app, "admin", "admin-pass").await;
+    send_as(&app, "POST", "/api/auth/users", Some(&admin),
+        json!({ "username": "erin", "password": "erin-pass", "role": "engineer" })).await;
+    let engineer = login(&app, "erin", "erin-pass").await;
+
+    let query = |sql: &str| json!({ "database": "site.db", "query": sql });
+    let escalate = query("UPDATE APP_USERS SET role = 'admin'");
+    let (status, body) = send_as(&app, "POST", "/api/develop/database/query", Some(&engineer), query("SELECT role FROM APP_USERS")).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["rows"][0][0], "engineer");
+    assert_eq!(send_as(&app, "POST", "/api/develop/database/query", Some(&engineer), escalate.clone()).await.0, StatusCode::FORBIDDEN);
+    let (_, body) = send_as(&app, "POST", "/api/develop/database/query", Some(&engineer), query("SELECT role FROM APP_USERS")).await;
+    assert_eq!(body["rows"][0][0], "engineer");
+
+    assert_eq!(send_as(&app, "POST", "/api/develop/database/query", Some(&admin), escalate).await.0, StatusCode::OK);
+    let (_, body) = send_as(&app, "POST", "/api/develop/database/query", Some(&admin), query("SELECT role FROM APP_USERS")).await;
+    assert_eq!(body["rows"][0][0], "admin");
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,295 @@
+//! BACnet/IP client tests against the in-process simulated device
+//! (`bacnet::sim`) on 127.0.0.1.
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use sea_orm::{ActiveModelTrait, ConnectionTrait, Database, Schema, Set};
+use t3_webview_api::bacnet::client::{BacnetClient, BacnetError};
+use t3_webview_api::bacnet::codec::{
+    error_code, object_type, property, ObjectId, PropertyRef, ReadAccessSpec, Value,
+};
+use t3_webview_api::bacnet::points::{self, BacnetTarget, PointKind, PointWrite};
+use t3_webview_api::bacnet::sim::{SimDevice, SimHandle};
+use t3_webview_api::entity::t3_device::devices;
+
+#[path = "../mcp/common.rs"]
+mod common;
+
+const DEVICE_INSTANCE: u32 = 260_001;
+
+fn localhost() -> SocketAddr {
+    "127.0.0.1:0".parse().unwrap()
+}
+
+async fn sim() -> SimHandle {
+    SimDevice::new(DEVICE_INSTANCE, "T3-BB Sim")
+        .with_analog(object_type::ANALOG_INPUT, 1, "SAT", "Supply Air Temp", 21.5)
+        .with_analog(object_type::ANALOG_INPUT, 2, "RAT", "Return Air Temp", 23.0)
+        .with_analog(object_type::ANALOG_OUTPUT, 1, "VLV", "Cooling Valve", 0.0)
+        .with_analog(object_type::ANALOG_VALUE, 1, "SP", "Cooling Setpoint", 22.0)
+        .spawn(localhost())
+        .await
+        .unwrap()
+}
+
+async fn client() -> BacnetClient {
+    BacnetClient::bind(localhost())
+        .await
+        .unwrap()
+        .with_timeout(Duration::from_millis(500), 1)
+}
+
+#[tokio::test]
+async fn test_who_is_returns_i_am() {
+    let device = sim().await;
+    let client = client().await;
+
+    let found = client.who_is(device.addr, None, Duration::from_millis(300)).await.unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].0, device.addr);
+    assert_eq!(found[0].1.device, ObjectId::new(object_type::DEVICE, DEVICE_INSTANCE));
+
+    // Out-of-range Who-Is gets no answer.
+    let none = client
+        .who_is(device.addr, Some((1, 10)), Duration::from_millis(200))
+        .await
+        .unwrap();
+    assert!(none.is_empty());
+}
+
+#[tokio::test]
+async fn test_read_property_and_errors() {
+    let device = sim().await;
+    let client = client().await;
+    let ai1 = ObjectId::new(object_type::ANALOG_INPUT, 1);
+
+    let pv = client
+        .read_property(device.addr, ai1, PropertyRef::new(property::PRESENT_VALUE))
+        .await
+        .unwrap();
+    assert_eq!(pv, vec![Value::Real(21.5)]);
+
+    let count = client
+        .read_property(
+            device.addr,
+            ObjectId::new(object_type::DEVICE, DEVICE_INSTANCE),
+            PropertyRef::at(property::OBJECT_LIST, 0),
+        )
+        .await
+        .unwrap();
+    assert_eq!(count, vec![Value::Unsigned(5)]);
+
+    let missing = client
+        .read_property(
+            device.addr,
+            ObjectId::new(object_type::ANALOG_INPUT, 99),
+            PropertyRef::new(property::PRESENT_VALUE),
+        )
+        .await;
+    match missing {
+        Err(BacnetError::Device(e)) => assert_eq!(e.code, error_code::UNKNOWN_OBJECT),
+        other => panic!("expected unknown-object error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_read_property_multiple() {
+    let device = sim().await;
+    let client = client().await;
+
+    let specs = vec![
+        ReadAccessSpec {
+            object: ObjectId::new(object_type::ANALOG_INPUT, 2),
+            properties: vec![PropertyRef::new(property::OBJECT_NAME), PropertyRef::new(property::PRESENT_VALUE)],
+        },
+        ReadAccessSpec {
+            object: ObjectId::new(object_type::ANALOG_VALUE, 1),
+            properties: vec![PropertyRef::new(property::PRESENT_VALUE), PropertyRef::new(property::VENDOR_IDENTIFIER)],
+        },
+    ];
+    let results = client.read_property_multiple(device.addr, &specs).await.unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].value(property::OBJECT_NAME), Some(&Value::CharacterString("RAT".into())));
+    assert_eq!(results[0].value(property::PRESENT_VALUE), Some(&Value::Real(23.0)));
+    assert_eq!(results[1].value(property::PRESENT_VALUE), Some(&Value::Real(22.0)));
+    // Per-property errors come back inside the ACK, not as a failed request.
+    assert_eq!(
+        results[1].results[1].value.as_ref().unwrap_err().code,
+        error_code::UNKNOWN_PROPERTY
+    );
+}
+
+#[tokio::test]
+async fn test_write_property_respects_priority() {
+    let device = sim().await;
+    let client = client().await;
+    let ao1 = ObjectId::new(object_type::ANALOG_OUTPUT, 1);
+    let pv = PropertyRef::new(property::PRESENT_VALUE);
+
+    client.write_property(device.addr, ao1, pv, Value::Real(40.0), Some(8)).await.unwrap();
+    client.write_property(device.addr, ao1, pv, Value::Real(75.0), Some(12)).await.unwrap();
+    // Priority 8 outranks 12.
+    assert_eq!(device.get(ao1, pv), Some(Value::Real(40.0)));
+
+    // Relinquishing 8 hands control to 12.
+    client.write_property(device.addr, ao1, pv, Value::Null, Some(8)).await.unwrap();
+    assert_eq!(device.get(ao1, pv), Some(Value::Real(75.0)));
+    assert_eq!(device.get(ao1, PropertyRef::at(property::PRIORITY_ARRAY, 12)), Some(Value::Real(75.0)));
+
+    assert!(matches!(
+        client.write_property(device.addr, ao1, pv, Value::Real(1.0), Some(17)).await,
+        Err(BacnetError::Protocol(_))
+    ));
+
+    // Inputs only accept a present-value write while out of service.
+    let ai1 = ObjectId::new(object_type::ANALOG_INPUT, 1);
+    match client.write_property(device.addr, ai1, pv, Value::Real(5.0), None).await {
+        Err(BacnetError::Device(e)) => assert_eq!(e.code, error_code::WRITE_ACCESS_DENIED),
+        other => panic!("expected write-access-denied, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_request_times_out_without_device() {
+    let client = client().await;
+    // A bound socket that never answers.
+    let silent = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+    let addr = silent.local_addr().unwrap();
+    let result = client
+        .read_property(
+            addr,
+            ObjectId::new(object_type::ANALOG_INPUT, 1),
+            PropertyRef::new(property::PRESENT_VALUE),
+        )
+        .await;
+    assert!(matches!(result, Err(BacnetError::Timeout(a)) if a == addr));
+}
+
+#[tokio::test]
+async fn test_point_items_match_webview_list_shape() {
+    let device = sim().await;
+    let client = client().await;
+    // No instance known: the device is discovered with Who-Is.
+    let target = BacnetTarget { addr: device.addr, device_instance: None };
+
+    let inputs = points::read_points(&client, &target, PointKind::Input, None).await.unwrap();
+    assert_eq!(inputs.len(), 2);
+    assert_eq!(inputs[0]["inputIndex"], 0);
+    assert_eq!(inputs[0]["label"], "SAT");
+    assert_eq!(inputs[0]["fullLabel"], "Supply Air Temp");
+    assert_eq!(inputs[0]["value"], 21.5);
+    assert_eq!(inputs[0]["autoManual"], 0);
+
+    let one = points::read_points(&client, &target, PointKind::Variable, Some(0)).await.unwrap();
+    assert_eq!(one.len(), 1);
+    assert_eq!(one[0]["variableIndex"], 0);
+    assert_eq!(one[0]["value"], 22.0);
+
+    assert!(points::read_points(&client, &target, PointKind::Output, Some(5)).await.is_err());
+}
+
+#[tokio::test]
+async fn test_write_point_switches_input_to_manual_first() {
+    let device = sim().await;
+    let client = client().await;
+    let target = BacnetTarget { addr: device.addr, device_instance: Some(DEVICE_INSTANCE) };
+
+    let write = PointWrite {
+        value: Some(30.5),
+        label: Some("SAT2".into()),
+        auto_manual: Some(1),
+        ..Default::default()
+    };
+    points::write_point(&client, &target, PointKind::Input, 0, &write).await.unwrap();
+
+    let ai1 = ObjectId::new(object_type::ANALOG_INPUT, 1);
+    assert_eq!(device.get(ai1, PropertyRef::new(property::OUT_OF_SERVICE)), Some(Value::Boolean(true)));
+    assert_eq!(device.get(ai1, PropertyRef::new(property::PRESENT_VALUE)), Some(Value::Real(30.5)));
+    assert_eq!(device.get(ai1, PropertyRef::new(property::OBJECT_NAME)), Some(Value::CharacterString("SAT2".into())));
+
+    // Outputs are commanded at the requested priority.
+    let write = PointWrite { value: Some(60.0), priority: Some(5), ..Default::default() };
+    points::write_point(&client, &target, PointKind::Output, 0, &write).await.unwrap();
+    let ao1 = ObjectId::new(object_type::ANALOG_OUTPUT, 1);
+    assert_eq!(device.get(ao1, PropertyRef::at(property::PRIORITY_ARRAY, 5)), Some(Value::Real(60.0)));
+}
+
+#[tokio::test]
+async fn test_refresh_resolves_device_address_from_database() {
+    let device = sim().await;
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    let backend = db.get_database_backend();
+    db.execute(backend.build(&Schema::new(backend).create_table_from_entity(devices::Entity)))
+        .await
+        .unwrap();
+    devices::ActiveModel {
+        serial_number: Set(4242),
+        ip_address: Set(Some("127.0.0.1".into())),
+        bacnet_ip_port: Set(Some(device.addr.port())),
+        object_instance: Set(Some(DEVICE_INSTANCE as i32)),
+        ..Default::default()
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+
+    let outputs = points::refresh(&db, 4242, PointKind::Output, None).await.unwrap();
+    assert_eq!(outputs.len(), 1);
+    assert_eq!(outputs[0]["outputIndex"], 0);
+    assert_eq!(outputs[0]["label"], "VLV");
+
+    let err = points::refresh(&db, 1, PointKind::Output, None).await.unwrap_err();
+    assert!(err.contains("not found"), "{}", err);
+}
+
+#[test]
+fn test_transport_defaults_to_ffi_unless_bacnet_is_requested() {
+    use t3_webview_api::bacnet::points::Transport;
+
+    assert_eq!(Transport::resolve(None), Ok(Transport::Ffi));
+    assert_eq!(Transport::resolve(Some(" ")), Ok(Transport::Ffi));
+    assert_eq!(Transport::resolve(Some("BACnet")), Ok(Transport::Bacnet));
+    assert!(Transport::resolve(Some("modbus")).is_err());
+}
+
+#[test]
+fn test_truncated_apdus_are_decode_errors() {
+    use t3_webview_api::bacnet::codec::decode_apdu;
+
+    // Error PDUs shorter than their 3-byte header used to panic on slicing
+    for datagram in [&[][..], &[0x50], &[0x50, 0x01], &[0x50, 0x01, 0x0c], &[0x00, 0x05, 0x01]] {
+        assert!(decode_apdu(datagram).is_err(), "{:02x?}", datagram);
+    }
+}
+
+#[tokio::test]
+async fn test_update_routes_reject_fields_bacnet_cannot_write() {
+    use axum::extract::{Json, Path, State};
+    use axum::http::StatusCode;
+    use t3_webview_api::t3_device::{input_update_routes, output_update_routes};
+
+    let state = common::app_state(&Database::connect("sqlite::memory:").await.unwrap());
+
+    let payload = serde_json::json!({ "transport": "bacnet", "value": 1.0, "range": 3, "calibrationH": 2 });
+    let (status, message) = input_update_routes::update_input_full(
+        State(state.clone()),
+        Path((4242, "0".to_string())),
+        Json(serde_json::from_value(payload).unwrap()),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(message.contains("range, calibrationH"), "{}", message);
+
+    let payload = serde_json::json!({ "transport": "bacnet", "highVoltage": 10.0 });
+    let (status, message) = output_update_routes::update_output_full(
+        State(state),
+        Path((4242, "0".to_string())),
+        Json(serde_json::from_value(payload).unwrap()),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(message.contains("highVoltage"), "{}", message);
+}
@@ -0,0 +1,487 @@
+//! Tests for the FDD REST API (`/api/fdd/*`).
+//!
+//! Runs the real router against an in-memory SQLite database, so no runtime
+//! DB or T3000 install is needed.
+
+use axum::{http::StatusCode, Router};
+use sea_orm::ConnectionTrait;
+use serde_json::{json, Value};
+
+#[path = "../mcp/common.rs"]
+mod common;
+use common::send;
+
+async fn test_app() -> (Router, sea_orm::DatabaseConnection) {
+    let db = sea_orm::Database::connect("sqlite::memory:").await.unwrap();
+    let app = t3_webview_api::fdd::routes::create_fdd_routes().with_state(common::app_state(&db));
+    (app, db)
+}
+
+#[tokio::test]
+async fn test_rules_list_returns_seeded_catalog() {
+    let (app, _db) = test_app().await;
+    let (status, body) = send(&app, "GET", "/api/fdd/rules", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["total"].as_u64().unwrap() >= 16);
+
+    let (status, body) = send(&app, "GET", "/api/fdd/rules?category=chw", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["rules"].as_array().unwrap().iter().all(|r| r["category"] == "chw"));
+}
+
+#[tokio::test]
+async fn test_rule_crud_lifecycle() {
+    let (app, _db) = test_app().await;
+    let rule = json!({
+        "rule_id": "API-1",
+        "rule_name": "API test rule",
+        "rule_kind": "RangeBand",
+        "required_roles": ["zone_t"],
+        "params": {"field": "zone_t", "lo": 68, "hi": 76}
+    });
+
+    let (status, _) = send(&app, "POST", "/api/fdd/rules", Some(rule.clone())).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, _) = send(&app, "POST", "/api/fdd/rules", Some(rule)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let (status, body) = send(&app, "GET", "/api/fdd/rules/API-1", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["category"], "custom");
+
+    let (status, body) = send(&app, "PUT", "/api/fdd/rules/API-1", Some(json!({"params": {"lo": 70}}))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["rule"]["params"]["lo"], 70);
+    assert_eq!(body["rule"]["params"]["hi"], 76);
+
+    let (status, body) = send(&app, "POST", "/api/fdd/rules/API-1/toggle", Some(json!({"enabled": false}))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["enabled"], false);
+
+    let (status, _) = send(&app, "DELETE", "/api/fdd/rules/API-1", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, "GET", "/api/fdd/rules/API-1", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_create_rejects_unknown_rule_kind() {
+    let (app, _db) = test_app().await;
+    let (status, body) = send(
+        &app,
+        "POST",
+        "/api/fdd/rules",
+        Some(json!({"rule_id": "BAD-1", "rule_kind": "NoSuchKind"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].as_str().unwrap().contains("rule_kind"));
+}
+
+#[tokio::test]
+async fn test_expression_rules_are_validated_on_save() {
+    let (app, _db) = test_app().await;
+    let mut rule = json!({
+        "rule_id": "EXPR-1",
+        "rule_name": "Fan on, SAT off setpoint",
+        "rule_kind": "Expression",
+        "required_roles": ["fan_cmd", "sat"],
+        "params": {"expression": "fan_cmd > 0.05 && abs(sat - sat_sp) > 5"}
+    });
+
+    // sat_sp is used but not declared.
+    let (status, body) = send(&app, "POST", "/api/fdd/rules", Some(rule.clone())).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].as_str().unwrap().contains("sat_sp"));
+
+    rule["required_roles"] = json!(["fan_cmd", "sat", "sat_sp"]);
+    let (status, _) = send(&app, "POST", "/api/fdd/rules", Some(rule)).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, _) = send(
+        &app,
+        "PUT",
+        "/api/fdd/rules/EXPR-1",
+        Some(json!({"params": {"expression": "fan_cmd >"}})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Dropping a role the stored expression still uses is rejected.
+    let (status, body) = send(
+        &app,
+        "PUT",
+        "/api/fdd/rules/EXPR-1",
+        Some(json!({"required_roles": ["fan_cmd", "sat"]})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].as_str().unwrap().contains("sat_sp"));
+
+    // A new role and an expression using it are validated together.
+    let (status, body) = send(
+        &app,
+        "PUT",
+        "/api/fdd/rules/EXPR-1",
+        Some(json!({
+            "required_roles": ["fan_cmd", "sat", "sat_sp", "zone_t"],
+            "params": {"expression": "fan_cmd > 0.05 && abs(sat - sat_sp) > 5 && zone_t > 78"}
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["rule"]["required_roles"], json!(["fan_cmd", "sat", "sat_sp", "zone_t"]));
+}
+
+#[tokio::test]
+async fn test_create_rejects_out_of_range_poll_seconds() {
+    let (app, _db) = test_app().await;
+    for poll in [json!(1), json!(86_400), json!("300")] {
+        let rule = json!({
+            "rule_id": "POLL-1",
+            "rule_kind": "ThresholdAbove",
+            "required_roles": ["sat"],
+            "params": {"field": "sat", "limit": 100, "poll_seconds": poll}
+        });
+        let (status, body) = send(&app, "POST", "/api/fdd/rules", Some(rule)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+        assert!(body["error"].as_str().unwrap().contains("poll_seconds"));
+    }
+}
+
+#[tokio::test]
+async fn test_analyze_rejects_out_of_range_windows() {
+    let (app, _db) = test_app().await;
+    for body in [
+        json!({"serial_number": 1, "range_hours": u64::MAX}),
+        json!({"serial_number": 1, "range_hours": 0}),
+        json!({"serial_number": 1, "range_hours": 48, "bucket_seconds": 1}),
+        json!({"serial_number": 1, "range_hours": 1, "bucket_seconds": 60, "max_staleness_seconds": u64::MAX}),
+        // The seeded rules' own 300 s polls would bucket a full year too finely.
+        json!({"serial_number": 1, "range_hours": 24 * 366}),
+    ] {
+        let (status, resp) = send(&app, "POST", "/api/fdd/analyze", Some(body.clone())).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{} -> {}", body, resp);
+    }
+}
+
+#[tokio::test]
+async fn test_rule_update_db_failure_is_a_server_error() {
+    let (app, db) = test_app().await;
+    send(&app, "GET", "/api/fdd/rules", None).await;
+    db.execute_unprepared(
+        "CREATE TRIGGER fdd_rules_readonly BEFORE UPDATE ON FDD_RULES BEGIN SELECT RAISE(FAIL, 'read-only'); END",
+    )
+    .await
+    .unwrap();
+    let (status, _) = send(&app, "PUT", "/api/fdd/rules/SAT-HIGH", Some(json!({"params": {"limit": 90}}))).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    let (status, _) = send(&app, "PUT", "/api/fdd/rules/SAT-HIGH", Some(json!({"rule_kind": "NoSuchKind"}))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_export_import_round_trip() {
+    let (app, _db) = test_app().await;
+    let (_, exported) = send(&app, "GET", "/api/fdd/rules/export?category=chw", None).await;
+    let mut rules = exported["rules"].as_array().unwrap().clone();
+    assert!(!rules.is_empty());
+    for r in rules.iter_mut() {
+        r["rule_id"] = json!(format!("{}-COPY", r["rule_id"].as_str().unwrap()));
+    }
+    rules.push(json!({"rule_id": "", "rule_kind": "RangeBand"}));
+    rules.push(json!({
+        "rule_id": "EXPR-BAD",
+        "rule_kind": "Expression",
+        "required_roles": ["sat"],
+        "params": {"expression": "sat > sat_sp"}
+    }));
+
+    let (status, body) = send(&app, "POST", "/api/fdd/rules/import", Some(json!({"rules": rules.clone()}))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["skipped"], 2);
+    let rejected = body["rejected"].as_array().unwrap();
+    assert_eq!(rejected[0]["index"], rules.len() - 2);
+    assert_eq!(rejected[1]["rule_id"], "EXPR-BAD");
+    assert!(rejected[1]["error"].as_str().unwrap().contains("sat_sp"), "{}", rejected[1]);
+    let (_, one) = send(&app, "GET", "/api/fdd/rules/CHW-1-COPY", None).await;
+    assert_eq!(one["rule_kind"], "ChwLowDeltaT");
+}
+
+/// Column names of FDD_FINDINGS, in order.
+async fn findings_columns(db: &sea_orm::DatabaseConnection) -> Vec<String> {
+    let rows = db
+        .query_all(sea_orm::Statement::from_string(db.get_database_backend(), "PRAGMA table_info(FDD_FINDINGS)"))
+        .await
+        .unwrap();
+    rows.iter().map(|r| r.try_get::<String>("", "name").unwrap()).collect()
+}
+
+#[tokio::test]
+async fn test_embedded_schema_matches_findings_ddl() {
+    let embedded = sea_orm::Database::connect("sqlite::memory:").await.unwrap();
+    embedded.execute_unprepared(t3_webview_api::db_schema::EMBEDDED_SCHEMA).await.unwrap();
+    let fresh = sea_orm::Database::connect("sqlite::memory:").await.unwrap();
+    t3_webview_api::fdd::rules::ensure_schema(&fresh).await.unwrap();
+    assert_eq!(findings_columns(&embedded).await, findings_columns(&fresh).await);
+}
+
+#[tokio::test]
+async fn test_findings_pagination_and_filters() {
+    let (app, db) = test_app().await;
+    // Touch the API once so the schema exists.
+    send(&app, "GET", "/api/fdd/findings", None).await;
+    for i in 0..7 {
+        let sql = format!(
+            "INSERT INTO FDD_FINDINGS (device_serial, equipment, rule_id, rule_name, severity, fault_hours, evidence, \
+             status, first_seen, last_seen) VALUES ({}, '', '{}', 'r', '{}', 1.0, '{{}}', 'open', \
+             '2026-01-0{} 00:00:00', '2026-01-0{} 12:00:00')",
+            if i % 2 == 0 { 100 } else { 200 },
+            if i < 4 { "SAT-HIGH" } else { "CHW-1" },
+            if i < 2 { "critical" } else { "warning" },
+            i + 1,
+            i + 1
+        );
+        db.execute(sea_orm::Statement::from_string(sea_orm::DatabaseBackend::Sqlite, sql))
+            .await
+            .unwrap();
+    }
+
+    let (_, page1) = send(&app, "GET", "/api/fdd/findings?page=1&page_size=3", None).await;
+    assert_eq!(page1["total"], 7);
+    assert_eq!(page1["total_pages"], 3);
+    assert_eq!(page1["findings"].as_array().unwrap().len(), 3);
+    let (_, page3) = send(&app, "GET", "/api/fdd/findings?page=3&page_size=3", None).await;
+    assert_eq!(page3["findings"].as_array().unwrap().len(), 1);
+
+    let (_, by_device) = send(&app, "GET", "/api/fdd/findings?serial_number=100", None).await;
+    assert_eq!(by_device["total"], 4);
+    let (_, by_rule) = send(&app, "GET", "/api/fdd/findings?rule_id=CHW-1&severity=warning", None).await;
+    assert_eq!(by_rule["total"], 3);
+    let (_, by_sev) = send(&app, "GET", "/api/fdd/findings?severity=critical", None).await;
+    assert_eq!(by_sev["total"], 2);
+    let (_, by_range) = send(
+        &app,
+        "GET",
+        "/api/fdd/findings?from=2026-01-03%2000:00:00&to=2026-01-05%2000:00:00",
+        None,
+    )
+    .await;
+    assert_eq!(by_range["total"], 3);
+}
+
+#[tokio::test]
+async fn test_role_map_shows_inferred_and_overridden_points() {
+    let (app, db) = test_app().await;
+    send(&app, "GET", "/api/fdd/rules", None).await; // creates FDD tables
+    for sql in [
+        "CREATE TABLE HAYSTACK_POINT_TAGS (serial_number INTEGER, point_type TEXT, point_index TEXT, point_id TEXT, tag_name TEXT)",
+        "INSERT INTO HAYSTACK_POINT_TAGS VALUES (42,'INPUT','1','IN1','discharge'),(42,'INPUT','1','IN1','temp')",
+    ] {
+        db.execute(sea_orm::Statement::from_string(sea_orm::DatabaseBackend::Sqlite, sql.to_string()))
+            .await
+            .unwrap();
+    }
+
+    let (status, body) = send(&app, "GET", "/api/fdd/roles/42", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let sat = &body["roles"][0];
+    assert_eq!(sat["role"], "sat");
+    assert_eq!(sat["source"], "inferred");
+    assert_eq!(sat["effective"]["point_id"], "IN1");
+
+    let (status, body) = send(
+        &app,
+        "PUT",
+        "/api/fdd/roles/42/sat",
+        Some(json!({"point_type": "input", "point_index": 3, "note": "tags on IN1 are wrong"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let sat = &body["role_map"]["roles"][0];
+    assert_eq!(sat["source"], "override");
+    assert_eq!(sat["effective"]["point_id"], "IN3");
+    assert_eq!(sat["inferred"]["point_id"], "IN1");
+
+    let (status, _) = send(
+        &app,
+        "PUT",
+        "/api/fdd/roles/42/sat",
+        Some(json!({"point_type": "SCHEDULE", "point_index": 1})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = send(&app, "DELETE", "/api/fdd/roles/42/sat", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, "DELETE", "/api/fdd/roles/42/sat", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (_, body) = send(&app, "GET", "/api/fdd/roles/42", None).await;
+    assert_eq!(body["roles"][0]["source"], "inferred");
+}
+
+#[tokio::test]
+async fn test_analyze_runs_rules_per_equipment() {
+    let (app, db) = test_app().await;
+    send(&app, "GET", "/api/fdd/rules", None).await;
+    let now = chrono::Utc::now();
+    let mut stmts = vec![
+        "CREATE TABLE HAYSTACK_POINT_TAGS (serial_number INTEGER, point_type TEXT, point_index TEXT, point_id TEXT, tag_name TEXT)".to_string(),
+        // Not a rowid alias, so a corrupt parent id can be stored below.
+        "CREATE TABLE TRENDLOG_DATA (id INTEGER, SerialNumber INTEGER, PointType TEXT, PointIndex INTEGER)".to_string(),
+        "CREATE TABLE TRENDLOG_DATA_DETAIL (ParentId INTEGER, Value TEXT, LoggingTime_Fmt TEXT)".to_string(),
+        // Two AHUs on one controller, each with its own discharge temp.
+        "INSERT INTO HAYSTACK_POINT_TAGS VALUES \
+         (9,'INPUT','1','IN1','discharge'),(9,'INPUT','1','IN1','temp'),(9,'INPUT','1','IN1','equipRef:@AHU-1'), \
+         (9,'INPUT','2','IN2','discharge'),(9,'INPUT','2','IN2','temp'),(9,'INPUT','2','IN2','equipRef:@AHU-2')"
+            .to_string(),
+        "INSERT INTO TRENDLOG_DATA VALUES (1, 9, 'INPUT', 1), (2, 9, 'INPUT', 2)".to_string(),
+        // Opened before the points carried equipRef tags.
+        "INSERT INTO FDD_FINDINGS (device_serial, equipment, rule_id, status) VALUES (9, '', 'SAT-HIGH', 'open')".to_string(),
+    ];
+    for i in 0..6 {
+        let ts = (now - chrono::Duration::minutes(5 * (6 - i))).format("%Y-%m-%d %H:%M:%S");
+        // AHU-1 runs hot, AHU-2 is fine.
+        stmts.push(format!(
+            "INSERT INTO TRENDLOG_DATA_DETAIL VALUES (1, '120', '{ts}'), (2, '55', '{ts}')"
+        ));
+    }
+    for sql in stmts {
+        db.execute(sea_orm::Statement::from_string(sea_orm::DatabaseBackend::Sqlite, sql))
+            .await
+            .unwrap();
+    }
+
+    let (status, body) = send(
+        &app,
+        "POST",
+        "/api/fdd/analyze",
+        Some(json!({"serial_number": 9, "range_hours": 2, "rules": ["SAT-HIGH"]})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["equipment_instances"], json!(["AHU-1", "AHU-2"]));
+    let findings = body["findings"].as_array().unwrap();
+    assert_eq!(findings.len(), 2);
+    let hot = findings.iter().find(|f| f["equipment"] == "AHU-1").unwrap();
+    assert!(hot["fault_hours"].as_f64().unwrap() > 0.0);
+    let ok = findings.iter().find(|f| f["equipment"] == "AHU-2").unwrap();
+    assert_eq!(ok["fault_hours"], 0.0);
+
+    // The device-wide fault is closed now that each AHU is analyzed on its own.
+    let (_, stored) = send(&app, "GET", "/api/fdd/findings?serial_number=9&status=active", None).await;
+    assert_eq!(stored["total"], 1);
+    assert_eq!(stored["findings"][0]["equipment"], "AHU-1");
+
+    // An instance whose history can't be loaded doesn't sink the others.
+    db.execute_unprepared(
+        "INSERT INTO HAYSTACK_POINT_TAGS VALUES \
+         (9,'INPUT','3','IN3','discharge'),(9,'INPUT','3','IN3','temp'),(9,'INPUT','3','IN3','equipRef:@AHU-3'); \
+         INSERT INTO TRENDLOG_DATA VALUES ('corrupt', 9, 'INPUT', 3);",
+    )
+    .await
+    .unwrap();
+    let (status, body) = send(
+        &app,
+        "POST",
+        "/api/fdd/analyze",
+        Some(json!({"serial_number": 9, "range_hours": 2, "rules": ["SAT-HIGH"]})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["findings"].as_array().unwrap().len(), 2);
+    let broken = body["by_equipment"].as_array().unwrap().iter().find(|r| r["equipment"] == "AHU-3").unwrap();
+    assert!(broken["error"].is_string(), "{}", broken);
+
+    // Without its role mapping the rule can't run, and AHU-1's open fault closes.
+    db.execute_unprepared("DELETE FROM HAYSTACK_POINT_TAGS WHERE point_id = 'IN1' AND tag_name = 'discharge'")
+        .await
+        .unwrap();
+    let (_, body) = send(
+        &app,
+        "POST",
+        "/api/fdd/analyze",
+        Some(json!({"serial_number": 9, "range_hours": 2, "rules": ["SAT-HIGH"]})),
+    )
+    .await;
+    let unmapped = body["findings"].as_array().unwrap().iter().find(|f| f["equipment"] == "AHU-1").unwrap();
+    assert_eq!(unmapped["status"], "insufficient_roles");
+    assert_eq!(unmapped["fault_status"], "cleared");
+}
+
+#[tokio::test]
+async fn test_each_rule_is_bucketed_at_its_own_poll() {
+    let (app, db) = test_app().await;
+    send(&app, "GET", "/api/fdd/rules", None).await;
+    let now = chrono::Utc::now();
+    let mut stmts = vec![
+        "CREATE TABLE HAYSTACK_POINT_TAGS (serial_number INTEGER, point_type TEXT, point_index TEXT, point_id TEXT, tag_name TEXT)".to_string(),
+        "CREATE TABLE TRENDLOG_DATA (id INTEGER PRIMARY KEY, SerialNumber INTEGER, PointType TEXT, PointIndex INTEGER)".to_string(),
+        "CREATE TABLE TRENDLOG_DATA_DETAIL (ParentId INTEGER, Value TEXT, LoggingTime_Fmt TEXT)".to_string(),
+        "INSERT INTO HAYSTACK_POINT_TAGS VALUES (5,'INPUT','1','IN1','discharge'),(5,'INPUT','1','IN1','temp')".to_string(),
+        "INSERT INTO TRENDLOG_DATA VALUES (1, 5, 'INPUT', 1)".to_string(),
+    ];
+    for i in 0..6 {
+        let ts = (now - chrono::Duration::minutes(5 * (6 - i))).format("%Y-%m-%d %H:%M:%S");
+        stmts.push(format!("INSERT INTO TRENDLOG_DATA_DETAIL VALUES (1, '120', '{ts}')"));
+    }
+    for sql in stmts {
+        db.execute_unprepared(&sql).await.unwrap();
+    }
+    let fast = json!({
+        "rule_id": "FAST-1",
+        "rule_kind": "ThresholdAbove",
+        "required_roles": ["sat"],
+        "params": {"field": "sat", "limit": 100, "confirm_rows": 4, "poll_seconds": 60}
+    });
+    assert_eq!(send(&app, "POST", "/api/fdd/rules", Some(fast)).await.0, StatusCode::CREATED);
+
+    let analyze = |rules: Value| send(&app, "POST", "/api/fdd/analyze", Some(json!({"serial_number": 5, "range_hours": 2, "rules": rules})));
+    let (_, alone) = analyze(json!(["SAT-HIGH"])).await;
+    let (status, both) = analyze(json!(["SAT-HIGH", "FAST-1"])).await;
+    assert_eq!(status, StatusCode::OK, "{}", both);
+    let finding = |body: &Value, id: &str| body["findings"].as_array().unwrap().iter().find(|f| f["rule_id"] == id).unwrap().clone();
+    // The 60 s rule doesn't shrink SAT-HIGH's 4×300 s confirmation window.
+    assert_eq!(finding(&both, "SAT-HIGH")["bucket_seconds"], 300);
+    assert_eq!(finding(&both, "SAT-HIGH")["fault_hours"], finding(&alone, "SAT-HIGH")["fault_hours"]);
+    assert_eq!(finding(&both, "FAST-1")["bucket_seconds"], 60);
+    // Five-minute logging under 60 s buckets carries forward without gaps.
+    assert_eq!(both["gap_count"], 0);
+}
+
+#[tokio::test]
+async fn test_tariff_and_top_faults_by_cost() {
+    let (app, db) = test_app().await;
+    // The tariff lives in APPLICATION_CONFIG, so load the real schema.
+    db.execute_unprepared(t3_webview_api::db_schema::EMBEDDED_SCHEMA)
+        .await
+        .unwrap();
+
+    let (_, body) = send(&app, "GET", "/api/fdd/tariff", None).await;
+    assert!(body["tariff"].is_null());
+    let (status, _) = send(&app, "PUT", "/api/fdd/tariff", Some(json!({"price_per_kwh": -1.0}))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = send(&app, "PUT", "/api/fdd/tariff", Some(json!({"price_per_kwh": 0.2}))).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, body) = send(&app, "GET", "/api/fdd/tariff", None).await;
+    assert_eq!(body["tariff"]["price_per_kwh"], 0.2);
+    assert_eq!(body["tariff"]["currency"], "USD");
+
+    for (serial, kwh, cost) in [(1, "5.0", "1.0"), (2, "50.0", "10.0"), (3, "NULL", "NULL")] {
+        let sql = format!(
+            "INSERT INTO FDD_FINDINGS (device_serial, equipment, rule_id, severity, fault_hours, status, est_kwh, est_cost) \
+             VALUES ({}, '', 'ECON-4', 'warning', 1.0, 'open', {}, {})",
+            serial, kwh, cost
+        );
+        db.execute(sea_orm::Statement::from_string(sea_orm::DatabaseBackend::Sqlite, sql))
+            .await
+            .unwrap();
+    }
+
+    let (status, body) = send(&app, "GET", "/api/fdd/findings/top-cost?status=active", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["total"], 2);
+    assert_eq!(body["findings"][0]["device_serial"], 2);
+    assert_eq!(body["total_est_cost"], 11.0);
+    assert_eq!(body["tariff"]["price_per_kwh"], 0.2);
+}
@@ -1,11 +1,21 @@
 //! Shared test helpers for MCP tool tests.
 //!
 //! Provides argument builders, JSON assertion macros, and DB-connection
-//! utilities so individual test modules stay concise.
+//! utilities so individual test modules stay concise. The app-state and
+//! request helpers at the end are also pulled into the other integration
+//! test binaries with `#[path = "../mcp/common.rs"] mod common;`.
 #![allow(dead_code)]
 
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+    Router,
+};
 use serde_json::{json, Value};
-use std::sync::OnceLock;
+use std::sync::{Arc, OnceLock};
+use tower::ServiceExt;
+
+use t3_webview_api::app_state::T3AppState;
 
 // ═══ Runtime DB path ═══
 
@@ -148,3 +158,59 @@ where
         None => println!("⚠️  SKIP {} — runtime DB not available", test_name),
     }
 }
+
+// ═══ App state and HTTP helpers ═══
+
+/// App state with every connection on `db` and no server database.
+pub fn app_state(db: &sea_orm::DatabaseConnection) -> T3AppState {
+    let conn = Arc::new(tokio::sync::Mutex::new(db.clone()));
+    T3AppState {
+        conn: conn.clone(),
+        t3_device_conn: Some(conn.clone()),
+        local_config_conn: Some(conn),
+        mssql_pool: None,
+        server_db_enabled: false,
+        server_db_role: "client".into(),
+        server_db_connected: false,
+    }
+}
+
+/// Send a request through `app` with an optional bearer token and JSON body
+/// (none for `None` or `Value::Null`); the status and the response text.
+pub async fn send_text(
+    app: &Router,
+    method: &str,
+    uri: &str,
+    token: Option<&str>,
+    body: impl Into<Option<Value>>,
+) -> (StatusCode, String) {
+    let mut req = Request::builder().method(method).uri(uri).header("content-type", "application/json");
+    if let Some(token) = token {
+        req = req.header("authorization", format!("Bearer {}", token));
+    }
+    let body = match body.into() {
+        None | Some(Value::Null) => Body::empty(),
+        Some(body) => Body::from(body.to_string()),
+    };
+    let resp = app.clone().oneshot(req.body(body).unwrap()).await.unwrap();
+    let status = resp.status();
+    let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    (status, String::from_utf8_lossy(&bytes).to_string())
+}
+
+/// [`send_text`] with the response parsed as JSON (other text comes back as a string).
+pub async fn send_as(
+    app: &Router,
+    method: &str,
+    uri: &str,
+    token: Option<&str>,
+    body: impl Into<Option<Value>>,
+) -> (StatusCode, Value) {
+    let (status, text) = send_text(app, method, uri, token, body).await;
+    (status, serde_json::from_str(&text).unwrap_or(Value::String(text)))
+}
+
+/// [`send_as`] without a token.
+pub async fn send(app: &Router, method: &str, uri: &str, body: impl Into<Option<Value>>) -> (StatusCode, Value) {
+    send_as(app, method, uri, None, body).await
+}
@@ -20,6 +20,7 @@ fn test_fdd_tools_exist() {
         "t3000_fdd_rule_export",
         "t3000_fdd_rule_import",
         "t3000_fdd_faults",
+        "t3000_fdd_top_faults",
     ];
     for name in &names {
         assert!(
@@ -229,3 +230,30 @@ async fn test_fdd_analyze_returns_findings_shape() {
     })
     .await;
 }
+
+#[test]
+fn test_fdd_faults_exposes_status_filter() {
+    let tool = common::all_tools()
+        .iter()
+        .find(|t| t.name == "t3000_fdd_faults")
+        .unwrap();
+    let props = tool.input_schema.get("properties").and_then(|v| v.as_object()).unwrap();
+    assert!(props.contains_key("status"), "fdd_faults should accept a lifecycle 'status' filter");
+}
+
+#[tokio::test]
+async fn test_fdd_faults_active_records_have_lifecycle_fields() {
+    common::with_db_or_skip("fdd_faults_active_records_have_lifecycle_fields", |db| async move {
+        let result = common::execute_tool_json("t3000_fdd_faults", &json!({"status": "active"}), &db)
+            .await
+            .expect("faults should succeed");
+        for f in result.get("findings").and_then(|v| v.as_array()).unwrap() {
+            let status = f.get("status").and_then(|v| v.as_str()).unwrap_or("");
+            assert!(status == "open" || status == "ongoing", "unexpected status {}", status);
+            assert!(f.get("first_seen").is_some(), "first_seen missing");
+            assert!(f.get("occurrences").is_some(), "occurrences missing");
+            assert!(f.get("cleared_at").is_none_or(|v| v.is_null()), "active fault has cleared_at");
+        }
+    })
+    .await;
+}
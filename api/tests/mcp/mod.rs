@@ -24,3 +24,6 @@ pub mod device_operations;
 pub mod diagnostics;
 pub mod fdd;
 pub mod integration_flows;
+pub mod resources_and_prompts;
+pub mod notifications;
+pub mod tokens;
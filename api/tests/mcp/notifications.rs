@@ -0,0 +1,176 @@
+//! Notifications — alarm / device status / point value events pushed on the
+//! session's SSE stream for subscribed resources.
+//!
+//! The bus is process-wide, so each test uses its own serial numbers.
+
+use std::time::Duration;
+
+use axum::{body::Body, http::Request, Router};
+use futures::StreamExt;
+use sea_orm::Database;
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use t3_webview_api::entity::t3_device::alarms;
+use t3_webview_api::mcp::notifications::{self, Event};
+
+use crate::mcp::common;
+
+async fn app() -> Router {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    t3_webview_api::mcp::create_mcp_routes().with_state(common::app_state(&db))
+}
+
+async fn rpc(app: &Router, session: Option<&str>, method: &str, params: Value) -> (String, Value) {
+    let mut req = Request::builder().method("POST").uri("/api/mcp").header("content-type", "application/json");
+    if let Some(id) = session {
+        req = req.header("mcp-session-id", id);
+    }
+    let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+    let resp = app.clone().oneshot(req.body(Body::from(body.to_string())).unwrap()).await.unwrap();
+    let session = resp.headers()["mcp-session-id"].to_str().unwrap().to_string();
+    let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    (session, serde_json::from_slice(&bytes).unwrap())
+}
+
+/// `data:` payloads of the SSE frames in `chunk`, skipping the endpoint event.
+fn messages(chunk: &str) -> Vec<Value> {
+    chunk
+        .split("\n\n")
+        .filter(|frame| frame.contains("event: message"))
+        .filter_map(|frame| frame.lines().find_map(|l| l.strip_prefix("data: ")))
+        .map(|data| serde_json::from_str(data).unwrap())
+        .collect()
+}
+
+fn alarm(serial: i32, status: &str, acknowledged: &str) -> alarms::Model {
+    alarms::Model {
+        serial_number: serial,
+        alarm_id: Some("2".into()),
+        panel: None,
+        message: Some("High SAT".into()),
+        status: Some(status.into()),
+        priority: Some("1".into()),
+        notification_id: None,
+        alarm_state: None,
+        alarm_type: None,
+        source: None,
+        description: None,
+        acknowledged: Some(acknowledged.into()),
+        action_field: None,
+        time_stamp: None,
+        low_limit: None,
+        high_limit: None,
+    }
+}
+
+#[tokio::test]
+async fn test_only_transitions_are_published() {
+    let mut rx = notifications::subscribe();
+    let mut next = || {
+        let mut ours = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            if event.uris()[1].contains("/device/7101") {
+                ours.push(event);
+            }
+        }
+        ours
+    };
+
+    notifications::alarm_update(&alarm(7101, "0", "0"), Some("1"), None);
+    notifications::alarm_update(&alarm(7101, "1", "0"), Some("1"), Some("1"));
+    notifications::alarm_update(&alarm(7101, "1", "1"), Some("1"), Some("1"));
+    assert_eq!(next(), vec![
+        Event::AlarmRaised { serial_number: 7101, alarm_id: "2".into(), message: Some("High SAT".into()), priority: Some("1".into()) },
+        Event::AlarmAcknowledged { serial_number: 7101, alarm_id: "2".into() },
+    ]);
+
+    notifications::device_status(7101, Some(1), true);
+    notifications::device_status(7101, Some(1), false);
+    notifications::device_status(7101, None, true);
+    assert_eq!(next(), vec![
+        Event::DeviceStatus { serial_number: 7101, online: false },
+        Event::DeviceStatus { serial_number: 7101, online: true },
+    ]);
+
+    notifications::point_value(7101, "INPUT", 2, Some("55000"), 55000.0);
+    notifications::point_value(7101, "INPUT", 2, Some("55000"), 56500.0);
+    notifications::point_value(7101, "VARIABLE", 0, None, 3.0);
+    assert_eq!(next(), vec![
+        Event::PointValue { serial_number: 7101, point_type: "INPUT".into(), point_index: 3, value: 56.5 },
+        Event::PointValue { serial_number: 7101, point_type: "VARIABLE".into(), point_index: 1, value: 3.0 },
+    ]);
+}
+
+#[tokio::test]
+async fn test_sse_stream_carries_subscribed_notifications_only() {
+    let app = app().await;
+    let (session, _) = rpc(&app, None, "initialize", json!({})).await;
+    for uri in ["t3000://device/7202/point/INPUT/3", "t3000://alarms"] {
+        let (_, resp) = rpc(&app, Some(&session), "resources/subscribe", json!({ "uri": uri })).await;
+        assert_eq!(resp["result"], json!({}), "{}", uri);
+    }
+
+    let req = Request::builder().method("GET").uri("/api/mcp").header("mcp-session-id", &session).body(Body::empty()).unwrap();
+    let resp = app.clone().oneshot(req).await.unwrap();
+    assert_eq!(resp.headers()["content-type"], "text/event-stream");
+    let mut body = resp.into_body().into_data_stream();
+
+    // Not subscribed: other points of the device, other devices' status.
+    notifications::point_value(7202, "INPUT", 3, Some("0"), 1000.0);
+    notifications::device_status(7202, Some(1), false);
+    // Subscribed.
+    notifications::point_value(7202, "INPUT", 2, Some("0"), 21500.0);
+    notifications::alarm_update(&alarm(7202, "0", "0"), Some("1"), None);
+
+    let mut received = Vec::new();
+    while received.len() < 2 {
+        let chunk = tokio::time::timeout(Duration::from_secs(5), body.next()).await.expect("notification").unwrap().unwrap();
+        received.extend(
+            messages(&String::from_utf8_lossy(&chunk))
+                .into_iter()
+                .filter(|m| m["params"]["uri"] != "t3000://alarms" || m["params"]["change"]["serial_number"] == 7202),
+        );
+    }
+    assert_eq!(received[0]["method"], "notifications/resources/updated");
+    assert_eq!(received[0]["params"]["uri"], "t3000://device/7202/point/INPUT/3");
+    assert_eq!(received[0]["params"]["change"]["event"], "point_value");
+    assert_eq!(received[0]["params"]["change"]["value"], 21.5);
+    assert_eq!(received[1]["params"]["uri"], "t3000://alarms");
+    assert_eq!(received[1]["params"]["change"]["event"], "alarm_raised");
+}
+
+#[tokio::test]
+async fn test_subscriptions_match_case_insensitive_point_types() {
+    let app = app().await;
+    let (session, _) = rpc(&app, None, "initialize", json!({})).await;
+    let (_, resp) = rpc(&app, Some(&session), "resources/subscribe", json!({ "uri": "t3000://device/7303/point/input/4" })).await;
+    assert_eq!(resp["result"], json!({}));
+
+    let req = Request::builder().method("GET").uri("/api/mcp").header("mcp-session-id", &session).body(Body::empty()).unwrap();
+    let mut body = app.clone().oneshot(req).await.unwrap().into_body().into_data_stream();
+    notifications::point_value(7303, "INPUT", 3, Some("0"), 4000.0);
+
+    let mut received = Vec::new();
+    while received.is_empty() {
+        let chunk = tokio::time::timeout(Duration::from_secs(5), body.next()).await.expect("notification").unwrap().unwrap();
+        received.extend(messages(&String::from_utf8_lossy(&chunk)));
+    }
+    // Delivered under the URI the client subscribed with
+    assert_eq!(received[0]["params"]["uri"], "t3000://device/7303/point/input/4");
+    assert_eq!(received[0]["params"]["change"]["value"], 4.0);
+
+    let (_, resp) = rpc(&app, Some(&session), "resources/unsubscribe", json!({ "uri": "t3000://device/7303/point/Input/4" })).await;
+    assert_eq!(resp["result"], json!({}));
+    let mut rx = notifications::subscribe();
+    notifications::point_value(7303, "INPUT", 3, Some("0"), 5000.0);
+    rx.recv().await.unwrap();
+    assert!(tokio::time::timeout(Duration::from_millis(200), body.next()).await.is_err());
+}
+
+#[tokio::test]
+async fn test_sse_unknown_session_is_rejected() {
+    let app = app().await;
+    let req = Request::builder().method("GET").uri("/api/mcp").header("mcp-session-id", "nope").body(Body::empty()).unwrap();
+    assert_eq!(app.oneshot(req).await.unwrap().status(), 404);
+}
@@ -0,0 +1,111 @@
+//! Resources & Prompts — resources/list, templates, read and subscribe, and
+//! prompts/list/get over the `/api/mcp` JSON-RPC endpoint.
+
+use axum::{body::Body, http::Request, Router};
+use sea_orm::{ConnectionTrait, Database, Schema};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use t3_webview_api::entity::t3_device::devices;
+use t3_webview_api::mcp::resources::Resource;
+
+use crate::mcp::common;
+
+async fn app() -> Router {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    let backend = db.get_database_backend();
+    db.execute(backend.build(&Schema::new(backend).create_table_from_entity(devices::Entity))).await.unwrap();
+    db.execute_unprepared("INSERT INTO DEVICES (SerialNumber, PanelId, Panel_Number, Product_Name) VALUES (4001, 7, 7, 'AHU-1')")
+        .await
+        .unwrap();
+    t3_webview_api::mcp::create_mcp_routes().with_state(common::app_state(&db))
+}
+
+async fn rpc(app: &Router, session: Option<&str>, method: &str, params: Value) -> (String, Value) {
+    let mut req = Request::builder().method("POST").uri("/api/mcp").header("content-type", "application/json");
+    if let Some(id) = session {
+        req = req.header("mcp-session-id", id);
+    }
+    let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+    let resp = app.clone().oneshot(req.body(Body::from(body.to_string())).unwrap()).await.unwrap();
+    let session = resp.headers()["mcp-session-id"].to_str().unwrap().to_string();
+    let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    (session, serde_json::from_slice(&bytes).unwrap())
+}
+
+#[test]
+fn test_resource_uris_parse() {
+    assert_eq!(Resource::parse("t3000://devices"), Some(Resource::Devices));
+    assert_eq!(Resource::parse("t3000://device/4001/points"), Some(Resource::Points(4001)));
+    assert_eq!(
+        Resource::parse("t3000://device/4001/trendlog/input/3"),
+        Some(Resource::Series { serial: 4001, point_type: "INPUT".into(), point_index: 3 })
+    );
+    assert_eq!(Resource::parse("t3000://docs/quick-start/overview"), Some(Resource::Doc("quick-start/overview".into())));
+    assert_eq!(Resource::parse("t3000://fdd/findings"), Some(Resource::Faults(None)));
+    for bad in ["t3000://device/abc", "t3000://device/1/trendlog/PID/1", "t3000://docs/../secrets", "http://devices"] {
+        assert_eq!(Resource::parse(bad), None, "{}", bad);
+    }
+}
+
+#[tokio::test]
+async fn test_initialize_advertises_resources_and_prompts() {
+    let app = app().await;
+    let (_, resp) = rpc(&app, None, "initialize", json!({})).await;
+    let caps = &resp["result"]["capabilities"];
+    assert_eq!(caps["resources"]["subscribe"], true);
+    assert!(caps["prompts"].is_object());
+}
+
+#[tokio::test]
+async fn test_resources_list_read_and_subscribe() {
+    let app = app().await;
+    let (session, resp) = rpc(&app, None, "resources/list", json!({})).await;
+    let uris: Vec<&str> = resp["result"]["resources"].as_array().unwrap().iter().map(|r| r["uri"].as_str().unwrap()).collect();
+    assert!(uris.contains(&"t3000://devices"));
+    assert!(uris.contains(&"t3000://device/4001/points"));
+
+    let (_, resp) = rpc(&app, Some(&session), "resources/templates/list", json!({})).await;
+    assert!(resp["result"]["resourceTemplates"].as_array().unwrap().iter()
+        .any(|t| t["uriTemplate"] == "t3000://device/{serial}/trendlog/{point_type}/{point_index}"));
+
+    let (_, resp) = rpc(&app, Some(&session), "resources/read", json!({ "uri": "t3000://devices" })).await;
+    let content = &resp["result"]["contents"][0];
+    assert_eq!(content["mimeType"], "application/json");
+    let devices: Value = serde_json::from_str(content["text"].as_str().unwrap()).unwrap();
+    assert!(devices.to_string().contains("AHU-1"), "{}", devices);
+
+    let (_, resp) = rpc(&app, Some(&session), "resources/read", json!({ "uri": "t3000://docs/quick-start/overview" })).await;
+    assert_eq!(resp["result"]["contents"][0]["mimeType"], "text/markdown");
+    assert!(resp["result"]["contents"][0]["text"].as_str().unwrap().starts_with('#'));
+
+    let (_, resp) = rpc(&app, Some(&session), "resources/read", json!({ "uri": "t3000://nope" })).await;
+    assert_eq!(resp["error"]["code"], -32002);
+
+    let (_, resp) = rpc(&app, Some(&session), "resources/subscribe", json!({ "uri": "t3000://device/4001/points" })).await;
+    assert_eq!(resp["result"], json!({}));
+    let (_, resp) = rpc(&app, Some(&session), "resources/subscribe", json!({ "uri": "t3000://device/x" })).await;
+    assert_eq!(resp["error"]["code"], -32002);
+}
+
+#[tokio::test]
+async fn test_prompts_list_and_get() {
+    let app = app().await;
+    let (session, resp) = rpc(&app, None, "prompts/list", json!({})).await;
+    let prompts = resp["result"]["prompts"].as_array().unwrap();
+    let ahu = prompts.iter().find(|p| p["name"] == "commission_ahu").unwrap();
+    assert_eq!(ahu["arguments"][0]["name"], "serial");
+    assert_eq!(ahu["arguments"][0]["required"], true);
+
+    let (_, resp) = rpc(&app, Some(&session), "prompts/get",
+        json!({ "name": "commission_ahu", "arguments": { "serial": "4001" } })).await;
+    let text = resp["result"]["messages"][0]["content"]["text"].as_str().unwrap();
+    assert!(text.contains("t3000://device/4001/points"));
+    assert!(text.contains("Notes from site: none"));
+    assert!(!text.contains('{'));
+
+    let (_, resp) = rpc(&app, Some(&session), "prompts/get", json!({ "name": "explain_fault", "arguments": {} })).await;
+    assert_eq!(resp["error"]["code"], -32602);
+    let (_, resp) = rpc(&app, Some(&session), "prompts/get", json!({ "name": "nope" })).await;
+    assert_eq!(resp["error"]["code"], -32602);
+}
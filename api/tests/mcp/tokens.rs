@@ -0,0 +1,172 @@
+//! MCP API tokens — token CRUD, and tool allow-lists, device allow-lists,
+//! read-only mode and rate limits enforced on `/api/mcp` calls.
+
+use axum::{http::StatusCode, middleware, Router};
+use sea_orm::{ConnectionTrait, Database, DatabaseConnection, Schema};
+use serde_json::{json, Value};
+
+use t3_webview_api::auth::{self, store, Role};
+use t3_webview_api::entity::t3_device::devices;
+use t3_webview_api::mcp::tokens::{self, TokenSpec};
+
+use crate::mcp::common::{self, send_as};
+
+/// MCP routes behind the role middleware, with one admin account so
+/// authentication is switched on.
+async fn app() -> (Router, DatabaseConnection, String) {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    store::ensure_schema(&db).await.unwrap();
+    tokens::ensure_schema(&db).await.unwrap();
+    store::create_user(&db, "admin", "admin-pass-1", Role::Admin).await.unwrap();
+    let admin = store::login(&db, "admin", "admin-pass-1").await.unwrap().unwrap().token;
+    let state = common::app_state(&db);
+    let app = t3_webview_api::mcp::create_mcp_routes()
+        .with_state(state.clone())
+        .layer(middleware::from_fn_with_state(state, auth::enforce_roles));
+    (app, db, admin)
+}
+
+async fn rpc(app: &Router, token: &str, method: &str, params: Value) -> (StatusCode, Value) {
+    send_as(app, "POST", "/api/mcp", Some(token), json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params })).await
+}
+
+async fn call(app: &Router, token: &str, tool: &str, args: Value) -> Value {
+    let (status, resp) = rpc(app, token, "tools/call", json!({ "name": tool, "arguments": args })).await;
+    assert_eq!(status, StatusCode::OK, "{}", resp);
+    resp["result"].clone()
+}
+
+fn text(result: &Value) -> &str {
+    result["content"][0]["text"].as_str().unwrap_or("")
+}
+
+fn spec(value: Value) -> TokenSpec {
+    serde_json::from_value(value).unwrap()
+}
+
+#[tokio::test]
+async fn test_token_crud_is_admin_only() {
+    let (app, _, admin) = app().await;
+    let (status, _) = send_as(&app, "GET", "/api/mcp/tokens", None, Value::Null).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, created) = send_as(&app, "POST", "/api/mcp/tokens", Some(&admin),
+        json!({ "name": "BMS gateway", "allowedSerials": [4001] })).await;
+    assert_eq!(status, StatusCode::OK, "{}", created);
+    let secret = created["secret"].as_str().unwrap();
+    assert!(secret.starts_with(tokens::TOKEN_PREFIX));
+    assert_eq!(created["token"]["readOnly"], true);
+    assert_eq!(created["token"]["createdBy"], "admin");
+    let id = created["token"]["id"].as_i64().unwrap();
+
+    // The secret is shown once and never listed; an MCP token can't manage tokens
+    let (_, list) = send_as(&app, "GET", "/api/mcp/tokens", Some(&admin), Value::Null).await;
+    assert_eq!(list["tokens"].as_array().unwrap().len(), 1);
+    assert!(!list.to_string().contains(secret));
+    let (status, _) = send_as(&app, "GET", "/api/mcp/tokens", Some(secret), Value::Null).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, updated) = send_as(&app, "PUT", &format!("/api/mcp/tokens/{}", id), Some(&admin),
+        json!({ "name": "BMS gateway", "enabled": false })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["enabled"], false);
+    let (status, resp) = rpc(&app, secret, "tools/list", json!({})).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(resp["error"]["code"], -32001);
+
+    let (status, _) = send_as(&app, "DELETE", &format!("/api/mcp/tokens/{}", id), Some(&admin), Value::Null).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send_as(&app, "DELETE", &format!("/api/mcp/tokens/{}", id), Some(&admin), Value::Null).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_invalid_token_is_rejected() {
+    let (app, _, _) = app().await;
+    let (status, resp) = rpc(&app, "t3mcp_not-a-real-token", "tools/list", json!({})).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(resp["error"]["code"], -32001);
+
+    // The SSE stream and session termination check the token too
+    for method in ["GET", "DELETE"] {
+        let (status, resp) = send_as(&app, method, "/api/mcp", Some("t3mcp_not-a-real-token"), Value::Null).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", method);
+        assert_eq!(resp["error"]["code"], -32001);
+    }
+}
+
+#[tokio::test]
+async fn test_read_only_and_tool_allow_list() {
+    let (app, db, _) = app().await;
+    let (_, read_only) = tokens::create(&db, &spec(json!({ "name": "viewer" })), None).await.unwrap();
+    let (_, listed) = tokens::create(&db, &spec(json!({
+        "name": "narrow", "readOnly": false, "allowedTools": ["t3000_ping", "t3000_point_write"]
+    })), None).await.unwrap();
+
+    let (_, resp) = rpc(&app, &read_only, "tools/list", json!({})).await;
+    let names: Vec<&str> = resp["result"]["tools"].as_array().unwrap().iter().map(|t| t["name"].as_str().unwrap()).collect();
+    assert!(names.contains(&"t3000_point_read"));
+    assert!(!names.contains(&"t3000_point_write"));
+    assert!(!names.contains(&"t3000_task_create"));
+
+    let result = call(&app, &read_only, "t3000_point_write",
+        json!({ "serial_number": 4001, "point_type": "OUTPUT", "point_index": 1, "value": 1, "confirm": true })).await;
+    assert_eq!(result["isError"], true);
+    assert!(text(&result).contains("read-only"), "{}", result);
+
+    let (_, resp) = rpc(&app, &listed, "tools/list", json!({})).await;
+    let names: Vec<&str> = resp["result"]["tools"].as_array().unwrap().iter().map(|t| t["name"].as_str().unwrap()).collect();
+    assert_eq!(names, vec!["t3000_ping", "t3000_point_write"]);
+    let result = call(&app, &listed, "t3000_point_read", json!({ "serial_number": 4001, "point_type": "INPUT", "point_index": 1 })).await;
+    assert!(text(&result).contains("allow-list"), "{}", result);
+    assert_ne!(call(&app, &listed, "t3000_ping", json!({})).await["isError"], true);
+}
+
+#[tokio::test]
+async fn test_device_allow_list() {
+    let (app, db, _) = app().await;
+    let backend = db.get_database_backend();
+    db.execute(backend.build(&Schema::new(backend).create_table_from_entity(devices::Entity))).await.unwrap();
+    db.execute_unprepared("INSERT INTO DEVICES (SerialNumber, PanelId, Panel_Number, Product_Name) VALUES (4001, 1, 1, 'AHU-1'), (9999, 2, 2, 'AHU-2')")
+        .await
+        .unwrap();
+    let (_, secret) = tokens::create(&db, &spec(json!({ "name": "ahu", "allowedSerials": [4001] })), None).await.unwrap();
+
+    let result = call(&app, &secret, "t3000_point_read", json!({ "serial_number": 9999, "point_type": "INPUT", "point_index": 1 })).await;
+    assert_eq!(result["isError"], true);
+    assert!(text(&result).contains("Device 9999"), "{}", result);
+    let result = call(&app, &secret, "t3000_point_read_batch",
+        json!({ "points": [{ "serial_number": 4001 }, { "serial_number": 9999 }] })).await;
+    assert!(text(&result).contains("Device 9999"), "{}", result);
+    let result = call(&app, &secret, "t3000_point_read", json!({ "serial_number": 4001, "point_type": "INPUT", "point_index": 1 })).await;
+    assert!(!text(&result).contains("allow-list"), "{}", result);
+
+    // Site-wide views would leak other devices
+    let result = call(&app, &secret, "t3000_device_list", json!({})).await;
+    assert!(text(&result).contains("would cover every device"), "{}", result);
+    let result = call(&app, &secret, "t3000_device_current", json!({})).await;
+    assert_eq!(result["isError"], true);
+    assert!(text(&result).contains("doesn't take a serial_number"), "{}", result);
+    let (_, resp) = rpc(&app, &secret, "resources/read", json!({ "uri": "t3000://devices" })).await;
+    assert_eq!(resp["error"]["code"], -32003);
+    let (_, resp) = rpc(&app, &secret, "resources/subscribe", json!({ "uri": "t3000://device/9999/points" })).await;
+    assert_eq!(resp["error"]["code"], -32003);
+    let (_, resp) = rpc(&app, &secret, "resources/list", json!({})).await;
+    let uris = resp["result"]["resources"].to_string();
+    assert!(!uris.contains("t3000://devices\""), "{}", uris);
+    assert!(uris.contains("t3000://device/4001/points"), "{}", uris);
+    assert!(!uris.contains("t3000://device/9999"), "{}", uris);
+    assert!(uris.contains("t3000://docs"), "{}", uris);
+}
+
+#[tokio::test]
+async fn test_rate_limit() {
+    let (app, db, _) = app().await;
+    let (_, secret) = tokens::create(&db, &spec(json!({ "name": "poller", "rateLimitPerMinute": 2 })), None).await.unwrap();
+    for _ in 0..2 {
+        assert_ne!(call(&app, &secret, "t3000_ping", json!({})).await["isError"], true);
+    }
+    let result = call(&app, &secret, "t3000_ping", json!({})).await;
+    assert_eq!(result["isError"], true);
+    assert!(text(&result).contains("Rate limit of 2"), "{}", result);
+}
@@ -11,11 +11,11 @@ fn all_tools() -> &'static [t3_webview_api::mcp::ToolDef] {
 // ═══ Count ═══
 
 #[test]
-fn test_tool_count_is_70() {
+fn test_tool_count_is_71() {
     let count = all_tools().len();
     assert_eq!(
-        count, 70,
-        "Expected 70 MCP tools, found {}. If you added/removed tools, update this test.",
+        count, 71,
+        "Expected 71 MCP tools, found {}. If you added/removed tools, update this test.",
         count
     );
 }
@@ -0,0 +1,288 @@
+//! Modbus TCP client and poller tests against the in-process stand-in
+//! (`modbus::sim`) on 127.0.0.1.
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use sea_orm::{ActiveModelTrait, ConnectionTrait, Database, DatabaseConnection, EntityTrait, Schema, Set};
+use serde_json::json;
+
+use t3_webview_api::entity::{files, modbus_register, modbus_register_devices, modbus_register_product_device_mapping};
+use t3_webview_api::modbus::client::{ModbusError, ModbusTcpClient};
+use t3_webview_api::modbus::format::RegisterValue;
+use t3_webview_api::modbus::poller::{self, ModbusPollerConfig};
+use t3_webview_api::modbus::sim::{SimDevice, SimHandle};
+
+#[path = "../mcp/common.rs"]
+mod common;
+
+const PRODUCT_ID: i32 = 88;
+
+fn localhost() -> SocketAddr {
+    "127.0.0.1:0".parse().unwrap()
+}
+
+async fn sim() -> SimHandle {
+    SimDevice::new()
+        // Serial number 0x12345678, one byte per register, LSB first.
+        .with_holding(0, &[0x78, 0x56, 0x34, 0x12])
+        // Supply temp 21.5 (int16 /10), setpoint 22.0 (float32 CDAB).
+        .with_holding(100, &[215])
+        .with_holding(101, &[0x0000, 0x41B0])
+        .with_input(200, &[0xFFF6])
+        .with_coils(10, &[true, false])
+        .spawn(localhost())
+        .await
+        .unwrap()
+}
+
+fn client(device: &SimHandle) -> ModbusTcpClient {
+    ModbusTcpClient::new(device.addr, 1).with_timeout(Duration::from_millis(500))
+}
+
+#[tokio::test]
+async fn test_read_and_write_functions() {
+    let device = sim().await;
+    let client = client(&device);
+
+    assert_eq!(client.read_holding_registers(100, 3).await.unwrap(), vec![215, 0, 0x41B0]);
+    assert_eq!(client.read_input_registers(200, 1).await.unwrap(), vec![0xFFF6]);
+    assert_eq!(client.read_coils(10, 2).await.unwrap(), vec![true, false]);
+
+    client.write_single_register(100, 230).await.unwrap();
+    client.write_multiple_registers(101, &[0x0000, 0x41C8]).await.unwrap();
+    client.write_single_coil(11, true).await.unwrap();
+    client.write_multiple_coils(10, &[false, false]).await.unwrap();
+    assert_eq!(device.holding(100), Some(230));
+    assert_eq!(device.holding(102), Some(0x41C8));
+    assert_eq!(device.coil(10), Some(false));
+    assert_eq!(device.coil(11), Some(false));
+}
+
+#[tokio::test]
+async fn test_exceptions_and_timeouts() {
+    let device = sim().await;
+    let client = client(&device);
+
+    match client.read_holding_registers(500, 1).await {
+        Err(ModbusError::Exception { function: 3, code: 2 }) => {}
+        other => panic!("expected illegal-data-address, got {:?}", other),
+    }
+    // The connection stays usable after an exception.
+    assert_eq!(client.read_holding_registers(100, 1).await.unwrap(), vec![215]);
+
+    // A listener that accepts but never answers.
+    let silent = tokio::net::TcpListener::bind(localhost()).await.unwrap();
+    let addr = silent.local_addr().unwrap();
+    let _accept = tokio::spawn(async move {
+        let _conn = silent.accept().await;
+        tokio::time::sleep(Duration::from_secs(5)).await;
+    });
+    let slow = ModbusTcpClient::new(addr, 1).with_timeout(Duration::from_millis(200));
+    assert!(matches!(slow.read_holding_registers(0, 1).await, Err(ModbusError::Timeout(a)) if a == addr));
+}
+
+fn register(address: i32, op: &str, format: &str) -> modbus_register::Model {
+    modbus_register::Model {
+        id: address,
+        register_address: Some(address),
+        operation: Some(op.into()),
+        register_length: 1,
+        register_name: None,
+        data_format: Some(format.into()),
+        description: None,
+        device_id: None,
+        status: "ACTIVE".into(),
+        unit: None,
+        private: None,
+        created_at: String::new(),
+        updated_at: String::new(),
+    }
+}
+
+#[tokio::test]
+async fn test_mixed_coil_and_register_operations() {
+    let device = sim().await;
+    let client = client(&device);
+
+    // Read a holding register, write through a coil
+    let def = poller::RegisterDef::from_model(&register(100, "03_05", "16 Bit Signed Integer/10")).unwrap();
+    assert_eq!(poller::read_register(&client, &def).await.unwrap(), RegisterValue::Number(21.5));
+
+    // Read a coil, write a single register
+    let def = poller::RegisterDef::from_model(&register(10, "01_06", "16 Bit Unsigned Integer")).unwrap();
+    assert_eq!(poller::read_register(&client, &def).await.unwrap(), RegisterValue::Number(1.0));
+    device.set_holding(10, 0);
+    poller::write_register(&client, &def, &RegisterValue::Number(7.0)).await.unwrap();
+    assert_eq!(device.holding(10), Some(7));
+
+    // The register side needs a format it can decode
+    assert!(poller::RegisterDef::from_model(&register(10, "01_06", "")).is_err());
+}
+
+/// One in-memory database holding both the webview register tables and the
+/// T3000 device schema.
+async fn setup_db(serial: i32, device: &SimHandle) -> DatabaseConnection {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    db.execute_unprepared(t3_webview_api::db_schema::EMBEDDED_SCHEMA).await.unwrap();
+    let backend = db.get_database_backend();
+    let schema = Schema::new(backend);
+    db.execute(backend.build(&schema.create_table_from_entity(files::Entity)))
+        .await
+        .unwrap();
+    db.execute(backend.build(&schema.create_table_from_entity(modbus_register_devices::Entity)))
+        .await
+        .unwrap();
+    db.execute(backend.build(&schema.create_table_from_entity(modbus_register::Entity)))
+        .await
+        .unwrap();
+    db.execute(backend.build(&schema.create_table_from_entity(modbus_register_product_device_mapping::Entity)))
+        .await
+        .unwrap();
+
+    db.execute_unprepared(&format!(
+        "INSERT INTO DEVICES (SerialNumber, PanelId, Product_ID, ip_address, modbus_port, modbus_address) \
+         VALUES ({}, 3, {}, '127.0.0.1', {}, 1)",
+        serial,
+        PRODUCT_ID,
+        device.addr.port()
+    ))
+    .await
+    .unwrap();
+
+    let now = chrono::Utc::now();
+    let map = modbus_register_devices::ActiveModel {
+        name: Set("Sim AHU".into()),
+        status: Set("ACTIVE".into()),
+        private: Set(false),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+    modbus_register_product_device_mapping::ActiveModel {
+        product_id: Set(PRODUCT_ID),
+        device_id: Set(map.id),
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+
+    for (address, length, op, format, name, status) in [
+        (0, 4, "03 Read Holding Registers (4x)", "32 Bit Unsigned Integer LO_HI", "SERIAL", "ACTIVE"),
+        (100, 1, "03_06 Read Holding and Write Single", "16 Bit Signed Integer/10", "SAT", "ACTIVE"),
+        (101, 2, "16 Read Write Multiple Registers", "32 Bit Float_CDAB", "SP", "ACTIVE"),
+        (200, 1, "04 Read Input Registers (3x)", "16 Bit Signed Integer", "OAT", "ACTIVE"),
+        (10, 1, "05 Write Single Coil", "8 Bit Unsigned Integer", "FAN", "ACTIVE"),
+        (300, 1, "03 Read Holding Registers (4x)", "16 Bit Unsigned Integer", "GONE", "DELETED"),
+    ] {
+        modbus_register::ActiveModel {
+            register_address: Set(Some(address)),
+            operation: Set(Some(op.into())),
+            register_length: Set(length),
+            register_name: Set(Some(name.into())),
+            data_format: Set(Some(format.into())),
+            device_id: Set(Some(map.id)),
+            status: Set(status.into()),
+            unit: Set(Some("degC".into())),
+            created_at: Set(now.to_rfc3339()),
+            updated_at: Set(now.to_rfc3339()),
+            ..Default::default()
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+    }
+    db
+}
+
+fn value_of(readings: &[poller::RegisterReading], name: &str) -> RegisterValue {
+    readings
+        .iter()
+        .find(|r| r.name == name)
+        .and_then(|r| r.value.clone())
+        .unwrap_or_else(|| panic!("no value for {}", name))
+}
+
+#[tokio::test]
+async fn test_poll_device_decodes_and_stores_values() {
+    let device = sim().await;
+    let db = setup_db(9001, &device).await;
+
+    let readings = poller::poll_device(&db, &db, 9001, Duration::from_millis(500)).await.unwrap();
+    assert_eq!(readings.len(), 5, "deleted registers are not polled");
+    assert_eq!(value_of(&readings, "SERIAL"), RegisterValue::Number(0x1234_5678 as f64));
+    assert_eq!(value_of(&readings, "SAT"), RegisterValue::Number(21.5));
+    assert_eq!(value_of(&readings, "SP"), RegisterValue::Number(22.0));
+    assert_eq!(value_of(&readings, "OAT"), RegisterValue::Number(-10.0));
+    assert_eq!(value_of(&readings, "FAN"), RegisterValue::Number(1.0));
+
+    let rows = db
+        .query_all(sea_orm::Statement::from_string(
+            db.get_database_backend(),
+            "SELECT p.PointId, p.PointIndex, p.PointType, d.Value FROM TRENDLOG_DATA p \
+             JOIN TRENDLOG_DATA_DETAIL d ON d.ParentId = p.id WHERE p.SerialNumber = 9001 ORDER BY p.PointIndex"
+                .to_string(),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 5);
+    let sat = rows.iter().find(|r| r.try_get::<String>("", "PointId").unwrap() == "SAT").unwrap();
+    assert_eq!(sat.try_get::<i32>("", "PointIndex").unwrap(), 100);
+    assert_eq!(sat.try_get::<String>("", "PointType").unwrap(), "MODBUS");
+
+    // A second cycle through the background path appends another sample each.
+    device.set_holding(100, 220);
+    let stored = poller::run_cycle(&db, &db, &ModbusPollerConfig::default()).await.unwrap();
+    assert_eq!(stored, 5);
+}
+
+#[tokio::test]
+async fn test_routes_list_poll_and_write() {
+    let device = sim().await;
+    let db = setup_db(9002, &device).await;
+    let app = t3_webview_api::modbus::routes::create_modbus_routes().with_state(common::app_state(&db));
+
+    let (status, body) = common::send(&app, "GET", "/api/modbus/9002/registers", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["registers"].as_array().unwrap().len(), 5);
+    assert_eq!(body["target"]["unit_id"], 1);
+
+    let (status, body) = common::send(&app, "POST", "/api/modbus/9002/poll", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["readings"].as_array().unwrap().len(), 5);
+
+    let models = modbus_register::Entity::find().all(&db).await.unwrap();
+    let id_of = |name: &str| models.iter().find(|m| m.register_name.as_deref() == Some(name)).unwrap().id;
+
+    let uri = format!("/api/modbus/9002/registers/{}", id_of("SAT"));
+    let (status, body) = common::send(&app, "PUT", &uri, Some(json!({"value": 19.5}))).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["reading"]["value"], 19.5);
+    assert_eq!(device.holding(100), Some(195));
+
+    let uri = format!("/api/modbus/9002/registers/{}", id_of("SP"));
+    let (status, _) = common::send(&app, "PUT", &uri, Some(json!({"value": 24.0}))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!((device.holding(101), device.holding(102)), (Some(0x0000), Some(0x41C0)));
+
+    let uri = format!("/api/modbus/9002/registers/{}", id_of("FAN"));
+    let (status, _) = common::send(&app, "PUT", &uri, Some(json!({"value": 0}))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(device.coil(10), Some(false));
+
+    let uri = format!("/api/modbus/9002/registers/{}", id_of("OAT"));
+    let (status, _) = common::send(&app, "PUT", &uri, Some(json!({"value": 1}))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "input registers are read-only");
+
+    let uri = format!("/api/modbus/9002/registers/{}", id_of("SAT"));
+    let (status, _) = common::send(&app, "PUT", &uri, Some(json!({"value": 1e9}))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "values the register can't hold are rejected");
+    assert_eq!(device.holding(100), Some(195));
+
+    let (status, _) = common::send(&app, "POST", "/api/modbus/1/poll", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,223 @@
+//! Simulator transport tests — site loading, time evolution, writes, and the
+//! refresh routes, LOGGING_DATA parser, trendlog monitor and LAN-scan parser
+//! running against a simulated site.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use sea_orm::{ConnectionTrait, Database, Schema};
+use serde_json::{json, Value};
+use tokio::net::UdpSocket;
+use tokio::sync::Mutex;
+
+use t3_webview_api::app_state::T3AppState;
+use t3_webview_api::entity::t3_device::{devices, input_points, protocol_settings};
+use t3_webview_api::lan_scan::protocol;
+use t3_webview_api::t3_device::t3_ffi_sync_service::T3000MainService;
+use t3_webview_api::t3_device::transport::sim::{LanResponder, PointKind, Signal, SimTransport, Site};
+use t3_webview_api::t3_device::transport::{self, DeviceTransport};
+use t3_webview_api::t3_device::trendlog_monitor_service::TrendlogMonitorService;
+
+#[path = "../mcp/common.rs"]
+mod common;
+
+/// The installed transport is process-wide; tests that install one take this.
+static INSTALL_LOCK: Mutex<()> = Mutex::const_new(());
+
+const SITE_YAML: &str = r#"
+name: test
+panels:
+  - serial_number: 4001
+    panel_number: 7
+    name: AHU-7
+    object_instance: 260001
+    ip: 10.0.0.7
+    inputs:
+      - label: SAT
+        description: Supply Air Temp
+        range: 4
+        signal: { model: ramp, min: 50.0, max: 60.0, period_secs: 100 }
+    outputs:
+      - label: SF
+        digital: true
+        signal: { model: square, low: 0.0, high: 1.0, period_secs: 60, duty: 0.5 }
+    variables:
+      - label: SP
+        signal: { model: constant, value: 55.0 }
+    trendlogs:
+      - label: AHU
+        interval_secs: 60
+        inputs:
+          - { kind: input, index: 0 }
+          - { kind: output, index: 0 }
+"#;
+
+fn site() -> Site {
+    Site::from_yaml_str(SITE_YAML).unwrap()
+}
+
+async fn state() -> T3AppState {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    let backend = db.get_database_backend();
+    let schema = Schema::new(backend);
+    db.execute(backend.build(&schema.create_table_from_entity(devices::Entity))).await.unwrap();
+    db.execute(backend.build(&schema.create_table_from_entity(protocol_settings::Entity))).await.unwrap();
+    db.execute(backend.build(&schema.create_table_from_entity(input_points::Entity))).await.unwrap();
+    db.execute_unprepared(
+        "INSERT INTO DEVICES (SerialNumber, PanelId, Panel_Number) VALUES (4001, 7, 7); \
+         INSERT INTO PROTOCOL_SETTINGS (SerialNumber, Object_Instance) VALUES (4001, 260001);",
+    )
+    .await
+    .unwrap();
+    common::app_state(&db)
+}
+
+#[test]
+fn test_site_loads_from_yaml_json_and_file() {
+    let yaml = site();
+    let json = Site::from_json_str(&serde_json::to_string(&yaml).unwrap()).unwrap();
+    assert_eq!(json.panels[0].serial_number, 4001);
+    assert_eq!(json.panels[0].inputs[0].signal, yaml.panels[0].inputs[0].signal);
+    // Defaults fill in what the file leaves out.
+    assert_eq!(yaml.panels[0].product_id, 88);
+    assert_eq!(yaml.panels[0].bacnet_port, 47808);
+
+    let path = std::env::temp_dir().join(format!("t3-sim-site-{}.json", std::process::id()));
+    std::fs::write(&path, serde_json::to_string(&yaml).unwrap()).unwrap();
+    assert_eq!(Site::load(&path).unwrap().panels[0].name, "AHU-7");
+    std::fs::remove_file(&path).ok();
+
+    assert!(!Site::demo().panels.is_empty());
+    let bad = SITE_YAML.replace("index: 0 }\n          - { kind: output", "index: 5 }\n          - { kind: output");
+    assert!(Site::from_yaml_str(&bad).unwrap_err().contains("missing INPUT 5"));
+}
+
+#[test]
+fn test_signals_are_pure_functions_of_time() {
+    let sine = Signal::Sine { base: 10.0, amplitude: 2.0, period_secs: 40.0, phase_secs: 0.0 };
+    assert!((sine.at(10.0) - 12.0).abs() < 1e-9);
+    assert!((sine.at(50.0) - 12.0).abs() < 1e-9);
+    let ramp = Signal::Ramp { min: 0.0, max: 10.0, period_secs: 10.0 };
+    assert_eq!(ramp.at(2.5), 2.5);
+    assert_eq!(ramp.at(12.5), 2.5);
+    let square = Signal::Square { low: 0.0, high: 1.0, period_secs: 10.0, duty: 0.3 };
+    assert_eq!(square.at(2.0), 1.0);
+    assert_eq!(square.at(5.0), 0.0);
+}
+
+#[tokio::test]
+async fn test_values_evolve_with_manual_clock_and_writes_stick() {
+    let sim = SimTransport::with_manual_clock(site());
+    assert_eq!(sim.value(4001, PointKind::Input, 0), Some(50.0));
+    assert_eq!(sim.value(4001, PointKind::Output, 0), Some(1.0));
+    sim.advance(Duration::from_secs(40));
+    assert_eq!(sim.value(4001, PointKind::Input, 0), Some(54.0));
+    assert_eq!(sim.value(4001, PointKind::Output, 0), Some(0.0));
+
+    // Manual pins the point; back in auto it follows its signal again.
+    let write = |auto_manual: i32, value: f64| {
+        json!({ "action": 16, "panelId": 7, "serialNumber": 4001, "entryType": 1, "entryIndex": 0,
+                "value": value, "auto_manual": auto_manual, "label": "SAT2" })
+    };
+    assert_eq!(sim.update_webview_list(&write(1, 99.0)).await.unwrap()["success"], true);
+    sim.advance(Duration::from_secs(10));
+    assert_eq!(sim.value(4001, PointKind::Input, 0), Some(99.0));
+    sim.update_webview_list(&write(0, 99.0)).await.unwrap();
+    assert_eq!(sim.value(4001, PointKind::Input, 0), Some(55.0));
+    assert_eq!(sim.site().panels[0].inputs[0].label, "SAT2");
+
+    // Constant points are setpoints: an auto write replaces the value.
+    let setpoint = json!({ "panelId": 7, "entryType": 2, "entryIndex": 0, "value": 58.5, "auto_manual": 0 });
+    sim.update_webview_list(&setpoint).await.unwrap();
+    assert_eq!(sim.value(4001, PointKind::Variable, 0), Some(58.5));
+
+    let missing = json!({ "panelId": 9, "entryType": 1, "entryIndex": 0 });
+    assert!(sim.update_webview_list(&missing).await.is_err());
+}
+
+#[tokio::test]
+async fn test_logging_data_and_panels_list_parse() {
+    let sim = SimTransport::with_manual_clock(site());
+    sim.advance(Duration::from_secs(25));
+
+    let logging = sim.logging_data().await.unwrap();
+    let parsed = T3000MainService::parse_logging_response(&logging.to_string()).unwrap();
+    assert_eq!(parsed.devices.len(), 1);
+    let device = &parsed.devices[0];
+    assert_eq!(device.device_info.panel_serial_number, 4001);
+    assert_eq!(device.device_info.panel_ipaddress, "10.0.0.7");
+    assert_eq!(device.input_points.len(), 1);
+    assert_eq!(device.input_points[0].value, 52.5);
+    assert_eq!(device.output_points.len(), 1);
+    assert_eq!(device.variable_points[0].value, 55.0);
+
+    let list: Value = serde_json::from_str(&sim.call(4, "").await.unwrap()).unwrap();
+    assert_eq!(list["action"], "GET_PANELS_LIST_RES");
+    assert_eq!(list["data"][0]["object_instance"], 260001);
+
+    // Unsimulated actions report an error the way T3000 does.
+    assert!(sim.call(9, "{}").await.is_err());
+}
+
+#[tokio::test]
+async fn test_refresh_route_and_trendlog_monitor_against_sim() {
+    let _lock = INSTALL_LOCK.lock().await;
+    let sim = Arc::new(SimTransport::with_manual_clock(site()));
+    sim.advance(Duration::from_secs(600));
+    transport::install(sim.clone());
+
+    let app = t3_webview_api::t3_device::input_refresh_routes::create_input_refresh_routes()
+        .with_state(state().await);
+    let (status, body) = common::send(&app, "POST", "/inputs/4001/refresh", json!({})).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["items"][0]["inputIndex"], 0);
+    assert_eq!(body["items"][0]["label"], "SAT");
+    assert_eq!(body["items"][0]["value"], 50.0);
+
+    let request = json!({ "panelId": 7, "serialNumber": 4001, "entryType": 1, "entryIndexStart": 0, "entryIndexEnd": 63 });
+    let list = sim.get_webview_list(&request).await.unwrap();
+    assert_eq!(list["data"]["device_data"][0]["type"], "INPUT");
+
+    let db = state().await.conn;
+    let monitor = TrendlogMonitorService::new(db);
+    let trendlogs = monitor.get_trendlog_list(7).await.unwrap();
+    assert_eq!(trendlogs.total_monitors, 1);
+    let ahu = &trendlogs.trendlogs[0];
+    assert_eq!((ahu.label.as_str(), ahu.num_inputs, ahu.an_inputs), ("AHU", 2, 1));
+    // Ten one-minute samples of two 4-byte inputs.
+    assert_eq!(ahu.data_size_kb, 80.0 / 1024.0);
+    let entry = monitor.get_trendlog_entry(7, 0).await.unwrap();
+    assert_eq!(entry.inputs.len(), 2);
+    assert_eq!((entry.inputs[0].point_type, entry.inputs[1].point_type), (2, 1));
+    assert_eq!(monitor.sync_monitor_data_to_global(7).await.unwrap(), 1);
+
+    transport::install(transport::from_name("offline").unwrap());
+}
+
+#[tokio::test]
+async fn test_lan_responder_answers_scan_query() {
+    let responder = LanResponder::spawn(&site(), SocketAddr::from(([127, 0, 0, 1], 0))).await.unwrap();
+
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    socket.send_to(&protocol::build_scan_query(), responder.local_addr()).await.unwrap();
+    let mut buf = [0u8; 512];
+    let (len, _) = tokio::time::timeout(Duration::from_secs(5), socket.recv_from(&mut buf))
+        .await
+        .expect("responder should answer")
+        .unwrap();
+
+    let device = protocol::parse_scan_response(&buf[..len]).expect("response should parse");
+    assert_eq!(device.serial_number, 4001);
+    assert_eq!(device.panel_number, 7);
+    assert_eq!(device.panel_name, "AHU-7");
+    assert_eq!(device.object_instance, 260001);
+    assert_eq!(device.ip_address, "10.0.0.7");
+    assert_eq!(device.bacnetip_port, 47808);
+}
+
+#[test]
+fn test_sim_is_selectable_by_name() {
+    assert_eq!(transport::from_name("sim").unwrap().name(), "sim");
+}
@@ -0,0 +1,194 @@
+//! Device transport tests — the refresh routes, the FFI pass-through service
+//! and the trendlog monitor running headless against a scripted transport.
+
+use std::sync::{Arc, Mutex as StdMutex};
+
+use async_trait::async_trait;
+use axum::http::StatusCode;
+use sea_orm::{ConnectionTrait, Database, Schema};
+use serde_json::{json, Value};
+use tokio::sync::Mutex;
+
+use t3_webview_api::app_state::T3AppState;
+use t3_webview_api::entity::t3_device::{devices, programs, protocol_settings};
+use t3_webview_api::t3_device::t3_ffi_api_service::T3000FfiApiService;
+use t3_webview_api::t3_device::transport::{
+    self, DeviceTransport, OfflineTransport, TransportError, TransportReply,
+};
+use t3_webview_api::t3_device::trendlog_monitor_service::TrendlogMonitorService;
+
+#[path = "../mcp/common.rs"]
+mod common;
+
+/// The installed transport is process-wide; tests that install one take this.
+static INSTALL_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// Answers every action with a canned reply and records what was sent.
+#[derive(Default)]
+struct ScriptedTransport {
+    replies: StdMutex<Vec<(i32, TransportReply)>>,
+    sent: StdMutex<Vec<(i32, Value)>>,
+}
+
+impl ScriptedTransport {
+    fn reply(self, action: i32, code: i32, body: Value) -> Self {
+        self.replies.lock().unwrap().push((action, TransportReply { code, body: body.to_string() }));
+        self
+    }
+
+    fn sent(&self) -> Vec<(i32, Value)> {
+        self.sent.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl DeviceTransport for ScriptedTransport {
+    fn name(&self) -> &'static str {
+        "scripted"
+    }
+
+    async fn is_available(&self) -> bool {
+        true
+    }
+
+    async fn send(&self, action: i32, request: &str) -> Result<TransportReply, TransportError> {
+        self.sent
+            .lock()
+            .unwrap()
+            .push((action, serde_json::from_str(request).unwrap_or(Value::Null)));
+        self.replies
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map(|(_, reply)| reply.clone())
+            .ok_or(TransportError::Failed(-1))
+    }
+
+    async fn trendlog_list(&self, panel_id: i32) -> Result<String, TransportError> {
+        Ok(json!({
+            "success": true,
+            "panel_id": panel_id,
+            "total_monitors": 1,
+            "trendlogs": [{
+                "num": 0, "id": "MON1", "label": "AHU", "interval_seconds": 60,
+                "interval_text": "1m", "status": "ON", "status_code": 1,
+                "data_size_kb": 1.5, "data_size_text": "1.50", "num_inputs": 2, "an_inputs": 2
+            }],
+            "timestamp": 0
+        })
+        .to_string())
+    }
+
+    async fn trendlog_entry(&self, _panel_id: i32, _monitor_index: i32) -> Result<String, TransportError> {
+        Err(TransportError::Failed(-1))
+    }
+}
+
+async fn state() -> T3AppState {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    let backend = db.get_database_backend();
+    let schema = Schema::new(backend);
+    db.execute(backend.build(&schema.create_table_from_entity(devices::Entity))).await.unwrap();
+    db.execute(backend.build(&schema.create_table_from_entity(protocol_settings::Entity))).await.unwrap();
+    db.execute(backend.build(&schema.create_table_from_entity(programs::Entity))).await.unwrap();
+    db.execute_unprepared(
+        "INSERT INTO DEVICES (SerialNumber, PanelId, Panel_Number) VALUES (4001, 7, 7); \
+         INSERT INTO PROTOCOL_SETTINGS (SerialNumber, Object_Instance) VALUES (4001, 260001);",
+    )
+    .await
+    .unwrap();
+    common::app_state(&db)
+}
+
+#[tokio::test]
+async fn test_refresh_route_goes_through_installed_transport() {
+    let _lock = INSTALL_LOCK.lock().await;
+    let scripted = Arc::new(ScriptedTransport::default().reply(
+        17,
+        0,
+        json!({ "success": true, "items": [{ "programIndex": 0, "fullLabel": "MAIN" }] }),
+    ));
+    transport::install(scripted.clone());
+
+    let app = t3_webview_api::t3_device::program_refresh_routes::create_program_refresh_routes()
+        .with_state(state().await);
+    let (status, body) = common::send(&app, "POST", "/programs/4001/refresh", json!({ "index": 0 })).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["count"], 1);
+
+    let sent = scripted.sent();
+    assert_eq!(sent.len(), 1);
+    let (action, request) = &sent[0];
+    assert_eq!(*action, 17);
+    assert_eq!(request["panelId"], 7);
+    assert_eq!(request["objectinstance"], 260001);
+    assert_eq!(request["entryType"], 6);
+    assert_eq!(request["entryIndex"], 0);
+
+    // A failing transport surfaces as a route error rather than a panic.
+    transport::install(Arc::new(OfflineTransport));
+    let (status, _) = common::send(&app, "POST", "/programs/4001/refresh", json!({})).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+#[tokio::test]
+async fn test_ffi_call_service_keeps_code_mapping() {
+    let _lock = INSTALL_LOCK.lock().await;
+    transport::install(Arc::new(
+        ScriptedTransport::default()
+            .reply(0, 0, json!({ "action": "GET_PANEL_DATA_RES", "data": [] }))
+            .reply(6, -1, json!({ "error": "device offline" }))
+            .reply(3, -2, json!({})),
+    ));
+    let service = T3000FfiApiService::new();
+
+    let ok = service.call_ffi(r#"{"action":0,"panelId":1}"#).await.unwrap();
+    assert!(ok.contains("GET_PANEL_DATA_RES"));
+    // -1 with a JSON body is passed through for the frontend to show.
+    let offline = service.call_ffi(r#"{"header":{},"message":{"action":6}}"#).await.unwrap();
+    assert!(offline.contains("device offline"));
+    let err = service.call_ffi(r#"{"action":3}"#).await.unwrap_err();
+    assert!(err.to_string().contains("MFC application not initialized"));
+}
+
+#[tokio::test]
+async fn test_typed_helpers_and_trendlog_monitor() {
+    let _lock = INSTALL_LOCK.lock().await;
+    let scripted = Arc::new(
+        ScriptedTransport::default()
+            .reply(15, 0, json!({ "action": "LOGGING_DATA_RES", "devices": [] }))
+            .reply(16, 0, json!({ "success": true })),
+    );
+    transport::install(scripted.clone());
+
+    let current = transport::current();
+    assert_eq!(current.name(), "scripted");
+    assert_eq!(current.logging_data().await.unwrap()["action"], "LOGGING_DATA_RES");
+    let update = json!({ "panelId": 7, "entryType": 1, "entryIndex": 3 });
+    assert_eq!(current.update_webview_list(&update).await.unwrap()["success"], true);
+    assert_eq!(scripted.sent()[1], (16, update));
+    assert!(matches!(current.get_panel_data(7).await, Err(TransportError::Failed(-1))));
+
+    let db = state().await.conn;
+    let monitor = TrendlogMonitorService::new(db);
+    let list = monitor.get_trendlog_list(7).await.unwrap();
+    assert_eq!(list.trendlogs.len(), 1);
+    assert_eq!(list.trendlogs[0].label, "AHU");
+    // Entry failures fall back to a placeholder instead of erroring.
+    let entry = monitor.get_trendlog_entry(7, 0).await.unwrap();
+    assert_eq!(entry.trendlog.status_code, -1);
+    assert_eq!(monitor.sync_monitor_data_to_global(7).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_offline_transport_and_selection() {
+    let offline = OfflineTransport;
+    assert!(!offline.is_available().await);
+    assert!(matches!(offline.send(0, "{}").await, Err(TransportError::Unavailable(_))));
+    assert!(matches!(offline.trendlog_list(1).await, Err(TransportError::Unavailable(_))));
+
+    assert_eq!(transport::from_name("offline").unwrap().name(), "offline");
+    assert_eq!(transport::from_name(" FFI ").unwrap().name(), "ffi");
+    assert!(transport::from_name("serial").is_err());
+}
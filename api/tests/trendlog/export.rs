@@ -0,0 +1,317 @@
+// Columnar export — long/wide layouts, Parquet and Arrow IPC round trips,
+// point metadata and archived partitions.
+
+use arrow_array::{Array, Float64Array, Int32Array, RecordBatch, StringArray, TimestampSecondArray};
+use arrow_schema::SchemaRef;
+use axum::body::Bytes;
+use chrono::NaiveDateTime;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use sea_orm::{ConnectionTrait, Database, DatabaseConnection};
+use t3_webview_api::db_schema::EMBEDDED_SCHEMA;
+use t3_webview_api::server_db::trendlog_detail as detail;
+use t3_webview_api::server_db::trendlog_export::{
+    ExportFormat, ExportLayout, ExportPlan, ExportRequest, ExportSummary, PointKey,
+};
+
+async fn setup(url: &str) -> DatabaseConnection {
+    let db = Database::connect(url).await.unwrap();
+    db.execute_unprepared(EMBEDDED_SCHEMA).await.unwrap();
+    db.execute_unprepared(
+        "INSERT INTO TRENDLOG_DATA (id, SerialNumber, PanelId, PointId, PointIndex, PointType, Units) \
+         VALUES (1, 1234, 1, 'IN1', 1, 'INPUT', 'C'), (2, 1234, 1, 'VAR3', 3, 'VARIABLE', '')",
+    )
+    .await
+    .unwrap();
+    db
+}
+
+async fn add_samples(db: &DatabaseConnection, samples: &[(i32, &str, &str)]) {
+    for (parent, value, time) in samples {
+        db.execute_unprepared(&format!(
+            "INSERT INTO TRENDLOG_DATA_DETAIL (ParentId, Value, LoggingTime_Fmt) VALUES ({parent}, '{value}', '{time}')"
+        ))
+        .await
+        .unwrap();
+    }
+}
+
+fn request(format: ExportFormat, layout: ExportLayout) -> ExportRequest {
+    ExportRequest {
+        format,
+        layout,
+        start: "2026-03-05".into(),
+        end: "2026-03-05".into(),
+        ..Default::default()
+    }
+}
+
+async fn export(db: &DatabaseConnection, request: &ExportRequest) -> (Vec<u8>, ExportSummary) {
+    let plan = ExportPlan::prepare(db, request).await.unwrap();
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<std::io::Result<Vec<u8>>>(2);
+    let collect = async move {
+        let mut out = Vec::new();
+        while let Some(chunk) = rx.recv().await {
+            out.extend(chunk.unwrap());
+        }
+        out
+    };
+    let run = async move { plan.run(&tx).await.unwrap() };
+    let (bytes, summary) = tokio::join!(collect, run);
+    (bytes, summary)
+}
+
+fn read_arrow(bytes: Vec<u8>) -> Vec<RecordBatch> {
+    let reader = arrow_ipc::reader::StreamReader::try_new(std::io::Cursor::new(bytes), None).unwrap();
+    reader.map(|b| b.unwrap()).collect()
+}
+
+fn read_parquet(bytes: Vec<u8>) -> (SchemaRef, Vec<RecordBatch>) {
+    let builder = ParquetRecordBatchReaderBuilder::try_new(Bytes::from(bytes)).unwrap();
+    let schema = builder.schema().clone();
+    (schema, builder.build().unwrap().map(|b| b.unwrap()).collect())
+}
+
+fn epoch(s: &str) -> i64 {
+    detail::local_epoch(NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap())
+}
+
+#[tokio::test]
+async fn long_layout_round_trips_through_arrow_ipc() {
+    let db = setup("sqlite::memory:").await;
+    add_samples(
+        &db,
+        &[(1, "21.5", "2026-03-05 08:00:00"), (2, "ON", "2026-03-05 08:00:00"), (1, "22", "2026-03-05 09:00:00")],
+    )
+    .await;
+    // Outside the range
+    add_samples(&db, &[(1, "99", "2026-03-06 00:00:00")]).await;
+
+    let mut req = request(ExportFormat::Arrow, ExportLayout::Long);
+    req.batch_rows = Some(2);
+    let (bytes, summary) = export(&db, &req).await;
+    assert_eq!((summary.rows, summary.points), (3, 2));
+    assert_eq!(summary.sources, vec!["main".to_string()]);
+
+    let batches = read_arrow(bytes);
+    assert_eq!(batches.iter().map(RecordBatch::num_rows).collect::<Vec<_>>(), vec![2, 1]);
+    let (first, second) = (&batches[0], &batches[1]);
+    let col = |batch: &RecordBatch, name: &str| batch.column_by_name(name).unwrap().clone();
+    let times = col(first, "timestamp");
+    let times = times.as_any().downcast_ref::<TimestampSecondArray>().unwrap();
+    assert_eq!(times.value(0), epoch("2026-03-05 08:00:00"));
+    assert_eq!(times.timezone(), Some("UTC"));
+    let ids = col(first, "point_id");
+    let ids = ids.as_any().downcast_ref::<StringArray>().unwrap();
+    assert_eq!((ids.value(0), ids.value(1)), ("IN1", "VAR3"));
+    let serials = col(second, "serial_number");
+    assert_eq!(serials.as_any().downcast_ref::<Int32Array>().unwrap().value(0), 1234);
+    let values = col(first, "value");
+    let values = values.as_any().downcast_ref::<Float64Array>().unwrap();
+    assert_eq!(values.value(0), 21.5);
+    // Non-numeric text is a null, not a zero
+    assert!(values.is_null(1));
+    let values = col(second, "value");
+    assert_eq!(values.as_any().downcast_ref::<Float64Array>().unwrap().value(0), 22.0);
+}
+
+#[tokio::test]
+async fn wide_parquet_carries_point_metadata() {
+    let db = setup("sqlite::memory:").await;
+    db.execute_unprepared(
+        "INSERT INTO VARIABLES (SerialNumber, Variable_Index, Full_Label, Label, Units) VALUES (1234, '3', 'Supply setpoint', 'SP', '%'); \
+         INSERT INTO HAYSTACK_POINT_TAGS (serial_number, point_type, point_index, point_id, tag_name) \
+         VALUES (1234, 'VARIABLE', '3', 'dev1234.var3', 'sp'), (1234, 'VARIABLE', '3', 'dev1234.var3', 'point')",
+    )
+    .await
+    .unwrap();
+    add_samples(
+        &db,
+        &[(1, "20", "2026-03-05 08:00:00"), (2, "55", "2026-03-05 08:00:00"), (1, "21", "2026-03-05 08:05:00")],
+    )
+    .await;
+
+    let (bytes, summary) = export(&db, &request(ExportFormat::Parquet, ExportLayout::Wide)).await;
+    assert_eq!(summary.rows, 2);
+    let (schema, batches) = read_parquet(bytes);
+
+    let names: Vec<&str> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+    assert_eq!(names, vec!["timestamp", "1234_IN1", "1234_VAR3"]);
+    let var = schema.field_with_name("1234_VAR3").unwrap().metadata();
+    assert_eq!(var.get("label").map(String::as_str), Some("Supply setpoint"));
+    // TRENDLOG_DATA had no units; the VARIABLES row fills them in
+    assert_eq!(var.get("units").map(String::as_str), Some("%"));
+    assert_eq!(var.get("tags").map(String::as_str), Some(r#"["point","sp"]"#));
+    assert_eq!(schema.field_with_name("1234_IN1").unwrap().metadata().get("units").map(String::as_str), Some("C"));
+    let points: serde_json::Value = serde_json::from_str(&schema.metadata()["t3.points"]).unwrap();
+    assert_eq!(points.as_array().unwrap().len(), 2);
+    assert!(schema.metadata()["t3.export"].contains("\"layout\":\"wide\""));
+
+    let batch = &batches[0];
+    let column = |name: &str| batch.column_by_name(name).unwrap().as_any().downcast_ref::<Float64Array>().unwrap().clone();
+    let (input, var) = (column("1234_IN1"), column("1234_VAR3"));
+    assert_eq!((input.value(0), var.value(0)), (20.0, 55.0));
+    assert_eq!(input.value(1), 21.0);
+    assert!(var.is_null(1));
+}
+
+#[tokio::test]
+async fn wide_rows_merge_samples_of_one_second_across_pages_and_sources() {
+    // One detail row per page: the two 08:00 samples arrive on separate pages
+    let db = setup("sqlite::memory:").await;
+    add_samples(&db, &[(1, "20", "2026-03-05 08:00:00"), (2, "55", "2026-03-05 08:00:00")]).await;
+    let mut req = request(ExportFormat::Arrow, ExportLayout::Wide);
+    req.batch_rows = Some(1);
+    let (_, summary) = export(&db, &req).await;
+    assert_eq!(summary.rows, 1);
+
+    // An archived partition overlapping the main database
+    let path = std::env::temp_dir().join(format!("export_overlap_{}.db", uuid::Uuid::new_v4()));
+    let partition = setup(&format!("sqlite://{}?mode=rwc", path.display())).await;
+    add_samples(&partition, &[(1, "20", "2026-03-05 08:00:00"), (1, "21", "2026-03-05 08:05:00")]).await;
+    partition.close().await.unwrap();
+    let db = setup("sqlite::memory:").await;
+    add_samples(&db, &[(2, "55", "2026-03-05 08:00:00"), (1, "22", "2026-03-05 08:10:00")]).await;
+    db.execute_unprepared(&format!(
+        "INSERT INTO DATABASE_FILES (file_name, file_path, partition_identifier, start_date, end_date, is_active, is_archived, last_accessed_at) \
+         VALUES ('mar.db', '{}', '2026-03', '2026-03-01 00:00:00', '2026-03-05 08:05:00', 0, 1, '2026-03-05 09:00:00')",
+        path.display()
+    ))
+    .await
+    .unwrap();
+
+    let (bytes, summary) = export(&db, &request(ExportFormat::Arrow, ExportLayout::Wide)).await;
+    std::fs::remove_file(&path).ok();
+    assert_eq!(summary.sources, vec!["2026-03".to_string(), "main".to_string()]);
+    assert_eq!(summary.rows, 3);
+
+    let batch = &read_arrow(bytes)[0];
+    let times = batch.column_by_name("timestamp").unwrap();
+    let times = times.as_any().downcast_ref::<TimestampSecondArray>().unwrap();
+    assert_eq!(
+        times.values().to_vec(),
+        vec![epoch("2026-03-05 08:00:00"), epoch("2026-03-05 08:05:00"), epoch("2026-03-05 08:10:00")]
+    );
+    let column = |name: &str| batch.column_by_name(name).unwrap().as_any().downcast_ref::<Float64Array>().unwrap().clone();
+    let (input, var) = (column("1234_IN1"), column("1234_VAR3"));
+    assert_eq!((input.value(0), var.value(0)), (20.0, 55.0));
+    assert_eq!((input.value(1), input.value(2)), (21.0, 22.0));
+    assert!(var.is_null(1) && var.is_null(2));
+}
+
+#[tokio::test]
+async fn wide_rows_merge_samples_of_one_second_across_batch_cuts() {
+    // A batch is cut after every row; the second samples of 08:00 and 08:05
+    // come from main after the partition, and one is spelled with a `T`
+    let path = std::env::temp_dir().join(format!("export_cut_{}.db", uuid::Uuid::new_v4()));
+    let partition = setup(&format!("sqlite://{}?mode=rwc", path.display())).await;
+    add_samples(&partition, &[(1, "20", "2026-03-05 08:00:00"), (1, "21", "2026-03-05 08:05:00")]).await;
+    partition.close().await.unwrap();
+    let db = setup("sqlite::memory:").await;
+    add_samples(
+        &db,
+        &[(2, "55", "2026-03-05 08:00:00"), (1, "22", "2026-03-05 08:10:00"), (2, "56", "2026-03-05T08:05:00")],
+    )
+    .await;
+    db.execute_unprepared(&format!(
+        "INSERT INTO DATABASE_FILES (file_name, file_path, partition_identifier, start_date, end_date, is_active, is_archived, last_accessed_at) \
+         VALUES ('mar.db', '{}', '2026-03', '2026-03-01 00:00:00', '2026-03-05 08:05:00', 0, 1, '2026-03-05 09:00:00')",
+        path.display()
+    ))
+    .await
+    .unwrap();
+
+    let mut req = request(ExportFormat::Arrow, ExportLayout::Wide);
+    // `T` sorts after the space, so the range must reach past the day
+    req.end = "2026-03-06".into();
+    req.batch_rows = Some(1);
+    let (bytes, summary) = export(&db, &req).await;
+    std::fs::remove_file(&path).ok();
+    assert_eq!(summary.rows, 3);
+
+    let batches = read_arrow(bytes);
+    assert!(batches.len() > 1);
+    let times: Vec<i64> = batches
+        .iter()
+        .flat_map(|b| {
+            let times = b.column_by_name("timestamp").unwrap();
+            times.as_any().downcast_ref::<TimestampSecondArray>().unwrap().values().to_vec()
+        })
+        .collect();
+    assert_eq!(
+        times,
+        vec![epoch("2026-03-05 08:00:00"), epoch("2026-03-05 08:05:00"), epoch("2026-03-05 08:10:00")]
+    );
+    let column = |name: &str| -> Vec<Option<f64>> {
+        batches
+            .iter()
+            .flat_map(|b| {
+                let values = b.column_by_name(name).unwrap();
+                values.as_any().downcast_ref::<Float64Array>().unwrap().iter().collect::<Vec<_>>()
+            })
+            .collect()
+    };
+    assert_eq!(column("1234_IN1"), vec![Some(20.0), Some(21.0), Some(22.0)]);
+    assert_eq!(column("1234_VAR3"), vec![Some(55.0), Some(56.0), None]);
+}
+
+#[tokio::test]
+async fn selected_points_span_archived_partitions() {
+    let path = std::env::temp_dir().join(format!("export_partition_{}.db", uuid::Uuid::new_v4()));
+    let partition = setup(&format!("sqlite://{}?mode=rwc", path.display())).await;
+    // Legacy partition without the numeric columns
+    partition.execute_unprepared("DROP TABLE TRENDLOG_DATA_DETAIL").await.unwrap();
+    partition
+        .execute_unprepared("CREATE TABLE TRENDLOG_DATA_DETAIL (ParentId INTEGER NOT NULL, Value TEXT NOT NULL, LoggingTime_Fmt TEXT NOT NULL)")
+        .await
+        .unwrap();
+    add_samples(&partition, &[(1, "10", "2026-02-28 23:00:00"), (2, "1", "2026-02-28 23:00:00")]).await;
+    partition.close().await.unwrap();
+
+    let db = setup("sqlite::memory:").await;
+    add_samples(&db, &[(1, "11", "2026-03-01 01:00:00")]).await;
+    db.execute_unprepared(&format!(
+        "INSERT INTO DATABASE_FILES (file_name, file_path, partition_identifier, start_date, end_date, is_active, is_archived, last_accessed_at) \
+         VALUES ('feb.db', '{}', '2026-02', '2026-02-01 00:00:00', '2026-02-28 23:59:59', 0, 1, '2026-03-01 00:00:00'), \
+                ('jan.db', '/nonexistent/jan.db', '2026-01', '2026-01-01 00:00:00', '2026-01-31 23:59:59', 0, 1, '2026-02-01 00:00:00')",
+        path.display()
+    ))
+    .await
+    .unwrap();
+
+    let req = ExportRequest {
+        format: ExportFormat::Arrow,
+        start: "2026-01-15 00:00:00".into(),
+        end: "2026-03-31T00:00:00".into(),
+        points: vec![PointKey { serial_number: 1234, point_type: "INPUT".into(), point_index: 1 }],
+        ..Default::default()
+    };
+    let (bytes, summary) = export(&db, &req).await;
+    std::fs::remove_file(&path).ok();
+
+    assert_eq!(summary.sources, vec!["2026-02".to_string(), "main".to_string()]);
+    assert_eq!(summary.skipped.len(), 1);
+    assert!(summary.skipped[0].starts_with("2026-01"));
+    let batches = read_arrow(bytes);
+    let values: Vec<f64> = batches
+        .iter()
+        .flat_map(|b| b.column_by_name("value").unwrap().as_any().downcast_ref::<Float64Array>().unwrap().values().to_vec())
+        .collect();
+    assert_eq!(values, vec![10.0, 11.0]);
+}
+
+#[tokio::test]
+async fn invalid_requests_fail_before_streaming() {
+    let db = setup("sqlite::memory:").await;
+    let mut req = request(ExportFormat::Parquet, ExportLayout::Long);
+    req.start = "yesterday".into();
+    assert!(ExportPlan::prepare(&db, &req).await.is_err());
+
+    let mut req = request(ExportFormat::Parquet, ExportLayout::Long);
+    req.start = "2026-03-06".into();
+    assert!(ExportPlan::prepare(&db, &req).await.is_err());
+
+    // No data still yields a readable, empty file
+    let (bytes, summary) = export(&db, &request(ExportFormat::Parquet, ExportLayout::Long)).await;
+    assert_eq!(summary.rows, 0);
+    assert!(read_parquet(bytes).1.is_empty());
+}
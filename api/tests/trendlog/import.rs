@@ -0,0 +1,245 @@
+// CSV import — wide and long mappings, dedupe, rejected rows, quoted fields
+// and routing into existing and newly created partition files.
+
+use sea_orm::{ConnectionTrait, Database, DatabaseConnection, Statement};
+use t3_webview_api::db_schema::EMBEDDED_SCHEMA;
+use t3_webview_api::server_db::trendlog_import::{
+    import_csv, ColumnMapping, ImportMapping, ImportOptions, ImportSummary, LongMapping, MAX_RECORD_BYTES,
+    MAX_RECORD_FIELDS,
+};
+use t3_webview_api::error::Error;
+
+async fn setup(url: &str) -> DatabaseConnection {
+    let db = Database::connect(url).await.unwrap();
+    db.execute_unprepared(EMBEDDED_SCHEMA).await.unwrap();
+    db.execute_unprepared(
+        "INSERT INTO TRENDLOG_DATA (id, SerialNumber, PanelId, PointId, PointIndex, PointType, Units) \
+         VALUES (1, 1234, 1, 'IN1', 1, 'INPUT', 'C')",
+    )
+    .await
+    .unwrap();
+    db
+}
+
+fn wide(columns: &[(&str, &str, i32)]) -> ImportMapping {
+    ImportMapping {
+        time_column: "time".into(),
+        columns: columns
+            .iter()
+            .map(|(column, point_type, point_index)| ColumnMapping {
+                column: column.to_string(),
+                serial_number: 1234,
+                point_type: point_type.to_string(),
+                point_index: *point_index,
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+fn options(dir: &std::path::Path) -> ImportOptions {
+    ImportOptions { partition_dir: dir.to_path_buf(), batch_size: 3 }
+}
+
+async fn import(db: &DatabaseConnection, mapping: &ImportMapping, csv: &str) -> ImportSummary {
+    import_csv(db, mapping, csv.as_bytes(), &options(&std::env::temp_dir())).await.unwrap()
+}
+
+async fn scalar(db: &DatabaseConnection, sql: &str) -> i64 {
+    let row = db
+        .query_one(Statement::from_string(db.get_database_backend(), sql.to_string()))
+        .await
+        .unwrap()
+        .unwrap();
+    row.try_get_by_index(0).unwrap()
+}
+
+#[tokio::test]
+async fn wide_file_imports_into_main_and_skips_duplicates() {
+    let db = setup("sqlite::memory:").await;
+    db.execute_unprepared("INSERT INTO TRENDLOG_DATA_DETAIL (ParentId, Value, LoggingTime_Fmt) VALUES (1, '20', '2026-03-05 08:00:00')")
+        .await
+        .unwrap();
+    db.execute_unprepared("INSERT INTO DEVICES (SerialNumber, PanelId) VALUES (1234, 7)").await.unwrap();
+
+    let mapping = wide(&[("Room", "IN", 1), ("Fan", "OUTPUT", 2)]);
+    let csv = "time,Room,Fan\n\
+               2026-03-05 08:00:00,20,1\n\
+               2026-03-05 08:05,21.5,\n\
+               2026-03-05T08:10:00,22,0\n";
+    let summary = import(&db, &mapping, csv).await;
+    assert_eq!((summary.records, summary.imported, summary.duplicates, summary.blank), (3, 4, 1, 1));
+    assert!(summary.rollups_rebuilt);
+
+    let room = &summary.points[0];
+    assert_eq!((room.parent_id, room.imported, room.duplicates), (1, 2, 1));
+    assert_eq!(room.first_time.as_deref(), Some("2026-03-05 08:05:00"));
+    assert_eq!(room.targets.get("main"), Some(&2));
+    // New parent takes the device's panel and the usual point id
+    let fan = &summary.points[1];
+    assert_eq!((fan.point_id.as_str(), fan.imported), ("OUT2", 2));
+    assert_eq!(
+        scalar(&db, &format!("SELECT PanelId FROM TRENDLOG_DATA WHERE id = {}", fan.parent_id)).await,
+        7
+    );
+    assert_eq!(
+        scalar(&db, "SELECT COUNT(*) FROM TRENDLOG_DATA_DETAIL WHERE NumericValue = 21.5 AND LoggingTime IS NOT NULL").await,
+        1
+    );
+    assert_eq!(scalar(&db, "SELECT COUNT(*) FROM TRENDLOG_ROLLUP_HOURLY").await, 2);
+
+    // Re-importing the same file adds nothing
+    let again = import(&db, &mapping, csv).await;
+    assert_eq!((again.imported, again.duplicates), (0, 5));
+    assert!(!again.rollups_rebuilt);
+    assert_eq!(scalar(&db, "SELECT COUNT(*) FROM TRENDLOG_DATA_DETAIL").await, 5);
+}
+
+#[tokio::test]
+async fn long_file_reports_rejected_rows() {
+    let db = setup("sqlite::memory:").await;
+    let mapping = ImportMapping {
+        time_column: "ts".into(),
+        long: Some(LongMapping {
+            serial_column: "serial".into(),
+            point_type_column: "type".into(),
+            point_index_column: "index".into(),
+            value_column: "value".into(),
+        }),
+        panel_id: Some(3),
+        rebuild_rollups: Some(false),
+        ..Default::default()
+    };
+    let csv = "serial,type,index,ts,value\n\
+               1234,input,1,2026-03-05 08:00:00,20\n\
+               1234,VAR,4,2026-03-05 08:00:00,1\n\
+               1234,VAR,4,2026-03-05 08:05:00,ON\n\
+               1234,INPUT,1,not a time,21\n\
+               1234,SENSOR,1,2026-03-05 09:00:00,22\n\
+               1234,INPUTS,1,2026-03-05 08:00:00,20\n\
+               1234,INPUT,1,2026-03-05 08:10:00,\"1,5\"\n";
+    let summary = import(&db, &mapping, csv).await;
+    assert_eq!((summary.records, summary.imported, summary.duplicates, summary.rejected), (7, 2, 1, 4));
+    assert_eq!(summary.rejected_samples.len(), 4);
+    assert!(summary.rejected_samples[0].starts_with("row 4: non-numeric value 'ON'"));
+    assert!(summary.rejected_samples[1].starts_with("row 5: invalid time"));
+    assert!(summary.rejected_samples[2].starts_with("row 6: invalid point"));
+    assert!(summary.rejected_samples[3].starts_with("row 8: non-numeric value '1,5'"));
+    assert!(!summary.rollups_rebuilt);
+
+    let var = &summary.points[1];
+    assert_eq!((var.point_type.as_str(), var.point_id.as_str()), ("VARIABLE", "VAR4"));
+    assert_eq!(scalar(&db, "SELECT PanelId FROM TRENDLOG_DATA WHERE PointId = 'VAR4'").await, 3);
+    // Non-numeric values are not imported
+    assert_eq!(scalar(&db, "SELECT COUNT(*) FROM TRENDLOG_DATA_DETAIL WHERE NumericValue IS NULL").await, 0);
+
+    // Mapping errors are caught from the header
+    let mut bad = mapping.clone();
+    bad.time_column = "when".into();
+    assert!(import_csv(&db, &bad, csv.as_bytes(), &ImportOptions::default()).await.is_err());
+    assert!(import_csv(&db, &mapping, "".as_bytes(), &ImportOptions::default()).await.is_err());
+}
+
+#[tokio::test]
+async fn quoted_fields_custom_delimiter_and_time_format() {
+    let db = setup("sqlite::memory:").await;
+    let mut mapping = wide(&[("Room \"A\"; north", "INPUT", 1)]);
+    mapping.time_column = "Time".into();
+    mapping.delimiter = Some(';');
+    mapping.time_format = Some("%d/%m/%Y %H:%M".into());
+
+    let csv = "\"Time\";\"Room \"\"A\"\"; north\";\"Note\"\n\
+               05/03/2026 08:00;\"20.5\";\"spans\ntwo lines\"\n\
+               05/03/2026 08:15;21;\n";
+    let summary = import(&db, &mapping, csv).await;
+    assert_eq!((summary.records, summary.imported), (2, 2));
+    assert_eq!(summary.points[0].last_time.as_deref(), Some("2026-03-05 08:15:00"));
+    assert_eq!(scalar(&db, "SELECT COUNT(*) FROM TRENDLOG_DATA_DETAIL WHERE Value = '20.5'").await, 1);
+}
+
+#[tokio::test]
+async fn oversized_records_are_rejected() {
+    let db = setup("sqlite::memory:").await;
+    let mapping = wide(&[("IN1", "INPUT", 1)]);
+
+    // An unterminated quote swallows everything after it into one field.
+    let csv = format!("time,IN1\n2026-03-05 08:00:00,\"{}", "x".repeat(MAX_RECORD_BYTES + 1));
+    let err = import_csv(&db, &mapping, csv.as_bytes(), &ImportOptions::default()).await.unwrap_err();
+    assert!(matches!(err, Error::ValidationError(_)), "{:?}", err);
+
+    let csv = format!("time,IN1\n2026-03-05 08:00:00,20{}\n", ",".repeat(MAX_RECORD_FIELDS));
+    let err = import_csv(&db, &mapping, csv.as_bytes(), &ImportOptions::default()).await.unwrap_err();
+    assert!(matches!(err, Error::ValidationError(_)), "{:?}", err);
+}
+
+#[tokio::test]
+async fn samples_are_routed_into_partition_files() {
+    let dir = std::env::temp_dir().join(format!("import_partitions_{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let feb_path = dir.join("webview_t3_device_2026-02.db");
+    let feb = setup(&format!("sqlite://{}?mode=rwc", feb_path.display())).await;
+    feb.execute_unprepared("INSERT INTO TRENDLOG_DATA_DETAIL (ParentId, Value, LoggingTime_Fmt) VALUES (1, '10', '2026-02-10 00:00:00')")
+        .await
+        .unwrap();
+    feb.close().await.unwrap();
+
+    let db = setup("sqlite::memory:").await;
+    db.execute_unprepared(&format!(
+        "INSERT INTO DATABASE_FILES (file_name, file_path, partition_identifier, record_count, start_date, end_date, is_active, is_archived, last_accessed_at) \
+         VALUES ('webview_t3_device_2026-02.db', '{}', '2026-02', 1, '2026-02-01 00:00:00', '2026-02-28 23:59:59', 0, 0, '2026-03-01 00:00:00')",
+        feb_path.display()
+    ))
+    .await
+    .unwrap();
+    // Main still holds a January row from before partitioning
+    db.execute_unprepared("INSERT INTO TRENDLOG_DATA_DETAIL (ParentId, Value, LoggingTime_Fmt) VALUES (1, '5', '2026-01-20 00:00:00')")
+        .await
+        .unwrap();
+
+    let mapping = wide(&[("t1", "INPUT", 1), ("v9", "VARIABLE", 9)]);
+    let csv = "time,t1,v9\n\
+               2026-01-15 12:00:00,1,100\n\
+               2026-02-10 00:00:00,10,200\n\
+               2026-02-11 00:00:00,11,\n\
+               2026-03-02 00:00:00,12,300\n";
+    let summary = import_csv(&db, &mapping, csv.as_bytes(), &options(&dir)).await.unwrap();
+    assert_eq!(summary.partitions_created, vec!["2026-01".to_string()]);
+    assert_eq!((summary.imported, summary.duplicates), (6, 1));
+    let t1 = &summary.points[0];
+    assert_eq!(t1.targets.get("2026-01"), Some(&1));
+    assert_eq!(t1.targets.get("2026-02"), Some(&1));
+    assert_eq!(t1.targets.get("main"), Some(&1));
+    let v9_id = summary.points[1].parent_id;
+
+    // Only March stays in main; main's January row moved into the new file
+    assert_eq!(scalar(&db, "SELECT COUNT(*) FROM TRENDLOG_DATA_DETAIL WHERE LoggingTime_Fmt >= '2026-03-01'").await, 2);
+    assert_eq!(scalar(&db, "SELECT COUNT(*) FROM TRENDLOG_DATA_DETAIL WHERE LoggingTime_Fmt < '2026-03-01'").await, 0);
+    assert_eq!(scalar(&db, "SELECT record_count FROM DATABASE_FILES WHERE partition_identifier = '2026-02'").await, 3);
+
+    // The new point's parent was mirrored into the existing partition with the same id
+    let feb = Database::connect(format!("sqlite://{}?mode=ro", feb_path.display())).await.unwrap();
+    assert_eq!(scalar(&feb, "SELECT COUNT(*) FROM TRENDLOG_DATA_DETAIL").await, 3);
+    assert_eq!(
+        scalar(&feb, &format!("SELECT COUNT(*) FROM TRENDLOG_DATA_DETAIL d JOIN TRENDLOG_DATA p ON p.id = d.ParentId WHERE p.id = {} AND p.PointId = 'VAR9'", v9_id)).await,
+        1
+    );
+    feb.close().await.unwrap();
+
+    // The January file holds main's January row plus the imported ones
+    let jan_path: String = db
+        .query_one(Statement::from_string(
+            db.get_database_backend(),
+            "SELECT file_path FROM DATABASE_FILES WHERE partition_identifier = '2026-01'".to_string(),
+        ))
+        .await
+        .unwrap()
+        .unwrap()
+        .try_get("", "file_path")
+        .unwrap();
+    assert_eq!(scalar(&db, "SELECT record_count FROM DATABASE_FILES WHERE partition_identifier = '2026-01'").await, 3);
+    let jan = Database::connect(format!("sqlite://{}?mode=ro", jan_path)).await.unwrap();
+    assert_eq!(scalar(&jan, "SELECT COUNT(*) FROM TRENDLOG_DATA_DETAIL WHERE LoggingTime IS NOT NULL").await, 3);
+    jan.close().await.unwrap();
+
+    std::fs::remove_dir_all(&dir).ok();
+}
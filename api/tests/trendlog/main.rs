@@ -0,0 +1,205 @@
+//! Trendlog storage tests: history aggregation, hourly/daily rollups,
+//! numeric detail storage, columnar export and CSV import.
+//!
+//! The aggregation functions are pure, so those tests run without a
+//! database; only the row-cap test goes through the history query.
+
+mod export;
+mod import;
+mod numeric;
+mod rollup;
+
+use sea_orm::{ConnectionTrait, Database};
+use t3_webview_api::db_schema::EMBEDDED_SCHEMA;
+use t3_webview_api::server_db::trendlog_aggregation::{
+    self as agg, AggFn, AggregationMode, AggregationRequest, HistoryRow,
+};
+use t3_webview_api::t3_device::trendlog_data_service::{T3TrendlogDataService, TrendlogHistoryRequest};
+
+fn request(json: serde_json::Value) -> AggregationRequest {
+    serde_json::from_value(json).unwrap()
+}
+
+#[test]
+fn auto_bucket_width_lands_on_round_sizes() {
+    let req = AggregationRequest::default();
+    // One day at 1000 points → 86.4 s → 2 minute buckets
+    assert_eq!(req.bucket_seconds(86_400), 120);
+    // A year at 1000 points → 8.76 hours → 12 hour buckets
+    assert_eq!(req.bucket_seconds(365 * 86_400), 43_200);
+    // Ten years → beyond a day, rounded up to whole days
+    assert_eq!(req.bucket_seconds(10 * 365 * 86_400), 4 * 86_400);
+
+    let explicit = request(serde_json::json!({ "bucket_seconds": 900 }));
+    assert_eq!(explicit.bucket_seconds(365 * 86_400), 900);
+    let fewer = request(serde_json::json!({ "target_points": 24 }));
+    assert_eq!(fewer.bucket_seconds(86_400), 3600);
+}
+
+#[test]
+fn buckets_compute_each_function() {
+    let samples = [(0, 1.0), (10, 3.0), (50, 2.0), (60, 10.0), (90, 20.0)];
+    let fns = [AggFn::Avg, AggFn::Min, AggFn::Max, AggFn::First, AggFn::Last, AggFn::Count];
+    let out = agg::buckets(&samples, 60, &fns);
+
+    assert_eq!(out.len(), 2);
+    assert_eq!(out[0].time, 0);
+    assert_eq!(out[0].count, 3);
+    assert_eq!(out[0].value, 2.0);
+    assert_eq!(out[0].aggregates[&AggFn::Min], 1.0);
+    assert_eq!(out[0].aggregates[&AggFn::Max], 3.0);
+    assert_eq!(out[0].aggregates[&AggFn::First], 1.0);
+    assert_eq!(out[0].aggregates[&AggFn::Last], 2.0);
+    assert_eq!(out[0].aggregates[&AggFn::Count], 3.0);
+    assert_eq!(out[1].time, 60);
+    assert_eq!(out[1].value, 15.0);
+}
+
+#[test]
+fn empty_buckets_are_left_out() {
+    let samples = [(0, 1.0), (400, 2.0)];
+    let out = agg::buckets(&samples, 60, &[AggFn::Avg]);
+    assert_eq!(out.iter().map(|p| p.time).collect::<Vec<_>>(), vec![0, 360]);
+}
+
+#[test]
+fn time_weighted_average_follows_how_long_values_held() {
+    // 0 for 50 s, then 100 for the last 10 s of the bucket
+    let samples = [(0, 0.0), (50, 100.0)];
+    let out = agg::buckets(&samples, 60, &[AggFn::TimeWeightedAvg, AggFn::Avg]);
+    assert!((out[0].value - 100.0 * 10.0 / 60.0).abs() < 1e-9);
+    assert_eq!(out[0].aggregates[&AggFn::Avg], 50.0);
+
+    // The value before a bucket carries into its start
+    let samples = [(0, 10.0), (90, 40.0)];
+    let out = agg::buckets(&samples, 60, &[AggFn::TimeWeightedAvg]);
+    assert_eq!(out[1].time, 60);
+    assert!((out[1].value - (10.0 * 30.0 + 40.0 * 30.0) / 60.0).abs() < 1e-9);
+}
+
+#[test]
+fn on_fraction_measures_digital_on_time() {
+    // On at 0, off at 15, on at 45: on for 30 of 60 seconds
+    let samples = [(0, 1.0), (15, 0.0), (45, 1.0)];
+    let out = agg::buckets(&samples, 60, &[AggFn::OnFraction]);
+    assert!((out[0].value - 0.5).abs() < 1e-9);
+}
+
+#[test]
+fn lttb_keeps_endpoints_and_peaks() {
+    let mut samples: Vec<(i64, f64)> = (0..1000).map(|t| (t, 0.0)).collect();
+    samples[500].1 = 100.0;
+    samples[750].1 = -50.0;
+
+    let out = agg::lttb(&samples, 50);
+    assert_eq!(out.len(), 50);
+    assert_eq!(out[0], (0, 0.0));
+    assert_eq!(out[49], (999, 0.0));
+    assert!(out.contains(&(500, 100.0)));
+    assert!(out.contains(&(750, -50.0)));
+    assert!(out.windows(2).all(|w| w[0].0 < w[1].0));
+
+    // Nothing to do when the series is already small enough
+    assert_eq!(agg::lttb(&samples[..10], 50).len(), 10);
+}
+
+#[test]
+fn history_rows_are_aggregated_per_point() {
+    let times: Vec<String> = (0..120).map(|m| agg::format_time(1_700_000_000 / 3600 * 3600 + m * 60)).collect();
+    let mut rows = Vec::new();
+    for (m, time) in times.iter().enumerate() {
+        rows.push(HistoryRow {
+            time,
+            value: m as f64,
+            point_id: "IN1",
+            point_type: "INPUT",
+            point_index: 1,
+            units: Some("degC"),
+            range: None,
+            is_analog: true,
+        });
+        rows.push(HistoryRow {
+            time,
+            value: if m % 4 == 0 { 1.0 } else { 0.0 },
+            point_id: "OUT2",
+            point_type: "OUTPUT",
+            point_index: 2,
+            units: None,
+            range: None,
+            is_analog: false,
+        });
+    }
+
+    let req = request(serde_json::json!({ "bucket_seconds": 3600 }));
+    let (data, summary) = agg::aggregate_history_rows(rows, &req, None);
+
+    assert_eq!(summary.mode, AggregationMode::Buckets);
+    assert_eq!(summary.bucket_seconds, Some(3600));
+    assert_eq!(summary.raw_points, 240);
+    assert_eq!(summary.series, 2);
+    assert_eq!(data.len(), 4);
+
+    // Newest first, like the raw query
+    assert!(data[0]["time"].as_str().unwrap() > data[3]["time"].as_str().unwrap());
+    let analog = data.iter().find(|r| r["point_id"] == "IN1").unwrap();
+    assert_eq!(analog["count"], 60);
+    assert_eq!(analog["units"], "degC");
+    assert!(analog["aggregates"]["min"].is_number() && analog["aggregates"]["max"].is_number());
+    let digital = data.iter().find(|r| r["point_id"] == "OUT2").unwrap();
+    assert!((digital["aggregates"]["on_fraction"].as_f64().unwrap() - 0.25).abs() < 1e-9);
+}
+
+#[test]
+fn request_accepts_function_aliases() {
+    let req = request(serde_json::json!({ "mode": "lttb", "functions": ["twa", "on_fraction"] }));
+    assert_eq!(req.mode, AggregationMode::Lttb);
+    assert_eq!(req.functions, vec![AggFn::TimeWeightedAvg, AggFn::OnFraction]);
+    assert_eq!(agg::parse_time("2026-03-01T12:00:00"), agg::parse_time("2026-03-01 12:00:00"));
+}
+
+#[tokio::test]
+async fn history_query_reports_when_the_row_cap_truncates() {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    db.execute_unprepared(EMBEDDED_SCHEMA).await.unwrap();
+    db.execute_unprepared(
+        "INSERT INTO TRENDLOG_DATA (id, SerialNumber, PanelId, PointId, PointIndex, PointType) \
+         VALUES (1, 1234, 1, 'IN1', 1, 'INPUT')",
+    )
+    .await
+    .unwrap();
+    for m in 0..5 {
+        db.execute_unprepared(&format!(
+            "INSERT INTO TRENDLOG_DATA_DETAIL (ParentId, Value, LoggingTime_Fmt) VALUES (1, '{}', '2026-03-05 08:0{}:00')",
+            m * 1000,
+            m
+        ))
+        .await
+        .unwrap();
+    }
+
+    let history = |limit| TrendlogHistoryRequest {
+        serial_number: 1234,
+        panel_id: 1,
+        trendlog_id: "1".into(),
+        start_time: Some("2026-03-05 00:00:00".into()),
+        end_time: Some("2026-03-06 00:00:00".into()),
+        limit,
+        point_types: None,
+        specific_points: None,
+        aggregation: Some(request(serde_json::json!({ "bucket_seconds": 3600 }))),
+    };
+
+    let full = T3TrendlogDataService::get_trendlog_history(&db, history(None)).await.unwrap();
+    assert_eq!(full["aggregation"]["raw_points"], 5);
+    assert_eq!(full["aggregation"]["truncated"], false);
+    assert_eq!(full["data"][0]["count"], 5);
+
+    // The newest rows are read first, so a cap drops the start of the range
+    let capped = T3TrendlogDataService::get_trendlog_history(&db, history(Some(3))).await.unwrap();
+    assert_eq!(capped["aggregation"]["raw_points"], 3);
+    assert_eq!(capped["aggregation"]["truncated"], true);
+    assert_eq!(capped["data"][0]["aggregates"]["min"], 2.0);
+
+    let exact = T3TrendlogDataService::get_trendlog_history(&db, history(Some(5))).await.unwrap();
+    assert_eq!(exact["aggregation"]["truncated"], false);
+}
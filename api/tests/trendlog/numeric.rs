@@ -0,0 +1,177 @@
+// Numeric detail storage — layout detection, range SQL over mixed rows and
+// the online backfill job (main database and legacy partition files).
+
+use chrono::NaiveDateTime;
+use sea_orm::{ConnectionTrait, Database, DatabaseConnection, Statement};
+use t3_webview_api::db_schema::EMBEDDED_SCHEMA;
+use t3_webview_api::server_db::trendlog_detail::{self as detail, DetailLayout};
+use t3_webview_api::t3_device::migrate_trendlog_numeric::{self as job, BackfillOptions};
+
+const LEGACY_DETAIL: &str = "CREATE TABLE TRENDLOG_DATA_DETAIL (\
+    ParentId INTEGER NOT NULL, Value TEXT NOT NULL, LoggingTime_Fmt TEXT NOT NULL)";
+
+fn at(s: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap()
+}
+
+fn quick() -> BackfillOptions {
+    BackfillOptions { batch_size: 2, pause: std::time::Duration::ZERO }
+}
+
+async fn insert_text_rows(db: &DatabaseConnection, rows: &[(&str, &str)]) {
+    for (value, time) in rows {
+        db.execute_unprepared(&format!(
+            "INSERT INTO TRENDLOG_DATA_DETAIL (ParentId, Value, LoggingTime_Fmt) VALUES (1, '{value}', '{time}')"
+        ))
+        .await
+        .unwrap();
+    }
+}
+
+async fn count(db: &DatabaseConnection, where_sql: &str) -> i64 {
+    let row = db
+        .query_one(Statement::from_string(
+            db.get_database_backend(),
+            format!("SELECT COUNT(*) AS n FROM TRENDLOG_DATA_DETAIL d WHERE {}", where_sql),
+        ))
+        .await
+        .unwrap()
+        .unwrap();
+    row.try_get("", "n").unwrap()
+}
+
+#[test]
+fn stored_forms_parse_text_columns() {
+    assert_eq!(detail::numeric_value(" 21.5 "), Some(21.5));
+    assert_eq!(detail::numeric_value("NaN"), None);
+    assert_eq!(detail::numeric_value("ON"), None);
+
+    let t = at("2026-03-05 14:27:09");
+    assert_eq!(detail::epoch_from_fmt("2026-03-05 14:27:09"), Some(detail::local_epoch(t)));
+    assert_eq!(detail::epoch_from_fmt("2026-03-05T14:27:09"), Some(detail::local_epoch(t)));
+    assert_eq!(detail::epoch_from_fmt("yesterday"), None);
+    assert_eq!(detail::local_epoch(t + chrono::Duration::minutes(1)) - detail::local_epoch(t), 60);
+}
+
+#[test]
+fn legacy_range_sql_uses_text_column_only() {
+    let sql = DetailLayout::Legacy.time_range_sql("d", Some(at("2026-03-05 00:00:00")), None);
+    assert_eq!(sql, "(d.LoggingTime_Fmt >= '2026-03-05 00:00:00')");
+    assert_eq!(DetailLayout::Numeric.time_range_sql("d", None, None), "1 = 1");
+    assert_eq!(DetailLayout::Legacy.numeric_sql("d"), "NULL");
+}
+
+#[tokio::test]
+async fn detects_layout_of_legacy_and_current_tables() {
+    let legacy = Database::connect("sqlite::memory:").await.unwrap();
+    legacy.execute_unprepared(LEGACY_DETAIL).await.unwrap();
+    assert_eq!(DetailLayout::detect(&legacy, "main").await.unwrap(), DetailLayout::Legacy);
+
+    let current = Database::connect("sqlite::memory:").await.unwrap();
+    current.execute_unprepared(EMBEDDED_SCHEMA).await.unwrap();
+    assert_eq!(DetailLayout::detect(&current, "main").await.unwrap(), DetailLayout::Numeric);
+}
+
+#[tokio::test]
+async fn range_sql_matches_rows_before_and_after_backfill() {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    db.execute_unprepared(EMBEDDED_SCHEMA).await.unwrap();
+    insert_text_rows(
+        &db,
+        &[("1", "2026-03-04 23:59:59"), ("2", "2026-03-05 08:00:00"), ("3", "2026-03-05 20:00:00"), ("4", "2026-03-06 00:00:01")],
+    )
+    .await;
+
+    let layout = DetailLayout::Numeric;
+    let range = layout.time_range_sql("d", Some(at("2026-03-05 00:00:00")), Some(at("2026-03-06 00:00:00")));
+    let sum = |db: DatabaseConnection, range: String| async move {
+        let row = db
+            .query_one(Statement::from_string(
+                db.get_database_backend(),
+                format!("SELECT SUM({}) AS s FROM TRENDLOG_DATA_DETAIL d WHERE {}", layout.value_sql("d"), range),
+            ))
+            .await
+            .unwrap()
+            .unwrap();
+        row.try_get::<f64>("", "s").unwrap()
+    };
+
+    // Text-only rows
+    assert_eq!(count(&db, &range).await, 2);
+    assert_eq!(sum(db.clone(), range.clone()).await, 5.0);
+
+    // Half backfilled
+    db.execute_unprepared(&format!(
+        "UPDATE TRENDLOG_DATA_DETAIL SET NumericValue = 2, LoggingTime = {} WHERE Value = '2'",
+        detail::local_epoch(at("2026-03-05 08:00:00"))
+    ))
+    .await
+    .unwrap();
+    assert_eq!(count(&db, &range).await, 2);
+
+    // Fully backfilled
+    let (updated, unparseable) = job::backfill_detail_rows(&db, &quick()).await.unwrap();
+    assert_eq!((updated, unparseable), (3, 0));
+    assert_eq!(count(&db, "d.LoggingTime IS NULL").await, 0);
+    assert_eq!(count(&db, &range).await, 2);
+    assert_eq!(sum(db.clone(), range).await, 5.0);
+}
+
+#[tokio::test]
+async fn backfill_fills_columns_and_is_rerunnable() {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    db.execute_unprepared(EMBEDDED_SCHEMA).await.unwrap();
+    insert_text_rows(&db, &[("21.5", "2026-03-05 08:00:00"), ("ON", "2026-03-05 08:05:00"), ("7", "garbage")]).await;
+
+    let (updated, unparseable) = job::backfill_detail_rows(&db, &quick()).await.unwrap();
+    assert_eq!((updated, unparseable), (2, 1));
+    let expected = detail::local_epoch(at("2026-03-05 08:00:00"));
+    assert_eq!(count(&db, &format!("d.NumericValue = 21.5 AND d.LoggingTime = {}", expected)).await, 1);
+    // Non-numeric values keep a NULL NumericValue but still get their time
+    assert_eq!(count(&db, "d.Value = 'ON' AND d.NumericValue IS NULL AND d.LoggingTime IS NOT NULL").await, 1);
+
+    // Only the unparseable row is left, and it stays that way
+    assert_eq!(job::backfill_detail_rows(&db, &quick()).await.unwrap(), (0, 1));
+}
+
+#[tokio::test]
+async fn migration_upgrades_legacy_partition_files() {
+    let path = std::env::temp_dir().join(format!("numeric_partition_{}.db", uuid::Uuid::new_v4()));
+    let partition = Database::connect(format!("sqlite://{}?mode=rwc", path.display())).await.unwrap();
+    partition.execute_unprepared(LEGACY_DETAIL).await.unwrap();
+    insert_text_rows(&partition, &[("1", "2025-12-01 10:00:00"), ("5", "2025-12-01 10:30:00")]).await;
+    partition.close().await.unwrap();
+
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    db.execute_unprepared(EMBEDDED_SCHEMA).await.unwrap();
+    insert_text_rows(&db, &[("3", "2026-03-05 08:00:00")]).await;
+    db.execute_unprepared(&format!(
+        "INSERT INTO DATABASE_FILES (file_name, file_path, partition_identifier, start_date, end_date, is_active, is_archived, last_accessed_at) \
+         VALUES ('old.db', '{}', '2025-12-01', '2025-12-01 00:00:00', '2025-12-01 23:59:59', 0, 1, '2025-12-02 00:00:00')",
+        path.display()
+    ))
+    .await
+    .unwrap();
+
+    let summary = job::migrate_trendlog_numeric(&db, &quick()).await.unwrap();
+    assert_eq!(summary.databases.len(), 2);
+    assert!(!summary.databases[0].upgraded);
+    assert_eq!(summary.databases[1].source, "2025-12-01");
+    assert!(summary.databases[1].upgraded);
+    assert_eq!(summary.databases[1].error, None);
+    assert_eq!(summary.rows_updated(), 3);
+
+    let partition = Database::connect(format!("sqlite://{}?mode=ro", path.display())).await.unwrap();
+    assert_eq!(DetailLayout::detect(&partition, "main").await.unwrap(), DetailLayout::Numeric);
+    assert_eq!(count(&partition, "d.NumericValue IS NOT NULL AND d.LoggingTime IS NOT NULL").await, 2);
+    let index = partition
+        .query_one(Statement::from_string(
+            partition.get_database_backend(),
+            format!("SELECT name FROM sqlite_master WHERE type = 'index' AND name = '{}'", detail::COVERING_INDEX),
+        ))
+        .await
+        .unwrap();
+    assert!(index.is_some());
+    partition.close().await.unwrap();
+    std::fs::remove_file(&path).ok();
+}
@@ -0,0 +1,262 @@
+// Hourly/daily rollups — incremental upserts from every detail writer,
+// rebuild from raw detail rows (main database and partition files) and
+// retention.
+
+use chrono::{NaiveDate, NaiveDateTime};
+use sea_orm::{ConnectionTrait, Database, DatabaseConnection, FromQueryResult, Statement};
+use t3_webview_api::db_schema::EMBEDDED_SCHEMA;
+use t3_webview_api::server_db::trendlog_detail as detail;
+use t3_webview_api::server_db::trendlog_rollup::{
+    self as rollup, Resolution, RollupQuery, RollupRetention,
+};
+use t3_webview_api::t3_device::trendlog_data_service::{CreateTrendlogDataRequest, T3TrendlogDataService};
+
+#[derive(Debug, FromQueryResult)]
+struct Bucket {
+    parent_id: i32,
+    bucket_start: String,
+    min_value: f64,
+    max_value: f64,
+    sum_value: f64,
+    sample_count: i64,
+    last_value: f64,
+    last_time: String,
+}
+
+async fn setup(url: &str) -> DatabaseConnection {
+    let db = Database::connect(url).await.unwrap();
+    db.execute_unprepared(EMBEDDED_SCHEMA).await.unwrap();
+    db.execute_unprepared(
+        "INSERT INTO TRENDLOG_DATA (id, SerialNumber, PanelId, PointId, PointIndex, PointType, Units) \
+         VALUES (1, 1234, 1, 'IN1', 0, 'INPUT', 'C')",
+    )
+    .await
+    .unwrap();
+    db
+}
+
+async fn add_samples(db: &DatabaseConnection, samples: &[(&str, &str)]) {
+    for (value, time) in samples {
+        db.execute_unprepared(&format!(
+            "INSERT INTO TRENDLOG_DATA_DETAIL (ParentId, Value, LoggingTime_Fmt) VALUES (1, '{value}', '{time}')"
+        ))
+        .await
+        .unwrap();
+    }
+}
+
+async fn buckets(db: &DatabaseConnection, resolution: Resolution) -> Vec<Bucket> {
+    Bucket::find_by_statement(Statement::from_string(
+        db.get_database_backend(),
+        format!(
+            "SELECT ParentId AS parent_id, BucketStart AS bucket_start, MinValue AS min_value, MaxValue AS max_value, \
+                    SumValue AS sum_value, SampleCount AS sample_count, LastValue AS last_value, \
+                    LastTime AS last_time \
+             FROM {} ORDER BY BucketStart, ParentId",
+            resolution.table()
+        ),
+    ))
+    .all(db)
+    .await
+    .unwrap()
+}
+
+async fn scalar(db: &DatabaseConnection, sql: &str) -> i64 {
+    let row = db
+        .query_one(Statement::from_string(db.get_database_backend(), sql.to_string()))
+        .await
+        .unwrap()
+        .unwrap();
+    row.try_get_by_index(0).unwrap()
+}
+
+fn at(s: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap()
+}
+
+#[test]
+fn bucket_start_truncates_local_time() {
+    assert_eq!(Resolution::Hourly.bucket_start("2026-03-05 14:27:09").as_deref(), Some("2026-03-05 14:00:00"));
+    assert_eq!(Resolution::Daily.bucket_start("2026-03-05 14:27:09").as_deref(), Some("2026-03-05 00:00:00"));
+    assert_eq!(Resolution::Hourly.bucket_start("not a time"), None);
+}
+
+#[tokio::test]
+async fn record_folds_samples_into_both_resolutions() {
+    let db = setup("sqlite::memory:").await;
+    rollup::record(&db, 1, 20.0, "2026-03-05 14:10:00").await.unwrap();
+    rollup::record(&db, 1, 26.0, "2026-03-05 14:50:00").await.unwrap();
+    // Arrives late: counted, but doesn't become the bucket's last value
+    rollup::record(&db, 1, 18.0, "2026-03-05 14:05:00").await.unwrap();
+    rollup::record(&db, 1, 30.0, "2026-03-05 15:00:00").await.unwrap();
+
+    let hourly = buckets(&db, Resolution::Hourly).await;
+    assert_eq!(hourly.len(), 2);
+    let first = &hourly[0];
+    assert_eq!(first.bucket_start, "2026-03-05 14:00:00");
+    assert_eq!((first.min_value, first.max_value, first.sum_value), (18.0, 26.0, 64.0));
+    assert_eq!(first.sample_count, 3);
+    assert_eq!((first.last_value, first.last_time.as_str()), (26.0, "2026-03-05 14:50:00"));
+
+    let daily = buckets(&db, Resolution::Daily).await;
+    assert_eq!(daily.len(), 1);
+    assert_eq!(daily[0].sample_count, 4);
+    assert_eq!((daily[0].min_value, daily[0].max_value), (18.0, 30.0));
+    assert_eq!(daily[0].last_value, 30.0);
+}
+
+#[tokio::test]
+async fn detail_writers_keep_rollups_current() {
+    let db = setup("sqlite::memory:").await;
+    detail::insert_detail(&db, 1, "21.5", "2026-03-05 14:10:00").await.unwrap();
+    // Non-numeric values are stored but not rolled up
+    detail::insert_detail(&db, 1, "ON", "2026-03-05 14:20:00").await.unwrap();
+    let hourly = buckets(&db, Resolution::Hourly).await;
+    assert_eq!(hourly.len(), 1);
+    assert_eq!((hourly[0].sample_count, hourly[0].last_value), (1, 21.5));
+
+    // Realtime saves (also used by the Modbus poller) go through the same path
+    let point = |index: i32, value: &str| CreateTrendlogDataRequest {
+        serial_number: 97_531,
+        panel_id: 1,
+        point_id: format!("VAR{}", index),
+        point_index: index,
+        point_type: "VARIABLE".into(),
+        value: value.into(),
+        range_field: None,
+        digital_analog: Some("1".into()),
+        units: None,
+        data_source: None,
+        sync_interval: None,
+        created_by: None,
+        poll_cycle_id: None,
+    };
+    T3TrendlogDataService::save_realtime_data(&db, point(1, "5")).await.unwrap();
+    T3TrendlogDataService::save_realtime_batch(&db, vec![point(1, "7"), point(2, "9"), point(3, "")])
+        .await
+        .unwrap();
+    assert_eq!(
+        scalar(&db, "SELECT SUM(SampleCount) FROM TRENDLOG_ROLLUP_DAILY r JOIN TRENDLOG_DATA p ON p.id = r.ParentId WHERE p.SerialNumber = 97531").await,
+        3
+    );
+    assert_eq!(
+        scalar(&db, "SELECT COUNT(*) FROM TRENDLOG_DATA_DETAIL d JOIN TRENDLOG_DATA p ON p.id = d.ParentId WHERE p.SerialNumber = 97531").await,
+        3
+    );
+}
+
+#[tokio::test]
+async fn batches_are_aggregated_before_the_upsert() {
+    let db = setup("sqlite::memory:").await;
+    rollup::record(&db, 1, 10.0, "2026-03-05 14:00:00").await.unwrap();
+    rollup::record_details(&db, &[(1, "12"), (2, "3"), (1, "8"), (2, "ON"), (1, "11")], "2026-03-05 14:30:00").await;
+
+    let hourly = buckets(&db, Resolution::Hourly).await;
+    assert_eq!(hourly.len(), 2);
+    let first = hourly.iter().find(|b| b.parent_id == 1).unwrap();
+    assert_eq!((first.min_value, first.max_value, first.sum_value), (8.0, 12.0, 41.0));
+    assert_eq!(first.sample_count, 4);
+    assert_eq!((first.last_value, first.last_time.as_str()), (11.0, "2026-03-05 14:30:00"));
+    let second = hourly.iter().find(|b| b.parent_id == 2).unwrap();
+    assert_eq!((second.sample_count, second.last_value), (1, 3.0));
+    assert_eq!(buckets(&db, Resolution::Daily).await.iter().map(|b| b.sample_count).sum::<i64>(), 5);
+}
+
+#[tokio::test]
+async fn rebuild_replaces_buckets_from_raw_rows() {
+    let db = setup("sqlite::memory:").await;
+    add_samples(&db, &[("10", "2026-03-05 08:15:00"), ("14", "2026-03-05 08:45:00"), ("3", "2026-03-06 09:00:00")]).await;
+    // A drifted bucket with raw rows, and one whose raw rows are long gone
+    db.execute_unprepared(
+        "INSERT INTO TRENDLOG_ROLLUP_HOURLY VALUES (1, '2026-03-05 08:00:00', 0, 99, 500, 50, 99, '2026-03-05 08:59:00'), \
+                                                   (1, '2025-01-01 00:00:00', 5, 5, 5, 1, 5, '2025-01-01 00:10:00')",
+    )
+    .await
+    .unwrap();
+
+    let summary = rollup::rebuild(&db, None).await.unwrap();
+    assert_eq!(summary.sources, vec!["main".to_string()]);
+    assert_eq!((summary.hourly_buckets, summary.daily_buckets), (2, 2));
+
+    let hourly = buckets(&db, Resolution::Hourly).await;
+    assert_eq!(hourly.len(), 3);
+    assert_eq!(hourly[0].bucket_start, "2025-01-01 00:00:00");
+    let fixed = &hourly[1];
+    assert_eq!((fixed.min_value, fixed.max_value, fixed.sum_value), (10.0, 14.0, 24.0));
+    assert_eq!((fixed.sample_count, fixed.last_value), (2, 14.0));
+
+    // A bounded rebuild leaves other days alone
+    db.execute_unprepared("UPDATE TRENDLOG_ROLLUP_DAILY SET SampleCount = 7").await.unwrap();
+    let day = NaiveDate::from_ymd_opt(2026, 3, 6).unwrap();
+    let summary = rollup::rebuild(&db, Some((day, day))).await.unwrap();
+    assert_eq!(summary.daily_buckets, 1);
+    let counts: Vec<i64> = buckets(&db, Resolution::Daily).await.iter().map(|b| b.sample_count).collect();
+    assert_eq!(counts, vec![7, 1]);
+}
+
+#[tokio::test]
+async fn rebuild_reads_archived_partitions() {
+    let path = std::env::temp_dir().join(format!("rollup_partition_{}.db", uuid::Uuid::new_v4()));
+    let partition = setup(&format!("sqlite://{}?mode=rwc", path.display())).await;
+    add_samples(&partition, &[("1", "2025-12-01 10:00:00"), ("5", "2025-12-01 10:30:00")]).await;
+    partition.close().await.unwrap();
+
+    let db = setup("sqlite::memory:").await;
+    db.execute_unprepared(&format!(
+        "INSERT INTO DATABASE_FILES (file_name, file_path, partition_identifier, start_date, end_date, is_active, is_archived, last_accessed_at) \
+         VALUES ('archived.db', '{}', '2025-12-01', '2025-12-01 00:00:00', '2025-12-01 23:59:59', 0, 1, '2025-12-02 00:00:00'), \
+                ('missing.db', '/nonexistent/missing.db', '2025-12-02', '2025-12-02 00:00:00', '2025-12-02 23:59:59', 0, 0, '2025-12-03 00:00:00')",
+        path.display()
+    ))
+    .await
+    .unwrap();
+
+    let summary = rollup::rebuild(&db, None).await.unwrap();
+    std::fs::remove_file(&path).ok();
+
+    assert_eq!(summary.sources, vec!["main".to_string(), "2025-12-01".to_string()]);
+    assert_eq!(summary.skipped.len(), 1);
+    assert!(summary.skipped[0].starts_with("2025-12-02"));
+    let hourly = buckets(&db, Resolution::Hourly).await;
+    assert_eq!(hourly.len(), 1);
+    assert_eq!((hourly[0].sample_count, hourly[0].max_value), (2, 5.0));
+}
+
+#[tokio::test]
+async fn retention_prunes_each_resolution_separately() {
+    let db = setup("sqlite::memory:").await;
+    rollup::record(&db, 1, 1.0, "2025-01-10 12:00:00").await.unwrap();
+    rollup::record(&db, 1, 2.0, "2026-03-01 12:00:00").await.unwrap();
+
+    let retention = RollupRetention { hourly_days: 30, daily_days: 0 };
+    let pruned = rollup::apply_retention(&db, &retention, at("2026-03-05 00:00:00")).await.unwrap();
+    assert_eq!((pruned.hourly_deleted, pruned.daily_deleted), (1, 0));
+    assert_eq!(buckets(&db, Resolution::Hourly).await.len(), 1);
+    assert_eq!(buckets(&db, Resolution::Daily).await.len(), 2);
+
+    assert_eq!(rollup::load_retention(&db).await, RollupRetention::default());
+    rollup::save_retention(&db, &retention).await.unwrap();
+    assert_eq!(rollup::load_retention(&db).await, retention);
+}
+
+#[tokio::test]
+async fn query_joins_point_metadata_and_averages() {
+    let db = setup("sqlite::memory:").await;
+    for (value, time) in [(10.0, "2026-03-05 08:00:00"), (20.0, "2026-03-05 08:30:00"), (40.0, "2026-03-05 09:00:00")] {
+        rollup::record(&db, 1, value, time).await.unwrap();
+    }
+
+    let rows = rollup::query(&db, &RollupQuery { start: Some("2026-03-05 08:00:00".into()), ..Default::default() })
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!((rows[0].serial_number, rows[0].point_id.as_str(), rows[0].units.as_deref()), (1234, "IN1", Some("C")));
+    assert_eq!(rows[0].avg_value, 15.0);
+    assert_eq!(rows[1].bucket_start, "2026-03-05 09:00:00");
+
+    let daily = rollup::query(&db, &RollupQuery { resolution: Resolution::Daily, ..Default::default() }).await.unwrap();
+    assert_eq!((daily.len(), daily[0].sample_count), (1, 3));
+
+    let other = RollupQuery { point_id: Some("IN2".into()), ..Default::default() };
+    assert!(rollup::query(&db, &other).await.unwrap().is_empty());
+}
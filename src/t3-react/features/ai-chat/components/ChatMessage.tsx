@@ -18,6 +18,7 @@ import {
   CheckmarkCircleFilled,
   DismissCircleFilled,
 } from '@fluentui/react-icons';
+import { API_BASE_URL } from '../../../config/constants';
 import styles from '../AiChat.module.css';
 import type {
   ChatMessage as ChatMessageType,
@@ -167,6 +168,19 @@ const ToolCallTagInline: React.FC<{ tool: ToolCallRecord }> = ({ tool }) => {
   const [expanded, setExpanded] = useState(false);
   const isPending = tool.status === 'pending';
   const isError = tool.status === 'error';
+  const [deciding, setDeciding] = useState(false);
+
+  const decide = async (verb: 'approve' | 'reject') => {
+    if (!tool.approvalId) return;
+    setDeciding(true);
+    try {
+      await fetch(`${API_BASE_URL}/api/ai/approvals/${tool.approvalId}/${verb}`, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: '{}',
+      });
+    } catch {}
+  };
 
   let formattedArgs = '';
   try { formattedArgs = JSON.stringify(JSON.parse(tool.args), null, 2); } catch { formattedArgs = tool.args || '(empty)'; }
@@ -196,6 +210,17 @@ const ToolCallTagInline: React.FC<{ tool: ToolCallRecord }> = ({ tool }) => {
         <span className={styles.toolTagArrow}>{expanded ? '▾' : '→'}</span>
       </button>
 
+      {tool.approval === 'pending' && (
+        <span className={styles.stepToolApproval}>
+          Approve this write?
+          <button disabled={deciding} onClick={() => decide('approve')}>Approve</button>
+          <button disabled={deciding} onClick={() => decide('reject')}>Reject</button>
+        </span>
+      )}
+      {tool.approval && tool.approval !== 'pending' && tool.approval !== 'approved' && (
+        <span className={styles.stepToolApproval}>Write {tool.approval}</span>
+      )}
+
       {expanded && (
         <div className={styles.stepToolDetail}>
           <div className={styles.toolDetailSection}>
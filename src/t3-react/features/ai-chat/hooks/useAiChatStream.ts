@@ -5,6 +5,7 @@
  *   1. POST /api/ai/chat with fetch()
  *   2. Parse SSE stream via ReadableStream + TextDecoder
  *   3. Dispatch events: text_delta -> streaming text, tool_call -> pending card,
+ *      approval_required/approval_resolved -> approve/reject on the card,
  *      tool_result -> resolved card, done -> finalize, error -> system message
  */
 
@@ -51,6 +52,9 @@ export interface ToolCallRecord {
   args: string;
   result?: string;
   status: 'pending' | 'success' | 'error';
+  /** Write tools wait for a human decision (POST /api/ai/approvals/:id/approve|reject) */
+  approvalId?: string;
+  approval?: 'pending' | 'approved' | 'rejected' | 'expired';
 }
 
 interface StreamEvent {
@@ -67,6 +71,8 @@ interface StreamEvent {
     message?: string;
     steps?: number;
     duration_ms?: number;
+    action_id?: string;
+    status?: string;
   };
 }
 
@@ -103,6 +109,7 @@ const TOOL_DESCRIPTIONS: Record<string, string> = {
   t3000_fdd_rule_export: 'Exporting fault detection rules\u2026',
   t3000_fdd_rule_import: 'Importing fault detection rules\u2026',
   t3000_fdd_faults: 'Listing fault detection findings\u2026',
+  t3000_fdd_top_faults: 'Ranking faults by energy cost\u2026',
   t3000_building_summary: 'Generating building health summary\u2026',
   t3000_point_search: 'Searching for matching points\u2026',
   t3000_point_read: 'Reading point value\u2026',
@@ -379,6 +386,16 @@ export function useAiChatStream(settings: AiProviderSettings, onSaved?: () => vo
         };
 
         // Push blocks + in-progress block to the store for live streaming display
+        // Patch a tool call everywhere it is referenced (records, flat and block steps)
+        const patchTool = (id: string, patch: Partial<ToolCallRecord>) => {
+          storeSetActiveToolCalls((prev) => (prev[id] ? { ...prev, [id]: { ...prev[id], ...patch } } : prev));
+          const idx = toolCallRecords.findIndex((t) => t.id === id);
+          if (idx !== -1) toolCallRecords[idx] = { ...toolCallRecords[idx], ...patch };
+          for (const s of [...steps, ...currentThinkingSteps]) {
+            if (s.toolCall?.id === id) s.toolCall = { ...s.toolCall, ...patch };
+          }
+        };
+
         const pushBlocks = () => {
           const display: MessageBlock[] = [...blocks];
           if (currentBlockType === 'thinking' && currentThinkingSteps.length > 0) {
@@ -488,6 +505,20 @@ export function useAiChatStream(settings: AiProviderSettings, onSaved?: () => vo
                 pushBlocks();
                 break;
               }
+              case 'approval_required': {
+                patchTool(event.data?.id || '', { approvalId: event.data?.action_id, approval: 'pending' });
+                storeSetStreamingSteps([...steps]);
+                pushBlocks();
+                break;
+              }
+              case 'approval_resolved': {
+                const status = event.data?.status;
+                const approval = status === 'approved' || status === 'rejected' ? status : 'expired';
+                patchTool(event.data?.id || '', { approval });
+                storeSetStreamingSteps([...steps]);
+                pushBlocks();
+                break;
+              }
               case 'tool_result': {
                 const id = event.data?.id || '';
                 const result = event.data?.result || '';